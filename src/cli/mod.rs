@@ -1,39 +1,99 @@
-use std::collections::{HashMap, HashSet};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ffi::OsStr;
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::OnceLock;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
 use anyhow::Context;
-use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use indicatif::{MultiProgress, ProgressDrawTarget};
 use serde::{Deserialize, Serialize};
 
-use crate::config::resolve::resolve_workspace_with_overrides;
-use crate::core::changeset::{load_changeset_files, select_active_changeset, ChangesetFile};
+use crate::config::keys::{
+    config_key_line, config_key_lines, flatten_config_value, schema_config_keys,
+    schema_repo_config_keys, validate_config_key, validate_repo_config_key,
+};
+use crate::config::resolve::{config_env_overrides, resolve_workspace_with_overrides};
+use crate::config::{RepoConfig, WorkspaceConfig};
+use crate::core::changeset::{
+    changesets_dir, load_changeset_files, select_active_changeset, ChangesetFile,
+};
+use crate::core::coverage::{
+    coverage_index_path, format_percent, load_coverage_report, render_coverage_index,
+    CoverageSummary, RepoCoverage,
+};
+use crate::core::lockfile::{load_lockfile, save_lockfile, LockedRepo, Lockfile};
 use crate::core::repo::{Dependency, Repo, RepoId};
+use crate::core::secrets::{SecretFinding, SecretScanner};
+use crate::core::select::{SelectContext, SelectExpr};
+use crate::core::selection::{load_selection, save_selection, SavedSelection, LAST_SELECTION};
+use crate::core::snapshot::{
+    diff_snapshots, list_snapshots, load_snapshot, save_snapshot, snapshot_path, Snapshot,
+    SnapshotRepo, SNAPSHOT_LOCKFILES,
+};
+use crate::core::state::{
+    collect_state_files, corrupted_bundle, is_encrypted_bundle, load_state_manifest, seal_bundle,
+    unseal_bundle, validate_state_path, StateManifest, STATE_FORMAT, STATE_MANIFEST,
+    STATE_PASSPHRASE_ENV,
+};
+use crate::core::stats::{
+    append_stats, failure_category, load_stats, stats_path, summarize_stats, StatsRecord,
+};
+use crate::core::teams::{
+    repo_teams, team_members, team_reviewers, ReviewerBalancer, ReviewerStrategy,
+};
+use crate::core::toolchain::{parse_tool_version, required_version, version_satisfies, Tool};
 use crate::core::version::{
-    bump_version, parse_bump_level, parse_bump_mode, parse_version_kind, BumpMode, Version,
-    VersionKind,
+    bump_version, parse_bump_level, parse_bump_mode, parse_version_kind, BumpLevel, BumpMode,
+    Version, VersionKind, VersionReq,
 };
-use crate::core::workspace::Workspace;
+use crate::core::workspace::{is_pause_date, today_date, validate_workspace_config, Workspace};
+use crate::ecosystem::cache::{cache_dir, ManifestCache};
 use crate::ecosystem::{plugin_for, EcosystemId};
 use crate::error::{HarmoniaError, Result};
-use crate::forge::traits::{CreateIssueParams, CreateMrParams, MergeMrParams, UpdateMrParams};
-use crate::forge::{client_from_forge_config, CiState, MrState};
+use crate::forge::router::HostRouter;
+use crate::forge::traits::{
+    CreateIssueParams, CreateMrParams, CreateReleaseParams, ListIssuesParams, MergeMrParams,
+    UpdateMrParams,
+};
+use crate::forge::webhook::{
+    read_request, verify_delivery, webhook_action, webhook_event_allowed, write_response,
+    WebhookDelivery,
+};
+use crate::forge::{
+    client_for_forge_host, client_from_forge_config, CheckRun, CiState, IssueState,
+    MergeRequirements, MrChanges, MrState, Pipeline,
+};
 use crate::git::ops::{
-    branch_exists, checkout_branch, clone_repo, create_and_checkout_branch, create_branch,
-    current_branch, open_repo, repo_status, set_branch_upstream, sync_repo, SyncOptions,
+    apply_sync, branch_exists, branch_target, branch_upstream, case_conflicts, checkout_branch,
+    checkout_branch_autostash, checkout_commit, clone_repo, clone_repo_atomic, commits_ahead_of,
+    create_and_checkout_branch, create_branch, current_branch, default_push_remote, fetch_for_sync,
+    head_commit, inspect_clone, open_repo, preview_sync, remove_clone_dir,
+    remove_stale_clone_staging, repo_status, set_branch_target, set_branch_upstream, tag_exists,
+    verify_clone, worktree_blob_id, AutostashOutcome, CloneHealth, SyncOptions,
+};
+use crate::git::status::{ChangeKind, StatusSummary};
+use crate::git::transport::{
+    has_extra_header, has_pre_push_hook, is_auth_error, native_fetch, native_push,
+    native_supports_url, GitTransport, PushSpec,
 };
-use crate::git::status::StatusSummary;
 use crate::graph::constraint::{check_constraints, ConstraintReport, ViolationType};
 use crate::graph::ops::{
+    critical_path, dependencies_for, duplicate_package_names, graph_levels, impact_scores,
     internal_dependencies_for, merge_order, package_map, resolve_internal_edges, topological_order,
-    transitive_dependencies, transitive_dependents,
+    transitive_dependencies, transitive_dependents, DuplicatePackage, ImpactScore,
 };
 use crate::graph::viz;
-use crate::util::template::render_template_file;
-use crate::util::{output, parallel};
+use crate::util::remote_url::{forge_web_root, RemoteUrl};
+use crate::util::template::{
+    list_template_entries, render_template, render_template_dir, render_template_entry,
+    render_template_file, unknown_template_variables,
+};
+use crate::util::{events, output, parallel, report, watch};
 
 #[derive(Parser, Debug)]
 #[command(name = "harmonia")]
@@ -62,6 +122,57 @@ pub struct Cli {
     pub quiet: bool,
     #[arg(long, help = "Disable colored output.")]
     pub no_color: bool,
+    #[arg(
+        long,
+        help = "Print one JSON document on stdout instead of text (put it before the subcommand)."
+    )]
+    pub json: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "EXPR",
+        help = "Narrow repo selection with an expression, e.g. \"changed & group:backend | dependents(core)\"."
+    )]
+    pub select: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        help = "Narrow repo selection to repos owned by a [teams] entry (same as --select team:NAME)."
+    )]
+    pub team: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        num_args = 0..=1,
+        default_missing_value = LAST_SELECTION,
+        help = "Record the repos this command selects under NAME (default: last)."
+    )]
+    pub save_selection: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        num_args = 0..=1,
+        default_missing_value = LAST_SELECTION,
+        help = "Operate on the repos recorded by --save-selection NAME (default: last)."
+    )]
+    pub selection: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Refuse commands that commit, push, or change MRs, versions, or dependencies."
+    )]
+    pub read_only: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "KINDS",
+        value_delimiter = ',',
+        help = "What counts as changed for --changed: dirty, unpushed, branch (default: all)."
+    )]
+    pub changed_as: Vec<String>,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -93,6 +204,10 @@ pub enum Commands {
     Run(RunArgs),
     #[command(about = "Run a command in each selected repository, optionally through a shell.")]
     Each(EachArgs),
+    #[command(
+        about = "Run a git subcommand in each selected repository and summarize the results."
+    )]
+    Git(GitArgs),
     #[command(about = "Inspect dependency relationships between repositories.")]
     Graph(GraphArgs),
     #[command(
@@ -109,18 +224,30 @@ pub enum Commands {
     Push(PushArgs),
     #[command(about = "Show git diffs across selected repositories.")]
     Diff(DiffArgs),
+    #[command(about = "Search tracked files across repositories with git grep.")]
+    Grep(GrepArgs),
     #[command(about = "Run ecosystem test commands across selected repositories.")]
     Test(TestArgs),
     #[command(about = "Run ecosystem lint commands across selected repositories.")]
     Lint(LintArgs),
+    #[command(
+        about = "Download each repo's ecosystem dependencies (cargo fetch, go mod download, ...) to warm caches before going offline."
+    )]
+    Prefetch(PrefetchArgs),
     #[command(about = "Inspect, validate, and bump repository versions.")]
     Version(VersionArgs),
     #[command(about = "Inspect and update repository dependency declarations.")]
     Deps(DepsArgs),
     #[command(about = "Open workspace or repository paths in your editor.")]
     Edit(EditArgs),
-    #[command(about = "Clean untracked files and directories with git clean.")]
+    #[command(
+        about = "Clean untracked files with git clean, or build outputs with --build-artifacts."
+    )]
     Clean(CleanArgs),
+    #[command(
+        about = "Check the toolchains selected repos need, and install missing ones with --install."
+    )]
+    Setup(SetupArgs),
     #[command(about = "Show and edit workspace configuration values.")]
     Config(ConfigArgs),
     #[command(about = "List, add, remove, and inspect repositories in workspace config.")]
@@ -129,6 +256,34 @@ pub enum Commands {
     Plan(PlanArgs),
     #[command(about = "Create, inspect, update, merge, and close merge requests.")]
     Mr(MrArgs),
+    #[command(
+        about = "List, create, close, and comment on issues in the coordination repo or a workspace repo."
+    )]
+    Issue(IssueArgs),
+    #[command(about = "Record and compare exact repo versions per environment.")]
+    Snapshot(SnapshotArgs),
+    #[command(
+        about = "Pin the HEAD commit of each repo in .harmonia/lockfile.toml, or check them out again."
+    )]
+    Lock(LockArgs),
+    #[command(
+        about = "Tag each repo at its manifest version after version bump, then push and publish releases."
+    )]
+    Release(ReleaseArgs),
+    #[command(about = "Assemble selected repos into a single tree for vendors or audits.")]
+    Export(ExportArgs),
+    #[command(about = "Sync shared files from the workspace propagate templates into repos.")]
+    Propagate(PropagateArgs),
+    #[command(about = "Back up or restore workspace coordination state under .harmonia.")]
+    State(StateArgs),
+    #[command(about = "Validate and preview MR and tracking issue templates locally.")]
+    Template(TemplateArgs),
+    #[command(about = "Show local command usage stats recorded with [workspace].stats.")]
+    Stats(StatsArgs),
+    #[command(about = "Manage the manifest and dependency graph cache under .harmonia/cache.")]
+    Cache(CacheArgs),
+    #[command(about = "Receive forge webhooks and run the configured actions.")]
+    Webhook(WebhookArgs),
     #[command(about = "Generate shell completion scripts.")]
     Completion(CompletionArgs),
     #[command(
@@ -139,7 +294,9 @@ pub enum Commands {
 
 #[derive(Args, Debug)]
 pub struct InitArgs {
-    #[arg(help = "Optional source URL/path to seed the workspace from.")]
+    #[arg(
+        help = "Optional source URL/path to seed the workspace from, or gitlab://group/subgroup / github://org to list projects from the forge."
+    )]
     pub source: Option<String>,
     #[arg(short = 'n', long, help = "Workspace name to write into config.")]
     pub name: Option<String>,
@@ -149,6 +306,36 @@ pub struct InitArgs {
     pub no_clone: bool,
     #[arg(long, help = "Initial repo group to clone after init.")]
     pub group: Option<String>,
+    #[arg(
+        long,
+        value_name = "HOST",
+        help = "Forge host for gitlab:// or github:// sources (defaults to gitlab.com / github.com)."
+    )]
+    pub forge_host: Option<String>,
+    #[arg(
+        long,
+        help = "Also add archived projects from gitlab:// or github:// sources."
+    )]
+    pub include_archived: bool,
+    #[arg(
+        long,
+        conflicts_with = "source",
+        help = "Workspace template to scaffold from: local path, git URL, or gh:org/repo / gl:group/repo."
+    )]
+    pub template: Option<String>,
+    #[arg(
+        long,
+        help = "Forge group passed to the template as forge_group.",
+        requires = "template"
+    )]
+    pub forge_group: Option<String>,
+    #[arg(
+        long = "var",
+        value_name = "KEY=VALUE",
+        requires = "template",
+        help = "Extra template variable (repeatable)."
+    )]
+    pub vars: Vec<String>,
 }
 
 #[derive(Args, Debug)]
@@ -175,6 +362,18 @@ pub struct CloneArgs {
     pub protocol: Option<String>,
     #[arg(long, help = "Fail when repo path already exists instead of skipping.")]
     pub strict: bool,
+    #[arg(
+        long,
+        help = "Run git fsck on existing clones instead of only skipping them."
+    )]
+    pub verify: bool,
+    #[arg(
+        long,
+        value_parser = ["text", "json"],
+        default_value = "text",
+        help = "Final report format: text summary on stderr, or json on stdout."
+    )]
+    pub report: String,
 }
 
 #[derive(Args, Debug)]
@@ -193,6 +392,11 @@ pub struct StatusArgs {
     pub changed: bool,
     #[arg(long, help = "Emit tab-delimited porcelain-style output.")]
     pub porcelain: bool,
+    #[arg(
+        long,
+        help = "Also report external repositories (read-only freshness info)."
+    )]
+    pub include_external: bool,
 }
 
 #[derive(Args, Debug)]
@@ -227,8 +431,36 @@ pub struct SyncArgs {
         help = "Prune stale remote-tracking branches while fetching."
     )]
     pub prune: bool,
-    #[arg(long, help = "Number of repositories to sync in parallel.")]
+    #[arg(
+        long,
+        help = "Number of repositories to sync in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
+    #[arg(
+        long,
+        requires = "fetch_only",
+        help = "Also fetch external repositories (requires --fetch-only)."
+    )]
+    pub include_external: bool,
+    #[arg(
+        long,
+        conflicts_with = "fetch_only",
+        help = "Fetch and report what each repo would do (fast-forward, merge, rebase, diverged) without changing branches."
+    )]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Stream structured progress events to stdout; FORMAT is ndjson."
+    )]
+    pub events: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "events",
+        help = "Write --events to FILE instead of stdout."
+    )]
+    pub events_file: Option<PathBuf>,
 }
 
 #[derive(Args, Debug, Default)]
@@ -249,6 +481,38 @@ pub struct SubmitArgs {
     pub no_auto_branch: bool,
     #[arg(long, help = "Branch name to use for auto-branching in submit flow.")]
     pub branch_name: Option<String>,
+    #[arg(
+        long,
+        help = "Print the full submit plan and ask for confirmation before running it."
+    )]
+    pub review: bool,
+    #[arg(
+        long,
+        help = "Confirm each phase (mr create, add, commit, push) before running it."
+    )]
+    pub step: bool,
+    #[arg(long, help = "Skip confirmation prompts for --review and --step.")]
+    pub yes: bool,
+    #[arg(
+        long,
+        help = "Discard saved progress from an interrupted submit and start over."
+    )]
+    pub restart: bool,
+    #[arg(long, help = "Skip scanning changes for likely secrets.")]
+    pub no_secret_scan: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Stream structured progress events to stdout; FORMAT is ndjson."
+    )]
+    pub events: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "events",
+        help = "Write --events to FILE instead of stdout."
+    )]
+    pub events_file: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
@@ -259,12 +523,42 @@ pub struct ExecArgs {
     pub all: bool,
     #[arg(long, help = "Run only on repositories with local changes.")]
     pub changed: bool,
-    #[arg(long, help = "Number of repositories to run in parallel.")]
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
     #[arg(long, help = "Stop after first command failure.")]
     pub fail_fast: bool,
     #[arg(long, help = "Continue even when commands fail.")]
     pub ignore_errors: bool,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill a repo's command after this many seconds and report it as timed out (0 disables defaults.command_timeout)."
+    )]
+    pub timeout: Option<u64>,
+    #[arg(
+        long = "graph-order",
+        help = "Run repositories level by level in dependency order (each level in parallel)."
+    )]
+    pub graph_order: bool,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with = "no_prefix",
+        help = "Prefix for each output line; {repo} is the padded repo name (default \"{repo} | \")."
+    )]
+    pub prefix: Option<String>,
+    #[arg(long, help = "Do not prefix output lines with the repo name.")]
+    pub no_prefix: bool,
+    #[arg(
+        long,
+        value_parser = ["interleaved", "grouped", "json"],
+        default_value = "interleaved",
+        help = "Stream output as it arrives, print each repo's output once it finishes, or print a JSON summary."
+    )]
+    pub output: String,
     #[arg(
         last = true,
         required = true,
@@ -273,30 +567,98 @@ pub struct ExecArgs {
     pub command: Vec<String>,
 }
 
+#[derive(Args, Debug)]
+pub struct GitArgs {
+    #[arg(long, help = "Comma-separated repositories to target.")]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Run only on repositories with local changes.")]
+    pub changed: bool,
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
+    pub parallel: Option<usize>,
+    #[arg(
+        long,
+        help = "Skip the confirmation prompt for destructive subcommands."
+    )]
+    pub yes: bool,
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "git subcommand and arguments, e.g. `log -1 --oneline`."
+    )]
+    pub args: Vec<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct RunArgs {
-    #[arg(help = "Hook name to execute (workspace and repo hook precedence applies).")]
-    pub hook: String,
+    #[arg(
+        required_unless_present = "list",
+        help = "Hook name to execute (workspace and repo hook precedence applies)."
+    )]
+    pub hook: Option<String>,
     #[arg(long, help = "Comma-separated repositories to target.")]
     pub repos: Vec<String>,
     #[arg(long, help = "Run on all configured repositories.")]
     pub all: bool,
     #[arg(long, help = "Run only on repositories with local changes.")]
     pub changed: bool,
-    #[arg(long, help = "Number of repositories to run in parallel.")]
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
     #[arg(long, help = "Stop after first hook failure.")]
     pub fail_fast: bool,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill a repo's command after this many seconds and report it as timed out (0 disables defaults.command_timeout)."
+    )]
+    pub timeout: Option<u64>,
+    #[arg(
+        long = "graph-order",
+        help = "Run repositories level by level in dependency order (each level in parallel)."
+    )]
+    pub graph_order: bool,
+    #[arg(
+        long,
+        help = "List configured workspace and repo hooks instead of running one."
+    )]
+    pub list: bool,
+    #[arg(
+        long,
+        help = "Print which commands would run where without executing them."
+    )]
+    pub dry_run: bool,
 }
 
 #[derive(Args, Debug)]
 pub struct EachArgs {
     #[arg(long, help = "Comma-separated repositories to target.")]
     pub repos: Vec<String>,
-    #[arg(long, help = "Number of repositories to run in parallel.")]
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
     #[arg(long, help = "Run command through shell (sh -c / cmd /C).")]
     pub shell: bool,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill a repo's command after this many seconds and report it as timed out (0 disables defaults.command_timeout)."
+    )]
+    pub timeout: Option<u64>,
+    #[arg(
+        long,
+        value_parser = ["interleaved", "grouped", "json"],
+        default_value = "interleaved",
+        help = "Stream output as it arrives, print each repo's output once it finishes, or print a JSON summary."
+    )]
+    pub output: String,
     #[arg(
         last = true,
         required = true,
@@ -323,6 +685,8 @@ pub enum GraphCommand {
     Order(GraphOrderArgs),
     #[command(about = "Validate dependency constraints and optionally auto-fix known issues.")]
     Check(GraphCheckArgs),
+    #[command(about = "Show the longest dependency chain and per-repo impact scores.")]
+    CriticalPath(GraphCriticalPathArgs),
 }
 
 #[derive(Args, Debug)]
@@ -332,15 +696,58 @@ pub struct GraphShowArgs {
     #[arg(
         long,
         default_value = "tree",
-        help = "Output format: tree, mermaid, dot, or json."
+        help = "Output format: tree, flat, mermaid, dot, graphml, or json."
     )]
     pub format: String,
+    #[arg(
+        long,
+        value_name = "KEY",
+        help = "Cluster dot/graphml nodes by group or ecosystem."
+    )]
+    pub cluster_by: Option<String>,
     #[arg(
         long,
         default_value = "down",
         help = "Traversal direction: down (dependencies) or up (dependents)."
     )]
     pub direction: String,
+    #[arg(
+        long,
+        value_name = "FILTER",
+        help = "Keep only repos matching group:<name>, ecosystem:<id>, repo:<name>, changed, or external. Repeatable."
+    )]
+    pub only: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FILTER",
+        help = "Drop repos matching a filter (same syntax as --only). Repeatable."
+    )]
+    pub exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "REPO",
+        help = "Only show repos reachable from this repo in the chosen direction."
+    )]
+    pub from: Option<String>,
+    #[arg(
+        long,
+        requires = "from",
+        value_name = "N",
+        help = "Limit --from to repos at most N edges away."
+    )]
+    pub depth: Option<usize>,
+    #[arg(
+        long,
+        help = "Append impact scores (transitive dependents and depth) to node labels."
+    )]
+    pub impact: bool,
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "PATH",
+        help = "Write the rendered graph to PATH instead of stdout."
+    )]
+    pub output: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
@@ -371,6 +778,12 @@ pub struct GraphOrderArgs {
     pub json: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct GraphCriticalPathArgs {
+    #[arg(long, help = "Emit machine-readable JSON output.")]
+    pub json: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct GraphCheckArgs {
     #[arg(long, help = "Apply safe, automatic fixes for detected violations.")]
@@ -381,8 +794,21 @@ pub struct GraphCheckArgs {
 
 #[derive(Args, Debug)]
 pub struct BranchArgs {
-    #[arg(help = "Branch name to checkout/create in selected repositories.")]
-    pub name: String,
+    #[arg(
+        help = "Branch name to checkout/create in selected repositories.",
+        required_unless_present_any = ["ticket", "slug"]
+    )]
+    pub name: Option<String>,
+    #[arg(
+        long,
+        help = "Ticket id for generating the branch name from [branching].template."
+    )]
+    pub ticket: Option<String>,
+    #[arg(
+        long,
+        help = "Short description for generating the branch name from [branching].template."
+    )]
+    pub slug: Option<String>,
     #[arg(short = 'c', long, help = "Create branch if missing before checkout.")]
     pub create: bool,
     #[arg(
@@ -417,9 +843,14 @@ pub struct BranchArgs {
     #[arg(
         short = 't',
         long,
-        help = "Set upstream tracking target after checkout."
+        help = "Set upstream tracking target after checkout. New branches otherwise track origin/<branch> after their first push."
     )]
     pub track: Option<String>,
+    #[arg(
+        long,
+        help = "Stash local changes before switching and re-apply them afterwards."
+    )]
+    pub autostash: bool,
 }
 
 #[derive(Args, Debug)]
@@ -441,6 +872,11 @@ pub struct CheckoutArgs {
         help = "Fallback branch to try if the requested branch does not exist."
     )]
     pub fallback: Option<String>,
+    #[arg(
+        long,
+        help = "Stash local changes before switching and re-apply them afterwards."
+    )]
+    pub autostash: bool,
 }
 
 #[derive(Args, Debug)]
@@ -488,6 +924,19 @@ pub struct CommitArgs {
         help = "Add one or more commit trailers (key=value or raw)."
     )]
     pub trailers: Vec<String>,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill pre-commit hooks that run longer than this many seconds."
+    )]
+    pub hook_timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "Print committed repos and hook results as JSON; hook output is captured."
+    )]
+    pub json: bool,
+    #[arg(long, help = "Skip scanning staged changes for likely secrets.")]
+    pub no_secret_scan: bool,
 }
 
 #[derive(Args, Debug)]
@@ -521,6 +970,17 @@ pub struct PushArgs {
     pub yes: bool,
     #[arg(long, help = "Show what would be pushed without pushing.")]
     pub dry_run: bool,
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill pre-push hooks that run longer than this many seconds."
+    )]
+    pub hook_timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "Print pushed repos and hook results as JSON; hook output is captured."
+    )]
+    pub json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -531,6 +991,11 @@ pub struct DiffArgs {
     pub staged: bool,
     #[arg(long, help = "Show summary statistics instead of full patch.")]
     pub stat: bool,
+    #[arg(
+        long,
+        help = "Show one line per repo with files changed, insertions, deletions, and renames, plus workspace totals."
+    )]
+    pub summary: bool,
     #[arg(long = "name-only", help = "Show only changed file names.")]
     pub name_only: bool,
     #[arg(long, help = "Number of context lines to include in patch output.")]
@@ -541,6 +1006,42 @@ pub struct DiffArgs {
         help = "Output format: patch, name-only, or json."
     )]
     pub format: String,
+    #[arg(
+        long,
+        help = "Include each file's unified diff hunks in --format json output."
+    )]
+    pub hunks: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct GrepArgs {
+    #[arg(help = "Extended regular expression to search for.")]
+    pub pattern: String,
+    #[arg(help = "Specific repositories to search (defaults to all cloned repos).")]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Search only repositories with local changes.")]
+    pub changed: bool,
+    #[arg(short = 'i', long, help = "Match case-insensitively.")]
+    pub ignore_case: bool,
+    #[arg(short = 'F', long, help = "Treat the pattern as a literal string.")]
+    pub fixed_strings: bool,
+    #[arg(short = 'w', long, help = "Match only whole words.")]
+    pub word_regexp: bool,
+    #[arg(
+        short = 'l',
+        long,
+        help = "Print only the names of files with matches."
+    )]
+    pub files_with_matches: bool,
+    #[arg(long, help = "Also search untracked files that are not ignored.")]
+    pub untracked: bool,
+    #[arg(
+        long,
+        help = "Number of repositories to search in parallel (0 searches them one at a time)."
+    )]
+    pub parallel: Option<usize>,
+    #[arg(long, help = "Emit machine-readable JSON output.")]
+    pub json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -556,21 +1057,48 @@ pub struct TestArgs {
         help = "Run repositories in dependency-safe graph order."
     )]
     pub graph_order: bool,
-    #[arg(long, help = "Number of repositories to run in parallel.")]
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
     #[arg(long, help = "Stop after first test failure.")]
     pub fail_fast: bool,
     #[arg(
         long,
-        help = "Enable coverage mode when supported by ecosystem plugin."
+        value_name = "SECS",
+        help = "Kill a repo's command after this many seconds and report it as timed out (0 disables defaults.command_timeout)."
     )]
-    pub coverage: bool,
+    pub timeout: Option<u64>,
     #[arg(
-        short = 'k',
-        long = "filter",
-        help = "Filter expression forwarded to ecosystem test command when supported."
+        long,
+        help = "Enable coverage mode when supported by ecosystem plugin, then summarize coverage across repos."
     )]
-    pub filter: Option<String>,
+    pub coverage: bool,
+    #[arg(
+        long,
+        requires = "coverage",
+        help = "Print the coverage summary as JSON."
+    )]
+    pub json: bool,
+    #[arg(
+        long = "retry-failed",
+        value_name = "N",
+        default_value_t = 0,
+        help = "Rerun a repo's failing tests up to N more times; repos that then pass are reported as flaky."
+    )]
+    pub retry_failed: u32,
+    #[arg(
+        short = 'k',
+        long = "filter",
+        help = "Filter expression forwarded to ecosystem test command when supported."
+    )]
+    pub filter: Option<String>,
+    #[arg(
+        long,
+        help = "Keep running and re-test each repo whose files change (plus its dependents with --graph-order)."
+    )]
+    pub watch: bool,
 }
 
 #[derive(Args, Debug)]
@@ -583,10 +1111,288 @@ pub struct LintArgs {
     pub changed: bool,
     #[arg(long, help = "Apply auto-fixes where supported by ecosystem plugin.")]
     pub fix: bool,
-    #[arg(long, help = "Number of repositories to run in parallel.")]
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Kill a repo's command after this many seconds and report it as timed out (0 disables defaults.command_timeout)."
+    )]
+    pub timeout: Option<u64>,
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
+    pub parallel: Option<usize>,
+    #[arg(long, help = "Keep running and re-lint each repo whose files change.")]
+    pub watch: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PrefetchArgs {
+    #[arg(help = "Repositories to prefetch (defaults to all cloned repos).")]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Repository group to prefetch.")]
+    pub group: Option<String>,
+    #[arg(
+        long,
+        help = "Number of repositories to run in parallel (0 runs them one at a time)."
+    )]
     pub parallel: Option<usize>,
 }
 
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[arg(help = "Directory to assemble the export in (must not exist or be empty).")]
+    pub dest: PathBuf,
+    #[arg(
+        long,
+        required = true,
+        help = "Merge selected repos into one tree with one subdirectory per repo."
+    )]
+    pub merged: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to export (defaults to all repos)."
+    )]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Repository group to export.")]
+    pub group: Option<String>,
+    #[arg(
+        long,
+        help = "Include the transitive dependency closure of selected repositories."
+    )]
+    pub with_deps: bool,
+    #[arg(
+        long,
+        help = "Preserve each repository's git history via subtree merges."
+    )]
+    pub history: bool,
+    #[arg(long, help = "Also write <dest>.tar.gz next to the export directory.")]
+    pub archive: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PropagateArgs {
+    #[arg(help = "Repositories to update. Defaults to all configured repos.")]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Repository group to update.")]
+    pub group: Option<String>,
+    #[arg(
+        long,
+        help = "Report files that differ from the templates without writing; fails on drift."
+    )]
+    pub check: bool,
+    #[arg(long, help = "Write updated files without staging them.")]
+    pub no_stage: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[arg(long, help = "Emit machine-readable JSON output.")]
+    pub json: bool,
+    #[arg(long, help = "Delete recorded stats.")]
+    pub clear: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    #[command(about = "Delete cached manifests so the next command parses every repo again.")]
+    Clear,
+}
+
+#[derive(Args, Debug)]
+pub struct WebhookArgs {
+    #[command(subcommand)]
+    pub command: WebhookCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WebhookCommand {
+    #[command(
+        about = "Listen for GitHub and GitLab webhooks and run the [webhook.actions] command of each verified event."
+    )]
+    Serve(WebhookServeArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct WebhookServeArgs {
+    #[arg(
+        long,
+        help = "Address to listen on (default: [webhook].listen, then 127.0.0.1:8787)."
+    )]
+    pub listen: Option<String>,
+    #[arg(
+        long,
+        help = "Verify and filter deliveries, but only log the actions they would run."
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StateArgs {
+    #[command(subcommand)]
+    pub command: StateCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StateCommand {
+    #[command(
+        about = "Bundle config, changesets, MR state, snapshots, and selections into a tarball."
+    )]
+    Export(StateExportArgs),
+    #[command(about = "Restore workspace state from a bundle written by `state export`.")]
+    Import(StateImportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct StateExportArgs {
+    #[arg(help = "Bundle to write (default: harmonia-state-<timestamp>.tar.gz).")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Encrypt the bundle with openssl using the passphrase in HARMONIA_STATE_PASSPHRASE."
+    )]
+    pub encrypt: bool,
+    #[arg(long, help = "Overwrite an existing bundle.")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StateImportArgs {
+    #[arg(help = "Bundle written by `harmonia state export`.")]
+    pub archive: PathBuf,
+    #[arg(long, help = "Overwrite state files that differ from the bundle.")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateArgs {
+    #[command(subcommand)]
+    pub command: TemplateCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateCommand {
+    #[command(
+        about = "Check a template's syntax and variables, then print it rendered with a sample context."
+    )]
+    Render(TemplateRenderArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateRenderArgs {
+    #[arg(
+        help = "Template file (default: [mr].template, or [mr].issue_template with --kind issue)."
+    )]
+    pub path: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value = "mr",
+        help = "Which context the template receives: mr or issue."
+    )]
+    pub kind: String,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "JSON file to render with instead of the built-in sample context."
+    )]
+    pub context: Option<PathBuf>,
+    #[arg(long, help = "Only validate; do not print the rendered output.")]
+    pub check: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct LockArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to lock or restore (defaults to all cloned repos)."
+    )]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Check every locked repo out at its recorded commit.")]
+    pub restore: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ReleaseArgs {
+    #[arg(
+        help = "Repositories to release (defaults to every repo whose version is not tagged yet)."
+    )]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Push the new tags to each repo's remote.")]
+    pub push: bool,
+    #[arg(
+        long,
+        requires = "push",
+        help = "Also publish a forge release for each pushed tag."
+    )]
+    pub forge_release: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Tag name template with {version} and {repo} (default: [versioning].tag_format or v{version})."
+    )]
+    pub tag_format: Option<String>,
+    #[arg(
+        long,
+        help = "Create signed tags with `git tag -s` (default: [versioning].sign_tags); needs user.signingkey."
+    )]
+    pub sign: bool,
+    #[arg(
+        long,
+        help = "Show the tags that would be created without creating them."
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    pub command: Option<SnapshotCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommand {
+    #[command(about = "List recorded snapshots.")]
+    List,
+    #[command(about = "Record the commit, branch, and version of each repository.")]
+    Create(SnapshotCreateArgs),
+    #[command(about = "Show repositories whose commit, version, or lockfiles differ.")]
+    Diff(SnapshotDiffArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct SnapshotCreateArgs {
+    #[arg(help = "Snapshot name, e.g. staging or prod-2024-06-01.")]
+    pub name: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to record (defaults to all cloned repos)."
+    )]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Also record content hashes of known lockfiles.")]
+    pub lockfiles: bool,
+    #[arg(long, help = "Overwrite an existing snapshot with the same name.")]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SnapshotDiffArgs {
+    #[arg(help = "Snapshot to compare from.")]
+    pub from: String,
+    #[arg(help = "Snapshot to compare to.")]
+    pub to: String,
+    #[arg(long, help = "Emit machine-readable JSON output.")]
+    pub json: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct VersionArgs {
     #[command(subcommand)]
@@ -656,6 +1462,11 @@ pub enum DepsCommand {
     Check(DepsCheckArgs),
     #[command(about = "Update dependency files with new package version constraints.")]
     Update(DepsUpdateArgs),
+    #[command(
+        name = "bump-external",
+        about = "Bump an external dependency in every repo that uses it and open linked MRs."
+    )]
+    BumpExternal(DepsBumpExternalArgs),
 }
 
 #[derive(Args, Debug)]
@@ -678,6 +1489,33 @@ pub struct DepsUpdateArgs {
     pub dry_run: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct DepsBumpExternalArgs {
+    #[arg(long, help = "External package to update.")]
+    pub package: String,
+    #[arg(
+        long,
+        value_name = "CONSTRAINT",
+        help = "New version constraint, for example ^1.5 or 1.5.0."
+    )]
+    pub to: String,
+    #[arg(
+        long,
+        help = "Branch to create in each repo (default: deps/<package>-<constraint>)."
+    )]
+    pub branch_name: Option<String>,
+    #[arg(
+        short = 'm',
+        long,
+        help = "Commit message and MR title (default: chore(deps): bump <package> to <constraint>)."
+    )]
+    pub message: Option<String>,
+    #[arg(long, help = "Create MRs as drafts.")]
+    pub draft: bool,
+    #[arg(long, help = "Preview updates without writing files.")]
+    pub dry_run: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct EditArgs {
     #[arg(help = "Specific repositories to open.")]
@@ -689,6 +1527,24 @@ pub struct EditArgs {
     pub editor: Option<String>,
     #[arg(long, help = "Open changed repositories instead of workspace root.")]
     pub all: bool,
+    #[arg(
+        long,
+        help = "Open the changed files in the selected (or changed) repos instead of repo roots."
+    )]
+    pub files: bool,
+    #[arg(
+        long,
+        requires = "files",
+        help = "Editor arguments template with {editor}, {file} and {line}, for example '{editor} -g {file}:{line}'."
+    )]
+    pub editor_args: Option<String>,
+    #[arg(
+        long,
+        requires = "files",
+        value_name = "N",
+        help = "Open at most N files per editor invocation."
+    )]
+    pub batch_size: Option<usize>,
 }
 
 #[derive(Args, Debug)]
@@ -709,6 +1565,31 @@ pub struct CleanArgs {
     pub directories: bool,
     #[arg(short = 'x', long, help = "Also remove files ignored by .gitignore.")]
     pub ignored: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["directories", "ignored"],
+        help = "Run each repo's ecosystem cleanup (cargo clean, rm -rf node_modules, ...) instead of git clean."
+    )]
+    pub build_artifacts: bool,
+    #[arg(
+        long,
+        requires = "build_artifacts",
+        help = "With --build-artifacts, also clear caches shared outside the repo (go clean -cache)."
+    )]
+    pub shared_caches: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SetupArgs {
+    #[arg(help = "Repositories to check (defaults to all repos).")]
+    pub repos: Vec<String>,
+    #[arg(
+        long,
+        help = "Install missing toolchains with rustup and uv, and package managers with corepack."
+    )]
+    pub install: bool,
+    #[arg(long, help = "Print per-repo readiness as JSON.")]
+    pub json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -720,15 +1601,43 @@ pub struct ConfigArgs {
 #[derive(Subcommand, Debug)]
 pub enum ConfigCommand {
     #[command(about = "Print the effective workspace configuration TOML.")]
-    Show,
+    Show(ConfigShowArgs),
+    #[command(about = "List effective config values as dotted keys.")]
+    List(ConfigListArgs),
     #[command(about = "Read a single config value by dotted key path.")]
     Get(ConfigGetArgs),
     #[command(about = "Set a config value by dotted key path.")]
     Set(ConfigSetArgs),
+    #[command(
+        about = "Add values to an array, or name=value entries to a table, by dotted key path."
+    )]
+    Add(ConfigAddArgs),
+    #[command(about = "Remove values from an array, entries from a table, or a whole key.")]
+    Remove(ConfigRemoveArgs),
     #[command(about = "Open the workspace config file in an editor.")]
     Edit(ConfigEditArgs),
 }
 
+#[derive(Args, Debug, Default)]
+pub struct ConfigShowArgs {
+    #[arg(
+        long,
+        help = "Annotate each effective setting with the file and line or env var it comes from."
+    )]
+    pub origin: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigListArgs {
+    #[arg(help = "Only list keys under this dotted prefix, for example repos.api.")]
+    pub prefix: Option<String>,
+    #[arg(
+        long,
+        help = "List every key the workspace config understands instead of set values."
+    )]
+    pub schema: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct ConfigGetArgs {
     #[arg(help = "Dotted config path, for example workspace.name or defaults.clone_protocol.")]
@@ -743,6 +1652,29 @@ pub struct ConfigSetArgs {
     pub value: String,
 }
 
+#[derive(Args, Debug)]
+pub struct ConfigAddArgs {
+    #[arg(
+        help = "Dotted path of an array (for example groups.core) or table (for example repos.api)."
+    )]
+    pub key: String,
+    #[arg(
+        required = true,
+        help = "Values to append to an array, or name=value entries for a table. Values already present are skipped."
+    )]
+    pub values: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigRemoveArgs {
+    #[arg(help = "Dotted path of the array, table, or key to remove.")]
+    pub key: String,
+    #[arg(
+        help = "Array values or table entry names to remove. Without values the key itself is removed."
+    )]
+    pub values: Vec<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct ConfigEditArgs {
     #[arg(
@@ -768,11 +1700,126 @@ pub enum RepoCommand {
     Remove(RepoRemoveArgs),
     #[command(about = "Show repository details from workspace config.")]
     Show(RepoShowArgs),
+    #[command(
+        about = "Summarize a repository: description, version, open MRs, CI, last commit, and graph position."
+    )]
+    Info(RepoInfoArgs),
+    #[command(about = "Move a repository checkout and record its new path in config.")]
+    Move(RepoMoveArgs),
+    #[command(about = "Move all checkouts to a flat or grouped directory layout.")]
+    Relayout(RepoRelayoutArgs),
+    #[command(about = "Register an existing checkout, inferring its URL, branch, and ecosystem.")]
+    Adopt(RepoAdoptArgs),
+    #[command(about = "Temporarily exclude a repository from orchestration.")]
+    Pause(RepoPauseArgs),
+    #[command(about = "Include a paused repository again.")]
+    Resume(RepoResumeArgs),
+    #[command(about = "Read or write settings in repositories' own .harmonia.toml files.")]
+    Config(RepoConfigArgs),
 }
 
 #[derive(Args, Debug)]
-pub struct RepoAddArgs {
-    #[arg(help = "Repository key in [repos].")]
+pub struct RepoConfigArgs {
+    #[command(subcommand)]
+    pub command: RepoConfigCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RepoConfigCommand {
+    #[command(about = "List repo config values as dotted keys.")]
+    List(RepoConfigListArgs),
+    #[command(about = "Read a repo config value by dotted key path.")]
+    Get(RepoConfigGetArgs),
+    #[command(about = "Set a repo config value by dotted key path.")]
+    Set(RepoConfigSetArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RepoConfigListArgs {
+    #[arg(help = "Only list keys under this dotted prefix, for example hooks.")]
+    pub prefix: Option<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to read (defaults to all repos)."
+    )]
+    pub repos: Vec<String>,
+    #[arg(
+        long,
+        help = "List every key a repo config understands instead of set values."
+    )]
+    pub schema: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoConfigGetArgs {
+    #[arg(help = "Dotted config path, for example ci.required_checks or hooks.pre_commit.")]
+    pub key: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to read (defaults to all repos)."
+    )]
+    pub repos: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoConfigSetArgs {
+    #[arg(help = "Dotted config path to write.")]
+    pub key: String,
+    #[arg(help = "New value. Parsed as TOML when possible, otherwise stored as string.")]
+    pub value: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated repositories to write; names may be globs."
+    )]
+    pub repos: Vec<String>,
+    #[arg(long, help = "Write to every configured repository.")]
+    pub all: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoAdoptArgs {
+    #[arg(help = "Existing git checkout, relative to the workspace root.")]
+    pub path: PathBuf,
+    #[arg(
+        long,
+        help = "Repository key in [repos] (defaults to the directory name)."
+    )]
+    pub name: Option<String>,
+    #[arg(long, help = "Clone URL (defaults to the origin remote).")]
+    pub url: Option<String>,
+    #[arg(
+        long = "default-branch",
+        help = "Default branch (defaults to the remote HEAD branch)."
+    )]
+    pub default_branch: Option<String>,
+    #[arg(long, help = "Ecosystem id (defaults to the detected manifest).")]
+    pub ecosystem: Option<String>,
+    #[arg(long, help = "Optional group name to place this repository into.")]
+    pub group: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoPauseArgs {
+    #[arg(help = "Repository key to pause.")]
+    pub name: String,
+    #[arg(long, help = "Resume automatically on this date (YYYY-MM-DD).")]
+    pub until: Option<String>,
+    #[arg(long, help = "Why the repository is paused, shown in status.")]
+    pub reason: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoResumeArgs {
+    #[arg(help = "Repository key to resume.")]
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoAddArgs {
+    #[arg(help = "Repository key in [repos].")]
     pub name: String,
     #[arg(long, help = "Explicit clone URL.")]
     pub url: Option<String>,
@@ -808,6 +1855,36 @@ pub struct RepoShowArgs {
     pub name: String,
 }
 
+#[derive(Args, Debug)]
+pub struct RepoInfoArgs {
+    #[arg(help = "Repository key to summarize.")]
+    pub name: String,
+    #[arg(long, help = "Print the summary as JSON.")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoMoveArgs {
+    #[arg(help = "Repository key to move.")]
+    pub name: String,
+    #[arg(help = "New checkout path (relative to workspace root or absolute).")]
+    pub new_path: PathBuf,
+    #[arg(long, help = "Print the move without touching files or config.")]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RepoRelayoutArgs {
+    #[arg(
+        long,
+        default_value = "grouped",
+        help = "Target layout: flat (<repos_dir>/<name>) or grouped (<repos_dir>/<group>/<name>)."
+    )]
+    pub layout: String,
+    #[arg(long, help = "Print planned moves without touching files or config.")]
+    pub dry_run: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct PlanArgs {
     #[arg(long, help = "Emit machine-readable JSON output.")]
@@ -824,6 +1901,120 @@ pub struct PlanArgs {
         help = "Comma-separated repositories to exclude."
     )]
     pub exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "tree",
+        help = "Render the changed repos with merge-order numbers and MR status (tree|dot|mermaid)."
+    )]
+    pub graph: Option<String>,
+    #[arg(
+        long,
+        help = "Simulate merging the changed repos in order: predicted versions and constraint violations after each merge."
+    )]
+    pub simulate: bool,
+    #[arg(
+        long,
+        requires = "simulate",
+        help = "Bump level (patch, minor, major) assumed for each simulated release."
+    )]
+    pub level: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "graph"],
+        help = "Run dependents' tests against the local checkouts of the changed repos."
+    )]
+    pub verify: bool,
+    #[arg(
+        long,
+        help = "Fetch the workspace repos first so ahead/behind and recommendations use current remote state."
+    )]
+    pub fresh: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct IssueArgs {
+    #[command(subcommand)]
+    pub command: IssueCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IssueCommand {
+    #[command(about = "List issues, newest first.")]
+    List(IssueListArgs),
+    #[command(about = "Open a new issue.")]
+    Create(IssueCreateArgs),
+    #[command(about = "Close an issue, optionally leaving a comment first.")]
+    Close(IssueCloseArgs),
+    #[command(about = "Comment on an issue.")]
+    Comment(IssueCommentArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct IssueListArgs {
+    #[arg(
+        long,
+        help = "Workspace repo whose issues to list (defaults to the [mr.coordination] repo)."
+    )]
+    pub repo: Option<String>,
+    #[arg(
+        long,
+        default_value = "open",
+        help = "Issue state: open, closed, or all."
+    )]
+    pub state: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated labels issues must all carry."
+    )]
+    pub labels: Vec<String>,
+    #[arg(long, default_value_t = 30, help = "Maximum number of issues to list.")]
+    pub limit: usize,
+    #[arg(long, help = "Emit machine-readable JSON output.")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct IssueCreateArgs {
+    #[arg(
+        long,
+        help = "Workspace repo to open the issue in (defaults to the [mr.coordination] repo)."
+    )]
+    pub repo: Option<String>,
+    #[arg(long, help = "Issue title.")]
+    pub title: String,
+    #[arg(long, default_value = "", help = "Issue description.")]
+    pub body: String,
+    #[arg(long, value_delimiter = ',', help = "Comma-separated labels to apply.")]
+    pub labels: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct IssueCloseArgs {
+    #[arg(help = "Issue number.")]
+    pub iid: u64,
+    #[arg(
+        long,
+        help = "Workspace repo the issue is in (defaults to the [mr.coordination] repo)."
+    )]
+    pub repo: Option<String>,
+    #[arg(long, help = "Comment to post before closing.")]
+    pub comment: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct IssueCommentArgs {
+    #[arg(help = "Issue number.")]
+    pub iid: u64,
+    #[arg(
+        long,
+        help = "Workspace repo the issue is in (defaults to the [mr.coordination] repo)."
+    )]
+    pub repo: Option<String>,
+    #[arg(long, help = "Comment text.")]
+    pub body: String,
 }
 
 #[derive(Args, Debug)]
@@ -844,6 +2035,28 @@ pub enum MrCommand {
     Merge(MrMergeArgs),
     #[command(about = "Close open merge requests without merging.")]
     Close(MrCloseArgs),
+    #[command(
+        about = "Report tracked MRs in merge order with CI, approvals, and blockers, optionally as a static HTML page."
+    )]
+    Report(MrReportArgs),
+    #[command(
+        about = "Share tracked MR state with teammates through a branch of the workspace repo."
+    )]
+    State(MrStateArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct MrStateArgs {
+    #[command(subcommand)]
+    pub command: MrStateCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MrStateCommand {
+    #[command(about = "Merge local MR state into the shared state branch and push it.")]
+    Push,
+    #[command(about = "Merge the shared state branch into local MR state.")]
+    Pull,
 }
 
 #[derive(Args, Debug, Default)]
@@ -869,6 +2082,11 @@ pub struct MrCreateArgs {
         help = "Comma-separated reviewer usernames."
     )]
     pub reviewers: Vec<String>,
+    #[arg(
+        long,
+        help = "How team reviewers are spread across MRs: all, round-robin, or load. Overrides mr.reviewer_strategy."
+    )]
+    pub reviewer_strategy: Option<String>,
     #[arg(
         long,
         help = "Automatically create and switch changed default-branch repos onto a feature branch before creating MRs."
@@ -876,9 +2094,14 @@ pub struct MrCreateArgs {
     pub auto_branch: bool,
     #[arg(
         long,
-        help = "Branch name used with --auto-branch. Defaults to active changeset branch, then a generated feature/harmonia-<timestamp> name."
+        help = "Branch name used with --auto-branch. Defaults to active changeset branch, then a name generated from [branching].template."
     )]
     pub branch_name: Option<String>,
+    #[arg(
+        long,
+        help = "Skip repos that already have a tracked MR for their current branch."
+    )]
+    pub skip_existing: bool,
     #[arg(long, help = "Preview MR payloads without calling forge APIs.")]
     pub dry_run: bool,
 }
@@ -895,6 +2118,16 @@ pub struct MrStatusArgs {
         help = "Wait timeout in minutes when --wait is enabled."
     )]
     pub timeout: u64,
+    #[arg(
+        long,
+        help = "Fetch the repos with tracked MRs first, or the plan's repos when none are tracked."
+    )]
+    pub fresh: bool,
+    #[arg(
+        long,
+        help = "Show each MR's changed files and added/deleted lines, with totals."
+    )]
+    pub diff: bool,
 }
 
 #[derive(Args, Debug, Default)]
@@ -920,10 +2153,48 @@ pub struct MrMergeArgs {
     pub no_wait: bool,
     #[arg(long, help = "Request squash merge when forge supports it.")]
     pub squash: bool,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        requires = "squash",
+        help = "Squash commit message template (overrides [mr].squash_template)."
+    )]
+    pub squash_message: Option<String>,
     #[arg(long = "delete-branch", help = "Delete source branches after merge.")]
     pub delete_branch: bool,
     #[arg(short = 'y', long, help = "Skip confirmation prompts.")]
     pub yes: bool,
+    #[arg(
+        long,
+        help = "Allow merging into [safety].release_branches (requires --yes)."
+    )]
+    pub confirm_release: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Stream structured progress events to stdout; FORMAT is ndjson."
+    )]
+    pub events: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "events",
+        help = "Write --events to FILE instead of stdout."
+    )]
+    pub events_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct MrReportArgs {
+    #[arg(long, help = "Render a static HTML status page instead of text.")]
+    pub html: bool,
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "PATH",
+        help = "Write the report to PATH instead of stdout."
+    )]
+    pub output: Option<PathBuf>,
 }
 
 #[derive(Args, Debug, Default)]
@@ -954,41 +2225,225 @@ pub struct CompletionArgs {
 }
 
 pub fn run() {
-    let cli = Cli::parse();
-    if let Err(err) = dispatch(cli) {
+    let matches = Cli::command().get_matches();
+    let command = subcommand_path(&matches);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let started = Instant::now();
+    let result = dispatch(cli);
+    record_command_stats(&command, started.elapsed(), result.as_ref().err());
+    report::finish(&command, result.as_ref().err().map(|err| err.to_string()));
+    if let Err(err) = &result {
         output::error(&err.to_string());
+    }
+    if let Some(signal) = interrupt_signal() {
+        std::process::exit(128 + signal);
+    }
+    if result.is_err() {
         std::process::exit(1);
     }
 }
 
+/// Space-separated subcommand names of an invocation, e.g. `mr merge`.
+/// Switches a command to its own JSON output when the global `--json` flag is
+/// set; commands without one get the generic `report` summary instead.
+fn native_json(flag: &mut bool) {
+    if report::enabled() {
+        *flag = true;
+        report::native();
+    }
+}
+
+/// Like [`native_json`] for commands that pick JSON through a format option.
+fn native_json_format(format: &mut String, json: &str) {
+    if report::enabled() {
+        *format = json.to_string();
+        report::native();
+    }
+}
+
+fn subcommand_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name.to_string());
+        current = sub;
+    }
+    names.join(" ")
+}
+
+/// Workspace root to record stats into, set by `load_workspace` when
+/// `[workspace].stats` is enabled.
+static STATS_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Largest repo selection made during this invocation.
+static STATS_REPOS: AtomicUsize = AtomicUsize::new(0);
+
+/// Appends this invocation to `.harmonia/stats.db`. Stats are best effort and
+/// never change the outcome of the command.
+fn record_command_stats(command: &str, elapsed: Duration, err: Option<&HarmoniaError>) {
+    let Some(root) = STATS_ROOT.get() else {
+        return;
+    };
+    if command == "stats" {
+        return;
+    }
+    let record = StatsRecord {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+        command: command.to_string(),
+        duration_ms: elapsed.as_millis() as u64,
+        repos: STATS_REPOS.load(AtomicOrdering::Relaxed),
+        success: err.is_none(),
+        failure: err.map(|err| failure_category(err).to_string()),
+    };
+    if let Err(err) = append_stats(root, &record) {
+        output::warn(&format!("failed to record stats: {}", err));
+    }
+}
+
+/// Selection flags for this invocation, applied by `select_repos`.
+#[derive(Debug, Default)]
+struct RepoSelection {
+    /// `--select` expression.
+    expr: Option<SelectExpr>,
+    /// `--selection` name to reuse.
+    reuse: Option<String>,
+    /// `--save-selection` name to record.
+    save: Option<String>,
+}
+
+static REPO_SELECTION: OnceLock<RepoSelection> = OnceLock::new();
+
+/// Whether `--read-only` was given for this invocation.
+static READ_ONLY: OnceLock<bool> = OnceLock::new();
+
+/// `--changed-as` kinds for this invocation; empty defers to
+/// `[defaults].changed_as`.
+static CHANGED_AS: OnceLock<Vec<ChangeKind>> = OnceLock::new();
+
 fn dispatch(cli: Cli) -> Result<()> {
+    let _ = READ_ONLY.set(cli.read_only);
+    let _ = CHANGED_AS.set(parse_change_kinds(&cli.changed_as)?);
+    if cli.json {
+        report::enable();
+    }
+    if cli.no_color || env::var_os("HARMONIA_NO_COLOR").is_some() {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    let team = cli.team.clone().map(SelectExpr::Team);
+    let expr = match (
+        cli.select.as_deref().map(SelectExpr::parse).transpose()?,
+        team,
+    ) {
+        (Some(expr), Some(team)) => Some(SelectExpr::And(Box::new(expr), Box::new(team))),
+        (expr, team) => expr.or(team),
+    };
+    let _ = REPO_SELECTION.set(RepoSelection {
+        expr,
+        reuse: cli.selection.clone(),
+        save: cli.save_selection.clone(),
+    });
     match cli.command {
         Commands::Init(args) => handle_init(args),
-        Commands::Clone(args) => handle_clone(args, cli.workspace, cli.config),
-        Commands::Status(args) => handle_status(args, cli.workspace, cli.config),
+        Commands::Clone(mut args) => {
+            native_json_format(&mut args.report, "json");
+            handle_clone(args, cli.workspace, cli.config)
+        }
+        Commands::Status(mut args) => {
+            native_json(&mut args.json);
+            handle_status(args, cli.workspace, cli.config)
+        }
         Commands::Sync(args) => handle_sync(args, cli.workspace, cli.config),
         Commands::Refresh(args) => handle_refresh(args, cli.workspace, cli.config),
         Commands::Submit(args) => handle_submit(args, cli.workspace, cli.config),
-        Commands::Exec(args) => handle_exec(args, cli.workspace, cli.config),
+        Commands::Exec(mut args) => {
+            native_json_format(&mut args.output, "json");
+            handle_exec(args, cli.workspace, cli.config)
+        }
         Commands::Run(args) => handle_run(args, cli.workspace, cli.config),
-        Commands::Each(args) => handle_each(args, cli.workspace, cli.config),
+        Commands::Each(mut args) => {
+            native_json_format(&mut args.output, "json");
+            handle_each(args, cli.workspace, cli.config)
+        }
+        Commands::Git(args) => handle_git(args, cli.workspace, cli.config),
         Commands::Branch(args) => handle_branch(args, cli.workspace, cli.config),
         Commands::Checkout(args) => handle_checkout(args, cli.workspace, cli.config),
-        Commands::Graph(args) => handle_graph(args, cli.workspace, cli.config),
+        Commands::Graph(args) => {
+            let workspace = load_workspace(cli.workspace, cli.config)?;
+            handle_graph(args, &WorkspaceState::new(&workspace))
+        }
         Commands::Add(args) => handle_add(args, cli.workspace, cli.config),
-        Commands::Commit(args) => handle_commit(args, cli.workspace, cli.config),
-        Commands::Push(args) => handle_push(args, cli.workspace, cli.config),
-        Commands::Diff(args) => handle_diff(args, cli.workspace, cli.config),
-        Commands::Test(args) => handle_test(args, cli.workspace, cli.config),
+        Commands::Commit(mut args) => {
+            native_json(&mut args.json);
+            handle_commit(args, cli.workspace, cli.config)
+        }
+        Commands::Push(mut args) => {
+            native_json(&mut args.json);
+            handle_push(args, cli.workspace, cli.config)
+        }
+        Commands::Diff(mut args) => {
+            native_json_format(&mut args.format, "json");
+            handle_diff(args, cli.workspace, cli.config)
+        }
+        Commands::Grep(mut args) => {
+            native_json(&mut args.json);
+            handle_grep(args, cli.workspace, cli.config)
+        }
+        Commands::Test(mut args) => {
+            if args.coverage {
+                native_json(&mut args.json);
+            }
+            handle_test(args, cli.workspace, cli.config)
+        }
         Commands::Lint(args) => handle_lint(args, cli.workspace, cli.config),
-        Commands::Version(args) => handle_version(args, cli.workspace, cli.config),
-        Commands::Deps(args) => handle_deps(args, cli.workspace, cli.config),
+        Commands::Prefetch(args) => handle_prefetch(args, cli.workspace, cli.config),
+        Commands::Version(args) => {
+            let workspace = load_workspace(cli.workspace, cli.config)?;
+            handle_version(args, &WorkspaceState::new(&workspace))
+        }
+        Commands::Deps(args) => {
+            let workspace = load_workspace(cli.workspace.clone(), cli.config.clone())?;
+            handle_deps(
+                args,
+                &WorkspaceState::new(&workspace),
+                cli.workspace,
+                cli.config,
+            )
+        }
         Commands::Edit(args) => handle_edit(args, cli.workspace, cli.config),
         Commands::Clean(args) => handle_clean(args, cli.workspace, cli.config),
+        Commands::Setup(mut args) => {
+            native_json(&mut args.json);
+            handle_setup(args, cli.workspace, cli.config)
+        }
         Commands::Config(args) => handle_config(args, cli.workspace, cli.config),
         Commands::Repo(args) => handle_repo(args, cli.workspace, cli.config),
-        Commands::Plan(args) => handle_plan(args, cli.workspace, cli.config),
-        Commands::Mr(args) => handle_mr(args, cli.workspace, cli.config),
+        Commands::Plan(mut args) => {
+            native_json(&mut args.json);
+            let workspace = load_workspace(cli.workspace, cli.config)?;
+            handle_plan(args, &WorkspaceState::new(&workspace))
+        }
+        Commands::Mr(args) => {
+            let workspace = load_workspace(cli.workspace, cli.config)?;
+            handle_mr(args, &WorkspaceState::new(&workspace))
+        }
+        Commands::Issue(args) => handle_issue(args, cli.workspace, cli.config),
+        Commands::Snapshot(args) => handle_snapshot(args, cli.workspace, cli.config),
+        Commands::Lock(args) => handle_lock(args, cli.workspace, cli.config),
+        Commands::Release(args) => handle_release(args, cli.workspace, cli.config),
+        Commands::Export(args) => handle_export(args, cli.workspace, cli.config),
+        Commands::Propagate(args) => handle_propagate(args, cli.workspace, cli.config),
+        Commands::State(args) => handle_state(args, cli.workspace, cli.config),
+        Commands::Template(args) => handle_template(args, cli.workspace, cli.config),
+        Commands::Stats(mut args) => {
+            native_json(&mut args.json);
+            handle_stats(args, cli.workspace, cli.config)
+        }
+        Commands::Cache(args) => handle_cache(args, cli.workspace, cli.config),
+        Commands::Webhook(args) => handle_webhook(args, cli.workspace, cli.config),
         Commands::Completion(args) => handle_completion(args),
         Commands::Shell(args) => handle_shell(args, cli.workspace, cli.config),
     }
@@ -1000,23 +2455,36 @@ fn handle_init(args: InitArgs) -> Result<()> {
         fs::create_dir_all(&target_dir)?;
     }
 
+    let mut clone = !args.no_clone;
     if let Some(source) = args.source.as_ref() {
         if target_dir.read_dir()?.next().is_some() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(
                 "target directory is not empty"
             )));
         }
-        if Path::new(source).is_dir() {
+        if let Some((forge_type, group)) = parse_forge_source(source)? {
+            seed_workspace_from_forge(&target_dir, forge_type, &group, &args)?;
+            // A whole org is rarely wanted locally; only clone a chosen group.
+            clone &= args.group.is_some();
+        } else if Path::new(source).is_dir() {
             copy_workspace_from_path(Path::new(source), &target_dir)?;
         } else {
             output::git_op(&format!("clone {} {}", source, target_dir.display()));
             clone_repo(source, &target_dir, None)?;
         }
+    } else if let Some(template) = args.template.as_ref() {
+        if target_dir.read_dir()?.next().is_some() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "target directory is not empty"
+            )));
+        }
+        apply_workspace_template(template, &target_dir, &args)?;
+        ensure_workspace_layout(&target_dir, args.name.as_deref())?;
     } else {
         ensure_workspace_layout(&target_dir, args.name.as_deref())?;
     }
 
-    if !args.no_clone {
+    if clone {
         let clone_args = CloneArgs {
             repos: Vec::new(),
             group: args.group,
@@ -1025,6 +2493,8 @@ fn handle_init(args: InitArgs) -> Result<()> {
             full: false,
             protocol: None,
             strict: false,
+            verify: false,
+            report: "text".to_string(),
         };
         handle_clone(clone_args, Some(target_dir.clone()), None)?;
     }
@@ -1032,22 +2502,257 @@ fn handle_init(args: InitArgs) -> Result<()> {
     Ok(())
 }
 
-fn determine_init_directory(args: &InitArgs) -> Result<PathBuf> {
-    if let Some(dir) = args.directory.as_ref() {
-        return Ok(dir.clone());
+/// Splits `gitlab://group/subgroup` or `github://org` into the forge type and
+/// group path. Other sources return `None`.
+fn parse_forge_source(source: &str) -> Result<Option<(&'static str, String)>> {
+    let (forge_type, rest) = if let Some(rest) = source.strip_prefix("gitlab://") {
+        ("gitlab", rest)
+    } else if let Some(rest) = source.strip_prefix("github://") {
+        ("github", rest)
+    } else {
+        return Ok(None);
+    };
+    let group = rest.trim().trim_matches('/');
+    if group.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} source is missing a group (expected {}://<group>)",
+            forge_type, forge_type
+        ))));
     }
-
-    if let Some(source) = args.source.as_ref() {
-        let path = Path::new(source);
-        if path.is_dir() {
-            if let Some(name) = path.file_name() {
-                return Ok(PathBuf::from(name));
-            }
-        }
-
-        if let Some(name) = derive_repo_name(source) {
-            return Ok(PathBuf::from(name));
-        }
+    if forge_type == "github" && group.contains('/') {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "github source '{}' must name a single org",
+            source
+        ))));
+    }
+    Ok(Some((forge_type, group.to_string())))
+}
+
+/// Writes a workspace config listing every project in a forge group, with
+/// one harmonia group per forge subgroup.
+fn seed_workspace_from_forge(
+    target: &Path,
+    forge_type: &str,
+    group: &str,
+    args: &InitArgs,
+) -> Result<()> {
+    let forge_config = crate::config::ForgeConfig {
+        forge_type: forge_type.to_string(),
+        host: args.forge_host.clone(),
+        default_group: Some(group.to_string()),
+        token: None,
+        ..Default::default()
+    };
+    let client = client_from_forge_config(&forge_config)?;
+    let mut projects = client.list_projects(group)?;
+    let total = projects.len();
+    if !args.include_archived {
+        projects.retain(|project| !project.archived);
+    }
+    if projects.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "no projects found in {}://{}",
+            forge_type, group
+        ))));
+    }
+
+    ensure_workspace_layout(target, args.name.as_deref())?;
+    let config_path = target.join(".harmonia").join("config.toml");
+    let mut value = read_workspace_config_value(&config_path)?;
+    let root = value.as_table_mut().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!("workspace config root must be a table"))
+    })?;
+
+    let mut forge = toml::map::Map::new();
+    forge.insert(
+        "type".to_string(),
+        toml::Value::String(forge_type.to_string()),
+    );
+    if let Some(host) = args.forge_host.as_ref() {
+        forge.insert("host".to_string(), toml::Value::String(host.clone()));
+    }
+    forge.insert(
+        "default_group".to_string(),
+        toml::Value::String(group.to_string()),
+    );
+    root.insert("forge".to_string(), toml::Value::Table(forge));
+
+    let mut repos = toml::map::Map::new();
+    let mut groups: std::collections::BTreeMap<String, Vec<toml::Value>> =
+        std::collections::BTreeMap::new();
+    for (name, group_name, project) in forge_project_entries(group, &projects) {
+        let mut entry = toml::map::Map::new();
+        entry.insert(
+            "url".to_string(),
+            toml::Value::String(project.ssh_url.clone()),
+        );
+        if let Some(branch) = project.default_branch.as_ref() {
+            entry.insert(
+                "default_branch".to_string(),
+                toml::Value::String(branch.clone()),
+            );
+        }
+        groups
+            .entry(group_name)
+            .or_default()
+            .push(toml::Value::String(name.clone()));
+        repos.insert(name, toml::Value::Table(entry));
+    }
+    let group_count = groups.len();
+    root.insert("repos".to_string(), toml::Value::Table(repos));
+    root.insert(
+        "groups".to_string(),
+        toml::Value::Table(
+            groups
+                .into_iter()
+                .map(|(name, members)| (name, toml::Value::Array(members)))
+                .collect(),
+        ),
+    );
+    write_workspace_config_value(&config_path, &value)?;
+
+    output::info(&format!(
+        "added {} repos in {} groups from {}://{}{}",
+        projects.len(),
+        group_count,
+        forge_type,
+        group,
+        if total > projects.len() {
+            format!(" ({} archived skipped)", total - projects.len())
+        } else {
+            String::new()
+        }
+    ));
+    Ok(())
+}
+
+/// Repo name and harmonia group for each project. Projects directly in
+/// `root_group` go to a group named after its last segment; projects in
+/// subgroups go to a group named after the subgroup path, joined with `-`.
+/// Names that repeat across subgroups are prefixed with that group.
+fn forge_project_entries<'a>(
+    root_group: &str,
+    projects: &'a [crate::forge::ForgeProject],
+) -> Vec<(String, String, &'a crate::forge::ForgeProject)> {
+    let root_group = root_group.trim_matches('/');
+    let root_name = root_group.rsplit('/').next().unwrap_or(root_group);
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for project in projects {
+        *name_counts.entry(project.name.as_str()).or_default() += 1;
+    }
+
+    let mut entries: Vec<_> = projects
+        .iter()
+        .map(|project| {
+            let relative = project
+                .namespace
+                .strip_prefix(root_group)
+                .unwrap_or(&project.namespace)
+                .trim_matches('/');
+            let group = if relative.is_empty() {
+                root_name.to_string()
+            } else {
+                relative.replace('/', "-")
+            };
+            let name = if name_counts[project.name.as_str()] > 1 && !relative.is_empty() {
+                format!("{}-{}", group, project.name)
+            } else {
+                project.name.clone()
+            };
+            (name, group, project)
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn apply_workspace_template(template: &str, target: &Path, args: &InitArgs) -> Result<()> {
+    let workspace_name = args
+        .name
+        .clone()
+        .or_else(|| {
+            target
+                .canonicalize()
+                .ok()
+                .and_then(|path| path.file_name().and_then(OsStr::to_str).map(str::to_string))
+        })
+        .unwrap_or_else(|| "workspace".to_string());
+    let mut context = serde_json::Map::new();
+    for var in &args.vars {
+        let (key, value) = var.split_once('=').ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "invalid --var '{}' (expected KEY=VALUE)",
+                var
+            )))
+        })?;
+        context.insert(key.trim().to_string(), serde_json::json!(value));
+    }
+    context.insert(
+        "workspace_name".to_string(),
+        serde_json::json!(workspace_name),
+    );
+    if let Some(group) = args.forge_group.as_ref() {
+        context.insert("forge_group".to_string(), serde_json::json!(group));
+    }
+    context
+        .entry("forge_group".to_string())
+        .or_insert_with(|| serde_json::json!(""));
+    let context = serde_json::Value::Object(context);
+
+    let local = Path::new(template);
+    let written = if local.is_dir() {
+        render_template_dir(local, target, &context)?
+    } else {
+        let url = resolve_template_url(template);
+        let staging = env::temp_dir().join(format!(
+            "harmonia-template-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos())
+                .unwrap_or_default()
+        ));
+        output::git_op(&format!("clone {} (template)", url));
+        let result = clone_repo(&url, &staging, Some(1))
+            .and_then(|_| render_template_dir(&staging, target, &context));
+        let _ = fs::remove_dir_all(&staging);
+        result?
+    };
+    output::info(&format!(
+        "applied template {} ({} files)",
+        template,
+        written.len()
+    ));
+    Ok(())
+}
+
+/// Expands `gh:org/repo` and `gl:group/repo` shorthands to HTTPS clone URLs.
+fn resolve_template_url(template: &str) -> String {
+    if let Some(path) = template.strip_prefix("gh:") {
+        return format!("https://github.com/{}.git", path.trim_end_matches(".git"));
+    }
+    if let Some(path) = template.strip_prefix("gl:") {
+        return format!("https://gitlab.com/{}.git", path.trim_end_matches(".git"));
+    }
+    template.to_string()
+}
+
+fn determine_init_directory(args: &InitArgs) -> Result<PathBuf> {
+    if let Some(dir) = args.directory.as_ref() {
+        return Ok(dir.clone());
+    }
+
+    if let Some(source) = args.source.as_ref() {
+        let path = Path::new(source);
+        if path.is_dir() {
+            if let Some(name) = path.file_name() {
+                return Ok(PathBuf::from(name));
+            }
+        }
+
+        if let Some(name) = derive_repo_name(source) {
+            return Ok(PathBuf::from(name));
+        }
     }
 
     env::current_dir().map_err(HarmoniaError::from)
@@ -1170,9 +2875,16 @@ fn handle_clone(
         .and_then(|defaults| defaults.clone_depth.as_deref());
     let depth = parse_depth(args.depth.as_deref(), args.full, default_depth)?;
     let protocol = resolve_clone_protocol(args.protocol.as_deref(), &workspace)?;
-    let jobs = resolve_parallel(None);
+    let web_root = workspace_web_root(&workspace);
+    let jobs = resolve_parallel(&workspace, "clone", None);
+    let network = network_queue(&workspace);
 
+    let names: Vec<String> = repos
+        .iter()
+        .map(|repo| repo.id.as_str().to_string())
+        .collect();
     let results = parallel::run_in_parallel(repos, jobs, |repo| {
+        let _permit = network.acquire();
         if repo.remote_url.is_empty() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
                 "repo {} missing url",
@@ -1180,30 +2892,273 @@ fn handle_clone(
             ))));
         }
 
-        if !args.strict && repo.path.exists() {
-            output::warn(&format!(
-                "skipping {} because {} already exists",
-                repo.id.as_str(),
-                repo.path.display()
+        let stale = remove_stale_clone_staging(&repo.path)?;
+        if stale > 0 {
+            output::info(&format!(
+                "removed {} stale clone staging dir(s) for {}",
+                stale,
+                repo.id.as_str()
             ));
-            return Ok(());
         }
 
-        let clone_url = resolve_clone_url(&repo.remote_url, protocol.as_deref());
+        match inspect_clone(&repo.path) {
+            CloneHealth::Missing => {}
+            CloneHealth::Valid => {
+                if args.verify {
+                    output::git_op(&format!("fsck (repo {})", repo.id.as_str()));
+                    verify_clone(&repo.path)?;
+                    output::info(&format!("verified {}", repo.id.as_str()));
+                    return Ok(CloneOutcome::Verified);
+                }
+                if args.strict {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "{} already exists at {}",
+                        repo.id.as_str(),
+                        repo.path.display()
+                    ))));
+                }
+                output::warn(&format!(
+                    "skipping {} because {} already exists",
+                    repo.id.as_str(),
+                    repo.path.display()
+                ));
+                return Ok(CloneOutcome::Skipped);
+            }
+            CloneHealth::Partial(reason) => {
+                output::warn(&format!(
+                    "removing incomplete clone of {} at {} ({}); cloning again",
+                    repo.id.as_str(),
+                    repo.path.display(),
+                    reason
+                ));
+                remove_clone_dir(&repo.path)?;
+            }
+            CloneHealth::Foreign(reason) => {
+                let message = format!(
+                    "{} exists but is not a usable clone ({}); move it aside to clone {}",
+                    repo.path.display(),
+                    reason,
+                    repo.id.as_str()
+                );
+                if args.strict || args.verify {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(message)));
+                }
+                output::warn(&format!("skipping: {}", message));
+                return Ok(CloneOutcome::Conflict(message));
+            }
+        }
+
+        let clone_url = resolve_clone_url(&repo.remote_url, protocol.as_deref(), &web_root);
         if let Some(parent) = repo.path.parent() {
             crate::git::ops::ensure_repo_dir(parent)?;
         }
-        output::git_op(&format!("clone {} {}", clone_url, repo.path.display()));
-        clone_repo(&clone_url, &repo.path, depth)
+        let mut attempt = 1;
+        loop {
+            output::git_op(&format!("clone {} {}", clone_url, repo.path.display()));
+            match clone_repo_atomic(&clone_url, &repo.path, depth) {
+                Ok(()) => {
+                    if let Ok(open) = open_repo(&repo.path) {
+                        warn_case_conflicts(repo.id.as_str(), &case_conflicts(&open.repo)?);
+                    }
+                    return Ok(CloneOutcome::Cloned);
+                }
+                Err(err) if attempt < CLONE_ATTEMPTS && !repo.path.exists() => {
+                    output::warn(&format!(
+                        "clone of {} failed ({}); retrying",
+                        repo.id.as_str(),
+                        err
+                    ));
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
     });
 
-    for result in results {
-        result?;
+    let mut report = CloneReport::default();
+    for (name, result) in names.into_iter().zip(results) {
+        match result {
+            Ok(CloneOutcome::Cloned) => report.cloned.push(name),
+            Ok(CloneOutcome::Verified) => report.verified.push(name),
+            Ok(CloneOutcome::Skipped) => report.skipped.push(name),
+            Ok(CloneOutcome::Conflict(message)) => report.failed.push(CloneFailure {
+                repo: name,
+                kind: "conflict",
+                error: message,
+                fatal: false,
+            }),
+            Err(err) => {
+                let error = error_chain(&err);
+                report.failed.push(CloneFailure {
+                    repo: name,
+                    kind: clone_failure_kind(&error),
+                    error,
+                    fatal: true,
+                });
+            }
+        }
+    }
+    let retry = clone_retry_command(&report.failed, &args);
+
+    if args.report == "json" {
+        let value = serde_json::json!({
+            "cloned": report.cloned,
+            "verified": report.verified,
+            "skipped": report.skipped,
+            "failed": report
+                .failed
+                .iter()
+                .map(|failure| serde_json::json!({
+                    "repo": failure.repo,
+                    "kind": failure.kind,
+                    "error": failure.error,
+                }))
+                .collect::<Vec<_>>(),
+            "retry": retry,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
+    } else if report.total() > 1 || !report.failed.is_empty() {
+        output::info(&format!(
+            "clone: {} cloned, {} verified, {} skipped, {} failed",
+            report.cloned.len(),
+            report.verified.len(),
+            report.skipped.len(),
+            report.failed.len()
+        ));
+        for failure in &report.failed {
+            output::error(&format!(
+                "{} ({}): {}",
+                failure.repo, failure.kind, failure.error
+            ));
+        }
+        if let Some(retry) = &retry {
+            output::info(&format!("retry with: {}", retry));
+        }
     }
 
+    let fatal = report.failed.iter().filter(|failure| failure.fatal).count();
+    if fatal > 0 {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "clone failed for {} of {} repositories",
+            fatal,
+            report.total()
+        ))));
+    }
     Ok(())
 }
 
+enum CloneOutcome {
+    Cloned,
+    Verified,
+    Skipped,
+    /// The path holds something other than a usable clone and was left alone.
+    Conflict(String),
+}
+
+#[derive(Default)]
+struct CloneReport {
+    cloned: Vec<String>,
+    verified: Vec<String>,
+    skipped: Vec<String>,
+    failed: Vec<CloneFailure>,
+}
+
+impl CloneReport {
+    fn total(&self) -> usize {
+        self.cloned.len() + self.verified.len() + self.skipped.len() + self.failed.len()
+    }
+}
+
+struct CloneFailure {
+    repo: String,
+    /// auth, network, conflict, missing-url, or other.
+    kind: &'static str,
+    error: String,
+    /// False for conflicts that were skipped without failing the command.
+    fatal: bool,
+}
+
+/// An error and its sources joined into one line, so that the git or network
+/// cause is not hidden behind a generic "git error".
+fn error_chain(err: &HarmoniaError) -> String {
+    let mut parts = vec![err.to_string()];
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !parts.iter().any(|part| part.contains(&text)) {
+            parts.push(text);
+        }
+        source = cause.source();
+    }
+    parts.join(": ")
+}
+
+fn clone_failure_kind(error: &str) -> &'static str {
+    let lower = error.to_ascii_lowercase();
+    let matches = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+    if matches(&["missing url"]) {
+        "missing-url"
+    } else if matches(&["already exists", "not a usable clone"]) {
+        "conflict"
+    } else if matches(&[
+        "authentication",
+        "permission denied",
+        "could not read username",
+        "401",
+        "403",
+        "unauthorized",
+        "forbidden",
+        "publickey",
+    ]) {
+        "auth"
+    } else if matches(&[
+        "could not resolve",
+        "resolve host",
+        "dns error",
+        "talking to the server",
+        "error sending request",
+        "connection refused",
+        "connection reset",
+        "timed out",
+        "timeout",
+        "network",
+        "unable to access",
+        "no route to host",
+    ]) {
+        "network"
+    } else {
+        "other"
+    }
+}
+
+/// `harmonia clone` invocation that retries the failed repos with the same
+/// depth and protocol options.
+fn clone_retry_command(failed: &[CloneFailure], args: &CloneArgs) -> Option<String> {
+    if failed.is_empty() {
+        return None;
+    }
+    let mut command = vec!["harmonia".to_string(), "clone".to_string()];
+    command.extend(failed.iter().map(|failure| failure.repo.clone()));
+    if args.full {
+        command.push("--full".to_string());
+    } else if let Some(depth) = &args.depth {
+        command.push(format!("--depth {depth}"));
+    }
+    if let Some(protocol) = &args.protocol {
+        command.push(format!("--protocol {protocol}"));
+    }
+    if args.strict {
+        command.push("--strict".to_string());
+    }
+    Some(command.join(" "))
+}
+
+const CLONE_ATTEMPTS: usize = 2;
+
 fn parse_depth(
     depth: Option<&str>,
     full: bool,
@@ -1248,46 +3203,17 @@ fn resolve_clone_protocol(input: Option<&str>, workspace: &Workspace) -> Result<
     }
 }
 
-fn resolve_clone_url(remote_url: &str, protocol: Option<&str>) -> String {
+fn resolve_clone_url(remote_url: &str, protocol: Option<&str>, web_root: &str) -> String {
+    let Some(url) = RemoteUrl::parse(remote_url) else {
+        return remote_url.to_string();
+    };
     match protocol {
-        Some("https") => to_https_url(remote_url).unwrap_or_else(|| remote_url.to_string()),
-        Some("ssh") => to_ssh_url(remote_url).unwrap_or_else(|| remote_url.to_string()),
+        Some("https") => url.to_https(web_root),
+        Some("ssh") => url.to_ssh(web_root),
         _ => remote_url.to_string(),
     }
 }
 
-fn to_https_url(remote_url: &str) -> Option<String> {
-    if remote_url.starts_with("http://") || remote_url.starts_with("https://") {
-        return Some(remote_url.to_string());
-    }
-    if remote_url.starts_with("file://") {
-        return None;
-    }
-    if let Some(rest) = remote_url.strip_prefix("git@") {
-        let (host, path) = rest.split_once(':')?;
-        return Some(format!("https://{host}/{path}"));
-    }
-    if let Some(rest) = remote_url.strip_prefix("ssh://git@") {
-        let (host, path) = rest.split_once('/')?;
-        return Some(format!("https://{host}/{path}"));
-    }
-    None
-}
-
-fn to_ssh_url(remote_url: &str) -> Option<String> {
-    if remote_url.starts_with("git@") || remote_url.starts_with("ssh://git@") {
-        return Some(remote_url.to_string());
-    }
-    if remote_url.starts_with("file://") {
-        return None;
-    }
-    let stripped = remote_url
-        .strip_prefix("https://")
-        .or_else(|| remote_url.strip_prefix("http://"))?;
-    let (host, path) = stripped.split_once('/')?;
-    Some(format!("git@{host}:{path}"))
-}
-
 fn handle_status(
     args: StatusArgs,
     workspace_root: Option<PathBuf>,
@@ -1295,7 +3221,7 @@ fn handle_status(
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
     let include_untracked = include_untracked_by_default(&workspace);
-    let mut repos = select_repos(&workspace, &[], None, true, false)?;
+    let mut repos = select_repos(&workspace, &[], None, true, args.include_external)?;
     repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
 
     let mut rows = Vec::new();
@@ -1310,16 +3236,20 @@ fn handle_status(
             status.untracked.clear();
         }
         let (ahead, behind) = ahead_behind_for_repo(&repo.path);
-        if args.changed && status.is_clean() {
+        if args.changed && !repo_is_changed(&workspace, &repo, &status)? {
             continue;
         }
+        let case_conflicts = case_conflicts(&open.repo)?;
         rows.push(StatusRow {
             repo: repo.id.as_str().to_string(),
+            teams: repo_teams(&workspace.config, repo.id.as_str()),
+            external: repo.external,
             path: repo.path.clone(),
             branch,
             ahead,
             behind,
             status,
+            case_conflicts,
         });
     }
 
@@ -1327,6 +3257,9 @@ fn handle_status(
         print_status_json(&rows)?;
         return Ok(());
     }
+    for row in &rows {
+        warn_case_conflicts(&row.repo, &row.case_conflicts);
+    }
     if args.porcelain {
         print_status_porcelain(&rows);
         return Ok(());
@@ -1337,18 +3270,100 @@ fn handle_status(
     }
 
     print_status_table(&workspace, &rows, args.short)?;
+    if !args.short {
+        print_paused_repos(&workspace);
+    }
     Ok(())
 }
 
+/// Warns once per group of tracked paths that differ only by case.
+fn warn_case_conflicts(repo: &str, conflicts: &[Vec<String>]) {
+    for paths in conflicts {
+        output::warn(&format!(
+            "{}: paths differ only by case and collide on macOS and Windows: {}",
+            repo,
+            paths.join(", ")
+        ));
+    }
+}
+
+/// Lists paused repos below the status table so they are not silently missing.
+fn print_paused_repos(workspace: &Workspace) {
+    let mut paused: Vec<&Repo> = workspace
+        .repos
+        .values()
+        .filter(|repo| repo.paused.is_some())
+        .collect();
+    if paused.is_empty() {
+        return;
+    }
+    paused.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    println!();
+    println!("Paused:");
+    for repo in paused {
+        let Some(pause) = repo.paused.as_ref() else {
+            continue;
+        };
+        let until = pause
+            .until
+            .as_deref()
+            .map(|until| format!("until {until}"))
+            .unwrap_or_else(|| "until resumed".to_string());
+        match pause.reason.as_deref() {
+            Some(reason) => println!("  {} ({}): {}", repo.id.as_str(), until, reason),
+            None => println!("  {} ({})", repo.id.as_str(), until),
+        }
+    }
+}
+
 fn handle_sync(
     args: SyncArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
-    let mut repos = select_repos(&workspace, &args.repos, None, args.repos.is_empty(), false)?;
+    if !args.dry_run && !args.fetch_only {
+        ensure_writable(&workspace, "sync")?;
+    }
+    let mut repos = select_repos(
+        &workspace,
+        &args.repos,
+        None,
+        args.repos.is_empty(),
+        args.include_external,
+    )?;
+    // External mirrors are optional locally; only fetch the ones that exist.
+    repos.retain(|repo| {
+        if repo.external && args.repos.is_empty() && !repo.path.is_dir() {
+            output::info(&format!(
+                "skipping external repo {} (not cloned)",
+                repo.id.as_str()
+            ));
+            return false;
+        }
+        true
+    });
     repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
-    let jobs = resolve_parallel(args.parallel);
+    let jobs = resolve_parallel(&workspace, "sync", args.parallel);
+    let network = network_queue(&workspace);
+    if let Some(format) = args.events.as_deref() {
+        events::init("sync", format, args.events_file.as_deref())?;
+    }
+    let names: Vec<String> = repos
+        .iter()
+        .map(|repo| repo.id.as_str().to_string())
+        .collect();
+    events::emit("started", serde_json::json!({ "repos": names }));
+    let options = SyncOptions {
+        fetch_only: args.fetch_only,
+        ff_only: args.ff_only,
+        rebase: args.rebase,
+        autostash: args.autostash,
+        prune: args.prune,
+    };
+    if args.dry_run {
+        return preview_sync_repos(repos, &names, jobs, &network, options);
+    }
 
     let results = parallel::run_in_parallel(repos, jobs, |repo| {
         let repo_name = repo.id.as_str().to_string();
@@ -1359,36 +3374,46 @@ fn handle_sync(
             ))));
         }
         let open = open_repo(&repo.path)?;
+        events::emit("repo_started", serde_json::json!({ "repo": repo_name }));
         output::git_op(&format!("fetch (repo {})", repo_name));
-        let outcome = sync_repo(
-            &open.repo,
-            SyncOptions {
-                fetch_only: args.fetch_only,
-                ff_only: args.ff_only,
-                rebase: args.rebase,
-                autostash: args.autostash,
-                prune: args.prune,
-            },
-        )
-        .map_err(|err| HarmoniaError::Other(anyhow::anyhow!(format!("{repo_name}: {err}"))))?;
+        let with_repo = |err: HarmoniaError| {
+            HarmoniaError::Other(anyhow::anyhow!(format!("{repo_name}: {err}")))
+        };
+        // Only the fetch counts against the network limit; merging or
+        // rebasing afterwards is local work.
+        let fetch = {
+            let _permit = network.acquire();
+            fetch_for_sync(&open.repo, options).map_err(with_repo)?
+        };
+        let outcome = apply_sync(&open.repo, fetch, options).map_err(with_repo)?;
         Ok((repo_name, outcome))
     });
 
     let mut failures = Vec::new();
-    for result in results {
+    for (name, result) in names.iter().zip(results) {
         match result {
             Ok((repo_name, outcome)) => {
-                if args.fetch_only {
-                    output::git_op(&format!("fetched (repo {})", repo_name));
+                let action = if args.fetch_only {
+                    "fetched"
                 } else if outcome.fast_forwarded {
-                    output::git_op(&format!("fast-forward (repo {})", repo_name));
+                    "fast-forward"
                 } else if outcome.rebased {
-                    output::git_op(&format!("rebase (repo {})", repo_name));
+                    "rebase"
                 } else if outcome.merged {
-                    output::git_op(&format!("merge (repo {})", repo_name));
+                    "merge"
                 } else {
-                    output::git_op(&format!("up-to-date (repo {})", repo_name));
-                }
+                    "up-to-date"
+                };
+                output::git_op(&format!("{} (repo {})", action, repo_name));
+                let fields = serde_json::json!({
+                    "repo": repo_name,
+                    "ok": true,
+                    "outcome": action,
+                    "autostashed": outcome.autostashed,
+                    "pruned": outcome.pruned,
+                });
+                events::emit("repo_finished", fields.clone());
+                report::repo(fields);
                 if outcome.autostashed {
                     output::info(&format!(
                         "autostash reapplied local changes in {}",
@@ -1402,9 +3427,19 @@ fn handle_sync(
                     ));
                 }
             }
-            Err(err) => failures.push(err.to_string()),
+            Err(err) => {
+                let fields =
+                    serde_json::json!({ "repo": name, "ok": false, "error": err.to_string() });
+                events::emit("repo_finished", fields.clone());
+                report::repo(fields);
+                failures.push(err.to_string());
+            }
         }
     }
+    events::emit(
+        "finished",
+        serde_json::json!({ "ok": failures.is_empty(), "failed": failures.len() }),
+    );
 
     if !failures.is_empty() {
         for failure in &failures {
@@ -1419,6 +3454,78 @@ fn handle_sync(
     Ok(())
 }
 
+/// `sync --dry-run`: fetches each repo and prints what `sync` would do with
+/// the same options, without touching branches.
+fn preview_sync_repos(
+    repos: Vec<Repo>,
+    names: &[String],
+    jobs: Option<usize>,
+    network: &parallel::NetworkQueue,
+    options: SyncOptions,
+) -> Result<()> {
+    let results = parallel::run_in_parallel(repos, jobs, |repo| {
+        if !repo.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "repository is not cloned"
+            )));
+        }
+        let _permit = network.acquire();
+        let open = open_repo(&repo.path)?;
+        preview_sync(&open.repo, options)
+    });
+
+    let mut failures = 0;
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok(preview) => {
+                let mut line = format!(
+                    "{}: {} (ahead {}, behind {})",
+                    name,
+                    preview.action.as_str(),
+                    preview.ahead,
+                    preview.behind
+                );
+                if preview.needs_stash {
+                    line.push_str("; uncommitted changes need --autostash");
+                }
+                if !report::enabled() {
+                    println!("{}", line);
+                }
+                let fields = serde_json::json!({
+                    "repo": name,
+                    "ok": true,
+                    "outcome": preview.action.as_str(),
+                    "ahead": preview.ahead,
+                    "behind": preview.behind,
+                    "needs_stash": preview.needs_stash,
+                    "dry_run": true,
+                });
+                events::emit("repo_finished", fields.clone());
+                report::repo(fields);
+            }
+            Err(err) => {
+                failures += 1;
+                output::error(&format!("{}: {}", name, err));
+                let fields =
+                    serde_json::json!({ "repo": name, "ok": false, "error": err.to_string() });
+                events::emit("repo_finished", fields.clone());
+                report::repo(fields);
+            }
+        }
+    }
+    events::emit(
+        "finished",
+        serde_json::json!({ "ok": failures == 0, "failed": failures }),
+    );
+    if failures > 0 {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "sync preview failed in {} repositories",
+            failures
+        ))));
+    }
+    Ok(())
+}
+
 fn handle_refresh(
     _args: RefreshArgs,
     workspace_root: Option<PathBuf>,
@@ -1432,6 +3539,7 @@ fn handle_refresh(
             all: true,
             graceful: true,
             fallback: Some("master".to_string()),
+            autostash: false,
         },
         workspace_root.clone(),
         config_path.clone(),
@@ -1447,80 +3555,443 @@ fn handle_refresh(
             autostash: true,
             prune: false,
             parallel: None,
+            include_external: false,
+            dry_run: false,
+            events: None,
+            events_file: None,
         },
         workspace_root,
         config_path,
     )
 }
 
-fn handle_submit(
-    args: SubmitArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root.clone(), config_path.clone())?;
-    let plan = build_plan_summary(&workspace, &[], &[])?;
-    if plan.changed.is_empty() {
-        output::info("no changed repositories detected; nothing to submit");
-        return Ok(());
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubmitPhase {
+    CreateMrs,
+    Stage,
+    Commit,
+    Push,
+}
 
-    let target_repos: Vec<String> = ordered_plan_repos(&plan)
-        .into_iter()
-        .map(|repo| repo.as_str().to_string())
-        .collect();
-    let commit_message = args.message.unwrap_or_else(|| "updates".to_string());
-    let mr_args = MrCreateArgs {
-        auto_branch: !args.no_auto_branch,
-        branch_name: args.branch_name,
-        ..MrCreateArgs::default()
+impl SubmitPhase {
+    const ALL: [SubmitPhase; 4] = [
+        SubmitPhase::CreateMrs,
+        SubmitPhase::Stage,
+        SubmitPhase::Commit,
+        SubmitPhase::Push,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SubmitPhase::CreateMrs => "creating merge requests",
+            SubmitPhase::Stage => "staging changes",
+            SubmitPhase::Commit => "committing changes",
+            SubmitPhase::Push => "pushing branches",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            SubmitPhase::CreateMrs => "mr_create",
+            SubmitPhase::Stage => "add",
+            SubmitPhase::Commit => "commit",
+            SubmitPhase::Push => "push",
+        }
+    }
+}
+
+/// Progress of an in-flight `submit`, saved after every completed phase so a
+/// rerun resumes where the previous attempt stopped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SubmitState {
+    #[serde(default)]
+    repos: Vec<String>,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    branch_name: Option<String>,
+    #[serde(default)]
+    completed: Vec<String>,
+}
+
+impl SubmitState {
+    fn is_completed(&self, phase: SubmitPhase) -> bool {
+        self.completed.iter().any(|key| key == phase.key())
+    }
+}
+
+fn handle_submit(
+    args: SubmitArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    if let Some(format) = args.events.as_deref() {
+        events::init("submit", format, args.events_file.as_deref())?;
+    }
+    let result = run_submit(args, workspace_root, config_path);
+    emit_finished(&result);
+    result
+}
+
+/// Final `--events` line for a command, carrying its error when it failed.
+fn emit_finished(result: &Result<()>) {
+    match result {
+        Ok(()) => events::emit("finished", serde_json::json!({ "ok": true })),
+        Err(err) => events::emit(
+            "finished",
+            serde_json::json!({ "ok": false, "error": err.to_string() }),
+        ),
+    }
+}
+
+fn run_submit(
+    args: SubmitArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root.clone(), config_path.clone())?;
+    ensure_writable(&workspace, "submit")?;
+    if args.restart {
+        clear_submit_state(&workspace)?;
+    }
+    let auto_branch = !args.no_auto_branch;
+
+    let mut state = match load_submit_state(&workspace)? {
+        Some(mut state) => {
+            if let Some(requested) = args.branch_name.as_deref() {
+                if state.branch_name.as_deref() != Some(requested) {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(
+                        "saved submit uses branch '{}', not '{}'; rerun with --restart to start over on '{}'",
+                        state.branch_name.as_deref().unwrap_or("(current branch)"),
+                        requested,
+                        requested
+                    )));
+                }
+            }
+            if let Some(message) = args.message.clone() {
+                state.message = message;
+            }
+            output::info(&format!(
+                "resuming submit for {} (completed: {}); use --restart to start over",
+                state.repos.join(", "),
+                if state.completed.is_empty() {
+                    "none".to_string()
+                } else {
+                    state.completed.join(", ")
+                }
+            ));
+            if args.review {
+                print_submit_resume_review(&state);
+            }
+            state
+        }
+        None => {
+            let plan = build_plan_summary(&WorkspaceState::new(&workspace), &[], &[])?;
+            if plan.changed.is_empty() {
+                output::info("no changed repositories detected; nothing to submit");
+                return Ok(());
+            }
+            let mut branch_name = args.branch_name.clone();
+            let needs_branch = plan.changed.iter().any(|changed| {
+                workspace
+                    .repos
+                    .get(&changed.id)
+                    .is_some_and(|repo| repo.default_branch == changed.branch)
+            });
+            if auto_branch && needs_branch && branch_name.is_none() {
+                // Pin the generated name so the reviewed plan matches what runs
+                // and the commit message can seed the branch slug.
+                let probe = MrCreateArgs {
+                    auto_branch,
+                    ..MrCreateArgs::default()
+                };
+                branch_name = Some(resolve_mr_auto_branch_name(
+                    &workspace,
+                    &probe,
+                    &plan,
+                    args.message.as_deref(),
+                )?);
+            }
+            let state = SubmitState {
+                repos: ordered_plan_repos(&plan)
+                    .into_iter()
+                    .map(|repo| repo.as_str().to_string())
+                    .collect(),
+                message: args
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| "updates".to_string()),
+                branch_name,
+                completed: Vec::new(),
+            };
+            if args.review {
+                print_submit_review(
+                    &workspace,
+                    &plan,
+                    &state.message,
+                    auto_branch,
+                    state.branch_name.as_deref(),
+                )?;
+            }
+            state
+        }
     };
 
-    output::info("submit: creating merge requests");
-    handle_mr_create(mr_args, &workspace)?;
+    if args.review
+        && !output::confirm("proceed with submit?", args.yes)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+    {
+        output::info("submit cancelled");
+        return Ok(());
+    }
 
-    output::info("submit: staging changes");
-    handle_add(
-        AddArgs {
-            repos: target_repos.clone(),
-            all: false,
-            patch: false,
-            pathspec: Vec::new(),
-        },
-        workspace_root.clone(),
-        config_path.clone(),
-    )?;
+    // Scan before any MR is created so a leaked credential never reaches the
+    // forge; the commit phase scans the staged result again.
+    if !args.no_secret_scan && !state.is_completed(SubmitPhase::Commit) {
+        let repos: Vec<Repo> = state
+            .repos
+            .iter()
+            .filter_map(|name| workspace.repos.get(&RepoId::new(name.clone())).cloned())
+            .collect();
+        ensure_no_secrets(&workspace, &repos, SecretScanScope::WorkingTree)?;
+    }
 
-    output::info("submit: committing changes");
-    handle_commit(
-        CommitArgs {
-            message: Some(commit_message),
-            all: false,
-            repos: target_repos.clone(),
-            amend: false,
-            no_hooks: false,
-            yes: false,
-            allow_empty: false,
-            trailers: Vec::new(),
-        },
-        workspace_root.clone(),
-        config_path.clone(),
-    )?;
+    events::emit(
+        "started",
+        serde_json::json!({
+            "repos": state.repos,
+            "branch": state.branch_name,
+            "completed": state.completed,
+        }),
+    );
+    for phase in SubmitPhase::ALL {
+        if state.is_completed(phase) {
+            output::info(&format!("submit: {} (already done)", phase.as_str()));
+            continue;
+        }
+        if args.step
+            && !output::confirm(
+                &format!("submit: run phase '{}'?", phase.as_str()),
+                args.yes,
+            )
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        {
+            save_submit_state(&workspace, &state)?;
+            output::info(&format!(
+                "submit stopped before {}; rerun `harmonia submit` to resume",
+                phase.as_str()
+            ));
+            return Ok(());
+        }
 
-    output::info("submit: pushing branches");
-    handle_push(
-        PushArgs {
-            repos: target_repos,
-            force: false,
-            force_with_lease: false,
-            set_upstream: true,
-            no_hooks: false,
-            yes: false,
-            dry_run: false,
-        },
-        workspace_root,
-        config_path,
-    )
+        output::info(&format!("submit: {}", phase.as_str()));
+        events::emit("phase_started", serde_json::json!({ "phase": phase.key() }));
+        save_submit_state(&workspace, &state)?;
+        let result = match phase {
+            SubmitPhase::CreateMrs => handle_mr_create(
+                MrCreateArgs {
+                    auto_branch,
+                    branch_name: state.branch_name.clone(),
+                    skip_existing: true,
+                    ..MrCreateArgs::default()
+                },
+                &WorkspaceState::new(&workspace),
+            ),
+            SubmitPhase::Stage => handle_add(
+                AddArgs {
+                    repos: state.repos.clone(),
+                    all: false,
+                    patch: false,
+                    pathspec: Vec::new(),
+                },
+                workspace_root.clone(),
+                config_path.clone(),
+            ),
+            SubmitPhase::Commit => handle_commit(
+                CommitArgs {
+                    message: Some(state.message.clone()),
+                    all: false,
+                    repos: state.repos.clone(),
+                    amend: false,
+                    no_hooks: false,
+                    yes: false,
+                    allow_empty: false,
+                    trailers: Vec::new(),
+                    hook_timeout: None,
+                    json: false,
+                    no_secret_scan: args.no_secret_scan,
+                },
+                workspace_root.clone(),
+                config_path.clone(),
+            ),
+            SubmitPhase::Push => handle_push(
+                PushArgs {
+                    repos: state.repos.clone(),
+                    force: false,
+                    force_with_lease: false,
+                    set_upstream: true,
+                    no_hooks: false,
+                    yes: false,
+                    dry_run: false,
+                    hook_timeout: None,
+                    json: false,
+                },
+                workspace_root.clone(),
+                config_path.clone(),
+            ),
+        };
+        events::emit(
+            "phase_finished",
+            serde_json::json!({ "phase": phase.key(), "ok": result.is_ok() }),
+        );
+        if let Err(err) = result {
+            output::warn(&format!(
+                "submit failed while {}; rerun `harmonia submit` to resume from this step",
+                phase.as_str()
+            ));
+            return Err(err);
+        }
+        state.completed.push(phase.key().to_string());
+        save_submit_state(&workspace, &state)?;
+    }
+
+    clear_submit_state(&workspace)?;
+    if LifecycleHook::PostSubmit.command(&workspace).is_some() {
+        let store = load_mr_state(&workspace)?;
+        let mrs: Vec<StoredMrEntry> =
+            tracked_mrs_for_current_branches(&WorkspaceState::new(&workspace), &store)?
+                .into_iter()
+                .filter(|tracked| {
+                    state
+                        .repos
+                        .iter()
+                        .any(|repo| repo == tracked.repo.id.as_str())
+                })
+                .map(|tracked| tracked.entry)
+                .collect();
+        run_lifecycle_hook(
+            &workspace,
+            LifecycleHook::PostSubmit,
+            serde_json::json!({
+                "repos": state.repos,
+                "branch": state.branch_name,
+                "message": state.message,
+                "mrs": mrs,
+            }),
+        )?;
+    }
+    Ok(())
+}
+
+fn submit_state_path(workspace: &Workspace) -> PathBuf {
+    workspace.root.join(".harmonia").join("submit-state.json")
+}
+
+fn load_submit_state(workspace: &Workspace) -> Result<Option<SubmitState>> {
+    let path = submit_state_path(workspace);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path)?;
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    let state = serde_json::from_str::<SubmitState>(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {} (rerun with --restart to discard it)",
+            path.display(),
+            err
+        )))
+    })?;
+    if state.repos.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(state))
+}
+
+fn save_submit_state(workspace: &Workspace, state: &SubmitState) -> Result<()> {
+    let path = submit_state_path(workspace);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(state)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+fn clear_submit_state(workspace: &Workspace) -> Result<()> {
+    let path = submit_state_path(workspace);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn print_submit_resume_review(state: &SubmitState) {
+    println!("Submit Plan (resumed)");
+    println!("=====================");
+    println!("commit message: {}", state.message);
+    if let Some(branch_name) = state.branch_name.as_deref() {
+        println!("branch: {}", branch_name);
+    }
+    println!("repos (merge order): {}", state.repos.join(", "));
+    for phase in SubmitPhase::ALL {
+        let marker = if state.is_completed(phase) {
+            "done"
+        } else {
+            "pending"
+        };
+        println!("  {}: {}", phase.as_str(), marker);
+    }
+}
+
+fn print_submit_review(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    commit_message: &str,
+    auto_branch: bool,
+    branch_name: Option<&str>,
+) -> Result<()> {
+    println!("Submit Plan");
+    println!("===========");
+    println!("mr title: {}", resolve_mr_title(workspace, plan, None)?);
+    println!("commit message: {}", commit_message);
+    println!("repos (merge order):");
+    for (index, repo_id) in ordered_plan_repos(plan).iter().enumerate() {
+        let Some(plan_repo) = plan.changed.iter().find(|repo| &repo.id == repo_id) else {
+            continue;
+        };
+        let Some(repo) = workspace.repos.get(repo_id) else {
+            continue;
+        };
+        let (source, target) = match branch_name {
+            Some(name) if auto_branch && plan_repo.branch == repo.default_branch => (
+                format!("{} -> {} (auto-branch)", plan_repo.branch, name),
+                repo.default_branch.clone(),
+            ),
+            _ => (
+                plan_repo.branch.clone(),
+                mr_target_branch(repo, &plan_repo.branch),
+            ),
+        };
+        println!(
+            "  {}. {} [{}] -> {}",
+            index + 1,
+            repo_id.as_str(),
+            source,
+            target
+        );
+    }
+    println!(
+        "phases: {}",
+        SubmitPhase::ALL
+            .iter()
+            .map(|phase| phase.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
 }
 
 fn handle_exec(
@@ -1537,25 +4008,113 @@ fn handle_exec(
         args.all || default_changed,
         false,
     )?;
-    let jobs = resolve_parallel(args.parallel);
+    let jobs = resolve_parallel(&workspace, "exec", args.parallel);
+    let timeout = resolve_command_timeout(&workspace, args.timeout);
+    let repos: Vec<Repo> = repos
+        .into_iter()
+        .filter(|repo| {
+            if !(args.changed || default_changed) || !repo.path.is_dir() {
+                return true;
+            }
+            let status = open_repo(&repo.path)
+                .and_then(|open| repo_status(&open.repo))
+                .unwrap_or_default();
+            repo_is_changed(&workspace, repo, &status).unwrap_or(false)
+        })
+        .collect();
 
-    let results = parallel::run_in_parallel(repos, jobs, |repo| {
-        if !repo.path.is_dir() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "repo {} not cloned",
-                repo.id.as_str()
-            ))));
-        }
-        let status = open_repo(&repo.path)
-            .and_then(|open| repo_status(&open.repo))
-            .unwrap_or_default();
-        if (args.changed || default_changed) && status.is_clean() {
-            return Ok(());
-        }
-        run_command_in_repo(&repo.path, &args.command)
-    });
+    // Output of a single repo needs no attribution.
+    let prefix = if args.no_prefix || repos.len() <= 1 {
+        None
+    } else {
+        Some(
+            args.prefix
+                .clone()
+                .unwrap_or_else(|| exec_prefix_template(&workspace)),
+        )
+    };
+    let width = repos
+        .iter()
+        .map(|repo| repo.id.as_str().chars().count())
+        .max()
+        .unwrap_or(0);
 
-    for result in results {
+    let batches = if args.graph_order {
+        graph_level_batches(&workspace, repos)?
+    } else {
+        vec![repos]
+    };
+
+    if args.output != "interleaved" {
+        let ignore_errors = args.ignore_errors;
+        let runs = run_in_batches(
+            batches,
+            jobs,
+            |repo| {
+                if !repo.path.is_dir() {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "repo {} not cloned",
+                        repo.id.as_str()
+                    ))));
+                }
+                let env_vars = workspace.command_env(Some(&repo))?;
+                let run = run_command_captured_with_env(
+                    &repo.path,
+                    &args.command,
+                    &env_vars,
+                    false,
+                    timeout,
+                )?;
+                if args.output == "grouped" {
+                    let prefix = prefix
+                        .as_deref()
+                        .map(|template| render_exec_prefix(template, repo.id.as_str(), width));
+                    print_captured_run(&run, prefix.as_deref().unwrap_or(""));
+                }
+                Ok(run)
+            },
+            |result: &Result<CapturedRun>| {
+                !ignore_errors && !matches!(result, Ok(run) if run.exit_code == Some(0))
+            },
+        );
+        let (ids, results): (Vec<RepoId>, Vec<_>) = runs.into_iter().unzip();
+        return report_captured_runs(
+            "exec",
+            &args.command,
+            &ids,
+            results,
+            args.output == "json",
+            args.ignore_errors,
+        );
+    }
+
+    let ignore_errors = args.ignore_errors;
+    let results = run_in_batches(
+        batches,
+        jobs,
+        |repo| {
+            if !repo.path.is_dir() {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "repo {} not cloned",
+                    repo.id.as_str()
+                ))));
+            }
+            let env_vars = workspace.command_env(Some(&repo))?;
+            match prefix.as_deref() {
+                Some(template) => run_command_in_repo_prefixed(
+                    &repo.path,
+                    &args.command,
+                    &env_vars,
+                    &render_exec_prefix(template, repo.id.as_str(), width),
+                    timeout,
+                ),
+                None => run_command_in_repo_with_env(&repo.path, &args.command, &env_vars, timeout),
+            }
+        },
+        |result: &Result<()>| result.is_err() && !ignore_errors,
+    );
+
+    for (_, result) in results {
         match result {
             Ok(()) => {}
             Err(err) => {
@@ -1578,10 +4137,17 @@ fn handle_run(
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
-    let repos = select_repos(&workspace, &args.repos, None, args.all, false)?;
-    let jobs = resolve_parallel(args.parallel);
+    let list_all = args.list && args.repos.is_empty();
+    let repos = select_repos(&workspace, &args.repos, None, args.all || list_all, false)?;
+
+    if args.list {
+        print_hook_listing(&workspace, &repos);
+        return Ok(());
+    }
 
-    let hook_name = args.hook;
+    let jobs = resolve_parallel(&workspace, "run", args.parallel);
+    let timeout = resolve_command_timeout(&workspace, args.timeout);
+    let hook_name = args.hook.unwrap_or_default();
     let workspace_hook = workspace
         .config
         .hooks
@@ -1589,42 +4155,193 @@ fn handle_run(
         .and_then(|hooks| hooks.custom.as_ref())
         .and_then(|custom| custom.get(&hook_name))
         .cloned();
-    let workspace_disabled = repos.iter().any(|repo| {
-        repo.config
-            .as_ref()
-            .and_then(|cfg| cfg.hooks.as_ref())
-            .and_then(|hooks| hooks.disable_workspace_hooks.as_ref())
-            .map(|disabled| disabled.iter().any(|name| name == &hook_name))
-            .unwrap_or(false)
-    });
+    let disabled_by: Vec<&Repo> = repos
+        .iter()
+        .filter(|repo| repo_disables_hook(repo, &hook_name))
+        .collect();
+    let workspace_disabled = !disabled_by.is_empty();
+
+    if args.dry_run {
+        println!("hook {} plan:", hook_name);
+        match workspace_hook.as_deref() {
+            Some(command) if workspace_disabled => println!(
+                "  workspace: {} (skipped; disabled by {})",
+                command,
+                disabled_by
+                    .iter()
+                    .map(|repo| repo.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Some(command) => println!("  workspace: {} (in {})", command, workspace.root.display()),
+            None => {}
+        }
+        let mut planned = 0;
+        for repo in &repos {
+            if let Some(command) = repo_custom_hook(repo, &hook_name) {
+                println!(
+                    "  {}: {} (in {})",
+                    repo.id.as_str(),
+                    command,
+                    repo.path.display()
+                );
+                planned += 1;
+            }
+        }
+        if workspace_hook.is_none() && planned == 0 {
+            println!("  (no commands configured for hook {})", hook_name);
+        }
+        return Ok(());
+    }
+
     if let Some(command) = workspace_hook {
         if !workspace_disabled {
-            run_command_in_repo(&workspace.root, &split_command(&command))?;
+            let env_vars = workspace.command_env(None)?;
+            run_command_in_repo_with_env(
+                &workspace.root,
+                &split_command(&command),
+                &env_vars,
+                timeout,
+            )?;
         }
     }
-    let results = parallel::run_in_parallel(repos, jobs, |repo| {
-        let hook = repo
-            .config
-            .as_ref()
-            .and_then(|config| config.hooks.as_ref())
-            .and_then(|hooks| hooks.custom.as_ref())
-            .and_then(|custom| custom.get(&hook_name))
-            .cloned();
-
-        if let Some(command) = hook {
-            run_command_in_repo(&repo.path, &split_command(&command))
-        } else {
-            Ok(())
-        }
-    });
+    let batches = if args.graph_order {
+        graph_level_batches(&workspace, repos)?
+    } else {
+        vec![repos]
+    };
+    let results = run_in_batches(
+        batches,
+        jobs,
+        |repo| {
+            if let Some(command) = repo_custom_hook(&repo, &hook_name) {
+                let env_vars = workspace.command_env(Some(&repo))?;
+                run_command_in_repo_with_env(
+                    &repo.path,
+                    &split_command(&command),
+                    &env_vars,
+                    timeout,
+                )
+            } else {
+                Ok(())
+            }
+        },
+        Result::is_err,
+    );
 
-    for result in results {
+    for (_, result) in results {
         result?;
     }
 
     Ok(())
 }
 
+/// The repo's `[hooks.custom]` command named `hook_name`, else the one its
+/// ecosystem provides (see `[ecosystems.<name>].commands`).
+fn repo_custom_hook(repo: &Repo, hook_name: &str) -> Option<String> {
+    repo.config
+        .as_ref()
+        .and_then(|config| config.hooks.as_ref())
+        .and_then(|hooks| hooks.custom.as_ref())
+        .and_then(|custom| custom.get(hook_name))
+        .cloned()
+        .or_else(|| {
+            let ecosystem = repo.ecosystem.as_ref()?;
+            plugin_for(ecosystem)
+                .hook_command(hook_name)
+                .map(str::to_string)
+        })
+}
+
+fn print_hook_listing(workspace: &Workspace, repos: &[Repo]) {
+    let mut workspace_hooks: Vec<(String, String)> = Vec::new();
+    if let Some(hooks) = workspace.config.hooks.as_ref() {
+        for (name, command) in [
+            ("pre_commit", hooks.pre_commit.as_ref()),
+            ("pre_push", hooks.pre_push.as_ref()),
+            ("pre_mr_create", hooks.pre_mr_create.as_ref()),
+            ("post_mr_create", hooks.post_mr_create.as_ref()),
+            ("post_mr_merge", hooks.post_mr_merge.as_ref()),
+            ("post_submit", hooks.post_submit.as_ref()),
+        ] {
+            if let Some(command) = command {
+                workspace_hooks.push((name.to_string(), command.clone()));
+            }
+        }
+        if let Some(custom) = hooks.custom.as_ref() {
+            let mut custom: Vec<_> = custom.iter().collect();
+            custom.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, command) in custom {
+                workspace_hooks.push((name.clone(), command.clone()));
+            }
+        }
+    }
+    let workspace_names: HashSet<&str> = workspace_hooks
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    println!("workspace ({}):", workspace.root.display());
+    if workspace_hooks.is_empty() {
+        println!("  (no hooks)");
+    }
+    for (name, command) in &workspace_hooks {
+        println!("  {} = {}", name, command);
+    }
+
+    let mut repos: Vec<&Repo> = repos.iter().collect();
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    for repo in repos {
+        let hooks = repo
+            .config
+            .as_ref()
+            .and_then(|config| config.hooks.as_ref());
+        let mut entries: Vec<(String, String)> = Vec::new();
+        if let Some(hooks) = hooks {
+            for (name, command) in [
+                ("pre_commit", hooks.pre_commit.as_ref()),
+                ("pre_push", hooks.pre_push.as_ref()),
+            ] {
+                if let Some(command) = command {
+                    entries.push((name.to_string(), command.clone()));
+                }
+            }
+            if let Some(custom) = hooks.custom.as_ref() {
+                let mut custom: Vec<_> = custom.iter().collect();
+                custom.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, command) in custom {
+                    entries.push((name.clone(), command.clone()));
+                }
+            }
+        }
+        let disabled = hooks
+            .and_then(|hooks| hooks.disable_workspace_hooks.clone())
+            .unwrap_or_default();
+        if entries.is_empty() && disabled.is_empty() {
+            continue;
+        }
+
+        println!("{}:", repo.id.as_str());
+        for (name, command) in &entries {
+            if workspace_names.contains(name.as_str()) {
+                let relation = if repo_disables_hook(repo, name) {
+                    "workspace hook disabled"
+                } else if matches!(name.as_str(), "test" | "lint") {
+                    "overrides workspace hook"
+                } else {
+                    "runs after workspace hook"
+                };
+                println!("  {} = {} ({})", name, command, relation);
+            } else {
+                println!("  {} = {}", name, command);
+            }
+        }
+        if !disabled.is_empty() {
+            println!("  disables workspace hooks: {}", disabled.join(", "));
+        }
+    }
+}
+
 fn handle_each(
     args: EachArgs,
     workspace_root: Option<PathBuf>,
@@ -1633,13 +4350,50 @@ fn handle_each(
     let workspace = load_workspace(workspace_root, config_path)?;
     let all = args.repos.is_empty();
     let repos = select_repos(&workspace, &args.repos, None, all, false)?;
-    let jobs = resolve_parallel(args.parallel);
+    let jobs = resolve_parallel(&workspace, "each", args.parallel);
+    let timeout = resolve_command_timeout(&workspace, args.timeout);
+
+    if args.output != "interleaved" {
+        let ids: Vec<RepoId> = repos.iter().map(|repo| repo.id.clone()).collect();
+        let template = (ids.len() > 1).then(|| exec_prefix_template(&workspace));
+        let width = ids
+            .iter()
+            .map(|id| id.as_str().chars().count())
+            .max()
+            .unwrap_or(0);
+        let results = parallel::run_in_parallel(repos, jobs, |repo| {
+            let env_vars = workspace.command_env(Some(&repo))?;
+            let run = run_command_captured_with_env(
+                &repo.path,
+                &args.command,
+                &env_vars,
+                args.shell,
+                timeout,
+            )?;
+            if args.output == "grouped" {
+                let prefix = template
+                    .as_deref()
+                    .map(|template| render_exec_prefix(template, repo.id.as_str(), width));
+                print_captured_run(&run, prefix.as_deref().unwrap_or(""));
+            }
+            Ok(run)
+        });
+        return report_captured_runs(
+            "each",
+            &args.command,
+            &ids,
+            results,
+            args.output == "json",
+            false,
+        );
+    }
 
     let results = parallel::run_in_parallel(repos, jobs, |repo| {
+        let env_vars = workspace.command_env(Some(&repo))?;
         if args.shell {
-            run_shell_command_in_repo(&repo.path, &args.command)
+            run_shell_command_in_repo(&repo.path, &args.command, &env_vars, timeout)
         } else {
-            run_command_in_repo(&repo.path, &args.command)
+            run_command_in_repo_with_env(&repo.path, &args.command, &env_vars, timeout)
         }
     });
 
@@ -1650,24 +4404,261 @@ fn handle_each(
     Ok(())
 }
 
-fn handle_graph(
-    args: GraphArgs,
+fn handle_git(
+    args: GitArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
+    let all = args.repos.is_empty();
+    let mut repos = select_repos(&workspace, &args.repos, None, all, false)?;
+    repos.retain(|repo| {
+        if !repo.path.is_dir() {
+            output::warn(&format!("repo {} not cloned; skipping", repo.id.as_str()));
+            return false;
+        }
+        if !args.changed {
+            return true;
+        }
+        let status = open_repo(&repo.path)
+            .and_then(|open| repo_status(&open.repo))
+            .unwrap_or_default();
+        repo_is_changed(&workspace, repo, &status).unwrap_or(false)
+    });
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    if repos.is_empty() {
+        output::info("no repos selected for git");
+        return Ok(());
+    }
+
+    let rendered = args.args.join(" ");
+    if !read_only_git(&args.args) {
+        ensure_writable(&workspace, &format!("git {}", rendered))?;
+    }
+    if let Some(reason) = destructive_git_reason(&args.args) {
+        let names = repos
+            .iter()
+            .map(|repo| repo.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let prompt = format!(
+            "`git {}` {} in {} repo(s) ({}). Continue?",
+            rendered,
+            reason,
+            repos.len(),
+            names
+        );
+        if !output::confirm(&prompt, args.yes)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        {
+            output::info("git aborted");
+            return Ok(());
+        }
+    }
+
+    let mut command = vec!["git".to_string()];
+    command.extend(args.args.iter().cloned());
+    let jobs = resolve_parallel(&workspace, "git", args.parallel);
+    let ids = repos.iter().map(|repo| repo.id.clone()).collect::<Vec<_>>();
+    let results = parallel::run_in_parallel(repos, jobs, |repo| {
+        run_command_captured_in_repo(&repo.path, &command)
+    });
+
+    // Output is buffered per repo and printed in name order so parallel runs
+    // stay readable.
+    let multi = ids.len() > 1;
+    let mut failed = Vec::new();
+    for (id, result) in ids.iter().zip(results) {
+        if multi {
+            println!("== {} ==", id.as_str());
+        }
+        match result {
+            Ok((success, text)) => {
+                print!("{}", text);
+                if !text.is_empty() && !text.ends_with('\n') {
+                    println!();
+                }
+                if !success {
+                    failed.push(id.as_str().to_string());
+                }
+            }
+            Err(err) => {
+                output::error(&err.to_string());
+                failed.push(id.as_str().to_string());
+            }
+        }
+    }
+
+    if multi {
+        println!();
+        println!(
+            "git {}: {} succeeded, {} failed",
+            rendered,
+            ids.len() - failed.len(),
+            failed.len()
+        );
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "git {} failed in: {}",
+            rendered,
+            failed.join(", ")
+        ))))
+    }
+}
+
+/// Why a `harmonia git` invocation is destructive, or `None` when it only
+/// reads or makes recoverable changes.
+/// The git subcommand in `args` and the arguments after it, skipping git's
+/// global options; `-C` and `-c` take a separate value.
+fn git_subcommand(args: &[String]) -> Option<(&str, &[String])> {
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        if !arg.starts_with('-') {
+            break;
+        }
+        index += if arg == "-C" || arg == "-c" { 2 } else { 1 };
+    }
+    let subcommand = args.get(index)?.as_str();
+    Some((subcommand, &args[(index + 1).min(args.len())..]))
+}
+
+/// Whether `git <args>` only reads, so it may run in a read-only workspace.
+/// Anything not on this list is treated as a write.
+fn read_only_git(args: &[String]) -> bool {
+    let Some((subcommand, rest)) = git_subcommand(args) else {
+        // `git --version` and friends.
+        return true;
+    };
+    let has = |flags: &[&str]| rest.iter().any(|arg| flags.contains(&arg.as_str()));
+    let first = rest.first().map(String::as_str);
+    match subcommand {
+        "status" | "log" | "diff" | "show" | "shortlog" | "blame" | "grep" | "ls-files"
+        | "ls-tree" | "ls-remote" | "rev-parse" | "rev-list" | "describe" | "cat-file"
+        | "for-each-ref" | "show-ref" | "show-branch" | "merge-base" | "name-rev" | "cherry"
+        | "count-objects" | "whatchanged" | "fetch" | "help" | "version" => true,
+        "branch" => {
+            has(&["--list", "-l", "--show-current"])
+                || rest.iter().all(|arg| {
+                    arg.starts_with('-')
+                        && !arg.starts_with("--set-upstream-to")
+                        && ![
+                            "-d",
+                            "-D",
+                            "--delete",
+                            "-m",
+                            "-M",
+                            "--move",
+                            "-c",
+                            "-C",
+                            "--copy",
+                            "-u",
+                            "--unset-upstream",
+                            "--edit-description",
+                            "-f",
+                            "--force",
+                        ]
+                        .contains(&arg.as_str())
+                })
+        }
+        "tag" => rest.is_empty() || has(&["--list", "-l"]),
+        "remote" => matches!(first, None | Some("-v" | "--verbose" | "show" | "get-url")),
+        "stash" => matches!(first, Some("list" | "show")),
+        "worktree" => first == Some("list"),
+        "reflog" => matches!(first, None | Some("show")),
+        "notes" => matches!(first, None | Some("list" | "show")),
+        "config" => has(&["--get", "--get-all", "--get-regexp", "--list", "-l"]),
+        _ => false,
+    }
+}
+
+fn destructive_git_reason(args: &[String]) -> Option<&'static str> {
+    let (subcommand, rest) = git_subcommand(args)?;
+    let has = |flags: &[&str]| rest.iter().any(|arg| flags.contains(&arg.as_str()));
+    let has_short = |flag: char| {
+        rest.iter().any(|arg| {
+            arg.len() > 1 && arg.starts_with('-') && !arg.starts_with("--") && arg.contains(flag)
+        })
+    };
+    match subcommand {
+        "reset" if has(&["--hard", "--merge", "--keep"]) => Some("discards local changes"),
+        "clean" if has_short('f') || has(&["--force"]) => Some("deletes untracked files"),
+        "checkout" | "switch" if has_short('f') || has(&["--force", "--discard-changes"]) => {
+            Some("discards local changes")
+        }
+        "checkout" if has(&["--", "."]) => Some("discards local changes"),
+        "restore" if !has(&["--staged", "-S"]) || has(&["--worktree", "-W"]) => {
+            Some("discards local changes")
+        }
+        "push"
+            if has_short('f')
+                || has_short('d')
+                || has(&["--force", "--delete", "--mirror", "--prune"])
+                || rest.iter().any(|arg| {
+                    arg.starts_with("--force-with-lease")
+                        || arg.starts_with('+')
+                        || arg.starts_with(':')
+                }) =>
+        {
+            Some("rewrites or deletes remote refs")
+        }
+        "branch" if has_short('D') || (has(&["-d", "--delete"]) && has(&["-f", "--force"])) => {
+            Some("deletes unmerged branches")
+        }
+        "tag" if has_short('d') || has(&["--delete"]) => Some("deletes tags"),
+        "update-ref" if has(&["-d"]) => Some("deletes refs"),
+        "stash" if has(&["drop", "clear"]) => Some("drops stashed changes"),
+        "reflog" if has(&["expire", "delete"]) => Some("prunes reflog entries"),
+        "gc" if rest.iter().any(|arg| arg.starts_with("--prune")) => {
+            Some("prunes unreachable objects")
+        }
+        "filter-branch" | "filter-repo" => Some("rewrites history"),
+        _ => None,
+    }
+}
+
+fn handle_graph(args: GraphArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
     let command = args.command.unwrap_or(GraphCommand::Show(GraphShowArgs {
         changed: false,
         format: "tree".to_string(),
+        cluster_by: None,
         direction: "down".to_string(),
+        only: Vec::new(),
+        exclude: Vec::new(),
+        from: None,
+        depth: None,
+        impact: false,
+        output: None,
     }));
 
     match command {
-        GraphCommand::Show(show) => handle_graph_show(show, &workspace),
-        GraphCommand::Deps(deps) => handle_graph_deps(deps, &workspace),
-        GraphCommand::Dependents(dependents) => handle_graph_dependents(dependents, &workspace),
-        GraphCommand::Order(order) => handle_graph_order(order, &workspace),
-        GraphCommand::Check(check) => handle_graph_check(check, &workspace),
+        GraphCommand::Show(mut show) => {
+            native_json_format(&mut show.format, "json");
+            handle_graph_show(show, state)
+        }
+        GraphCommand::Deps(mut deps) => {
+            native_json(&mut deps.json);
+            handle_graph_deps(deps, workspace)
+        }
+        GraphCommand::Dependents(mut dependents) => {
+            native_json(&mut dependents.json);
+            handle_graph_dependents(dependents, workspace)
+        }
+        GraphCommand::Order(mut order) => {
+            native_json(&mut order.json);
+            handle_graph_order(order, state)
+        }
+        GraphCommand::Check(mut check) => {
+            native_json(&mut check.json);
+            handle_graph_check(check, state)
+        }
+        GraphCommand::CriticalPath(mut critical) => {
+            native_json(&mut critical.json);
+            handle_graph_critical_path(critical, workspace)
+        }
     }
 }
 
@@ -1690,7 +4681,8 @@ fn parse_graph_direction(input: &str) -> Result<GraphDirection> {
     }
 }
 
-fn handle_graph_show(args: GraphShowArgs, workspace: &Workspace) -> Result<()> {
+fn handle_graph_show(args: GraphShowArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
     let direction = parse_graph_direction(&args.direction)?;
     let mut scope: HashSet<RepoId> = workspace
         .repos
@@ -1700,7 +4692,7 @@ fn handle_graph_show(args: GraphShowArgs, workspace: &Workspace) -> Result<()> {
         .collect();
 
     if args.changed {
-        let changed = changed_repos(workspace)?;
+        let changed = changed_repos(state)?;
         let mut expanded = changed.clone();
         for repo in &changed {
             if matches!(direction, GraphDirection::Down | GraphDirection::Both) {
@@ -1717,46 +4709,124 @@ fn handle_graph_show(args: GraphShowArgs, workspace: &Workspace) -> Result<()> {
         scope = expanded;
     }
 
+    if let Some(from) = args.from.as_deref() {
+        let start = RepoId::new(from.to_string());
+        if !scope.contains(&start) {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                from
+            ))));
+        }
+        let edges = build_directional_edges(&workspace.graph, &workspace.repos, direction, &scope);
+        scope = reachable_within(&edges, &start, args.depth);
+    }
+
+    let changed = changed_repo_flags(state);
+    let only = args
+        .only
+        .iter()
+        .map(|raw| parse_graph_node_filter(raw, workspace))
+        .collect::<Result<Vec<_>>>()?;
+    let exclude = args
+        .exclude
+        .iter()
+        .map(|raw| parse_graph_node_filter(raw, workspace))
+        .collect::<Result<Vec<_>>>()?;
+    scope.retain(|id| {
+        let Some(repo) = workspace.repos.get(id) else {
+            return false;
+        };
+        let kept = only.is_empty()
+            || only
+                .iter()
+                .any(|filter| filter.matches(repo, workspace, &changed));
+        kept && !exclude
+            .iter()
+            .any(|filter| filter.matches(repo, workspace, &changed))
+    });
+
     let edges = build_directional_edges(&workspace.graph, &workspace.repos, direction, &scope);
     let roots = graph_roots(&edges, &scope);
-    let versions = collect_versions(workspace)?;
+    let versions = state.versions()?;
+    let impact: HashMap<RepoId, ImpactScore> = impact_scores(&workspace.graph, &workspace.repos)
+        .into_iter()
+        .map(|score| (score.repo.clone(), score))
+        .collect();
     let mut labels = HashMap::new();
+    let mut metadata = HashMap::new();
     for repo in &scope {
-        let label = if let Some(version) = versions.get(repo) {
+        let mut label = if let Some(version) = versions.get(repo) {
             format!("{} ({})", repo.as_str(), version.raw)
         } else {
             repo.as_str().to_string()
         };
+        if args.impact {
+            if let Some(score) = impact.get(repo) {
+                label.push_str(&format!(
+                    " [{} dependents, depth {}]",
+                    score.dependents, score.depth
+                ));
+            }
+        }
         labels.insert(repo.clone(), label);
+        let entry = workspace.repos.get(repo);
+        metadata.insert(
+            repo.clone(),
+            viz::NodeMetadata {
+                ecosystem: entry
+                    .and_then(|entry| entry.ecosystem.as_ref())
+                    .map(|ecosystem| ecosystem.as_str().to_string()),
+                version: versions.get(repo).map(|version| version.raw.clone()),
+                changed: changed.contains(repo),
+                external: entry.is_some_and(|entry| entry.external),
+                mr_status: None,
+            },
+        );
     }
 
-    match args.format.to_ascii_lowercase().as_str() {
-        "tree" => {
-            print!("{}", viz::render_tree(&roots, &edges, &labels));
-            Ok(())
+    let clusters = graph_clusters(workspace, &scope, &metadata, args.cluster_by.as_deref())?;
+
+    let rendered = match args.format.to_ascii_lowercase().as_str() {
+        "tree" => viz::render_tree(&roots, &edges, &labels),
+        "flat" => viz::render_flat(&roots, &edges, &labels),
+        "dot" => viz::render_dot(&edges, &labels, &metadata, &clusters),
+        "graphml" => viz::render_graphml(&edges, &labels, &metadata, &clusters),
+        "mermaid" => {
+            // Keep dependencies below their dependents whichever way edges point.
+            let orientation = match direction {
+                GraphDirection::Up => "BT",
+                GraphDirection::Down | GraphDirection::Both => "TD",
+            };
+            viz::render_mermaid(&edges, &labels, &metadata, orientation)
         }
-        "flat" => {
-            print!("{}", viz::render_flat(&roots, &edges, &labels));
-            Ok(())
+        "json" => {
+            let json = graph_to_json(&edges, &labels, &metadata, &impact, workspace);
+            let mut json = serde_json::to_string_pretty(&json)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+            json.push('\n');
+            json
         }
-        "dot" => {
-            print!("{}", viz::render_dot(&edges, &labels));
-            Ok(())
+        other => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown graph format '{}'",
+                other
+            ))))
         }
-        "json" => {
-            let json = graph_to_json(&edges, &labels);
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&json)
-                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
-            );
-            Ok(())
+    };
+    match args.output {
+        Some(path) => {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, rendered)?;
+            output::info(&format!("wrote graph to {}", path.display()));
         }
-        other => Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-            "unknown graph format '{}'",
-            other
-        )))),
+        None => print!("{rendered}"),
     }
+    Ok(())
 }
 
 fn handle_graph_deps(args: GraphDepsArgs, workspace: &Workspace) -> Result<()> {
@@ -1774,10 +4844,14 @@ fn handle_graph_deps(args: GraphDepsArgs, workspace: &Workspace) -> Result<()> {
             .map(|id| id.as_str().to_string())
             .collect()
     } else {
-        internal_dependencies_for(&workspace.graph, &repo_id)
+        dependencies_for(&workspace.graph, &repo_id)
             .into_iter()
+            .filter(|dep| dep.is_internal || workspace.graph.external.contains_key(&dep.name))
             .map(|dep| match map.get(&dep.name) {
                 Some(target) => target.as_str().to_string(),
+                None if workspace.graph.external.contains_key(&dep.name) => {
+                    format!("{} (external)", dep.name)
+                }
                 None => format!("{} (missing)", dep.name),
             })
             .collect()
@@ -1839,9 +4913,10 @@ fn handle_graph_dependents(args: GraphDependentsArgs, workspace: &Workspace) ->
     Ok(())
 }
 
-fn handle_graph_order(args: GraphOrderArgs, workspace: &Workspace) -> Result<()> {
+fn handle_graph_order(args: GraphOrderArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
     let order = if args.changed {
-        let changed = changed_repos(workspace)?;
+        let changed = changed_repos(state)?;
         if changed.is_empty() {
             Vec::new()
         } else {
@@ -1874,9 +4949,62 @@ fn handle_graph_order(args: GraphOrderArgs, workspace: &Workspace) -> Result<()>
     Ok(())
 }
 
-fn handle_graph_check(args: GraphCheckArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
-    let report = check_constraints(&workspace.graph, &workspace.repos, &versions);
+fn handle_graph_critical_path(args: GraphCriticalPathArgs, workspace: &Workspace) -> Result<()> {
+    let path = critical_path(&workspace.graph, &workspace.repos).map_err(HarmoniaError::Other)?;
+    let scores: Vec<ImpactScore> = impact_scores(&workspace.graph, &workspace.repos)
+        .into_iter()
+        .filter(|score| {
+            workspace
+                .repos
+                .get(&score.repo)
+                .is_some_and(|repo| !repo.ignored)
+        })
+        .collect();
+
+    if args.json {
+        let json = serde_json::json!({
+            "critical_path": path.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
+            "impact": scores.iter().map(|score| {
+                serde_json::json!({
+                    "repo": score.repo.as_str(),
+                    "dependents": score.dependents,
+                    "depth": score.depth,
+                })
+            }).collect::<Vec<_>>(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
+        return Ok(());
+    }
+
+    println!("Critical path ({} repos):", path.len());
+    println!(
+        "  {}",
+        path.iter()
+            .map(|repo| repo.as_str())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    );
+    println!();
+    println!("Impact:");
+    println!("  {:<24} {:>10} {:>6}", "repo", "dependents", "depth");
+    for score in &scores {
+        println!(
+            "  {:<24} {:>10} {:>6}",
+            score.repo.as_str(),
+            score.dependents,
+            score.depth
+        );
+    }
+    Ok(())
+}
+
+fn handle_graph_check(args: GraphCheckArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let report = check_constraints(state.graph(), &workspace.repos, state.versions()?);
 
     if args.json {
         println!(
@@ -1896,21 +5024,27 @@ fn handle_branch(
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
-    if args.force_create && !args.yes {
-        let confirm = output::confirm(
-            &format!("Force-create branch '{}' in all selected repos?", args.name),
-            false,
-        )
-        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-        if !confirm {
-            return Ok(());
+    let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "branch")?;
+    let creating = args.create || args.force_create;
+    let name = match args.name.as_deref() {
+        Some(name) => name.trim().to_string(),
+        None if creating => {
+            generate_branch_name(&workspace, args.ticket.as_deref(), args.slug.as_deref())
         }
+        None => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "--ticket/--slug generate a new branch name and require --create or --force-create"
+            )))
+        }
+    };
+    if creating {
+        check_branch_name_policy(&workspace, &name)?;
     }
 
-    let workspace = load_workspace(workspace_root, config_path)?;
     let mut repos = select_repos(&workspace, &args.repos, None, false, false)?;
     if args.changed {
-        repos = filter_changed_repos(repos)?;
+        repos = filter_changed_repos(&workspace, repos)?;
     }
     if args.with_deps || args.with_all_deps {
         repos = expand_branch_scope(&workspace, repos, args.with_deps, args.with_all_deps);
@@ -1920,7 +5054,22 @@ fn handle_branch(
         output::info("no repos selected for branch");
         return Ok(());
     }
+    if args.force_create
+        && !confirm_dangerous(
+            &workspace,
+            DangerousOperation::ForceBranch,
+            &format!("Force-create branch '{}' in all selected repos?", name),
+            &repos,
+            args.yes,
+        )?
+    {
+        return Ok(());
+    }
+    if args.name.is_none() {
+        output::info(&format!("generated branch name: {}", name));
+    }
 
+    let mut stashes = Vec::new();
     for repo in repos {
         if !repo.path.is_dir() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
@@ -1929,27 +5078,71 @@ fn handle_branch(
             ))));
         }
         let open = open_repo(&repo.path)?;
-        if args.create || args.force_create {
-            create_branch(&open.repo, &args.name, args.force_create)?;
-        } else if !branch_exists(&open.repo, &args.name)? {
+        let base = current_branch(&open.repo)?;
+        if creating {
+            create_branch(&open.repo, &name, args.force_create)?;
+        } else if !branch_exists(&open.repo, &name)? {
             return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
                 "branch {} does not exist in {}",
-                args.name,
+                name,
                 repo.id.as_str()
             ))));
         }
-        checkout_branch(&open.repo, &args.name)?;
+        if args.autostash {
+            stashes.push((
+                repo.id.as_str().to_string(),
+                checkout_branch_autostash(&open.repo, &name)?,
+            ));
+        } else {
+            checkout_branch(&open.repo, &name)?;
+        }
+        if creating {
+            // Branching from another feature branch targets it; otherwise the default branch.
+            let target = if base == name || base == "HEAD" || base == "(detached)" {
+                repo.default_branch.clone()
+            } else {
+                base
+            };
+            set_branch_target(&open.repo, &name, &target)?;
+        }
         if let Some(track) = args.track.as_ref() {
             output::git_op(&format!(
                 "branch --set-upstream-to {} {} (repo {})",
                 track,
-                args.name,
+                name,
                 repo.id.as_str()
             ));
-            set_branch_upstream(&open.repo, &args.name, track)?;
+            set_branch_upstream(&open.repo, &name, track)?;
         }
     }
 
+    report_autostash(&stashes)
+}
+
+/// Summarizes `--autostash` on branch switches: which repos had local changes
+/// re-applied, and an error naming the repos whose stash conflicted.
+fn report_autostash(outcomes: &[(String, AutostashOutcome)]) -> Result<()> {
+    let names = |wanted: AutostashOutcome| {
+        outcomes
+            .iter()
+            .filter(|(_, outcome)| *outcome == wanted)
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+    };
+    let reapplied = names(AutostashOutcome::Reapplied);
+    let conflicted = names(AutostashOutcome::Conflicted);
+    if !reapplied.is_empty() {
+        output::info(&format!(
+            "autostash reapplied local changes in {}",
+            reapplied.join(", ")
+        ));
+    }
+    if !conflicted.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "autostash conflicted in {}; resolve the conflicts, then drop the entry with 'git stash drop' (the changes are kept in 'git stash list')",
+            conflicted.join(", ")
+        ))));
+    }
     Ok(())
 }
 
@@ -1959,8 +5152,10 @@ fn handle_checkout(
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "checkout")?;
     let repos = select_repos(&workspace, &args.repos, None, args.all, false)?;
 
+    let mut stashes = Vec::new();
     for repo in repos {
         if !repo.path.is_dir() {
             if args.graceful {
@@ -1996,10 +5191,17 @@ fn handle_checkout(
                 ))));
             }
         }
-        checkout_branch(&open.repo, &target)?;
+        if args.autostash {
+            stashes.push((
+                repo.id.as_str().to_string(),
+                checkout_branch_autostash(&open.repo, &target)?,
+            ));
+        } else {
+            checkout_branch(&open.repo, &target)?;
+        }
     }
 
-    Ok(())
+    report_autostash(&stashes)
 }
 
 fn handle_add(
@@ -2008,6 +5210,7 @@ fn handle_add(
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "add")?;
     let default_all = args.repos.is_empty();
     let repos = select_repos(&workspace, &args.repos, None, default_all, false)?;
 
@@ -2048,17 +5251,21 @@ fn handle_commit(
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
-    if args.amend && !args.yes {
-        let confirm = output::confirm("Amend commits in selected repos?", false)
-            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-        if !confirm {
-            return Ok(());
-        }
-    }
-
     let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "commit")?;
     let repos = select_repos(&workspace, &args.repos, None, false, false)?;
-    let mut commit_repos = Vec::new();
+    if args.amend
+        && !confirm_dangerous(
+            &workspace,
+            DangerousOperation::Amend,
+            "Amend commits in selected repos?",
+            &repos,
+            args.yes,
+        )?
+    {
+        return Ok(());
+    }
+    let mut commit_repos = Vec::new();
 
     for repo in repos {
         if !repo.path.is_dir() {
@@ -2081,11 +5288,37 @@ fn handle_commit(
     }
 
     if commit_repos.is_empty() {
-        output::info("nothing to commit");
+        if args.json {
+            print_hook_report(&HookReport::default())?;
+        } else {
+            output::info("nothing to commit");
+        }
         return Ok(());
     }
 
-    run_hook_for_repos(&workspace, &commit_repos, "pre_commit", args.no_hooks)?;
+    if !args.no_secret_scan {
+        ensure_no_secrets(&workspace, &commit_repos, SecretScanScope::Staged)?;
+    }
+
+    let hook_options = HookRunOptions {
+        skip: args.no_hooks,
+        timeout: args.hook_timeout.map(Duration::from_secs),
+        capture: args.json,
+    };
+    let mut report = HookReport::default();
+    let hooks = run_hook_for_repos(
+        &workspace,
+        &commit_repos,
+        "pre_commit",
+        &hook_options,
+        &mut report.hooks,
+    );
+    if let Err(err) = hooks {
+        if args.json {
+            print_hook_report(&report)?;
+        }
+        return Err(err);
+    }
 
     for repo in commit_repos {
         let mut cmd = vec!["git".to_string(), "commit".to_string()];
@@ -2110,9 +5343,17 @@ fn handle_commit(
                 repo.id.as_str()
             ));
         }
-        run_command_in_repo(&repo.path, &cmd)?;
+        if args.json {
+            run_command_in_repo_quiet(&repo.path, &cmd)?;
+        } else {
+            run_command_in_repo(&repo.path, &cmd)?;
+        }
+        report.repos.push(repo.id.as_str().to_string());
     }
 
+    if args.json {
+        print_hook_report(&report)?;
+    }
     Ok(())
 }
 
@@ -2127,19 +5368,53 @@ fn handle_push(
         )));
     }
 
-    if (args.force || args.force_with_lease) && !args.yes {
-        let confirm = output::confirm("Force push selected repos?", false)
-            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-        if !confirm {
-            return Ok(());
-        }
-    }
-
     let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "push")?;
     let repos = select_repos(&workspace, &args.repos, None, false, false)?;
+    if (args.force || args.force_with_lease)
+        && !confirm_dangerous(
+            &workspace,
+            DangerousOperation::ForcePush,
+            "Force push selected repos?",
+            &repos,
+            args.yes,
+        )?
+    {
+        return Ok(());
+    }
 
-    run_hook_for_repos(&workspace, &repos, "pre_push", args.no_hooks)?;
+    let set_upstream_by_default = workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.push_set_upstream)
+        .unwrap_or(false);
+    let hook_options = HookRunOptions {
+        skip: args.no_hooks,
+        timeout: args.hook_timeout.map(Duration::from_secs),
+        capture: args.json,
+    };
+    let mut report = HookReport::default();
+    let hooks = run_hook_for_repos(
+        &workspace,
+        &repos,
+        "pre_push",
+        &hook_options,
+        &mut report.hooks,
+    );
+    if let Err(err) = hooks {
+        if args.json {
+            print_hook_report(&report)?;
+        }
+        return Err(err);
+    }
 
+    let transport = git_transport(&workspace)?;
+    let progress = if args.json {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    };
     for repo in repos {
         if !repo.path.is_dir() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
@@ -2147,6 +5422,20 @@ fn handle_push(
                 repo.id.as_str()
             ))));
         }
+        let open = open_repo(&repo.path)?;
+        let branch = current_branch(&open.repo)?;
+        let upstream = branch_upstream(&open.repo, &branch)?;
+        // Branches created by Harmonia get their upstream on first push.
+        let set_upstream = args.set_upstream
+            || set_upstream_by_default
+            || branch_target(&open.repo, &branch)?.is_some();
+        let target = push_target(
+            configured_push_remote(&workspace, &repo),
+            default_push_remote(&open.repo),
+            upstream.as_deref(),
+            set_upstream,
+        );
+
         let mut cmd = vec!["git".to_string(), "push".to_string()];
         if args.dry_run {
             cmd.push("--dry-run".to_string());
@@ -2156,16 +5445,262 @@ fn handle_push(
         } else if args.force {
             cmd.push("--force".to_string());
         }
-        if args.set_upstream {
+        let set_upstream = target.set_upstream || (args.set_upstream && target.remote.is_none());
+        if set_upstream {
             cmd.push("-u".to_string());
         }
+        if let Some(remote) = &target.remote {
+            cmd.push(remote.clone());
+            cmd.push(branch.clone());
+        }
         log_git_command_for_repo(repo.id.as_str(), &cmd);
-        run_command_in_repo(&repo.path, &cmd)?;
+        let native = if transport == GitTransport::Native && !args.dry_run && !args.force_with_lease
+        {
+            native_push_destination(&repo.path, &branch, target.remote.as_deref())
+        } else {
+            None
+        };
+        // Credentials libgit2 cannot find may still work through the CLI's
+        // helpers and prompts, so an auth failure retries there.
+        let pushed_natively = match native {
+            Some((remote, remote_branch)) => {
+                let git = git2::Repository::open(&repo.path)
+                    .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?;
+                let pushed = native_push(
+                    &git,
+                    &PushSpec {
+                        remote: &remote,
+                        branch: &branch,
+                        remote_branch: &remote_branch,
+                        force: args.force,
+                    },
+                    repo.id.as_str(),
+                    &progress,
+                );
+                match pushed {
+                    Ok(()) => {
+                        if set_upstream {
+                            set_branch_upstream(
+                                &open.repo,
+                                &branch,
+                                &format!("{remote}/{remote_branch}"),
+                            )?;
+                        }
+                        true
+                    }
+                    Err(err) if is_auth_error(&err) => {
+                        output::warn(&format!(
+                            "{}: {}; retrying with git push",
+                            repo.id.as_str(),
+                            err
+                        ));
+                        false
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            None => false,
+        };
+        if !pushed_natively {
+            if args.json {
+                run_command_in_repo_quiet(&repo.path, &cmd)?;
+            } else {
+                run_command_in_repo(&repo.path, &cmd)?;
+            }
+        }
+        report.repos.push(repo.id.as_str().to_string());
+    }
+
+    if args.json {
+        print_hook_report(&report)?;
+    }
+    Ok(())
+}
+
+/// Where `push` sends a branch: an explicit remote, or `None` to let git push
+/// to the branch's upstream.
+#[derive(Debug, PartialEq, Eq)]
+struct PushTarget {
+    remote: Option<String>,
+    set_upstream: bool,
+}
+
+/// With a configured `push_remote` every branch goes there (fork setups), and
+/// tracking is set until the upstream points at that remote. Otherwise only
+/// branches without an upstream name a remote, on their first push.
+fn push_target(
+    push_remote: Option<&str>,
+    default_remote: Option<String>,
+    upstream: Option<&str>,
+    set_upstream: bool,
+) -> PushTarget {
+    if let Some(remote) = push_remote {
+        let tracked = upstream.is_some_and(|upstream| {
+            upstream
+                .strip_prefix(remote)
+                .is_some_and(|rest| rest.starts_with('/'))
+        });
+        return PushTarget {
+            remote: Some(remote.to_string()),
+            set_upstream: set_upstream && !tracked,
+        };
+    }
+    if upstream.is_some() || !set_upstream {
+        return PushTarget {
+            remote: None,
+            set_upstream: false,
+        };
+    }
+    PushTarget {
+        remote: default_remote,
+        set_upstream: true,
+    }
+}
+
+fn configured_push_remote<'a>(workspace: &'a Workspace, repo: &Repo) -> Option<&'a str> {
+    workspace
+        .config
+        .repos
+        .get(repo.id.as_str())
+        .and_then(|entry| entry.push_remote.as_deref())
+        .map(str::trim)
+        .filter(|remote| !remote.is_empty())
+}
+
+/// One `grep` hit. `line` and `text` are absent with `--files-with-matches`.
+#[derive(Debug, PartialEq, Serialize)]
+struct GrepMatch {
+    repo: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+fn handle_grep(
+    args: GrepArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let mut repos = select_repos(
+        &workspace,
+        &args.repos,
+        None,
+        args.repos.is_empty() || args.changed,
+        false,
+    )?;
+    if args.changed {
+        repos = filter_changed_repos(&workspace, repos)?;
+    }
+    repos.retain(|repo| repo.path.is_dir());
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    if repos.is_empty() {
+        output::info("no repos selected for grep");
+        return Ok(());
+    }
+
+    let command = git_grep_command(&args);
+    let jobs = resolve_parallel(&workspace, "grep", args.parallel);
+    let results = parallel::run_in_parallel(repos, jobs, |repo| {
+        let output = std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .current_dir(&repo.path)
+            .output()
+            .with_context(|| format!("failed to run {:?}", command))?;
+        // git grep exits 1 when nothing matched.
+        match output.status.code() {
+            Some(0) | Some(1) => Ok(parse_git_grep_output(
+                repo.id.as_str(),
+                &String::from_utf8_lossy(&output.stdout),
+                args.files_with_matches,
+            )),
+            _ => Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "git grep failed in {}: {}",
+                repo.id.as_str(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))),
+        }
+    });
+    let mut matches = Vec::new();
+    for result in results {
+        matches.extend(result?);
     }
 
+    if args.json {
+        let json = serde_json::to_string_pretty(&matches)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        println!("{}", json);
+        return Ok(());
+    }
+    if matches.is_empty() {
+        output::info("no matches");
+        return Ok(());
+    }
+    for entry in &matches {
+        match (entry.line, entry.text.as_deref()) {
+            (Some(line), Some(text)) => println!("{}/{}:{}:{}", entry.repo, entry.path, line, text),
+            _ => println!("{}/{}", entry.repo, entry.path),
+        }
+    }
     Ok(())
 }
 
+fn git_grep_command(args: &GrepArgs) -> Vec<String> {
+    let mut command: Vec<String> = ["git", "grep", "-I", "--null", "--no-color"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    command.push(if args.files_with_matches { "-l" } else { "-n" }.to_string());
+    command.push(if args.fixed_strings { "-F" } else { "-E" }.to_string());
+    if args.ignore_case {
+        command.push("-i".to_string());
+    }
+    if args.word_regexp {
+        command.push("-w".to_string());
+    }
+    if args.untracked {
+        command.push("--untracked".to_string());
+    }
+    command.push("-e".to_string());
+    command.push(args.pattern.clone());
+    command
+}
+
+/// Parses `git grep --null` output: `path\0line\0text` per line, or
+/// NUL-terminated paths with `-l`.
+fn parse_git_grep_output(repo: &str, stdout: &str, files_only: bool) -> Vec<GrepMatch> {
+    if files_only {
+        return stdout
+            .split('\0')
+            .map(|path| path.trim_start_matches('\n'))
+            .filter(|path| !path.is_empty())
+            .map(|path| GrepMatch {
+                repo: repo.to_string(),
+                path: path.to_string(),
+                line: None,
+                text: None,
+            })
+            .collect();
+    }
+    stdout
+        .lines()
+        .filter_map(|record| {
+            let mut fields = record.splitn(3, '\0');
+            let path = fields.next()?;
+            let line = fields.next()?.parse().ok()?;
+            let text = fields.next()?;
+            Some(GrepMatch {
+                repo: repo.to_string(),
+                path: path.to_string(),
+                line: Some(line),
+                text: Some(text.to_string()),
+            })
+        })
+        .collect()
+}
+
 fn handle_diff(
     args: DiffArgs,
     workspace_root: Option<PathBuf>,
@@ -2173,11 +5708,42 @@ fn handle_diff(
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
     let include_untracked = include_untracked_by_default(&workspace);
-    let default_changed = args.repos.is_empty();
+    let default_changed = args.repos.is_empty() && !repo_selection_active();
     let mut repos = select_repos(&workspace, &args.repos, None, default_changed, false)?;
 
     if default_changed {
-        repos = filter_changed_repos(repos)?;
+        repos = filter_changed_repos(&workspace, repos)?;
+    }
+
+    if args.summary {
+        let mut rows = Vec::new();
+        for repo in &repos {
+            let summary =
+                git_diff_summary(&repo.path, repo.id.as_str(), args.staged, include_untracked)?;
+            rows.push((repo.id.as_str().to_string(), summary));
+        }
+        if args.format.eq_ignore_ascii_case("json") {
+            let entries: Vec<DiffSummaryJsonEntry> = rows
+                .into_iter()
+                .map(|(repo, summary)| DiffSummaryJsonEntry { repo, summary })
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries)
+                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+            );
+        } else {
+            for line in render_diff_summary(&rows, console::colors_enabled()) {
+                println!("{line}");
+            }
+        }
+        return Ok(());
+    }
+
+    if args.hunks && !args.format.eq_ignore_ascii_case("json") {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "--hunks requires --format json"
+        )));
     }
 
     if args.format.eq_ignore_ascii_case("json") {
@@ -2185,9 +5751,18 @@ fn handle_diff(
         for repo in repos {
             let files =
                 git_diff_files(&repo.path, repo.id.as_str(), args.staged, include_untracked)?;
+            let changes = git_diff_changes(
+                &repo.path,
+                repo.id.as_str(),
+                &args,
+                include_untracked && !args.staged,
+            )?;
             entries.push(DiffJsonEntry {
                 repo: repo.id.as_str().to_string(),
                 files,
+                insertions: changes.iter().map(|change| change.insertions).sum(),
+                deletions: changes.iter().map(|change| change.deletions).sum(),
+                changes,
             });
         }
         println!(
@@ -2226,12 +5801,8 @@ fn handle_diff(
     Ok(())
 }
 
-fn handle_version(
-    args: VersionArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root, config_path)?;
+fn handle_version(args: VersionArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
     let command = args
         .command
         .unwrap_or(VersionCommand::Show(VersionShowArgs {
@@ -2240,3058 +5811,10914 @@ fn handle_version(
         }));
 
     match command {
-        VersionCommand::Show(show) => handle_version_show(show, &workspace),
-        VersionCommand::Check(check) => handle_version_check(check, &workspace),
-        VersionCommand::Bump(bump) => handle_version_bump(bump, &workspace),
+        VersionCommand::Show(mut show) => {
+            native_json(&mut show.json);
+            handle_version_show(show, state)
+        }
+        VersionCommand::Check(mut check) => {
+            native_json(&mut check.json);
+            handle_version_check(check, state)
+        }
+        VersionCommand::Bump(bump) => {
+            if !bump.dry_run {
+                ensure_writable(workspace, "version bump")?;
+            }
+            handle_version_bump(bump, workspace)
+        }
     }
 }
 
-fn handle_deps(
-    args: DepsArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root, config_path)?;
-    let command = args
-        .command
-        .unwrap_or(DepsCommand::Show(DepsShowArgs { json: false }));
-
-    match command {
-        DepsCommand::Show(show) => handle_deps_show(show, &workspace),
-        DepsCommand::Check(check) => handle_deps_check(check, &workspace),
-        DepsCommand::Update(update) => handle_deps_update(update, &workspace),
-    }
-}
+const DEFAULT_PROPAGATE_DIR: &str = ".harmonia/propagate";
 
-fn handle_edit(
-    args: EditArgs,
+fn handle_propagate(
+    args: PropagateArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
-    if args.all && !args.repos.is_empty() {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "--all cannot be combined with explicit repos"
-        )));
-    }
-
     let workspace = load_workspace(workspace_root, config_path)?;
-    let targets: Vec<PathBuf> = if !args.repos.is_empty() {
-        select_repos(&workspace, &args.repos, None, false, false)?
-            .into_iter()
-            .map(|repo| repo.path)
-            .collect()
-    } else if args.all {
-        let repos = select_repos(&workspace, &[], None, true, false)?;
-        filter_changed_repos(repos)?
-            .into_iter()
-            .map(|repo| repo.path)
-            .collect()
-    } else {
-        vec![workspace.root.clone()]
-    };
-
-    if targets.is_empty() {
-        output::info("no paths selected for edit");
-        return Ok(());
+    if !args.check {
+        ensure_writable(&workspace, "propagate")?;
     }
-
-    let mut command = resolve_editor_command(args.editor.as_deref())?;
-    for target in targets {
-        command.push(target.to_string_lossy().to_string());
+    let config = workspace.config.propagate.clone().unwrap_or_default();
+    let dir = resolve_template_path(
+        &workspace,
+        config.dir.as_deref().unwrap_or(DEFAULT_PROPAGATE_DIR),
+    );
+    if !dir.is_dir() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "propagate template directory {} not found (set [propagate].dir)",
+            dir.display()
+        ))));
     }
-    run_command_in_repo(&workspace.root, &command)
-}
-
-fn handle_clean(
-    args: CleanArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root, config_path)?;
-    let mut repos = if args.repos.is_empty() {
-        select_repos(&workspace, &[], None, true, false)?
-    } else {
-        select_repos(&workspace, &args.repos, None, false, false)?
-    };
-    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
-
-    if repos.is_empty() {
-        output::info("no repos selected for clean");
+    let entries = list_template_entries(&dir)?;
+    if entries.is_empty() {
+        output::info(&format!("no files in {}", dir.display()));
         return Ok(());
     }
-    if !args.force {
-        output::info("clean is running in dry-run mode; pass --force to delete files");
-    }
 
-    let multi = repos.len() > 1;
+    let all = args.repos.is_empty() && args.group.is_none();
+    let repos = select_repos(&workspace, &args.repos, args.group.as_deref(), all, false)?;
+    let mut drifted_repos = 0;
     for repo in repos {
         if !repo.path.is_dir() {
-            output::warn(&format!("repo {} not cloned; skipping", repo.id.as_str()));
+            output::warn(&format!("skipping {}: not cloned", repo.id.as_str()));
             continue;
         }
-        if multi {
-            println!("== {} ==", repo.id.as_str());
-        }
-        let mut command = vec!["git".to_string(), "clean".to_string()];
-        if args.force {
-            command.push("-f".to_string());
-        } else {
-            command.push("-n".to_string());
+        let context = propagate_context(&workspace, &repo, &config.vars);
+        let mut changed = Vec::new();
+        for entry in &entries {
+            let desired = render_template_entry(entry, &context)?;
+            let destination = repo.path.join(&entry.target);
+            let current = fs::read(&destination).ok();
+            if current.as_deref() == Some(desired.as_slice()) {
+                continue;
+            }
+            let target = entry.target.to_string_lossy().replace('\\', "/");
+            if args.check {
+                let state = if current.is_some() {
+                    "differs"
+                } else {
+                    "missing"
+                };
+                println!("{}: {} ({})", repo.id.as_str(), target, state);
+            } else {
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&destination, &desired)?;
+                if current.is_none() {
+                    fs::set_permissions(&destination, fs::metadata(&entry.source)?.permissions())?;
+                }
+                println!(
+                    "{}: {} {}",
+                    repo.id.as_str(),
+                    if current.is_some() {
+                        "updated"
+                    } else {
+                        "created"
+                    },
+                    target
+                );
+            }
+            changed.push(target);
         }
-        if args.directories {
-            command.push("-d".to_string());
+        if changed.is_empty() {
+            continue;
         }
-        if args.ignored {
-            command.push("-x".to_string());
+        drifted_repos += 1;
+        if !args.check && !args.no_stage {
+            let mut cmd = vec!["git".to_string(), "add".to_string(), "--".to_string()];
+            cmd.extend(changed);
+            log_git_command_for_repo(repo.id.as_str(), &cmd);
+            run_command_in_repo(&repo.path, &cmd)?;
         }
-        log_git_command_for_repo(repo.id.as_str(), &command);
-        run_command_in_repo(&repo.path, &command)?;
     }
 
+    if drifted_repos == 0 {
+        output::info("all repos match the propagate templates");
+    } else if args.check {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "propagated files drifted in {} repo(s); run `harmonia propagate` to update them",
+            drifted_repos
+        ))));
+    }
     Ok(())
 }
 
-fn handle_config(
-    args: ConfigArgs,
+/// Template context for one repo: `workspace_name`, `repo.*` metadata, and
+/// `[propagate].vars` as `vars.*`.
+fn propagate_context(
+    workspace: &Workspace,
+    repo: &Repo,
+    vars: &HashMap<String, String>,
+) -> serde_json::Value {
+    let workspace_name = if workspace.config.workspace.name.is_empty() {
+        workspace
+            .root
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or("workspace")
+            .to_string()
+    } else {
+        workspace.config.workspace.name.clone()
+    };
+    serde_json::json!({
+        "workspace_name": workspace_name,
+        "repo": {
+            "name": repo.id.as_str(),
+            "package_name": repo.package_name.as_deref().unwrap_or(repo.id.as_str()),
+            "default_branch": repo.default_branch,
+            "ecosystem": repo.ecosystem.as_ref().map(|ecosystem| ecosystem.as_str()).unwrap_or(""),
+            "url": repo.remote_url,
+        },
+        "vars": vars,
+    })
+}
+
+const EXPORT_MANIFEST: &str = "HARMONIA_EXPORT.json";
+
+fn handle_export(
+    args: ExportArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
-    let (workspace_root, config_path) = resolve_workspace_paths(workspace_root, config_path)?;
-    let command = args.command.unwrap_or(ConfigCommand::Show);
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let all = args.repos.is_empty() && args.group.is_none();
+    let mut selected = select_repos(&workspace, &args.repos, args.group.as_deref(), all, false)?;
+    if args.with_deps {
+        let mut ids: HashSet<RepoId> = selected.iter().map(|repo| repo.id.clone()).collect();
+        for repo in &selected {
+            ids.extend(transitive_dependencies(
+                &workspace.graph,
+                &workspace.repos,
+                &repo.id,
+            ));
+        }
+        selected = ids
+            .into_iter()
+            .filter_map(|id| workspace.repos.get(&id).cloned())
+            .filter(|repo| should_include_repo(repo, false))
+            .collect();
+    }
+    if selected.is_empty() {
+        output::info("no repos selected for export");
+        return Ok(());
+    }
+    for repo in &selected {
+        if !repo.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo {} not cloned",
+                repo.id.as_str()
+            ))));
+        }
+    }
 
-    match command {
-        ConfigCommand::Show => handle_config_show(&config_path),
-        ConfigCommand::Get(get) => handle_config_get(&config_path, get),
-        ConfigCommand::Set(set) => handle_config_set(&config_path, set),
-        ConfigCommand::Edit(edit) => handle_config_edit(&workspace_root, &config_path, edit),
+    // Dependencies first, so history imports read bottom-up.
+    let targets: Vec<RepoId> = selected.iter().map(|repo| repo.id.clone()).collect();
+    let order =
+        merge_order(&workspace.graph, &workspace.repos, &targets).map_err(HarmoniaError::Other)?;
+    let mut repos: Vec<Repo> = order
+        .iter()
+        .filter_map(|id| selected.iter().find(|repo| &repo.id == id).cloned())
+        .collect();
+    let mut remaining: Vec<Repo> = selected
+        .into_iter()
+        .filter(|repo| !order.contains(&repo.id))
+        .collect();
+    remaining.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    repos.extend(remaining);
+
+    let dest = if args.dest.is_absolute() {
+        args.dest.clone()
+    } else {
+        env::current_dir()?.join(&args.dest)
+    };
+    if dest.exists() && fs::read_dir(&dest)?.next().is_some() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "export destination {} is not empty",
+            dest.display()
+        ))));
     }
-}
+    fs::create_dir_all(&dest)?;
 
-fn handle_config_show(config_path: &Path) -> Result<()> {
-    let contents = fs::read_to_string(config_path)?;
-    if contents.is_empty() {
-        return Ok(());
+    let mut manifest = Vec::new();
+    for repo in &repos {
+        let open = open_repo(&repo.path)?;
+        if !repo_status(&open.repo)?.is_clean() {
+            output::warn(&format!(
+                "{} has uncommitted changes; only committed HEAD is exported",
+                repo.id.as_str()
+            ));
+        }
+        manifest.push(serde_json::json!({
+            "repo": repo.id.as_str(),
+            "path": repo.id.as_str(),
+            "branch": current_branch(&open.repo)?,
+            "commit": head_commit(&open.repo)?,
+        }));
+    }
+
+    if args.history {
+        export_with_history(&dest, &repos)?;
+    } else {
+        for repo in &repos {
+            output::git_op(&format!("archive HEAD (repo {})", repo.id.as_str()));
+            export_repo_tree(&dest, repo)?;
+        }
+    }
+
+    let manifest_json = serde_json::to_string_pretty(&serde_json::json!({ "repos": manifest }))
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(dest.join(EXPORT_MANIFEST), manifest_json)?;
+    if args.history {
+        run_git_in(&dest, &["add", EXPORT_MANIFEST])?;
+        run_git_in(
+            &dest,
+            &["commit", "--quiet", "-m", "Add Harmonia export manifest"],
+        )?;
+    }
+    output::info(&format!(
+        "exported {} repos to {}",
+        repos.len(),
+        dest.display()
+    ));
+
+    if args.archive {
+        let name = dest
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("invalid export destination")))?
+            .to_string();
+        let parent = dest.parent().unwrap_or(Path::new("/"));
+        let archive = parent.join(format!("{name}.tar.gz"));
+        run_command_in_repo(
+            parent,
+            &[
+                "tar".to_string(),
+                "-czf".to_string(),
+                archive.to_string_lossy().to_string(),
+                name,
+            ],
+        )?;
+        output::info(&format!("wrote archive {}", archive.display()));
     }
-    print!("{}", contents);
     Ok(())
 }
 
-fn handle_config_get(config_path: &Path, args: ConfigGetArgs) -> Result<()> {
-    let value = read_workspace_config_value(config_path)?;
-    let found = workspace_config_get(&value, &args.key).ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!(format!(
-            "config key '{}' not found",
-            args.key
-        )))
-    })?;
-    println!("{}", format_config_value(found));
-    Ok(())
+/// Copies the committed tree at HEAD into `<dest>/<repo>` without history.
+fn export_repo_tree(dest: &Path, repo: &Repo) -> Result<()> {
+    let tarball = dest.join(format!(".harmonia-export-{}.tar", std::process::id()));
+    let prefix = format!("{}/", repo.id.as_str());
+    run_git_in(
+        &repo.path,
+        &[
+            "archive",
+            "--format=tar",
+            "--prefix",
+            prefix.as_str(),
+            "-o",
+            tarball.to_string_lossy().as_ref(),
+            "HEAD",
+        ],
+    )?;
+    let extracted = run_command_in_repo(
+        dest,
+        &[
+            "tar".to_string(),
+            "-xf".to_string(),
+            tarball.to_string_lossy().to_string(),
+        ],
+    );
+    fs::remove_file(&tarball)?;
+    extracted
 }
 
-fn handle_config_set(config_path: &Path, args: ConfigSetArgs) -> Result<()> {
-    let mut value = read_workspace_config_value(config_path)?;
-    let parsed = parse_config_value(&args.value)?;
-    workspace_config_set(&mut value, &args.key, parsed)?;
-    write_workspace_config_value(config_path, &value)?;
-    output::info(&format!("updated {}", args.key));
+/// Builds a git repo at `dest` and subtree-merges each repo's HEAD under its
+/// own prefix, keeping full history.
+fn export_with_history(dest: &Path, repos: &[Repo]) -> Result<()> {
+    run_git_in(dest, &["init", "--quiet"])?;
+    let has_identity = std::process::Command::new("git")
+        .args(["config", "user.email"])
+        .current_dir(dest)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !has_identity {
+        run_git_in(dest, &["config", "user.name", "Harmonia Export"])?;
+        run_git_in(dest, &["config", "user.email", "harmonia-export@localhost"])?;
+    }
+    run_git_in(
+        dest,
+        &[
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "-m",
+            "Initialize merged export",
+        ],
+    )?;
+
+    for repo in repos {
+        let source = repo.path.to_string_lossy().to_string();
+        let prefix = format!("{}/", repo.id.as_str());
+        output::git_op(&format!(
+            "subtree merge into {} (repo {})",
+            prefix,
+            repo.id.as_str()
+        ));
+        run_git_in(dest, &["fetch", "--quiet", source.as_str(), "HEAD"])?;
+        run_git_in(
+            dest,
+            &[
+                "merge",
+                "--quiet",
+                "-s",
+                "ours",
+                "--no-commit",
+                "--allow-unrelated-histories",
+                "FETCH_HEAD",
+            ],
+        )?;
+        run_git_in(
+            dest,
+            &["read-tree", "--prefix", prefix.as_str(), "-u", "FETCH_HEAD"],
+        )?;
+        let message = format!("Import {} history", repo.id.as_str());
+        run_git_in(dest, &["commit", "--quiet", "-m", message.as_str()])?;
+    }
     Ok(())
 }
 
-fn handle_config_edit(
-    workspace_root: &Path,
-    config_path: &Path,
-    args: ConfigEditArgs,
-) -> Result<()> {
-    let mut command = resolve_editor_command(args.editor.as_deref())?;
-    command.push(config_path.to_string_lossy().to_string());
-    run_command_in_repo(workspace_root, &command)
+fn run_git_in(dir: &Path, args: &[&str]) -> Result<()> {
+    let command: Vec<String> = std::iter::once("git")
+        .chain(args.iter().copied())
+        .map(str::to_string)
+        .collect();
+    run_command_in_repo(dir, &command)
 }
 
-fn handle_repo(
-    args: RepoArgs,
+fn handle_cache(
+    args: CacheArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
-    let (_, config_path) = resolve_workspace_paths(workspace_root, config_path)?;
-    let command = args.command.unwrap_or(RepoCommand::List);
-
-    match command {
-        RepoCommand::List => handle_repo_list(&config_path),
-        RepoCommand::Add(add) => handle_repo_add(&config_path, add),
-        RepoCommand::Remove(remove) => handle_repo_remove(&config_path, remove),
-        RepoCommand::Show(show) => handle_repo_show(&config_path, show),
+    // Resolve the root without loading the workspace, which would repopulate
+    // the cache before it is cleared.
+    let (root, _) = resolve_workspace_paths(workspace_root, config_path)?;
+    match args.command {
+        CacheCommand::Clear => {
+            let dir = cache_dir(&root);
+            if dir.is_dir() {
+                fs::remove_dir_all(&dir)?;
+                output::info(&format!("removed {}", dir.display()));
+            } else {
+                output::info("cache is already empty");
+            }
+        }
     }
+    Ok(())
 }
 
-fn handle_repo_list(config_path: &Path) -> Result<()> {
-    let value = read_workspace_config_value(config_path)?;
-    let repos = workspace_repos_table(&value)?;
-    if repos.is_empty() {
-        output::info("no repos configured");
-        return Ok(());
-    }
+const DEFAULT_WEBHOOK_LISTEN: &str = "127.0.0.1:8787";
+/// How long a connection gets to send its request.
+const WEBHOOK_READ_TIMEOUT: Duration = Duration::from_secs(10);
 
-    println!("Repo          URL                              External  Ignored");
-    println!("----------------------------------------------------------------");
-    let mut names: Vec<&String> = repos.keys().collect();
-    names.sort();
-    for name in names {
-        let Some(entry) = repos.get(name) else {
-            continue;
-        };
-        let url = entry
-            .get("url")
-            .and_then(|value| value.as_str())
-            .unwrap_or("-");
-        let external = entry
-            .get("external")
-            .and_then(|value| value.as_bool())
-            .unwrap_or(false);
-        let ignored = entry
-            .get("ignored")
-            .and_then(|value| value.as_bool())
-            .unwrap_or(false);
-        println!(
-            "{:<13} {:<32} {:<8} {}",
-            name.as_str(),
-            url,
-            external,
-            ignored
-        );
+fn handle_webhook(
+    args: WebhookArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    match args.command {
+        WebhookCommand::Serve(serve) => handle_webhook_serve(serve, &workspace),
     }
-
-    Ok(())
 }
 
-fn handle_repo_show(config_path: &Path, args: RepoShowArgs) -> Result<()> {
-    let value = read_workspace_config_value(config_path)?;
-    let repos = workspace_repos_table(&value)?;
-    let entry = repos.get(&args.name).ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!(format!(
-            "repo '{}' not found in config",
-            args.name
-        )))
+/// Answers deliveries one at a time, so actions never overlap. A delivery is
+/// acknowledged before its action runs, since forges give up on slow
+/// responses.
+fn handle_webhook_serve(args: WebhookServeArgs, workspace: &Workspace) -> Result<()> {
+    let config = workspace.config.webhook.clone().unwrap_or_default();
+    let secret = config.resolve_secret().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(
+            "webhook serve needs a secret to verify deliveries; set [webhook].secret_env or [webhook].secret"
+        ))
     })?;
+    let listen = args
+        .listen
+        .or_else(|| config.listen.clone())
+        .unwrap_or_else(|| DEFAULT_WEBHOOK_LISTEN.to_string());
+    let listener = std::net::TcpListener::bind(&listen)
+        .with_context(|| format!("cannot listen on {}", listen))?;
+    // Polled rather than blocking, so an interrupt recorded while an action
+    // ran under a timeout still ends the loop.
+    listener.set_nonblocking(true)?;
+    output::info(&format!(
+        "webhook: listening on http://{}{}",
+        listener.local_addr()?,
+        if args.dry_run { " (dry run)" } else { "" }
+    ));
 
-    let url = entry.get("url").and_then(|value| value.as_str());
-    let default_branch = entry.get("default_branch").and_then(|value| value.as_str());
-    let package_name = entry.get("package_name").and_then(|value| value.as_str());
-    let ecosystem = entry.get("ecosystem").and_then(|value| value.as_str());
-    let external = entry
-        .get("external")
-        .and_then(|value| value.as_bool())
-        .unwrap_or(false);
-    let ignored = entry
-        .get("ignored")
-        .and_then(|value| value.as_bool())
-        .unwrap_or(false);
-
-    println!("repo: {}", args.name);
-    println!("  url: {}", url.unwrap_or("(default)"));
-    println!(
-        "  default_branch: {}",
-        default_branch.unwrap_or("(workspace default)")
-    );
-    println!("  package_name: {}", package_name.unwrap_or("(repo name)"));
-    println!(
-        "  ecosystem: {}",
-        ecosystem.unwrap_or("(repo config/default)")
-    );
-    println!("  external: {}", external);
-    println!("  ignored: {}", ignored);
+    while interrupt_signal().is_none() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                serve_webhook_delivery(workspace, &config, &secret, stream, args.dry_run)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(err) => {
+                output::warn(&format!("webhook: accept failed: {}", err));
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+    output::info("webhook: stopped");
     Ok(())
 }
 
-fn handle_repo_add(config_path: &Path, args: RepoAddArgs) -> Result<()> {
-    let mut value = read_workspace_config_value(config_path)?;
-    let root = value.as_table_mut().ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!("workspace config root must be a table"))
-    })?;
-    let repos = root
-        .entry("repos".to_string())
-        .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
-        .as_table_mut()
-        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
-
-    if repos.contains_key(&args.name) {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-            "repo '{}' already exists",
-            args.name
-        ))));
+fn serve_webhook_delivery(
+    workspace: &Workspace,
+    config: &crate::config::WebhookConfig,
+    secret: &str,
+    mut stream: std::net::TcpStream,
+    dry_run: bool,
+) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(WEBHOOK_READ_TIMEOUT));
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(err) => {
+            output::warn(&format!("webhook: unreadable request: {}", err));
+            let _ = write_response(
+                &mut stream,
+                400,
+                &serde_json::json!({ "error": err.to_string() }),
+            );
+            return;
+        }
+    };
+    if request.method != "POST" {
+        let _ = write_response(
+            &mut stream,
+            405,
+            &serde_json::json!({ "error": "webhooks are delivered with POST" }),
+        );
+        return;
     }
+    let delivery = match verify_delivery(secret, &request) {
+        Ok(delivery) => delivery,
+        Err(rejection) => {
+            output::warn(&format!("webhook: rejected delivery: {}", rejection));
+            let _ = write_response(
+                &mut stream,
+                rejection.status(),
+                &serde_json::json!({ "error": rejection.to_string() }),
+            );
+            return;
+        }
+    };
 
-    let mut entry = toml::map::Map::new();
-    if let Some(url) = args.url {
-        entry.insert("url".to_string(), toml::Value::String(url));
-    }
-    if let Some(default_branch) = args.default_branch {
-        entry.insert(
-            "default_branch".to_string(),
-            toml::Value::String(default_branch),
-        );
-    }
-    if let Some(package_name) = args.package_name {
-        entry.insert(
-            "package_name".to_string(),
-            toml::Value::String(package_name),
+    let label = match delivery.project.as_deref() {
+        Some(project) => format!("{} on {}", delivery.event, project),
+        None => delivery.event.clone(),
+    };
+    if !webhook_event_allowed(&config.events, &delivery.event) {
+        output::info(&format!(
+            "webhook: ignoring {} (not in [webhook].events)",
+            label
+        ));
+        let _ = write_response(
+            &mut stream,
+            200,
+            &serde_json::json!({ "event": delivery.event, "action": "ignored" }),
         );
+        return;
     }
-    if let Some(ecosystem) = args.ecosystem {
-        let ecosystem = ecosystem.trim();
-        if ecosystem.is_empty() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(
-                "--ecosystem cannot be empty"
-            )));
-        }
-        entry.insert(
-            "ecosystem".to_string(),
-            toml::Value::String(ecosystem.to_string()),
+    let Some(command) = webhook_action(&config.actions, &delivery.event) else {
+        output::info(&format!("webhook: no action configured for {}", label));
+        let _ = write_response(
+            &mut stream,
+            200,
+            &serde_json::json!({ "event": delivery.event, "action": "none" }),
         );
-    }
-    if args.external {
-        entry.insert("external".to_string(), toml::Value::Boolean(true));
-    }
-    if args.ignored {
-        entry.insert("ignored".to_string(), toml::Value::Boolean(true));
-    }
-
-    repos.insert(args.name.clone(), toml::Value::Table(entry));
+        return;
+    };
+    let _ = write_response(
+        &mut stream,
+        202,
+        &serde_json::json!({ "event": delivery.event, "action": command, "dry_run": dry_run }),
+    );
+    drop(stream);
 
-    if let Some(group) = args.group {
-        let groups = root
-            .entry("groups".to_string())
-            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
-            .as_table_mut()
-            .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[groups] must be a table")))?;
-        let members = groups
-            .entry(group.clone())
-            .or_insert_with(|| toml::Value::Array(Vec::new()))
-            .as_array_mut()
-            .ok_or_else(|| {
-                HarmoniaError::Other(anyhow::anyhow!(format!(
-                    "[groups].{} must be an array",
-                    group
-                )))
-            })?;
-        if !members
-            .iter()
-            .any(|value| value.as_str() == Some(args.name.as_str()))
-        {
-            members.push(toml::Value::String(args.name.clone()));
-        }
+    if dry_run {
+        output::info(&format!(
+            "webhook: dry run: would run '{}' for {}",
+            command, label
+        ));
+        return;
     }
-
-    write_workspace_config_value(config_path, &value)?;
-    output::info(&format!("added repo {}", args.name));
-    Ok(())
-}
-
-fn handle_repo_remove(config_path: &Path, args: RepoRemoveArgs) -> Result<()> {
-    let mut value = read_workspace_config_value(config_path)?;
-    let root = value.as_table_mut().ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!("workspace config root must be a table"))
-    })?;
-    let repos = root
-        .get_mut("repos")
-        .and_then(|value| value.as_table_mut())
-        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
-
-    if repos.remove(&args.name).is_none() {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-            "repo '{}' not found in config",
-            args.name
-        ))));
+    output::info(&format!("webhook: running '{}' for {}", command, label));
+    if let Err(err) = run_webhook_action(workspace, &delivery, command) {
+        output::error(&format!("webhook: {}", err));
     }
+}
 
-    if let Some(groups) = root
-        .get_mut("groups")
-        .and_then(|value| value.as_table_mut())
-    {
-        for (_, value) in groups.iter_mut() {
-            if let Some(array) = value.as_array_mut() {
-                array.retain(|item| item.as_str() != Some(args.name.as_str()));
-            }
+/// Runs `command` in the workspace root with the delivery described in
+/// `HARMONIA_WEBHOOK_*` variables; `HARMONIA_WEBHOOK_REPO` names the
+/// workspace repo of the event's project, when there is one.
+fn run_webhook_action(
+    workspace: &Workspace,
+    delivery: &WebhookDelivery,
+    command: &str,
+) -> Result<()> {
+    let mut env_vars = workspace.command_env(None)?;
+    env_vars.push((
+        "HARMONIA_WEBHOOK_SOURCE".to_string(),
+        delivery.source.as_str().to_string(),
+    ));
+    env_vars.push(("HARMONIA_WEBHOOK_EVENT".to_string(), delivery.event.clone()));
+    if let Some(project) = delivery.project.as_deref() {
+        env_vars.push(("HARMONIA_WEBHOOK_PROJECT".to_string(), project.to_string()));
+        if let Some(repo) = workspace
+            .repos
+            .values()
+            .find(|repo| forge_project_path(workspace, repo).eq_ignore_ascii_case(project))
+        {
+            env_vars.push((
+                "HARMONIA_WEBHOOK_REPO".to_string(),
+                repo.id.as_str().to_string(),
+            ));
         }
     }
-
-    write_workspace_config_value(config_path, &value)?;
-    output::info(&format!("removed repo {}", args.name));
-    Ok(())
+    run_shell_command_in_repo(
+        &workspace.root,
+        &[command.to_string()],
+        &env_vars,
+        resolve_command_timeout(workspace, None),
+    )
 }
 
-fn handle_test(
-    args: TestArgs,
+fn handle_stats(
+    args: StatsArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
-    let default_changed = args.repos.is_empty() && !args.all && !args.changed;
-    let changed_scope = args.changed || default_changed;
-    let mut repos = select_repos(
-        &workspace,
-        &args.repos,
-        None,
-        args.all || changed_scope,
-        false,
-    )?;
-    if changed_scope {
-        repos = filter_changed_repos(repos)?;
-    }
-    if args.graph_order {
-        repos = repos_in_graph_order(&workspace, repos)?;
-    } else {
-        repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    if args.clear {
+        let path = stats_path(&workspace.root);
+        if path.is_file() {
+            fs::remove_file(&path)?;
+        }
+        output::info("cleared recorded stats");
+        return Ok(());
     }
-    if repos.is_empty() {
-        output::info("no repos selected for test");
+
+    let records = load_stats(&workspace.root)?;
+    let summary = summarize_stats(&records);
+    if args.json {
+        let rows: Vec<serde_json::Value> = summary
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "command": entry.command,
+                    "runs": entry.runs,
+                    "failures": entry.failures,
+                    "total_ms": entry.total_ms,
+                    "avg_ms": entry.avg_ms(),
+                    "max_ms": entry.max_ms,
+                    "avg_repos": entry.avg_repos(),
+                    "failure_categories": entry.failure_categories,
+                })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&rows)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        println!("{}", json);
         return Ok(());
     }
 
-    let mut commands = Vec::new();
-    for repo in repos {
-        if !repo.path.is_dir() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "repo {} not cloned",
-                repo.id.as_str()
-            ))));
+    if records.is_empty() {
+        if workspace.config.workspace.stats {
+            output::info("no stats recorded yet");
+        } else {
+            output::info("no stats recorded; enable them with `[workspace] stats = true`");
         }
-        let base = match resolve_quality_command(&workspace, &repo, QualityKind::Test) {
-            Some(command) => command,
-            None => {
-                output::warn(&format!(
-                    "no test command configured for {}; skipping",
-                    repo.id.as_str()
-                ));
-                continue;
-            }
-        };
-        let mut command = base;
-        if args.coverage {
-            let (updated, applied) = apply_test_coverage(&command, &repo);
-            command = updated;
-            if !applied {
-                output::warn(&format!(
-                    "coverage requested for {} but command has no automatic coverage flag; running as-is",
-                    repo.id.as_str()
-                ));
-            }
+        return Ok(());
+    }
+    println!(
+        "{:<20} {:>6} {:>8} {:>10} {:>10} {:>10} {:>6}",
+        "COMMAND", "RUNS", "FAILED", "TOTAL", "AVG", "MAX", "REPOS"
+    );
+    for entry in &summary {
+        println!(
+            "{:<20} {:>6} {:>8} {:>10} {:>10} {:>10} {:>6.1}",
+            entry.command,
+            entry.runs,
+            entry.failures,
+            format_duration_ms(entry.total_ms),
+            format_duration_ms(entry.avg_ms()),
+            format_duration_ms(entry.max_ms),
+            entry.avg_repos()
+        );
+    }
+    let mut failures: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in &summary {
+        for (category, count) in &entry.failure_categories {
+            *failures.entry(category.as_str()).or_default() += count;
         }
-        if let Some(filter) = args.filter.as_deref() {
-            command = apply_test_filter(&command, &repo, filter);
+    }
+    if !failures.is_empty() {
+        println!();
+        println!("Failures by category:");
+        for (category, count) in failures {
+            println!("  {:<12} {}", category, count);
         }
-        commands.push(QualityCommand { repo, command });
     }
+    Ok(())
+}
 
-    if commands.is_empty() {
-        output::info("no repos selected for test");
-        return Ok(());
+fn format_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000)
     }
+}
 
-    if args.graph_order && args.parallel.unwrap_or(1) > 1 {
-        output::warn("graph-order test execution is sequential; ignoring --parallel > 1");
+fn handle_state(
+    args: StateArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    match args.command {
+        StateCommand::Export(export) => handle_state_export(export, workspace_root, config_path),
+        StateCommand::Import(import) => handle_state_import(import, workspace_root, config_path),
     }
+}
 
-    let sequential = args.graph_order || args.fail_fast;
-    if sequential {
-        for command in commands {
-            run_quality_command(QualityKind::Test, command)?;
+fn handle_template(
+    args: TemplateArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    match args.command {
+        TemplateCommand::Render(render) => {
+            handle_template_render(render, workspace_root, config_path)
         }
-        return Ok(());
     }
+}
 
-    let jobs = resolve_parallel(args.parallel);
-    let results = parallel::run_in_parallel(commands, jobs, |command| {
-        run_quality_command(QualityKind::Test, command)
-    });
-    for result in results {
-        result?;
+fn handle_template_render(
+    args: TemplateRenderArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let kind = args.kind.trim().to_ascii_lowercase();
+    let schema = match kind.as_str() {
+        "mr" => sample_mr_template_context(),
+        "issue" => sample_issue_template_context(),
+        other => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown template kind '{}' (expected mr or issue)",
+                other
+            ))))
+        }
+    };
+    let path = match args.path {
+        Some(path) => path,
+        None => {
+            let workspace = load_workspace(workspace_root, config_path)?;
+            let mr = workspace.config.mr.as_ref();
+            let configured = if kind == "issue" {
+                mr.and_then(|config| config.issue_template.as_deref())
+            } else {
+                mr.and_then(|config| config.template.as_deref())
+            };
+            let Some(configured) = configured else {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "no [mr].{} configured; pass a template path",
+                    if kind == "issue" {
+                        "issue_template"
+                    } else {
+                        "template"
+                    }
+                ))));
+            };
+            resolve_template_path(&workspace, configured)
+        }
+    };
+    let template = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read template {}", path.display()))?;
+
+    let unknown = unknown_template_variables(&template, &schema).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} is not a valid template: {}",
+            path.display(),
+            err
+        )))
+    })?;
+    if !unknown.is_empty() {
+        for variable in &unknown {
+            output::error(&format!(
+                "{} uses `{}`, which is not in the {} template context",
+                path.display(),
+                variable,
+                kind
+            ));
+        }
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} references {} unknown variable(s)",
+            path.display(),
+            unknown.len()
+        ))));
     }
 
+    let context = match args.context.as_ref() {
+        Some(context_path) => {
+            let raw = fs::read_to_string(context_path)?;
+            serde_json::from_str::<serde_json::Value>(&raw).map_err(|err| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "failed to parse {}: {}",
+                    context_path.display(),
+                    err
+                )))
+            })?
+        }
+        None => schema,
+    };
+    let rendered = render_template(&template, &context).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to render {}: {}",
+            path.display(),
+            err
+        )))
+    })?;
+    if args.check {
+        output::info(&format!("{} is valid", path.display()));
+    } else {
+        print!("{}", rendered);
+    }
     Ok(())
 }
 
-fn handle_lint(
-    args: LintArgs,
+fn handle_state_export(
+    args: StateExportArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
+    let cwd = env::current_dir()?;
+    let resolved =
+        resolve_workspace_with_overrides(cwd.clone(), workspace_root.clone(), config_path.clone())?;
     let workspace = load_workspace(workspace_root, config_path)?;
-    let default_changed = args.repos.is_empty() && !args.all && !args.changed;
-    let changed_scope = args.changed || default_changed;
-    let mut repos = select_repos(
-        &workspace,
-        &args.repos,
-        None,
-        args.all || changed_scope,
-        false,
-    )?;
-    if changed_scope {
-        repos = filter_changed_repos(repos)?;
-    }
-    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
-    if repos.is_empty() {
-        output::info("no repos selected for lint");
-        return Ok(());
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let output_path = match args.output {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => cwd.join(path),
+        None => cwd.join(format!(
+            "harmonia-state-{created_at}.tar.gz{}",
+            if args.encrypt { ".enc" } else { "" }
+        )),
+    };
+    if output_path.exists() && !args.force {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} already exists; pass --force to overwrite it",
+            output_path.display()
+        ))));
     }
+    let passphrase = if args.encrypt {
+        Some(state_passphrase()?)
+    } else {
+        None
+    };
 
-    let mut commands = Vec::new();
-    for repo in repos {
-        if !repo.path.is_dir() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "repo {} not cloned",
-                repo.id.as_str()
-            ))));
-        }
-        let base = match resolve_quality_command(&workspace, &repo, QualityKind::Lint) {
-            Some(command) => command,
-            None => {
-                output::warn(&format!(
-                    "no lint command configured for {}; skipping",
-                    repo.id.as_str()
-                ));
-                continue;
-            }
-        };
-        let mut command = base;
-        if args.fix {
-            let (updated, applied) = apply_lint_fix(&command, &repo);
-            command = updated;
-            if !applied {
-                output::warn(&format!(
-                    "fix requested for {} but command has no automatic --fix mapping; running as-is",
-                    repo.id.as_str()
-                ));
-            }
-        }
-        commands.push(QualityCommand { repo, command });
+    let extra = vec![
+        resolved.config_path,
+        workspace.root.join(changesets_dir(&workspace.config)?),
+    ];
+    let (files, outside) = collect_state_files(&workspace.root, &extra)?;
+    for path in &outside {
+        output::warn(&format!(
+            "skipping {} (outside the workspace root)",
+            path.display()
+        ));
     }
-
-    if commands.is_empty() {
-        output::info("no repos selected for lint");
+    if files.is_empty() {
+        output::info("no workspace state to export");
         return Ok(());
     }
 
-    let jobs = resolve_parallel(args.parallel);
-    let results = parallel::run_in_parallel(commands, jobs, |command| {
-        run_quality_command(QualityKind::Lint, command)
-    });
-    for result in results {
-        result?;
-    }
-
-    Ok(())
-}
-
-#[derive(Clone, Copy)]
-enum QualityKind {
-    Test,
-    Lint,
-}
-
-impl QualityKind {
-    fn as_str(self) -> &'static str {
-        match self {
-            QualityKind::Test => "test",
-            QualityKind::Lint => "lint",
+    let staging = state_staging_dir("export")?;
+    let result = (|| -> Result<()> {
+        let bundle = staging.join("bundle");
+        for file in &files {
+            let target = bundle.join(file);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(workspace.root.join(file), &target)?;
         }
-    }
-}
+        let manifest = StateManifest {
+            format: STATE_FORMAT,
+            created_at,
+            workspace_root: workspace.root.to_string_lossy().to_string(),
+            files: files
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+        };
+        let manifest_json = serde_json::to_string_pretty(&manifest)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        fs::write(bundle.join(STATE_MANIFEST), manifest_json)?;
 
-#[derive(Clone)]
-struct QualityCommand {
-    repo: Repo,
-    command: String,
-}
+        let tarball = staging.join("state.tar.gz");
+        run_command_in_repo(
+            &bundle,
+            &[
+                "tar".to_string(),
+                "-czf".to_string(),
+                tarball.to_string_lossy().to_string(),
+                ".".to_string(),
+            ],
+        )?;
+        match passphrase.as_deref() {
+            Some(passphrase) => {
+                let encrypted = staging.join("state.tar.gz.enc");
+                run_openssl_enc(&staging, &tarball, &encrypted, passphrase, false)?;
+                fs::write(
+                    &output_path,
+                    seal_bundle(&fs::read(&encrypted)?, passphrase),
+                )
+                .map_err(Into::into)
+            }
+            None => fs::copy(&tarball, &output_path)
+                .map(|_| ())
+                .map_err(Into::into),
+        }
+    })();
+    let _ = fs::remove_dir_all(&staging);
+    result?;
 
-fn run_quality_command(kind: QualityKind, item: QualityCommand) -> Result<()> {
     output::info(&format!(
-        "[{}] {}: {}",
-        item.repo.id.as_str(),
-        kind.as_str(),
-        item.command
+        "exported {} state files to {}{}",
+        files.len(),
+        output_path.display(),
+        if args.encrypt { " (encrypted)" } else { "" }
     ));
-    run_shell_command_in_repo(&item.repo.path, &[item.command])
+    Ok(())
 }
 
-fn repos_in_graph_order(workspace: &Workspace, repos: Vec<Repo>) -> Result<Vec<Repo>> {
-    let order =
-        topological_order(&workspace.graph, &workspace.repos).map_err(HarmoniaError::Other)?;
-    let order_index: HashMap<RepoId, usize> = order
-        .into_iter()
-        .enumerate()
-        .map(|(index, id)| (id, index))
-        .collect();
-    let mut repos = repos;
-    repos.sort_by(|a, b| {
-        let a_index = order_index.get(&a.id).copied().unwrap_or(usize::MAX);
-        let b_index = order_index.get(&b.id).copied().unwrap_or(usize::MAX);
-        a_index
-            .cmp(&b_index)
-            .then_with(|| a.id.as_str().cmp(b.id.as_str()))
-    });
-    Ok(repos)
-}
+fn handle_state_import(
+    args: StateImportArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    // Restoring after loss must work without an existing config, so fall back to
+    // the requested or current directory when no workspace can be resolved.
+    let cwd = env::current_dir()?;
+    let root =
+        match resolve_workspace_with_overrides(cwd.clone(), workspace_root.clone(), config_path) {
+            Ok(resolved) => resolved.root,
+            Err(_) => workspace_root.unwrap_or_else(|| cwd.clone()),
+        };
+    let archive = if args.archive.is_absolute() {
+        args.archive.clone()
+    } else {
+        cwd.join(&args.archive)
+    };
+    let mut header = [0u8; 8];
+    let read = {
+        use std::io::Read;
+        fs::File::open(&archive)?.read(&mut header)?
+    };
 
-fn expand_branch_scope(
-    workspace: &Workspace,
-    repos: Vec<Repo>,
-    include_dependents: bool,
-    include_all_deps: bool,
-) -> Vec<Repo> {
-    let mut selected: HashSet<RepoId> = repos.iter().map(|repo| repo.id.clone()).collect();
-    if include_dependents || include_all_deps {
-        for repo in &repos {
-            for dependent in transitive_dependents(&workspace.graph, &workspace.repos, &repo.id) {
-                selected.insert(dependent);
+    let staging = state_staging_dir("import")?;
+    let result = (|| -> Result<(StateManifest, usize)> {
+        let tarball = if is_encrypted_bundle(&header[..read]) {
+            let tarball = staging.join("state.tar.gz");
+            let passphrase = state_passphrase()?;
+            // Check the tag before decrypting so a tampered or wrong-key archive
+            // never reaches tar.
+            let sealed = fs::read(&archive)?;
+            let encrypted = staging.join("state.tar.gz.enc");
+            fs::write(&encrypted, unseal_bundle(&sealed, &passphrase)?)?;
+            run_openssl_enc(&staging, &encrypted, &tarball, &passphrase, true)
+                .map_err(|_| corrupted_bundle())?;
+            tarball
+        } else {
+            archive.clone()
+        };
+        let bundle = staging.join("bundle");
+        fs::create_dir_all(&bundle)?;
+        run_command_in_repo(
+            &bundle,
+            &[
+                "tar".to_string(),
+                "-xzf".to_string(),
+                tarball.to_string_lossy().to_string(),
+            ],
+        )?;
+        let manifest = load_state_manifest(&bundle.join(STATE_MANIFEST))?;
+        let entries = manifest
+            .files
+            .iter()
+            .map(|entry| validate_state_path(entry))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut conflicts = Vec::new();
+        for entry in &entries {
+            let source = bundle.join(entry);
+            if !source.is_file() {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "state bundle is missing {}",
+                    entry.display()
+                ))));
             }
-        }
-    }
-    if include_all_deps {
-        for repo in &repos {
-            for dependency in transitive_dependencies(&workspace.graph, &workspace.repos, &repo.id)
-            {
-                selected.insert(dependency);
+            let target = root.join(entry);
+            if target.is_file() && fs::read(&target)? != fs::read(&source)? {
+                conflicts.push(entry.display().to_string());
             }
         }
-    }
-
-    let mut expanded: Vec<Repo> = selected
-        .into_iter()
-        .filter_map(|id| workspace.repos.get(&id).cloned())
-        .filter(|repo| should_include_repo(repo, false))
-        .collect();
-    expanded.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
-    expanded
-}
-
-fn resolve_quality_command(
-    workspace: &Workspace,
-    repo: &Repo,
-    kind: QualityKind,
-) -> Option<String> {
-    let key = kind.as_str();
-    let repo_override = repo
-        .config
-        .as_ref()
-        .and_then(|config| config.hooks.as_ref())
-        .and_then(|hooks| hooks.custom.as_ref())
-        .and_then(|custom| custom.get(key))
-        .cloned();
-    if repo_override.is_some() {
-        return repo_override;
-    }
+        if !conflicts.is_empty() && !args.force {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "state files differ from the bundle: {}; pass --force to overwrite them",
+                conflicts.join(", ")
+            ))));
+        }
 
-    let workspace_override = workspace
-        .config
-        .hooks
-        .as_ref()
-        .and_then(|hooks| hooks.custom.as_ref())
-        .and_then(|custom| custom.get(key))
-        .cloned();
-    if workspace_override.is_some() {
-        return workspace_override;
-    }
+        for entry in &entries {
+            let target = root.join(entry);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(bundle.join(entry), &target)?;
+        }
+        Ok((manifest, entries.len()))
+    })();
+    let _ = fs::remove_dir_all(&staging);
+    let (manifest, restored) = result?;
 
-    let ecosystem = repo.ecosystem.as_ref()?;
-    let plugin = plugin_for(ecosystem);
-    match kind {
-        QualityKind::Test => plugin.default_test_command().map(|cmd| cmd.to_string()),
-        QualityKind::Lint => plugin.default_lint_command().map(|cmd| cmd.to_string()),
+    let exported_root = PathBuf::from(&manifest.workspace_root);
+    if !manifest.workspace_root.is_empty() && exported_root != root {
+        rewrite_state_paths(&root, &exported_root, &root)?;
     }
+    output::info(&format!(
+        "restored {} state files into {}",
+        restored,
+        root.display()
+    ));
+    Ok(())
 }
 
-fn apply_test_filter(command: &str, repo: &Repo, filter: &str) -> String {
-    let quoted = shell_single_quote(filter);
-    match repo.ecosystem.as_ref() {
-        Some(EcosystemId::Python) => format!("{command} -k {quoted}"),
-        Some(EcosystemId::Go) => format!("{command} -run {quoted}"),
-        Some(EcosystemId::Node) => format!("{command} -- {quoted}"),
-        _ => format!("{command} {quoted}"),
-    }
+fn state_passphrase() -> Result<String> {
+    env::var(STATE_PASSPHRASE_ENV)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "set {} to the bundle passphrase",
+                STATE_PASSPHRASE_ENV
+            )))
+        })
 }
 
-fn apply_test_coverage(command: &str, repo: &Repo) -> (String, bool) {
-    match repo.ecosystem.as_ref() {
-        Some(EcosystemId::Python) if !command.contains("--cov") => {
-            (format!("{command} --cov"), true)
-        }
-        Some(EcosystemId::Go) if !command.contains("-cover") => (format!("{command} -cover"), true),
-        _ => (command.to_string(), false),
-    }
+fn state_staging_dir(kind: &str) -> Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let dir = env::temp_dir().join(format!(
+        "harmonia-state-{kind}-{}-{nanos}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Encrypts or decrypts a bundle with `openssl enc`, passing the passphrase
+/// through the environment so it never shows up in the process list.
+fn run_openssl_enc(
+    cwd: &Path,
+    input: &Path,
+    output_path: &Path,
+    passphrase: &str,
+    decrypt: bool,
+) -> Result<()> {
+    let mut command = vec![
+        "openssl".to_string(),
+        "enc".to_string(),
+        "-aes-256-cbc".to_string(),
+        "-pbkdf2".to_string(),
+        "-salt".to_string(),
+    ];
+    if decrypt {
+        command.push("-d".to_string());
+    }
+    command.extend([
+        "-in".to_string(),
+        input.to_string_lossy().to_string(),
+        "-out".to_string(),
+        output_path.to_string_lossy().to_string(),
+        "-pass".to_string(),
+        format!("env:{STATE_PASSPHRASE_ENV}"),
+    ]);
+    run_command_in_repo_with_env(
+        cwd,
+        &command,
+        &[(STATE_PASSPHRASE_ENV.to_string(), passphrase.to_string())],
+        None,
+    )
 }
 
-fn apply_lint_fix(command: &str, repo: &Repo) -> (String, bool) {
-    if command.contains("--fix") {
-        return (command.to_string(), true);
-    }
-
-    match repo.ecosystem.as_ref() {
-        Some(EcosystemId::Rust) if command.trim_start().starts_with("cargo clippy") => (
-            format!("{command} --fix --allow-dirty --allow-staged"),
-            true,
-        ),
-        Some(EcosystemId::Python) if command.trim_start().starts_with("ruff check") => {
-            (format!("{command} --fix"), true)
+fn handle_snapshot(
+    args: SnapshotArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    match args.command.unwrap_or(SnapshotCommand::List) {
+        SnapshotCommand::List => {
+            let names = list_snapshots(&workspace.root)?;
+            if names.is_empty() {
+                output::info(
+                    "no snapshots recorded; create one with `harmonia snapshot create <name>`",
+                );
+            }
+            for name in names {
+                println!("{}", name);
+            }
+            Ok(())
         }
-        Some(EcosystemId::Go) if command.contains("golangci-lint") => {
-            (format!("{command} --fix"), true)
+        SnapshotCommand::Create(create) => handle_snapshot_create(create, &workspace),
+        SnapshotCommand::Diff(mut diff) => {
+            native_json(&mut diff.json);
+            handle_snapshot_diff(diff, &workspace)
         }
-        Some(EcosystemId::Node) => (format!("{command} -- --fix"), true),
-        _ => (command.to_string(), false),
     }
 }
 
-fn shell_single_quote(value: &str) -> String {
-    let escaped = value.replace('\'', "'\"'\"'");
-    format!("'{escaped}'")
+/// A tag `harmonia release` is about to create.
+struct PlannedRelease {
+    repo: Repo,
+    version: String,
+    tag: String,
+    /// Latest tag reachable from HEAD before this release.
+    previous_tag: Option<String>,
 }
 
-fn handle_plan(
-    args: PlanArgs,
+fn handle_release(
+    args: ReleaseArgs,
     workspace_root: Option<PathBuf>,
     config_path: Option<PathBuf>,
 ) -> Result<()> {
     let workspace = load_workspace(workspace_root, config_path)?;
-    let plan = build_plan_summary(&workspace, &args.include, &args.exclude)?;
-    if args.json {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&plan_to_json(&plan))
-                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
-        );
-    } else {
-        print_plan_summary(&plan);
+    if !args.dry_run {
+        ensure_writable(&workspace, "release")?;
     }
-    Ok(())
-}
-
-fn handle_mr(
-    args: MrArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root, config_path)?;
-    let command = args.command.unwrap_or(MrCommand::Status(MrStatusArgs {
-        json: false,
-        wait: false,
-        timeout: 30,
-    }));
-
-    match command {
-        MrCommand::Create(args) => handle_mr_create(args, &workspace),
-        MrCommand::Status(args) => handle_mr_status(args, &workspace),
-        MrCommand::Update(args) => handle_mr_update(args, &workspace),
-        MrCommand::Merge(args) => handle_mr_merge(args, &workspace),
-        MrCommand::Close(args) => handle_mr_close(args, &workspace),
+    if args.forge_release && workspace.config.forge.is_none() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "--forge-release needs a [forge] section in the workspace config"
+        )));
     }
-}
-
-fn handle_mr_create(args: MrCreateArgs, workspace: &Workspace) -> Result<()> {
-    let mut plan = build_plan_summary(workspace, &[], &[])?;
-    if plan.changed.is_empty() {
-        output::info("no changed repositories detected; nothing to create");
-        return Ok(());
-    }
-
-    let draft = args.draft
+    let tag_format = args
+        .tag_format
+        .clone()
+        .or_else(|| {
+            workspace
+                .config
+                .versioning
+                .as_ref()
+                .and_then(|versioning| versioning.tag_format.clone())
+        })
+        .unwrap_or_else(|| DEFAULT_TAG_FORMAT.to_string());
+    let sign = args.sign
         || workspace
             .config
-            .mr
+            .versioning
             .as_ref()
-            .and_then(|config| config.draft)
+            .and_then(|versioning| versioning.sign_tags)
             .unwrap_or(false);
-    let labels = merged_labels(workspace, &args.labels);
-    let mut ordered = ordered_plan_repos(&plan);
-    let link_behavior = effective_link_behavior(workspace, &args)?;
-    let create_tracking_issue =
-        should_create_tracking_issue(workspace, &args, ordered.len(), link_behavior);
-    let title_override = args.title.clone();
-    let shared_description = args.description.clone().or_else(|| {
-        plan.changeset
-            .as_ref()
-            .map(|changeset| changeset.description.clone())
-    });
 
-    if args.dry_run {
-        println!("MR Create Plan");
-        println!("==============");
-        println!("draft: {}", draft);
-        println!("link related mrs: {}", link_behavior.related);
-        println!("link in description: {}", link_behavior.description);
-        println!("create tracking issue: {}", create_tracking_issue);
-        println!("require tests: {}", mr_require_tests_enabled(workspace));
-        if !labels.is_empty() {
-            println!("labels: {}", labels.join(", "));
+    let explicit = !args.repos.is_empty();
+    let mut repos = select_repos(&workspace, &args.repos, None, !explicit, false)?;
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut planned = Vec::new();
+    let mut dirty = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            if explicit {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "repo {} not cloned",
+                    repo.id.as_str()
+                ))));
+            }
+            continue;
         }
-        if !args.reviewers.is_empty() {
-            println!("reviewers: {}", args.reviewers.join(", "));
+        let Some(version) = read_repo_version(&repo, &workspace)? else {
+            if explicit {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "repo {} has no manifest version to release",
+                    repo.id.as_str()
+                ))));
+            }
+            continue;
+        };
+        let tag = render_tag(&tag_format, repo.id.as_str(), &version.raw);
+        let open = open_repo(&repo.path)?;
+        if tag_exists(&open.repo, &tag)? {
+            if explicit {
+                output::info(&format!("{}: {} already tagged", repo.id.as_str(), tag));
+            }
+            continue;
         }
-        println!("merge order:");
-        for (index, repo_id) in ordered.iter().enumerate() {
-            println!("  {}. {}", index + 1, repo_id.as_str());
+        if !repo_status(&open.repo)?.is_clean() {
+            dirty.push(repo.id.as_str().to_string());
+            continue;
         }
-        return Ok(());
+        let previous_tag = run_command_output_in_repo(
+            &repo.path,
+            &[
+                "git".to_string(),
+                "describe".to_string(),
+                "--tags".to_string(),
+                "--abbrev=0".to_string(),
+            ],
+        )
+        .ok()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty());
+        planned.push(PlannedRelease {
+            repo,
+            version: version.raw,
+            tag,
+            previous_tag,
+        });
     }
-
-    prepare_mr_create_branches(&args, workspace, &mut plan, &mut ordered)?;
-    ensure_mr_branches_are_mergeable(workspace, &plan, &ordered)?;
-
-    if mr_require_tests_enabled(workspace) {
-        run_required_mr_tests(workspace, &ordered)?;
+    if !dirty.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "release tags HEAD, so commit the version bump first; uncommitted changes in: {}",
+            dirty.join(", ")
+        ))));
     }
-
-    let forge = workspace_forge_client(workspace)?;
-    let mut created = Vec::new();
-    let mut state = load_mr_state(workspace)?;
-    let base_title = title_override
-        .or_else(|| {
-            plan.changeset
-                .as_ref()
-                .map(|changeset| changeset.title.clone())
-        })
-        .unwrap_or_else(|| {
-            let branch = plan
-                .changed
-                .first()
-                .map(|repo| repo.branch.clone())
-                .unwrap_or_else(|| "changeset".to_string());
-            format!("changeset: {branch}")
-        });
-
-    for repo_id in ordered.clone() {
-        let plan_repo = plan
-            .changed
-            .iter()
-            .find(|repo| repo.id == repo_id)
-            .ok_or_else(|| {
-                HarmoniaError::Other(anyhow::anyhow!(format!(
-                    "missing plan entry for {}",
-                    repo_id.as_str()
-                )))
-            })?;
-        let repo = workspace.repos.get(&repo_id).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {}",
-                repo_id.as_str()
-            )))
-        })?;
-        let forge_repo = forge_repo_for_repo(workspace, repo);
-        let description = build_mr_description(
-            workspace,
-            &plan,
-            repo,
-            shared_description.as_deref().unwrap_or_default(),
-        )?;
-        let mr = forge.create_mr(
-            &forge_repo,
-            CreateMrParams {
-                title: base_title.clone(),
-                description,
-                source_branch: plan_repo.branch.clone(),
-                target_branch: repo.default_branch.clone(),
-                draft,
-                labels: labels.clone(),
-                reviewers: args.reviewers.clone(),
-            },
-        )?;
-
-        output::info(&format!(
-            "created MR for {}: !{} {}",
-            repo.id.as_str(),
-            mr.iid,
-            mr.url
-        ));
-        let entry = StoredMrEntry {
-            repo: repo.id.as_str().to_string(),
-            forge_repo: forge_repo.as_str().to_string(),
-            branch: plan_repo.branch.clone(),
-            mr_id: mr.iid.to_string(),
-            iid: mr.iid,
-            url: mr.url.clone(),
-            source_branch: mr.source_branch.clone(),
-            target_branch: mr.target_branch.clone(),
-        };
-        upsert_mr_state_entry(&mut state, entry.clone());
-        created.push(entry);
+    if planned.is_empty() {
+        output::info("nothing to release; every selected version is already tagged");
+        return Ok(());
     }
-
-    if link_behavior.related && created.len() > 1 {
-        let links: Vec<(RepoId, String)> = created
+    if sign {
+        // Check every repo up front so a missing key never leaves some repos
+        // tagged and others not.
+        let unsigned: Vec<&str> = planned
             .iter()
-            .map(|entry| (RepoId::new(entry.forge_repo.clone()), entry.mr_id.clone()))
+            .filter(|release| !has_signing_key(&release.repo.path))
+            .map(|release| release.repo.id.as_str())
             .collect();
-        forge.link_mrs(&links)?;
-        output::info("linked merge requests in merge order");
+        if !unsigned.is_empty() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "signed release tags need a signing key; set user.signingkey (git config user.signingkey <key>) for: {}",
+                unsigned.join(", ")
+            ))));
+        }
     }
 
-    if link_behavior.description && created.len() > 1 {
-        for entry in &created {
-            let repo = RepoId::new(entry.forge_repo.clone());
-            let current = forge.get_mr(&repo, &entry.mr_id)?;
-            let updated_description = with_related_mr_links(
-                &current.description,
-                &created,
-                entry.repo.as_str(),
-                plan.changeset
-                    .as_ref()
-                    .map(|changeset| changeset.id.as_str()),
+    if args.dry_run {
+        for release in &planned {
+            println!(
+                "{}: would tag {}{} ({})",
+                release.repo.id.as_str(),
+                release.tag,
+                if sign { " (signed)" } else { "" },
+                release
+                    .previous_tag
+                    .as_deref()
+                    .map(|previous| format!("after {}", previous))
+                    .unwrap_or_else(|| "first tag".to_string())
             );
-            forge.update_mr(
-                &repo,
-                &entry.mr_id,
-                UpdateMrParams {
-                    title: None,
-                    description: Some(updated_description),
-                    labels: None,
-                    reviewers: None,
-                },
-            )?;
         }
-        output::info("updated MR descriptions with related links");
+        return Ok(());
     }
 
-    if create_tracking_issue {
-        if let Some(first) = created.first() {
-            let issue_title = format!("Tracking: {}", base_title);
-            let issue_description = build_tracking_issue_description(
-                workspace,
-                &plan,
-                &created,
-                shared_description.as_deref(),
+    let forge = if args.forge_release {
+        Some(workspace_forge_client(&workspace)?)
+    } else {
+        None
+    };
+    for release in &planned {
+        let repo = &release.repo;
+        let name = format!("{} {}", repo.id.as_str(), release.version);
+        let command = vec![
+            "git".to_string(),
+            "tag".to_string(),
+            if sign { "-s" } else { "-a" }.to_string(),
+            release.tag.clone(),
+            "-m".to_string(),
+            name.clone(),
+        ];
+        log_git_command_for_repo(repo.id.as_str(), &command);
+        run_command_in_repo_quiet(&repo.path, &command)?;
+        println!(
+            "{}: {} {}",
+            repo.id.as_str(),
+            if sign { "signed" } else { "tagged" },
+            release.tag
+        );
+
+        if !args.push {
+            continue;
+        }
+        let open = open_repo(&repo.path)?;
+        let remote = default_push_remote(&open.repo).unwrap_or_else(|| "origin".to_string());
+        let command = vec![
+            "git".to_string(),
+            "push".to_string(),
+            remote.clone(),
+            format!("refs/tags/{}", release.tag),
+        ];
+        log_git_command_for_repo(repo.id.as_str(), &command);
+        run_command_in_repo_quiet(&repo.path, &command)?;
+        println!("{}: pushed {} to {}", repo.id.as_str(), release.tag, remote);
+
+        if let Some(forge) = forge.as_ref() {
+            let published = forge.create_release(
+                &forge_repo_for_repo(&workspace, repo),
+                CreateReleaseParams {
+                    tag: release.tag.clone(),
+                    name,
+                    notes: release_notes(&repo.path, &release.tag, release.previous_tag.as_deref()),
+                    target: None,
+                },
             )?;
-            let issue = forge.create_issue(CreateIssueParams {
-                project: Some(RepoId::new(first.forge_repo.clone())),
-                title: issue_title,
-                description: issue_description,
-                labels: labels.clone(),
-            })?;
-            output::info(&format!(
-                "created tracking issue #{} {}",
-                issue.iid, issue.url
-            ));
+            println!("{}: released {}", repo.id.as_str(), published.url);
         }
     }
+    Ok(())
+}
 
-    if mr_add_trailers_enabled(workspace) {
-        let changeset_id = plan
-            .changeset
-            .as_ref()
-            .map(|changeset| changeset.id.as_str())
-            .unwrap_or("local-changeset");
-        output::warn(&format!(
-            "mr.add_trailers is enabled; add commit trailers manually (e.g. Changeset-ID: {}) before merge",
-            changeset_id
-        ));
-    }
+const DEFAULT_TAG_FORMAT: &str = "v{version}";
 
-    save_mr_state(workspace, &state)?;
-    run_post_mr_create_hook(workspace)?;
-    Ok(())
+/// Whether git has a `user.signingkey` to sign tags with in `repo_path`.
+fn has_signing_key(repo_path: &Path) -> bool {
+    run_command_output_in_repo(
+        repo_path,
+        &[
+            "git".to_string(),
+            "config".to_string(),
+            "--get".to_string(),
+            "user.signingkey".to_string(),
+        ],
+    )
+    .is_ok_and(|key| !key.trim().is_empty())
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct MrBranchConflict {
-    repo: RepoId,
-    source_branch: String,
-    target_branch: String,
+fn render_tag(format: &str, repo: &str, version: &str) -> String {
+    format.replace("{version}", version).replace("{repo}", repo)
 }
 
-fn prepare_mr_create_branches(
-    args: &MrCreateArgs,
-    workspace: &Workspace,
-    plan: &mut PlanSummary,
-    ordered: &mut Vec<RepoId>,
-) -> Result<()> {
-    let conflicts = collect_mr_branch_conflicts(workspace, plan, ordered)?;
-    if conflicts.is_empty() {
-        return Ok(());
-    }
+/// The changelog section for `tag`, built from the commits since
+/// `previous_tag`.
+fn release_notes(repo_path: &Path, tag: &str, previous_tag: Option<&str>) -> String {
+    let subjects: Vec<String> = match previous_tag {
+        Some(previous_tag) => run_command_output_in_repo(
+            repo_path,
+            &[
+                "git".to_string(),
+                "log".to_string(),
+                "--no-merges".to_string(),
+                "--format=%s".to_string(),
+                format!("{}..HEAD", previous_tag),
+            ],
+        )
+        .map(|log| log.lines().map(str::to_string).collect())
+        .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    render_changelog_section(tag, &today_date(), &subjects)
+}
 
-    let branch_name = resolve_mr_auto_branch_name(args, plan)?;
-    let mut should_auto_branch = args.auto_branch || args.branch_name.is_some();
-    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
-    if !should_auto_branch && interactive {
-        let prompt = format!(
-            "{} repositories are on their default branch for MR creation. create and checkout '{}' in those repositories now?",
-            conflicts.len(),
-            branch_name
-        );
-        should_auto_branch = output::confirm(&prompt, false)
-            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-    }
+/// Changelog groups, in the order they are rendered.
+const CHANGELOG_GROUPS: &[&str] = &["Breaking changes", "Features", "Fixes", "Other changes"];
 
-    if !should_auto_branch {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            format_mr_branch_conflict_error(&conflicts, Some(branch_name.as_str()))
-        )));
+/// A markdown changelog section: a `## <tag> (<date>)` header followed by the
+/// commit subjects grouped by their conventional-commit type. A release
+/// without earlier commits to list is noted as the initial release.
+fn render_changelog_section(tag: &str, date: &str, subjects: &[String]) -> String {
+    let mut section = format!("## {tag} ({date})\n");
+    if subjects.is_empty() {
+        section.push_str("\nInitial release.\n");
+        return section;
+    }
+    let mut groups: Vec<Vec<String>> = vec![Vec::new(); CHANGELOG_GROUPS.len()];
+    for subject in subjects {
+        let (group, entry) = changelog_entry(subject.trim());
+        groups[group].push(entry);
     }
+    for (title, entries) in CHANGELOG_GROUPS.iter().zip(groups) {
+        if entries.is_empty() {
+            continue;
+        }
+        section.push_str(&format!("\n### {title}\n\n"));
+        for entry in entries {
+            section.push_str(&format!("- {entry}\n"));
+        }
+    }
+    section
+}
 
-    auto_branch_conflicted_mr_repos(workspace, &conflicts, branch_name.as_str())?;
-    output::info(&format!(
-        "auto-branch switched {} repositories to {}",
-        conflicts.len(),
-        branch_name
-    ));
-
-    *plan = build_plan_summary(workspace, &[], &[])?;
-    *ordered = ordered_plan_repos(plan);
-    Ok(())
-}
-
-fn resolve_mr_auto_branch_name(args: &MrCreateArgs, plan: &PlanSummary) -> Result<String> {
-    if let Some(branch_name) = args.branch_name.as_ref() {
-        let trimmed = branch_name.trim();
-        if trimmed.is_empty() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(
-                "--branch-name cannot be empty"
-            )));
-        }
-        return Ok(trimmed.to_string());
+/// Index into [`CHANGELOG_GROUPS`] and the entry text for one commit subject.
+/// `type(scope)!: description` becomes `scope: description`; subjects that
+/// are not conventional commits are kept as written under "Other changes".
+fn changelog_entry(subject: &str) -> (usize, String) {
+    let Some((prefix, description)) = subject.split_once(": ") else {
+        return (3, subject.to_string());
+    };
+    let breaking = prefix.ends_with('!');
+    let prefix = prefix.trim_end_matches('!');
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, scope.strip_suffix(')')),
+        None => (prefix, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return (3, subject.to_string());
     }
+    let entry = match scope {
+        Some(scope) if !scope.is_empty() => format!("{scope}: {description}"),
+        _ => description.to_string(),
+    };
+    let group = if breaking {
+        0
+    } else {
+        match kind.to_ascii_lowercase().as_str() {
+            "feat" => 1,
+            "fix" => 2,
+            _ => 3,
+        }
+    };
+    (group, entry)
+}
 
-    if let Some(changeset) = plan.changeset.as_ref() {
-        let trimmed = changeset.branch.trim();
-        if !trimmed.is_empty() {
-            return Ok(trimmed.to_string());
+fn handle_lock(
+    args: LockArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    if args.restore {
+        return restore_lock(&workspace, &args.repos);
+    }
+
+    let explicit = !args.repos.is_empty();
+    let repos = select_repos(&workspace, &args.repos, None, true, false)?;
+    let mut lockfile = Lockfile {
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+        repos: Default::default(),
+    };
+    for repo in repos {
+        if !repo.path.is_dir() {
+            if explicit {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "repo {} not cloned",
+                    repo.id.as_str()
+                ))));
+            }
+            continue;
         }
+        let open = open_repo(&repo.path)?;
+        let branch = current_branch(&open.repo)?;
+        lockfile.repos.insert(
+            repo.id.as_str().to_string(),
+            LockedRepo {
+                commit: head_commit(&open.repo)?,
+                branch: if branch == "HEAD" {
+                    String::new()
+                } else {
+                    branch
+                },
+            },
+        );
     }
 
-    Ok(generated_mr_auto_branch_name())
+    let path = save_lockfile(&workspace.root, &lockfile)?;
+    output::info(&format!(
+        "locked {} repos in {}",
+        lockfile.repos.len(),
+        path.display()
+    ));
+    Ok(())
 }
 
-fn generated_mr_auto_branch_name() -> String {
-    let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
-        Ok(duration) => duration.as_secs(),
-        Err(_) => 0,
-    };
-    format!("feature/harmonia-{timestamp}")
-}
+/// `lock --restore`: checks each locked repo out at its recorded commit,
+/// on its recorded branch when that branch still points there.
+fn restore_lock(workspace: &Workspace, names: &[String]) -> Result<()> {
+    ensure_writable(workspace, "lock --restore")?;
+    let lockfile = load_lockfile(&workspace.root)?;
+    for name in names {
+        if !lockfile.repos.contains_key(name) {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo {} is not in the lockfile",
+                name
+            ))));
+        }
+    }
 
-fn auto_branch_conflicted_mr_repos(
-    workspace: &Workspace,
-    conflicts: &[MrBranchConflict],
-    branch_name: &str,
-) -> Result<()> {
-    let mut existing = Vec::new();
-    for conflict in conflicts {
-        let repo = workspace.repos.get(&conflict.repo).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {}",
-                conflict.repo.as_str()
-            )))
-        })?;
+    let mut failed = Vec::new();
+    for (name, locked) in &lockfile.repos {
+        if !names.is_empty() && !names.contains(name) {
+            continue;
+        }
+        let Some(repo) = workspace.repos.get(&RepoId::new(name.clone())) else {
+            output::warn(&format!("{}: not in workspace config; skipped", name));
+            continue;
+        };
+        if !repo.path.is_dir() {
+            output::warn(&format!("{}: not cloned; skipped", name));
+            continue;
+        }
+        let short = &locked.commit[..locked.commit.len().min(8)];
         let open = open_repo(&repo.path)?;
-        if branch_exists(&open.repo, branch_name)? {
-            existing.push(repo.id.clone());
+        let branch = current_branch(&open.repo)?;
+        let on_branch = locked.branch.is_empty() || branch == locked.branch || branch == "HEAD";
+        if head_commit(&open.repo)? == locked.commit && on_branch {
+            println!("{}: already at {}", name, short);
+            continue;
+        }
+        let branch = Some(locked.branch.as_str()).filter(|branch| !branch.is_empty());
+        match checkout_commit(&open.repo, &locked.commit, branch) {
+            Ok(true) => println!("{}: {} ({})", name, short, locked.branch),
+            Ok(false) => println!("{}: {} (detached)", name, short),
+            Err(err) => {
+                output::error(&format!("{}: {}", name, err));
+                failed.push(name.clone());
+            }
         }
     }
 
-    if !existing.is_empty() {
-        let names = existing
-            .iter()
-            .map(|repo| repo.as_str())
-            .collect::<Vec<_>>()
-            .join(", ");
+    if !failed.is_empty() {
         return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-            "cannot auto-create branch '{}' because it already exists in: {}. choose a different --branch-name or switch branches manually",
-            branch_name, names
+            "lock restore failed in: {}",
+            failed.join(", ")
+        ))));
+    }
+    Ok(())
+}
+
+fn handle_snapshot_create(args: SnapshotCreateArgs, workspace: &Workspace) -> Result<()> {
+    let path = snapshot_path(&workspace.root, &args.name)?;
+    if path.exists() && !args.force {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "snapshot '{}' already exists; pass --force to overwrite it",
+            args.name
         ))));
     }
 
-    for conflict in conflicts {
-        let repo = workspace.repos.get(&conflict.repo).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {}",
-                conflict.repo.as_str()
-            )))
-        })?;
+    let explicit = !args.repos.is_empty();
+    let mut repos = select_repos(workspace, &args.repos, None, true, false)?;
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut snapshot = Snapshot {
+        name: args.name.trim().to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+        repos: Default::default(),
+    };
+    for repo in repos {
+        if !repo.path.is_dir() {
+            if explicit {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "repo {} not cloned",
+                    repo.id.as_str()
+                ))));
+            }
+            continue;
+        }
         let open = open_repo(&repo.path)?;
-        output::git_op(&format!(
-            "checkout -b {} (repo {})",
-            branch_name,
-            repo.id.as_str()
-        ));
-        create_and_checkout_branch(&open.repo, branch_name).map_err(|err| {
-            HarmoniaError::Other(anyhow::anyhow!(format!("{}: {}", repo.id.as_str(), err)))
-        })?;
-        output::info(&format!(
-            "created and checked out '{}' in {}",
-            branch_name,
-            repo.id.as_str()
-        ));
+        let mut lockfiles = std::collections::BTreeMap::new();
+        if args.lockfiles {
+            for name in SNAPSHOT_LOCKFILES {
+                if repo.path.join(name).is_file() {
+                    lockfiles.insert(name.to_string(), worktree_blob_id(&open.repo, name)?);
+                }
+            }
+        }
+        snapshot.repos.insert(
+            repo.id.as_str().to_string(),
+            SnapshotRepo {
+                commit: head_commit(&open.repo)?,
+                branch: current_branch(&open.repo)?,
+                version: read_repo_version(&repo, workspace)?.map(|version| version.raw),
+                lockfiles,
+            },
+        );
     }
 
+    let path = save_snapshot(&workspace.root, &snapshot)?;
+    output::info(&format!(
+        "recorded snapshot '{}' ({} repos) at {}",
+        snapshot.name,
+        snapshot.repos.len(),
+        path.display()
+    ));
     Ok(())
 }
 
-fn ensure_mr_branches_are_mergeable(
-    workspace: &Workspace,
-    plan: &PlanSummary,
-    ordered: &[RepoId],
-) -> Result<()> {
-    let conflicts = collect_mr_branch_conflicts(workspace, plan, ordered)?;
-    if conflicts.is_empty() {
+fn handle_snapshot_diff(args: SnapshotDiffArgs, workspace: &Workspace) -> Result<()> {
+    let from = load_snapshot(&workspace.root, &args.from)?;
+    let to = load_snapshot(&workspace.root, &args.to)?;
+    let diff = diff_snapshots(&from, &to);
+
+    if args.json {
+        let rows: Vec<serde_json::Value> = diff
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "repo": entry.repo,
+                    "from": entry.before,
+                    "to": entry.after,
+                    "lockfiles_changed": entry.changed_lockfiles(),
+                })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&rows)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        println!("{}", json);
         return Ok(());
     }
 
-    Err(HarmoniaError::Other(anyhow::anyhow!(
-        format_mr_branch_conflict_error(&conflicts, None)
-    )))
+    println!("Snapshot diff: {} -> {}", from.name, to.name);
+    if diff.is_empty() {
+        println!("  no differences");
+        return Ok(());
+    }
+    for entry in &diff {
+        match (entry.before.as_ref(), entry.after.as_ref()) {
+            (Some(before), Some(after)) => println!(
+                "  {}: {} -> {} ({} -> {})",
+                entry.repo,
+                before.version.as_deref().unwrap_or("-"),
+                after.version.as_deref().unwrap_or("-"),
+                short_commit(&before.commit),
+                short_commit(&after.commit)
+            ),
+            (None, Some(after)) => println!(
+                "  {}: added ({} @ {})",
+                entry.repo,
+                after.version.as_deref().unwrap_or("-"),
+                short_commit(&after.commit)
+            ),
+            (Some(before), None) => println!(
+                "  {}: removed (was {} @ {})",
+                entry.repo,
+                before.version.as_deref().unwrap_or("-"),
+                short_commit(&before.commit)
+            ),
+            (None, None) => {}
+        }
+        for lockfile in entry.changed_lockfiles() {
+            println!("    lockfile changed: {}", lockfile);
+        }
+    }
+    Ok(())
 }
 
-fn collect_mr_branch_conflicts(
-    workspace: &Workspace,
-    plan: &PlanSummary,
-    ordered: &[RepoId],
-) -> Result<Vec<MrBranchConflict>> {
-    let mut conflicts = Vec::new();
-    for repo_id in ordered {
-        let Some(plan_repo) = plan.changed.iter().find(|repo| &repo.id == repo_id) else {
-            continue;
-        };
-        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {}",
-                repo_id.as_str()
-            )))
-        })?;
+fn short_commit(commit: &str) -> &str {
+    commit.get(..8).unwrap_or(commit)
+}
 
-        if plan_repo.branch == repo.default_branch {
-            conflicts.push(MrBranchConflict {
-                repo: repo.id.clone(),
-                source_branch: plan_repo.branch.clone(),
-                target_branch: repo.default_branch.clone(),
-            });
+fn handle_deps(
+    args: DepsArgs,
+    state: &WorkspaceState,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = state.workspace;
+    let command = args
+        .command
+        .unwrap_or(DepsCommand::Show(DepsShowArgs { json: false }));
+
+    match command {
+        DepsCommand::Show(mut show) => {
+            native_json(&mut show.json);
+            handle_deps_show(show, state)
+        }
+        DepsCommand::Check(mut check) => {
+            native_json(&mut check.json);
+            handle_deps_check(check, state)
+        }
+        DepsCommand::Update(update) => {
+            if !update.dry_run {
+                ensure_writable(workspace, "deps update")?;
+            }
+            handle_deps_update(update, state)
+        }
+        DepsCommand::BumpExternal(bump) => {
+            handle_deps_bump_external(bump, state, workspace_root, config_path)
         }
     }
-
-    Ok(conflicts)
 }
 
-fn format_mr_branch_conflict_error(
-    conflicts: &[MrBranchConflict],
-    suggested_auto_branch: Option<&str>,
-) -> String {
-    let mut message =
-        "cannot create merge requests where source and target branches are the same".to_string();
-    for conflict in conflicts {
-        message.push_str(&format!(
-            "\n  - {} (source: {}, target: {})",
-            conflict.repo.as_str(),
-            conflict.source_branch,
-            conflict.target_branch
-        ));
+fn handle_edit(
+    args: EditArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    if args.all && !args.repos.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "--all cannot be combined with explicit repos"
+        )));
     }
-    message.push_str("\ncreate or switch to a feature branch before running `harmonia mr create`");
-    message.push_str("\nhelper: re-run with `harmonia mr create --auto-branch` to create feature branches automatically for affected repos");
-    if let Some(branch) = suggested_auto_branch {
-        message.push_str(&format!(
-            "\ndefault auto-branch name for this run: {}",
-            branch
-        ));
+
+    let workspace = load_workspace(workspace_root, config_path)?;
+    if args.files {
+        return handle_edit_files(&workspace, args);
     }
-    message.push_str("\nexample: harmonia branch <feature-name> --create --changed");
-    message
-}
+    let targets: Vec<PathBuf> = if !args.repos.is_empty() {
+        select_repos(&workspace, &args.repos, None, false, false)?
+            .into_iter()
+            .map(|repo| repo.path)
+            .collect()
+    } else if args.all {
+        let repos = select_repos(&workspace, &[], None, true, false)?;
+        filter_changed_repos(&workspace, repos)?
+            .into_iter()
+            .map(|repo| repo.path)
+            .collect()
+    } else {
+        vec![workspace.root.clone()]
+    };
 
-fn handle_mr_status(args: MrStatusArgs, workspace: &Workspace) -> Result<()> {
-    let store = load_mr_state(workspace)?;
-    let tracked = tracked_mrs_for_current_branches(workspace, &store)?;
-    if tracked.is_empty() {
-        let plan = build_plan_summary(workspace, &[], &[])?;
-        if args.json {
-            let payload = serde_json::json!({
-                "tracked_mrs": [],
-                "changed_repos": plan.changed.iter().map(|repo| repo.id.as_str()).collect::<Vec<_>>(),
-                "merge_order": plan.merge_order.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
-                "wait": args.wait,
-                "timeout_minutes": args.timeout,
-            });
-            println!(
-                "{}",
-                serde_json::to_string_pretty(&payload)
-                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
-            );
-        } else {
-            println!("MR Preview");
-            println!("==========");
-            if plan.changed.is_empty() {
-                println!("No changed repositories detected.");
-            } else {
-                println!("Repositories with local changes:");
-                for repo in &plan.changed {
-                    println!("  {}", repo.id.as_str());
-                }
-                println!();
-                println!("Suggested merge order:");
-                for (index, repo) in plan.merge_order.iter().enumerate() {
-                    println!("  {}. {}", index + 1, repo.as_str());
-                }
-            }
-            output::info("no tracked MRs found for current branches");
-        }
+    if targets.is_empty() {
+        output::info("no paths selected for edit");
         return Ok(());
     }
 
-    let forge = match workspace_forge_client(workspace) {
-        Ok(forge) => forge,
-        Err(err) => {
-            if args.wait {
-                return Err(err);
-            }
-            if args.json {
-                let payload = serde_json::json!({
-                    "tracked_mrs": tracked.iter().map(|item| {
-                        serde_json::json!({
-                            "repo": item.repo.id.as_str(),
-                            "branch": item.entry.branch.as_str(),
-                            "mr_iid": item.entry.iid,
-                            "url": item.entry.url.as_str(),
-                            "state": "unknown",
-                            "ci_state": serde_json::Value::Null,
-                        })
-                    }).collect::<Vec<_>>(),
-                    "wait": false,
-                    "timeout_minutes": args.timeout,
-                    "note": "forge config missing; remote status unavailable",
-                });
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&payload)
-                        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
-                );
-            } else {
-                output::warn("forge config missing; showing tracked MR metadata only");
-                for item in tracked {
-                    println!(
-                        "{}: !{} ({})",
-                        item.repo.id.as_str(),
-                        item.entry.iid,
-                        item.entry.url
-                    );
-                }
-            }
-            return Ok(());
-        }
-    };
+    let mut command = resolve_editor_command(args.editor.as_deref())?;
+    for target in targets {
+        command.push(target.to_string_lossy().to_string());
+    }
+    run_command_in_repo(&workspace.root, &command)
+}
 
-    let deadline = Instant::now()
-        .checked_add(Duration::from_secs(args.timeout.saturating_mul(60)))
-        .unwrap_or_else(Instant::now);
-    let mut timed_out = false;
-    let rows = loop {
-        let rows = collect_mr_status_rows(forge.as_ref(), &tracked)?;
-        let waiting = rows.iter().any(|row| {
-            matches!(
-                row.ci_state,
-                Some(CiState::Pending) | Some(CiState::Running)
-            ) || !row.missing_required_checks.is_empty()
-        });
-        let has_failed_required = rows
-            .iter()
-            .any(|row| !row.failed_required_checks.is_empty());
-        if has_failed_required {
-            break rows;
-        }
-        if !args.wait || !waiting {
-            break rows;
-        }
-        if Instant::now() >= deadline {
-            timed_out = true;
-            break rows;
-        }
-        std::thread::sleep(Duration::from_secs(5));
+fn handle_edit_files(workspace: &Workspace, args: EditArgs) -> Result<()> {
+    let repos = if args.repos.is_empty() {
+        filter_changed_repos(workspace, select_repos(workspace, &[], None, true, false)?)?
+    } else {
+        select_repos(workspace, &args.repos, None, false, false)?
     };
+    let include_untracked = include_untracked_by_default(workspace);
 
-    if args.json {
-        let payload = serde_json::json!({
-            "tracked_mrs": rows.iter().map(mr_status_row_to_json).collect::<Vec<_>>(),
-            "wait": args.wait,
-            "timeout_minutes": args.timeout,
-            "timed_out": timed_out,
-        });
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&payload)
-                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
-        );
-    } else {
-        println!("Merge Request Status");
-        println!("====================");
-        for row in &rows {
-            let ci_state = row
-                .ci_state
-                .as_ref()
-                .map(ci_state_label)
-                .unwrap_or("unknown");
-            println!(
-                "{}: !{} {} (state: {}, ci: {}, approvals: {})",
-                row.repo.as_str(),
-                row.iid,
-                row.url,
-                mr_state_label(&row.state),
-                ci_state,
-                row.approvals.join(", ")
-            );
-            if !row.missing_required_checks.is_empty() {
-                println!(
-                    "  waiting required checks: {}",
-                    row.missing_required_checks.join(", ")
-                );
-            }
-            if !row.failed_required_checks.is_empty() {
-                println!(
-                    "  failed required checks: {}",
-                    row.failed_required_checks.join(", ")
-                );
-            }
+    let mut files = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            continue;
         }
-        if timed_out {
-            output::warn("timed out while waiting for CI to settle");
+        for (file, line) in changed_file_lines(&repo.path, repo.id.as_str(), include_untracked)? {
+            files.push((repo.path.join(file), line));
         }
     }
-
-    if args.wait
-        && rows
-            .iter()
-            .any(|row| !row.failed_required_checks.is_empty())
-    {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "one or more required checks failed"
-        )));
+    if files.is_empty() {
+        output::info("no changed files to edit");
+        return Ok(());
     }
 
-    if timed_out {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "timeout waiting for CI to complete"
-        )));
+    let editor = resolve_editor_command(args.editor.as_deref())?;
+    let template = args.editor_args.or_else(|| {
+        workspace
+            .config
+            .defaults
+            .as_ref()
+            .and_then(|defaults| defaults.editor_args.clone())
+    });
+    let batch_size = args.batch_size.unwrap_or(files.len()).max(1);
+    for batch in files.chunks(batch_size) {
+        let command = render_editor_invocation(&editor, template.as_deref(), batch);
+        run_command_in_repo(&workspace.root, &command)?;
     }
-
     Ok(())
 }
 
-fn handle_mr_update(args: MrUpdateArgs, workspace: &Workspace) -> Result<()> {
-    if args.description.is_none() && args.labels.is_empty() {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "mr update requires --description and/or --labels"
-        )));
-    }
+/// Builds one editor command line for `files`. Template tokens containing
+/// `{file}` or `{line}` are repeated once per file; `{editor}` expands to the
+/// editor command. Without a template the files are appended to the editor.
+fn render_editor_invocation(
+    editor: &[String],
+    template: Option<&str>,
+    files: &[(PathBuf, usize)],
+) -> Vec<String> {
+    let Some(template) = template else {
+        let mut command = editor.to_vec();
+        command.extend(
+            files
+                .iter()
+                .map(|(file, _)| file.to_string_lossy().to_string()),
+        );
+        return command;
+    };
 
-    let store = load_mr_state(workspace)?;
-    let tracked = tracked_mrs_for_current_branches(workspace, &store)?;
-    if tracked.is_empty() {
-        output::info("no tracked MRs found for current branches");
-        return Ok(());
+    let mut command = Vec::new();
+    for token in split_command(template) {
+        if token == "{editor}" {
+            command.extend(editor.iter().cloned());
+        } else if token.contains("{file}") || token.contains("{line}") {
+            command.extend(files.iter().map(|(file, line)| {
+                token
+                    .replace("{file}", &file.to_string_lossy())
+                    .replace("{line}", &line.to_string())
+            }));
+        } else {
+            command.push(token.replace("{editor}", &editor.join(" ")));
+        }
     }
-    let forge = workspace_forge_client(workspace)?;
+    command
+}
 
-    for item in tracked {
-        let params = UpdateMrParams {
-            title: None,
-            description: args.description.clone(),
-            labels: if args.labels.is_empty() {
-                None
-            } else {
-                Some(args.labels.clone())
-            },
-            reviewers: None,
-        };
-        let updated = forge.update_mr(&item.forge_repo, &item.entry.mr_id, params)?;
-        output::info(&format!(
-            "updated MR for {}: !{}",
-            item.repo.id.as_str(),
-            updated.iid
-        ));
+/// Changed files relative to the repo root, each with the first changed line
+/// in the working tree (1 for untracked files and changes without hunks).
+fn changed_file_lines(
+    repo_path: &Path,
+    repo_name: &str,
+    include_untracked: bool,
+) -> Result<Vec<(String, usize)>> {
+    let cmd = vec![
+        "git".to_string(),
+        "diff".to_string(),
+        "HEAD".to_string(),
+        "-U0".to_string(),
+        "--no-color".to_string(),
+        "--no-ext-diff".to_string(),
+    ];
+    log_git_command_for_repo(repo_name, &cmd);
+    let mut files = parse_first_changed_lines(&run_command_output_in_repo(repo_path, &cmd)?);
+
+    if include_untracked {
+        let untracked_cmd = vec![
+            "git".to_string(),
+            "ls-files".to_string(),
+            "--others".to_string(),
+            "--exclude-standard".to_string(),
+        ];
+        log_git_command_for_repo(repo_name, &untracked_cmd);
+        let untracked = run_command_output_in_repo(repo_path, &untracked_cmd)?;
+        files.extend(
+            untracked
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| (line.to_string(), 1)),
+        );
     }
 
-    Ok(())
+    files.sort();
+    files.dedup_by(|left, right| left.0 == right.0);
+    Ok(files)
 }
 
-fn handle_mr_merge(args: MrMergeArgs, workspace: &Workspace) -> Result<()> {
-    let store = load_mr_state(workspace)?;
-    let tracked = tracked_mrs_for_current_branches(workspace, &store)?;
-    if tracked.is_empty() {
-        output::info("no tracked MRs found for current branches");
-        return Ok(());
-    }
-    let forge = workspace_forge_client(workspace)?;
-
-    let ordered = tracked_mrs_in_merge_order(workspace, tracked)?;
-    if args.dry_run {
-        println!("MR Merge Plan");
-        println!("=============");
-        for (index, item) in ordered.iter().enumerate() {
-            println!(
-                "  {}. {} (!{})",
-                index + 1,
-                item.repo.id.as_str(),
-                item.entry.iid
-            );
+/// Parses `git diff -U0` output into `(path, first changed new-side line)`.
+/// Deleted files are skipped.
+fn parse_first_changed_lines(diff: &str) -> Vec<(String, usize)> {
+    let mut files: Vec<(String, usize)> = Vec::new();
+    let mut current: Option<usize> = None;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            current = None;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(|path| {
+                files.push((path.to_string(), 0));
+                files.len() - 1
+            });
+        } else if let (Some(hunk), Some(index)) = (line.strip_prefix("@@ "), current) {
+            if files[index].1 != 0 {
+                continue;
+            }
+            let start = hunk
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse::<usize>().ok())
+                .unwrap_or(1);
+            files[index].1 = start;
         }
-        return Ok(());
     }
-
-    if !output::confirm("merge tracked MRs in dependency order?", args.yes)
-        .map_err(|err| HarmoniaError::Other(anyhow::anyhow!(err.to_string())))?
-    {
-        output::info("merge cancelled");
-        return Ok(());
+    for file in &mut files {
+        file.1 = file.1.max(1);
     }
+    files
+}
 
-    for item in ordered {
-        let mr = forge.get_mr(&item.forge_repo, &item.entry.mr_id)?;
-        if mr.state == MrState::Merged {
-            output::info(&format!(
-                "MR for {} is already merged; skipping",
-                item.repo.id.as_str()
-            ));
+fn handle_clean(
+    args: CleanArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    ensure_writable(&workspace, "clean")?;
+    let mut repos = if args.repos.is_empty() {
+        select_repos(&workspace, &[], None, true, false)?
+    } else {
+        select_repos(&workspace, &args.repos, None, false, false)?
+    };
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    if repos.is_empty() {
+        output::info("no repos selected for clean");
+        return Ok(());
+    }
+    if !args.force {
+        output::info("clean is running in dry-run mode; pass --force to delete files");
+    }
+
+    let multi = repos.len() > 1;
+    for repo in repos {
+        if !repo.path.is_dir() {
+            output::warn(&format!("repo {} not cloned; skipping", repo.id.as_str()));
             continue;
         }
-        if mr.state == MrState::Closed {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "MR for {} is closed and cannot be merged",
-                item.repo.id.as_str()
-            ))));
+        if multi {
+            println!("== {} ==", repo.id.as_str());
         }
-
-        if !args.no_wait {
-            wait_for_ci_success(forge.as_ref(), &item)?;
+        if args.build_artifacts {
+            let Some(command) = resolve_clean_command(&workspace, &repo, args.shared_caches) else {
+                output::info(&format!(
+                    "{}: no build-artifact cleanup for this ecosystem; skipping",
+                    repo.id.as_str()
+                ));
+                continue;
+            };
+            if args.force {
+                output::info(&format!("{}: {}", repo.id.as_str(), command));
+                run_shell_command_in_repo(&repo.path, &[command], &[], None)?;
+            } else {
+                output::info(&format!("{}: would run {}", repo.id.as_str(), command));
+            }
+            continue;
         }
-
-        forge.merge_mr(
-            &item.forge_repo,
-            &item.entry.mr_id,
-            MergeMrParams {
-                squash: args.squash,
-                delete_source_branch: args.delete_branch,
-            },
-        )?;
-        output::info(&format!(
-            "merged MR for {}: !{}",
-            item.repo.id.as_str(),
-            item.entry.iid
-        ));
+        let mut command = vec!["git".to_string(), "clean".to_string()];
+        if args.force {
+            command.push("-f".to_string());
+        } else {
+            command.push("-n".to_string());
+        }
+        if args.directories {
+            command.push("-d".to_string());
+        }
+        if args.ignored {
+            command.push("-x".to_string());
+        }
+        log_git_command_for_repo(repo.id.as_str(), &command);
+        run_command_in_repo(&repo.path, &command)?;
     }
 
     Ok(())
 }
 
-fn handle_mr_close(args: MrCloseArgs, workspace: &Workspace) -> Result<()> {
-    let mut store = load_mr_state(workspace)?;
-    let tracked = tracked_mrs_for_current_branches(workspace, &store)?;
-    if tracked.is_empty() {
-        output::info("no tracked MRs found for current branches");
-        return Ok(());
-    }
-    let forge = workspace_forge_client(workspace)?;
+/// One tool a repo needs, as found by `harmonia setup`.
+#[derive(Debug, Serialize)]
+struct ToolReadiness {
+    tool: String,
+    required: Option<String>,
+    found: Option<String>,
+    ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
 
-    if !output::confirm("close tracked MRs for current branches?", args.yes)
-        .map_err(|err| HarmoniaError::Other(anyhow::anyhow!(err.to_string())))?
-    {
-        output::info("close cancelled");
-        return Ok(());
-    }
+#[derive(Debug, Serialize)]
+struct RepoReadiness {
+    repo: String,
+    ready: bool,
+    tools: Vec<ToolReadiness>,
+}
 
-    for item in &tracked {
-        forge.close_mr(&item.forge_repo, &item.entry.mr_id)?;
-        output::info(&format!(
-            "closed MR for {}: !{}",
-            item.repo.id.as_str(),
-            item.entry.iid
-        ));
-    }
+fn handle_setup(
+    args: SetupArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let mut repos = if args.repos.is_empty() {
+        select_repos(&workspace, &[], None, true, false)?
+    } else {
+        select_repos(&workspace, &args.repos, None, false, false)?
+    };
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
 
-    for item in tracked {
-        store
-            .entries
-            .retain(|entry| !(entry.repo == item.entry.repo && entry.branch == item.entry.branch));
+    let mut reports = Vec::new();
+    for repo in &repos {
+        let dir = if repo.path.is_dir() {
+            repo.path.clone()
+        } else {
+            workspace.root.clone()
+        };
+        let mut tools = Vec::new();
+        if let Some(tool) = repo.ecosystem.as_ref().and_then(Tool::for_ecosystem) {
+            let required = required_version(
+                tool,
+                repo.config
+                    .as_ref()
+                    .and_then(|config| config.toolchain.as_ref()),
+                workspace.config.toolchain.as_ref(),
+            );
+            let mut check = check_tool(tool, required.as_deref(), &dir);
+            if !check.ready && args.install {
+                if let Some(command) = tool_install_command(tool, required.as_deref()) {
+                    output::info(&format!(
+                        "{}: installing {} ({})",
+                        repo.id.as_str(),
+                        tool.as_str(),
+                        command.join(" ")
+                    ));
+                    match run_command_in_repo(&dir, &command) {
+                        Ok(()) => check = check_tool(tool, required.as_deref(), &dir),
+                        Err(err) => output::warn(&format!("{}: {}", repo.id.as_str(), err)),
+                    }
+                }
+            }
+            tools.push(check);
+        }
+        if let Some(manager) = corepack_package_manager(&repo.path) {
+            tools.push(check_corepack(repo, &manager, args.install));
+        }
+        reports.push(RepoReadiness {
+            repo: repo.id.as_str().to_string(),
+            ready: tools.iter().all(|tool| tool.ready),
+            tools,
+        });
     }
-    save_mr_state(workspace, &store)?;
 
-    Ok(())
-}
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "repos": reports }))
+                .map_err(|err| HarmoniaError::Other(err.into()))?
+        );
+    } else {
+        for report in &reports {
+            print_repo_readiness(report);
+        }
+        let ready = reports.iter().filter(|report| report.ready).count();
+        output::info(&format!("{}/{} repos ready", ready, reports.len()));
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct StoredMrEntry {
-    repo: String,
-    forge_repo: String,
-    branch: String,
-    mr_id: String,
-    iid: u64,
-    url: String,
-    source_branch: String,
-    target_branch: String,
+    let not_ready = reports.iter().filter(|report| !report.ready).count();
+    if not_ready == 0 {
+        return Ok(());
+    }
+    Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+        "{} repo{} not ready{}",
+        not_ready,
+        if not_ready == 1 { " is" } else { "s are" },
+        if args.install {
+            ""
+        } else {
+            "; run harmonia setup --install"
+        }
+    ))))
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct MrStateStore {
-    #[serde(default)]
-    entries: Vec<StoredMrEntry>,
+fn print_repo_readiness(report: &RepoReadiness) {
+    if report.tools.is_empty() {
+        println!("{}: nothing to check", report.repo);
+        return;
+    }
+    if report.ready {
+        let found: Vec<String> = report
+            .tools
+            .iter()
+            .map(|tool| format!("{} {}", tool.tool, tool.found.as_deref().unwrap_or("?")))
+            .collect();
+        println!("{}: ready ({})", report.repo, found.join(", "));
+        return;
+    }
+    println!("{}: not ready", report.repo);
+    for tool in report.tools.iter().filter(|tool| !tool.ready) {
+        let mut line = format!(
+            "  {} {}",
+            tool.tool,
+            tool.found.as_deref().unwrap_or("not found")
+        );
+        if let Some(required) = tool.required.as_deref() {
+            line.push_str(&format!(", need {}", required));
+        }
+        if let Some(hint) = tool.hint.as_deref() {
+            line.push_str(&format!(": {}", hint));
+        }
+        println!("{}", line);
+    }
 }
 
-#[derive(Debug, Clone)]
-struct TrackedMr {
-    repo: Repo,
-    forge_repo: RepoId,
-    entry: StoredMrEntry,
+/// Checks `tool` from `dir`. A `required` version is looked up through
+/// rustup or uv first, since those can hold versions that are not on `PATH`;
+/// otherwise the tool on `PATH` must match it.
+fn check_tool(tool: Tool, required: Option<&str>, dir: &Path) -> ToolReadiness {
+    let managed = required.and_then(|required| match tool {
+        Tool::Rust => tool_output(dir, "rustup", &["run", required, "rustc", "--version"]),
+        Tool::Python => tool_output(dir, "uv", &["python", "find", required])
+            .and_then(|path| tool_output(dir, path.trim(), &["--version"])),
+        Tool::Node | Tool::Go => None,
+    });
+    let managed = managed.as_deref().and_then(parse_tool_version);
+    let found = managed.clone().or_else(|| {
+        let (program, args): (&str, &[&str]) = match tool {
+            Tool::Rust => ("rustc", &["--version"]),
+            Tool::Node => ("node", &["--version"]),
+            Tool::Python => ("python3", &["--version"]),
+            Tool::Go => ("go", &["version"]),
+        };
+        tool_output(dir, program, args)
+            .as_deref()
+            .and_then(parse_tool_version)
+    });
+    let ready = match (required, &found) {
+        (_, None) => false,
+        _ if managed.is_some() => true,
+        (None, Some(_)) => true,
+        // A channel such as `stable` is only ready when rustup has it.
+        (Some(required), Some(found)) => {
+            required.starts_with(|ch: char| ch.is_ascii_digit())
+                && version_satisfies(required, found)
+        }
+    };
+    let hint = (!ready).then(|| match tool_install_command(tool, required) {
+        Some(command) => command.join(" "),
+        None => {
+            let version = required.unwrap_or("(any version)");
+            match tool {
+                Tool::Node => format!("install node {} with nvm, fnm, or volta", version),
+                Tool::Go => format!("install go {} from https://go.dev/dl", version),
+                Tool::Rust | Tool::Python => format!("install {} {}", tool.as_str(), version),
+            }
+        }
+    });
+    ToolReadiness {
+        tool: tool.as_str().to_string(),
+        required: required.map(str::to_string),
+        found,
+        ready,
+        hint,
+    }
+}
+
+/// Command that installs `tool`, for the tools setup knows how to install.
+fn tool_install_command(tool: Tool, required: Option<&str>) -> Option<Vec<String>> {
+    let command: Vec<&str> = match tool {
+        Tool::Rust => vec![
+            "rustup",
+            "toolchain",
+            "install",
+            required.unwrap_or("stable"),
+        ],
+        Tool::Python => {
+            let mut command = vec!["uv", "python", "install"];
+            command.extend(required);
+            command
+        }
+        Tool::Node | Tool::Go => return None,
+    };
+    Some(command.into_iter().map(str::to_string).collect())
+}
+
+/// The `packageManager` pinned in a cloned repo's `package.json`, such as
+/// `pnpm@9.1.0`.
+fn corepack_package_manager(repo_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(repo_path.join("package.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+    manifest
+        .get("packageManager")
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
+/// Corepack provides the pinned package manager; with `install`, it is
+/// downloaded now instead of on first use.
+fn check_corepack(repo: &Repo, manager: &str, install: bool) -> ToolReadiness {
+    let found = tool_output(&repo.path, "corepack", &["--version"])
+        .as_deref()
+        .and_then(parse_tool_version);
+    let mut hint = None;
+    if found.is_none() {
+        hint = Some("corepack ships with node 16.9+; install node first".to_string());
+    } else if install {
+        let command = vec!["corepack".to_string(), "install".to_string()];
+        output::info(&format!(
+            "{}: installing {} (corepack install)",
+            repo.id.as_str(),
+            manager
+        ));
+        if let Err(err) = run_command_in_repo(&repo.path, &command) {
+            hint = Some(err.to_string());
+        }
+    }
+    ToolReadiness {
+        tool: "corepack".to_string(),
+        required: Some(manager.to_string()),
+        ready: hint.is_none(),
+        found,
+        hint,
+    }
 }
 
-#[derive(Debug, Clone)]
-struct MrStatusRow {
-    repo: RepoId,
-    iid: u64,
-    url: String,
-    state: MrState,
-    ci_state: Option<CiState>,
-    approvals: Vec<String>,
-    checks: Vec<(String, String)>,
-    missing_required_checks: Vec<String>,
-    failed_required_checks: Vec<String>,
+/// Combined stdout and stderr of a successful `program args` run in `dir`.
+fn tool_output(dir: &Path, program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Some(text)
 }
 
-fn run_post_mr_create_hook(workspace: &Workspace) -> Result<()> {
-    let Some(command) = workspace
-        .config
-        .hooks
-        .as_ref()
-        .and_then(|hooks| hooks.post_mr_create.as_deref())
-    else {
-        return Ok(());
-    };
-    run_command_in_repo(&workspace.root, &split_command(command))
-}
+fn handle_config(
+    args: ConfigArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let config_source = config_path_source(workspace_root.as_deref(), config_path.as_deref());
+    let (workspace_root, config_path) = resolve_workspace_paths(workspace_root, config_path)?;
+    let command = args
+        .command
+        .unwrap_or(ConfigCommand::Show(ConfigShowArgs::default()));
 
-fn load_mr_state(workspace: &Workspace) -> Result<MrStateStore> {
-    let path = mr_state_path(workspace);
-    if !path.exists() {
-        return Ok(MrStateStore::default());
-    }
-    let raw = fs::read_to_string(&path)?;
-    if raw.trim().is_empty() {
-        return Ok(MrStateStore::default());
+    match command {
+        ConfigCommand::Show(show) if show.origin => {
+            handle_config_show_origin(&workspace_root, &config_path, &config_source)
+        }
+        ConfigCommand::Show(_) => handle_config_show(&config_path),
+        ConfigCommand::List(list) => handle_config_list(&config_path, list),
+        ConfigCommand::Get(get) => handle_config_get(&config_path, get),
+        ConfigCommand::Set(set) => handle_config_set(&config_path, set),
+        ConfigCommand::Add(add) => handle_config_add(&config_path, add),
+        ConfigCommand::Remove(remove) => handle_config_remove(&config_path, remove),
+        ConfigCommand::Edit(edit) => handle_config_edit(&workspace_root, &config_path, edit),
     }
-    serde_json::from_str::<MrStateStore>(&raw).map_err(|err| {
-        HarmoniaError::Other(anyhow::anyhow!(format!(
-            "failed to parse {}: {}",
-            path.display(),
-            err
-        )))
-    })
 }
 
-fn save_mr_state(workspace: &Workspace, state: &MrStateStore) -> Result<()> {
-    let path = mr_state_path(workspace);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+fn handle_config_show(config_path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(config_path)?;
+    if contents.is_empty() {
+        return Ok(());
     }
-    let contents = serde_json::to_string_pretty(state)
-        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-    fs::write(path, contents)?;
+    print!("{}", contents);
     Ok(())
 }
 
-fn mr_state_path(workspace: &Workspace) -> PathBuf {
-    workspace.root.join(".harmonia").join("mr-state.json")
+/// How the workspace config file was located, mirroring the precedence in
+/// `resolve_workspace_with_overrides`.
+fn config_path_source(workspace_root: Option<&Path>, config_path: Option<&Path>) -> String {
+    if config_path.is_some() {
+        return "command line (--config)".to_string();
+    }
+    if workspace_root.is_some() {
+        return "command line (--workspace)".to_string();
+    }
+    if env::var_os("HARMONIA_WORKSPACE").is_some() {
+        return "env:HARMONIA_WORKSPACE".to_string();
+    }
+    if env::var_os("HARMONIA_CONFIG").is_some() {
+        return "env:HARMONIA_CONFIG".to_string();
+    }
+    "discovered from current directory".to_string()
 }
 
-fn upsert_mr_state_entry(state: &mut MrStateStore, entry: StoredMrEntry) {
-    state
-        .entries
-        .retain(|existing| !(existing.repo == entry.repo && existing.branch == entry.branch));
-    state.entries.push(entry);
-}
+fn handle_config_show_origin(
+    workspace_root: &Path,
+    config_path: &Path,
+    config_source: &str,
+) -> Result<()> {
+    let display = |path: &Path| {
+        path.strip_prefix(workspace_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    println!(
+        "# config file: {} ({})",
+        config_path.display(),
+        config_source
+    );
 
-fn tracked_mrs_for_current_branches(
-    workspace: &Workspace,
-    state: &MrStateStore,
-) -> Result<Vec<TrackedMr>> {
-    let mut repos: Vec<&Repo> = workspace
-        .repos
-        .values()
-        .filter(|repo| !repo.ignored && !repo.external && repo.path.is_dir())
-        .collect();
-    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    let contents = fs::read_to_string(config_path)?;
+    let value: toml::Value =
+        toml::from_str(&contents).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    let lines = config_key_lines(&contents);
+    let config_file = display(config_path);
+    let mut env_overrides = config_env_overrides();
+    if let Some(token) = env::var("HARMONIA_FORGE_TOKEN")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+    {
+        env_overrides.push(("forge.token", "HARMONIA_FORGE_TOKEN", token));
+    }
 
-    let mut by_repo_branch: HashMap<(String, String), StoredMrEntry> = HashMap::new();
-    for entry in &state.entries {
-        by_repo_branch.insert((entry.repo.clone(), entry.branch.clone()), entry.clone());
+    let workspace_entries = flatten_config_value(&value);
+    for (key, leaf) in &workspace_entries {
+        let origin = match config_key_line(&lines, key) {
+            Some(line) => format!("file:{config_file}:{line}"),
+            None => format!("file:{config_file}"),
+        };
+        let overridden = env_overrides
+            .iter()
+            .find(|(overridden, _, _)| overridden == key)
+            .map(|(_, var, _)| format!("  # overridden by env:{var}"))
+            .unwrap_or_default();
+        println!(
+            "{origin}\t{key} = {}{overridden}",
+            config_origin_value(key, leaf)
+        );
+    }
+    for (key, var, value) in &env_overrides {
+        let leaf = toml::Value::String(value.clone());
+        println!("env:{var}\t{key} = {}", config_origin_value(key, &leaf));
     }
 
-    let mut tracked = Vec::new();
+    let workspace = Workspace::load_from(workspace_root.to_path_buf(), config_path.to_path_buf())?;
+    let mut repos: Vec<&Repo> = workspace.repos.values().collect();
+    repos.sort_by(|left, right| left.id.as_str().cmp(right.id.as_str()));
     for repo in repos {
-        let open = open_repo(&repo.path)?;
-        let branch = current_branch(&open.repo)?;
-        let key = (repo.id.as_str().to_string(), branch);
-        let Some(entry) = by_repo_branch.get(&key).cloned() else {
+        let repo_config_path = repo.path.join(".harmonia.toml");
+        if !repo_config_path.is_file() {
             continue;
-        };
-        tracked.push(TrackedMr {
-            repo: repo.clone(),
-            forge_repo: RepoId::new(entry.forge_repo.clone()),
-            entry,
-        });
+        }
+        let contents = fs::read_to_string(&repo_config_path)?;
+        let value: toml::Value = toml::from_str(&contents)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        let lines = config_key_lines(&contents);
+        let repo_file = display(&repo_config_path);
+        let workspace_ecosystem = format!("repos.{}.ecosystem", repo.id.as_str());
+        for (key, leaf) in flatten_config_value(&value) {
+            let origin = match config_key_line(&lines, &key) {
+                Some(line) => format!("file:{repo_file}:{line}"),
+                None => format!("file:{repo_file}"),
+            };
+            let note = if key == "package.ecosystem"
+                && workspace_entries
+                    .iter()
+                    .any(|(existing, _)| *existing == workspace_ecosystem)
+            {
+                format!("  # overridden by {workspace_ecosystem}")
+            } else if (key.starts_with("hooks.") || key.starts_with("env."))
+                && workspace_entries
+                    .iter()
+                    .any(|(existing, _)| *existing == key)
+            {
+                format!("  # overrides {key} for {}", repo.id.as_str())
+            } else {
+                String::new()
+            };
+            println!(
+                "{origin}\t{key} = {}{note}",
+                config_origin_value(&key, &leaf)
+            );
+        }
     }
-    Ok(tracked)
+    Ok(())
 }
 
-fn tracked_mrs_in_merge_order(
-    workspace: &Workspace,
-    tracked: Vec<TrackedMr>,
-) -> Result<Vec<TrackedMr>> {
-    if tracked.len() <= 1 {
-        return Ok(tracked);
+fn config_origin_value(key: &str, value: &toml::Value) -> String {
+    let host_token = key.starts_with("forge.hosts.") && key.ends_with(".token");
+    if key == "forge.token" || key.ends_with(".forge.token") || host_token {
+        return "\"<redacted>\"".to_string();
     }
-    let targets: Vec<RepoId> = tracked.iter().map(|item| item.repo.id.clone()).collect();
-    let order =
-        merge_order(&workspace.graph, &workspace.repos, &targets).map_err(HarmoniaError::Other)?;
-    let mut by_repo: HashMap<RepoId, TrackedMr> = tracked
-        .into_iter()
-        .map(|item| (item.repo.id.clone(), item))
-        .collect();
+    value.to_string()
+}
 
-    let mut ordered = Vec::new();
-    for repo_id in order {
-        if let Some(item) = by_repo.remove(&repo_id) {
-            ordered.push(item);
+fn handle_config_list(config_path: &Path, args: ConfigListArgs) -> Result<()> {
+    let prefix = args
+        .prefix
+        .as_deref()
+        .map(|prefix| prefix.trim_matches('.'))
+        .filter(|prefix| !prefix.is_empty());
+    if let Some(prefix) = prefix {
+        validate_config_key(prefix, true)?;
+    }
+
+    if args.schema {
+        for key in schema_config_keys(prefix) {
+            println!("{key}");
         }
+        return Ok(());
     }
 
-    let mut remaining: Vec<TrackedMr> = by_repo.into_values().collect();
-    remaining.sort_by(|a, b| a.repo.id.as_str().cmp(b.repo.id.as_str()));
-    ordered.extend(remaining);
-    Ok(ordered)
+    let value = read_workspace_config_value(config_path)?;
+    for (key, leaf) in flatten_config_value(&value) {
+        let selected = match prefix {
+            Some(prefix) => key == prefix || key.starts_with(&format!("{prefix}.")),
+            None => true,
+        };
+        if selected {
+            println!("{key} = {leaf}");
+        }
+    }
+    Ok(())
 }
 
-fn workspace_forge_client(workspace: &Workspace) -> Result<Box<dyn crate::forge::traits::Forge>> {
-    let config = workspace.config.forge.as_ref().ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!(
-            "forge config is required (set [forge] in .harmonia/config.toml or .harmonia.toml)"
-        ))
+fn handle_config_get(config_path: &Path, args: ConfigGetArgs) -> Result<()> {
+    validate_config_key(&args.key, true)?;
+    let value = read_workspace_config_value(config_path)?;
+    let found = workspace_config_get(&value, &args.key).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "config key '{}' not found",
+            args.key
+        )))
     })?;
-    client_from_forge_config(config)
+    println!("{}", format_config_value(found));
+    Ok(())
 }
 
-fn forge_repo_for_repo(workspace: &Workspace, repo: &Repo) -> RepoId {
-    if let Some(path) = project_path_from_remote_url(&repo.remote_url) {
-        return RepoId::new(path);
-    }
-    if let Some(group) = workspace
-        .config
-        .forge
-        .as_ref()
-        .and_then(|forge| forge.default_group.as_deref())
-    {
-        let group = group.trim().trim_matches('/');
-        if !group.is_empty() {
-            return RepoId::new(format!("{}/{}", group, repo.id.as_str()));
-        }
+fn handle_config_set(config_path: &Path, args: ConfigSetArgs) -> Result<()> {
+    validate_config_key(&args.key, false)?;
+    let mut value = read_workspace_config_value(config_path)?;
+    let parsed = parse_config_value(&args.value)?;
+    workspace_config_set(&mut value, &args.key, parsed)?;
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!("updated {}", args.key));
+    Ok(())
+}
+
+/// Repos whose `.harmonia.toml` a `repo config` command reads or writes,
+/// skipping ones that are not cloned.
+fn repo_config_targets(workspace: &Workspace, repos: &[String], all: bool) -> Result<Vec<Repo>> {
+    let selected = select_repos(workspace, repos, None, all, false)?;
+    Ok(selected
+        .into_iter()
+        .filter(|repo| {
+            let cloned = repo.path.exists();
+            if !cloned {
+                output::warn(&format!("{}: not cloned, skipping", repo.id.as_str()));
+            }
+            cloned
+        })
+        .collect())
+}
+
+/// Reads a repo's `.harmonia.toml`; a missing file is an empty document.
+fn read_repo_config_value(repo: &Repo) -> Result<toml::Value> {
+    let path = repo.path.join(".harmonia.toml");
+    if !path.exists() {
+        return Ok(toml::Value::Table(toml::map::Map::new()));
     }
-    repo.id.clone()
+    read_workspace_config_value(&path).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!("{}: {}", path.display(), err)))
+    })
 }
 
-fn project_path_from_remote_url(remote_url: &str) -> Option<String> {
-    let trimmed = remote_url
-        .trim()
-        .trim_end_matches('/')
-        .trim_end_matches(".git");
-    if trimmed.is_empty() {
-        return None;
+fn handle_repo_config_list(workspace: &Workspace, args: RepoConfigListArgs) -> Result<()> {
+    let prefix = args
+        .prefix
+        .as_deref()
+        .map(|prefix| prefix.trim_matches('.'))
+        .filter(|prefix| !prefix.is_empty());
+    if let Some(prefix) = prefix {
+        validate_repo_config_key(prefix, true)?;
     }
 
-    if let Some(rest) = trimmed.strip_prefix("git@") {
-        let (_, path) = rest.split_once(':')?;
-        let path = path.trim_start_matches('/').trim();
-        if path.is_empty() {
-            return None;
+    if args.schema {
+        for key in schema_repo_config_keys(prefix) {
+            println!("{key}");
         }
-        return Some(path.to_string());
+        return Ok(());
     }
 
-    if let Some(rest) = trimmed.strip_prefix("ssh://") {
-        let rest = rest.split_once('@').map(|(_, value)| value).unwrap_or(rest);
-        let (_, path) = rest.split_once('/')?;
-        let path = path.trim_start_matches('/').trim();
-        if path.is_empty() {
-            return None;
+    let repos = repo_config_targets(workspace, &args.repos, false)?;
+    let multiple = repos.len() > 1;
+    for repo in repos {
+        let value = read_repo_config_value(&repo)?;
+        for (key, leaf) in flatten_config_value(&value) {
+            let selected = match prefix {
+                Some(prefix) => key == prefix || key.starts_with(&format!("{prefix}.")),
+                None => true,
+            };
+            if !selected {
+                continue;
+            }
+            if multiple {
+                println!("{}: {key} = {leaf}", repo.id.as_str());
+            } else {
+                println!("{key} = {leaf}");
+            }
         }
-        return Some(path.to_string());
     }
+    Ok(())
+}
 
-    if let Some(rest) = trimmed
-        .strip_prefix("https://")
-        .or_else(|| trimmed.strip_prefix("http://"))
-    {
-        let (_, path) = rest.split_once('/')?;
-        let path = path.trim_start_matches('/').trim();
-        if path.is_empty() {
-            return None;
-        }
-        return Some(path.to_string());
+fn handle_repo_config_get(workspace: &Workspace, args: RepoConfigGetArgs) -> Result<()> {
+    validate_repo_config_key(&args.key, true)?;
+    let repos = repo_config_targets(workspace, &args.repos, false)?;
+    if let [repo] = repos.as_slice() {
+        let value = read_repo_config_value(repo)?;
+        let found = workspace_config_get(&value, &args.key).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "{}: config key '{}' not set",
+                repo.id.as_str(),
+                args.key
+            )))
+        })?;
+        println!("{}", format_config_value(found));
+        return Ok(());
     }
 
-    if let Some((_, path)) = trimmed.split_once(':') {
-        if path.contains('/') {
-            let path = path.trim_start_matches('/').trim();
-            if path.is_empty() {
-                return None;
-            }
-            return Some(path.to_string());
-        }
+    for repo in &repos {
+        let value = read_repo_config_value(repo)?;
+        let shown = workspace_config_get(&value, &args.key)
+            .map(format_config_value)
+            .unwrap_or_else(|| "(unset)".to_string());
+        println!("{}: {}", repo.id.as_str(), shown);
     }
+    Ok(())
+}
 
-    None
+fn handle_repo_config_set(workspace: &Workspace, args: RepoConfigSetArgs) -> Result<()> {
+    if args.repos.is_empty() && !args.all {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "name repositories with --repos or pass --all"
+        )));
+    }
+    validate_repo_config_key(&args.key, false)?;
+    let parsed = parse_config_value(&args.value)?;
+    let repos = repo_config_targets(workspace, &args.repos, args.all)?;
+
+    // Validate every document before writing any, so a type error leaves all
+    // repos untouched.
+    let mut updates = Vec::new();
+    for repo in &repos {
+        let mut value = read_repo_config_value(repo)?;
+        workspace_config_set(&mut value, &args.key, parsed.clone())?;
+        validate_repo_config_document(&value).map_err(|err| {
+            HarmoniaError::Other(anyhow::anyhow!(format!("{}: {}", repo.id.as_str(), err)))
+        })?;
+        updates.push((repo, value));
+    }
+    for (repo, value) in updates {
+        write_workspace_config_value(&repo.path.join(".harmonia.toml"), &value)?;
+        output::info(&format!("{}: updated {}", repo.id.as_str(), args.key));
+    }
+    Ok(())
 }
 
-fn ordered_plan_repos(plan: &PlanSummary) -> Vec<RepoId> {
-    let changed_ids: HashSet<RepoId> = plan.changed.iter().map(|repo| repo.id.clone()).collect();
-    let mut ordered: Vec<RepoId> = plan
-        .merge_order
-        .iter()
-        .filter(|repo| changed_ids.contains(*repo))
-        .cloned()
-        .collect();
-    let seen: HashSet<RepoId> = ordered.iter().cloned().collect();
-    let mut remaining: Vec<RepoId> = plan
-        .changed
-        .iter()
-        .map(|repo| repo.id.clone())
-        .filter(|repo| !seen.contains(repo))
-        .collect();
-    remaining.sort_by(|a, b| a.as_str().cmp(b.as_str()));
-    ordered.extend(remaining);
-    ordered
+fn handle_config_add(config_path: &Path, args: ConfigAddArgs) -> Result<()> {
+    validate_config_key(&args.key, true)?;
+    let mut value = read_workspace_config_value(config_path)?;
+    let added = workspace_config_add(&mut value, &args.key, &args.values)?;
+    if added == 0 {
+        output::info(&format!("{} already has every value", args.key));
+        return Ok(());
+    }
+    validate_config_document(&value)?;
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!("added {} value(s) to {}", added, args.key));
+    Ok(())
 }
 
-fn merged_labels(workspace: &Workspace, cli_labels: &[String]) -> Vec<String> {
-    let mut labels = Vec::new();
-    let mut seen = HashSet::new();
-    for label in workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.labels.as_ref())
-        .into_iter()
-        .flatten()
-        .chain(cli_labels.iter())
-    {
-        if seen.insert(label.clone()) {
-            labels.push(label.clone());
+fn handle_config_remove(config_path: &Path, args: ConfigRemoveArgs) -> Result<()> {
+    validate_config_key(&args.key, true)?;
+    let mut value = read_workspace_config_value(config_path)?;
+    let removed = workspace_config_remove(&mut value, &args.key, &args.values)?;
+    if removed == 0 {
+        output::info(&format!("nothing to remove from {}", args.key));
+        return Ok(());
+    }
+    validate_config_document(&value)?;
+    write_workspace_config_value(config_path, &value)?;
+    if args.values.is_empty() {
+        output::info(&format!("removed {}", args.key));
+    } else {
+        output::info(&format!("removed {} value(s) from {}", removed, args.key));
+    }
+    Ok(())
+}
+
+fn handle_config_edit(
+    workspace_root: &Path,
+    config_path: &Path,
+    args: ConfigEditArgs,
+) -> Result<()> {
+    let mut command = resolve_editor_command(args.editor.as_deref())?;
+    command.push(config_path.to_string_lossy().to_string());
+    run_command_in_repo(workspace_root, &command)
+}
+
+fn handle_repo(
+    args: RepoArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let (root, config_path) = resolve_workspace_paths(workspace_root, config_path)?;
+    let command = args.command.unwrap_or(RepoCommand::List);
+
+    match command {
+        RepoCommand::List => handle_repo_list(&config_path),
+        RepoCommand::Add(add) => handle_repo_add(&config_path, add),
+        RepoCommand::Remove(remove) => handle_repo_remove(&config_path, remove),
+        RepoCommand::Show(show) => handle_repo_show(&config_path, show),
+        RepoCommand::Info(mut info) => {
+            native_json(&mut info.json);
+            let workspace = Workspace::load_from(root, config_path.clone())?;
+            handle_repo_info(&workspace, info)
+        }
+        RepoCommand::Adopt(adopt) => {
+            let workspace = Workspace::load_from(root, config_path.clone())?;
+            handle_repo_adopt(&workspace, &config_path, adopt)
+        }
+        RepoCommand::Pause(pause) => handle_repo_pause(&config_path, pause),
+        RepoCommand::Resume(resume) => handle_repo_resume(&config_path, resume),
+        RepoCommand::Config(args) => {
+            let workspace = Workspace::load_from(root, config_path.clone())?;
+            match args.command {
+                RepoConfigCommand::List(list) => handle_repo_config_list(&workspace, list),
+                RepoConfigCommand::Get(get) => handle_repo_config_get(&workspace, get),
+                RepoConfigCommand::Set(set) => {
+                    ensure_writable(&workspace, "repo config set")?;
+                    handle_repo_config_set(&workspace, set)
+                }
+            }
+        }
+        RepoCommand::Move(args) => {
+            let workspace = Workspace::load_from(root, config_path.clone())?;
+            let repo = workspace
+                .repos
+                .get(&RepoId::new(args.name.clone()))
+                .ok_or_else(|| {
+                    HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "repo '{}' not found in config",
+                        args.name
+                    )))
+                })?;
+            let target = if args.new_path.is_absolute() {
+                args.new_path.clone()
+            } else {
+                workspace.root.join(&args.new_path)
+            };
+            let moves = vec![RepoMove {
+                name: args.name.clone(),
+                from: repo.path.clone(),
+                to: target,
+            }];
+            apply_repo_moves(&workspace, &config_path, &moves, args.dry_run)
+        }
+        RepoCommand::Relayout(args) => {
+            let workspace = Workspace::load_from(root, config_path.clone())?;
+            let moves = plan_repo_relayout(&workspace, &args.layout)?;
+            if moves.is_empty() {
+                output::info(&format!("repos already use the {} layout", args.layout));
+                return Ok(());
+            }
+            apply_repo_moves(&workspace, &config_path, &moves, args.dry_run)
         }
     }
-    labels
 }
 
-#[derive(Debug, Clone, Copy)]
-struct LinkBehavior {
-    related: bool,
-    description: bool,
-    issue: bool,
+#[derive(Debug, Clone)]
+struct RepoMove {
+    name: String,
+    from: PathBuf,
+    to: PathBuf,
 }
 
-fn effective_link_behavior(workspace: &Workspace, args: &MrCreateArgs) -> Result<LinkBehavior> {
-    let configured = workspace
+fn plan_repo_relayout(workspace: &Workspace, layout: &str) -> Result<Vec<RepoMove>> {
+    let repos_dir = workspace_repos_dir(workspace);
+    let mut group_names: Vec<(&String, &Vec<String>)> = workspace
         .config
-        .mr
+        .groups
         .as_ref()
-        .and_then(|config| config.link_strategy.as_deref())
-        .unwrap_or("all")
-        .to_ascii_lowercase();
-
-    let mut behavior = match configured.as_str() {
-        "related" => LinkBehavior {
-            related: true,
-            description: false,
-            issue: false,
-        },
-        "description" => LinkBehavior {
-            related: false,
-            description: true,
-            issue: false,
-        },
-        "issue" => LinkBehavior {
-            related: false,
-            description: false,
-            issue: true,
-        },
-        "all" => LinkBehavior {
-            related: true,
-            description: true,
-            issue: true,
-        },
-        other => {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "invalid mr.link_strategy '{}': expected related, description, issue, or all",
-                other
-            ))))
+        .map(|groups| groups.groups.iter().collect())
+        .unwrap_or_default();
+    group_names.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut moves = Vec::new();
+    let mut names: Vec<&RepoId> = workspace.repos.keys().collect();
+    names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    for id in names {
+        let repo = &workspace.repos[id];
+        let target = match layout {
+            "flat" => repos_dir.join(id.as_str()),
+            "grouped" => match group_names
+                .iter()
+                .find(|(_, members)| members.iter().any(|member| member == id.as_str()))
+            {
+                Some((group, _)) => repos_dir.join(group).join(id.as_str()),
+                None => repos_dir.join(id.as_str()),
+            },
+            other => {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "unknown layout '{}'; expected flat or grouped",
+                    other
+                ))))
+            }
+        };
+        if target != repo.path {
+            moves.push(RepoMove {
+                name: id.as_str().to_string(),
+                from: repo.path.clone(),
+                to: target,
+            });
         }
-    };
-
-    if args.no_link {
-        behavior.related = false;
-        behavior.description = false;
-    }
-    if args.no_issue {
-        behavior.issue = false;
     }
-    Ok(behavior)
+    Ok(moves)
 }
 
-fn should_create_tracking_issue(
-    workspace: &Workspace,
-    args: &MrCreateArgs,
-    mr_count: usize,
-    behavior: LinkBehavior,
-) -> bool {
-    if !behavior.issue || args.no_issue {
-        return false;
+fn workspace_repos_dir(workspace: &Workspace) -> PathBuf {
+    let repos_dir = workspace.config.workspace.repos_dir.as_str();
+    if repos_dir.is_empty() {
+        workspace.root.join("repos")
+    } else {
+        workspace.root.join(repos_dir)
     }
-    workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.create_tracking_issue)
-        .unwrap_or(mr_count > 1)
-}
-
-fn mr_require_tests_enabled(workspace: &Workspace) -> bool {
-    workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.require_tests)
-        .unwrap_or(false)
 }
 
-fn mr_add_trailers_enabled(workspace: &Workspace) -> bool {
-    workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.add_trailers)
-        .unwrap_or(false)
-}
+fn apply_repo_moves(
+    workspace: &Workspace,
+    config_path: &Path,
+    moves: &[RepoMove],
+    dry_run: bool,
+) -> Result<()> {
+    // A repo that is not cloned has nothing to move, and pointing its config
+    // at the new path would lose track of where it really is.
+    let moves: Vec<&RepoMove> = moves
+        .iter()
+        .filter(|item| {
+            if item.from.exists() {
+                return true;
+            }
+            output::warn(&format!(
+                "repo {} is not cloned at {}; skipping",
+                item.name,
+                item.from.display()
+            ));
+            false
+        })
+        .collect();
+    for item in &moves {
+        if item.to.exists() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "cannot move {}: {} already exists",
+                item.name,
+                item.to.display()
+            ))));
+        }
+    }
 
-fn run_required_mr_tests(workspace: &Workspace, repos: &[RepoId]) -> Result<()> {
-    if repos.is_empty() {
+    if dry_run {
+        println!("repo move plan:");
+        for item in &moves {
+            println!(
+                "  {}: {} -> {}",
+                item.name,
+                item.from.display(),
+                item.to.display()
+            );
+        }
         return Ok(());
     }
-    output::info("mr.require_tests=true, running tests for selected repos");
-    for repo_id in repos {
-        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {} in MR test scope",
-                repo_id.as_str()
-            )))
+
+    let mut value = read_workspace_config_value(config_path)?;
+    if !value.get("repos").is_some_and(toml::Value::is_table) {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "[repos] must be a table"
+        )));
+    }
+    let default_dir = workspace_repos_dir(workspace);
+
+    // Config is written after every move so a failure part way leaves each
+    // checkout where its config says it is.
+    for item in moves {
+        if let Some(parent) = item.to.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "failed to move {} to {}",
+                    item.from.display(),
+                    item.to.display()
+                )
+            })?;
+        }
+        fs::rename(&item.from, &item.to).with_context(|| {
+            format!(
+                "failed to move {} to {}",
+                item.from.display(),
+                item.to.display()
+            )
         })?;
-        let command =
-            resolve_quality_command(workspace, repo, QualityKind::Test).ok_or_else(|| {
+
+        let repos = value
+            .get_mut("repos")
+            .and_then(|value| value.as_table_mut())
+            .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
+        let entry = repos
+            .entry(item.name.clone())
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+            .as_table_mut()
+            .ok_or_else(|| {
                 HarmoniaError::Other(anyhow::anyhow!(format!(
-                    "mr.require_tests=true but no test command is configured for {}",
-                    repo.id.as_str()
+                    "[repos].{} must be a table",
+                    item.name
                 )))
             })?;
-        run_quality_command(
-            QualityKind::Test,
-            QualityCommand {
-                repo: repo.clone(),
-                command,
-            },
-        )?;
+        if item.to == default_dir.join(&item.name) {
+            entry.remove("path");
+        } else {
+            let stored = item
+                .to
+                .strip_prefix(&workspace.root)
+                .unwrap_or(&item.to)
+                .to_string_lossy()
+                .to_string();
+            entry.insert("path".to_string(), toml::Value::String(stored));
+        }
+        write_workspace_config_value(config_path, &value)?;
+        rewrite_state_paths(&workspace.root, &item.from, &item.to)?;
+        output::info(&format!("moved {} to {}", item.name, item.to.display()));
     }
     Ok(())
 }
 
-fn build_mr_description(
-    workspace: &Workspace,
-    plan: &PlanSummary,
-    repo: &Repo,
-    description_text: &str,
-) -> Result<String> {
-    let description = description_text.trim().to_string();
-    let mrs = changeset_template_rows(workspace, plan, None);
-    let context = serde_json::json!({
-        "repo": repo.id.as_str(),
-        "description": description,
-        "title": plan.changeset.as_ref().map(|changeset| changeset.title.as_str()).unwrap_or(""),
-        "changeset": {
-            "id": plan.changeset.as_ref().map(|changeset| changeset.id.as_str()).unwrap_or(""),
-            "branch": plan.changeset.as_ref().map(|changeset| changeset.branch.as_str()).unwrap_or(""),
-            "repos": plan.changed.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(),
-            "merge_order": plan.merge_order.iter().map(|id| id.as_str()).collect::<Vec<_>>(),
-            "mrs": mrs,
-            "repo_summary": plan
-                .changed
-                .iter()
-                .find(|item| item.id == repo.id)
-                .and_then(|item| item.changeset_summary.as_deref())
-                .unwrap_or(""),
-        },
-    });
-
-    if let Some(path) = workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.template.as_deref())
-        .map(|path| resolve_template_path(workspace, path))
-    {
-        return render_template_file(&path, &context);
-    }
-
-    let mut body = String::new();
-    if !description.is_empty() {
-        body.push_str(&description);
-        body.push_str("\n\n");
-    }
-    body.push_str("---\n\n");
-    body.push_str("## Coordinated Changeset\n\n");
-    body.push_str("Merge order:\n");
-    for (index, repo_id) in plan.merge_order.iter().enumerate() {
-        body.push_str(&format!("{}. {}\n", index + 1, repo_id.as_str()));
+/// Rewrites absolute checkout paths recorded in `.harmonia/*.json` state files.
+fn rewrite_state_paths(workspace_root: &Path, from: &Path, to: &Path) -> Result<()> {
+    let state_dir = workspace_root.join(".harmonia");
+    if !state_dir.is_dir() {
+        return Ok(());
     }
-    if let Some(summary) = plan
-        .changed
-        .iter()
-        .find(|item| item.id == repo.id)
-        .and_then(|item| item.changeset_summary.as_deref())
-    {
-        body.push_str("\nRepo summary:\n");
-        body.push_str(summary);
-        body.push('\n');
+    let from = from.to_string_lossy().to_string();
+    let to = to.to_string_lossy().to_string();
+    for entry in fs::read_dir(&state_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(OsStr::to_str) != Some("json") {
+            continue;
+        }
+        let raw = fs::read_to_string(&path)?;
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&raw) else {
+            continue;
+        };
+        if replace_path_prefix(&mut value, &from, &to) {
+            let updated = serde_json::to_string_pretty(&value)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+            fs::write(&path, updated)?;
+        }
     }
-    Ok(body)
+    Ok(())
 }
 
-fn build_tracking_issue_description(
-    workspace: &Workspace,
-    plan: &PlanSummary,
-    created: &[StoredMrEntry],
-    cli_description: Option<&str>,
-) -> Result<String> {
-    let description = cli_description.unwrap_or("").trim().to_string();
-    let title = plan
-        .changeset
-        .as_ref()
-        .map(|changeset| changeset.title.clone())
-        .unwrap_or_default();
-    let mrs = changeset_template_rows(workspace, plan, Some(created));
-    let context = serde_json::json!({
-        "title": title,
-        "description": description,
-        "now": format!("{:?}", std::time::SystemTime::now()),
-        "changeset": {
-            "id": plan.changeset.as_ref().map(|changeset| changeset.id.as_str()).unwrap_or(""),
-            "branch": plan
-                .changeset
-                .as_ref()
-                .map(|changeset| changeset.branch.as_str())
-                .unwrap_or(""),
-            "mrs": mrs,
-        },
-    });
-
-    if let Some(path) = workspace
-        .config
-        .mr
-        .as_ref()
-        .and_then(|config| config.issue_template.as_deref())
-        .map(|path| resolve_template_path(workspace, path))
-    {
-        return render_template_file(&path, &context);
+fn replace_path_prefix(value: &mut serde_json::Value, from: &str, to: &str) -> bool {
+    match value {
+        serde_json::Value::String(text) => {
+            let matches = text.as_str() == from
+                || text
+                    .strip_prefix(from)
+                    .is_some_and(|rest| rest.starts_with('/') || rest.starts_with('\\'));
+            if matches {
+                *text = format!("{}{}", to, &text[from.len()..]);
+            }
+            matches
+        }
+        serde_json::Value::Array(items) => {
+            let mut changed = false;
+            for item in items {
+                changed |= replace_path_prefix(item, from, to);
+            }
+            changed
+        }
+        serde_json::Value::Object(map) => {
+            let mut changed = false;
+            for item in map.values_mut() {
+                changed |= replace_path_prefix(item, from, to);
+            }
+            changed
+        }
+        _ => false,
     }
+}
 
-    let mut body = String::new();
-    if !description.is_empty() {
-        body.push_str(&description);
-        body.push_str("\n\n");
-    }
-    body.push_str("This issue tracks coordinated merge requests:\n");
-    for entry in created {
-        body.push_str(&format!(
-            "- {}: !{} ({})\n",
-            entry.repo, entry.iid, entry.url
-        ));
+fn handle_repo_list(config_path: &Path) -> Result<()> {
+    let value = read_workspace_config_value(config_path)?;
+    let repos = workspace_repos_table(&value)?;
+    if repos.is_empty() {
+        output::info("no repos configured");
+        return Ok(());
     }
-    Ok(body)
-}
 
-fn with_related_mr_links(
-    description: &str,
-    created: &[StoredMrEntry],
-    current_repo: &str,
-    changeset_id: Option<&str>,
-) -> String {
-    let start_marker = "<!-- harmonia:related:start -->";
-    let end_marker = "<!-- harmonia:related:end -->";
-    let base = if let Some(start) = description.find(start_marker) {
-        let suffix = &description[start..];
-        if suffix.contains(end_marker) {
-            let mut truncated = String::from(&description[..start]);
-            truncated = truncated.trim_end().to_string();
-            truncated
-        } else {
-            description.trim_end().to_string()
+    println!("Repo          URL                              External  Ignored");
+    println!("----------------------------------------------------------------");
+    let mut names: Vec<&String> = repos.keys().collect();
+    names.sort();
+    for name in names {
+        let Some(entry) = repos.get(name) else {
+            continue;
+        };
+        let url = entry
+            .get("url")
+            .and_then(|value| value.as_str())
+            .unwrap_or("-");
+        let external = entry
+            .get("external")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let ignored = entry
+            .get("ignored")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        println!(
+            "{:<13} {:<32} {:<8} {}",
+            name.as_str(),
+            url,
+            external,
+            ignored
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_repo_show(config_path: &Path, args: RepoShowArgs) -> Result<()> {
+    let value = read_workspace_config_value(config_path)?;
+    let repos = workspace_repos_table(&value)?;
+    let entry = repos.get(&args.name).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "repo '{}' not found in config",
+            args.name
+        )))
+    })?;
+
+    let url = entry.get("url").and_then(|value| value.as_str());
+    let default_branch = entry.get("default_branch").and_then(|value| value.as_str());
+    let package_name = entry.get("package_name").and_then(|value| value.as_str());
+    let ecosystem = entry.get("ecosystem").and_then(|value| value.as_str());
+    let path = entry.get("path").and_then(|value| value.as_str());
+    let external = entry
+        .get("external")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let ignored = entry
+        .get("ignored")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+
+    println!("repo: {}", args.name);
+    println!("  url: {}", url.unwrap_or("(default)"));
+    println!(
+        "  default_branch: {}",
+        default_branch.unwrap_or("(workspace default)")
+    );
+    println!("  package_name: {}", package_name.unwrap_or("(repo name)"));
+    println!(
+        "  ecosystem: {}",
+        ecosystem.unwrap_or("(repo config/default)")
+    );
+    println!("  path: {}", path.unwrap_or("(repos_dir/<name>)"));
+    println!("  external: {}", external);
+    println!("  ignored: {}", ignored);
+    if entry.get("paused").and_then(|value| value.as_bool()) == Some(true) {
+        let until = entry.get("paused_until").and_then(|value| value.as_str());
+        let reason = entry.get("pause_reason").and_then(|value| value.as_str());
+        println!("  paused: until {}", until.unwrap_or("resumed"));
+        if let Some(reason) = reason {
+            println!("  pause_reason: {}", reason);
+        }
+    }
+    Ok(())
+}
+
+fn handle_repo_info(workspace: &Workspace, args: RepoInfoArgs) -> Result<()> {
+    let repo_id = RepoId::new(args.name.clone());
+    let repo = workspace.repos.get(&repo_id).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "repo '{}' not found in config",
+            args.name
+        )))
+    })?;
+    let cloned = repo.path.join(".git").exists();
+
+    let mut description = None;
+    let mut open_mrs = None;
+    let mut ci = None;
+    if workspace.config.forge.is_some() {
+        let forge = workspace_forge_client(workspace)?;
+        let forge_repo = forge_repo_for_repo(workspace, repo);
+        match forge.get_project(&forge_repo) {
+            Ok(project) => description = project.description,
+            Err(err) => output::warn(&format!(
+                "{}: cannot read project: {}",
+                repo_id.as_str(),
+                err
+            )),
+        }
+        match forge.count_open_mrs(&forge_repo) {
+            Ok(count) => open_mrs = Some(count),
+            Err(err) => output::warn(&format!(
+                "{}: cannot count open MRs: {}",
+                repo_id.as_str(),
+                err
+            )),
         }
+        match forge.get_ci_status(&forge_repo, &repo.default_branch) {
+            Ok(status) => ci = Some(ci_state_label(&status.state)),
+            Err(err) => output::warn(&format!(
+                "{}: cannot read CI status: {}",
+                repo_id.as_str(),
+                err
+            )),
+        }
+    }
+    if description.is_none() && cloned {
+        description = readme_summary(&repo.path);
+    }
+
+    let (latest_tag, last_commit) = if cloned {
+        let tag = run_command_output_in_repo(
+            &repo.path,
+            &[
+                "git".to_string(),
+                "describe".to_string(),
+                "--tags".to_string(),
+                "--abbrev=0".to_string(),
+            ],
+        )
+        .ok()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty());
+        let commit = run_command_output_in_repo(
+            &repo.path,
+            &[
+                "git".to_string(),
+                "log".to_string(),
+                "-1".to_string(),
+                "--date=short".to_string(),
+                "--format=%H%x1f%an%x1f%ad%x1f%s".to_string(),
+            ],
+        )
+        .ok()
+        .and_then(|line| {
+            let mut fields = line.trim_end().splitn(4, '\u{1f}');
+            let sha = fields.next()?.to_string();
+            let author = fields.next()?.to_string();
+            let date = fields.next()?.to_string();
+            let subject = fields.next()?.to_string();
+            Some(serde_json::json!({
+                "sha": sha,
+                "author": author,
+                "date": date,
+                "subject": subject,
+            }))
+        });
+        (tag, commit)
     } else {
-        description.trim_end().to_string()
+        (None, None)
+    };
+    let version = if cloned {
+        read_repo_version(repo, workspace)?.map(|version| version.raw)
+    } else {
+        None
     };
 
-    let mut out = base;
-    if !out.is_empty() {
-        out.push_str("\n\n");
+    let resolved = resolve_internal_edges(&workspace.graph, &workspace.repos);
+    let mut dependencies: Vec<String> = resolved
+        .edges
+        .get(&repo_id)
+        .map(|targets| {
+            targets
+                .iter()
+                .filter(|target| **target != repo_id)
+                .map(|target| target.as_str().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    dependencies.sort();
+    dependencies.dedup();
+    let mut dependents: Vec<String> =
+        direct_dependents(&workspace.graph, &workspace.repos, &repo_id)
+            .into_iter()
+            .map(|id| id.as_str().to_string())
+            .collect();
+    dependents.sort();
+    dependents.dedup();
+
+    if args.json {
+        let value = serde_json::json!({
+            "repo": repo_id.as_str(),
+            "path": repo.path.display().to_string(),
+            "cloned": cloned,
+            "description": description,
+            "default_branch": repo.default_branch,
+            "latest_tag": latest_tag,
+            "version": version,
+            "open_mrs": open_mrs,
+            "ci": ci,
+            "last_commit": last_commit,
+            "dependencies": dependencies,
+            "dependents": dependents,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
+        return Ok(());
     }
-    out.push_str(start_marker);
-    out.push('\n');
-    out.push_str("## Related Merge Requests\n");
-    if let Some(changeset_id) = changeset_id {
-        out.push_str(&format!("Changeset: `{}`\n", changeset_id));
+
+    let forge_field = |value: Option<String>| {
+        value.unwrap_or_else(|| {
+            if workspace.config.forge.is_some() {
+                "-".to_string()
+            } else {
+                "(no forge configured)".to_string()
+            }
+        })
+    };
+    println!("repo: {}", repo_id.as_str());
+    if let Some(description) = &description {
+        println!("  description: {}", description);
     }
-    out.push('\n');
-    for entry in created {
-        let current = if entry.repo == current_repo {
-            " (this MR)"
-        } else {
-            ""
-        };
-        out.push_str(&format!(
-            "- {}: !{} ({}){}\n",
-            entry.repo, entry.iid, entry.url, current
-        ));
+    println!("  path: {}", repo.path.display());
+    if !cloned {
+        println!("  cloned: no");
     }
-    out.push_str(end_marker);
-    out.push('\n');
-    out
+    println!("  default_branch: {}", repo.default_branch);
+    println!("  latest_tag: {}", latest_tag.as_deref().unwrap_or("-"));
+    println!("  version: {}", version.as_deref().unwrap_or("-"));
+    println!(
+        "  open_mrs: {}",
+        forge_field(open_mrs.map(|count| count.to_string()))
+    );
+    println!(
+        "  ci ({}): {}",
+        repo.default_branch,
+        forge_field(ci.map(str::to_string))
+    );
+    match &last_commit {
+        Some(commit) => println!(
+            "  last_commit: {} {} ({}, {})",
+            commit["sha"]
+                .as_str()
+                .map(|sha| &sha[..sha.len().min(8)])
+                .unwrap_or("-"),
+            commit["subject"].as_str().unwrap_or_default(),
+            commit["author"].as_str().unwrap_or_default(),
+            commit["date"].as_str().unwrap_or_default()
+        ),
+        None => println!("  last_commit: -"),
+    }
+    println!(
+        "  depends on ({}): {}",
+        dependencies.len(),
+        if dependencies.is_empty() {
+            "-".to_string()
+        } else {
+            dependencies.join(", ")
+        }
+    );
+    println!(
+        "  dependents ({}): {}",
+        dependents.len(),
+        if dependents.is_empty() {
+            "-".to_string()
+        } else {
+            dependents.join(", ")
+        }
+    );
+    Ok(())
 }
 
-fn changeset_template_rows(
-    workspace: &Workspace,
-    plan: &PlanSummary,
-    created: Option<&[StoredMrEntry]>,
-) -> Vec<serde_json::Value> {
-    let package_lookup = package_map(&workspace.repos);
-    let selected: HashSet<RepoId> = plan.changed.iter().map(|item| item.id.clone()).collect();
-    let merge_order_map: HashMap<RepoId, usize> = plan
-        .merge_order
+/// First prose paragraph of the repo README, joined onto one line, skipping
+/// headings, badges, and HTML.
+fn readme_summary(repo_path: &Path) -> Option<String> {
+    let content = ["README.md", "README.rst", "README.txt", "README"]
         .iter()
-        .enumerate()
-        .map(|(index, repo)| (repo.clone(), index + 1))
-        .collect();
+        .find_map(|name| fs::read_to_string(repo_path.join(name)).ok())?;
+    readme_first_paragraph(&content)
+}
+
+fn readme_first_paragraph(content: &str) -> Option<String> {
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let skip = line.starts_with('#')
+            || line.starts_with('<')
+            || line.starts_with("[![")
+            || line.starts_with("![")
+            || line.starts_with("===")
+            || line.starts_with("---");
+        if line.is_empty() || skip {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(line);
+    }
+    if paragraph.is_empty() {
+        None
+    } else {
+        Some(paragraph.join(" "))
+    }
+}
 
-    let created_lookup: HashMap<String, &StoredMrEntry> = created
-        .unwrap_or_default()
-        .iter()
-        .map(|entry| (entry.repo.clone(), entry))
-        .collect();
+fn handle_repo_add(config_path: &Path, args: RepoAddArgs) -> Result<()> {
+    let mut value = read_workspace_config_value(config_path)?;
+    let name = args.name.clone();
+    insert_repo_entry(&mut value, args)?;
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!("added repo {}", name));
+    Ok(())
+}
 
-    let mut rows = Vec::new();
-    for item in &plan.changed {
-        let dependencies = internal_dependencies_for(&workspace.graph, &item.id)
-            .into_iter()
-            .filter_map(|dep| package_lookup.get(&dep.name).cloned())
-            .filter(|dep| selected.contains(dep))
-            .map(|dep| dep.as_str().to_string())
-            .collect::<Vec<_>>();
-        let mut dependencies = dependencies;
-        dependencies.sort();
-        dependencies.dedup();
+fn handle_repo_adopt(workspace: &Workspace, config_path: &Path, args: RepoAdoptArgs) -> Result<()> {
+    let path = if args.path.is_absolute() {
+        args.path.clone()
+    } else {
+        workspace.root.join(&args.path)
+    };
+    if !path.join(".git").exists() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} is not a git checkout",
+            path.display()
+        ))));
+    }
+    let path = path.canonicalize()?;
+    let name = match args.name {
+        Some(name) => name,
+        None => path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!("cannot infer a repo name; pass --name"))
+            })?,
+    };
+    if let Some(existing) = workspace.repos.values().find(|repo| {
+        repo.path
+            .canonicalize()
+            .is_ok_and(|existing| existing == path)
+    }) {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} is already registered as repo '{}'",
+            path.display(),
+            existing.id.as_str()
+        ))));
+    }
 
-        let mut dependents = direct_dependents(&workspace.graph, &workspace.repos, &item.id)
-            .into_iter()
-            .filter(|dep| selected.contains(dep))
-            .map(|dep| dep.as_str().to_string())
-            .collect::<Vec<_>>();
-        dependents.sort();
-        dependents.dedup();
+    let git = |args: &[&str]| {
+        let mut command = vec!["git".to_string()];
+        command.extend(args.iter().map(|arg| arg.to_string()));
+        run_command_output_in_repo(&path, &command)
+            .ok()
+            .map(|output| output.trim().to_string())
+            .filter(|output| !output.is_empty())
+    };
+    let remote = open_repo(&path)
+        .ok()
+        .and_then(|open| default_push_remote(&open.repo));
+    let url = args.url.or_else(|| {
+        remote
+            .as_deref()
+            .and_then(|remote| git(&["remote", "get-url", remote]))
+    });
+    let workspace_default = workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.default_branch.clone())
+        .unwrap_or_else(|| "main".to_string());
+    let default_branch = args
+        .default_branch
+        .or_else(|| {
+            let remote = remote.as_deref()?;
+            let head = git(&[
+                "symbolic-ref",
+                "--quiet",
+                "--short",
+                &format!("refs/remotes/{remote}/HEAD"),
+            ])?;
+            head.strip_prefix(&format!("{remote}/")).map(str::to_string)
+        })
+        .filter(|branch| *branch != workspace_default);
+    let ecosystem = args.ecosystem.or_else(|| detect_ecosystem(&path));
 
-        let (link, status, status_emoji) = if let Some(entry) = created_lookup.get(item.id.as_str())
-        {
-            (
-                format!("[!{}]({})", entry.iid, entry.url),
-                "open".to_string(),
-                "🟢".to_string(),
-            )
-        } else {
-            (
-                "pending".to_string(),
-                "pending".to_string(),
-                "🟡".to_string(),
-            )
+    let default_dir = workspace_repos_dir(workspace);
+    let default_dir = default_dir.canonicalize().unwrap_or(default_dir);
+    let stored_path = if path == default_dir.join(&name) {
+        None
+    } else {
+        let root = workspace.root.canonicalize()?;
+        Some(
+            path.strip_prefix(&root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string(),
+        )
+    };
+
+    let mut value = read_workspace_config_value(config_path)?;
+    insert_repo_entry(
+        &mut value,
+        RepoAddArgs {
+            name: name.clone(),
+            url: url.clone(),
+            default_branch: default_branch.clone(),
+            package_name: None,
+            ecosystem: ecosystem.clone(),
+            external: false,
+            ignored: false,
+            group: args.group,
+        },
+    )?;
+    if let Some(stored) = stored_path.as_deref() {
+        workspace_repo_entry_mut(&mut value, &name)?
+            .insert("path".to_string(), toml::Value::String(stored.to_string()));
+    }
+    write_workspace_config_value(config_path, &value)?;
+
+    output::info(&format!("adopted {} as repo {}", path.display(), name));
+    output::info(&format!("  url: {}", url.as_deref().unwrap_or("(default)")));
+    output::info(&format!(
+        "  default_branch: {}",
+        default_branch
+            .as_deref()
+            .unwrap_or(workspace_default.as_str())
+    ));
+    output::info(&format!(
+        "  ecosystem: {}",
+        ecosystem.as_deref().unwrap_or("(not detected)")
+    ));
+    if let Some(stored) = stored_path {
+        output::info(&format!("  path: {}", stored));
+    }
+    Ok(())
+}
+
+/// Ecosystem of the first known manifest at the root of `path`.
+fn detect_ecosystem(path: &Path) -> Option<String> {
+    const JAVA_MANIFESTS: &[&str] = &["pom.xml", "build.gradle", "build.gradle.kts"];
+    let detected = [
+        EcosystemId::Rust,
+        EcosystemId::Go,
+        EcosystemId::Node,
+        EcosystemId::Python,
+        EcosystemId::Php,
+    ]
+    .into_iter()
+    .find(|id| {
+        plugin_for(id)
+            .file_patterns()
+            .iter()
+            .any(|name| path.join(name).is_file())
+    });
+    match detected {
+        Some(id) => Some(plugin_for(&id).id().to_string()),
+        None if JAVA_MANIFESTS.iter().any(|name| path.join(name).is_file()) => {
+            Some("java".to_string())
+        }
+        None => None,
+    }
+}
+
+/// Adds a `[repos]` entry (and group membership) described by `args`.
+fn insert_repo_entry(value: &mut toml::Value, args: RepoAddArgs) -> Result<()> {
+    let root = value.as_table_mut().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!("workspace config root must be a table"))
+    })?;
+    let repos = root
+        .entry("repos".to_string())
+        .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+        .as_table_mut()
+        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
+
+    if repos.contains_key(&args.name) {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "repo '{}' already exists",
+            args.name
+        ))));
+    }
+
+    let mut entry = toml::map::Map::new();
+    if let Some(url) = args.url {
+        entry.insert("url".to_string(), toml::Value::String(url));
+    }
+    if let Some(default_branch) = args.default_branch {
+        entry.insert(
+            "default_branch".to_string(),
+            toml::Value::String(default_branch),
+        );
+    }
+    if let Some(package_name) = args.package_name {
+        entry.insert(
+            "package_name".to_string(),
+            toml::Value::String(package_name),
+        );
+    }
+    if let Some(ecosystem) = args.ecosystem {
+        let ecosystem = ecosystem.trim();
+        if ecosystem.is_empty() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "--ecosystem cannot be empty"
+            )));
+        }
+        entry.insert(
+            "ecosystem".to_string(),
+            toml::Value::String(ecosystem.to_string()),
+        );
+    }
+    if args.external {
+        entry.insert("external".to_string(), toml::Value::Boolean(true));
+    }
+    if args.ignored {
+        entry.insert("ignored".to_string(), toml::Value::Boolean(true));
+    }
+
+    repos.insert(args.name.clone(), toml::Value::Table(entry));
+
+    if let Some(group) = args.group {
+        let groups = root
+            .entry("groups".to_string())
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+            .as_table_mut()
+            .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[groups] must be a table")))?;
+        let members = groups
+            .entry(group.clone())
+            .or_insert_with(|| toml::Value::Array(Vec::new()))
+            .as_array_mut()
+            .ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "[groups].{} must be an array",
+                    group
+                )))
+            })?;
+        if !members
+            .iter()
+            .any(|value| value.as_str() == Some(args.name.as_str()))
+        {
+            members.push(toml::Value::String(args.name.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn handle_repo_remove(config_path: &Path, args: RepoRemoveArgs) -> Result<()> {
+    let mut value = read_workspace_config_value(config_path)?;
+    let root = value.as_table_mut().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!("workspace config root must be a table"))
+    })?;
+    let repos = root
+        .get_mut("repos")
+        .and_then(|value| value.as_table_mut())
+        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
+
+    if repos.remove(&args.name).is_none() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "repo '{}' not found in config",
+            args.name
+        ))));
+    }
+
+    if let Some(groups) = root
+        .get_mut("groups")
+        .and_then(|value| value.as_table_mut())
+    {
+        for (_, value) in groups.iter_mut() {
+            if let Some(array) = value.as_array_mut() {
+                array.retain(|item| item.as_str() != Some(args.name.as_str()));
+            }
+        }
+    }
+
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!("removed repo {}", args.name));
+    Ok(())
+}
+
+fn handle_repo_pause(config_path: &Path, args: RepoPauseArgs) -> Result<()> {
+    if let Some(until) = args.until.as_deref() {
+        if !is_pause_date(until) {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "invalid --until '{}' (expected YYYY-MM-DD)",
+                until
+            ))));
+        }
+        if until <= today_date().as_str() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "--until {} is not in the future",
+                until
+            ))));
+        }
+    }
+    let mut value = read_workspace_config_value(config_path)?;
+    let entry = workspace_repo_entry_mut(&mut value, &args.name)?;
+    entry.insert("paused".to_string(), toml::Value::Boolean(true));
+    match args.until.as_deref() {
+        Some(until) => {
+            entry.insert(
+                "paused_until".to_string(),
+                toml::Value::String(until.to_string()),
+            );
+        }
+        None => {
+            entry.remove("paused_until");
+        }
+    }
+    match args
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+    {
+        Some(reason) => {
+            entry.insert(
+                "pause_reason".to_string(),
+                toml::Value::String(reason.to_string()),
+            );
+        }
+        None => {
+            entry.remove("pause_reason");
+        }
+    }
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!(
+        "paused repo {}{}",
+        args.name,
+        args.until
+            .as_deref()
+            .map(|until| format!(" until {until}"))
+            .unwrap_or_default()
+    ));
+    Ok(())
+}
+
+fn handle_repo_resume(config_path: &Path, args: RepoResumeArgs) -> Result<()> {
+    let mut value = read_workspace_config_value(config_path)?;
+    let entry = workspace_repo_entry_mut(&mut value, &args.name)?;
+    let was_paused = entry
+        .remove("paused")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    entry.remove("paused_until");
+    entry.remove("pause_reason");
+    if !was_paused {
+        output::info(&format!("repo {} is not paused", args.name));
+        return Ok(());
+    }
+    write_workspace_config_value(config_path, &value)?;
+    output::info(&format!("resumed repo {}", args.name));
+    Ok(())
+}
+
+fn workspace_repo_entry_mut<'a>(
+    value: &'a mut toml::Value,
+    name: &str,
+) -> Result<&'a mut toml::map::Map<String, toml::Value>> {
+    value
+        .get_mut("repos")
+        .and_then(|value| value.as_table_mut())
+        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?
+        .get_mut(name)
+        .ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo '{}' not found in config",
+                name
+            )))
+        })?
+        .as_table_mut()
+        .ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!("[repos].{} must be a table", name)))
+        })
+}
+
+fn handle_test(
+    args: TestArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let default_changed =
+        args.repos.is_empty() && !args.all && !args.changed && !repo_selection_active();
+    let changed_scope = args.changed || default_changed;
+    let mut repos = select_repos(
+        &workspace,
+        &args.repos,
+        None,
+        args.all || changed_scope,
+        false,
+    )?;
+    if changed_scope {
+        repos = filter_changed_repos(&workspace, repos)?;
+    }
+    if args.graph_order {
+        repos = repos_in_graph_order(&workspace, repos)?;
+    } else {
+        repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    }
+    if repos.is_empty() {
+        output::info("no repos selected for test");
+        return Ok(());
+    }
+    if args.watch {
+        return watch_quality(
+            &workspace,
+            QualityKind::Test,
+            repos,
+            args.graph_order,
+            |repos| test_commands(&workspace, &args, repos),
+        );
+    }
+
+    let commands = test_commands(&workspace, &args, repos)?;
+    if commands.is_empty() {
+        output::info("no repos selected for test");
+        return Ok(());
+    }
+
+    if args.graph_order && args.parallel.unwrap_or(1) > 1 {
+        output::warn("graph-order test execution is sequential; ignoring --parallel > 1");
+    }
+
+    if args.json {
+        // Test output goes to stderr so stdout holds only the summary.
+        report::enable();
+        report::native();
+    }
+    let started = SystemTime::now();
+    let tested: Vec<Repo> = commands
+        .iter()
+        .map(|command| command.repo.clone())
+        .collect();
+    // Narrowing a retry to the failed tests would leave a partial coverage
+    // report behind.
+    let narrow = !args.coverage;
+    let retries = args.retry_failed;
+    let mut flaky = Vec::new();
+    let mut failure = None;
+    let sequential = args.graph_order || args.fail_fast;
+    if sequential {
+        for command in commands {
+            let repo = command.repo.id.clone();
+            match run_test_command(command, retries, narrow) {
+                Ok(attempts) if attempts > 1 => flaky.push((repo, attempts)),
+                Ok(_) => {}
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+    } else {
+        let jobs = resolve_parallel(&workspace, "test", args.parallel);
+        let results = parallel::run_in_parallel(commands, jobs, |command| {
+            let repo = command.repo.id.clone();
+            (repo, run_test_command(command, retries, narrow))
+        });
+        for (repo, result) in results {
+            match result {
+                Ok(attempts) if attempts > 1 => flaky.push((repo, attempts)),
+                Ok(_) => {}
+                Err(err) => {
+                    failure.get_or_insert(err);
+                }
+            }
+        }
+    }
+
+    if !flaky.is_empty() && !report::enabled() {
+        println!("Flaky (passed on retry)");
+        for (repo, attempts) in &flaky {
+            println!("  {}: passed on attempt {}", repo.as_str(), attempts);
+        }
+    }
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    if args.coverage {
+        report_workspace_coverage(&workspace, &tested, started, args.json)?;
+    }
+    Ok(())
+}
+
+/// Reads the coverage report each repo's test run wrote, then prints the
+/// combined summary and writes the HTML index. Reports older than `started`
+/// are left over from earlier runs and ignored.
+fn report_workspace_coverage(
+    workspace: &Workspace,
+    repos: &[Repo],
+    started: SystemTime,
+    json: bool,
+) -> Result<()> {
+    let mut rows = Vec::new();
+    for repo in repos {
+        let report = find_coverage_report(repo, started);
+        let lines = match &report {
+            Some(path) => match load_coverage_report(&repo.path.join(path)) {
+                Ok(lines) => Some(lines),
+                Err(err) => {
+                    output::warn(&format!("{}: {}", repo.id.as_str(), err));
+                    None
+                }
+            },
+            None => {
+                output::warn(&format!("{}: no coverage report found", repo.id.as_str()));
+                None
+            }
         };
+        rows.push(RepoCoverage {
+            repo: repo.id.as_str().to_string(),
+            report: lines.and(report),
+            lines: lines.unwrap_or_default(),
+            percent: lines.and_then(|lines| lines.percent()),
+        });
+    }
+    let summary = CoverageSummary::new(rows);
+
+    let index = coverage_index_path(&workspace.root);
+    if let Some(parent) = index.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&index, render_coverage_index(&summary))?;
+
+    if json {
+        let rendered = serde_json::to_string_pretty(&summary)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        println!("{rendered}");
+        return Ok(());
+    }
+
+    let width = summary
+        .repos
+        .iter()
+        .map(|repo| repo.repo.len())
+        .max()
+        .unwrap_or(0)
+        .max("total".len());
+    println!("Coverage");
+    for repo in &summary.repos {
+        match &repo.report {
+            Some(report) => println!(
+                "  {:<width$}  {:>6}  {}/{}  {}",
+                repo.repo,
+                format_percent(repo.percent),
+                repo.lines.covered,
+                repo.lines.total,
+                report.display()
+            ),
+            None => println!("  {:<width$}  {:>6}  no report", repo.repo, "-"),
+        }
+    }
+    println!(
+        "  {:<width$}  {:>6}  {}/{}",
+        "total",
+        format_percent(summary.percent),
+        summary.lines.covered,
+        summary.lines.total
+    );
+    output::info(&format!("wrote {}", index.display()));
+    Ok(())
+}
+
+/// First of the ecosystem plugin's coverage report paths that the current
+/// test run wrote.
+fn find_coverage_report(repo: &Repo, started: SystemTime) -> Option<PathBuf> {
+    // File timestamps come from a coarse clock that can trail `started`.
+    let started = started - Duration::from_secs(1);
+    let ecosystem = repo.ecosystem.as_ref()?;
+    plugin_for(ecosystem)
+        .coverage_reports()
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| {
+            repo.path
+                .join(path)
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| modified >= started)
+        })
+}
+
+fn test_commands(
+    workspace: &Workspace,
+    args: &TestArgs,
+    repos: Vec<Repo>,
+) -> Result<Vec<QualityCommand>> {
+    let mut commands = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo {} not cloned",
+                repo.id.as_str()
+            ))));
+        }
+        let base = match resolve_quality_command(workspace, &repo, QualityKind::Test) {
+            Some(command) => command,
+            None => {
+                output::warn(&format!(
+                    "no test command configured for {}; skipping",
+                    repo.id.as_str()
+                ));
+                continue;
+            }
+        };
+        let mut command = base;
+        if args.coverage {
+            let (updated, applied) = apply_test_coverage(&command, &repo);
+            command = updated;
+            if !applied {
+                output::warn(&format!(
+                    "coverage requested for {} but command has no automatic coverage flag; running as-is",
+                    repo.id.as_str()
+                ));
+            }
+        }
+        if let Some(filter) = args.filter.as_deref() {
+            command = apply_test_filter(&command, &repo, filter);
+        }
+        let env = workspace.command_env(Some(&repo))?;
+        commands.push(QualityCommand {
+            repo,
+            command,
+            env,
+            timeout: resolve_command_timeout(workspace, args.timeout),
+        });
+    }
+    Ok(commands)
+}
+
+fn handle_lint(
+    args: LintArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let default_changed =
+        args.repos.is_empty() && !args.all && !args.changed && !repo_selection_active();
+    let changed_scope = args.changed || default_changed;
+    let mut repos = select_repos(
+        &workspace,
+        &args.repos,
+        None,
+        args.all || changed_scope,
+        false,
+    )?;
+    if changed_scope {
+        repos = filter_changed_repos(&workspace, repos)?;
+    }
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    if repos.is_empty() {
+        output::info("no repos selected for lint");
+        return Ok(());
+    }
+    if args.watch {
+        return watch_quality(&workspace, QualityKind::Lint, repos, false, |repos| {
+            lint_commands(&workspace, &args, repos)
+        });
+    }
+
+    let commands = lint_commands(&workspace, &args, repos)?;
+    if commands.is_empty() {
+        output::info("no repos selected for lint");
+        return Ok(());
+    }
+
+    let jobs = resolve_parallel(&workspace, "lint", args.parallel);
+    let results = parallel::run_in_parallel(commands, jobs, |command| {
+        run_quality_command(QualityKind::Lint, command)
+    });
+    for result in results {
+        result?;
+    }
+
+    Ok(())
+}
+
+fn handle_prefetch(
+    args: PrefetchArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let all = args.repos.is_empty() && args.group.is_none();
+    let mut repos = select_repos(&workspace, &args.repos, args.group.as_deref(), all, false)?;
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut commands = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            output::warn(&format!("repo {} not cloned; skipping", repo.id.as_str()));
+            continue;
+        }
+        let Some(command) = resolve_quality_command(&workspace, &repo, QualityKind::Prefetch)
+        else {
+            output::info(&format!(
+                "{}: no prefetch command for this ecosystem; skipping",
+                repo.id.as_str()
+            ));
+            continue;
+        };
+        let env = workspace.command_env(Some(&repo))?;
+        commands.push(QualityCommand {
+            repo,
+            command,
+            env,
+            timeout: None,
+        });
+    }
+    if commands.is_empty() {
+        output::info("no repos selected for prefetch");
+        return Ok(());
+    }
+
+    let jobs = resolve_parallel(&workspace, "prefetch", args.parallel);
+    let results = parallel::run_in_parallel(commands, jobs, |command| {
+        let repo = command.repo.id.clone();
+        (repo, run_quality_command(QualityKind::Prefetch, command))
+    });
+    let failed: Vec<(RepoId, HarmoniaError)> = results
+        .into_iter()
+        .filter_map(|(repo, result)| result.err().map(|err| (repo, err)))
+        .collect();
+    for (repo, err) in &failed {
+        output::warn(&format!("[{}] prefetch failed: {}", repo.as_str(), err));
+    }
+    if !failed.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "prefetch failed for {} repo(s)",
+            failed.len()
+        ))));
+    }
+    Ok(())
+}
+
+fn lint_commands(
+    workspace: &Workspace,
+    args: &LintArgs,
+    repos: Vec<Repo>,
+) -> Result<Vec<QualityCommand>> {
+    let mut commands = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo {} not cloned",
+                repo.id.as_str()
+            ))));
+        }
+        let base = match resolve_quality_command(workspace, &repo, QualityKind::Lint) {
+            Some(command) => command,
+            None => {
+                output::warn(&format!(
+                    "no lint command configured for {}; skipping",
+                    repo.id.as_str()
+                ));
+                continue;
+            }
+        };
+        let mut command = base;
+        if args.fix {
+            let (updated, applied) = apply_lint_fix(&command, &repo);
+            command = updated;
+            if !applied {
+                output::warn(&format!(
+                    "fix requested for {} but command has no automatic --fix mapping; running as-is",
+                    repo.id.as_str()
+                ));
+            }
+        }
+        let env = workspace.command_env(Some(&repo))?;
+        commands.push(QualityCommand {
+            repo,
+            command,
+            env,
+            timeout: resolve_command_timeout(workspace, args.timeout),
+        });
+    }
+    Ok(commands)
+}
+
+/// Runs `kind` once for `repos`, then polls their files and re-runs it for
+/// each repo that changed, plus (with `include_dependents`) every cloned repo
+/// downstream of it, in graph order. Failures are reported and watching
+/// continues until interrupted.
+fn watch_quality<F>(
+    workspace: &Workspace,
+    kind: QualityKind,
+    repos: Vec<Repo>,
+    include_dependents: bool,
+    commands_for: F,
+) -> Result<()>
+where
+    F: Fn(Vec<Repo>) -> Result<Vec<QualityCommand>>,
+{
+    let run = |repos: Vec<Repo>| match commands_for(repos) {
+        Ok(commands) => {
+            for command in commands {
+                let repo = command.repo.id.clone();
+                if let Err(err) = run_quality_command(kind, command) {
+                    output::warn(&format!(
+                        "[{}] {} failed: {}",
+                        repo.as_str(),
+                        kind.as_str(),
+                        err
+                    ));
+                }
+            }
+        }
+        Err(err) => output::warn(&err.to_string()),
+    };
+
+    let mut stamps: Vec<(Repo, watch::TreeStamp)> = repos
+        .iter()
+        .map(|repo| (repo.clone(), watch::TreeStamp::capture(&repo.path)))
+        .collect();
+    run(repos);
+    output::info(&format!(
+        "watching {} repo(s) for changes; press Ctrl-C to stop",
+        stamps.len()
+    ));
+    loop {
+        std::thread::sleep(Duration::from_millis(watch::POLL_INTERVAL_MS));
+        let mut changed: Vec<Repo> = Vec::new();
+        for (repo, stamp) in stamps.iter_mut() {
+            let current = watch::TreeStamp::capture(&repo.path);
+            let paths = current.changed_since(stamp);
+            if paths.is_empty() {
+                continue;
+            }
+            output::info(&format!(
+                "[{}] changed: {}",
+                repo.id.as_str(),
+                paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            *stamp = current;
+            changed.push(repo.clone());
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        if include_dependents {
+            let mut ids: HashSet<RepoId> = changed.iter().map(|repo| repo.id.clone()).collect();
+            for repo in changed.clone() {
+                for dependent in transitive_dependents(&workspace.graph, &workspace.repos, &repo.id)
+                {
+                    let Some(dependent) = workspace.repos.get(&dependent) else {
+                        continue;
+                    };
+                    if dependent.path.is_dir()
+                        && should_include_repo(dependent, false)
+                        && ids.insert(dependent.id.clone())
+                    {
+                        changed.push(dependent.clone());
+                    }
+                }
+            }
+            changed = repos_in_graph_order(workspace, changed)?;
+        }
+        run(changed);
+        if matches!(kind, QualityKind::Lint) {
+            // `lint --fix` rewrites files; do not treat its edits as a new change.
+            for (repo, stamp) in stamps.iter_mut() {
+                *stamp = watch::TreeStamp::capture(&repo.path);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum QualityKind {
+    Test,
+    Lint,
+    Prefetch,
+}
+
+impl QualityKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            QualityKind::Test => "test",
+            QualityKind::Lint => "lint",
+            QualityKind::Prefetch => "prefetch",
+        }
+    }
+}
+
+#[derive(Clone)]
+struct QualityCommand {
+    repo: Repo,
+    command: String,
+    env: Vec<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+fn run_quality_command(kind: QualityKind, item: QualityCommand) -> Result<()> {
+    output::info(&format!(
+        "[{}] {}: {}",
+        item.repo.id.as_str(),
+        kind.as_str(),
+        item.command
+    ));
+    let started = Instant::now();
+    let result = run_shell_command_in_repo(
+        &item.repo.path,
+        std::slice::from_ref(&item.command),
+        &item.env,
+        item.timeout,
+    );
+    report::repo(serde_json::json!({
+        "repo": item.repo.id.as_str(),
+        "ok": result.is_ok(),
+        "command": item.command,
+        "duration_ms": started.elapsed().as_millis() as u64,
+        "error": result.as_ref().err().map(|err| err.to_string()),
+    }));
+    result
+}
+
+/// Runs a test command, rerunning it up to `retries` more times while it
+/// fails. With `narrow`, reruns only the previously failed tests where the
+/// runner supports it. Returns the number of attempts it took to pass.
+fn run_test_command(item: QualityCommand, retries: u32, narrow: bool) -> Result<u32> {
+    let started = Instant::now();
+    let mut command = item.command.clone();
+    let mut attempts = 1;
+    let result = loop {
+        if attempts == 1 {
+            output::info(&format!("[{}] test: {}", item.repo.id.as_str(), command));
+        } else {
+            output::info(&format!(
+                "[{}] test (retry {}/{}): {}",
+                item.repo.id.as_str(),
+                attempts - 1,
+                retries,
+                command
+            ));
+        }
+        let result = run_shell_command_in_repo(
+            &item.repo.path,
+            std::slice::from_ref(&command),
+            &item.env,
+            item.timeout,
+        );
+        if result.is_ok() || attempts > retries {
+            break result;
+        }
+        output::warn(&format!("{}: tests failed", item.repo.id.as_str()));
+        if narrow {
+            command = apply_test_retry_failed(&item.command, &item.repo);
+        }
+        attempts += 1;
+    };
+    let mut fields = serde_json::json!({
+        "repo": item.repo.id.as_str(),
+        "ok": result.is_ok(),
+        "command": item.command,
+        "duration_ms": started.elapsed().as_millis() as u64,
+        "error": result.as_ref().err().map(|err| err.to_string()),
+    });
+    if retries > 0 {
+        fields["attempts"] = serde_json::Value::from(attempts);
+        fields["flaky"] = serde_json::Value::from(result.is_ok() && attempts > 1);
+    }
+    report::repo(fields);
+    result.map(|_| attempts)
+}
+
+/// `repos` split into dependency levels; see [`graph_levels`].
+fn graph_level_batches(workspace: &Workspace, repos: Vec<Repo>) -> Result<Vec<Vec<Repo>>> {
+    let ids: Vec<RepoId> = repos.iter().map(|repo| repo.id.clone()).collect();
+    let levels =
+        graph_levels(&workspace.graph, &workspace.repos, &ids).map_err(HarmoniaError::Other)?;
+    let mut by_id: HashMap<RepoId, Repo> = repos
+        .into_iter()
+        .map(|repo| (repo.id.clone(), repo))
+        .collect();
+    Ok(levels
+        .into_iter()
+        .map(|level| {
+            level
+                .into_iter()
+                .filter_map(|id| by_id.remove(&id))
+                .collect()
+        })
+        .collect())
+}
+
+/// Runs `func` over each batch in turn, the repos of a batch in parallel.
+/// Once a batch has a result that `failed` rejects, later batches are skipped
+/// because they may depend on the failed repos.
+fn run_in_batches<R, F, S>(
+    batches: Vec<Vec<Repo>>,
+    jobs: Option<usize>,
+    func: F,
+    failed: S,
+) -> Vec<(RepoId, R)>
+where
+    R: Send,
+    F: Fn(Repo) -> R + Send + Sync,
+    S: Fn(&R) -> bool,
+{
+    let mut results = Vec::new();
+    let mut batches = batches.into_iter();
+    for batch in batches.by_ref() {
+        let ids: Vec<RepoId> = batch.iter().map(|repo| repo.id.clone()).collect();
+        let outcomes = parallel::run_in_parallel(batch, jobs, &func);
+        let stop = outcomes.iter().any(&failed);
+        results.extend(ids.into_iter().zip(outcomes));
+        if stop {
+            break;
+        }
+    }
+    let skipped: Vec<String> = batches
+        .flatten()
+        .map(|repo| repo.id.as_str().to_string())
+        .collect();
+    if !skipped.is_empty() {
+        output::warn(&format!(
+            "skipped after an upstream failure: {}",
+            skipped.join(", ")
+        ));
+    }
+    results
+}
+
+fn repos_in_graph_order(workspace: &Workspace, repos: Vec<Repo>) -> Result<Vec<Repo>> {
+    let order =
+        topological_order(&workspace.graph, &workspace.repos).map_err(HarmoniaError::Other)?;
+    let order_index: HashMap<RepoId, usize> = order
+        .into_iter()
+        .enumerate()
+        .map(|(index, id)| (id, index))
+        .collect();
+    let mut repos = repos;
+    repos.sort_by(|a, b| {
+        let a_index = order_index.get(&a.id).copied().unwrap_or(usize::MAX);
+        let b_index = order_index.get(&b.id).copied().unwrap_or(usize::MAX);
+        a_index
+            .cmp(&b_index)
+            .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+    });
+    Ok(repos)
+}
+
+fn expand_branch_scope(
+    workspace: &Workspace,
+    repos: Vec<Repo>,
+    include_dependents: bool,
+    include_all_deps: bool,
+) -> Vec<Repo> {
+    let mut selected: HashSet<RepoId> = repos.iter().map(|repo| repo.id.clone()).collect();
+    if include_dependents || include_all_deps {
+        for repo in &repos {
+            for dependent in transitive_dependents(&workspace.graph, &workspace.repos, &repo.id) {
+                selected.insert(dependent);
+            }
+        }
+    }
+    if include_all_deps {
+        for repo in &repos {
+            for dependency in transitive_dependencies(&workspace.graph, &workspace.repos, &repo.id)
+            {
+                selected.insert(dependency);
+            }
+        }
+    }
+
+    let mut expanded: Vec<Repo> = selected
+        .into_iter()
+        .filter_map(|id| workspace.repos.get(&id).cloned())
+        .filter(|repo| should_include_repo(repo, false))
+        .collect();
+    expanded.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    expanded
+}
+
+fn resolve_quality_command(
+    workspace: &Workspace,
+    repo: &Repo,
+    kind: QualityKind,
+) -> Option<String> {
+    if let Some(command) = custom_hook_command(workspace, repo, kind.as_str()) {
+        return Some(command);
+    }
+
+    let ecosystem = repo.ecosystem.as_ref()?;
+    let plugin = plugin_for(ecosystem);
+    match kind {
+        QualityKind::Test => plugin.default_test_command().map(|cmd| cmd.to_string()),
+        QualityKind::Lint => plugin.default_lint_command().map(|cmd| cmd.to_string()),
+        QualityKind::Prefetch => plugin
+            .prefetch_command(&repo.path)
+            .map(|cmd| cmd.to_string()),
+    }
+}
+
+/// Build-artifact cleanup for `clean --build-artifacts`: a `clean` custom
+/// hook when configured, else the ecosystem plugin's command.
+fn resolve_clean_command(
+    workspace: &Workspace,
+    repo: &Repo,
+    shared_caches: bool,
+) -> Option<String> {
+    custom_hook_command(workspace, repo, "clean").or_else(|| {
+        let ecosystem = repo.ecosystem.as_ref()?;
+        plugin_for(ecosystem)
+            .clean_command(shared_caches)
+            .map(|cmd| cmd.to_string())
+    })
+}
+
+/// `[hooks.custom]` command named `key`, from the repo's config first and
+/// then the workspace's.
+fn custom_hook_command(workspace: &Workspace, repo: &Repo, key: &str) -> Option<String> {
+    repo.config
+        .as_ref()
+        .and_then(|config| config.hooks.as_ref())
+        .and_then(|hooks| hooks.custom.as_ref())
+        .and_then(|custom| custom.get(key))
+        .or_else(|| {
+            workspace
+                .config
+                .hooks
+                .as_ref()
+                .and_then(|hooks| hooks.custom.as_ref())
+                .and_then(|custom| custom.get(key))
+        })
+        .cloned()
+}
+
+fn apply_test_filter(command: &str, repo: &Repo, filter: &str) -> String {
+    let quoted = shell_single_quote(filter);
+    match repo.ecosystem.as_ref() {
+        Some(EcosystemId::Python) => format!("{command} -k {quoted}"),
+        Some(EcosystemId::Go) => format!("{command} -run {quoted}"),
+        Some(EcosystemId::Php) => format!("{command} --filter {quoted}"),
+        Some(EcosystemId::Rust) => insert_before_test_binary_args(command, &quoted),
+        Some(EcosystemId::Node) => {
+            let flag = match detect_node_test_runner(command, &repo.path) {
+                NodeTestRunner::Jest | NodeTestRunner::Vitest => "--testNamePattern",
+                NodeTestRunner::NodeTest => "--test-name-pattern",
+                NodeTestRunner::Unknown => return format!("{command} -- {quoted}"),
+            };
+            if is_node_script_command(command) {
+                format!("{command} -- {flag} {quoted}")
+            } else {
+                format!("{command} {flag} {quoted}")
+            }
+        }
+        _ => format!("{command} {quoted}"),
+    }
+}
+
+fn apply_test_coverage(command: &str, repo: &Repo) -> (String, bool) {
+    match repo.ecosystem.as_ref() {
+        Some(EcosystemId::Python) if !command.contains("--cov") => (
+            format!("{command} --cov --cov-report=term --cov-report=xml"),
+            true,
+        ),
+        Some(EcosystemId::Go) if !command.contains("-cover") => {
+            (format!("{command} -coverprofile=coverage.out"), true)
+        }
+        Some(EcosystemId::Php)
+            if command.contains("phpunit") && !command.contains("--coverage") =>
+        {
+            (format!("{command} --coverage-cobertura coverage.xml"), true)
+        }
+        Some(EcosystemId::Rust)
+            if command.contains("llvm-cov") || command.contains("tarpaulin") =>
+        {
+            (command.to_string(), true)
+        }
+        Some(EcosystemId::Rust) => {
+            let trimmed = command.trim_start();
+            match trimmed.strip_prefix("cargo test") {
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                    // Write lcov so the workspace summary can read it; test
+                    // binary arguments after `--` stay last.
+                    let lcov = " --lcov --output-path target/lcov.info";
+                    let command = match rest.find(" -- ") {
+                        Some(index) => {
+                            format!("cargo llvm-cov{}{lcov}{}", &rest[..index], &rest[index..])
+                        }
+                        None => format!("cargo llvm-cov{rest}{lcov}"),
+                    };
+                    (command, true)
+                }
+                _ => (command.to_string(), false),
+            }
+        }
+        Some(EcosystemId::Node) if command.contains("--coverage") => (command.to_string(), true),
+        Some(EcosystemId::Node) => {
+            let flag = match detect_node_test_runner(command, &repo.path) {
+                NodeTestRunner::Jest => "--coverage",
+                // vitest writes no lcov report by default.
+                NodeTestRunner::Vitest => {
+                    "--coverage --coverage.reporter=text --coverage.reporter=lcov"
+                }
+                NodeTestRunner::NodeTest => "--experimental-test-coverage",
+                NodeTestRunner::Unknown => return (command.to_string(), false),
+            };
+            if is_node_script_command(command) {
+                (format!("{command} -- {flag}"), true)
+            } else {
+                (format!("{command} {flag}"), true)
+            }
+        }
+        _ => (command.to_string(), false),
+    }
+}
+
+/// `command` narrowed to the tests that failed in its previous run, for
+/// runners that remember them: pytest's `--lf` and jest's `--onlyFailures`.
+fn apply_test_retry_failed(command: &str, repo: &Repo) -> String {
+    match repo.ecosystem.as_ref() {
+        Some(EcosystemId::Python) if command.contains("pytest") && !command.contains("--lf") => {
+            format!("{command} --lf")
+        }
+        Some(EcosystemId::Node)
+            if detect_node_test_runner(command, &repo.path) == NodeTestRunner::Jest =>
+        {
+            if is_node_script_command(command) {
+                format!("{command} -- --onlyFailures")
+            } else {
+                format!("{command} --onlyFailures")
+            }
+        }
+        _ => command.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeTestRunner {
+    Jest,
+    Vitest,
+    NodeTest,
+    Unknown,
+}
+
+fn detect_node_test_runner(command: &str, repo_path: &Path) -> NodeTestRunner {
+    let direct = node_test_runner_from_text(command);
+    if direct != NodeTestRunner::Unknown || !is_node_script_command(command) {
+        return direct;
+    }
+
+    let script = fs::read_to_string(repo_path.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|value| {
+            value
+                .get("scripts")
+                .and_then(|scripts| scripts.get("test"))
+                .and_then(|script| script.as_str())
+                .map(|script| script.to_string())
+        });
+    match script {
+        Some(script) => node_test_runner_from_text(&script),
+        None => NodeTestRunner::Unknown,
+    }
+}
+
+fn node_test_runner_from_text(command: &str) -> NodeTestRunner {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    if tokens
+        .iter()
+        .any(|token| *token == "jest" || token.ends_with("/jest"))
+    {
+        NodeTestRunner::Jest
+    } else if tokens
+        .iter()
+        .any(|token| *token == "vitest" || token.ends_with("/vitest"))
+    {
+        NodeTestRunner::Vitest
+    } else if tokens
+        .windows(2)
+        .any(|pair| pair[0] == "node" && pair[1] == "--test")
+    {
+        NodeTestRunner::NodeTest
+    } else {
+        NodeTestRunner::Unknown
+    }
+}
+
+fn is_node_script_command(command: &str) -> bool {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    match tokens.as_slice() {
+        [manager, "test", ..] | [manager, "run", "test", ..] => {
+            matches!(*manager, "npm" | "yarn" | "pnpm" | "bun")
+        }
+        _ => false,
+    }
+}
+
+fn insert_before_test_binary_args(command: &str, arg: &str) -> String {
+    match command.find(" -- ") {
+        Some(index) => format!("{} {arg}{}", &command[..index], &command[index..]),
+        None => format!("{command} {arg}"),
+    }
+}
+
+fn apply_lint_fix(command: &str, repo: &Repo) -> (String, bool) {
+    if command.contains("--fix") {
+        return (command.to_string(), true);
+    }
+
+    match repo.ecosystem.as_ref() {
+        Some(EcosystemId::Rust) if command.trim_start().starts_with("cargo clippy") => (
+            format!("{command} --fix --allow-dirty --allow-staged"),
+            true,
+        ),
+        Some(EcosystemId::Python) if command.trim_start().starts_with("ruff check") => {
+            (format!("{command} --fix"), true)
+        }
+        Some(EcosystemId::Go) if command.contains("golangci-lint") => {
+            (format!("{command} --fix"), true)
+        }
+        Some(EcosystemId::Node) => (format!("{command} -- --fix"), true),
+        Some(EcosystemId::Php) if command.contains("php-cs-fixer fix --dry-run") => (
+            command.replacen("php-cs-fixer fix --dry-run", "php-cs-fixer fix", 1),
+            true,
+        ),
+        _ => (command.to_string(), false),
+    }
+}
+
+fn shell_single_quote(value: &str) -> String {
+    let escaped = value.replace('\'', "'\"'\"'");
+    format!("'{escaped}'")
+}
+
+fn handle_plan(args: PlanArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    // The state computes lazily, so the fetch lands before anything is read.
+    if prefetch_enabled(workspace, args.fresh) {
+        prefetch_repos(workspace, plan_repos(workspace));
+    }
+    let plan = build_plan_summary(state, &args.include, &args.exclude)?;
+    if let Some(format) = args.graph.as_deref() {
+        return print_plan_graph(state, &plan, format);
+    }
+    let simulation = if args.simulate {
+        let level = match args.level.as_deref() {
+            Some(level) => Some(parse_bump_level(level).ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(format!("unknown bump level '{}'", level)))
+            })?),
+            None => None,
+        };
+        Some(simulate_plan_merges(state, &plan, level)?)
+    } else {
+        None
+    };
+    if args.json {
+        let mut value = plan_to_json(&plan);
+        if let Some(simulation) = simulation.as_ref() {
+            value["simulation"] = merge_simulation_to_json(simulation);
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
+    } else {
+        print_plan_summary(&plan);
+        if let Some(simulation) = simulation.as_ref() {
+            print_merge_simulation(simulation);
+        }
+    }
+    if args.verify {
+        verify_plan_dependents(workspace, &plan)?;
+    }
+    Ok(())
+}
+
+/// Renders only the repos in the merge order, numbered by position and
+/// colored by the state of the MR tracked for their current branch.
+fn print_plan_graph(state: &WorkspaceState, plan: &PlanSummary, format: &str) -> Result<()> {
+    let workspace = state.workspace;
+    let format = format.to_ascii_lowercase();
+    if !matches!(format.as_str(), "tree" | "dot" | "mermaid") {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "unknown plan graph format '{}' (expected tree, dot, or mermaid)",
+            format
+        ))));
+    }
+
+    let scope: HashSet<RepoId> = plan.merge_order.iter().cloned().collect();
+    let edges = build_directional_edges(
+        &workspace.graph,
+        &workspace.repos,
+        GraphDirection::Down,
+        &scope,
+    );
+    let roots = graph_roots(&edges, &scope);
+    let statuses = plan_mr_statuses(state)?;
+
+    let mut labels = HashMap::new();
+    let mut metadata = HashMap::new();
+    for (index, repo) in plan.merge_order.iter().enumerate() {
+        let (label, status) = match statuses.get(repo) {
+            Some((iid, status)) => (
+                format!("{}. {} !{} ({})", index + 1, repo.as_str(), iid, status),
+                status.clone(),
+            ),
+            None => (
+                format!("{}. {} (no MR)", index + 1, repo.as_str()),
+                "none".to_string(),
+            ),
+        };
+        labels.insert(repo.clone(), label);
+        metadata.insert(
+            repo.clone(),
+            viz::NodeMetadata {
+                changed: true,
+                mr_status: Some(status),
+                ..viz::NodeMetadata::default()
+            },
+        );
+    }
+
+    match format.as_str() {
+        "tree" => print!("{}", viz::render_tree(&roots, &edges, &labels)),
+        "dot" => print!(
+            "{}",
+            viz::render_dot(&edges, &labels, &metadata, &HashMap::new())
+        ),
+        _ => print!("{}", viz::render_mermaid(&edges, &labels, &metadata, "TD")),
+    }
+    Ok(())
+}
+
+/// MR iid and state per repo for MRs tracked on the current branches. States
+/// come from the forge when one is configured and reachable, otherwise the MR
+/// is reported as `tracked`.
+fn plan_mr_statuses(state: &WorkspaceState) -> Result<HashMap<RepoId, (u64, String)>> {
+    let workspace = state.workspace;
+    let store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    if tracked.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let forge = if workspace.config.forge.is_some() {
+        match workspace_forge_client(workspace) {
+            Ok(forge) => Some(forge),
+            Err(err) => {
+                output::warn(&format!("MR status unavailable: {err}"));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut statuses = HashMap::new();
+    for item in tracked {
+        let state = match forge.as_ref() {
+            Some(forge) => match forge.get_mr(&item.forge_repo, &item.entry.mr_id) {
+                Ok(mr) => mr_state_label(&mr.state).to_string(),
+                Err(err) => {
+                    output::warn(&format!(
+                        "{}: MR status unavailable: {}",
+                        item.repo.id.as_str(),
+                        err
+                    ));
+                    "tracked".to_string()
+                }
+            },
+            None => "tracked".to_string(),
+        };
+        statuses.insert(item.repo.id.clone(), (item.entry.iid, state));
+    }
+    Ok(statuses)
+}
+
+fn handle_mr(args: MrArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let command = args.command.unwrap_or(MrCommand::Status(MrStatusArgs {
+        json: false,
+        wait: false,
+        timeout: 30,
+        fresh: false,
+        diff: false,
+    }));
+
+    match command {
+        MrCommand::Create(args) => {
+            ensure_writable(workspace, "mr create")?;
+            handle_mr_create(args, state)
+        }
+        MrCommand::Status(mut args) => {
+            native_json(&mut args.json);
+            handle_mr_status(args, state)
+        }
+        MrCommand::Update(args) => {
+            ensure_writable(workspace, "mr update")?;
+            handle_mr_update(args, state)
+        }
+        MrCommand::Merge(args) => {
+            if !args.dry_run {
+                ensure_writable(workspace, "mr merge")?;
+            }
+            handle_mr_merge(args, state)
+        }
+        MrCommand::Close(args) => {
+            ensure_writable(workspace, "mr close")?;
+            handle_mr_close(args, state)
+        }
+        MrCommand::Report(args) => handle_mr_report(args, state),
+        MrCommand::State(args) => {
+            ensure_writable(workspace, "mr state")?;
+            handle_mr_state_sync(args, workspace)
+        }
+    }
+}
+
+fn handle_mr_create(args: MrCreateArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let mut plan = build_plan_summary(state, &[], &[])?;
+    if plan.changed.is_empty() {
+        output::info("no changed repositories detected; nothing to create");
+        return Ok(());
+    }
+
+    let draft = args.draft
+        || workspace
+            .config
+            .mr
+            .as_ref()
+            .and_then(|config| config.draft)
+            .unwrap_or(false);
+    let labels = merged_labels(workspace, &args.labels);
+    let mut ordered = ordered_plan_repos(&plan);
+    let link_behavior = effective_link_behavior(workspace, &args)?;
+    let reviewer_strategy = effective_reviewer_strategy(workspace, &args)?;
+    let create_tracking_issue =
+        should_create_tracking_issue(workspace, &args, ordered.len(), link_behavior);
+    let title_override = args.title.clone();
+    let shared_description = args.description.clone().or_else(|| {
+        plan.changeset
+            .as_ref()
+            .map(|changeset| changeset.description.clone())
+    });
+
+    if args.dry_run {
+        println!("MR Create Plan");
+        println!("==============");
+        println!(
+            "title: {}",
+            resolve_mr_title(workspace, &plan, title_override)?
+        );
+        println!("draft: {}", draft);
+        println!("link related mrs: {}", link_behavior.related);
+        println!("link in description: {}", link_behavior.description);
+        println!("create tracking issue: {}", create_tracking_issue);
+        println!("require tests: {}", mr_require_tests_enabled(workspace));
+        if !labels.is_empty() {
+            println!("labels: {}", labels.join(", "));
+        }
+        if !args.reviewers.is_empty() {
+            println!("reviewers: {}", args.reviewers.join(", "));
+        } else if reviewer_strategy != ReviewerStrategy::All {
+            println!(
+                "reviewer strategy: {}",
+                reviewer_strategy_label(reviewer_strategy)
+            );
+        }
+        println!("merge order:");
+        for (index, repo_id) in ordered.iter().enumerate() {
+            println!("  {}. {}", index + 1, repo_id.as_str());
+        }
+        return Ok(());
+    }
+
+    prepare_mr_create_branches(&args, workspace, &mut plan, &mut ordered)?;
+    ensure_mr_branches_are_mergeable(workspace, &plan, &ordered)?;
+
+    if mr_require_tests_enabled(workspace) {
+        run_required_mr_tests(workspace, &ordered)?;
+    }
+
+    let base_title = resolve_mr_title(workspace, &plan, title_override)?;
+    let pending: Vec<serde_json::Value> = ordered
+        .iter()
+        .filter_map(|repo_id| {
+            let plan_repo = plan.changed.iter().find(|repo| &repo.id == repo_id)?;
+            let repo = workspace.repos.get(repo_id)?;
+            Some(serde_json::json!({
+                "repo": repo_id.as_str(),
+                "source_branch": plan_repo.branch,
+                "target_branch": mr_target_branch(repo, &plan_repo.branch),
+            }))
+        })
+        .collect();
+    run_lifecycle_hook(
+        workspace,
+        LifecycleHook::PreMrCreate,
+        serde_json::json!({
+            "title": base_title,
+            "draft": draft,
+            "labels": labels,
+            "changeset": plan.changeset.as_ref().map(|changeset| changeset.id.as_str()),
+            "mrs": pending,
+        }),
+    )?;
+
+    let forge = workspace_forge_client(workspace)?;
+    let mut created = Vec::new();
+    let mut state = load_mr_state(workspace)?;
+    let edges = resolve_internal_edges(&workspace.graph, &workspace.repos).edges;
+    let mut balancer = ReviewerBalancer::new(mr_reviewers_per_mr(workspace));
+
+    for repo_id in ordered.clone() {
+        let plan_repo = plan
+            .changed
+            .iter()
+            .find(|repo| repo.id == repo_id)
+            .ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "missing plan entry for {}",
+                    repo_id.as_str()
+                )))
+            })?;
+        let repo = workspace.repos.get(&repo_id).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                repo_id.as_str()
+            )))
+        })?;
+        if args.skip_existing {
+            if let Some(existing) = state
+                .entries
+                .iter()
+                .find(|entry| entry.repo == repo.id.as_str() && entry.branch == plan_repo.branch)
+                .cloned()
+            {
+                output::info(&format!(
+                    "MR already tracked for {}: !{} {}",
+                    repo.id.as_str(),
+                    existing.iid,
+                    existing.url
+                ));
+                created.push(existing);
+                continue;
+            }
+        }
+        let forge_repo = forge_repo_for_repo(workspace, repo);
+        let description = build_mr_description(
+            workspace,
+            &plan,
+            repo,
+            shared_description.as_deref().unwrap_or_default(),
+        )?;
+        let reviewers = if args.reviewers.is_empty() {
+            assign_team_reviewers(
+                workspace,
+                forge.as_ref(),
+                reviewer_strategy,
+                &mut balancer,
+                repo,
+            )
+        } else {
+            args.reviewers.clone()
+        };
+        let mut labels = labels.clone();
+        if mr_auto_labels_enabled(workspace) {
+            labels.extend(auto_mr_labels(&ordered, &edges, &HashSet::new(), &repo.id));
+        }
+        // An open MR for the branch may exist without being tracked (opened by
+        // hand, or by a run that failed before saving state). Adopt and update
+        // it instead of opening a duplicate.
+        let mr = match forge.find_open_mr(&forge_repo, &plan_repo.branch)? {
+            Some(existing) => {
+                let mr = forge.update_mr(
+                    &forge_repo,
+                    &existing.iid.to_string(),
+                    UpdateMrParams {
+                        title: Some(base_title.clone()),
+                        description: Some(description),
+                        labels: (!labels.is_empty()).then_some(labels),
+                        reviewers: (!reviewers.is_empty()).then_some(reviewers),
+                    },
+                )?;
+                output::info(&format!(
+                    "adopted existing MR for {}: !{} {}",
+                    repo.id.as_str(),
+                    mr.iid,
+                    mr.url
+                ));
+                mr
+            }
+            None => {
+                let mr = forge.create_mr(
+                    &forge_repo,
+                    CreateMrParams {
+                        title: base_title.clone(),
+                        description,
+                        source_branch: plan_repo.branch.clone(),
+                        target_branch: mr_target_branch(repo, &plan_repo.branch),
+                        draft,
+                        labels,
+                        reviewers,
+                    },
+                )?;
+                output::info(&format!(
+                    "created MR for {}: !{} {}",
+                    repo.id.as_str(),
+                    mr.iid,
+                    mr.url
+                ));
+                mr
+            }
+        };
+        let entry = StoredMrEntry {
+            repo: repo.id.as_str().to_string(),
+            forge_repo: forge_repo.as_str().to_string(),
+            forge_host: forge_repo.host().map(str::to_string),
+            branch: plan_repo.branch.clone(),
+            mr_id: mr.iid.to_string(),
+            iid: mr.iid,
+            url: mr.url.clone(),
+            source_branch: mr.source_branch.clone(),
+            target_branch: mr.target_branch.clone(),
+        };
+        upsert_mr_state_entry(&mut state, entry.clone());
+        // Persist each MR as it is created so a failure later in the loop
+        // does not lose track of MRs that already exist on the forge.
+        save_mr_state(workspace, &state)?;
+        created.push(entry);
+    }
+
+    if link_behavior.related && created.len() > 1 {
+        let links: Vec<(RepoId, String)> = created
+            .iter()
+            .map(|entry| (entry.forge_repo_id(), entry.mr_id.clone()))
+            .collect();
+        forge.link_mrs(&links)?;
+        output::info("linked merge requests in merge order");
+    }
+
+    if link_behavior.description && created.len() > 1 {
+        for entry in &created {
+            let repo = entry.forge_repo_id();
+            let current = forge.get_mr(&repo, &entry.mr_id)?;
+            let updated_description = with_related_mr_links(
+                &current.description,
+                &created,
+                entry.repo.as_str(),
+                plan.changeset
+                    .as_ref()
+                    .map(|changeset| changeset.id.as_str()),
+            );
+            forge.update_mr(
+                &repo,
+                &entry.mr_id,
+                UpdateMrParams {
+                    title: None,
+                    description: Some(updated_description),
+                    labels: None,
+                    reviewers: None,
+                },
+            )?;
+        }
+        output::info("updated MR descriptions with related links");
+    }
+
+    if create_tracking_issue {
+        if let Some(first) = created.first() {
+            let issue_title = format!("Tracking: {}", base_title);
+            let issue_description = build_tracking_issue_description(
+                workspace,
+                &plan,
+                &created,
+                shared_description.as_deref(),
+            )?;
+            let coordination = mr_coordination(workspace)?;
+            let (issue_forge, project) = match coordination.as_ref() {
+                Some((coordination_forge, repo)) => (coordination_forge.as_ref(), repo.clone()),
+                None => (forge.as_ref(), first.forge_repo_id()),
+            };
+            let issue = issue_forge.create_issue(CreateIssueParams {
+                project: Some(project.clone()),
+                title: issue_title,
+                description: issue_description,
+                labels: labels.clone(),
+            })?;
+            output::info(&format!(
+                "created tracking issue #{} {}",
+                issue.iid, issue.url
+            ));
+            if coordination.is_some() {
+                state.tracking_issues.push(StoredTrackingIssue {
+                    project: project.as_str().to_string(),
+                    iid: issue.iid,
+                    url: issue.url.clone(),
+                    mrs: created.iter().map(|entry| entry.url.clone()).collect(),
+                });
+            }
+        }
+    }
+
+    if mr_add_trailers_enabled(workspace) {
+        let changeset_id = plan
+            .changeset
+            .as_ref()
+            .map(|changeset| changeset.id.as_str())
+            .unwrap_or("local-changeset");
+        output::warn(&format!(
+            "mr.add_trailers is enabled; add commit trailers manually (e.g. Changeset-ID: {}) before merge",
+            changeset_id
+        ));
+    }
+
+    save_mr_state(workspace, &state)?;
+    run_lifecycle_hook(
+        workspace,
+        LifecycleHook::PostMrCreate,
+        serde_json::json!({ "title": base_title, "mrs": created }),
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MrBranchConflict {
+    repo: RepoId,
+    source_branch: String,
+    target_branch: String,
+}
+
+fn prepare_mr_create_branches(
+    args: &MrCreateArgs,
+    workspace: &Workspace,
+    plan: &mut PlanSummary,
+    ordered: &mut Vec<RepoId>,
+) -> Result<()> {
+    let conflicts = collect_mr_branch_conflicts(workspace, plan, ordered)?;
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    let branch_name = resolve_mr_auto_branch_name(workspace, args, plan, None)?;
+    let mut should_auto_branch = args.auto_branch || args.branch_name.is_some();
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if !should_auto_branch && interactive {
+        let prompt = format!(
+            "{} repositories are on their default branch for MR creation. create and checkout '{}' in those repositories now?",
+            conflicts.len(),
+            branch_name
+        );
+        should_auto_branch = output::confirm(&prompt, false)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    }
+
+    if !should_auto_branch {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            format_mr_branch_conflict_error(&conflicts, Some(branch_name.as_str()))
+        )));
+    }
+
+    auto_branch_conflicted_mr_repos(workspace, &conflicts, branch_name.as_str())?;
+    output::info(&format!(
+        "auto-branch switched {} repositories to {}",
+        conflicts.len(),
+        branch_name
+    ));
+
+    // Branches changed above, so plan from a fresh state.
+    *plan = build_plan_summary(&WorkspaceState::new(workspace), &[], &[])?;
+    *ordered = ordered_plan_repos(plan);
+    Ok(())
+}
+
+fn resolve_mr_auto_branch_name(
+    workspace: &Workspace,
+    args: &MrCreateArgs,
+    plan: &PlanSummary,
+    slug_hint: Option<&str>,
+) -> Result<String> {
+    if let Some(branch_name) = args.branch_name.as_ref() {
+        let trimmed = branch_name.trim();
+        if trimmed.is_empty() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "--branch-name cannot be empty"
+            )));
+        }
+        check_branch_name_policy(workspace, trimmed)?;
+        return Ok(trimmed.to_string());
+    }
+
+    if let Some(changeset) = plan.changeset.as_ref() {
+        let trimmed = changeset.branch.trim();
+        if !trimmed.is_empty() {
+            return Ok(trimmed.to_string());
+        }
+    }
+
+    let slug_source = slug_hint.or(args.title.as_deref()).or_else(|| {
+        plan.changeset
+            .as_ref()
+            .map(|changeset| changeset.title.as_str())
+    });
+    let pattern = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.ticket_pattern.as_deref())
+        .unwrap_or(DEFAULT_TICKET_PATTERN);
+    let ticket = match slug_source {
+        Some(source) => extract_ticket(source, pattern)?,
+        None => None,
+    };
+    let slug = match (slug_source, ticket.as_deref()) {
+        (Some(source), Some(ticket)) => Some(source.replacen(ticket, "", 1)),
+        (source, _) => source.map(str::to_string),
+    };
+    let branch_name = generate_branch_name(workspace, ticket.as_deref(), slug.as_deref());
+    check_branch_name_policy(workspace, &branch_name)?;
+    Ok(branch_name)
+}
+
+const DEFAULT_BRANCH_TEMPLATE: &str = "feature/{user}/{ticket}-{slug}";
+const BRANCH_SLUG_MAX_LEN: usize = 40;
+
+/// Renders `[branching].template` (default `feature/{user}/{ticket}-{slug}`).
+/// Without a slug the current timestamp is used so generated names stay unique.
+fn generate_branch_name(workspace: &Workspace, ticket: Option<&str>, slug: Option<&str>) -> String {
+    let template = workspace
+        .config
+        .branching
+        .as_ref()
+        .and_then(|config| config.template.as_deref())
+        .unwrap_or(DEFAULT_BRANCH_TEMPLATE);
+    let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0,
+    };
+    let user = ["HARMONIA_USER", "USER", "USERNAME"]
+        .iter()
+        .find_map(|key| env::var(key).ok().map(|value| slugify_branch_part(&value)))
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "user".to_string());
+    let mut slug = slug.map(slugify_branch_part).unwrap_or_default();
+    if slug.is_empty() {
+        slug = format!("harmonia-{timestamp}");
+    }
+    render_branch_template(
+        template,
+        &user,
+        ticket.unwrap_or_default().trim(),
+        &slug,
+        timestamp,
+    )
+}
+
+fn render_branch_template(
+    template: &str,
+    user: &str,
+    ticket: &str,
+    slug: &str,
+    timestamp: u64,
+) -> String {
+    let rendered = template
+        .replace("{user}", user)
+        .replace("{ticket}", ticket)
+        .replace("{slug}", slug)
+        .replace("{timestamp}", &timestamp.to_string());
+    // Placeholders that render empty (e.g. no ticket) leave stray separators behind.
+    rendered
+        .split('/')
+        .map(|segment| {
+            let mut cleaned = String::new();
+            for ch in segment.chars() {
+                if ch == '-' && (cleaned.is_empty() || cleaned.ends_with('-')) {
+                    continue;
+                }
+                cleaned.push(ch);
+            }
+            cleaned.trim_end_matches(['-', '.']).to_string()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn slugify_branch_part(value: &str) -> String {
+    let mut slug = String::new();
+    for ch in value.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let mut slug = slug.trim_end_matches('-').to_string();
+    if slug.len() > BRANCH_SLUG_MAX_LEN {
+        slug.truncate(BRANCH_SLUG_MAX_LEN);
+        slug = slug.trim_end_matches('-').to_string();
+    }
+    slug
+}
+
+fn check_branch_name_policy(workspace: &Workspace, name: &str) -> Result<()> {
+    let Some(branching) = workspace.config.branching.as_ref() else {
+        return Ok(());
+    };
+    branch_name_policy_violation(name, branching.pattern.as_deref(), branching.max_length)?.map_or(
+        Ok(()),
+        |violation| {
+            Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "branch name '{}' violates [branching] policy: {}",
+                name, violation
+            ))))
+        },
+    )
+}
+
+fn branch_name_policy_violation(
+    name: &str,
+    pattern: Option<&str>,
+    max_length: Option<usize>,
+) -> Result<Option<String>> {
+    if let Some(max_length) = max_length {
+        if name.chars().count() > max_length {
+            return Ok(Some(format!("longer than max_length {}", max_length)));
+        }
+    }
+    if let Some(pattern) = pattern {
+        let regex = regex::Regex::new(pattern)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        if !regex.is_match(name) {
+            return Ok(Some(format!("does not match pattern '{}'", pattern)));
+        }
+    }
+    Ok(None)
+}
+
+fn auto_branch_conflicted_mr_repos(
+    workspace: &Workspace,
+    conflicts: &[MrBranchConflict],
+    branch_name: &str,
+) -> Result<()> {
+    let mut existing = Vec::new();
+    for conflict in conflicts {
+        let repo = workspace.repos.get(&conflict.repo).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                conflict.repo.as_str()
+            )))
+        })?;
+        let open = open_repo(&repo.path)?;
+        if branch_exists(&open.repo, branch_name)? {
+            existing.push(repo.id.clone());
+        }
+    }
+
+    if !existing.is_empty() {
+        let names = existing
+            .iter()
+            .map(|repo| repo.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "cannot auto-create branch '{}' because it already exists in: {}. choose a different --branch-name or switch branches manually",
+            branch_name, names
+        ))));
+    }
+
+    for conflict in conflicts {
+        let repo = workspace.repos.get(&conflict.repo).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                conflict.repo.as_str()
+            )))
+        })?;
+        let open = open_repo(&repo.path)?;
+        output::git_op(&format!(
+            "checkout -b {} (repo {})",
+            branch_name,
+            repo.id.as_str()
+        ));
+        create_and_checkout_branch(&open.repo, branch_name).map_err(|err| {
+            HarmoniaError::Other(anyhow::anyhow!(format!("{}: {}", repo.id.as_str(), err)))
+        })?;
+        set_branch_target(&open.repo, branch_name, &repo.default_branch)?;
+        output::info(&format!(
+            "created and checked out '{}' in {}",
+            branch_name,
+            repo.id.as_str()
+        ));
+    }
+
+    Ok(())
+}
+
+/// MR target for a source branch: the target recorded when Harmonia created the
+/// branch, falling back to the repo's default branch.
+fn mr_target_branch(repo: &Repo, branch: &str) -> String {
+    open_repo(&repo.path)
+        .ok()
+        .and_then(|open| branch_target(&open.repo, branch).ok().flatten())
+        .unwrap_or_else(|| repo.default_branch.clone())
+}
+
+fn ensure_mr_branches_are_mergeable(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    ordered: &[RepoId],
+) -> Result<()> {
+    let conflicts = collect_mr_branch_conflicts(workspace, plan, ordered)?;
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    Err(HarmoniaError::Other(anyhow::anyhow!(
+        format_mr_branch_conflict_error(&conflicts, None)
+    )))
+}
+
+fn collect_mr_branch_conflicts(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    ordered: &[RepoId],
+) -> Result<Vec<MrBranchConflict>> {
+    let mut conflicts = Vec::new();
+    for repo_id in ordered {
+        let Some(plan_repo) = plan.changed.iter().find(|repo| &repo.id == repo_id) else {
+            continue;
+        };
+        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                repo_id.as_str()
+            )))
+        })?;
+
+        if plan_repo.branch == repo.default_branch {
+            conflicts.push(MrBranchConflict {
+                repo: repo.id.clone(),
+                source_branch: plan_repo.branch.clone(),
+                target_branch: repo.default_branch.clone(),
+            });
+        }
+    }
+
+    Ok(conflicts)
+}
+
+fn format_mr_branch_conflict_error(
+    conflicts: &[MrBranchConflict],
+    suggested_auto_branch: Option<&str>,
+) -> String {
+    let mut message =
+        "cannot create merge requests where source and target branches are the same".to_string();
+    for conflict in conflicts {
+        message.push_str(&format!(
+            "\n  - {} (source: {}, target: {})",
+            conflict.repo.as_str(),
+            conflict.source_branch,
+            conflict.target_branch
+        ));
+    }
+    message.push_str("\ncreate or switch to a feature branch before running `harmonia mr create`");
+    message.push_str("\nhelper: re-run with `harmonia mr create --auto-branch` to create feature branches automatically for affected repos");
+    if let Some(branch) = suggested_auto_branch {
+        message.push_str(&format!(
+            "\ndefault auto-branch name for this run: {}",
+            branch
+        ));
+    }
+    message.push_str("\nexample: harmonia branch <feature-name> --create --changed");
+    message
+}
+
+fn handle_mr_status(args: MrStatusArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    if prefetch_enabled(workspace, args.fresh) {
+        // Without tracked MRs the preview below plans like `plan` does.
+        if tracked.is_empty() {
+            prefetch_repos(workspace, plan_repos(workspace));
+        } else {
+            prefetch_repos(workspace, tracked.iter().map(|item| &item.repo));
+        }
+    }
+    if tracked.is_empty() {
+        let plan = build_plan_summary(state, &[], &[])?;
+        if args.json {
+            let payload = serde_json::json!({
+                "tracked_mrs": [],
+                "changed_repos": plan.changed.iter().map(|repo| repo.id.as_str()).collect::<Vec<_>>(),
+                "merge_order": plan.merge_order.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
+                "wait": args.wait,
+                "timeout_minutes": args.timeout,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&payload)
+                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+            );
+        } else {
+            println!("MR Preview");
+            println!("==========");
+            if plan.changed.is_empty() {
+                println!("No changed repositories detected.");
+            } else {
+                println!("Repositories with local changes:");
+                for repo in &plan.changed {
+                    println!("  {}", repo.id.as_str());
+                }
+                println!();
+                println!("Suggested merge order:");
+                for (index, repo) in plan.merge_order.iter().enumerate() {
+                    println!("  {}. {}", index + 1, repo.as_str());
+                }
+            }
+            output::info("no tracked MRs found for current branches");
+        }
+        return Ok(());
+    }
+
+    let forge = match workspace_forge_client(workspace) {
+        Ok(forge) => forge,
+        Err(err) => {
+            if args.wait {
+                return Err(err);
+            }
+            if args.json {
+                let payload = serde_json::json!({
+                    "tracked_mrs": tracked.iter().map(|item| {
+                        serde_json::json!({
+                            "repo": item.repo.id.as_str(),
+                            "branch": item.entry.branch.as_str(),
+                            "mr_iid": item.entry.iid,
+                            "url": item.entry.url.as_str(),
+                            "state": "unknown",
+                            "ci_state": serde_json::Value::Null,
+                        })
+                    }).collect::<Vec<_>>(),
+                    "wait": false,
+                    "timeout_minutes": args.timeout,
+                    "note": "forge config missing; remote status unavailable",
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload)
+                        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+                );
+            } else {
+                output::warn("forge config missing; showing tracked MR metadata only");
+                for item in tracked {
+                    println!(
+                        "{}: !{} ({})",
+                        item.repo.id.as_str(),
+                        item.entry.iid,
+                        item.entry.url
+                    );
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let deadline = Instant::now()
+        .checked_add(Duration::from_secs(args.timeout.saturating_mul(60)))
+        .unwrap_or_else(Instant::now);
+    let mut timed_out = false;
+    let mut rows = loop {
+        let rows = collect_mr_status_rows(workspace, forge.as_ref(), &tracked)?;
+        let waiting = rows.iter().any(|row| {
+            matches!(
+                row.ci_state,
+                Some(CiState::Pending) | Some(CiState::Running)
+            ) || !row.missing_required_checks.is_empty()
+        });
+        let has_failed_required = rows
+            .iter()
+            .any(|row| !row.failed_required_checks.is_empty());
+        if has_failed_required {
+            break rows;
+        }
+        if !args.wait || !waiting {
+            break rows;
+        }
+        if Instant::now() >= deadline {
+            timed_out = true;
+            break rows;
+        }
+        std::thread::sleep(Duration::from_secs(5));
+    };
+
+    if mr_auto_labels_enabled(workspace) {
+        refresh_auto_mr_labels(workspace, forge.as_ref(), &tracked, &rows)?;
+    }
+    mirror_mr_status_to_tracking_issues(workspace, &store, &rows)?;
+    if args.diff {
+        for row in &mut rows {
+            if let Some(item) = tracked.iter().find(|item| item.repo.id == row.repo) {
+                row.changes = Some(forge.get_mr_changes(&item.forge_repo, &item.entry.mr_id)?);
+            }
+        }
+    }
+    let surface = args.diff.then(|| review_surface(&rows));
+
+    if args.json {
+        let mut payload = serde_json::json!({
+            "tracked_mrs": rows.iter().map(mr_status_row_to_json).collect::<Vec<_>>(),
+            "wait": args.wait,
+            "timeout_minutes": args.timeout,
+            "timed_out": timed_out,
+        });
+        if let (Some(surface), Some(object)) = (surface.as_ref(), payload.as_object_mut()) {
+            object.insert("review_surface".to_string(), surface.to_json());
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
+    } else {
+        println!("Merge Request Status");
+        println!("====================");
+        for row in &rows {
+            let ci_state = row
+                .ci_state
+                .as_ref()
+                .map(ci_state_label)
+                .unwrap_or("unknown");
+            println!(
+                "{}: !{} {} (state: {}, ci: {}, approvals: {})",
+                row.repo.as_str(),
+                row.iid,
+                row.url,
+                mr_state_label(&row.state),
+                ci_state,
+                row.approvals.join(", ")
+            );
+            if let Some(pipeline) = &row.pipeline {
+                println!("  pipeline: {}", pipeline_summary(pipeline));
+            }
+            if let Some(changes) = &row.changes {
+                println!("  diff: {}", ReviewSurface::of(changes).summary());
+            }
+            if missing_approvals(row.required_approvals, row.approvals.len()).is_some() {
+                println!(
+                    "  waiting for approvals: {} of {}",
+                    row.approvals.len(),
+                    row.required_approvals
+                );
+            }
+            for check in row
+                .checks
+                .iter()
+                .filter(|check| check_failed(&check.status))
+            {
+                match &check.url {
+                    Some(url) => println!("  failed check: {} {}", check.name, url),
+                    None => println!("  failed check: {}", check.name),
+                }
+            }
+            if !row.missing_required_checks.is_empty() {
+                println!(
+                    "  waiting required checks: {}",
+                    row.missing_required_checks.join(", ")
+                );
+            }
+            if !row.failed_required_checks.is_empty() {
+                println!(
+                    "  failed required checks: {}",
+                    row.failed_required_checks.join(", ")
+                );
+            }
+        }
+        if let Some(surface) = &surface {
+            println!();
+            println!(
+                "Review surface: {} across {} MR(s)",
+                surface.summary(),
+                surface.mrs
+            );
+        }
+        if timed_out {
+            output::warn("timed out while waiting for CI to settle");
+        }
+    }
+
+    if args.wait
+        && rows
+            .iter()
+            .any(|row| !row.failed_required_checks.is_empty())
+    {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "one or more required checks failed"
+        )));
+    }
+
+    if timed_out {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "timeout waiting for CI to complete"
+        )));
+    }
+
+    Ok(())
+}
+
+fn handle_mr_update(args: MrUpdateArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    if args.description.is_none() && args.labels.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "mr update requires --description and/or --labels"
+        )));
+    }
+
+    let store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    if tracked.is_empty() {
+        output::info("no tracked MRs found for current branches");
+        return Ok(());
+    }
+    let forge = workspace_forge_client(workspace)?;
+
+    for item in tracked {
+        let params = UpdateMrParams {
+            title: None,
+            description: args.description.clone(),
+            labels: if args.labels.is_empty() {
+                None
+            } else {
+                Some(args.labels.clone())
+            },
+            reviewers: None,
+        };
+        let updated = forge.update_mr(&item.forge_repo, &item.entry.mr_id, params)?;
+        output::info(&format!(
+            "updated MR for {}: !{}",
+            item.repo.id.as_str(),
+            updated.iid
+        ));
+    }
+
+    Ok(())
+}
+
+fn handle_mr_merge(args: MrMergeArgs, state: &WorkspaceState) -> Result<()> {
+    if let Some(format) = args.events.as_deref() {
+        events::init("mr merge", format, args.events_file.as_deref())?;
+    }
+    let result = merge_tracked_mrs(args, state);
+    emit_finished(&result);
+    result
+}
+
+fn merge_tracked_mrs(args: MrMergeArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    if tracked.is_empty() {
+        output::info("no tracked MRs found for current branches");
+        return Ok(());
+    }
+    let forge = workspace_forge_client(workspace)?;
+
+    let ordered = tracked_mrs_in_merge_order(workspace, tracked)?;
+    let squash_template = args
+        .squash
+        .then(|| {
+            args.squash_message.clone().or_else(|| {
+                workspace
+                    .config
+                    .mr
+                    .as_ref()
+                    .and_then(|config| config.squash_template.clone())
+            })
+        })
+        .flatten();
+    if args.dry_run {
+        println!("MR Merge Plan");
+        println!("=============");
+        for (index, item) in ordered.iter().enumerate() {
+            println!(
+                "  {}. {} (!{})",
+                index + 1,
+                item.repo.id.as_str(),
+                item.entry.iid
+            );
+        }
+        if let Some(template) = squash_template.as_deref() {
+            println!("squash message template: {}", template.escape_default());
+        }
+        return Ok(());
+    }
+
+    let release_targets: Vec<String> = ordered
+        .iter()
+        .filter(|item| is_release_branch(workspace, &item.entry.target_branch))
+        .map(|item| format!("{} -> {}", item.repo.id.as_str(), item.entry.target_branch))
+        .collect();
+    let release_confirmed = args.yes && args.confirm_release;
+    if !release_targets.is_empty() && !release_confirmed {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "merging into release branches ({}) requires --yes --confirm-release",
+            release_targets.join(", ")
+        ))));
+    }
+
+    let repos: Vec<Repo> = ordered.iter().map(|item| item.repo.clone()).collect();
+    if !confirm_dangerous(
+        workspace,
+        DangerousOperation::MrMerge,
+        "merge tracked MRs in dependency order?",
+        &repos,
+        args.yes,
+    )? {
+        output::info("merge cancelled");
+        return Ok(());
+    }
+
+    events::emit(
+        "started",
+        serde_json::json!({
+            "repos": ordered.iter().map(|item| item.repo.id.as_str()).collect::<Vec<_>>(),
+        }),
+    );
+    let mut merged = Vec::new();
+    for item in ordered {
+        events::emit(
+            "repo_started",
+            serde_json::json!({ "repo": item.repo.id.as_str(), "iid": item.entry.iid }),
+        );
+        let mr = forge.get_mr(&item.forge_repo, &item.entry.mr_id)?;
+        if mr.state == MrState::Merged {
+            output::info(&format!(
+                "MR for {} is already merged; skipping",
+                item.repo.id.as_str()
+            ));
+            events::emit(
+                "repo_finished",
+                serde_json::json!({
+                    "repo": item.repo.id.as_str(),
+                    "iid": item.entry.iid,
+                    "ok": true,
+                    "outcome": "already-merged",
+                }),
+            );
+            continue;
+        }
+        if mr.state == MrState::Closed {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "MR for {} is closed and cannot be merged",
+                item.repo.id.as_str()
+            ))));
+        }
+
+        let requirements = merge_requirements_for(workspace, forge.as_ref(), &item)?;
+        if !args.no_wait {
+            events::emit(
+                "waiting_for_ci",
+                serde_json::json!({
+                    "repo": item.repo.id.as_str(),
+                    "iid": item.entry.iid,
+                    "required_checks": requirements.required_checks,
+                }),
+            );
+            wait_for_ci_success(forge.as_ref(), &item, &requirements.required_checks)?;
+        }
+        if let Some(missing) =
+            missing_approvals(requirements.required_approvals, mr.approvals.len())
+        {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "MR for {} needs {} more approval(s) ({} of {})",
+                item.repo.id.as_str(),
+                missing,
+                mr.approvals.len(),
+                requirements.required_approvals
+            ))));
+        }
+
+        let squash_commit_message = match squash_template.as_deref() {
+            Some(template) => Some(squash_commit_message(
+                workspace, template, &item, &mr.title,
+            )?),
+            None => None,
+        };
+        forge.merge_mr(
+            &item.forge_repo,
+            &item.entry.mr_id,
+            MergeMrParams {
+                squash: args.squash,
+                delete_source_branch: args.delete_branch,
+                squash_commit_message,
+            },
+        )?;
+        if close_issues_enabled(workspace) {
+            verify_closed_issues(workspace, forge.as_ref(), &item, &mr.description);
+        }
+        output::info(&format!(
+            "merged MR for {}: !{}",
+            item.repo.id.as_str(),
+            item.entry.iid
+        ));
+        events::emit(
+            "repo_finished",
+            serde_json::json!({
+                "repo": item.repo.id.as_str(),
+                "iid": item.entry.iid,
+                "ok": true,
+                "outcome": "merged",
+                "url": item.entry.url,
+            }),
+        );
+        merged.push(item.entry);
+    }
+
+    if !merged.is_empty() {
+        run_lifecycle_hook(
+            workspace,
+            LifecycleHook::PostMrMerge,
+            serde_json::json!({ "squash": args.squash, "mrs": merged }),
+        )?;
+    }
+    Ok(())
+}
+
+fn handle_mr_close(args: MrCloseArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let mut store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    if tracked.is_empty() {
+        output::info("no tracked MRs found for current branches");
+        return Ok(());
+    }
+    let forge = workspace_forge_client(workspace)?;
+
+    let repos: Vec<Repo> = tracked.iter().map(|item| item.repo.clone()).collect();
+    if !confirm_dangerous(
+        workspace,
+        DangerousOperation::MrClose,
+        "close tracked MRs for current branches?",
+        &repos,
+        args.yes,
+    )? {
+        output::info("close cancelled");
+        return Ok(());
+    }
+
+    for item in &tracked {
+        forge.close_mr(&item.forge_repo, &item.entry.mr_id)?;
+        output::info(&format!(
+            "closed MR for {}: !{}",
+            item.repo.id.as_str(),
+            item.entry.iid
+        ));
+    }
+
+    for item in tracked {
+        store
+            .entries
+            .retain(|entry| !(entry.repo == item.entry.repo && entry.branch == item.entry.branch));
+        store.closed.push(ClosedMrEntry {
+            repo: item.entry.repo.clone(),
+            branch: item.entry.branch.clone(),
+            iid: item.entry.iid,
+        });
+    }
+    save_mr_state(workspace, &store)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredMrEntry {
+    repo: String,
+    forge_repo: String,
+    /// `[forge.hosts]` entry the project lives on, when not the default forge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    forge_host: Option<String>,
+    branch: String,
+    mr_id: String,
+    iid: u64,
+    url: String,
+    source_branch: String,
+    target_branch: String,
+}
+
+impl StoredMrEntry {
+    fn forge_repo_id(&self) -> RepoId {
+        match self.forge_host.as_deref() {
+            Some(host) => RepoId::on_host(self.forge_repo.clone(), host),
+            None => RepoId::new(self.forge_repo.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MrStateStore {
+    #[serde(default)]
+    entries: Vec<StoredMrEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tracking_issues: Vec<StoredTrackingIssue>,
+    /// MRs closed with `mr close`, so that merging shared state does not
+    /// bring them back.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    closed: Vec<ClosedMrEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ClosedMrEntry {
+    repo: String,
+    branch: String,
+    iid: u64,
+}
+
+/// Tracking issue opened in the `[mr.coordination]` repo, with the URLs of
+/// the MRs whose state `mr status` mirrors into it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredTrackingIssue {
+    project: String,
+    iid: u64,
+    url: String,
+    mrs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct TrackedMr {
+    repo: Repo,
+    forge_repo: RepoId,
+    entry: StoredMrEntry,
+}
+
+#[derive(Debug, Clone)]
+struct MrStatusRow {
+    repo: RepoId,
+    iid: u64,
+    url: String,
+    state: MrState,
+    labels: Vec<String>,
+    ci_state: Option<CiState>,
+    approvals: Vec<String>,
+    /// Approvals needed to merge, from the forge or `[ci].required_approvals`.
+    required_approvals: u32,
+    /// Latest pipeline of the source branch.
+    pipeline: Option<Pipeline>,
+    checks: Vec<CheckRun>,
+    missing_required_checks: Vec<String>,
+    failed_required_checks: Vec<String>,
+    /// Changed files, fetched only for `mr status --diff`.
+    changes: Option<MrChanges>,
+}
+
+/// Workspace hooks around the MR lifecycle.
+#[derive(Debug, Clone, Copy)]
+enum LifecycleHook {
+    PreMrCreate,
+    PostMrCreate,
+    PostMrMerge,
+    PostSubmit,
+}
+
+impl LifecycleHook {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::PreMrCreate => "pre_mr_create",
+            Self::PostMrCreate => "post_mr_create",
+            Self::PostMrMerge => "post_mr_merge",
+            Self::PostSubmit => "post_submit",
+        }
+    }
+
+    fn command(self, workspace: &Workspace) -> Option<&str> {
+        let hooks = workspace.config.hooks.as_ref()?;
+        match self {
+            Self::PreMrCreate => hooks.pre_mr_create.as_deref(),
+            Self::PostMrCreate => hooks.post_mr_create.as_deref(),
+            Self::PostMrMerge => hooks.post_mr_merge.as_deref(),
+            Self::PostSubmit => hooks.post_submit.as_deref(),
+        }
+    }
+}
+
+/// Runs a lifecycle hook from the workspace root. `context` gains a `hook`
+/// field and is written to the hook's stdin as JSON, and also set in
+/// `HARMONIA_HOOK_CONTEXT` for hooks that do not read stdin. A non-zero exit
+/// fails the command; for `pre_mr_create` that is before any MR exists.
+fn run_lifecycle_hook(
+    workspace: &Workspace,
+    hook: LifecycleHook,
+    mut context: serde_json::Value,
+) -> Result<()> {
+    let Some(command) = hook.command(workspace) else {
+        return Ok(());
+    };
+    let parts = split_command(command);
+    if parts.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} hook has no command",
+            hook.as_str()
+        ))));
+    }
+    if let Some(object) = context.as_object_mut() {
+        object.insert("hook".to_string(), serde_json::json!(hook.as_str()));
+    }
+    let payload = serde_json::to_string(&context)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    let mut env_vars = workspace.command_env(None)?;
+    env_vars.push(("HARMONIA_HOOK".to_string(), hook.as_str().to_string()));
+    env_vars.push(("HARMONIA_HOOK_CONTEXT".to_string(), payload.clone()));
+
+    let mut child = std::process::Command::new(&parts[0])
+        .args(&parts[1..])
+        .current_dir(&workspace.root)
+        .envs(env_vars.iter().map(|(key, value)| (key, value)))
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {} hook {:?}", hook.as_str(), parts))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks that ignore stdin may exit before reading it.
+        let _ = std::io::Write::write_all(&mut stdin, payload.as_bytes());
+    }
+    let status = child.wait()?;
+    if status.success() {
+        return Ok(());
+    }
+    let code = status
+        .code()
+        .map(|code| format!("exit code {}", code))
+        .unwrap_or_else(|| "killed by a signal".to_string());
+    Err(HarmoniaError::Other(anyhow::anyhow!(match hook {
+        LifecycleHook::PreMrCreate => format!(
+            "pre_mr_create hook '{}' rejected MR creation ({})",
+            command, code
+        ),
+        _ => format!("{} hook '{}' failed ({})", hook.as_str(), command, code),
+    })))
+}
+
+fn load_mr_state(workspace: &Workspace) -> Result<MrStateStore> {
+    let path = mr_state_path(workspace);
+    if !path.exists() {
+        return Ok(MrStateStore::default());
+    }
+    let raw = fs::read_to_string(&path)?;
+    if raw.trim().is_empty() {
+        return Ok(MrStateStore::default());
+    }
+    serde_json::from_str::<MrStateStore>(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {}",
+            path.display(),
+            err
+        )))
+    })
+}
+
+fn save_mr_state(workspace: &Workspace, state: &MrStateStore) -> Result<()> {
+    let path = mr_state_path(workspace);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(state)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+const DEFAULT_MR_STATE_BRANCH: &str = "harmonia/mr-state";
+const MR_STATE_FILE: &str = "mr-state.json";
+
+fn handle_mr_state_sync(args: MrStateArgs, workspace: &Workspace) -> Result<()> {
+    let mr_config = workspace.config.mr.as_ref();
+    let branch = mr_config
+        .and_then(|config| config.state_branch.clone())
+        .unwrap_or_else(|| DEFAULT_MR_STATE_BRANCH.to_string());
+    let remote = mr_config
+        .and_then(|config| config.state_remote.clone())
+        .unwrap_or_else(|| "origin".to_string());
+    let repo = git2::Repository::open(&workspace.root).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "mr state sync keeps state on a branch of the workspace repo, but {} is not a git repository: {}",
+            workspace.root.display(),
+            err.message()
+        )))
+    })?;
+
+    let (parent, shared) = fetch_shared_mr_state(workspace, &repo, &remote, &branch)?;
+    let local = load_mr_state(workspace)?;
+    let local_count = local.entries.len();
+    let merged = merge_mr_state(local, shared);
+
+    match args.command {
+        MrStateCommand::Pull => {
+            let added = merged.entries.len() as isize - local_count as isize;
+            save_mr_state(workspace, &merged)?;
+            output::info(&format!(
+                "pulled MR state from {}/{}: {} tracked MR(s) ({:+})",
+                remote,
+                branch,
+                merged.entries.len(),
+                added
+            ));
+        }
+        MrStateCommand::Push => {
+            let contents = serde_json::to_string_pretty(&merged)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+            let commit = commit_shared_mr_state(&repo, &contents, parent)?;
+            let command = vec![
+                "git".to_string(),
+                "push".to_string(),
+                "--quiet".to_string(),
+                remote.clone(),
+                format!("{}:refs/heads/{}", commit, branch),
+            ];
+            output::git_op(&format!("push {} {}", remote, branch));
+            run_command_in_repo_quiet(&workspace.root, &command).map_err(|err| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "{} (if someone pushed MR state meanwhile, run `harmonia mr state push` again)",
+                    err
+                )))
+            })?;
+            save_mr_state(workspace, &merged)?;
+            output::info(&format!(
+                "pushed MR state to {}/{}: {} tracked MR(s)",
+                remote,
+                branch,
+                merged.entries.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The shared state on `remote`'s `branch` and the commit it was read from,
+/// or empty state when the branch does not exist yet.
+fn fetch_shared_mr_state(
+    workspace: &Workspace,
+    repo: &git2::Repository,
+    remote: &str,
+    branch: &str,
+) -> Result<(Option<git2::Oid>, MrStateStore)> {
+    let heads = run_command_output_in_repo(
+        &workspace.root,
+        &[
+            "git".to_string(),
+            "ls-remote".to_string(),
+            "--heads".to_string(),
+            remote.to_string(),
+            format!("refs/heads/{}", branch),
+        ],
+    )?;
+    if heads.trim().is_empty() {
+        return Ok((None, MrStateStore::default()));
+    }
+    output::git_op(&format!("fetch {} {}", remote, branch));
+    run_command_in_repo_quiet(
+        &workspace.root,
+        &[
+            "git".to_string(),
+            "fetch".to_string(),
+            "--quiet".to_string(),
+            remote.to_string(),
+            format!("refs/heads/{}", branch),
+        ],
+    )?;
+
+    let git_error = |err: git2::Error| HarmoniaError::Git(anyhow::Error::new(err));
+    let commit = repo
+        .revparse_single("FETCH_HEAD")
+        .and_then(|object| object.peel_to_commit())
+        .map_err(git_error)?;
+    let tree = commit.tree().map_err(git_error)?;
+    let Some(entry) = tree.get_name(MR_STATE_FILE) else {
+        return Ok((Some(commit.id()), MrStateStore::default()));
+    };
+    let blob = repo.find_blob(entry.id()).map_err(git_error)?;
+    let state = serde_json::from_slice::<MrStateStore>(blob.content()).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {} on {}/{}: {}",
+            MR_STATE_FILE, remote, branch, err
+        )))
+    })?;
+    Ok((Some(commit.id()), state))
+}
+
+/// Commits `contents` as the only file of the state branch, on top of
+/// `parent`, without touching the workspace checkout.
+fn commit_shared_mr_state(
+    repo: &git2::Repository,
+    contents: &str,
+    parent: Option<git2::Oid>,
+) -> Result<git2::Oid> {
+    let git_error = |err: git2::Error| HarmoniaError::Git(anyhow::Error::new(err));
+    let blob = repo.blob(contents.as_bytes()).map_err(git_error)?;
+    let mut builder = repo.treebuilder(None).map_err(git_error)?;
+    builder
+        .insert(MR_STATE_FILE, blob, 0o100644)
+        .map_err(git_error)?;
+    let tree = repo
+        .find_tree(builder.write().map_err(git_error)?)
+        .map_err(git_error)?;
+    let parents = match parent {
+        Some(parent) => vec![repo.find_commit(parent).map_err(git_error)?],
+        None => Vec::new(),
+    };
+    let parents: Vec<&git2::Commit> = parents.iter().collect();
+    let signature = repo
+        .signature()
+        .or_else(|_| git2::Signature::now("harmonia", "harmonia@localhost"))
+        .map_err(git_error)?;
+    repo.commit(
+        None,
+        &signature,
+        &signature,
+        "Update MR state",
+        &tree,
+        &parents,
+    )
+    .map_err(git_error)
+}
+
+/// Union of two MR state files. Entries are keyed by repo and branch; when
+/// both track one, the higher iid (the newer MR) wins. MRs that either side
+/// closed are dropped.
+fn merge_mr_state(local: MrStateStore, shared: MrStateStore) -> MrStateStore {
+    let mut merged = MrStateStore::default();
+    for closed in local.closed.into_iter().chain(shared.closed) {
+        if !merged.closed.contains(&closed) {
+            merged.closed.push(closed);
+        }
+    }
+    for entry in local.entries.into_iter().chain(shared.entries) {
+        let is_closed = merged.closed.iter().any(|closed| {
+            closed.repo == entry.repo && closed.branch == entry.branch && closed.iid == entry.iid
+        });
+        if is_closed {
+            continue;
+        }
+        match merged
+            .entries
+            .iter_mut()
+            .find(|existing| existing.repo == entry.repo && existing.branch == entry.branch)
+        {
+            Some(existing) if existing.iid < entry.iid => *existing = entry,
+            Some(_) => {}
+            None => merged.entries.push(entry),
+        }
+    }
+    for issue in local
+        .tracking_issues
+        .into_iter()
+        .chain(shared.tracking_issues)
+    {
+        match merged
+            .tracking_issues
+            .iter_mut()
+            .find(|existing| existing.project == issue.project && existing.iid == issue.iid)
+        {
+            Some(existing) => {
+                for url in issue.mrs {
+                    if !existing.mrs.contains(&url) {
+                        existing.mrs.push(url);
+                    }
+                }
+            }
+            None => merged.tracking_issues.push(issue),
+        }
+    }
+    merged
+}
+
+fn mr_state_path(workspace: &Workspace) -> PathBuf {
+    workspace.root.join(".harmonia").join("mr-state.json")
+}
+
+fn upsert_mr_state_entry(state: &mut MrStateStore, entry: StoredMrEntry) {
+    state
+        .entries
+        .retain(|existing| !(existing.repo == entry.repo && existing.branch == entry.branch));
+    state.entries.push(entry);
+}
+
+fn tracked_mrs_for_current_branches(
+    workspace_state: &WorkspaceState,
+    state: &MrStateStore,
+) -> Result<Vec<TrackedMr>> {
+    let workspace = workspace_state.workspace;
+    let mut repos: Vec<&Repo> = workspace
+        .repos
+        .values()
+        .filter(|repo| !repo.ignored && !repo.external && repo.path.is_dir())
+        .collect();
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut by_repo_branch: HashMap<(String, String), StoredMrEntry> = HashMap::new();
+    for entry in &state.entries {
+        by_repo_branch.insert((entry.repo.clone(), entry.branch.clone()), entry.clone());
+    }
+
+    let mut tracked = Vec::new();
+    for repo in repos {
+        let branch = workspace_state.branch(repo)?;
+        let key = (repo.id.as_str().to_string(), branch);
+        let Some(entry) = by_repo_branch.get(&key).cloned() else {
+            continue;
+        };
+        tracked.push(TrackedMr {
+            repo: repo.clone(),
+            forge_repo: entry.forge_repo_id(),
+            entry,
+        });
+    }
+    Ok(tracked)
+}
+
+fn tracked_mrs_in_merge_order(
+    workspace: &Workspace,
+    tracked: Vec<TrackedMr>,
+) -> Result<Vec<TrackedMr>> {
+    if tracked.len() <= 1 {
+        return Ok(tracked);
+    }
+    let targets: Vec<RepoId> = tracked.iter().map(|item| item.repo.id.clone()).collect();
+    let order =
+        merge_order(&workspace.graph, &workspace.repos, &targets).map_err(HarmoniaError::Other)?;
+    let mut by_repo: HashMap<RepoId, TrackedMr> = tracked
+        .into_iter()
+        .map(|item| (item.repo.id.clone(), item))
+        .collect();
+
+    let mut ordered = Vec::new();
+    for repo_id in order {
+        if let Some(item) = by_repo.remove(&repo_id) {
+            ordered.push(item);
+        }
+    }
+
+    let mut remaining: Vec<TrackedMr> = by_repo.into_values().collect();
+    remaining.sort_by(|a, b| a.repo.id.as_str().cmp(b.repo.id.as_str()));
+    ordered.extend(remaining);
+    Ok(ordered)
+}
+
+fn workspace_forge_client(workspace: &Workspace) -> Result<Box<dyn crate::forge::traits::Forge>> {
+    let config = workspace.config.forge.as_ref().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(
+            "forge config is required (set [forge] in .harmonia/config.toml or .harmonia.toml)"
+        ))
+    })?;
+    if config.hosts.is_empty() {
+        return client_from_forge_config(config);
+    }
+    let mut projects: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for repo in workspace.repos.values() {
+        if let Some((name, _)) = repo_forge_host(workspace, repo) {
+            projects
+                .entry(name)
+                .or_default()
+                .push(forge_project_path(workspace, repo));
+        }
+    }
+    let default = config.clone();
+    let mut router = HostRouter::new(move || client_from_forge_config(&default));
+    for (name, projects) in projects {
+        let entry = &config.hosts[name];
+        router.add_host(name, client_for_forge_host(config, name, entry)?, projects);
+    }
+    Ok(Box::new(router))
+}
+
+/// The `[forge.hosts]` entry for the host of `repo`'s remote, if any.
+fn repo_forge_host<'a>(
+    workspace: &'a Workspace,
+    repo: &Repo,
+) -> Option<(&'a str, &'a crate::config::ForgeHostConfig)> {
+    let forge = workspace.config.forge.as_ref()?;
+    if forge.hosts.is_empty() {
+        return None;
+    }
+    let url = RemoteUrl::parse(&repo.remote_url)?;
+    forge.host_entry(&url.host)
+}
+
+/// Forge settings that apply to `repo`: its host's `[forge.hosts]` entry, else
+/// `[forge]`.
+fn repo_forge_config(workspace: &Workspace, repo: &Repo) -> Option<crate::config::ForgeConfig> {
+    let forge = workspace.config.forge.as_ref()?;
+    Some(match repo_forge_host(workspace, repo) {
+        Some((name, entry)) => forge.for_host(name, entry),
+        None => forge.clone(),
+    })
+}
+
+fn handle_issue(
+    args: IssueArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    match args.command {
+        IssueCommand::List(mut args) => {
+            native_json(&mut args.json);
+            let state = parse_issue_state_filter(&args.state)?;
+            let (forge, project) = issue_target(&workspace, args.repo.as_deref())?;
+            let issues = forge.list_issues(
+                &project,
+                ListIssuesParams {
+                    state,
+                    labels: args.labels,
+                    limit: args.limit,
+                },
+            )?;
+            if args.json {
+                let rows: Vec<serde_json::Value> = issues
+                    .iter()
+                    .map(|issue| {
+                        serde_json::json!({
+                            "iid": issue.iid,
+                            "title": issue.title,
+                            "state": issue_state_label(&issue.state),
+                            "url": issue.url,
+                        })
+                    })
+                    .collect();
+                let json = serde_json::to_string_pretty(&rows)
+                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+                println!("{}", json);
+                return Ok(());
+            }
+            if issues.is_empty() {
+                output::info(&format!("no issues in {}", project.as_str()));
+                return Ok(());
+            }
+            for issue in &issues {
+                println!(
+                    "#{:<6} {:<7} {}",
+                    issue.iid,
+                    issue_state_label(&issue.state),
+                    issue.title
+                );
+            }
+            Ok(())
+        }
+        IssueCommand::Create(args) => {
+            ensure_writable(&workspace, "issue create")?;
+            let (forge, project) = issue_target(&workspace, args.repo.as_deref())?;
+            let issue = forge.create_issue(CreateIssueParams {
+                project: Some(project),
+                title: args.title,
+                description: args.body,
+                labels: args.labels,
+            })?;
+            output::info(&format!("created issue #{} {}", issue.iid, issue.url));
+            Ok(())
+        }
+        IssueCommand::Close(args) => {
+            ensure_writable(&workspace, "issue close")?;
+            let (forge, project) = issue_target(&workspace, args.repo.as_deref())?;
+            if let Some(comment) = args.comment.as_deref() {
+                forge.comment_issue(&project, args.iid, comment)?;
+            }
+            let issue = forge.close_issue(&project, args.iid)?;
+            output::info(&format!("closed issue #{} {}", issue.iid, issue.url));
+            Ok(())
+        }
+        IssueCommand::Comment(args) => {
+            ensure_writable(&workspace, "issue comment")?;
+            let (forge, project) = issue_target(&workspace, args.repo.as_deref())?;
+            forge.comment_issue(&project, args.iid, &args.body)?;
+            output::info(&format!(
+                "commented on issue #{} in {}",
+                args.iid,
+                project.as_str()
+            ));
+            Ok(())
+        }
+    }
+}
+
+/// Forge client and project `harmonia issue` works on: the workspace repo
+/// named by `--repo`, else the `[mr.coordination]` repo.
+fn issue_target(
+    workspace: &Workspace,
+    repo: Option<&str>,
+) -> Result<(Box<dyn crate::forge::traits::Forge>, RepoId)> {
+    if let Some(name) = repo {
+        let repo = workspace
+            .repos
+            .get(&RepoId::new(name.to_string()))
+            .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!(format!("unknown repo {name}"))))?;
+        let project = forge_repo_for_repo(workspace, repo);
+        return Ok((workspace_forge_client(workspace)?, project));
+    }
+    mr_coordination(workspace)?.ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(
+            "no issue repo: pass --repo or set [mr.coordination] repo"
+        ))
+    })
+}
+
+/// `issue list --state`; `all` maps to `None`.
+fn parse_issue_state_filter(raw: &str) -> Result<Option<IssueState>> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "open" | "opened" => Ok(Some(IssueState::Open)),
+        "closed" => Ok(Some(IssueState::Closed)),
+        "all" => Ok(None),
+        other => Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "unknown issue state '{other}' (expected open, closed, or all)"
+        )))),
+    }
+}
+
+fn issue_state_label(state: &IssueState) -> &'static str {
+    match state {
+        IssueState::Open => "open",
+        IssueState::Closed => "closed",
+    }
+}
+
+/// Forge client and project of the `[mr.coordination]` repo, which may be on
+/// a different forge than the workspace repos.
+fn mr_coordination(
+    workspace: &Workspace,
+) -> Result<Option<(Box<dyn crate::forge::traits::Forge>, RepoId)>> {
+    let Some(coordination) = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.coordination.as_ref())
+    else {
+        return Ok(None);
+    };
+    let forge = match coordination.forge.as_ref() {
+        Some(config) => client_from_forge_config(config)?,
+        None => workspace_forge_client(workspace)?,
+    };
+    Ok(Some((
+        forge,
+        RepoId::new(coordination.repo.trim().to_string()),
+    )))
+}
+
+const MR_MIRROR_START: &str = "<!-- harmonia-mr-status -->";
+const MR_MIRROR_END: &str = "<!-- /harmonia-mr-status -->";
+
+/// Replaces the mirrored MR status table in a tracking issue description, or
+/// appends it, leaving the rest of the description as written.
+fn with_mirrored_mr_status(description: &str, rows: &[&MrStatusRow]) -> String {
+    let mut table = format!(
+        "{MR_MIRROR_START}\n### MR status\n\n| Repo | MR | State | CI | Approvals |\n| --- | --- | --- | --- | --- |\n"
+    );
+    for row in rows {
+        let approvals = if row.approvals.is_empty() {
+            "-".to_string()
+        } else {
+            row.approvals.join(", ")
+        };
+        table.push_str(&format!(
+            "| {} | [!{}]({}) | {} | {} | {} |\n",
+            row.repo.as_str(),
+            row.iid,
+            row.url,
+            mr_state_label(&row.state),
+            row.ci_state
+                .as_ref()
+                .map(ci_state_label)
+                .unwrap_or("unknown"),
+            approvals
+        ));
+    }
+    table.push_str(MR_MIRROR_END);
+
+    let existing = description.find(MR_MIRROR_START).and_then(|start| {
+        description[start..]
+            .find(MR_MIRROR_END)
+            .map(|end| (start, start + end + MR_MIRROR_END.len()))
+    });
+    match existing {
+        Some((start, end)) => format!("{}{}{}", &description[..start], table, &description[end..]),
+        None if description.trim().is_empty() => table,
+        None => format!("{}\n\n{}", description.trim_end(), table),
+    }
+}
+
+/// Mirrors MR state into the tracking issues opened in the `[mr.coordination]`
+/// repo, so the coordination forge shows MRs hosted elsewhere.
+fn mirror_mr_status_to_tracking_issues(
+    workspace: &Workspace,
+    store: &MrStateStore,
+    rows: &[MrStatusRow],
+) -> Result<()> {
+    if store.tracking_issues.is_empty() {
+        return Ok(());
+    }
+    let Some((forge, _)) = mr_coordination(workspace)? else {
+        return Ok(());
+    };
+    for issue in &store.tracking_issues {
+        let mirrored: Vec<&MrStatusRow> = rows
+            .iter()
+            .filter(|row| issue.mrs.contains(&row.url))
+            .collect();
+        if mirrored.is_empty() {
+            continue;
+        }
+        let project = RepoId::new(issue.project.clone());
+        let current = forge.get_issue(&project, issue.iid)?;
+        let description = with_mirrored_mr_status(&current.description, &mirrored);
+        if description != current.description {
+            forge.update_issue(&project, issue.iid, &description)?;
+            output::info(&format!(
+                "mirrored MR status to tracking issue #{} {}",
+                issue.iid, issue.url
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The forge project of `repo`, tagged with its `[forge.hosts]` entry when it
+/// has one so the host router cannot confuse it with the same path elsewhere.
+fn forge_repo_for_repo(workspace: &Workspace, repo: &Repo) -> RepoId {
+    let path = forge_project_path(workspace, repo);
+    match repo_forge_host(workspace, repo) {
+        Some((host, _)) => RepoId::on_host(path, host),
+        None => RepoId::new(path),
+    }
+}
+
+fn forge_project_path(workspace: &Workspace, repo: &Repo) -> String {
+    let forge = repo_forge_config(workspace, repo);
+    let web_root = forge
+        .as_ref()
+        .and_then(|forge| {
+            let host = forge.host.as_deref()?;
+            Some(forge_web_root(&forge.forge_type, host))
+        })
+        .unwrap_or_default();
+    if let Some(path) =
+        RemoteUrl::parse(&repo.remote_url).and_then(|url| url.project_path(&web_root))
+    {
+        return path;
+    }
+    if let Some(group) = forge
+        .as_ref()
+        .and_then(|forge| forge.default_group.as_deref())
+    {
+        let group = group.trim().trim_matches('/');
+        if !group.is_empty() {
+            return format!("{}/{}", group, repo.id.as_str());
+        }
+    }
+    repo.id.as_str().to_string()
+}
+
+/// Path prefix the workspace forge is served under, for self-hosted forges
+/// configured with a host such as `https://git.corp/gitlab`.
+fn workspace_web_root(workspace: &Workspace) -> String {
+    workspace
+        .config
+        .forge
+        .as_ref()
+        .and_then(|forge| {
+            let host = forge.host.as_deref()?;
+            Some(forge_web_root(&forge.forge_type, host))
+        })
+        .unwrap_or_default()
+}
+
+fn ordered_plan_repos(plan: &PlanSummary) -> Vec<RepoId> {
+    let changed_ids: HashSet<RepoId> = plan.changed.iter().map(|repo| repo.id.clone()).collect();
+    let mut ordered: Vec<RepoId> = plan
+        .merge_order
+        .iter()
+        .filter(|repo| changed_ids.contains(*repo))
+        .cloned()
+        .collect();
+    let seen: HashSet<RepoId> = ordered.iter().cloned().collect();
+    let mut remaining: Vec<RepoId> = plan
+        .changed
+        .iter()
+        .map(|repo| repo.id.clone())
+        .filter(|repo| !seen.contains(repo))
+        .collect();
+    remaining.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    ordered.extend(remaining);
+    ordered
+}
+
+fn mr_auto_labels_enabled(workspace: &Workspace) -> bool {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.auto_labels)
+        .unwrap_or(false)
+}
+
+const MERGE_ORDER_LABEL_PREFIX: &str = "merge-order:";
+const BLOCKED_BY_LABEL_PREFIX: &str = "blocked-by:";
+const LEAF_CHANGE_LABEL: &str = "leaf-change";
+
+fn is_auto_mr_label(label: &str) -> bool {
+    label.starts_with(MERGE_ORDER_LABEL_PREFIX)
+        || label.starts_with(BLOCKED_BY_LABEL_PREFIX)
+        || label == LEAF_CHANGE_LABEL
+}
+
+/// `[mr].auto_labels` for `repo`: its 1-based position in `order`,
+/// `blocked-by:<repo>` for each repo in `order` it depends on directly whose
+/// MR is not in `merged`, and `leaf-change` when nothing else in `order`
+/// depends on it.
+fn auto_mr_labels(
+    order: &[RepoId],
+    edges: &HashMap<RepoId, Vec<RepoId>>,
+    merged: &HashSet<RepoId>,
+    repo: &RepoId,
+) -> Vec<String> {
+    let mut labels = Vec::new();
+    if let Some(position) = order.iter().position(|id| id == repo) {
+        labels.push(format!("{MERGE_ORDER_LABEL_PREFIX}{}", position + 1));
+    }
+    let deps = edges.get(repo).map(Vec::as_slice).unwrap_or_default();
+    for upstream in order {
+        if upstream != repo && deps.contains(upstream) && !merged.contains(upstream) {
+            labels.push(format!("{BLOCKED_BY_LABEL_PREFIX}{}", upstream.as_str()));
+        }
+    }
+    let has_dependents = order.iter().any(|other| {
+        other != repo
+            && edges
+                .get(other)
+                .is_some_and(|other_deps| other_deps.contains(repo))
+    });
+    if !has_dependents {
+        labels.push(LEAF_CHANGE_LABEL.to_string());
+    }
+    labels
+}
+
+/// Recomputes auto-labels for open tracked MRs, dropping `blocked-by` labels
+/// for upstream MRs that have merged. Other labels are kept.
+fn refresh_auto_mr_labels(
+    workspace: &Workspace,
+    forge: &dyn crate::forge::traits::Forge,
+    tracked: &[TrackedMr],
+    rows: &[MrStatusRow],
+) -> Result<()> {
+    let order: Vec<RepoId> = tracked_mrs_in_merge_order(workspace, tracked.to_vec())?
+        .into_iter()
+        .map(|item| item.repo.id)
+        .collect();
+    let merged: HashSet<RepoId> = rows
+        .iter()
+        .filter(|row| row.state == MrState::Merged)
+        .map(|row| row.repo.clone())
+        .collect();
+    let edges = resolve_internal_edges(&workspace.graph, &workspace.repos).edges;
+    for row in rows {
+        if matches!(row.state, MrState::Merged | MrState::Closed) {
+            continue;
+        }
+        let Some(item) = tracked.iter().find(|item| item.repo.id == row.repo) else {
+            continue;
+        };
+        let mut labels: Vec<String> = row
+            .labels
+            .iter()
+            .filter(|label| !is_auto_mr_label(label))
+            .cloned()
+            .collect();
+        labels.extend(auto_mr_labels(&order, &edges, &merged, &row.repo));
+        if labels == row.labels {
+            continue;
+        }
+        forge.update_mr(
+            &item.forge_repo,
+            &item.entry.mr_id,
+            UpdateMrParams {
+                labels: Some(labels),
+                ..Default::default()
+            },
+        )?;
+        output::info(&format!("updated auto-labels for {}", row.repo.as_str()));
+    }
+    Ok(())
+}
+
+fn merged_labels(workspace: &Workspace, cli_labels: &[String]) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut seen = HashSet::new();
+    for label in workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.labels.as_ref())
+        .into_iter()
+        .flatten()
+        .chain(cli_labels.iter())
+    {
+        if seen.insert(label.clone()) {
+            labels.push(label.clone());
+        }
+    }
+    labels
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LinkBehavior {
+    related: bool,
+    description: bool,
+    issue: bool,
+}
+
+fn effective_reviewer_strategy(
+    workspace: &Workspace,
+    args: &MrCreateArgs,
+) -> Result<ReviewerStrategy> {
+    let (key, value) = match args.reviewer_strategy.as_deref() {
+        Some(value) => ("--reviewer-strategy", value),
+        None => (
+            "mr.reviewer_strategy",
+            workspace
+                .config
+                .mr
+                .as_ref()
+                .and_then(|config| config.reviewer_strategy.as_deref())
+                .unwrap_or("all"),
+        ),
+    };
+    ReviewerStrategy::parse(value).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "invalid {} '{}': expected all, round-robin, or load",
+            key, value
+        )))
+    })
+}
+
+fn reviewer_strategy_label(strategy: ReviewerStrategy) -> &'static str {
+    match strategy {
+        ReviewerStrategy::All => "all",
+        ReviewerStrategy::RoundRobin => "round-robin",
+        ReviewerStrategy::Load => "load",
+    }
+}
+
+fn mr_reviewers_per_mr(workspace: &Workspace) -> usize {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.reviewers_per_mr)
+        .unwrap_or(1)
+}
+
+/// Reviewers for `repo` from its teams' pool. Under `load`, each reviewer's
+/// open reviews are looked up once per run and count against them; a failed
+/// lookup counts as zero rather than blocking MR creation.
+fn assign_team_reviewers(
+    workspace: &Workspace,
+    forge: &dyn crate::forge::traits::Forge,
+    strategy: ReviewerStrategy,
+    balancer: &mut ReviewerBalancer,
+    repo: &Repo,
+) -> Vec<String> {
+    let pool = team_reviewers(&workspace.config, repo.id.as_str());
+    if strategy == ReviewerStrategy::All || pool.is_empty() {
+        return pool;
+    }
+    if strategy == ReviewerStrategy::Load {
+        for reviewer in &pool {
+            if balancer.knows(reviewer) {
+                continue;
+            }
+            let open = forge
+                .count_open_reviews(&forge_repo_for_repo(workspace, repo), reviewer)
+                .unwrap_or_else(|err| {
+                    output::warn(&format!(
+                        "could not count open reviews for {}: {}",
+                        reviewer, err
+                    ));
+                    0
+                });
+            balancer.set_load(reviewer, open);
+        }
+    }
+    balancer.assign(&pool)
+}
+
+fn effective_link_behavior(workspace: &Workspace, args: &MrCreateArgs) -> Result<LinkBehavior> {
+    let configured = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.link_strategy.as_deref())
+        .unwrap_or("all")
+        .to_ascii_lowercase();
+
+    let mut behavior = match configured.as_str() {
+        "related" => LinkBehavior {
+            related: true,
+            description: false,
+            issue: false,
+        },
+        "description" => LinkBehavior {
+            related: false,
+            description: true,
+            issue: false,
+        },
+        "issue" => LinkBehavior {
+            related: false,
+            description: false,
+            issue: true,
+        },
+        "all" => LinkBehavior {
+            related: true,
+            description: true,
+            issue: true,
+        },
+        other => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "invalid mr.link_strategy '{}': expected related, description, issue, or all",
+                other
+            ))))
+        }
+    };
+
+    if args.no_link {
+        behavior.related = false;
+        behavior.description = false;
+    }
+    if args.no_issue {
+        behavior.issue = false;
+    }
+    Ok(behavior)
+}
+
+fn should_create_tracking_issue(
+    workspace: &Workspace,
+    args: &MrCreateArgs,
+    mr_count: usize,
+    behavior: LinkBehavior,
+) -> bool {
+    if !behavior.issue || args.no_issue {
+        return false;
+    }
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.create_tracking_issue)
+        .unwrap_or(mr_count > 1)
+}
+
+fn mr_require_tests_enabled(workspace: &Workspace) -> bool {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.require_tests)
+        .unwrap_or(false)
+}
+
+fn mr_add_trailers_enabled(workspace: &Workspace) -> bool {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.add_trailers)
+        .unwrap_or(false)
+}
+
+fn run_required_mr_tests(workspace: &Workspace, repos: &[RepoId]) -> Result<()> {
+    if repos.is_empty() {
+        return Ok(());
+    }
+    output::info("mr.require_tests=true, running tests for selected repos");
+    for repo_id in repos {
+        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {} in MR test scope",
+                repo_id.as_str()
+            )))
+        })?;
+        let command =
+            resolve_quality_command(workspace, repo, QualityKind::Test).ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "mr.require_tests=true but no test command is configured for {}",
+                    repo.id.as_str()
+                )))
+            })?;
+        run_quality_command(
+            QualityKind::Test,
+            QualityCommand {
+                repo: repo.clone(),
+                command,
+                env: workspace.command_env(Some(repo))?,
+                timeout: None,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+const DEFAULT_TICKET_PATTERN: &str = r"[A-Z][A-Z0-9]+-\d+";
+
+/// Renders the squash commit message for a tracked MR from
+/// `--squash-message` or `[mr].squash_template`.
+fn squash_commit_message(
+    workspace: &Workspace,
+    template: &str,
+    item: &TrackedMr,
+    mr_title: &str,
+) -> Result<String> {
+    let pattern = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.ticket_pattern.as_deref())
+        .unwrap_or(DEFAULT_TICKET_PATTERN);
+    let branch = item.entry.source_branch.as_str();
+    let ticket = extract_ticket(branch, pattern)?;
+    let changeset = load_active_changeset(workspace, &HashSet::from([branch.to_string()]))?;
+    Ok(render_squash_message(
+        template,
+        &SquashMessageContext {
+            title: mr_title,
+            ticket: ticket.as_deref(),
+            changeset: changeset.as_ref().map(|changeset| changeset.id.as_str()),
+            repo: item.repo.id.as_str(),
+            branch,
+            iid: item.entry.iid,
+        },
+    ))
+}
+
+struct SquashMessageContext<'a> {
+    title: &'a str,
+    ticket: Option<&'a str>,
+    changeset: Option<&'a str>,
+    repo: &'a str,
+    branch: &'a str,
+    iid: u64,
+}
+
+/// Like `title_template`, a template that needs a ticket or changeset the
+/// branch does not have falls back to the bare MR title.
+fn render_squash_message(template: &str, context: &SquashMessageContext) -> String {
+    if (context.ticket.is_none() && template.contains("{ticket}"))
+        || (context.changeset.is_none() && template.contains("{changeset}"))
+    {
+        return context.title.to_string();
+    }
+    template
+        .replace("{ticket}", context.ticket.unwrap_or_default())
+        .replace("{changeset}", context.changeset.unwrap_or_default())
+        .replace("{title}", context.title)
+        .replace("{repo}", context.repo)
+        .replace("{branch}", context.branch)
+        .replace("{iid}", &context.iid.to_string())
+        .trim()
+        .to_string()
+}
+
+/// `(?:^|/)(\d+)[-_]` matches the issue number in branches like
+/// `feature/123-login` or `123_fix-crash`.
+const DEFAULT_ISSUE_PATTERN: &str = r"(?:^|/)(\d+)[-_]";
+const DEFAULT_CLOSING_KEYWORD: &str = "Closes";
+const ISSUE_CLOSE_RETRIES: usize = 3;
+
+fn close_issues_enabled(workspace: &Workspace) -> bool {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.close_issues)
+        .unwrap_or(false)
+}
+
+fn closing_keyword(workspace: &Workspace) -> &str {
+    workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.closing_keyword.as_deref())
+        .unwrap_or(DEFAULT_CLOSING_KEYWORD)
+}
+
+/// Closing lines for `repo`'s MR description. References come from the
+/// changeset's `closes` list and the issue number in the branch name. A
+/// `<repo>#N` reference goes to that repo's MR as `#N`; every other reference
+/// goes to the last MR in merge order, so the issue closes once the whole
+/// changeset has landed.
+fn closing_issue_lines(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    repo: &Repo,
+) -> Result<Vec<String>> {
+    if !close_issues_enabled(workspace) {
+        return Ok(Vec::new());
+    }
+    let mut refs: Vec<String> = plan
+        .changeset
+        .as_ref()
+        .map(|changeset| changeset.closes.clone())
+        .unwrap_or_default();
+    let pattern = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.issue_pattern.as_deref())
+        .unwrap_or(DEFAULT_ISSUE_PATTERN);
+    let branch = plan
+        .changed
+        .iter()
+        .find(|item| item.id == repo.id)
+        .map(|item| item.branch.as_str())
+        .or_else(|| {
+            plan.changeset
+                .as_ref()
+                .map(|changeset| changeset.branch.as_str())
+        });
+    if let Some(number) = branch
+        .map(|branch| extract_ticket(branch, pattern))
+        .transpose()?
+        .flatten()
+    {
+        let reference = if number.contains('#') {
+            number
+        } else {
+            format!("#{number}")
+        };
+        refs.push(reference);
+    }
+
+    Ok(closing_lines_for_repo(
+        &refs,
+        closing_keyword(workspace),
+        &repo.id,
+        plan.merge_order.last(),
+        |id| workspace.repos.contains_key(id),
+    ))
+}
+
+fn closing_lines_for_repo(
+    refs: &[String],
+    keyword: &str,
+    repo: &RepoId,
+    last: Option<&RepoId>,
+    is_workspace_repo: impl Fn(&RepoId) -> bool,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for reference in refs {
+        let reference = reference.trim();
+        let Some((project, number)) = reference.rsplit_once('#') else {
+            continue;
+        };
+        let target = RepoId::new(project.to_string());
+        let line = if is_workspace_repo(&target) {
+            (target == *repo).then(|| format!("{keyword} #{number}"))
+        } else {
+            (last == Some(repo)).then(|| format!("{keyword} {reference}"))
+        };
+        if let Some(line) = line.filter(|line| !lines.contains(line)) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// `(project, iid)` for each closing line in an MR description; `project` is
+/// `None` for references to the MR's own project.
+fn parse_closing_refs(description: &str, keyword: &str) -> Vec<(Option<String>, u64)> {
+    description
+        .lines()
+        .filter_map(|line| {
+            let (word, reference) = line.trim().split_once(' ')?;
+            if !word.eq_ignore_ascii_case(keyword) {
+                return None;
+            }
+            let (project, number) = reference.trim().rsplit_once('#')?;
+            let iid = number.parse().ok()?;
+            let project = (!project.is_empty()).then(|| project.to_string());
+            Some((project, iid))
+        })
+        .collect()
+}
+
+/// Warns about issues a merged MR was meant to close that are still open, for
+/// example because the MR targeted a branch other than the default branch.
+fn verify_closed_issues(
+    workspace: &Workspace,
+    forge: &dyn crate::forge::traits::Forge,
+    item: &TrackedMr,
+    description: &str,
+) {
+    for (project, iid) in parse_closing_refs(description, closing_keyword(workspace)) {
+        let (target, reference) = match project {
+            Some(project) => (RepoId::new(project.clone()), format!("{project}#{iid}")),
+            None => (item.forge_repo.clone(), format!("#{iid}")),
+        };
+        // Forges close issues asynchronously after the merge.
+        let mut state = forge.get_issue(&target, iid).map(|issue| issue.state);
+        for _ in 0..ISSUE_CLOSE_RETRIES {
+            if !matches!(state, Ok(IssueState::Open)) {
+                break;
+            }
+            std::thread::sleep(Duration::from_secs(2));
+            state = forge.get_issue(&target, iid).map(|issue| issue.state);
+        }
+        match state {
+            Ok(IssueState::Closed) => {}
+            Ok(_) => output::warn(&format!(
+                "{} !{} merged but issue {} is still open (closing keywords only apply to the default branch)",
+                item.repo.id.as_str(),
+                item.entry.iid,
+                reference
+            )),
+            Err(err) => output::warn(&format!(
+                "could not check issue {} for {}: {}",
+                reference,
+                item.repo.id.as_str(),
+                err
+            )),
+        }
+    }
+}
+
+fn resolve_mr_title(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    title_override: Option<String>,
+) -> Result<String> {
+    // An explicit --title is used as given; templates only shape derived titles.
+    if let Some(title) = title_override {
+        return Ok(title);
+    }
+    let branch = plan
+        .changeset
+        .as_ref()
+        .map(|changeset| changeset.branch.clone())
+        .or_else(|| plan.changed.first().map(|repo| repo.branch.clone()))
+        .unwrap_or_else(|| "changeset".to_string());
+    let mr_config = workspace.config.mr.as_ref();
+    let template = mr_config.and_then(|config| config.title_template.as_deref());
+    let pattern = mr_config
+        .and_then(|config| config.ticket_pattern.as_deref())
+        .unwrap_or(DEFAULT_TICKET_PATTERN);
+    let ticket = extract_ticket(&branch, pattern)?;
+
+    let title = plan
+        .changeset
+        .as_ref()
+        .map(|changeset| changeset.title.clone())
+        .unwrap_or_else(|| match template {
+            Some(_) => title_from_branch(&branch, ticket.as_deref()),
+            None => format!("changeset: {branch}"),
+        });
+
+    Ok(match template {
+        Some(template) => render_mr_title(template, &title, ticket.as_deref(), &branch),
+        None => title,
+    })
+}
+
+fn extract_ticket(branch: &str, pattern: &str) -> Result<Option<String>> {
+    let regex =
+        regex::Regex::new(pattern).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    Ok(regex.captures(branch).map(|captures| {
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|value| value.as_str().to_string())
+            .unwrap_or_default()
+    }))
+}
+
+fn title_from_branch(branch: &str, ticket: Option<&str>) -> String {
+    let name = branch.rsplit('/').next().unwrap_or(branch);
+    let name = match ticket {
+        Some(ticket) => name.replacen(ticket, "", 1),
+        None => name.to_string(),
+    };
+    let words: Vec<&str> = name
+        .split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        branch.to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+fn render_mr_title(template: &str, title: &str, ticket: Option<&str>, branch: &str) -> String {
+    if ticket.is_none() && template.contains("{ticket}") {
+        return title.to_string();
+    }
+    template
+        .replace("{ticket}", ticket.unwrap_or_default())
+        .replace("{title}", title)
+        .replace("{branch}", branch)
+}
+
+fn build_mr_description(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    repo: &Repo,
+    description_text: &str,
+) -> Result<String> {
+    let description = description_text.trim().to_string();
+    let closing = closing_issue_lines(workspace, plan, repo)?;
+    let mrs = changeset_template_rows(workspace, plan, None);
+    let impact = dependency_impact_rows(workspace, plan)?;
+    let context = serde_json::json!({
+        "repo": repo.id.as_str(),
+        "description": description,
+        "title": plan.changeset.as_ref().map(|changeset| changeset.title.as_str()).unwrap_or(""),
+        "changeset": {
+            "id": plan.changeset.as_ref().map(|changeset| changeset.id.as_str()).unwrap_or(""),
+            "branch": plan.changeset.as_ref().map(|changeset| changeset.branch.as_str()).unwrap_or(""),
+            "repos": plan.changed.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(),
+            "merge_order": plan.merge_order.iter().map(|id| id.as_str()).collect::<Vec<_>>(),
+            "mrs": mrs,
+            "dependency_impact": impact,
+            "repo_summary": plan
+                .changed
+                .iter()
+                .find(|item| item.id == repo.id)
+                .and_then(|item| item.changeset_summary.as_deref())
+                .unwrap_or(""),
+        },
+    });
+
+    if let Some(path) = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.template.as_deref())
+        .map(|path| resolve_template_path(workspace, path))
+    {
+        let body = render_template_file(&path, &context)?;
+        return Ok(with_closing_lines(body, &closing));
+    }
+
+    let mut body = String::new();
+    if !description.is_empty() {
+        body.push_str(&description);
+        body.push_str("\n\n");
+    }
+    body.push_str("---\n\n");
+    body.push_str("## Coordinated Changeset\n\n");
+    body.push_str("Merge order:\n");
+    for (index, repo_id) in plan.merge_order.iter().enumerate() {
+        body.push_str(&format!("{}. {}\n", index + 1, repo_id.as_str()));
+    }
+    if let Some(summary) = plan
+        .changed
+        .iter()
+        .find(|item| item.id == repo.id)
+        .and_then(|item| item.changeset_summary.as_deref())
+    {
+        body.push_str("\nRepo summary:\n");
+        body.push_str(summary);
+        body.push('\n');
+    }
+    push_dependency_impact(&mut body, &impact);
+    Ok(with_closing_lines(body, &closing))
+}
+
+fn with_closing_lines(body: String, closing: &[String]) -> String {
+    if closing.is_empty() {
+        return body;
+    }
+    format!("{}\n\n{}\n", body.trim_end(), closing.join("\n"))
+}
+
+fn build_tracking_issue_description(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    created: &[StoredMrEntry],
+    cli_description: Option<&str>,
+) -> Result<String> {
+    let description = cli_description.unwrap_or("").trim().to_string();
+    let title = plan
+        .changeset
+        .as_ref()
+        .map(|changeset| changeset.title.clone())
+        .unwrap_or_default();
+    let mrs = changeset_template_rows(workspace, plan, Some(created));
+    let impact = dependency_impact_rows(workspace, plan)?;
+    let context = serde_json::json!({
+        "title": title,
+        "description": description,
+        "now": format!("{:?}", std::time::SystemTime::now()),
+        "changeset": {
+            "id": plan.changeset.as_ref().map(|changeset| changeset.id.as_str()).unwrap_or(""),
+            "branch": plan
+                .changeset
+                .as_ref()
+                .map(|changeset| changeset.branch.as_str())
+                .unwrap_or(""),
+            "mrs": mrs,
+            "dependency_impact": impact,
+        },
+    });
+
+    if let Some(path) = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.issue_template.as_deref())
+        .map(|path| resolve_template_path(workspace, path))
+    {
+        return render_template_file(&path, &context);
+    }
+
+    let mut body = String::new();
+    if !description.is_empty() {
+        body.push_str(&description);
+        body.push_str("\n\n");
+    }
+    body.push_str("This issue tracks coordinated merge requests:\n");
+    for entry in created {
+        body.push_str(&format!(
+            "- {}: !{} ({})\n",
+            entry.repo, entry.iid, entry.url
+        ));
+    }
+    push_dependency_impact(&mut body, &impact);
+    Ok(body)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct DependencyImpactRow {
+    /// Dependent repo whose constraint changes.
+    repo: String,
+    dependency: String,
+    from: String,
+    to: String,
+    /// Whether the dependent is itself part of the changeset.
+    in_changeset: bool,
+}
+
+/// Version bumps carried by the changeset: changed repos whose version on
+/// their branch differs from the one on their MR target (`origin/<target>`
+/// when it exists).
+fn changeset_version_bumps(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+) -> Result<HashMap<RepoId, Version>> {
+    let mut bumped = HashMap::new();
+    for item in &plan.changed {
+        let Some(repo) = workspace.repos.get(&item.id) else {
+            continue;
+        };
+        let Some(current) = read_repo_version(repo, workspace)? else {
+            continue;
+        };
+        let target = mr_target_branch(repo, &item.branch);
+        let base = read_repo_version_at(repo, workspace, &format!("origin/{target}"))
+            .or_else(|| read_repo_version_at(repo, workspace, &target));
+        match base {
+            Some(base) if base.raw != current.raw => {
+                bumped.insert(item.id.clone(), current);
+            }
+            _ => {}
+        }
+    }
+    Ok(bumped)
+}
+
+/// Constraint changes the changeset's version bumps imply for dependents,
+/// computed the same way `version bump --cascade` rewrites them.
+fn dependency_impact_rows(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+) -> Result<Vec<DependencyImpactRow>> {
+    let bumped = changeset_version_bumps(workspace, plan)?;
+    if bumped.is_empty() {
+        return Ok(Vec::new());
+    }
+    let changed: HashSet<&RepoId> = plan.changed.iter().map(|item| &item.id).collect();
+    let mut rows = Vec::new();
+    for update in build_dependency_updates(workspace, &bumped)? {
+        let from = workspace
+            .graph
+            .edges
+            .get(&update.repo)
+            .and_then(|deps| deps.iter().find(|dep| dep.name == update.dependency))
+            .map(|dep| dep.constraint.raw.trim().to_string())
+            .unwrap_or_default();
+        if from == update.constraint {
+            continue;
+        }
+        rows.push(DependencyImpactRow {
+            in_changeset: changed.contains(&update.repo),
+            repo: update.repo.as_str().to_string(),
+            dependency: update.dependency,
+            from,
+            to: update.constraint,
+        });
+    }
+    rows.sort_by(|a, b| {
+        a.repo
+            .cmp(&b.repo)
+            .then_with(|| a.dependency.cmp(&b.dependency))
+    });
+    Ok(rows)
+}
+
+fn push_dependency_impact(body: &mut String, rows: &[DependencyImpactRow]) {
+    if rows.is_empty() {
+        return;
+    }
+    body.push_str("\n## Dependency Impact\n\n");
+    body.push_str("| Dependent | Dependency | From | To |\n");
+    body.push_str("| --- | --- | --- | --- |\n");
+    for row in rows {
+        let from = if row.from.is_empty() {
+            "-".to_string()
+        } else {
+            format!("`{}`", row.from)
+        };
+        body.push_str(&format!(
+            "| {} | {} | {} | `{}` |\n",
+            row.repo, row.dependency, from, row.to
+        ));
+    }
+}
+
+fn with_related_mr_links(
+    description: &str,
+    created: &[StoredMrEntry],
+    current_repo: &str,
+    changeset_id: Option<&str>,
+) -> String {
+    let start_marker = "<!-- harmonia:related:start -->";
+    let end_marker = "<!-- harmonia:related:end -->";
+    let base = if let Some(start) = description.find(start_marker) {
+        let suffix = &description[start..];
+        if suffix.contains(end_marker) {
+            let mut truncated = String::from(&description[..start]);
+            truncated = truncated.trim_end().to_string();
+            truncated
+        } else {
+            description.trim_end().to_string()
+        }
+    } else {
+        description.trim_end().to_string()
+    };
+
+    let mut out = base;
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(start_marker);
+    out.push('\n');
+    out.push_str("## Related Merge Requests\n");
+    if let Some(changeset_id) = changeset_id {
+        out.push_str(&format!("Changeset: `{}`\n", changeset_id));
+    }
+    out.push('\n');
+    for entry in created {
+        let current = if entry.repo == current_repo {
+            " (this MR)"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "- {}: !{} ({}){}\n",
+            entry.repo, entry.iid, entry.url, current
+        ));
+    }
+    out.push_str(end_marker);
+    out.push('\n');
+    out
+}
+
+fn changeset_template_rows(
+    workspace: &Workspace,
+    plan: &PlanSummary,
+    created: Option<&[StoredMrEntry]>,
+) -> Vec<serde_json::Value> {
+    let package_lookup = package_map(&workspace.repos);
+    let selected: HashSet<RepoId> = plan.changed.iter().map(|item| item.id.clone()).collect();
+    let merge_order_map: HashMap<RepoId, usize> = plan
+        .merge_order
+        .iter()
+        .enumerate()
+        .map(|(index, repo)| (repo.clone(), index + 1))
+        .collect();
+
+    let created_lookup: HashMap<String, &StoredMrEntry> = created
+        .unwrap_or_default()
+        .iter()
+        .map(|entry| (entry.repo.clone(), entry))
+        .collect();
+
+    let mut rows = Vec::new();
+    for item in &plan.changed {
+        let dependencies = internal_dependencies_for(&workspace.graph, &item.id)
+            .into_iter()
+            .filter_map(|dep| package_lookup.get(&dep.name).cloned())
+            .filter(|dep| selected.contains(dep))
+            .map(|dep| dep.as_str().to_string())
+            .collect::<Vec<_>>();
+        let mut dependencies = dependencies;
+        dependencies.sort();
+        dependencies.dedup();
+
+        let mut dependents = direct_dependents(&workspace.graph, &workspace.repos, &item.id)
+            .into_iter()
+            .filter(|dep| selected.contains(dep))
+            .map(|dep| dep.as_str().to_string())
+            .collect::<Vec<_>>();
+        dependents.sort();
+        dependents.dedup();
+
+        let (link, status, status_emoji) = if let Some(entry) = created_lookup.get(item.id.as_str())
+        {
+            (
+                format!("[!{}]({})", entry.iid, entry.url),
+                "open".to_string(),
+                "🟢".to_string(),
+            )
+        } else {
+            (
+                "pending".to_string(),
+                "pending".to_string(),
+                "🟡".to_string(),
+            )
+        };
+
+        rows.push(serde_json::json!({
+            "repo": item.id.as_str(),
+            "link": link,
+            "status": status,
+            "status_emoji": status_emoji,
+            "merge_order": merge_order_map.get(&item.id).copied().unwrap_or(0),
+            "dependencies": dependencies,
+            "dependents": dependents,
+            "summary": item.changeset_summary.as_deref().unwrap_or(""),
+            "commits": workspace
+                .repos
+                .get(&item.id)
+                .map(|repo| branch_commit_rows(workspace, repo, &item.branch))
+                .unwrap_or_default(),
+        }));
+    }
+
+    rows.sort_by(|a, b| {
+        let a_order = a
+            .get("merge_order")
+            .and_then(|value| value.as_u64())
+            .unwrap_or(u64::MAX);
+        let b_order = b
+            .get("merge_order")
+            .and_then(|value| value.as_u64())
+            .unwrap_or(u64::MAX);
+        a_order.cmp(&b_order)
+    });
+    rows
+}
+
+/// Commits on the repo's current branch that its MR target does not have,
+/// oldest first, for `changeset.mrs[].commits` in MR and issue templates.
+/// Empty when git cannot compare the two.
+fn branch_commit_rows(workspace: &Workspace, repo: &Repo, branch: &str) -> Vec<serde_json::Value> {
+    let target = mr_target_branch(repo, branch);
+    let remote_target = format!("origin/{target}");
+    let verify = vec![
+        "git".to_string(),
+        "rev-parse".to_string(),
+        "--verify".to_string(),
+        "--quiet".to_string(),
+        remote_target.clone(),
+    ];
+    let base = if run_command_output_in_repo(&repo.path, &verify).is_ok() {
+        remote_target
+    } else {
+        target
+    };
+    let command = vec![
+        "git".to_string(),
+        "log".to_string(),
+        "--reverse".to_string(),
+        "--format=%H%x1f%an%x1f%ae%x1f%s".to_string(),
+        format!("{base}..HEAD"),
+    ];
+    let Ok(output) = run_command_output_in_repo(&repo.path, &command) else {
+        return Vec::new();
+    };
+
+    let web_url = repo_web_url(&repo.remote_url, &workspace_web_root(workspace));
+    let github = workspace
+        .config
+        .forge
+        .as_ref()
+        .map(|forge| forge.forge_type == "github")
+        .unwrap_or_else(|| web_url.as_deref().is_some_and(|url| url.contains("github")));
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\u{1f}');
+            let sha = fields.next()?.trim();
+            let author = fields.next()?;
+            let email = fields.next()?;
+            let subject = fields.next()?;
+            if sha.is_empty() {
+                return None;
+            }
+            Some(serde_json::json!({
+                "sha": sha,
+                "short_sha": &sha[..sha.len().min(8)],
+                "subject": subject,
+                "author": author,
+                "author_email": email,
+                "url": web_url.as_deref().map(|url| commit_web_url(url, github, sha)),
+            }))
+        })
+        .collect()
+}
+
+/// Browser URL of the project behind `remote_url`, or `None` for local paths
+/// and `file://` remotes.
+fn repo_web_url(remote_url: &str, web_root: &str) -> Option<String> {
+    Some(RemoteUrl::parse(remote_url)?.web_url(web_root))
+}
+
+fn commit_web_url(project_url: &str, github: bool, sha: &str) -> String {
+    if github {
+        format!("{project_url}/commit/{sha}")
+    } else {
+        format!("{project_url}/-/commit/{sha}")
+    }
+}
+
+/// Sample `changeset.mrs[]` row for `template render`, with every field
+/// `changeset_template_rows` fills in.
+fn sample_template_mr_row(repo: &str, order: u64, dependencies: &[&str]) -> serde_json::Value {
+    serde_json::json!({
+        "repo": repo,
+        "link": format!("[!{}](https://gitlab.example.com/acme/{}/-/merge_requests/{})", order + 10, repo, order + 10),
+        "status": "open",
+        "status_emoji": "🟢",
+        "merge_order": order,
+        "dependencies": dependencies,
+        "dependents": if dependencies.is_empty() { vec!["api"] } else { Vec::new() },
+        "summary": format!("Update {} for the auth flow.", repo),
+        "commits": [{
+            "sha": "0123456789abcdef0123456789abcdef01234567",
+            "short_sha": "0123456",
+            "subject": format!("feat({}): auth flow", repo),
+            "author": "Ada Lovelace",
+            "author_email": "ada@example.com",
+            "url": format!("https://gitlab.example.com/acme/{}/-/commit/0123456", repo),
+        }],
+    })
+}
+
+fn sample_dependency_impact_rows() -> serde_json::Value {
+    serde_json::json!([{
+        "repo": "api",
+        "dependency": "core",
+        "from": "^1.2",
+        "to": "^1.3",
+        "in_changeset": true,
+    }])
+}
+
+/// Context `[mr].template` is rendered with (see `build_mr_description`),
+/// filled with sample values.
+fn sample_mr_template_context() -> serde_json::Value {
+    serde_json::json!({
+        "repo": "api",
+        "description": "Adds the auth flow across core and api.",
+        "title": "PROJ-123: auth flow",
+        "changeset": {
+            "id": "auth-flow",
+            "branch": "feature/PROJ-123-auth-flow",
+            "repos": ["core", "api"],
+            "merge_order": ["core", "api"],
+            "mrs": [
+                sample_template_mr_row("core", 1, &[]),
+                sample_template_mr_row("api", 2, &["core"]),
+            ],
+            "dependency_impact": sample_dependency_impact_rows(),
+            "repo_summary": "Update api for the auth flow.",
+        },
+    })
+}
+
+/// Context `[mr].issue_template` is rendered with (see
+/// `build_tracking_issue_description`), filled with sample values.
+fn sample_issue_template_context() -> serde_json::Value {
+    serde_json::json!({
+        "title": "PROJ-123: auth flow",
+        "description": "Adds the auth flow across core and api.",
+        "now": "SystemTime { tv_sec: 1767225600, tv_nsec: 0 }",
+        "changeset": {
+            "id": "auth-flow",
+            "branch": "feature/PROJ-123-auth-flow",
+            "mrs": [
+                sample_template_mr_row("core", 1, &[]),
+                sample_template_mr_row("api", 2, &["core"]),
+            ],
+            "dependency_impact": sample_dependency_impact_rows(),
+        },
+    })
+}
+
+fn resolve_template_path(workspace: &Workspace, path: &str) -> PathBuf {
+    let candidate = PathBuf::from(path);
+    if candidate.is_absolute() {
+        candidate
+    } else {
+        workspace.root.join(candidate)
+    }
+}
+
+fn collect_mr_status_rows(
+    workspace: &Workspace,
+    forge: &dyn crate::forge::traits::Forge,
+    tracked: &[TrackedMr],
+) -> Result<Vec<MrStatusRow>> {
+    let mut rows = Vec::new();
+    for item in tracked {
+        let mr = forge.get_mr(&item.forge_repo, &item.entry.mr_id)?;
+        let ci = forge.get_ci_status(&item.forge_repo, &item.entry.source_branch)?;
+        let ci_state = ci.state.clone();
+        let requirements = merge_requirements_for(workspace, forge, item)?;
+        let required_result = evaluate_required_checks(&requirements.required_checks, &ci.checks);
+        let (missing_required_checks, failed_required_checks) = match required_result {
+            RequiredChecksState::Pending(names) => (names, Vec::new()),
+            RequiredChecksState::Failed(names) => (Vec::new(), names),
+            RequiredChecksState::Satisfied => (Vec::new(), Vec::new()),
+        };
+        rows.push(MrStatusRow {
+            repo: item.repo.id.clone(),
+            iid: mr.iid,
+            url: mr.url,
+            state: mr.state,
+            labels: mr.labels,
+            ci_state: Some(ci_state),
+            approvals: mr
+                .approvals
+                .iter()
+                .map(|user| user.username.clone())
+                .collect(),
+            required_approvals: requirements.required_approvals,
+            pipeline: ci.pipelines.first().cloned(),
+            checks: ci.checks,
+            missing_required_checks,
+            failed_required_checks,
+            changes: None,
+        });
+    }
+    rows.sort_by(|a, b| a.repo.as_str().cmp(b.repo.as_str()));
+    Ok(rows)
+}
+
+fn wait_for_ci_success(
+    forge: &dyn crate::forge::traits::Forge,
+    item: &TrackedMr,
+    required_checks: &[String],
+) -> Result<()> {
+    let timeout_minutes = item
+        .repo
+        .config
+        .as_ref()
+        .and_then(|config| config.ci.as_ref())
+        .and_then(|ci| ci.timeout_minutes)
+        .unwrap_or(30);
+    let deadline = Instant::now()
+        .checked_add(Duration::from_secs(timeout_minutes.saturating_mul(60)))
+        .unwrap_or_else(Instant::now);
+    loop {
+        let status = forge.get_ci_status(&item.forge_repo, &item.entry.source_branch)?;
+        let required_result = evaluate_required_checks(required_checks, &status.checks);
+        match status.state {
+            CiState::Success | CiState::Skipped => match required_result {
+                RequiredChecksState::Satisfied => return Ok(()),
+                RequiredChecksState::Pending(names) => {
+                    if Instant::now() >= deadline {
+                        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                            "timeout waiting for required checks for {}: {}",
+                            item.repo.id.as_str(),
+                            names.join(", ")
+                        ))));
+                    }
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+                RequiredChecksState::Failed(names) => {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "required checks failed for {}: {}",
+                        item.repo.id.as_str(),
+                        names.join(", ")
+                    ))))
+                }
+            },
+            CiState::Failed | CiState::Canceled => {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "CI is not passing for {} (state: {})",
+                    item.repo.id.as_str(),
+                    ci_state_label(&status.state)
+                ))))
+            }
+            CiState::Pending | CiState::Running => {
+                if let RequiredChecksState::Failed(names) = required_result {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "required checks failed for {}: {}",
+                        item.repo.id.as_str(),
+                        names.join(", ")
+                    ))));
+                }
+                if Instant::now() >= deadline {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "timeout waiting for CI for {}",
+                        item.repo.id.as_str()
+                    ))));
+                }
+                std::thread::sleep(Duration::from_secs(5));
+            }
+        }
+    }
+}
+
+/// Checks and approvals `item` needs before it can merge. `required_checks`
+/// and `required_approvals` under `[ci]` in the repo's `.harmonia.toml`
+/// override what the forge reports for the target branch.
+fn merge_requirements_for(
+    workspace: &Workspace,
+    forge: &dyn crate::forge::traits::Forge,
+    item: &TrackedMr,
+) -> Result<MergeRequirements> {
+    let ci = item
+        .repo
+        .config
+        .as_ref()
+        .and_then(|config| config.ci.as_ref());
+    let local_checks = ci.and_then(|ci| ci.required_checks.clone());
+    let local_approvals = ci.and_then(|ci| ci.required_approvals);
+    let discover = workspace
+        .config
+        .mr
+        .as_ref()
+        .and_then(|config| config.forge_requirements)
+        .unwrap_or(true);
+    let discovered = if discover && (local_checks.is_none() || local_approvals.is_none()) {
+        forge.get_merge_requirements(&item.forge_repo, &item.entry.target_branch)?
+    } else {
+        MergeRequirements::default()
+    };
+
+    let mut required_checks = local_checks.unwrap_or(discovered.required_checks);
+    required_checks.sort();
+    required_checks.dedup();
+    Ok(MergeRequirements {
+        required_checks,
+        required_approvals: local_approvals.unwrap_or(discovered.required_approvals),
+    })
+}
+
+/// Approvals still missing, or `None` when `approvals` meets `required`.
+fn missing_approvals(required: u32, approvals: usize) -> Option<u32> {
+    let given = u32::try_from(approvals).unwrap_or(u32::MAX);
+    (given < required).then(|| required - given)
+}
+
+#[derive(Debug)]
+enum RequiredChecksState {
+    Satisfied,
+    Pending(Vec<String>),
+    Failed(Vec<String>),
+}
+
+fn evaluate_required_checks(
+    required: &[String],
+    checks: &[crate::forge::CheckRun],
+) -> RequiredChecksState {
+    if required.is_empty() {
+        return RequiredChecksState::Satisfied;
+    }
+
+    let mut pending = Vec::new();
+    let mut failed = Vec::new();
+    for required_name in required {
+        let matched = checks
+            .iter()
+            .find(|check| check.name == *required_name)
+            .map(|check| check.status.as_str());
+        match matched {
+            Some("success") | Some("passed") | Some("skipped") => {}
+            Some(
+                "pending"
+                | "running"
+                | "created"
+                | "preparing"
+                | "manual"
+                | "scheduled"
+                | "waiting_for_resource",
+            ) => pending.push(required_name.clone()),
+            Some(_) => failed.push(required_name.clone()),
+            None => pending.push(required_name.clone()),
+        }
+    }
+
+    if !failed.is_empty() {
+        return RequiredChecksState::Failed(failed);
+    }
+    if !pending.is_empty() {
+        return RequiredChecksState::Pending(pending);
+    }
+    RequiredChecksState::Satisfied
+}
+
+/// `failed https://… (3m05s, by alice)` for the `mr status` pipeline line.
+fn pipeline_summary(pipeline: &Pipeline) -> String {
+    let mut summary = pipeline.status.clone();
+    if let Some(url) = &pipeline.url {
+        summary.push(' ');
+        summary.push_str(url);
+    }
+    let details: Vec<String> = pipeline
+        .duration_secs
+        .map(|secs| format_duration_ms(secs.saturating_mul(1000)))
+        .into_iter()
+        .chain(
+            pipeline
+                .triggered_by
+                .as_ref()
+                .map(|user| format!("by {user}")),
+        )
+        .collect();
+    if !details.is_empty() {
+        summary.push_str(&format!(" ({})", details.join(", ")));
+    }
+    summary
+}
+
+fn check_failed(status: &str) -> bool {
+    matches!(
+        status.to_ascii_lowercase().as_str(),
+        "failed" | "failure" | "error" | "canceled" | "cancelled" | "timed_out"
+    )
+}
+
+/// Changed files and lines of one MR, or summed over every MR in
+/// `mr status --diff`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ReviewSurface {
+    mrs: usize,
+    files: usize,
+    additions: u64,
+    deletions: u64,
+}
+
+impl ReviewSurface {
+    fn of(changes: &MrChanges) -> Self {
+        Self {
+            mrs: 1,
+            files: changes.files.len(),
+            additions: changes.additions(),
+            deletions: changes.deletions(),
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} file(s), +{} -{}",
+            self.files, self.additions, self.deletions
+        )
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "mrs": self.mrs,
+            "files": self.files,
+            "additions": self.additions,
+            "deletions": self.deletions,
+        })
+    }
+}
+
+fn review_surface(rows: &[MrStatusRow]) -> ReviewSurface {
+    let mut total = ReviewSurface::default();
+    for changes in rows.iter().filter_map(|row| row.changes.as_ref()) {
+        let surface = ReviewSurface::of(changes);
+        total.mrs += surface.mrs;
+        total.files += surface.files;
+        total.additions += surface.additions;
+        total.deletions += surface.deletions;
+    }
+    total
+}
+
+fn mr_status_row_to_json(row: &MrStatusRow) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "repo": row.repo.as_str(),
+        "mr_iid": row.iid,
+        "url": row.url,
+        "state": mr_state_label(&row.state),
+        "ci_state": row.ci_state.as_ref().map(ci_state_label),
+        "approvals": row.approvals,
+        "required_approvals": row.required_approvals,
+        "missing_approvals": missing_approvals(row.required_approvals, row.approvals.len()).unwrap_or(0),
+        "pipeline": row.pipeline.as_ref().map(|pipeline| serde_json::json!({
+            "id": pipeline.id,
+            "status": pipeline.status,
+            "url": pipeline.url,
+            "duration_secs": pipeline.duration_secs,
+            "triggered_by": pipeline.triggered_by,
+        })),
+        "checks": row.checks.iter().map(|check| {
+            serde_json::json!({
+                "name": check.name,
+                "status": check.status,
+                "url": check.url,
+            })
+        }).collect::<Vec<_>>(),
+        "missing_required_checks": row.missing_required_checks,
+        "failed_required_checks": row.failed_required_checks,
+    });
+    if let (Some(changes), Some(object)) = (row.changes.as_ref(), value.as_object_mut()) {
+        object.insert(
+            "changes".to_string(),
+            serde_json::json!({
+                "files": changes.files.iter().map(|file| serde_json::json!({
+                    "path": file.path,
+                    "additions": file.additions,
+                    "deletions": file.deletions,
+                })).collect::<Vec<_>>(),
+                "additions": changes.additions(),
+                "deletions": changes.deletions(),
+            }),
+        );
+    }
+    value
+}
+
+/// One tracked MR in `mr report`, in merge order.
+#[derive(Debug)]
+struct MrReportRow {
+    repo: RepoId,
+    branch: String,
+    target_branch: String,
+    iid: u64,
+    url: String,
+    state: String,
+    ci_state: String,
+    approvals: Vec<String>,
+    checks: Vec<(String, String)>,
+    blockers: Vec<String>,
+}
+
+fn handle_mr_report(args: MrReportArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let store = load_mr_state(workspace)?;
+    let tracked = tracked_mrs_for_current_branches(state, &store)?;
+    let ordered = tracked_mrs_in_merge_order(workspace, tracked)?;
+    let live = if ordered.is_empty() {
+        None
+    } else {
+        match workspace_forge_client(workspace) {
+            Ok(forge) => Some(collect_mr_status_rows(workspace, forge.as_ref(), &ordered)?),
+            Err(_) => {
+                output::warn("forge config missing; report shows tracked MR metadata only");
+                None
+            }
+        }
+    };
+    let rows = mr_report_rows(workspace, &ordered, live.as_deref());
+    let plan = build_plan_summary(state, &[], &[])?;
+    let title = plan
+        .changeset
+        .as_ref()
+        .map(|changeset| changeset.title.clone())
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| "Merge queue".to_string());
+    let violations: Vec<String> = plan
+        .constraints
+        .violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "{} -> {} [{}]",
+                violation.from_repo.as_str(),
+                violation.to_repo.as_str(),
+                violation.violation_type.as_str()
+            )
+        })
+        .collect();
+    let generated = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let report = if args.html {
+        render_mr_report_html(&title, &rows, &violations, generated)
+    } else {
+        render_mr_report_text(&title, &rows, &violations)
+    };
+    match args.output {
+        Some(path) => {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, report)?;
+            output::info(&format!("wrote MR report to {}", path.display()));
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
+/// Report rows in merge order. Without live forge rows, state and CI are
+/// reported as `tracked` and `unknown`.
+fn mr_report_rows(
+    workspace: &Workspace,
+    ordered: &[TrackedMr],
+    live: Option<&[MrStatusRow]>,
+) -> Vec<MrReportRow> {
+    let packages = package_map(&workspace.repos);
+    let mut rows: Vec<MrReportRow> = Vec::new();
+    for item in ordered {
+        let status = live.and_then(|rows| rows.iter().find(|row| row.repo == item.repo.id));
+        let mut row = MrReportRow {
+            repo: item.repo.id.clone(),
+            branch: item.entry.source_branch.clone(),
+            target_branch: item.entry.target_branch.clone(),
+            iid: item.entry.iid,
+            url: item.entry.url.clone(),
+            state: "tracked".to_string(),
+            ci_state: "unknown".to_string(),
+            approvals: Vec::new(),
+            checks: Vec::new(),
+            blockers: Vec::new(),
+        };
+        if let Some(status) = status {
+            row.url = status.url.clone();
+            row.state = mr_state_label(&status.state).to_string();
+            row.ci_state = status
+                .ci_state
+                .as_ref()
+                .map(ci_state_label)
+                .unwrap_or("unknown")
+                .to_string();
+            row.approvals = status.approvals.clone();
+            row.checks = status
+                .checks
+                .iter()
+                .map(|check| (check.name.clone(), check.status.clone()))
+                .collect();
+            match status.state {
+                MrState::Closed => row.blockers.push("MR is closed".to_string()),
+                MrState::Draft => row.blockers.push("MR is a draft".to_string()),
+                _ => {}
+            }
+            if matches!(
+                status.ci_state,
+                Some(CiState::Failed) | Some(CiState::Canceled)
+            ) {
+                row.blockers.push(format!("CI {}", row.ci_state));
+            }
+            if !status.failed_required_checks.is_empty() {
+                row.blockers.push(format!(
+                    "required checks failed: {}",
+                    status.failed_required_checks.join(", ")
+                ));
+            }
+            if !status.missing_required_checks.is_empty() {
+                row.blockers.push(format!(
+                    "waiting for required checks: {}",
+                    status.missing_required_checks.join(", ")
+                ));
+            }
+            if let Some(missing) =
+                missing_approvals(status.required_approvals, status.approvals.len())
+                    .filter(|_| status.state != MrState::Merged)
+            {
+                row.blockers
+                    .push(format!("waiting for {missing} more approval(s)"));
+            }
+        }
+        if row.state != "merged" {
+            // Earlier MRs this repo depends on have to merge first.
+            let dependencies: HashSet<&RepoId> =
+                internal_dependencies_for(&workspace.graph, &item.repo.id)
+                    .iter()
+                    .filter_map(|dep| packages.get(&dep.name))
+                    .collect();
+            for earlier in &rows {
+                if earlier.state != "merged" && dependencies.contains(&earlier.repo) {
+                    row.blockers
+                        .push(format!("waiting on {} to merge", earlier.repo.as_str()));
+                }
+            }
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+fn render_mr_report_text(title: &str, rows: &[MrReportRow], violations: &[String]) -> String {
+    let mut out = format!("{title}\n{}\n", "=".repeat(title.chars().count()));
+    if rows.is_empty() {
+        out.push_str("No tracked MRs for current branches.\n");
+    }
+    for (index, row) in rows.iter().enumerate() {
+        out.push_str(&format!(
+            "{}. {} !{} {} -> {} (state: {}, ci: {}, approvals: {})\n",
+            index + 1,
+            row.repo.as_str(),
+            row.iid,
+            row.branch,
+            row.target_branch,
+            row.state,
+            row.ci_state,
+            if row.approvals.is_empty() {
+                "none".to_string()
+            } else {
+                row.approvals.join(", ")
+            }
+        ));
+        for blocker in &row.blockers {
+            out.push_str(&format!("   blocked: {blocker}\n"));
+        }
+    }
+    if !violations.is_empty() {
+        out.push_str("\nConstraint violations:\n");
+        for violation in violations {
+            out.push_str(&format!("  - {violation}\n"));
+        }
+    }
+    out
+}
+
+/// A self-contained HTML page (inline CSS, no scripts) for publishing to a
+/// static pages site.
+fn render_mr_report_html(
+    title: &str,
+    rows: &[MrReportRow],
+    violations: &[String],
+    generated: u64,
+) -> String {
+    let (year, month, day) = crate::core::version::civil_from_days((generated / 86_400) as i64);
+    let seconds = generated % 86_400;
+    let generated = format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3_600,
+        (seconds % 3_600) / 60
+    );
+    let merged = rows.iter().filter(|row| row.state == "merged").count();
+    let blocked = rows.iter().filter(|row| !row.blockers.is_empty()).count();
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", html_escape(title)));
+    out.push_str(
+        "<style>\n\
+         body { font-family: system-ui, sans-serif; margin: 2rem; color: #1f2328; }\n\
+         table { border-collapse: collapse; width: 100%; }\n\
+         th, td { border-bottom: 1px solid #d0d7de; padding: 0.5rem; text-align: left; vertical-align: top; }\n\
+         .state { border-radius: 1rem; padding: 0.1rem 0.6rem; font-size: 0.85rem; }\n\
+         .state-merged { background: #ddf4e4; } .state-open { background: #dbeafe; }\n\
+         .state-draft { background: #eeeeee; } .state-closed { background: #fde2e1; }\n\
+         .state-tracked { background: #fff4ce; }\n\
+         .ci-success { color: #1a7f37; } .ci-failed, .ci-canceled { color: #cf222e; }\n\
+         .ci-pending, .ci-running { color: #9a6700; }\n\
+         .blockers { color: #cf222e; margin: 0; padding-left: 1rem; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    out.push_str(&format!("<h1>{}</h1>\n", html_escape(title)));
+    out.push_str(&format!(
+        "<p>{} MRs, {} merged, {} blocked. Generated {}.</p>\n",
+        rows.len(),
+        merged,
+        blocked,
+        generated
+    ));
+    if rows.is_empty() {
+        out.push_str("<p>No tracked MRs for current branches.</p>\n");
+    } else {
+        out.push_str(
+            "<table>\n<thead><tr><th>#</th><th>Repo</th><th>MR</th><th>Branch</th>\
+             <th>State</th><th>CI</th><th>Approvals</th><th>Blockers</th></tr></thead>\n<tbody>\n",
+        );
+        for (index, row) in rows.iter().enumerate() {
+            let mr = if row.url.is_empty() {
+                format!("!{}", row.iid)
+            } else {
+                format!("<a href=\"{}\">!{}</a>", html_escape(&row.url), row.iid)
+            };
+            let checks = row
+                .checks
+                .iter()
+                .map(|(name, status)| format!("{}: {}", html_escape(name), html_escape(status)))
+                .collect::<Vec<_>>()
+                .join("; ");
+            let blockers = if row.blockers.is_empty() {
+                "&mdash;".to_string()
+            } else {
+                format!(
+                    "<ul class=\"blockers\">{}</ul>",
+                    row.blockers
+                        .iter()
+                        .map(|blocker| format!("<li>{}</li>", html_escape(blocker)))
+                        .collect::<String>()
+                )
+            };
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{} &rarr; {}</td>\
+                 <td><span class=\"state state-{}\">{}</span></td>\
+                 <td class=\"ci-{}\" title=\"{}\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                index + 1,
+                html_escape(row.repo.as_str()),
+                mr,
+                html_escape(&row.branch),
+                html_escape(&row.target_branch),
+                html_escape(&row.state),
+                html_escape(&row.state),
+                html_escape(&row.ci_state),
+                checks,
+                html_escape(&row.ci_state),
+                if row.approvals.is_empty() {
+                    "&mdash;".to_string()
+                } else {
+                    html_escape(&row.approvals.join(", "))
+                },
+                blockers
+            ));
+        }
+        out.push_str("</tbody>\n</table>\n");
+    }
+    if !violations.is_empty() {
+        out.push_str("<h2>Constraint violations</h2>\n<ul>\n");
+        for violation in violations {
+            out.push_str(&format!("<li>{}</li>\n", html_escape(violation)));
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn mr_state_label(state: &MrState) -> &'static str {
+    match state {
+        MrState::Open => "open",
+        MrState::Merged => "merged",
+        MrState::Closed => "closed",
+        MrState::Draft => "draft",
+    }
+}
+
+fn ci_state_label(state: &CiState) -> &'static str {
+    match state {
+        CiState::Pending => "pending",
+        CiState::Running => "running",
+        CiState::Success => "success",
+        CiState::Failed => "failed",
+        CiState::Canceled => "canceled",
+        CiState::Skipped => "skipped",
+    }
+}
+
+fn handle_shell(
+    args: ShellArgs,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = load_workspace(workspace_root, config_path)?;
+    let mut repos: Vec<Repo> = if args.repos.is_empty() {
+        workspace
+            .repos
+            .values()
+            .filter(|repo| !repo.ignored && repo.path.is_dir())
+            .cloned()
+            .collect()
+    } else {
+        select_repos(&workspace, &args.repos, None, false, true)?
+            .into_iter()
+            .filter(|repo| !repo.ignored && repo.path.is_dir())
+            .collect()
+    };
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut path_prefixes = Vec::new();
+    let mut pythonpath_prefixes = Vec::new();
+    for repo in &repos {
+        let bin_dir = repo.path.join("bin");
+        if bin_dir.is_dir() {
+            path_prefixes.push(bin_dir);
+        }
+        let src_dir = repo.path.join("src");
+        if src_dir.is_dir() {
+            pythonpath_prefixes.push(src_dir);
+        }
+    }
+
+    let path_value = compose_shell_env_value("PATH", path_prefixes)?;
+    let pythonpath_value = compose_shell_env_value("PYTHONPATH", pythonpath_prefixes)?;
+    let env_vars = workspace.command_env(None)?;
+
+    if let Some(command) = args.command.as_deref() {
+        let split = split_command(command);
+        if split.is_empty() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(
+                "shell command cannot be empty"
+            )));
+        }
+        return run_shell_command_with_env(
+            &workspace.root,
+            &split,
+            &workspace.root,
+            path_value.as_deref(),
+            pythonpath_value.as_deref(),
+            &env_vars,
+        );
+    }
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        let workspace_value = workspace.root.to_string_lossy().to_string();
+        for (key, value) in &env_vars {
+            println!("export {}={}", key, shell_single_quote(value));
+        }
+        println!(
+            "export HARMONIA_WORKSPACE={}",
+            shell_single_quote(&workspace_value)
+        );
+        if let Some(path) = path_value.as_ref() {
+            println!("export PATH={}", shell_single_quote(path));
+        }
+        if let Some(pythonpath) = pythonpath_value.as_ref() {
+            println!("export PYTHONPATH={}", shell_single_quote(pythonpath));
+        }
+        return Ok(());
+    }
+
+    let shell = env::var("SHELL").unwrap_or_else(|_| {
+        if cfg!(windows) {
+            "cmd".to_string()
+        } else {
+            "sh".to_string()
+        }
+    });
+
+    let mut cmd = std::process::Command::new(&shell);
+    cmd.current_dir(&workspace.root);
+    cmd.envs(env_vars.iter().map(|(key, value)| (key, value)));
+    cmd.env("HARMONIA_WORKSPACE", &workspace.root);
+    if let Some(path) = path_value {
+        cmd.env("PATH", path);
+    }
+    if let Some(pythonpath) = pythonpath_value {
+        cmd.env("PYTHONPATH", pythonpath);
+    }
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to launch shell '{}'", shell))?;
+    if status.success() {
+        return Ok(());
+    }
+
+    Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+        "shell '{}' exited unsuccessfully",
+        shell
+    ))))
+}
+
+fn handle_completion(args: CompletionArgs) -> Result<()> {
+    let mut cmd = Cli::command();
+    let bin_name = cmd.get_name().to_string();
+    clap_complete::generate(args.shell, &mut cmd, bin_name, &mut std::io::stdout());
+    Ok(())
+}
+
+/// Values several steps of one command derive from the working trees:
+/// versions, and the current branch and status of each repo. Each is computed
+/// on first use and reused for the rest of the invocation, so build a new
+/// state after switching branches, committing, or editing version files.
+/// `dispatch` builds one for the commands that read them and hands it to the
+/// handlers, along with the dependency graph of the workspace it loaded.
+struct WorkspaceState<'a> {
+    workspace: &'a Workspace,
+    versions: OnceCell<HashMap<RepoId, Version>>,
+    branches: RefCell<HashMap<RepoId, String>>,
+    statuses: RefCell<HashMap<RepoId, StatusSummary>>,
+}
+
+impl<'a> WorkspaceState<'a> {
+    fn new(workspace: &'a Workspace) -> Self {
+        Self {
+            workspace,
+            versions: OnceCell::new(),
+            branches: RefCell::new(HashMap::new()),
+            statuses: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn graph(&self) -> &crate::graph::DependencyGraph {
+        &self.workspace.graph
+    }
+
+    fn versions(&self) -> Result<&HashMap<RepoId, Version>> {
+        if let Some(versions) = self.versions.get() {
+            return Ok(versions);
+        }
+        let versions = collect_versions(self.workspace)?;
+        Ok(self.versions.get_or_init(|| versions))
+    }
+
+    fn branch(&self, repo: &Repo) -> Result<String> {
+        if let Some(branch) = self.branches.borrow().get(&repo.id) {
+            return Ok(branch.clone());
+        }
+        let open = open_repo(&repo.path)?;
+        let branch = current_branch(&open.repo)?;
+        self.branches
+            .borrow_mut()
+            .insert(repo.id.clone(), branch.clone());
+        Ok(branch)
+    }
+
+    fn status(&self, repo: &Repo) -> Result<StatusSummary> {
+        if let Some(status) = self.statuses.borrow().get(&repo.id) {
+            return Ok(status.clone());
+        }
+        let open = open_repo(&repo.path)?;
+        let status = repo_status(&open.repo)?;
+        self.statuses
+            .borrow_mut()
+            .insert(repo.id.clone(), status.clone());
+        Ok(status)
+    }
+
+    fn changed(&self, repo: &Repo) -> Result<bool> {
+        repo_is_changed(self.workspace, repo, &self.status(repo)?)
+    }
+}
+
+#[derive(Debug)]
+struct PlanSummary {
+    changed: Vec<PlanChangedRepo>,
+    merge_order: Vec<RepoId>,
+    constraints: ConstraintReport,
+    recommendations: Vec<String>,
+    changeset: Option<PlanChangeset>,
+}
+
+#[derive(Debug)]
+struct PlanChangedRepo {
+    id: RepoId,
+    branch: String,
+    status: StatusSummary,
+    diff_stat: String,
+    changeset_summary: Option<String>,
+    /// `[teams]` entries that own the repo.
+    teams: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct PlanChangeset {
+    id: String,
+    title: String,
+    description: String,
+    branch: String,
+    repo_summaries: HashMap<RepoId, String>,
+    closes: Vec<String>,
+}
+
+fn build_plan_summary(
+    state: &WorkspaceState,
+    include: &[String],
+    exclude: &[String],
+) -> Result<PlanSummary> {
+    let workspace = state.workspace;
+    let mut include_ids = resolve_plan_repo_ids(workspace, include, "include")?;
+    let exclude_ids = resolve_plan_repo_ids(workspace, exclude, "exclude")?;
+    let selected = selection_filter(workspace)?;
+
+    for repo_id in &include_ids {
+        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo {}",
+                repo_id.as_str()
+            )))
+        })?;
+        if !repo.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "repo {} not cloned",
+                repo_id.as_str()
+            ))));
+        }
+    }
+
+    let mut repos: Vec<&Repo> = workspace
+        .repos
+        .values()
+        .filter(|repo| !repo.ignored && !repo.external && repo.path.is_dir())
+        .collect();
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    let mut candidates = Vec::new();
+    let mut all_branches = HashSet::new();
+    let mut changed_branches = HashSet::new();
+    for repo in repos {
+        if exclude_ids.contains(&repo.id)
+            || selected
+                .as_ref()
+                .is_some_and(|selected| !selected.contains(&repo.id))
+        {
+            continue;
+        }
+        let branch = state.branch(repo)?;
+        let status = state.status(repo)?;
+        let changed = state.changed(repo)?;
+        all_branches.insert(branch.clone());
+        if changed {
+            changed_branches.insert(branch.clone());
+        }
+        let diff_stat = git_shortstat_output(&repo.path);
+        candidates.push((repo.clone(), branch, status, changed, diff_stat));
+    }
+
+    let branch_scope = if changed_branches.is_empty() {
+        &all_branches
+    } else {
+        &changed_branches
+    };
+    let changeset = load_active_changeset(workspace, branch_scope)?;
+    if let Some(changeset) = changeset.as_ref() {
+        for repo_id in changeset.repo_summaries.keys() {
+            include_ids.insert(repo_id.clone());
+        }
+    }
 
-        rows.push(serde_json::json!({
-            "repo": item.id.as_str(),
-            "link": link,
-            "status": status,
-            "status_emoji": status_emoji,
-            "merge_order": merge_order_map.get(&item.id).copied().unwrap_or(0),
-            "dependencies": dependencies,
-            "dependents": dependents,
-            "summary": item.changeset_summary.as_deref().unwrap_or(""),
-        }));
+    let mut changed = Vec::new();
+    for (repo, branch, status, changed_repo, diff_stat) in candidates {
+        if !changed_repo && !include_ids.contains(&repo.id) {
+            continue;
+        }
+        changed.push(PlanChangedRepo {
+            id: repo.id.clone(),
+            teams: repo_teams(&workspace.config, repo.id.as_str()),
+            branch,
+            status,
+            diff_stat,
+            changeset_summary: changeset
+                .as_ref()
+                .and_then(|changeset| changeset.repo_summaries.get(&repo.id))
+                .filter(|summary| !summary.trim().is_empty())
+                .cloned(),
+        });
+    }
+
+    let merge_order = if changed.is_empty() {
+        Vec::new()
+    } else {
+        let targets: Vec<RepoId> = changed.iter().map(|repo| repo.id.clone()).collect();
+        merge_order(&workspace.graph, &workspace.repos, &targets).map_err(HarmoniaError::Other)?
+    };
+    let constraints = check_constraints(&workspace.graph, &workspace.repos, state.versions()?);
+    let impact = impact_scores(&workspace.graph, &workspace.repos);
+    let recommendations = plan_recommendations(&changed, &constraints, &impact);
+
+    Ok(PlanSummary {
+        changed,
+        merge_order,
+        constraints,
+        recommendations,
+        changeset,
+    })
+}
+
+fn resolve_plan_repo_ids(
+    workspace: &Workspace,
+    repos: &[String],
+    flag: &str,
+) -> Result<HashSet<RepoId>> {
+    let mut ids = HashSet::new();
+    for repo_name in repos {
+        if is_repo_pattern(repo_name) {
+            let matched: Vec<RepoId> = workspace
+                .repos
+                .keys()
+                .filter(|id| repo_pattern_matches(repo_name, id.as_str()))
+                .cloned()
+                .collect();
+            if matched.is_empty() {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "no repos match '{}' passed to --{}",
+                    repo_name, flag
+                ))));
+            }
+            ids.extend(matched);
+            continue;
+        }
+        let repo_id = RepoId::new(repo_name.clone());
+        if !workspace.repos.contains_key(&repo_id) {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown repo '{}' passed to --{}",
+                repo_name, flag
+            ))));
+        }
+        ids.insert(repo_id);
+    }
+    Ok(ids)
+}
+
+fn load_active_changeset(
+    workspace: &Workspace,
+    branches: &HashSet<String>,
+) -> Result<Option<PlanChangeset>> {
+    let files = load_changeset_files(&workspace.root, &workspace.config)?;
+    let selected = select_active_changeset(&files, branches)?;
+    let Some(selected) = selected else {
+        return Ok(None);
+    };
+    normalize_changeset(workspace, selected).map(Some)
+}
+
+fn normalize_changeset(workspace: &Workspace, file: ChangesetFile) -> Result<PlanChangeset> {
+    let mut repo_summaries = HashMap::new();
+    for repo in &file.repos {
+        let repo_id = RepoId::new(repo.repo.clone());
+        let known = workspace.repos.get(&repo_id).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "changeset '{}' references unknown repo '{}'",
+                file.id, repo.repo
+            )))
+        })?;
+        if known.ignored {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "changeset '{}' references {} repo '{}'",
+                file.id,
+                if known.paused.is_some() {
+                    "paused"
+                } else {
+                    "ignored"
+                },
+                repo.repo
+            ))));
+        }
+        if known.external {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "changeset '{}' references external repo '{}'",
+                file.id, repo.repo
+            ))));
+        }
+        if !known.path.is_dir() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "changeset '{}' references repo '{}' which is not cloned",
+                file.id, repo.repo
+            ))));
+        }
+        repo_summaries.insert(repo_id, repo.summary.clone());
+    }
+
+    Ok(PlanChangeset {
+        id: file.id,
+        title: file.title,
+        description: file.description,
+        branch: file.branch,
+        repo_summaries,
+        closes: file.closes,
+    })
+}
+
+fn git_shortstat_output(repo_path: &Path) -> String {
+    let command = vec![
+        "git".to_string(),
+        "diff".to_string(),
+        "--shortstat".to_string(),
+    ];
+    let output = match run_command_output_in_repo(repo_path, &command) {
+        Ok(output) => output,
+        Err(_) => return "diff stat unavailable".to_string(),
+    };
+
+    let trimmed = output.trim();
+    if trimmed.is_empty() {
+        "no textual diff stats".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn print_plan_summary(plan: &PlanSummary) {
+    println!("Changeset Analysis");
+    println!("==================");
+
+    if let Some(changeset) = plan.changeset.as_ref() {
+        println!();
+        println!("Active changeset: {} ({})", changeset.id, changeset.branch);
+        if !changeset.title.trim().is_empty() {
+            println!("  title: {}", changeset.title);
+        }
+    }
+
+    if plan.changed.is_empty() {
+        println!();
+        println!("No changed repositories detected.");
+        return;
+    }
+
+    println!();
+    println!("Changed repositories:");
+    for repo in &plan.changed {
+        println!(
+            "  {:<18} {:<16} {}",
+            repo.id.as_str(),
+            repo.branch,
+            plan_status_summary(&repo.status)
+        );
+        println!("    {}", repo.diff_stat);
+        if !repo.teams.is_empty() {
+            println!("    team: {}", repo.teams.join(", "));
+        }
+        if let Some(summary) = repo.changeset_summary.as_deref() {
+            println!("    changeset: {}", summary);
+        }
+    }
+
+    println!();
+    println!("Merge order:");
+    for (index, repo) in plan.merge_order.iter().enumerate() {
+        println!("  {}. {}", index + 1, repo.as_str());
+    }
+
+    println!();
+    println!("Constraint analysis:");
+    println!("  cycles: {}", plan.constraints.cycles.len());
+    println!("  missing dependencies: {}", plan.constraints.missing.len());
+    println!(
+        "  external dependencies: {}",
+        plan.constraints.external.len()
+    );
+    println!(
+        "  duplicate package names: {}",
+        plan.constraints.duplicates.len()
+    );
+    println!("  violations: {}", plan.constraints.violations.len());
+
+    if !plan.constraints.violations.is_empty() {
+        println!("  top violations:");
+        for violation in plan.constraints.violations.iter().take(5) {
+            println!(
+                "    - {} -> {} [{}]",
+                violation.from_repo.as_str(),
+                violation.to_repo.as_str(),
+                violation.violation_type.as_str()
+            );
+        }
+    }
+
+    println!();
+    println!("Recommendations:");
+    for recommendation in &plan.recommendations {
+        println!("  - {}", recommendation);
     }
+}
+
+fn plan_to_json(plan: &PlanSummary) -> serde_json::Value {
+    serde_json::json!({
+        "changed_repos": plan.changed.iter().map(|repo| {
+            serde_json::json!({
+                "repo": repo.id.as_str(),
+                "branch": repo.branch,
+                "staged": repo.status.staged.len(),
+                "modified": repo.status.modified.len(),
+                "untracked": repo.status.untracked.len(),
+                "conflicts": repo.status.conflicts.len(),
+                "diff_stat": repo.diff_stat,
+                "changeset_summary": repo.changeset_summary.as_deref(),
+                "teams": repo.teams,
+            })
+        }).collect::<Vec<_>>(),
+        "changeset": plan.changeset.as_ref().map(|changeset| {
+            serde_json::json!({
+                "id": changeset.id,
+                "title": changeset.title,
+                "description": changeset.description,
+                "branch": changeset.branch,
+                "repos": changeset.repo_summaries.iter().map(|(repo, summary)| {
+                    serde_json::json!({
+                        "repo": repo.as_str(),
+                        "summary": summary,
+                    })
+                }).collect::<Vec<_>>(),
+            })
+        }),
+        "merge_order": plan.merge_order.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
+        "constraint_analysis": {
+            "cycles": plan.constraints.cycles.iter().map(|cycle| cycle.iter().map(|repo| repo.as_str()).collect::<Vec<_>>()).collect::<Vec<_>>(),
+            "missing": plan.constraints.missing.iter().map(|missing| {
+                serde_json::json!({
+                    "from": missing.from.as_str(),
+                    "dependency": missing.dependency.name,
+                    "constraint": missing.dependency.constraint.raw,
+                })
+            }).collect::<Vec<_>>(),
+            "violations": plan.constraints.violations.iter().map(|violation| {
+                serde_json::json!({
+                    "from": violation.from_repo.as_str(),
+                    "to": violation.to_repo.as_str(),
+                    "constraint": violation.constraint.raw,
+                    "actual_version": violation.actual_version.raw,
+                    "violation_type": violation.violation_type.as_str(),
+                })
+            }).collect::<Vec<_>>(),
+            "external": plan.constraints.external.iter().map(|external| {
+                serde_json::json!({
+                    "from": external.from.as_str(),
+                    "dependency": external.dependency.name,
+                    "constraint": external.dependency.constraint.raw,
+                    "version_url": external.package.version_url,
+                })
+            }).collect::<Vec<_>>(),
+            "duplicates": plan.constraints.duplicates.iter().map(|duplicate| {
+                serde_json::json!({
+                    "name": duplicate.name,
+                    "repos": duplicate.repos.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
+                })
+            }).collect::<Vec<_>>(),
+        },
+        "recommendations": plan.recommendations,
+    })
+}
 
-    rows.sort_by(|a, b| {
-        let a_order = a
-            .get("merge_order")
-            .and_then(|value| value.as_u64())
-            .unwrap_or(u64::MAX);
-        let b_order = b
-            .get("merge_order")
-            .and_then(|value| value.as_u64())
-            .unwrap_or(u64::MAX);
-        a_order.cmp(&b_order)
-    });
-    rows
+/// Outcome of `plan --simulate`: each merge in plan order with the version it
+/// is predicted to release and the constraint violations it introduces or
+/// clears.
+#[derive(Debug)]
+struct MergeSimulation {
+    cascade: bool,
+    steps: Vec<SimulatedMerge>,
+    /// Violations introduced by one merge and cleared by a later one.
+    mid_sequence: Vec<SimulatedViolation>,
+    /// Violations introduced during the sequence that remain after it.
+    unresolved: Vec<SimulatedViolation>,
 }
 
-fn resolve_template_path(workspace: &Workspace, path: &str) -> PathBuf {
-    let candidate = PathBuf::from(path);
-    if candidate.is_absolute() {
-        candidate
-    } else {
-        workspace.root.join(candidate)
-    }
+#[derive(Debug)]
+struct SimulatedMerge {
+    repo: RepoId,
+    from_version: Option<String>,
+    to_version: Option<String>,
+    constraint_updates: Vec<DependencyUpdate>,
+    introduced: Vec<SimulatedViolation>,
+    resolved: Vec<SimulatedViolation>,
 }
 
-fn collect_mr_status_rows(
-    forge: &dyn crate::forge::traits::Forge,
-    tracked: &[TrackedMr],
-) -> Result<Vec<MrStatusRow>> {
-    let mut rows = Vec::new();
-    for item in tracked {
-        let mr = forge.get_mr(&item.forge_repo, &item.entry.mr_id)?;
-        let ci = forge.get_ci_status(&item.forge_repo, &item.entry.source_branch)?;
-        let ci_state = ci.state.clone();
-        let checks = ci
-            .checks
-            .iter()
-            .map(|check| (check.name.clone(), check.status.clone()))
-            .collect::<Vec<_>>();
-        let required = required_checks_for_repo(&item.repo);
-        let required_result = evaluate_required_checks(&required, &ci.checks);
-        let (missing_required_checks, failed_required_checks) = match required_result {
-            RequiredChecksState::Pending(names) => (names, Vec::new()),
-            RequiredChecksState::Failed(names) => (Vec::new(), names),
-            RequiredChecksState::Satisfied => (Vec::new(), Vec::new()),
-        };
-        rows.push(MrStatusRow {
-            repo: item.repo.id.clone(),
-            iid: mr.iid,
-            url: mr.url,
-            state: mr.state,
-            ci_state: Some(ci_state),
-            approvals: mr
-                .approvals
-                .iter()
-                .map(|user| user.username.clone())
-                .collect(),
-            checks,
-            missing_required_checks,
-            failed_required_checks,
-        });
-    }
-    rows.sort_by(|a, b| a.repo.as_str().cmp(b.repo.as_str()));
-    Ok(rows)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SimulatedViolation {
+    from_repo: String,
+    to_repo: String,
+    constraint: String,
+    actual_version: String,
 }
 
-fn wait_for_ci_success(forge: &dyn crate::forge::traits::Forge, item: &TrackedMr) -> Result<()> {
-    let timeout_minutes = item
-        .repo
+/// Replays the merge order against a copy of the graph. Each merge bumps the
+/// repo's version with the configured bump mode and, with
+/// `versioning.cascade_bumps`, first moves its constraints on repos released
+/// earlier in the sequence, the way `version bump --cascade` would.
+fn simulate_plan_merges(
+    state: &WorkspaceState,
+    plan: &PlanSummary,
+    level: Option<BumpLevel>,
+) -> Result<MergeSimulation> {
+    let workspace = state.workspace;
+    let calver_format = workspace
         .config
+        .versioning
         .as_ref()
-        .and_then(|config| config.ci.as_ref())
-        .and_then(|ci| ci.timeout_minutes)
-        .unwrap_or(30);
-    let deadline = Instant::now()
-        .checked_add(Duration::from_secs(timeout_minutes.saturating_mul(60)))
-        .unwrap_or_else(Instant::now);
-    let required_checks = required_checks_for_repo(&item.repo);
+        .and_then(|config| config.calver_format.as_deref());
+    let cascade = workspace
+        .config
+        .versioning
+        .as_ref()
+        .and_then(|config| config.cascade_bumps)
+        .unwrap_or(false);
 
-    loop {
-        let status = forge.get_ci_status(&item.forge_repo, &item.entry.source_branch)?;
-        let required_result = evaluate_required_checks(&required_checks, &status.checks);
-        match status.state {
-            CiState::Success | CiState::Skipped => match required_result {
-                RequiredChecksState::Satisfied => return Ok(()),
-                RequiredChecksState::Pending(names) => {
-                    if Instant::now() >= deadline {
-                        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                            "timeout waiting for required checks for {}: {}",
-                            item.repo.id.as_str(),
-                            names.join(", ")
-                        ))));
+    let mut graph = crate::graph::DependencyGraph {
+        edges: workspace.graph.edges.clone(),
+        external: workspace.graph.external.clone(),
+    };
+    let mut versions = state.versions()?.clone();
+    let mut released: HashMap<RepoId, Version> = HashMap::new();
+    let mut current = unsatisfied_constraints(&graph, workspace, &versions);
+    let mut steps = Vec::new();
+
+    for repo_id in &plan.merge_order {
+        let Some(repo) = workspace.repos.get(repo_id) else {
+            continue;
+        };
+
+        let mut constraint_updates = Vec::new();
+        if cascade {
+            constraint_updates = build_dependency_updates(workspace, &released)?
+                .into_iter()
+                .filter(|update| update.repo == *repo_id)
+                .collect();
+            constraint_updates.sort_by(|a, b| a.dependency.cmp(&b.dependency));
+            if let Some(deps) = graph.edges.get_mut(repo_id) {
+                for update in &constraint_updates {
+                    for dep in deps.iter_mut().filter(|dep| dep.name == update.dependency) {
+                        dep.constraint = VersionReq::new(update.constraint.clone());
                     }
-                    std::thread::sleep(Duration::from_secs(5));
-                }
-                RequiredChecksState::Failed(names) => {
-                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                        "required checks failed for {}: {}",
-                        item.repo.id.as_str(),
-                        names.join(", ")
-                    ))))
                 }
-            },
-            CiState::Failed | CiState::Canceled => {
-                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                    "CI is not passing for {} (state: {})",
-                    item.repo.id.as_str(),
-                    ci_state_label(&status.state)
-                ))))
             }
-            CiState::Pending | CiState::Running => {
-                if let RequiredChecksState::Failed(names) = required_result {
-                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                        "required checks failed for {}: {}",
-                        item.repo.id.as_str(),
-                        names.join(", ")
-                    ))));
-                }
-                if Instant::now() >= deadline {
-                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                        "timeout waiting for CI for {}",
-                        item.repo.id.as_str()
-                    ))));
+        }
+
+        let from_version = versions.get(repo_id).cloned();
+        let mut to_version = None;
+        if let Some(version) = from_version.as_ref() {
+            let mode = resolve_bump_mode(repo, workspace, None)?;
+            let next = bump_version(version, mode, level, calver_format, None)
+                .map_err(|err| HarmoniaError::Other(anyhow::anyhow!(format!("{}", err))))?;
+            versions.insert(repo_id.clone(), next.clone());
+            released.insert(repo_id.clone(), next.clone());
+            to_version = Some(next.raw);
+        }
+
+        let after = unsatisfied_constraints(&graph, workspace, &versions);
+        let introduced = after
+            .iter()
+            .filter(|violation| !contains_violation(&current, violation))
+            .cloned()
+            .collect();
+        let resolved = current
+            .iter()
+            .filter(|violation| !contains_violation(&after, violation))
+            .cloned()
+            .collect();
+        steps.push(SimulatedMerge {
+            repo: repo_id.clone(),
+            from_version: from_version.map(|version| version.raw),
+            to_version,
+            constraint_updates,
+            introduced,
+            resolved,
+        });
+        current = after;
+    }
+
+    let mut mid_sequence = Vec::new();
+    let mut unresolved = Vec::new();
+    for step in &steps {
+        for violation in &step.introduced {
+            if contains_violation(&current, violation) {
+                if !contains_violation(&unresolved, violation) {
+                    unresolved.push(violation.clone());
                 }
-                std::thread::sleep(Duration::from_secs(5));
+            } else if !contains_violation(&mid_sequence, violation) {
+                mid_sequence.push(violation.clone());
             }
         }
     }
+
+    Ok(MergeSimulation {
+        cascade,
+        steps,
+        mid_sequence,
+        unresolved,
+    })
 }
 
-fn required_checks_for_repo(repo: &Repo) -> Vec<String> {
-    let mut checks = repo
-        .config
-        .as_ref()
-        .and_then(|config| config.ci.as_ref())
-        .and_then(|ci| ci.required_checks.clone())
-        .unwrap_or_default();
-    checks.sort();
-    checks.dedup();
-    checks
+/// Unsatisfied constraints only: exact pins and upper bounds are reported by
+/// the regular constraint analysis and do not change between merges.
+fn unsatisfied_constraints(
+    graph: &crate::graph::DependencyGraph,
+    workspace: &Workspace,
+    versions: &HashMap<RepoId, Version>,
+) -> Vec<SimulatedViolation> {
+    let mut violations: Vec<SimulatedViolation> =
+        check_constraints(graph, &workspace.repos, versions)
+            .violations
+            .into_iter()
+            .filter(|violation| violation.violation_type == ViolationType::Unsatisfied)
+            .map(|violation| SimulatedViolation {
+                from_repo: violation.from_repo.as_str().to_string(),
+                to_repo: violation.to_repo.as_str().to_string(),
+                constraint: violation.constraint.raw,
+                actual_version: violation.actual_version.raw,
+            })
+            .collect();
+    violations.sort_by(|a, b| (&a.from_repo, &a.to_repo).cmp(&(&b.from_repo, &b.to_repo)));
+    violations
 }
 
-#[derive(Debug)]
-enum RequiredChecksState {
-    Satisfied,
-    Pending(Vec<String>),
-    Failed(Vec<String>),
+fn contains_violation(violations: &[SimulatedViolation], violation: &SimulatedViolation) -> bool {
+    violations
+        .iter()
+        .any(|other| other.from_repo == violation.from_repo && other.to_repo == violation.to_repo)
 }
 
-fn evaluate_required_checks(
-    required: &[String],
-    checks: &[crate::forge::CheckRun],
-) -> RequiredChecksState {
-    if required.is_empty() {
-        return RequiredChecksState::Satisfied;
+fn print_merge_simulation(simulation: &MergeSimulation) {
+    println!();
+    println!("Merge simulation:");
+    if simulation.steps.is_empty() {
+        println!("  nothing to merge");
+        return;
     }
-
-    let mut pending = Vec::new();
-    let mut failed = Vec::new();
-    for required_name in required {
-        let matched = checks
-            .iter()
-            .find(|check| check.name == *required_name)
-            .map(|check| check.status.as_str());
-        match matched {
-            Some("success") | Some("passed") | Some("skipped") => {}
-            Some(
-                "pending"
-                | "running"
-                | "created"
-                | "preparing"
-                | "manual"
-                | "scheduled"
-                | "waiting_for_resource",
-            ) => pending.push(required_name.clone()),
-            Some(_) => failed.push(required_name.clone()),
-            None => pending.push(required_name.clone()),
+    for (index, step) in simulation.steps.iter().enumerate() {
+        match (step.from_version.as_deref(), step.to_version.as_deref()) {
+            (Some(from), Some(to)) => {
+                println!("  {}. {} {} -> {}", index + 1, step.repo.as_str(), from, to)
+            }
+            _ => println!("  {}. {} (no version)", index + 1, step.repo.as_str()),
+        }
+        for update in &step.constraint_updates {
+            println!(
+                "       update {} -> {}",
+                update.dependency, update.constraint
+            );
+        }
+        for violation in &step.introduced {
+            println!(
+                "       breaks {} -> {} ({} does not allow {})",
+                violation.from_repo,
+                violation.to_repo,
+                violation.constraint,
+                violation.actual_version
+            );
+        }
+        for violation in &step.resolved {
+            println!(
+                "       fixes {} -> {}",
+                violation.from_repo, violation.to_repo
+            );
         }
     }
-
-    if !failed.is_empty() {
-        return RequiredChecksState::Failed(failed);
+    println!(
+        "  mid-sequence violations: {}",
+        simulation.mid_sequence.len()
+    );
+    for violation in &simulation.mid_sequence {
+        println!("    - {} -> {}", violation.from_repo, violation.to_repo);
     }
-    if !pending.is_empty() {
-        return RequiredChecksState::Pending(pending);
+    println!("  unresolved violations: {}", simulation.unresolved.len());
+    for violation in &simulation.unresolved {
+        println!(
+            "    - {} -> {} ({} does not allow {})",
+            violation.from_repo, violation.to_repo, violation.constraint, violation.actual_version
+        );
+    }
+    if !simulation.cascade && !simulation.unresolved.is_empty() {
+        println!("  hint: set [versioning].cascade_bumps = true to update dependent constraints");
     }
-    RequiredChecksState::Satisfied
 }
 
-fn mr_status_row_to_json(row: &MrStatusRow) -> serde_json::Value {
+fn merge_simulation_to_json(simulation: &MergeSimulation) -> serde_json::Value {
+    let violation_json = |violation: &SimulatedViolation| {
+        serde_json::json!({
+            "from_repo": violation.from_repo,
+            "to_repo": violation.to_repo,
+            "constraint": violation.constraint,
+            "actual_version": violation.actual_version,
+        })
+    };
     serde_json::json!({
-        "repo": row.repo.as_str(),
-        "mr_iid": row.iid,
-        "url": row.url,
-        "state": mr_state_label(&row.state),
-        "ci_state": row.ci_state.as_ref().map(ci_state_label),
-        "approvals": row.approvals,
-        "checks": row.checks.iter().map(|(name, status)| {
+        "cascade": simulation.cascade,
+        "steps": simulation.steps.iter().map(|step| {
             serde_json::json!({
-                "name": name,
-                "status": status,
+                "repo": step.repo.as_str(),
+                "from_version": step.from_version,
+                "to_version": step.to_version,
+                "constraint_updates": step.constraint_updates.iter().map(|update| {
+                    serde_json::json!({
+                        "dependency": update.dependency,
+                        "constraint": update.constraint,
+                    })
+                }).collect::<Vec<_>>(),
+                "introduced": step.introduced.iter().map(violation_json).collect::<Vec<_>>(),
+                "resolved": step.resolved.iter().map(violation_json).collect::<Vec<_>>(),
             })
         }).collect::<Vec<_>>(),
-        "missing_required_checks": row.missing_required_checks,
-        "failed_required_checks": row.failed_required_checks,
+        "mid_sequence": simulation.mid_sequence.iter().map(violation_json).collect::<Vec<_>>(),
+        "unresolved": simulation.unresolved.iter().map(violation_json).collect::<Vec<_>>(),
     })
 }
 
-fn mr_state_label(state: &MrState) -> &'static str {
-    match state {
-        MrState::Open => "open",
-        MrState::Merged => "merged",
-        MrState::Closed => "closed",
-        MrState::Draft => "draft",
+/// Runs the tests of every repo downstream of the changed repos with its
+/// manifest temporarily resolving those repos from their local checkouts, so
+/// contract breaks show up before any MR is created.
+fn verify_plan_dependents(workspace: &Workspace, plan: &PlanSummary) -> Result<()> {
+    let mut upstreams_for: HashMap<RepoId, Vec<RepoId>> = HashMap::new();
+    for upstream in &plan.merge_order {
+        for dependent in transitive_dependents(&workspace.graph, &workspace.repos, upstream) {
+            upstreams_for
+                .entry(dependent)
+                .or_default()
+                .push(upstream.clone());
+        }
+    }
+    let dependents: Vec<Repo> = upstreams_for
+        .keys()
+        .filter_map(|id| workspace.repos.get(id).cloned())
+        .filter(|repo| should_include_repo(repo, false))
+        .collect();
+    let dependents = repos_in_graph_order(workspace, dependents)?;
+    let pre_verify = workspace
+        .config
+        .hooks
+        .as_ref()
+        .and_then(|hooks| hooks.pre_verify.as_deref());
+
+    let mut results: Vec<(RepoId, String)> = Vec::new();
+    let mut failed = Vec::new();
+    for repo in &dependents {
+        if !repo.path.is_dir() {
+            results.push((repo.id.clone(), "skipped (not cloned)".to_string()));
+            continue;
+        }
+        let Some(command) = resolve_quality_command(workspace, repo, QualityKind::Test) else {
+            results.push((repo.id.clone(), "skipped (no test command)".to_string()));
+            continue;
+        };
+        let links: Vec<(String, PathBuf)> = upstreams_for[&repo.id]
+            .iter()
+            .filter_map(|id| workspace.repos.get(id))
+            .map(|upstream| {
+                (
+                    upstream
+                        .package_name
+                        .clone()
+                        .unwrap_or_else(|| upstream.id.as_str().to_string()),
+                    upstream.path.clone(),
+                )
+            })
+            .collect();
+        match run_linked_tests(workspace, repo, &links, pre_verify, command) {
+            Ok(()) => results.push((repo.id.clone(), "ok".to_string())),
+            Err(err) => {
+                results.push((repo.id.clone(), format!("failed: {err}")));
+                failed.push(repo.id.as_str().to_string());
+            }
+        }
+    }
+
+    println!();
+    println!("Verify against local upstreams:");
+    if results.is_empty() {
+        println!("  no dependents of changed repos");
     }
+    for (repo, outcome) in &results {
+        println!("  {:<18} {}", repo.as_str(), outcome);
+    }
+    if !failed.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} dependent(s) failed against local upstreams: {}",
+            failed.len(),
+            failed.join(", ")
+        ))));
+    }
+    Ok(())
 }
 
-fn ci_state_label(state: &CiState) -> &'static str {
-    match state {
-        CiState::Pending => "pending",
-        CiState::Running => "running",
-        CiState::Success => "success",
-        CiState::Failed => "failed",
-        CiState::Canceled => "canceled",
-        CiState::Skipped => "skipped",
+/// Links `links` (package name, local path) into the repo's manifest, runs
+/// `[hooks].pre_verify` and the test command, then restores the manifest.
+fn run_linked_tests(
+    workspace: &Workspace,
+    repo: &Repo,
+    links: &[(String, PathBuf)],
+    pre_verify: Option<&str>,
+    command: String,
+) -> Result<()> {
+    let mut env = workspace.command_env(Some(repo))?;
+    env.push((
+        "HARMONIA_VERIFY_LINKS".to_string(),
+        links
+            .iter()
+            .map(|(package, path)| format!("{package}={}", path.display()))
+            .collect::<Vec<_>>()
+            .join(","),
+    ));
+
+    let original = link_upstream_packages(repo, links)?;
+    let result = (|| {
+        if let Some(hook) = pre_verify {
+            if !repo_disables_hook(repo, "pre_verify") {
+                run_command_in_repo_with_env(&repo.path, &split_command(hook), &env, None)?;
+            }
+        }
+        output::info(&format!(
+            "[{}] verify against {}",
+            repo.id.as_str(),
+            links
+                .iter()
+                .map(|(package, _)| package.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        run_quality_command(
+            QualityKind::Test,
+            QualityCommand {
+                repo: repo.clone(),
+                command,
+                env: env.clone(),
+                timeout: None,
+            },
+        )
+    })();
+    for (file, content) in original {
+        match content {
+            Some(content) => fs::write(&file, content)?,
+            None if file.exists() => fs::remove_file(&file)?,
+            None => {}
+        }
     }
+    result
 }
 
-fn handle_shell(
-    args: ShellArgs,
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<()> {
-    let workspace = load_workspace(workspace_root, config_path)?;
-    let mut repos: Vec<Repo> = if args.repos.is_empty() {
-        workspace
-            .repos
-            .values()
-            .filter(|repo| !repo.ignored && repo.path.is_dir())
-            .cloned()
-            .collect()
-    } else {
-        select_repos(&workspace, &args.repos, None, false, true)?
-            .into_iter()
-            .filter(|repo| !repo.ignored && repo.path.is_dir())
-            .collect()
+/// Rewrites the repo's dependency file so each package resolves from its
+/// local path. When it changed, returns the files to put back afterwards:
+/// the dependency file and the lockfiles beside it or at the repo root, which
+/// the test run may rewrite, with their original bytes (`None` for lockfiles
+/// that did not exist yet).
+fn link_upstream_packages(
+    repo: &Repo,
+    links: &[(String, PathBuf)],
+) -> Result<Vec<(PathBuf, Option<Vec<u8>>)>> {
+    let (Some(ecosystem), Some(file)) = (repo.ecosystem.as_ref(), deps_file_for_repo(repo)) else {
+        return Ok(Vec::new());
     };
-    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
-
-    let mut path_prefixes = Vec::new();
-    let mut pythonpath_prefixes = Vec::new();
-    for repo in &repos {
-        let bin_dir = repo.path.join("bin");
-        if bin_dir.is_dir() {
-            path_prefixes.push(bin_dir);
+    let plugin = plugin_for(ecosystem);
+    let original = fs::read_to_string(&file)?;
+    let mut content = original.clone();
+    let mut linked = false;
+    for (package, path) in links {
+        if let Some(updated) = plugin.link_dependency(&file, &content, package, path)? {
+            content = updated;
+            linked = true;
         }
-        let src_dir = repo.path.join("src");
-        if src_dir.is_dir() {
-            pythonpath_prefixes.push(src_dir);
+    }
+    if !linked {
+        return Ok(Vec::new());
+    }
+    let mut dirs = vec![repo.path.clone()];
+    if let Some(parent) = file.parent().filter(|parent| *parent != repo.path) {
+        dirs.push(parent.to_path_buf());
+    }
+    let mut snapshot = vec![(file.clone(), Some(original.into_bytes()))];
+    for dir in dirs {
+        for name in SNAPSHOT_LOCKFILES {
+            let lockfile = dir.join(name);
+            let content = fs::read(&lockfile).ok();
+            snapshot.push((lockfile, content));
         }
     }
+    fs::write(&file, content)?;
+    Ok(snapshot)
+}
 
-    let path_value = compose_shell_env_value("PATH", path_prefixes)?;
-    let pythonpath_value = compose_shell_env_value("PYTHONPATH", pythonpath_prefixes)?;
+fn plan_recommendations(
+    changed: &[PlanChangedRepo],
+    report: &ConstraintReport,
+    impact: &[ImpactScore],
+) -> Vec<String> {
+    let mut recommendations = Vec::new();
 
-    if let Some(command) = args.command.as_deref() {
-        let split = split_command(command);
-        if split.is_empty() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(
-                "shell command cannot be empty"
-            )));
-        }
-        return run_shell_command_with_env(
-            &workspace.root,
-            &split,
-            &workspace.root,
-            path_value.as_deref(),
-            pythonpath_value.as_deref(),
+    if !report.cycles.is_empty() {
+        recommendations
+            .push("resolve dependency cycles before creating merge requests".to_string());
+    }
+    if !report.missing.is_empty() {
+        recommendations
+            .push("add missing internal dependency mappings in repository configs".to_string());
+    }
+    if !report.duplicates.is_empty() {
+        recommendations
+            .push("give each repo a distinct package_name so dependencies resolve".to_string());
+    }
+    if report
+        .violations
+        .iter()
+        .any(|violation| matches!(violation.violation_type, ViolationType::Unsatisfied))
+    {
+        recommendations.push(
+            "run `harmonia deps update --dry-run` to preview required constraint updates"
+                .to_string(),
         );
     }
-
-    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
-        let workspace_value = workspace.root.to_string_lossy().to_string();
-        println!(
-            "export HARMONIA_WORKSPACE={}",
-            shell_single_quote(&workspace_value)
+    if report.violations.iter().any(|violation| {
+        matches!(
+            violation.violation_type,
+            ViolationType::ExactPin | ViolationType::UpperBound
+        )
+    }) {
+        recommendations.push(
+            "review strict dependency constraints (exact pins and upper bounds) before merges"
+                .to_string(),
         );
-        if let Some(path) = path_value.as_ref() {
-            println!("export PATH={}", shell_single_quote(path));
-        }
-        if let Some(pythonpath) = pythonpath_value.as_ref() {
-            println!("export PYTHONPATH={}", shell_single_quote(pythonpath));
+    }
+    if changed.len() > 1 {
+        // `impact` is sorted highest impact first, so the changed repos that
+        // the most other repos depend on are listed first.
+        for score in impact
+            .iter()
+            .filter(|score| score.dependents > 0)
+            .filter(|score| changed.iter().any(|repo| repo.id == score.repo))
+            .take(3)
+        {
+            recommendations.push(format!(
+                "stabilize {} early: {} repos depend on it (depth {})",
+                score.repo.as_str(),
+                score.dependents,
+                score.depth
+            ));
         }
-        return Ok(());
+        recommendations.push("merge in the listed order and wait for CI between steps".to_string());
+    }
+    if recommendations.is_empty() {
+        recommendations
+            .push("no blocking issues detected for the current local changeset".to_string());
     }
 
-    let shell = env::var("SHELL").unwrap_or_else(|_| {
-        if cfg!(windows) {
-            "cmd".to_string()
-        } else {
-            "sh".to_string()
-        }
-    });
+    recommendations
+}
 
-    let mut cmd = std::process::Command::new(&shell);
-    cmd.current_dir(&workspace.root);
-    cmd.env("HARMONIA_WORKSPACE", &workspace.root);
-    if let Some(path) = path_value {
-        cmd.env("PATH", path);
-    }
-    if let Some(pythonpath) = pythonpath_value {
-        cmd.env("PYTHONPATH", pythonpath);
+fn plan_status_summary(status: &StatusSummary) -> String {
+    format!(
+        "{} staged, {} modified, {} untracked, {} conflicts",
+        status.staged.len(),
+        status.modified.len(),
+        status.untracked.len(),
+        status.conflicts.len()
+    )
+}
+
+fn compose_shell_env_value(name: &str, mut prefixes: Vec<PathBuf>) -> Result<Option<String>> {
+    if let Some(existing) = env::var_os(name) {
+        prefixes.extend(env::split_paths(&existing));
     }
-    let status = cmd
-        .status()
-        .with_context(|| format!("failed to launch shell '{}'", shell))?;
-    if status.success() {
-        return Ok(());
+    if prefixes.is_empty() {
+        return Ok(None);
     }
 
-    Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-        "shell '{}' exited unsuccessfully",
-        shell
-    ))))
+    let joined = env::join_paths(prefixes).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to compute {} for shell command: {}",
+            name, err
+        )))
+    })?;
+    Ok(Some(joined.to_string_lossy().to_string()))
 }
 
-fn handle_completion(args: CompletionArgs) -> Result<()> {
-    let mut cmd = Cli::command();
-    let bin_name = cmd.get_name().to_string();
-    clap_complete::generate(args.shell, &mut cmd, bin_name, &mut std::io::stdout());
-    Ok(())
+fn resolve_workspace_paths(
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<(PathBuf, PathBuf)> {
+    let cwd = env::current_dir()?;
+    let resolved = resolve_workspace_with_overrides(cwd, workspace_root, config_path)?;
+    Ok((resolved.root, resolved.config_path))
 }
 
-#[derive(Debug)]
-struct PlanSummary {
-    changed: Vec<PlanChangedRepo>,
-    merge_order: Vec<RepoId>,
-    constraints: ConstraintReport,
-    recommendations: Vec<String>,
-    changeset: Option<PlanChangeset>,
+fn resolve_editor_command(editor: Option<&str>) -> Result<Vec<String>> {
+    let command = editor
+        .map(|value| value.to_string())
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| "code".to_string());
+    let command = split_command(&command);
+    if command.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "editor command cannot be empty"
+        )));
+    }
+    Ok(command)
 }
 
-#[derive(Debug)]
-struct PlanChangedRepo {
-    id: RepoId,
-    branch: String,
-    status: StatusSummary,
-    diff_stat: String,
-    changeset_summary: Option<String>,
+fn read_workspace_config_value(path: &Path) -> Result<toml::Value> {
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
 }
 
-#[derive(Debug, Clone)]
-struct PlanChangeset {
-    id: String,
-    title: String,
-    description: String,
-    branch: String,
-    repo_summaries: HashMap<RepoId, String>,
+fn write_workspace_config_value(path: &Path, value: &toml::Value) -> Result<()> {
+    let contents = toml::to_string_pretty(value)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(path, contents)?;
+    Ok(())
 }
 
-fn build_plan_summary(
-    workspace: &Workspace,
-    include: &[String],
-    exclude: &[String],
-) -> Result<PlanSummary> {
-    let mut include_ids = resolve_plan_repo_ids(workspace, include, "include")?;
-    let exclude_ids = resolve_plan_repo_ids(workspace, exclude, "exclude")?;
+fn workspace_config_get<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    let segments: Vec<&str> = key
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return None;
+    }
+
+    let mut current = value;
+    for segment in segments {
+        current = current.get(segment)?;
+    }
+    Some(current)
+}
 
-    for repo_id in &include_ids {
-        let repo = workspace.repos.get(repo_id).ok_or_else(|| {
+fn workspace_config_set(value: &mut toml::Value, key: &str, new_value: toml::Value) -> Result<()> {
+    let segments: Vec<&str> = key
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "config key cannot be empty"
+        )));
+    }
+
+    let mut current = value;
+    for segment in &segments[..segments.len() - 1] {
+        let table = current.as_table_mut().ok_or_else(|| {
             HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo {}",
-                repo_id.as_str()
+                "cannot set '{}' because '{}' is not a table",
+                key, segment
             )))
         })?;
-        if !repo.path.is_dir() {
+        current = table
+            .entry((*segment).to_string())
+            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
+        if !current.is_table() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "repo {} not cloned",
-                repo_id.as_str()
+                "cannot set '{}' because '{}' is not a table",
+                key, segment
             ))));
         }
     }
 
-    let mut repos: Vec<&Repo> = workspace
-        .repos
-        .values()
-        .filter(|repo| !repo.ignored && !repo.external && repo.path.is_dir())
-        .collect();
-    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    let leaf = segments[segments.len() - 1];
+    let table = current.as_table_mut().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "cannot set '{}' because parent path is not a table",
+            key
+        )))
+    })?;
+    table.insert(leaf.to_string(), new_value);
+    Ok(())
+}
 
-    let mut candidates = Vec::new();
-    let mut all_branches = HashSet::new();
-    let mut changed_branches = HashSet::new();
-    for repo in repos {
-        if exclude_ids.contains(&repo.id) {
-            continue;
+fn workspace_config_get_mut<'a>(
+    value: &'a mut toml::Value,
+    key: &str,
+) -> Option<&'a mut toml::Value> {
+    key.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| current.get_mut(segment))
+}
+
+/// Appends `items` to the array at `key`, or inserts `name=value` items into
+/// the table at `key`. A missing key becomes an array when the schema names it
+/// as a value, otherwise a table. Items already present are skipped, and a
+/// table entry that is set to something else is an error rather than
+/// overwritten. Returns how many items were added.
+fn workspace_config_add(value: &mut toml::Value, key: &str, items: &[String]) -> Result<usize> {
+    if workspace_config_get(value, key).is_none() {
+        let empty = if validate_config_key(key, false).is_ok() {
+            toml::Value::Array(Vec::new())
+        } else {
+            toml::Value::Table(toml::map::Map::new())
+        };
+        workspace_config_set(value, key, empty)?;
+    }
+    let Some(target) = workspace_config_get_mut(value, key) else {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "config key '{}' not found",
+            key
+        ))));
+    };
+
+    let mut added = 0;
+    match target {
+        toml::Value::Array(array) => {
+            for item in items {
+                let item = parse_config_value(item)?;
+                if !array.contains(&item) {
+                    array.push(item);
+                    added += 1;
+                }
+            }
         }
-        let open = open_repo(&repo.path)?;
-        let branch = current_branch(&open.repo)?;
-        let status = repo_status(&open.repo)?;
-        all_branches.insert(branch.clone());
-        if !status.is_clean() {
-            changed_branches.insert(branch.clone());
+        toml::Value::Table(table) => {
+            for item in items {
+                let (name, raw) = item
+                    .split_once('=')
+                    .map(|(name, raw)| (name.trim(), raw.trim()))
+                    .filter(|(name, _)| !name.is_empty())
+                    .ok_or_else(|| {
+                        HarmoniaError::Other(anyhow::anyhow!(format!(
+                            "'{}' is a table; give entries as name=value, got '{}'",
+                            key, item
+                        )))
+                    })?;
+                validate_config_key(&format!("{key}.{name}"), true)?;
+                let entry = parse_config_value(raw)?;
+                match table.get(name) {
+                    Some(existing) if *existing == entry => {}
+                    Some(existing) => {
+                        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                            "'{}.{}' is already set to {}; use `harmonia config set` to replace it",
+                            key, name, existing
+                        ))))
+                    }
+                    None => {
+                        table.insert(name.to_string(), entry);
+                        added += 1;
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "'{}' is not an array or table; use `harmonia config set`",
+                key
+            ))))
         }
-        let diff_stat = git_shortstat_output(&repo.path);
-        candidates.push((repo.clone(), branch, status, diff_stat));
     }
+    Ok(added)
+}
 
-    let branch_scope = if changed_branches.is_empty() {
-        &all_branches
-    } else {
-        &changed_branches
+/// Removes matching values from the array at `key`, or entries named by
+/// `items` from the table at `key`. Without items the key itself is removed.
+/// Missing keys and values are not an error. Returns how many were removed.
+fn workspace_config_remove(value: &mut toml::Value, key: &str, items: &[String]) -> Result<usize> {
+    if items.is_empty() {
+        let (parent, leaf) = match key.rsplit_once('.') {
+            Some((parent, leaf)) => (workspace_config_get_mut(value, parent), leaf),
+            None => (Some(value), key),
+        };
+        let removed = parent
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|table| table.remove(leaf));
+        return Ok(usize::from(removed.is_some()));
+    }
+
+    let Some(target) = workspace_config_get_mut(value, key) else {
+        return Ok(0);
     };
-    let changeset = load_active_changeset(workspace, branch_scope)?;
-    if let Some(changeset) = changeset.as_ref() {
-        for repo_id in changeset.repo_summaries.keys() {
-            include_ids.insert(repo_id.clone());
+    let mut removed = 0;
+    match target {
+        toml::Value::Array(array) => {
+            for item in items {
+                let item = parse_config_value(item)?;
+                let before = array.len();
+                array.retain(|existing| *existing != item);
+                removed += before - array.len();
+            }
+        }
+        toml::Value::Table(table) => {
+            for item in items {
+                if table.remove(item.as_str()).is_some() {
+                    removed += 1;
+                }
+            }
+        }
+        _ => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "'{}' is not an array or table; remove it with `harmonia config remove {}`",
+                key, key
+            ))))
         }
     }
+    Ok(removed)
+}
 
-    let mut changed = Vec::new();
-    for (repo, branch, status, diff_stat) in candidates {
-        if status.is_clean() && !include_ids.contains(&repo.id) {
-            continue;
+/// Rejects edits that would leave the workspace config unloadable, before
+/// anything is written.
+fn validate_config_document(value: &toml::Value) -> Result<()> {
+    let config: WorkspaceConfig = value.clone().try_into().map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "refusing to write invalid config: {}",
+            err
+        )))
+    })?;
+    validate_workspace_config(&config)?;
+    Ok(())
+}
+
+/// Like [`validate_config_document`] for a repo's `.harmonia.toml`.
+fn validate_repo_config_document(value: &toml::Value) -> Result<()> {
+    let _: RepoConfig = value.clone().try_into().map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "refusing to write invalid repo config: {}",
+            err
+        )))
+    })?;
+    Ok(())
+}
+
+fn parse_config_value(raw: &str) -> Result<toml::Value> {
+    let snippet = format!("value = {raw}");
+    if let Ok(parsed) = toml::from_str::<toml::Value>(&snippet) {
+        if let Some(value) = parsed.get("value") {
+            return Ok(value.clone());
         }
-        changed.push(PlanChangedRepo {
-            id: repo.id.clone(),
-            branch,
-            status,
-            diff_stat,
-            changeset_summary: changeset
-                .as_ref()
-                .and_then(|changeset| changeset.repo_summaries.get(&repo.id))
-                .filter(|summary| !summary.trim().is_empty())
-                .cloned(),
-        });
     }
 
-    let merge_order = if changed.is_empty() {
-        Vec::new()
-    } else {
-        let targets: Vec<RepoId> = changed.iter().map(|repo| repo.id.clone()).collect();
-        merge_order(&workspace.graph, &workspace.repos, &targets).map_err(HarmoniaError::Other)?
-    };
-    let versions = collect_versions(workspace)?;
-    let constraints = check_constraints(&workspace.graph, &workspace.repos, &versions);
-    let recommendations = plan_recommendations(&changed, &constraints);
+    Ok(toml::Value::String(raw.to_string()))
+}
 
-    Ok(PlanSummary {
-        changed,
-        merge_order,
-        constraints,
-        recommendations,
-        changeset,
-    })
+fn format_config_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        toml::Value::Integer(value) => value.to_string(),
+        toml::Value::Float(value) => value.to_string(),
+        toml::Value::Boolean(value) => value.to_string(),
+        toml::Value::Datetime(value) => value.to_string(),
+        _ => value.to_string(),
+    }
 }
 
-fn resolve_plan_repo_ids(
+fn workspace_repos_table(value: &toml::Value) -> Result<&toml::map::Map<String, toml::Value>> {
+    let repos = value
+        .get("repos")
+        .and_then(|value| value.as_table())
+        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
+    Ok(repos)
+}
+
+fn load_workspace(
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<Workspace> {
+    let cwd = env::current_dir()?;
+    let resolved = resolve_workspace_with_overrides(cwd, workspace_root, config_path)?;
+    let workspace =
+        Workspace::load_from(resolved.root, resolved.config_path).map_err(HarmoniaError::from)?;
+    if workspace.config.workspace.stats {
+        let _ = STATS_ROOT.set(workspace.root.clone());
+    }
+    for duplicate in duplicate_package_names(&workspace.repos) {
+        output::warn(&duplicate_package_message(&duplicate));
+    }
+    Ok(workspace)
+}
+
+fn duplicate_package_message(duplicate: &DuplicatePackage) -> String {
+    format!(
+        "package name '{}' is declared by {}; dependencies on it are not resolved (set a distinct package_name)",
+        duplicate.name,
+        duplicate
+            .repos
+            .iter()
+            .map(RepoId::as_str)
+            .collect::<Vec<_>>()
+            .join(" and ")
+    )
+}
+
+fn select_repos(
     workspace: &Workspace,
     repos: &[String],
-    flag: &str,
-) -> Result<HashSet<RepoId>> {
-    let mut ids = HashSet::new();
-    for repo_name in repos {
-        let repo_id = RepoId::new(repo_name.clone());
-        if !workspace.repos.contains_key(&repo_id) {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "unknown repo '{}' passed to --{}",
-                repo_name, flag
-            ))));
+    group: Option<&str>,
+    all: bool,
+    include_external: bool,
+) -> Result<Vec<crate::core::repo::Repo>> {
+    let selected = match selection_filter(workspace)? {
+        None => select_configured_repos(workspace, repos, group, all, include_external)?,
+        Some(matched) => {
+            // `--select` and `--selection` replace the default scope and narrow
+            // explicit repos or groups.
+            let explicit = !repos.is_empty() || group.is_some();
+            select_configured_repos(workspace, repos, group, all || !explicit, include_external)?
+                .into_iter()
+                .filter(|repo| matched.contains(&repo.id))
+                .collect()
         }
-        ids.insert(repo_id);
-    }
-    Ok(ids)
+    };
+    record_selection(workspace, &selected)?;
+    STATS_REPOS.fetch_max(selected.len(), AtomicOrdering::Relaxed);
+    Ok(selected)
 }
 
-fn load_active_changeset(
-    workspace: &Workspace,
-    branches: &HashSet<String>,
-) -> Result<Option<PlanChangeset>> {
-    let files = load_changeset_files(&workspace.root, &workspace.config)?;
-    let selected = select_active_changeset(&files, branches)?;
-    let Some(selected) = selected else {
+/// Repos allowed by `--select` and `--selection`, or `None` when neither was
+/// given.
+fn selection_filter(workspace: &Workspace) -> Result<Option<HashSet<RepoId>>> {
+    let Some(selection) = REPO_SELECTION.get() else {
         return Ok(None);
     };
-    normalize_changeset(workspace, selected).map(Some)
+    let mut matched: Option<HashSet<RepoId>> = None;
+    if let Some(name) = selection.reuse.as_deref() {
+        let saved = load_selection(&workspace.root, name)?;
+        matched = Some(saved.repos.into_iter().map(RepoId::new).collect());
+    }
+    if let Some(expr) = selection.expr.as_ref() {
+        let changed = if expr.uses_changed() {
+            changed_repos(&WorkspaceState::new(workspace))?
+        } else {
+            HashSet::new()
+        };
+        let no_groups = HashMap::new();
+        let teams = team_members(&workspace.config);
+        let evaluated = expr.evaluate(&SelectContext {
+            repos: &workspace.repos,
+            graph: &workspace.graph,
+            groups: workspace
+                .config
+                .groups
+                .as_ref()
+                .map(|groups| &groups.groups)
+                .unwrap_or(&no_groups),
+            teams: &teams,
+            changed: &changed,
+        })?;
+        matched = Some(match matched {
+            Some(saved) => saved.intersection(&evaluated).cloned().collect(),
+            None => evaluated,
+        });
+    }
+    Ok(matched)
 }
 
-fn normalize_changeset(workspace: &Workspace, file: ChangesetFile) -> Result<PlanChangeset> {
-    let mut repo_summaries = HashMap::new();
-    for repo in &file.repos {
-        let repo_id = RepoId::new(repo.repo.clone());
-        let known = workspace.repos.get(&repo_id).ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "changeset '{}' references unknown repo '{}'",
-                file.id, repo.repo
-            )))
-        })?;
-        if known.ignored {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "changeset '{}' references ignored repo '{}'",
-                file.id, repo.repo
-            ))));
-        }
-        if known.external {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "changeset '{}' references external repo '{}'",
-                file.id, repo.repo
-            ))));
-        }
-        if !known.path.is_dir() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "changeset '{}' references repo '{}' which is not cloned",
-                file.id, repo.repo
-            ))));
+/// Writes the repos a command settled on when `--save-selection` was given.
+/// Called again after later narrowing (such as `--changed`) so the last write
+/// wins.
+fn record_selection(workspace: &Workspace, repos: &[Repo]) -> Result<()> {
+    let Some(name) = REPO_SELECTION
+        .get()
+        .and_then(|selection| selection.save.as_deref())
+    else {
+        return Ok(());
+    };
+    let mut ids: Vec<String> = repos
+        .iter()
+        .map(|repo| repo.id.as_str().to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    save_selection(
+        &workspace.root,
+        &SavedSelection {
+            name: name.trim().to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            repos: ids,
+        },
+    )?;
+    Ok(())
+}
+
+/// Operations that ask for confirmation according to their `[safety]` policy.
+#[derive(Debug, Clone, Copy)]
+enum DangerousOperation {
+    ForcePush,
+    ForceBranch,
+    Amend,
+    MrMerge,
+    MrClose,
+}
+
+impl DangerousOperation {
+    fn policy_key(self) -> &'static str {
+        match self {
+            Self::ForcePush => "force_push",
+            Self::ForceBranch => "force_branch",
+            Self::Amend => "amend",
+            Self::MrMerge => "mr_merge",
+            Self::MrClose => "mr_close",
         }
-        repo_summaries.insert(repo_id, repo.summary.clone());
     }
 
-    Ok(PlanChangeset {
-        id: file.id,
-        title: file.title,
-        description: file.description,
-        branch: file.branch,
-        repo_summaries,
-    })
+    fn configured_policy(self, safety: &crate::config::SafetyConfig) -> Option<&str> {
+        match self {
+            Self::ForcePush => safety.force_push.as_deref(),
+            Self::ForceBranch => safety.force_branch.as_deref(),
+            Self::Amend => safety.amend.as_deref(),
+            Self::MrMerge => safety.mr_merge.as_deref(),
+            Self::MrClose => safety.mr_close.as_deref(),
+        }
+    }
 }
 
-fn git_shortstat_output(repo_path: &Path) -> String {
-    let command = vec![
-        "git".to_string(),
-        "diff".to_string(),
-        "--shortstat".to_string(),
-    ];
-    let output = match run_command_output_in_repo(repo_path, &command) {
-        Ok(output) => output,
-        Err(_) => return "diff stat unavailable".to_string(),
-    };
-
-    let trimmed = output.trim();
-    if trimmed.is_empty() {
-        "no textual diff stats".to_string()
-    } else {
-        trimmed.to_string()
+/// Confirms `operation` on `repos`. `confirm` (the default) asks once and is
+/// skipped by `--yes`, `type-name` asks for each repo name even with `--yes`,
+/// and `none` never asks.
+fn confirm_dangerous(
+    workspace: &Workspace,
+    operation: DangerousOperation,
+    prompt: &str,
+    repos: &[Repo],
+    assume_yes: bool,
+) -> Result<bool> {
+    let policy = workspace
+        .config
+        .safety
+        .as_ref()
+        .and_then(|safety| operation.configured_policy(safety))
+        .map(|policy| policy.trim().to_ascii_lowercase())
+        .unwrap_or_else(|| "confirm".to_string());
+    match policy.as_str() {
+        "none" => Ok(true),
+        "type-name" => {
+            if !std::io::stdin().is_terminal() {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "safety.{} = \"type-name\" requires an interactive terminal",
+                    operation.policy_key()
+                ))));
+            }
+            for repo in repos {
+                let confirmed = output::confirm_typed(prompt, repo.id.as_str())
+                    .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+                if !confirmed {
+                    output::warn(&format!("typed name did not match {}", repo.id.as_str()));
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => output::confirm(prompt, assume_yes)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err))),
     }
 }
 
-fn print_plan_summary(plan: &PlanSummary) {
-    println!("Changeset Analysis");
-    println!("==================");
+/// Which changes a secret scan reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SecretScanScope {
+    /// The index, i.e. what `git commit` is about to record.
+    Staged,
+    /// Staged and unstaged changes plus untracked files.
+    WorkingTree,
+}
 
-    if let Some(changeset) = plan.changeset.as_ref() {
-        println!();
-        println!("Active changeset: {} ({})", changeset.id, changeset.branch);
-        if !changeset.title.trim().is_empty() {
-            println!("  title: {}", changeset.title);
-        }
+/// Fails when changes in `repos` contain likely credentials, unless
+/// `[safety].secret_scan = false`.
+fn ensure_no_secrets(workspace: &Workspace, repos: &[Repo], scope: SecretScanScope) -> Result<()> {
+    let safety = workspace.config.safety.as_ref();
+    if !safety.and_then(|safety| safety.secret_scan).unwrap_or(true) {
+        return Ok(());
     }
+    let allowlist = safety
+        .and_then(|safety| safety.secret_allowlist.clone())
+        .unwrap_or_default();
+    let scanner = SecretScanner::new(&allowlist)?;
 
-    if plan.changed.is_empty() {
-        println!();
-        println!("No changed repositories detected.");
-        return;
+    let mut lines = Vec::new();
+    for repo in repos {
+        if !repo.path.is_dir() {
+            continue;
+        }
+        for finding in repo_secret_findings(&scanner, repo, scope)? {
+            lines.push(format!(
+                "  {}: {}:{} {} ({})",
+                repo.id.as_str(),
+                finding.file,
+                finding.line,
+                finding.rule,
+                finding.redacted
+            ));
+        }
+    }
+    if lines.is_empty() {
+        return Ok(());
     }
+    Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+        "possible secrets in {} changes:\n{}\nremove them, allow them with [safety].secret_allowlist or a `harmonia:allow-secret` comment, or pass --no-secret-scan",
+        match scope {
+            SecretScanScope::Staged => "staged",
+            SecretScanScope::WorkingTree => "local",
+        },
+        lines.join("\n")
+    ))))
+}
 
-    println!();
-    println!("Changed repositories:");
-    for repo in &plan.changed {
-        println!(
-            "  {:<18} {:<16} {}",
-            repo.id.as_str(),
-            repo.branch,
-            plan_status_summary(&repo.status)
-        );
-        println!("    {}", repo.diff_stat);
-        if let Some(summary) = repo.changeset_summary.as_deref() {
-            println!("    changeset: {}", summary);
+fn repo_secret_findings(
+    scanner: &SecretScanner,
+    repo: &Repo,
+    scope: SecretScanScope,
+) -> Result<Vec<SecretFinding>> {
+    let diff_command = |staged: bool| {
+        let mut cmd = vec![
+            "git".to_string(),
+            "diff".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--unified=0".to_string(),
+        ];
+        if staged {
+            cmd.push("--cached".to_string());
+        }
+        cmd
+    };
+    let mut findings = scanner.scan_diff(&run_command_output_in_repo(
+        &repo.path,
+        &diff_command(true),
+    )?);
+    if scope == SecretScanScope::WorkingTree {
+        findings.extend(scanner.scan_diff(&run_command_output_in_repo(
+            &repo.path,
+            &diff_command(false),
+        )?));
+        let untracked = run_command_output_in_repo(
+            &repo.path,
+            &[
+                "git".to_string(),
+                "ls-files".to_string(),
+                "--others".to_string(),
+                "--exclude-standard".to_string(),
+            ],
+        )?;
+        for file in untracked
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            // Binary files are skipped; the rules only look for text tokens.
+            if let Ok(content) = fs::read_to_string(repo.path.join(file)) {
+                findings.extend(scanner.scan_file(file, &content));
+            }
         }
     }
+    Ok(findings)
+}
 
-    println!();
-    println!("Merge order:");
-    for (index, repo) in plan.merge_order.iter().enumerate() {
-        println!("  {}. {}", index + 1, repo.as_str());
-    }
+/// Whether `branch` matches one of `[safety].release_branches`.
+fn is_release_branch(workspace: &Workspace, branch: &str) -> bool {
+    workspace
+        .config
+        .safety
+        .as_ref()
+        .and_then(|safety| safety.release_branches.as_ref())
+        .is_some_and(|patterns| branch_matches_any(branch, patterns))
+}
 
-    println!();
-    println!("Constraint analysis:");
-    println!("  cycles: {}", plan.constraints.cycles.len());
-    println!("  missing dependencies: {}", plan.constraints.missing.len());
-    println!("  violations: {}", plan.constraints.violations.len());
+/// Whether `branch` matches one of `patterns`, where `*` matches any run of
+/// characters.
+fn branch_matches_any(branch: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        let regex = format!("^{}$", regex::escape(pattern.trim()).replace("\\*", ".*"));
+        regex::Regex::new(&regex).is_ok_and(|regex| regex.is_match(branch))
+    })
+}
 
-    if !plan.constraints.violations.is_empty() {
-        println!("  top violations:");
-        for violation in plan.constraints.violations.iter().take(5) {
-            println!(
-                "    - {} -> {} [{}]",
-                violation.from_repo.as_str(),
-                violation.to_repo.as_str(),
-                violation.violation_type.as_str()
-            );
+/// Whether a `--repos` entry is a glob such as `service-*` rather than a repo
+/// name.
+fn is_repo_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '['])
+}
+
+/// Whether `repo` matches the glob `pattern`: `*` matches any run of
+/// characters (including `/`), `?` one character, and `[...]` one of a set,
+/// with `[!...]` negating it.
+fn repo_pattern_matches(pattern: &str, repo: &str) -> bool {
+    let mut regex = String::from("^");
+    let mut chars = pattern.trim().chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let mut class = String::new();
+                if chars.next_if_eq(&'!').is_some() {
+                    class.push('^');
+                }
+                let mut closed = false;
+                for ch in chars.by_ref() {
+                    if ch == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if ch == '\\' || ch == '^' || ch == '[' {
+                        class.push('\\');
+                    }
+                    class.push(ch);
+                }
+                if !closed {
+                    return false;
+                }
+                regex.push('[');
+                regex.push_str(&class);
+                regex.push(']');
+            }
+            other => regex.push_str(&regex::escape(&other.to_string())),
         }
     }
-
-    println!();
-    println!("Recommendations:");
-    for recommendation in &plan.recommendations {
-        println!("  - {}", recommendation);
-    }
+    regex.push('$');
+    regex::Regex::new(&regex).is_ok_and(|regex| regex.is_match(repo))
 }
 
-fn plan_to_json(plan: &PlanSummary) -> serde_json::Value {
-    serde_json::json!({
-        "changed_repos": plan.changed.iter().map(|repo| {
-            serde_json::json!({
-                "repo": repo.id.as_str(),
-                "branch": repo.branch,
-                "staged": repo.status.staged.len(),
-                "modified": repo.status.modified.len(),
-                "untracked": repo.status.untracked.len(),
-                "conflicts": repo.status.conflicts.len(),
-                "diff_stat": repo.diff_stat,
-                "changeset_summary": repo.changeset_summary.as_deref(),
-            })
-        }).collect::<Vec<_>>(),
-        "changeset": plan.changeset.as_ref().map(|changeset| {
-            serde_json::json!({
-                "id": changeset.id,
-                "title": changeset.title,
-                "description": changeset.description,
-                "branch": changeset.branch,
-                "repos": changeset.repo_summaries.iter().map(|(repo, summary)| {
-                    serde_json::json!({
-                        "repo": repo.as_str(),
-                        "summary": summary,
-                    })
-                }).collect::<Vec<_>>(),
-            })
-        }),
-        "merge_order": plan.merge_order.iter().map(|repo| repo.as_str()).collect::<Vec<_>>(),
-        "constraint_analysis": {
-            "cycles": plan.constraints.cycles.iter().map(|cycle| cycle.iter().map(|repo| repo.as_str()).collect::<Vec<_>>()).collect::<Vec<_>>(),
-            "missing": plan.constraints.missing.iter().map(|missing| {
-                serde_json::json!({
-                    "from": missing.from.as_str(),
-                    "dependency": missing.dependency.name,
-                    "constraint": missing.dependency.constraint.raw,
-                })
-            }).collect::<Vec<_>>(),
-            "violations": plan.constraints.violations.iter().map(|violation| {
-                serde_json::json!({
-                    "from": violation.from_repo.as_str(),
-                    "to": violation.to_repo.as_str(),
-                    "constraint": violation.constraint.raw,
-                    "actual_version": violation.actual_version.raw,
-                    "violation_type": violation.violation_type.as_str(),
-                })
-            }).collect::<Vec<_>>(),
-        },
-        "recommendations": plan.recommendations,
-    })
+/// Fails when the workspace is read-only, via `--read-only` or
+/// `[workspace].read_only`.
+fn ensure_writable(workspace: &Workspace, operation: &str) -> Result<()> {
+    let source = if READ_ONLY.get().copied().unwrap_or(false) {
+        "--read-only"
+    } else if workspace.config.workspace.read_only {
+        "[workspace].read_only"
+    } else {
+        return Ok(());
+    };
+    Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+        "{} is not allowed: workspace is read-only ({})",
+        operation, source
+    ))))
 }
 
-fn plan_recommendations(changed: &[PlanChangedRepo], report: &ConstraintReport) -> Vec<String> {
-    let mut recommendations = Vec::new();
+/// Whether `--select` or `--selection` was given, in which case commands skip
+/// their default `--changed` scope.
+fn repo_selection_active() -> bool {
+    REPO_SELECTION
+        .get()
+        .is_some_and(|selection| selection.expr.is_some() || selection.reuse.is_some())
+}
 
-    if !report.cycles.is_empty() {
-        recommendations
-            .push("resolve dependency cycles before creating merge requests".to_string());
-    }
-    if !report.missing.is_empty() {
-        recommendations
-            .push("add missing internal dependency mappings in repository configs".to_string());
-    }
-    if report
-        .violations
-        .iter()
-        .any(|violation| matches!(violation.violation_type, ViolationType::Unsatisfied))
-    {
-        recommendations.push(
-            "run `harmonia deps update --dry-run` to preview required constraint updates"
-                .to_string(),
-        );
-    }
-    if report.violations.iter().any(|violation| {
-        matches!(
-            violation.violation_type,
-            ViolationType::ExactPin | ViolationType::UpperBound
-        )
-    }) {
-        recommendations.push(
-            "review strict dependency constraints (exact pins and upper bounds) before merges"
-                .to_string(),
-        );
+fn select_configured_repos(
+    workspace: &Workspace,
+    repos: &[String],
+    group: Option<&str>,
+    all: bool,
+    include_external: bool,
+) -> Result<Vec<crate::core::repo::Repo>> {
+    if !repos.is_empty() {
+        let mut selected: Vec<Repo> = Vec::new();
+        for name in repos {
+            let matched: Vec<&Repo> = if is_repo_pattern(name) {
+                let mut matched: Vec<&Repo> = workspace
+                    .repos
+                    .values()
+                    .filter(|repo| should_include_repo(repo, include_external))
+                    .filter(|repo| repo_pattern_matches(name, repo.id.as_str()))
+                    .collect();
+                if matched.is_empty() {
+                    return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "no repos match {}",
+                        name
+                    ))));
+                }
+                matched.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+                matched
+            } else {
+                vec![workspace
+                    .repos
+                    .get(&RepoId::new(name.clone()))
+                    .ok_or_else(|| {
+                        HarmoniaError::Other(anyhow::anyhow!(format!("unknown repo {}", name)))
+                    })?]
+            };
+            for repo in matched {
+                if !selected.iter().any(|existing| existing.id == repo.id) {
+                    selected.push(repo.clone());
+                }
+            }
+        }
+        return Ok(selected);
     }
-    if changed.len() > 1 {
-        recommendations.push("merge in the listed order and wait for CI between steps".to_string());
+
+    if let Some(group_name) = group {
+        if let Some(groups) = workspace.config.groups.as_ref() {
+            if let Some(group_repos) = groups.groups.get(group_name) {
+                let mut selected = Vec::new();
+                for name in group_repos {
+                    if let Some(repo) = workspace
+                        .repos
+                        .get(&crate::core::repo::RepoId::new(name.clone()))
+                    {
+                        if should_include_repo(repo, include_external) {
+                            selected.push(repo.clone());
+                        }
+                    }
+                }
+                return Ok(selected);
+            }
+        }
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "unknown group {}",
+            group_name
+        ))));
     }
-    if recommendations.is_empty() {
-        recommendations
-            .push("no blocking issues detected for the current local changeset".to_string());
+
+    if all {
+        return Ok(workspace
+            .repos
+            .values()
+            .filter(|repo| should_include_repo(repo, include_external))
+            .cloned()
+            .collect());
     }
 
-    recommendations
-}
+    if let Some(groups) = workspace.config.groups.as_ref() {
+        if let Some(default_group) = groups.default.as_ref() {
+            return select_configured_repos(
+                workspace,
+                &[],
+                Some(default_group),
+                false,
+                include_external,
+            );
+        }
+    }
 
-fn plan_status_summary(status: &StatusSummary) -> String {
-    format!(
-        "{} staged, {} modified, {} untracked, {} conflicts",
-        status.staged.len(),
-        status.modified.len(),
-        status.untracked.len(),
-        status.conflicts.len()
-    )
+    Ok(workspace
+        .repos
+        .values()
+        .filter(|repo| should_include_repo(repo, include_external))
+        .cloned()
+        .collect())
 }
 
-fn compose_shell_env_value(name: &str, mut prefixes: Vec<PathBuf>) -> Result<Option<String>> {
-    if let Some(existing) = env::var_os(name) {
-        prefixes.extend(env::split_paths(&existing));
+fn should_include_repo(repo: &crate::core::repo::Repo, include_external: bool) -> bool {
+    if repo.ignored {
+        return false;
     }
-    if prefixes.is_empty() {
-        return Ok(None);
+    if repo.external && !include_external {
+        return false;
     }
+    true
+}
 
-    let joined = env::join_paths(prefixes).map_err(|err| {
-        HarmoniaError::Other(anyhow::anyhow!(format!(
-            "failed to compute {} for shell command: {}",
-            name, err
-        )))
-    })?;
-    Ok(Some(joined.to_string_lossy().to_string()))
+/// Worker count for a repo-parallel command: `--parallel`, then
+/// `HARMONIA_PARALLEL`, then `[<command>].parallel`, then
+/// `[defaults].parallel`, then every core. `0` at any level means one repo at
+/// a time.
+fn resolve_parallel(
+    workspace: &Workspace,
+    command: &str,
+    override_value: Option<usize>,
+) -> Option<usize> {
+    let config = &workspace.config;
+    let command_config = match command {
+        "sync" => config.sync.as_ref(),
+        "exec" => config.exec.as_ref(),
+        "run" => config.run.as_ref(),
+        "each" => config.each.as_ref(),
+        "git" => config.git.as_ref(),
+        "test" => config.test.as_ref(),
+        "lint" => config.lint.as_ref(),
+        _ => None,
+    };
+    override_value
+        .or_else(|| {
+            env::var("HARMONIA_PARALLEL")
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        })
+        .or_else(|| command_config.and_then(|command| command.parallel))
+        .or_else(|| {
+            config
+                .defaults
+                .as_ref()
+                .and_then(|defaults| defaults.parallel)
+        })
+        .map(|value| value.max(1))
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
 }
 
-fn resolve_workspace_paths(
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<(PathBuf, PathBuf)> {
-    let cwd = env::current_dir()?;
-    let resolved = resolve_workspace_with_overrides(cwd, workspace_root, config_path)?;
-    Ok((resolved.root, resolved.config_path))
+/// Limit for concurrent fetches and clones: `HARMONIA_NETWORK_PARALLEL`, then
+/// `[defaults].network_parallel`, then [`parallel::DEFAULT_NETWORK_PARALLEL`].
+fn network_queue(workspace: &Workspace) -> parallel::NetworkQueue {
+    parallel::NetworkQueue::new(network_parallel_limit(workspace))
 }
 
-fn resolve_editor_command(editor: Option<&str>) -> Result<Vec<String>> {
-    let command = editor
-        .map(|value| value.to_string())
-        .or_else(|| env::var("EDITOR").ok())
-        .unwrap_or_else(|| "code".to_string());
-    let command = split_command(&command);
-    if command.is_empty() {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "editor command cannot be empty"
-        )));
-    }
-    Ok(command)
+fn network_parallel_limit(workspace: &Workspace) -> usize {
+    env::var("HARMONIA_NETWORK_PARALLEL")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .or_else(|| {
+            workspace
+                .config
+                .defaults
+                .as_ref()
+                .and_then(|defaults| defaults.network_parallel)
+        })
+        .unwrap_or(parallel::DEFAULT_NETWORK_PARALLEL)
 }
 
-fn read_workspace_config_value(path: &Path) -> Result<toml::Value> {
-    let contents = fs::read_to_string(path)?;
-    toml::from_str(&contents).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
+/// Repos `plan` considers, and so fetches under `--fresh`.
+fn plan_repos(workspace: &Workspace) -> impl Iterator<Item = &Repo> {
+    workspace
+        .repos
+        .values()
+        .filter(|repo| !repo.ignored && !repo.external)
 }
 
-fn write_workspace_config_value(path: &Path, value: &toml::Value) -> Result<()> {
-    let contents = toml::to_string_pretty(value)
-        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-    fs::write(path, contents)?;
-    Ok(())
+/// Whether `plan` and `mr status` fetch before reading remote state: `--fresh`
+/// or `[defaults].prefetch`.
+fn prefetch_enabled(workspace: &Workspace, fresh: bool) -> bool {
+    fresh
+        || workspace
+            .config
+            .defaults
+            .as_ref()
+            .and_then(|defaults| defaults.prefetch)
+            .unwrap_or(false)
 }
 
-fn workspace_config_get<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
-    let segments: Vec<&str> = key
-        .split('.')
-        .filter(|segment| !segment.is_empty())
+/// Fetches `repos`, at most the network limit at a time. Failures only warn,
+/// leaving the command to work from the remote state it already has.
+fn prefetch_repos<'a>(workspace: &Workspace, repos: impl IntoIterator<Item = &'a Repo>) {
+    let repos: Vec<&Repo> = repos
+        .into_iter()
+        .filter(|repo| repo.path.is_dir())
         .collect();
-    if segments.is_empty() {
-        return None;
+    if repos.is_empty() {
+        return;
+    }
+    let transport = match git_transport(workspace) {
+        Ok(transport) => transport,
+        Err(err) => {
+            output::warn(&err.to_string());
+            return;
+        }
+    };
+    output::info(&format!("fetching {} repositories", repos.len()));
+    let progress = MultiProgress::new();
+    let results =
+        parallel::run_in_parallel(repos, Some(network_parallel_limit(workspace)), |repo| {
+            (repo, fetch_quietly(repo, transport, &progress))
+        });
+    for (repo, result) in results {
+        if let Err(failure) = result {
+            output::warn(&format!(
+                "fetch failed for {}: {}",
+                repo.id.as_str(),
+                failure
+            ));
+        }
     }
+}
 
-    let mut current = value;
-    for segment in segments {
-        current = current.get(segment)?;
+/// One fetch for `prefetch_repos`: libgit2 when the transport allows it,
+/// otherwise `git fetch --quiet`.
+fn fetch_quietly(
+    repo: &Repo,
+    transport: GitTransport,
+    progress: &MultiProgress,
+) -> std::result::Result<(), String> {
+    if transport == GitTransport::Native {
+        if let Some(remote) = native_fetch_remote(&repo.path) {
+            let git = git2::Repository::open(&repo.path).map_err(|err| err.to_string())?;
+            match native_fetch(&git, &remote, repo.id.as_str(), progress) {
+                Err(err) if !is_auth_error(&err) => return Err(error_chain(&err)),
+                Err(_) => {}
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+    let command = vec![
+        "git".to_string(),
+        "fetch".to_string(),
+        "--quiet".to_string(),
+    ];
+    match run_command_captured_in_repo(&repo.path, &command) {
+        Ok((true, _)) => Ok(()),
+        Ok((false, text)) => Err(text.trim().to_string()),
+        Err(err) => Err(err.to_string()),
     }
-    Some(current)
 }
 
-fn workspace_config_set(value: &mut toml::Value, key: &str, new_value: toml::Value) -> Result<()> {
-    let segments: Vec<&str> = key
-        .split('.')
-        .filter(|segment| !segment.is_empty())
-        .collect();
-    if segments.is_empty() {
-        return Err(HarmoniaError::Other(anyhow::anyhow!(
-            "config key cannot be empty"
-        )));
+fn git_transport(workspace: &Workspace) -> Result<GitTransport> {
+    workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.git_transport.as_deref())
+        .map(GitTransport::parse)
+        .unwrap_or(Ok(GitTransport::Native))
+}
+
+/// Remote `git fetch` would use (the current branch's upstream remote, else
+/// `origin`, else the only remote), when libgit2 can reach it and the repo
+/// does not authenticate through `http.extraheader`.
+fn native_fetch_remote(repo_path: &Path) -> Option<String> {
+    let git = git2::Repository::open(repo_path).ok()?;
+    if has_extra_header(&git) {
+        return None;
     }
+    let upstream_remote = git
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(str::to_string))
+        .and_then(|name| git.branch_upstream_remote(&name).ok())
+        .and_then(|remote| remote.as_str().map(str::to_string));
+    let names = git.remotes().ok()?;
+    let names: Vec<&str> = names.iter().flatten().collect();
+    let remote = upstream_remote.or_else(|| {
+        if names.contains(&"origin") {
+            Some("origin".to_string())
+        } else if names.len() == 1 {
+            Some(names[0].to_string())
+        } else {
+            None
+        }
+    })?;
+    let url = git.find_remote(&remote).ok()?.url()?.to_string();
+    native_supports_url(&url).then_some(remote)
+}
 
-    let mut current = value;
-    for segment in &segments[..segments.len() - 1] {
-        let table = current.as_table_mut().ok_or_else(|| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "cannot set '{}' because '{}' is not a table",
-                key, segment
-            )))
-        })?;
-        current = table
-            .entry((*segment).to_string())
-            .or_insert_with(|| toml::Value::Table(toml::map::Map::new()));
-        if !current.is_table() {
-            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-                "cannot set '{}' because '{}' is not a table",
-                key, segment
-            ))));
+/// Remote and remote branch for pushing `branch` with libgit2, or `None` when
+/// the push has to go through the git CLI: an SSH remote, a `pre-push` hook
+/// to run, an `http.extraheader` to send, or no remote to push to.
+fn native_push_destination(
+    repo_path: &Path,
+    branch: &str,
+    remote: Option<&str>,
+) -> Option<(String, String)> {
+    let git = git2::Repository::open(repo_path).ok()?;
+    if has_pre_push_hook(&git) || has_extra_header(&git) {
+        return None;
+    }
+    let (remote, remote_branch) = match remote {
+        Some(remote) => (remote.to_string(), branch.to_string()),
+        None => {
+            let local = format!("refs/heads/{branch}");
+            let remote = git
+                .branch_upstream_remote(&local)
+                .ok()?
+                .as_str()?
+                .to_string();
+            let merge = git.branch_upstream_merge(&local).ok()?;
+            let remote_branch = merge.as_str()?.strip_prefix("refs/heads/")?.to_string();
+            (remote, remote_branch)
         }
+    };
+    let found = git.find_remote(&remote).ok()?;
+    let url = found.pushurl().or(found.url())?;
+    native_supports_url(url).then_some((remote, remote_branch))
+}
+
+fn run_command_in_repo(repo_path: &Path, command: &[String]) -> Result<()> {
+    run_command_in_repo_with_env(repo_path, command, &[], None)
+}
+
+/// `defaults.command_timeout` unless `timeout` (seconds) overrides it. Zero
+/// means no limit.
+fn resolve_command_timeout(workspace: &Workspace, timeout: Option<u64>) -> Option<Duration> {
+    timeout
+        .or_else(|| {
+            workspace
+                .config
+                .defaults
+                .as_ref()
+                .and_then(|defaults| defaults.command_timeout)
+        })
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+/// Puts a command that has a deadline in its own process group, so a timeout
+/// or an interrupt can signal everything it started (a shell's children, a
+/// test runner's workers) and not just the direct child. Outside the
+/// terminal's foreground group it cannot read the terminal, so its stdin is
+/// closed.
+fn isolate_for_timeout(cmd: &mut std::process::Command, timeout: Option<Duration>) {
+    if timeout.is_none() {
+        return;
     }
+    cmd.stdin(std::process::Stdio::null());
+    #[cfg(unix)]
+    {
+        std::os::unix::process::CommandExt::process_group(cmd, 0);
+        watch_for_interrupts();
+    }
+}
+
+/// SIGINT or SIGTERM received while timed commands run, or 0. Timed commands
+/// sit in their own process groups, so a terminal Ctrl-C only reaches
+/// harmonia; the handler records it and [`wait_with_timeout`] passes it on to
+/// the command's group. A second signal terminates harmonia right away.
+#[cfg(unix)]
+static INTERRUPT: OnceLock<std::sync::Arc<std::sync::atomic::AtomicUsize>> = OnceLock::new();
+
+#[cfg(unix)]
+fn watch_for_interrupts() {
+    INTERRUPT.get_or_init(|| {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+        use std::sync::atomic::{AtomicBool, AtomicUsize};
+        use std::sync::Arc;
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let pending = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM] {
+            let _ = signal_hook::flag::register_conditional_shutdown(
+                signal,
+                128 + signal,
+                Arc::clone(&pending),
+            );
+            let _ = signal_hook::flag::register(signal, Arc::clone(&pending));
+            let _ =
+                signal_hook::flag::register_usize(signal, Arc::clone(&received), signal as usize);
+        }
+        received
+    });
+}
 
-    let leaf = segments[segments.len() - 1];
-    let table = current.as_table_mut().ok_or_else(|| {
-        HarmoniaError::Other(anyhow::anyhow!(format!(
-            "cannot set '{}' because parent path is not a table",
-            key
-        )))
-    })?;
-    table.insert(leaf.to_string(), new_value);
-    Ok(())
+/// The signal that interrupted timed commands, if any.
+fn interrupt_signal() -> Option<i32> {
+    #[cfg(unix)]
+    {
+        INTERRUPT
+            .get()
+            .map(|received| received.load(std::sync::atomic::Ordering::SeqCst))
+            .filter(|signal| *signal != 0)
+            .map(|signal| signal as i32)
+    }
+    #[cfg(not(unix))]
+    None
 }
 
-fn parse_config_value(raw: &str) -> Result<toml::Value> {
-    let snippet = format!("value = {raw}");
-    if let Ok(parsed) = toml::from_str::<toml::Value>(&snippet) {
-        if let Some(value) = parsed.get("value") {
-            return Ok(value.clone());
+/// Sends `signal` to the process group a timed `child` leads.
+#[cfg(unix)]
+fn signal_process_group(child: &std::process::Child, signal: rustix::process::Signal) {
+    let _ = rustix::process::kill_process_group(rustix::process::Pid::from_child(child), signal);
+}
+
+fn command_timed_out(label: &str, timeout: Duration) -> HarmoniaError {
+    HarmoniaError::Other(anyhow::anyhow!(format!(
+        "{} timed out after {}s",
+        label,
+        timeout.as_secs()
+    )))
+}
+
+/// How long an interrupted command gets to exit before it is killed.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(2);
+
+/// Waits for a child started with [`isolate_for_timeout`], killing its process
+/// group once `timeout` has passed. Returns `None` when it was killed. An
+/// interrupt is passed on to the group and returned as an `Interrupted` error
+/// so the caller unwinds normally.
+fn wait_with_timeout(
+    child: &mut std::process::Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<std::process::ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return child.wait().map(Some);
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(signal) = interrupt_signal() {
+            stop_interrupted(child, signal)?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "interrupted",
+            ));
+        }
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            #[cfg(unix)]
+            signal_process_group(child, rustix::process::Signal::KILL);
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
         }
+        std::thread::sleep(Duration::from_millis(50));
     }
+}
 
-    Ok(toml::Value::String(raw.to_string()))
+/// Forwards `signal` to an interrupted child's group, then kills the group if
+/// it is still running after [`INTERRUPT_GRACE`].
+fn stop_interrupted(child: &mut std::process::Child, signal: i32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(signal) = rustix::process::Signal::from_named_raw(signal) {
+        signal_process_group(child, signal);
+    }
+    #[cfg(not(unix))]
+    let _ = signal;
+    let grace = Instant::now() + INTERRUPT_GRACE;
+    while Instant::now() < grace {
+        if child.try_wait()?.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    #[cfg(unix)]
+    signal_process_group(child, rustix::process::Signal::KILL);
+    let _ = child.kill();
+    child.wait().map(|_| ())
 }
 
-fn format_config_value(value: &toml::Value) -> String {
-    match value {
-        toml::Value::String(value) => value.clone(),
-        toml::Value::Integer(value) => value.to_string(),
-        toml::Value::Float(value) => value.to_string(),
-        toml::Value::Boolean(value) => value.to_string(),
-        toml::Value::Datetime(value) => value.to_string(),
-        _ => value.to_string(),
+fn run_command_in_repo_with_env(
+    repo_path: &Path,
+    command: &[String],
+    env_vars: &[(String, String)],
+    timeout: Option<Duration>,
+) -> Result<()> {
+    if command.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
+    }
+    let mut cmd = std::process::Command::new(&command[0]);
+    if command.len() > 1 {
+        cmd.args(&command[1..]);
+    }
+    if report::enabled() {
+        cmd.stdout(std::io::stderr());
+    }
+    isolate_for_timeout(&mut cmd, timeout);
+    let mut child = cmd
+        .current_dir(repo_path)
+        .envs(env_vars.iter().map(|(key, value)| (key, value)))
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", command))?;
+    let status = wait_with_timeout(&mut child, timeout)
+        .with_context(|| format!("failed to run {:?}", command))?;
+    let Some(status) = status else {
+        return Err(command_timed_out(
+            &format!("command {:?}", command),
+            timeout.unwrap_or_default(),
+        ));
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "command {:?} failed",
+            command
+        ))))
     }
 }
 
-fn workspace_repos_table(value: &toml::Value) -> Result<&toml::map::Map<String, toml::Value>> {
-    let repos = value
-        .get("repos")
-        .and_then(|value| value.as_table())
-        .ok_or_else(|| HarmoniaError::Other(anyhow::anyhow!("[repos] must be a table")))?;
-    Ok(repos)
+/// Default `exec` line prefix, in the style of `docker compose` logs.
+const DEFAULT_EXEC_PREFIX: &str = "{repo} | ";
+
+fn exec_prefix_template(workspace: &Workspace) -> String {
+    workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.exec_prefix.clone())
+        .unwrap_or_else(|| DEFAULT_EXEC_PREFIX.to_string())
 }
 
-fn load_workspace(
-    workspace_root: Option<PathBuf>,
-    config_path: Option<PathBuf>,
-) -> Result<Workspace> {
-    let cwd = env::current_dir()?;
-    let resolved = resolve_workspace_with_overrides(cwd, workspace_root, config_path)?;
-    Workspace::load_from(resolved.root, resolved.config_path).map_err(HarmoniaError::from)
+/// A command that ran to completion, or was killed at its timeout, with its
+/// output captured.
+struct CapturedRun {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    stdout: String,
+    stderr: String,
+    duration: Duration,
 }
 
-fn select_repos(
-    workspace: &Workspace,
-    repos: &[String],
-    group: Option<&str>,
-    all: bool,
-    include_external: bool,
-) -> Result<Vec<crate::core::repo::Repo>> {
-    if !repos.is_empty() {
-        return repos
-            .iter()
-            .map(|name| {
-                workspace
-                    .repos
-                    .get(&crate::core::repo::RepoId::new(name.clone()))
-                    .cloned()
-                    .ok_or_else(|| {
-                        HarmoniaError::Other(anyhow::anyhow!(format!("unknown repo {}", name)))
-                    })
-            })
-            .collect();
+/// Runs `command` in `repo_path` (through the shell when `shell` is set)
+/// without inheriting the terminal, so its output can be grouped or reported.
+fn run_command_captured_with_env(
+    repo_path: &Path,
+    command: &[String],
+    env_vars: &[(String, String)],
+    shell: bool,
+    timeout: Option<Duration>,
+) -> Result<CapturedRun> {
+    if command.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
     }
+    let mut cmd = if !shell {
+        let mut cmd = std::process::Command::new(&command[0]);
+        cmd.args(&command[1..]);
+        cmd
+    } else if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.arg("/C").arg(command.join(" "));
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg(command.join(" "));
+        cmd
+    };
+    isolate_for_timeout(&mut cmd, timeout);
+    let started = Instant::now();
+    let mut child = cmd
+        .current_dir(repo_path)
+        .envs(env_vars.iter().map(|(key, value)| (key, value)))
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", command))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let (status, stdout, stderr) = std::thread::scope(|scope| {
+        let stdout = scope.spawn(|| read_pipe(stdout));
+        let stderr = scope.spawn(|| read_pipe(stderr));
+        let status = wait_with_timeout(&mut child, timeout);
+        (
+            status,
+            stdout.join().unwrap_or_default(),
+            stderr.join().unwrap_or_default(),
+        )
+    });
+    let status = status.with_context(|| format!("failed to run {:?}", command))?;
+    Ok(CapturedRun {
+        exit_code: status.and_then(|status| status.code()),
+        timed_out: status.is_none(),
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        duration: started.elapsed(),
+    })
+}
 
-    if let Some(group_name) = group {
-        if let Some(groups) = workspace.config.groups.as_ref() {
-            if let Some(group_repos) = groups.groups.get(group_name) {
-                let mut selected = Vec::new();
-                for name in group_repos {
-                    if let Some(repo) = workspace
-                        .repos
-                        .get(&crate::core::repo::RepoId::new(name.clone()))
-                    {
-                        if should_include_repo(repo, include_external) {
-                            selected.push(repo.clone());
-                        }
-                    }
+fn read_pipe(pipe: Option<impl std::io::Read>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buffer);
+    }
+    buffer
+}
+
+/// Writes a repo's captured stdout and stderr as one block, each line behind
+/// `prefix`, holding both stream locks so blocks of parallel repos never mix.
+fn print_captured_run(run: &CapturedRun, prefix: &str) {
+    let stdout = std::io::stdout();
+    let stderr = std::io::stderr();
+    let stdout = stdout.lock();
+    let stderr = stderr.lock();
+    copy_prefixed_lines(run.stdout.as_bytes(), stdout, prefix);
+    copy_prefixed_lines(run.stderr.as_bytes(), stderr, prefix);
+}
+
+/// Prints the JSON summary of captured runs when `json` is set and fails
+/// naming every repo whose command failed, unless `ignore_errors` is set.
+fn report_captured_runs(
+    label: &str,
+    command: &[String],
+    ids: &[RepoId],
+    results: Vec<Result<CapturedRun>>,
+    json: bool,
+    ignore_errors: bool,
+) -> Result<()> {
+    let mut failed = Vec::new();
+    let mut rows = Vec::new();
+    for (id, result) in ids.iter().zip(results) {
+        match result {
+            Ok(run) => {
+                if run.timed_out {
+                    failed.push(format!("{} (timed out)", id.as_str()));
+                } else if run.exit_code != Some(0) {
+                    failed.push(id.as_str().to_string());
                 }
-                return Ok(selected);
+                rows.push(serde_json::json!({
+                    "repo": id.as_str(),
+                    "success": run.exit_code == Some(0),
+                    "exit_code": run.exit_code,
+                    "timed_out": run.timed_out,
+                    "duration_ms": run.duration.as_millis() as u64,
+                    "stdout": run.stdout,
+                    "stderr": run.stderr,
+                }));
+            }
+            Err(err) => {
+                if !json {
+                    output::error(&format!("{}: {}", id.as_str(), err));
+                }
+                failed.push(id.as_str().to_string());
+                rows.push(serde_json::json!({
+                    "repo": id.as_str(),
+                    "success": false,
+                    "exit_code": null,
+                    "error": err.to_string(),
+                }));
             }
         }
-        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
-            "unknown group {}",
-            group_name
-        ))));
     }
-
-    if all {
-        return Ok(workspace
-            .repos
-            .values()
-            .filter(|repo| should_include_repo(repo, include_external))
-            .cloned()
-            .collect());
+    if json {
+        let summary = serde_json::json!({
+            "command": command,
+            "succeeded": ids.len() - failed.len(),
+            "failed": failed.len(),
+            "results": rows,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary)
+                .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+        );
     }
-
-    if let Some(groups) = workspace.config.groups.as_ref() {
-        if let Some(default_group) = groups.default.as_ref() {
-            return select_repos(workspace, &[], Some(default_group), false, include_external);
-        }
+    if failed.is_empty() || ignore_errors {
+        Ok(())
+    } else {
+        Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} {:?} failed in: {}",
+            label,
+            command,
+            failed.join(", ")
+        ))))
     }
+}
 
-    Ok(workspace
-        .repos
-        .values()
-        .filter(|repo| should_include_repo(repo, include_external))
-        .cloned()
-        .collect())
+/// Expands `{repo}` in an `exec` prefix template to `repo` padded to `width`.
+fn render_exec_prefix(template: &str, repo: &str, width: usize) -> String {
+    template.replace("{repo}", &format!("{repo:<width$}"))
 }
 
-fn should_include_repo(repo: &crate::core::repo::Repo, include_external: bool) -> bool {
-    if repo.ignored {
-        return false;
+/// Like `run_command_in_repo_with_env`, but writes each line of the command's
+/// stdout and stderr with `prefix` in front, so output of repos running in
+/// parallel can be told apart. Lines are written whole.
+fn run_command_in_repo_prefixed(
+    repo_path: &Path,
+    command: &[String],
+    env_vars: &[(String, String)],
+    prefix: &str,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    if command.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
     }
-    if repo.external && !include_external {
-        return false;
+    let mut cmd = std::process::Command::new(&command[0]);
+    isolate_for_timeout(&mut cmd, timeout);
+    let mut child = cmd
+        .args(&command[1..])
+        .current_dir(repo_path)
+        .envs(env_vars.iter().map(|(key, value)| (key, value)))
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", command))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let status = std::thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| copy_prefixed_lines(stdout, std::io::stdout(), prefix));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| copy_prefixed_lines(stderr, std::io::stderr(), prefix));
+        }
+        wait_with_timeout(&mut child, timeout)
+    })
+    .with_context(|| format!("failed to run {:?}", command))?;
+    let Some(status) = status else {
+        return Err(command_timed_out(
+            &format!("command {:?}", command),
+            timeout.unwrap_or_default(),
+        ));
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "command {:?} failed",
+            command
+        ))))
     }
-    true
 }
 
-fn resolve_parallel(override_value: Option<usize>) -> Option<usize> {
-    if let Some(value) = override_value {
-        return Some(value);
-    }
-    if let Ok(value) = env::var("HARMONIA_PARALLEL") {
-        if let Ok(parsed) = value.parse() {
-            return Some(parsed);
+fn copy_prefixed_lines(input: impl std::io::Read, output: impl std::io::Write, prefix: &str) {
+    let mut reader = std::io::BufReader::new(input);
+    let mut output = output;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match std::io::BufRead::read_until(&mut reader, b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if !line.ends_with(b"\n") {
+            line.push(b'\n');
+        }
+        let mut prefixed = Vec::with_capacity(prefix.len() + line.len());
+        prefixed.extend_from_slice(prefix.as_bytes());
+        prefixed.extend_from_slice(&line);
+        if output.write_all(&prefixed).is_err() {
+            break;
         }
     }
-    std::thread::available_parallelism().ok().map(|n| n.get())
+    let _ = output.flush();
 }
 
-fn run_command_in_repo(repo_path: &Path, command: &[String]) -> Result<()> {
+/// Like `run_command_in_repo`, but sends the command's stdout to stderr so that
+/// stdout stays reserved for machine-readable output.
+fn run_command_in_repo_quiet(repo_path: &Path, command: &[String]) -> Result<()> {
     if command.is_empty() {
         return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
     }
-    let mut cmd = std::process::Command::new(&command[0]);
-    if command.len() > 1 {
-        cmd.args(&command[1..]);
-    }
-    let status = cmd
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
         .current_dir(repo_path)
+        .stdout(std::io::stderr())
         .status()
         .with_context(|| format!("failed to run {:?}", command))?;
     if status.success() {
@@ -5326,7 +16753,28 @@ fn run_command_output_in_repo(repo_path: &Path, command: &[String]) -> Result<St
     }
 }
 
-fn run_shell_command_in_repo(repo_path: &Path, command: &[String]) -> Result<()> {
+/// Runs `command` in `repo_path` and returns whether it succeeded along with
+/// its stdout followed by its stderr.
+fn run_command_captured_in_repo(repo_path: &Path, command: &[String]) -> Result<(bool, String)> {
+    if command.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
+    }
+    let output = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("failed to run {:?}", command))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text))
+}
+
+fn run_shell_command_in_repo(
+    repo_path: &Path,
+    command: &[String],
+    env_vars: &[(String, String)],
+    timeout: Option<Duration>,
+) -> Result<()> {
     let joined = command.join(" ");
     if joined.is_empty() {
         return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
@@ -5341,10 +16789,23 @@ fn run_shell_command_in_repo(repo_path: &Path, command: &[String]) -> Result<()>
         cmd.arg("-c").arg(&joined);
         cmd
     };
-    let status = cmd
+    if report::enabled() {
+        cmd.stdout(std::io::stderr());
+    }
+    isolate_for_timeout(&mut cmd, timeout);
+    let mut child = cmd
         .current_dir(repo_path)
-        .status()
+        .envs(env_vars.iter().map(|(key, value)| (key, value)))
+        .spawn()
+        .with_context(|| format!("failed to run shell command {}", joined))?;
+    let status = wait_with_timeout(&mut child, timeout)
         .with_context(|| format!("failed to run shell command {}", joined))?;
+    let Some(status) = status else {
+        return Err(command_timed_out(
+            &format!("shell command '{}'", joined),
+            timeout.unwrap_or_default(),
+        ));
+    };
     if status.success() {
         Ok(())
     } else {
@@ -5361,6 +16822,7 @@ fn run_shell_command_with_env(
     workspace_root: &Path,
     path_value: Option<&str>,
     pythonpath_value: Option<&str>,
+    env_vars: &[(String, String)],
 ) -> Result<()> {
     if command.is_empty() {
         return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
@@ -5380,6 +16842,7 @@ fn run_shell_command_with_env(
         cmd
     };
     cmd.current_dir(repo_path);
+    cmd.envs(env_vars.iter().map(|(key, value)| (key, value)));
     cmd.env("HARMONIA_WORKSPACE", workspace_root);
     if let Some(path) = path_value {
         cmd.env("PATH", path);
@@ -5439,6 +16902,13 @@ struct GraphJson {
 struct GraphNode {
     id: String,
     label: String,
+    ecosystem: Option<String>,
+    version: Option<String>,
+    changed: bool,
+    external: bool,
+    groups: Vec<String>,
+    dependents: usize,
+    depth: usize,
 }
 
 #[derive(Serialize)]
@@ -5452,6 +16922,22 @@ struct GraphCheckJson {
     cycles: Vec<Vec<String>>,
     missing: Vec<GraphMissingJson>,
     violations: Vec<GraphViolationJson>,
+    duplicates: Vec<GraphDuplicateJson>,
+    external: Vec<GraphExternalJson>,
+}
+
+#[derive(Serialize)]
+struct GraphExternalJson {
+    from: String,
+    dependency: String,
+    constraint: String,
+    version_url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GraphDuplicateJson {
+    name: String,
+    repos: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -5474,6 +16960,61 @@ struct GraphViolationJson {
 struct DiffJsonEntry {
     repo: String,
     files: Vec<String>,
+    insertions: usize,
+    deletions: usize,
+    changes: Vec<DiffFileChange>,
+}
+
+/// One file of `diff --format json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct DiffFileChange {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_path: Option<String>,
+    /// `added`, `modified`, `deleted`, `renamed`, `copied`, `type-changed`,
+    /// or `untracked`.
+    status: &'static str,
+    insertions: usize,
+    deletions: usize,
+    binary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hunks: Option<Vec<DiffHunk>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct DiffHunk {
+    header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    /// Hunk body lines, each starting with ` `, `+`, `-`, or `\`.
+    lines: Vec<String>,
+}
+
+/// Per-repo totals for `diff --summary`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+struct DiffSummary {
+    files: usize,
+    insertions: usize,
+    deletions: usize,
+    renames: usize,
+}
+
+impl DiffSummary {
+    fn add(&mut self, other: &DiffSummary) {
+        self.files += other.files;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+        self.renames += other.renames;
+    }
+}
+
+#[derive(Serialize)]
+struct DiffSummaryJsonEntry {
+    repo: String,
+    #[serde(flatten)]
+    summary: DiffSummary,
 }
 
 #[derive(Serialize)]
@@ -5503,7 +17044,7 @@ struct DepsDepJson {
     actual: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct DependencyUpdate {
     repo: RepoId,
     dependency: String,
@@ -5513,12 +17054,25 @@ struct DependencyUpdate {
 fn graph_to_json(
     edges: &HashMap<RepoId, Vec<RepoId>>,
     labels: &HashMap<RepoId, String>,
+    metadata: &HashMap<RepoId, viz::NodeMetadata>,
+    impact: &HashMap<RepoId, ImpactScore>,
+    workspace: &Workspace,
 ) -> GraphJson {
     let mut nodes: Vec<GraphNode> = labels
         .iter()
-        .map(|(id, label)| GraphNode {
-            id: id.as_str().to_string(),
-            label: label.clone(),
+        .map(|(id, label)| {
+            let meta = metadata.get(id).cloned().unwrap_or_default();
+            GraphNode {
+                id: id.as_str().to_string(),
+                label: label.clone(),
+                ecosystem: meta.ecosystem,
+                version: meta.version,
+                changed: meta.changed,
+                external: meta.external,
+                groups: repo_group_names(workspace, id),
+                dependents: impact.get(id).map(|score| score.dependents).unwrap_or(0),
+                depth: impact.get(id).map(|score| score.depth).unwrap_or(0),
+            }
         })
         .collect();
     nodes.sort_by(|a, b| a.id.cmp(&b.id));
@@ -5572,10 +17126,34 @@ impl From<ConstraintReport> for GraphCheckJson {
                 kind: violation_type_label(violation.violation_type),
             })
             .collect();
+        let duplicates = report
+            .duplicates
+            .into_iter()
+            .map(|duplicate| GraphDuplicateJson {
+                name: duplicate.name,
+                repos: duplicate
+                    .repos
+                    .iter()
+                    .map(|id| id.as_str().to_string())
+                    .collect(),
+            })
+            .collect();
+        let external = report
+            .external
+            .into_iter()
+            .map(|item| GraphExternalJson {
+                from: item.from.as_str().to_string(),
+                dependency: item.dependency.name,
+                constraint: item.dependency.constraint.raw,
+                version_url: item.package.version_url,
+            })
+            .collect();
         Self {
             cycles,
             missing,
             violations,
+            duplicates,
+            external,
         }
     }
 }
@@ -5590,7 +17168,12 @@ fn violation_type_label(violation: ViolationType) -> String {
 }
 
 fn print_constraint_report(report: &ConstraintReport, show_fixes: bool) {
-    if report.cycles.is_empty() && report.missing.is_empty() && report.violations.is_empty() {
+    if report.cycles.is_empty()
+        && report.missing.is_empty()
+        && report.violations.is_empty()
+        && report.duplicates.is_empty()
+        && report.external.is_empty()
+    {
         output::info("no constraint issues found");
         return;
     }
@@ -5607,6 +17190,41 @@ fn print_constraint_report(report: &ConstraintReport, show_fixes: bool) {
         }
     }
 
+    if !report.duplicates.is_empty() {
+        println!("duplicate package names:");
+        for duplicate in &report.duplicates {
+            println!(
+                "  {}: {}",
+                duplicate.name,
+                duplicate
+                    .repos
+                    .iter()
+                    .map(RepoId::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+
+    if !report.external.is_empty() {
+        println!("external dependencies (unmanaged):");
+        for external in &report.external {
+            let source = external
+                .package
+                .version_url
+                .as_deref()
+                .map(|url| format!(" [versions: {url}]"))
+                .unwrap_or_default();
+            println!(
+                "  {} -> {} ({}){}",
+                external.from.as_str(),
+                external.dependency.name,
+                external.dependency.constraint.raw,
+                source
+            );
+        }
+    }
+
     if !report.missing.is_empty() {
         println!("missing internal dependencies:");
         for missing in &report.missing {
@@ -5683,13 +17301,161 @@ fn build_directional_edges(
         }
     }
 
-    let mut out = HashMap::new();
-    for (node, deps) in edges {
-        let mut list: Vec<RepoId> = deps.into_iter().collect();
-        list.sort_by(|a, b| a.as_str().cmp(b.as_str()));
-        out.insert(node, list);
-    }
-    out
+    let mut out = HashMap::new();
+    for (node, deps) in edges {
+        let mut list: Vec<RepoId> = deps.into_iter().collect();
+        list.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        out.insert(node, list);
+    }
+    out
+}
+
+/// `start` plus every node reachable from it along `edges`, at most
+/// `max_depth` edges away when given.
+fn reachable_within(
+    edges: &HashMap<RepoId, Vec<RepoId>>,
+    start: &RepoId,
+    max_depth: Option<usize>,
+) -> HashSet<RepoId> {
+    let mut seen = HashSet::from([start.clone()]);
+    let mut frontier = vec![start.clone()];
+    let mut depth = 0;
+    while !frontier.is_empty() && max_depth.is_none_or(|max| depth < max) {
+        let mut next = Vec::new();
+        for node in frontier {
+            for dep in edges.get(&node).into_iter().flatten() {
+                if seen.insert(dep.clone()) {
+                    next.push(dep.clone());
+                }
+            }
+        }
+        frontier = next;
+        depth += 1;
+    }
+    seen
+}
+
+/// A `graph show --only/--exclude` filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GraphNodeFilter {
+    Group(String),
+    Ecosystem(String),
+    Repo(String),
+    Changed,
+    External,
+}
+
+impl GraphNodeFilter {
+    fn matches(&self, repo: &Repo, workspace: &Workspace, changed: &HashSet<RepoId>) -> bool {
+        match self {
+            GraphNodeFilter::Group(group) => workspace
+                .config
+                .groups
+                .as_ref()
+                .and_then(|groups| groups.groups.get(group))
+                .is_some_and(|members| members.iter().any(|name| name == repo.id.as_str())),
+            GraphNodeFilter::Ecosystem(ecosystem) => repo
+                .ecosystem
+                .as_ref()
+                .is_some_and(|id| id.as_str().eq_ignore_ascii_case(ecosystem)),
+            GraphNodeFilter::Repo(name) => repo.id.as_str() == name,
+            GraphNodeFilter::Changed => changed.contains(&repo.id),
+            GraphNodeFilter::External => repo.external,
+        }
+    }
+}
+
+fn parse_graph_node_filter(raw: &str, workspace: &Workspace) -> Result<GraphNodeFilter> {
+    let raw = raw.trim();
+    let filter = match raw.split_once(':') {
+        Some(("group", name)) => {
+            let known = workspace
+                .config
+                .groups
+                .as_ref()
+                .is_some_and(|groups| groups.groups.contains_key(name));
+            if !known {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "unknown group {}",
+                    name
+                ))));
+            }
+            GraphNodeFilter::Group(name.to_string())
+        }
+        Some(("ecosystem", id)) => GraphNodeFilter::Ecosystem(id.to_string()),
+        Some(("repo", name)) => GraphNodeFilter::Repo(name.to_string()),
+        None if raw == "changed" => GraphNodeFilter::Changed,
+        None if raw == "external" => GraphNodeFilter::External,
+        _ => {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "invalid graph filter '{}' (expected group:<name>, ecosystem:<id>, repo:<name>, changed, or external)",
+                raw
+            ))))
+        }
+    };
+    Ok(filter)
+}
+
+/// Repos with local changes. Unlike `changed_repos`, repos that are missing
+/// or not git checkouts are treated as unchanged instead of failing.
+fn changed_repo_flags(state: &WorkspaceState) -> HashSet<RepoId> {
+    state
+        .workspace
+        .repos
+        .values()
+        .filter(|repo| !repo.ignored && repo.path.join(".git").exists())
+        .filter(|repo| state.changed(repo).unwrap_or(false))
+        .map(|repo| repo.id.clone())
+        .collect()
+}
+
+/// Cluster name per repo for `graph show --cluster-by`. Repos in several groups
+/// are placed in the first group alphabetically; repos without a group or
+/// ecosystem are left unclustered.
+fn graph_clusters(
+    workspace: &Workspace,
+    scope: &HashSet<RepoId>,
+    metadata: &HashMap<RepoId, viz::NodeMetadata>,
+    cluster_by: Option<&str>,
+) -> Result<HashMap<RepoId, String>> {
+    let Some(cluster_by) = cluster_by else {
+        return Ok(HashMap::new());
+    };
+    let mut clusters = HashMap::new();
+    for repo in scope {
+        let cluster = match cluster_by.to_ascii_lowercase().as_str() {
+            "group" => repo_group_names(workspace, repo).into_iter().next(),
+            "ecosystem" => metadata.get(repo).and_then(|meta| meta.ecosystem.clone()),
+            other => {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "unknown cluster key '{}' (expected group or ecosystem)",
+                    other
+                ))))
+            }
+        };
+        if let Some(cluster) = cluster {
+            clusters.insert(repo.clone(), cluster);
+        }
+    }
+    Ok(clusters)
+}
+
+fn repo_group_names(workspace: &Workspace, repo: &RepoId) -> Vec<String> {
+    let mut names: Vec<String> = workspace
+        .config
+        .groups
+        .as_ref()
+        .map(|groups| {
+            groups
+                .groups
+                .iter()
+                .filter(|(_, members)| members.iter().any(|name| name == repo.as_str()))
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
 }
 
 fn graph_roots(edges: &HashMap<RepoId, Vec<RepoId>>, scope: &HashSet<RepoId>) -> Vec<RepoId> {
@@ -5806,13 +17572,420 @@ fn git_diff_files(
     Ok(files)
 }
 
+/// Per-file status and line counts for `diff --format json`, plus hunks
+/// with `--hunks`. Untracked files count as fully inserted.
+fn git_diff_changes(
+    repo_path: &Path,
+    repo_name: &str,
+    args: &DiffArgs,
+    include_untracked: bool,
+) -> Result<Vec<DiffFileChange>> {
+    let diff_command = |mode: &str| {
+        let mut cmd = vec![
+            "git".to_string(),
+            "diff".to_string(),
+            "-M".to_string(),
+            "--no-color".to_string(),
+            mode.to_string(),
+        ];
+        if let Some(unified) = args.unified {
+            cmd.push(format!("--unified={}", unified));
+        }
+        if args.staged {
+            cmd.push("--staged".to_string());
+        }
+        cmd
+    };
+    let status_cmd = diff_command("--name-status");
+    log_git_command_for_repo(repo_name, &status_cmd);
+    let mut changes = parse_name_status(&run_command_output_in_repo(
+        repo_path,
+        &[status_cmd, vec!["-z".to_string()]].concat(),
+    )?);
+    let numstat_cmd = diff_command("--numstat");
+    log_git_command_for_repo(repo_name, &numstat_cmd);
+    let counts = parse_numstat_paths(&run_command_output_in_repo(
+        repo_path,
+        &[numstat_cmd, vec!["-z".to_string()]].concat(),
+    )?);
+    for change in changes.iter_mut() {
+        match counts.get(&change.path) {
+            Some(Some((insertions, deletions))) => {
+                change.insertions = *insertions;
+                change.deletions = *deletions;
+            }
+            Some(None) => change.binary = true,
+            None => {}
+        }
+    }
+    if args.hunks {
+        let patch_cmd = diff_command("--patch");
+        log_git_command_for_repo(repo_name, &patch_cmd);
+        let patch = run_command_output_in_repo(repo_path, &patch_cmd)?;
+        // The patch lists files in the same order as --name-status.
+        for (change, hunks) in changes.iter_mut().zip(parse_patch_hunks(&patch)) {
+            change.hunks = Some(hunks);
+        }
+    }
+
+    if include_untracked {
+        let untracked_cmd = vec![
+            "git".to_string(),
+            "ls-files".to_string(),
+            "--others".to_string(),
+            "--exclude-standard".to_string(),
+        ];
+        log_git_command_for_repo(repo_name, &untracked_cmd);
+        let untracked = run_command_output_in_repo(repo_path, &untracked_cmd)?;
+        for file in untracked
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let content = fs::read_to_string(repo_path.join(file)).ok();
+            let lines: Vec<String> = content
+                .as_deref()
+                .map(|content| content.lines().map(|line| format!("+{line}")).collect())
+                .unwrap_or_default();
+            let hunks = match (&content, args.hunks) {
+                (Some(_), true) if !lines.is_empty() => Some(vec![DiffHunk {
+                    header: format!("@@ -0,0 +1,{} @@", lines.len()),
+                    old_start: 0,
+                    old_lines: 0,
+                    new_start: 1,
+                    new_lines: lines.len() as u32,
+                    lines: lines.clone(),
+                }]),
+                (Some(_), true) => Some(Vec::new()),
+                _ => None,
+            };
+            changes.push(DiffFileChange {
+                path: file.to_string(),
+                old_path: None,
+                status: "untracked",
+                insertions: lines.len(),
+                deletions: 0,
+                binary: content.is_none(),
+                hunks,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Entries of `git diff --name-status -z`: a status letter (with a score for
+/// renames and copies), then the path, or the old and new paths.
+fn parse_name_status(output: &str) -> Vec<DiffFileChange> {
+    let mut fields = output.split('\0').filter(|field| !field.is_empty());
+    let mut changes = Vec::new();
+    while let Some(code) = fields.next() {
+        let status = match code.chars().next() {
+            Some('A') => "added",
+            Some('D') => "deleted",
+            Some('R') => "renamed",
+            Some('C') => "copied",
+            Some('T') => "type-changed",
+            _ => "modified",
+        };
+        let old_path = if matches!(status, "renamed" | "copied") {
+            fields.next().map(str::to_string)
+        } else {
+            None
+        };
+        let Some(path) = fields.next() else {
+            break;
+        };
+        changes.push(DiffFileChange {
+            path: path.to_string(),
+            old_path,
+            status,
+            insertions: 0,
+            deletions: 0,
+            binary: false,
+            hunks: None,
+        });
+    }
+    changes
+}
+
+/// Line counts of `git diff --numstat -z` by (new) path; `None` for binary
+/// files, which report `-` for both counts.
+fn parse_numstat_paths(output: &str) -> HashMap<String, Option<(usize, usize)>> {
+    let mut fields = output.split('\0');
+    let mut counts = HashMap::new();
+    while let Some(field) = fields.next() {
+        let mut parts = field.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // Renames leave the path empty and follow with the old and new paths.
+        let path = if path.is_empty() {
+            let _old = fields.next();
+            fields.next().unwrap_or_default()
+        } else {
+            path
+        };
+        let count = added
+            .parse::<usize>()
+            .ok()
+            .zip(deleted.parse::<usize>().ok());
+        counts.insert(path.to_string(), count);
+    }
+    counts
+}
+
+/// Hunks of each file section (`diff --git ...`) of a unified patch, in order.
+fn parse_patch_hunks(patch: &str) -> Vec<Vec<DiffHunk>> {
+    let mut files: Vec<Vec<DiffHunk>> = Vec::new();
+    for line in patch.lines() {
+        if line.starts_with("diff --git ") {
+            files.push(Vec::new());
+            continue;
+        }
+        let Some(hunks) = files.last_mut() else {
+            continue;
+        };
+        if let Some(header) = line.strip_prefix("@@ ") {
+            let mut ranges = header.split_whitespace();
+            let range = |value: Option<&str>, sign: char| {
+                let value = value
+                    .and_then(|value| value.strip_prefix(sign))
+                    .unwrap_or("0");
+                let mut parts = value.splitn(2, ',');
+                let start = parts
+                    .next()
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(0);
+                let lines = parts.next().map_or(Some(1), |lines| lines.parse().ok());
+                (start, lines.unwrap_or(0))
+            };
+            let (old_start, old_lines) = range(ranges.next(), '-');
+            let (new_start, new_lines) = range(ranges.next(), '+');
+            hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        }
+    }
+    files
+}
+
+fn git_diff_summary(
+    repo_path: &Path,
+    repo_name: &str,
+    staged: bool,
+    include_untracked: bool,
+) -> Result<DiffSummary> {
+    let mut cmd = vec![
+        "git".to_string(),
+        "diff".to_string(),
+        "--numstat".to_string(),
+        "-M".to_string(),
+    ];
+    if staged {
+        cmd.push("--staged".to_string());
+    }
+    log_git_command_for_repo(repo_name, &cmd);
+    let mut summary = parse_numstat(&run_command_output_in_repo(repo_path, &cmd)?);
+
+    if include_untracked && !staged {
+        let untracked_cmd = vec![
+            "git".to_string(),
+            "ls-files".to_string(),
+            "--others".to_string(),
+            "--exclude-standard".to_string(),
+        ];
+        log_git_command_for_repo(repo_name, &untracked_cmd);
+        let untracked = run_command_output_in_repo(repo_path, &untracked_cmd)?;
+        for file in untracked
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            summary.files += 1;
+            // Untracked files count as fully inserted; binary files add no lines.
+            if let Ok(content) = fs::read_to_string(repo_path.join(file)) {
+                summary.insertions += content.lines().count();
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Totals from `git diff --numstat -M`. Binary files report `-` for both
+/// counts and renames show up as `old => new` (or `dir/{old => new}`).
+fn parse_numstat(output: &str) -> DiffSummary {
+    let mut summary = DiffSummary::default();
+    for line in output.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        summary.files += 1;
+        summary.insertions += added.parse::<usize>().unwrap_or(0);
+        summary.deletions += deleted.parse::<usize>().unwrap_or(0);
+        if path.contains(" => ") {
+            summary.renames += 1;
+        }
+    }
+    summary
+}
+
+/// Aligned `diff --summary` lines: one per repo, then workspace totals.
+fn render_diff_summary(rows: &[(String, DiffSummary)], color: bool) -> Vec<String> {
+    let mut total = DiffSummary::default();
+    for (_, summary) in rows {
+        total.add(summary);
+    }
+    let total_label = format!(
+        "total ({} repo{})",
+        rows.len(),
+        if rows.len() == 1 { "" } else { "s" }
+    );
+    let all = rows
+        .iter()
+        .map(|(repo, summary)| (repo.as_str(), summary))
+        .chain(std::iter::once((total_label.as_str(), &total)))
+        .collect::<Vec<_>>();
+
+    let files_label = |summary: &DiffSummary| {
+        format!(
+            "{} file{}",
+            summary.files,
+            if summary.files == 1 { "" } else { "s" }
+        )
+    };
+    let name_width = all.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let files_width = all
+        .iter()
+        .map(|(_, summary)| files_label(summary).len())
+        .max()
+        .unwrap_or(0);
+    let insertions_width = all
+        .iter()
+        .map(|(_, summary)| summary.insertions.to_string().len() + 1)
+        .max()
+        .unwrap_or(0);
+    let deletions_width = all
+        .iter()
+        .map(|(_, summary)| summary.deletions.to_string().len() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (index, (name, summary)) in all.iter().enumerate() {
+        if index == rows.len() {
+            lines.push(
+                "-".repeat(name_width + files_width + insertions_width + deletions_width + 6),
+            );
+        }
+        let insertions = format!("{:>insertions_width$}", format!("+{}", summary.insertions));
+        let deletions = format!("{:>deletions_width$}", format!("-{}", summary.deletions));
+        let (insertions, deletions) = if color {
+            (
+                console::style(insertions).green().to_string(),
+                console::style(deletions).red().to_string(),
+            )
+        } else {
+            (insertions, deletions)
+        };
+        let mut line = format!(
+            "{:<name_width$}  {:>files_width$}  {insertions}  {deletions}",
+            name,
+            files_label(summary)
+        );
+        if summary.renames > 0 {
+            line.push_str(&format!("  {} renamed", summary.renames));
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+struct HookRunOptions {
+    skip: bool,
+    timeout: Option<Duration>,
+    /// Capture hook stdout/stderr into the results instead of streaming it.
+    capture: bool,
+}
+
+#[derive(Serialize, Default)]
+struct HookReport {
+    repos: Vec<String>,
+    hooks: Vec<HookRunResult>,
+}
+
+#[derive(Serialize, Debug)]
+struct HookRunResult {
+    /// `None` for workspace-level hooks run from the workspace root.
+    repo: Option<String>,
+    hook: String,
+    command: String,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    timed_out: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<String>,
+}
+
+impl HookRunResult {
+    fn succeeded(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+
+    fn failure_message(&self) -> String {
+        let scope = match self.repo.as_deref() {
+            Some(repo) => format!("repo {repo}"),
+            None => "workspace".to_string(),
+        };
+        if self.timed_out {
+            format!(
+                "{} hook '{}' ({}) timed out after {}ms",
+                self.hook, self.command, scope, self.duration_ms
+            )
+        } else {
+            let code = self
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "signal".to_string());
+            format!(
+                "{} hook '{}' ({}) failed with exit code {}",
+                self.hook, self.command, scope, code
+            )
+        }
+    }
+}
+
+fn print_hook_report(report: &HookReport) -> Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(report)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?
+    );
+    Ok(())
+}
+
+/// Runs `hook_name` for the workspace and then each repo, appending one result
+/// per hook to `results`. Stops at the first failing or timed-out hook.
 fn run_hook_for_repos(
     workspace: &Workspace,
     repos: &[Repo],
     hook_name: &str,
-    skip: bool,
+    options: &HookRunOptions,
+    results: &mut Vec<HookRunResult>,
 ) -> Result<()> {
-    if skip {
+    if options.skip {
         return Ok(());
     }
 
@@ -5830,7 +18003,21 @@ fn run_hook_for_repos(
         .any(|repo| !repo_disables_hook(repo, hook_name));
     if let Some(command) = workspace_hook {
         if should_run_workspace {
-            run_command_in_repo(&workspace.root, &split_command(command))?;
+            let env_vars = workspace.command_env(None)?;
+            let result = run_hook_command(
+                &workspace.root,
+                None,
+                hook_name,
+                command,
+                &env_vars,
+                options,
+            )?;
+            let failed = !result.succeeded();
+            let message = result.failure_message();
+            results.push(result);
+            if failed {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(message)));
+            }
         }
     }
 
@@ -5845,13 +18032,108 @@ fn run_hook_for_repos(
                 _ => None,
             });
         if let Some(command) = hook {
-            run_command_in_repo(&repo.path, &split_command(command))?;
+            let env_vars = workspace.command_env(Some(repo))?;
+            let result = run_hook_command(
+                &repo.path,
+                Some(repo.id.as_str()),
+                hook_name,
+                command,
+                &env_vars,
+                options,
+            )?;
+            let failed = !result.succeeded();
+            let message = result.failure_message();
+            results.push(result);
+            if failed {
+                return Err(HarmoniaError::Other(anyhow::anyhow!(message)));
+            }
         }
     }
 
     Ok(())
 }
 
+fn run_hook_command(
+    dir: &Path,
+    repo: Option<&str>,
+    hook_name: &str,
+    command: &str,
+    env_vars: &[(String, String)],
+    options: &HookRunOptions,
+) -> Result<HookRunResult> {
+    let parts = split_command(command);
+    if parts.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!("missing command")));
+    }
+    let mut cmd = std::process::Command::new(&parts[0]);
+    cmd.args(&parts[1..])
+        .current_dir(dir)
+        .envs(env_vars.iter().map(|(key, value)| (key, value)));
+    if options.capture {
+        cmd.stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+    }
+
+    let started = Instant::now();
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", parts))?;
+    let readers = options.capture.then(|| {
+        (
+            child.stdout.take().map(spawn_output_reader),
+            child.stderr.take().map(spawn_output_reader),
+        )
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if options
+            .timeout
+            .is_some_and(|timeout| started.elapsed() >= timeout)
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    // A timed-out hook may leave children holding the pipes open, so only
+    // collect output from hooks that exited on their own.
+    let (stdout, stderr) = match (readers, status.is_some()) {
+        (Some((stdout, stderr)), true) => (
+            stdout.and_then(|reader| reader.join().ok()),
+            stderr.and_then(|reader| reader.join().ok()),
+        ),
+        (Some(_), false) => (Some(String::new()), Some(String::new())),
+        (None, _) => (None, None),
+    };
+
+    Ok(HookRunResult {
+        repo: repo.map(str::to_string),
+        hook: hook_name.to_string(),
+        command: command.to_string(),
+        exit_code: status.and_then(|status| status.code()),
+        duration_ms,
+        timed_out: status.is_none(),
+        stdout,
+        stderr,
+    })
+}
+
+fn spawn_output_reader<R: std::io::Read + Send + 'static>(
+    mut source: R,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = source.read_to_end(&mut buffer);
+        String::from_utf8_lossy(&buffer).to_string()
+    })
+}
+
 fn repo_disables_hook(repo: &Repo, hook_name: &str) -> bool {
     repo.config
         .as_ref()
@@ -5861,22 +18143,88 @@ fn repo_disables_hook(repo: &Repo, hook_name: &str) -> bool {
         .unwrap_or(false)
 }
 
-fn changed_repos(workspace: &Workspace) -> Result<HashSet<RepoId>> {
+fn changed_repos(state: &WorkspaceState) -> Result<HashSet<RepoId>> {
     let mut changed = HashSet::new();
-    for repo in workspace.repos.values() {
+    for repo in state.workspace.repos.values() {
         if repo.ignored || !repo.path.is_dir() {
             continue;
         }
-        let open = open_repo(&repo.path)?;
-        let status = repo_status(&open.repo)?;
-        if !status.is_clean() {
+        if state.changed(repo)? {
             changed.insert(repo.id.clone());
         }
     }
     Ok(changed)
 }
 
-fn filter_changed_repos(repos: Vec<Repo>) -> Result<Vec<Repo>> {
+fn parse_change_kinds(values: &[String]) -> Result<Vec<ChangeKind>> {
+    values
+        .iter()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| {
+            ChangeKind::parse(value).ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(format!(
+                    "unknown change kind '{}' (expected dirty, unpushed, or branch)",
+                    value.trim()
+                )))
+            })
+        })
+        .collect()
+}
+
+/// Change kinds in effect: `--changed-as`, then `[defaults].changed_as`, then
+/// all of them.
+fn active_change_kinds(workspace: &Workspace) -> Result<Vec<ChangeKind>> {
+    if let Some(kinds) = CHANGED_AS.get().filter(|kinds| !kinds.is_empty()) {
+        return Ok(kinds.clone());
+    }
+    let configured = workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.changed_as.as_deref())
+        .map(parse_change_kinds)
+        .transpose()?
+        .unwrap_or_default();
+    if configured.is_empty() {
+        Ok(ChangeKind::ALL.to_vec())
+    } else {
+        Ok(configured)
+    }
+}
+
+/// Whether `repo` counts as changed under the active change kinds. `status` is
+/// its working-tree status; branch checks only run when the tree is clean.
+fn repo_is_changed(workspace: &Workspace, repo: &Repo, status: &StatusSummary) -> Result<bool> {
+    let kinds = active_change_kinds(workspace)?;
+    if kinds.contains(&ChangeKind::Dirty) && !status.is_clean() {
+        return Ok(true);
+    }
+    let unpushed = kinds.contains(&ChangeKind::Unpushed);
+    let branch = kinds.contains(&ChangeKind::Branch);
+    if !unpushed && !branch {
+        return Ok(false);
+    }
+    let open = open_repo(&repo.path)?;
+    if unpushed && commits_ahead_of(&open.repo, "@{upstream}")?.is_some_and(|ahead| ahead > 0) {
+        return Ok(true);
+    }
+    if branch {
+        let current = current_branch(&open.repo)?;
+        if current == repo.default_branch || current == "HEAD" || current == "(detached)" {
+            return Ok(false);
+        }
+        let remote = default_push_remote(&open.repo).unwrap_or_else(|| "origin".to_string());
+        let ahead =
+            match commits_ahead_of(&open.repo, &format!("{}/{}", remote, repo.default_branch))? {
+                Some(ahead) => Some(ahead),
+                None => commits_ahead_of(&open.repo, &repo.default_branch)?,
+            };
+        return Ok(ahead.is_some_and(|ahead| ahead > 0));
+    }
+    Ok(false)
+}
+
+fn filter_changed_repos(workspace: &Workspace, repos: Vec<Repo>) -> Result<Vec<Repo>> {
     let mut out = Vec::new();
     for repo in repos {
         if !repo.path.is_dir() {
@@ -5884,15 +18232,17 @@ fn filter_changed_repos(repos: Vec<Repo>) -> Result<Vec<Repo>> {
         }
         let open = open_repo(&repo.path)?;
         let status = repo_status(&open.repo)?;
-        if !status.is_clean() {
+        if repo_is_changed(workspace, &repo, &status)? {
             out.push(repo);
         }
     }
+    record_selection(workspace, &out)?;
     Ok(out)
 }
 
-fn handle_version_show(args: VersionShowArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
+fn handle_version_show(args: VersionShowArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let versions = state.versions()?;
     let package_map = package_map(&workspace.repos);
 
     let mut repos: Vec<&Repo> = workspace
@@ -5958,9 +18308,9 @@ fn handle_version_show(args: VersionShowArgs, workspace: &Workspace) -> Result<(
     Ok(())
 }
 
-fn handle_version_check(args: VersionCheckArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
-    let mut report = check_constraints(&workspace.graph, &workspace.repos, &versions);
+fn handle_version_check(args: VersionCheckArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let mut report = check_constraints(state.graph(), &workspace.repos, state.versions()?);
     report
         .violations
         .retain(|violation| matches!(violation.violation_type, ViolationType::Unsatisfied));
@@ -5992,10 +18342,10 @@ fn handle_version_bump(args: VersionBumpArgs, workspace: &Workspace) -> Result<(
         None => None,
     };
 
-    let default_changed = args.repos.is_empty();
+    let default_changed = args.repos.is_empty() && !repo_selection_active();
     let mut repos = select_repos(workspace, &args.repos, None, false, false)?;
     if args.changed || default_changed {
-        repos = filter_changed_repos(repos)?;
+        repos = filter_changed_repos(workspace, repos)?;
     }
     if repos.is_empty() {
         output::info("no repos selected for version bump");
@@ -6122,8 +18472,9 @@ fn handle_version_bump(args: VersionBumpArgs, workspace: &Workspace) -> Result<(
     Ok(())
 }
 
-fn handle_deps_show(args: DepsShowArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
+fn handle_deps_show(args: DepsShowArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let versions = state.versions()?;
     let package_map = package_map(&workspace.repos);
     let mut repos: Vec<&Repo> = workspace
         .repos
@@ -6175,9 +18526,9 @@ fn handle_deps_show(args: DepsShowArgs, workspace: &Workspace) -> Result<()> {
     Ok(())
 }
 
-fn handle_deps_check(args: DepsCheckArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
-    let mut report = check_constraints(&workspace.graph, &workspace.repos, &versions);
+fn handle_deps_check(args: DepsCheckArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let mut report = check_constraints(state.graph(), &workspace.repos, state.versions()?);
     report
         .violations
         .retain(|violation| matches!(violation.violation_type, ViolationType::Unsatisfied));
@@ -6195,8 +18546,9 @@ fn handle_deps_check(args: DepsCheckArgs, workspace: &Workspace) -> Result<()> {
     Ok(())
 }
 
-fn handle_deps_update(args: DepsUpdateArgs, workspace: &Workspace) -> Result<()> {
-    let versions = collect_versions(workspace)?;
+fn handle_deps_update(args: DepsUpdateArgs, state: &WorkspaceState) -> Result<()> {
+    let workspace = state.workspace;
+    let versions = state.versions()?;
     let map = package_map(&workspace.repos);
     let mut target_names = HashSet::new();
     if !args.packages.is_empty() {
@@ -6273,23 +18625,191 @@ fn handle_deps_update(args: DepsUpdateArgs, workspace: &Workspace) -> Result<()>
         update_dependency_in_repo(repo, &update.dependency, &update.constraint, args.dry_run)?;
     }
 
-    Ok(())
+    Ok(())
+}
+
+/// Updates `--package` to `--to` in every repo that depends on it, then runs
+/// the submit phases (mr create with auto-branch, add, commit, push) on just
+/// those repos so their MRs are linked like any other cross-repo change.
+fn handle_deps_bump_external(
+    args: DepsBumpExternalArgs,
+    state: &WorkspaceState,
+    workspace_root: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+) -> Result<()> {
+    let workspace = state.workspace;
+    let package = args.package.trim();
+    let constraint = args.to.trim();
+    if package.is_empty() || constraint.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "--package and --to cannot be empty"
+        )));
+    }
+    if let Some(repo_id) = package_map(&workspace.repos).get(package) {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} is provided by workspace repo {}; use `harmonia deps update` instead",
+            package,
+            repo_id.as_str()
+        ))));
+    }
+
+    let mut repos: Vec<&Repo> = workspace
+        .repos
+        .values()
+        .filter(|repo| !repo.ignored && !repo.external && repo.path.is_dir())
+        .collect();
+    repos.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    let mut targets = Vec::new();
+    for repo in repos {
+        let Some(dep) = workspace
+            .graph
+            .edges
+            .get(&repo.id)
+            .and_then(|deps| deps.iter().find(|dep| dep.name == package))
+        else {
+            continue;
+        };
+        if dep.constraint.raw == constraint {
+            continue;
+        }
+        targets.push((repo, dep.constraint.raw.clone()));
+    }
+    if targets.is_empty() {
+        output::info(&format!(
+            "no repos depend on {} at a constraint other than {}",
+            package, constraint
+        ));
+        return Ok(());
+    }
+
+    println!("external dependency bump: {} -> {}", package, constraint);
+    for (repo, current) in &targets {
+        println!("  {}: {} -> {}", repo.id.as_str(), current, constraint);
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+    ensure_writable(workspace, "deps bump-external")?;
+
+    // mr create picks up every changed repo, so unrelated edits would end up
+    // in the bump MRs.
+    let mut dirty = Vec::new();
+    for repo in workspace.repos.values() {
+        if repo.ignored || repo.external || !repo.path.is_dir() {
+            continue;
+        }
+        if !state.status(repo)?.is_clean() {
+            dirty.push(repo.id.as_str().to_string());
+        }
+    }
+    if !dirty.is_empty() {
+        dirty.sort();
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "deps bump-external needs a clean workspace; commit or stash changes in: {}",
+            dirty.join(", ")
+        ))));
+    }
+
+    for (repo, _) in &targets {
+        update_dependency_in_repo(repo, package, constraint, false)?;
+    }
+
+    let message = args
+        .message
+        .clone()
+        .unwrap_or_else(|| format!("chore(deps): bump {} to {}", package, constraint));
+    let branch_name = args.branch_name.clone().unwrap_or_else(|| {
+        format!(
+            "deps/{}-{}",
+            slugify_branch_part(package),
+            slugify_branch_part(constraint)
+        )
+    });
+    let repo_names: Vec<String> = targets
+        .iter()
+        .map(|(repo, _)| repo.id.as_str().to_string())
+        .collect();
+
+    // The dependency edits above changed the statuses `state` holds.
+    handle_mr_create(
+        MrCreateArgs {
+            title: Some(message.clone()),
+            draft: args.draft,
+            auto_branch: true,
+            branch_name: Some(branch_name),
+            skip_existing: true,
+            ..MrCreateArgs::default()
+        },
+        &WorkspaceState::new(workspace),
+    )?;
+    handle_add(
+        AddArgs {
+            repos: repo_names.clone(),
+            all: false,
+            patch: false,
+            pathspec: Vec::new(),
+        },
+        workspace_root.clone(),
+        config_path.clone(),
+    )?;
+    handle_commit(
+        CommitArgs {
+            message: Some(message),
+            all: false,
+            repos: repo_names.clone(),
+            amend: false,
+            no_hooks: false,
+            yes: false,
+            allow_empty: false,
+            trailers: Vec::new(),
+            hook_timeout: None,
+            json: false,
+            no_secret_scan: false,
+        },
+        workspace_root.clone(),
+        config_path.clone(),
+    )?;
+    handle_push(
+        PushArgs {
+            repos: repo_names,
+            force: false,
+            force_with_lease: false,
+            set_upstream: true,
+            no_hooks: false,
+            yes: false,
+            dry_run: false,
+            hook_timeout: None,
+            json: false,
+        },
+        workspace_root,
+        config_path,
+    )
 }
 
 fn collect_versions(workspace: &Workspace) -> Result<HashMap<RepoId, Version>> {
+    let mut cache = ManifestCache::load(&workspace.root);
     let mut versions = HashMap::new();
     for repo in workspace.repos.values() {
         if repo.ignored {
             continue;
         }
-        if let Some(version) = read_repo_version(repo, workspace)? {
+        if let Some(version) = read_repo_version_cached(repo, workspace, &mut cache)? {
             versions.insert(repo.id.clone(), version);
         }
     }
+    cache.save();
     Ok(versions)
 }
 
 fn read_repo_version(repo: &Repo, workspace: &Workspace) -> Result<Option<Version>> {
+    read_repo_version_cached(repo, workspace, &mut ManifestCache::disabled())
+}
+
+fn read_repo_version_cached(
+    repo: &Repo,
+    workspace: &Workspace,
+    cache: &mut ManifestCache,
+) -> Result<Option<Version>> {
     let file = match version_file_for_repo(repo) {
         Some(path) => path,
         None => return Ok(None),
@@ -6297,7 +18817,24 @@ fn read_repo_version(repo: &Repo, workspace: &Workspace) -> Result<Option<Versio
     if !file.is_file() {
         return Ok(None);
     }
-    let content = fs::read_to_string(&file)?;
+    parse_repo_version(repo, workspace, &file, None, cache)
+}
+
+/// Parses the version in `file`. `content` overrides what is on disk; when it
+/// is `None` the file is read, or skipped when the cache still matches it.
+fn parse_repo_version(
+    repo: &Repo,
+    workspace: &Workspace,
+    file: &Path,
+    content: Option<&str>,
+    cache: &mut ManifestCache,
+) -> Result<Option<Version>> {
+    let read = || -> Result<String> {
+        match content {
+            Some(content) => Ok(content.to_string()),
+            None => Ok(fs::read_to_string(file)?),
+        }
+    };
     let strategy = resolve_version_kind(repo, workspace)?;
     let version_cfg = repo
         .config
@@ -6305,14 +18842,15 @@ fn read_repo_version(repo: &Repo, workspace: &Workspace) -> Result<Option<Versio
         .and_then(|config| config.versioning.as_ref());
 
     let raw = if let Some(pattern) = version_cfg.and_then(|cfg| cfg.pattern.as_ref()) {
-        read_version_with_pattern(pattern, &content)?
+        read_version_with_pattern(pattern, &read()?)?
     } else if let Some(path) = version_cfg.and_then(|cfg| cfg.path.as_ref()) {
-        read_version_with_path(&file, &content, path)?
+        read_version_with_path(file, &read()?, path)?
     } else if let Some(ecosystem) = repo.ecosystem.as_ref() {
         let plugin = plugin_for(ecosystem);
-        plugin
-            .parse_version(&file, &content)?
-            .map(|version| version.raw)
+        match content {
+            Some(content) => cache.parse_version(plugin.as_ref(), file, content)?,
+            None => cache.load_version(plugin.as_ref(), file)?,
+        }
     } else {
         None
     };
@@ -6386,6 +18924,27 @@ fn resolve_bump_mode(
     Ok(BumpMode::Semver)
 }
 
+/// Version recorded in the repo's version file on `rev`, read with
+/// `git show` so the working tree is left alone. `None` when the file or
+/// revision is missing there.
+fn read_repo_version_at(repo: &Repo, workspace: &Workspace, rev: &str) -> Option<Version> {
+    let file = version_file_for_repo(repo)?;
+    let relative = file.strip_prefix(&repo.path).ok()?;
+    let spec = format!("{}:{}", rev, relative.to_string_lossy().replace('\\', "/"));
+    let content =
+        run_command_output_in_repo(&repo.path, &["git".to_string(), "show".to_string(), spec])
+            .ok()?;
+    parse_repo_version(
+        repo,
+        workspace,
+        &file,
+        Some(&content),
+        &mut ManifestCache::disabled(),
+    )
+    .ok()
+    .flatten()
+}
+
 fn version_file_for_repo(repo: &Repo) -> Option<PathBuf> {
     let configured = repo
         .config
@@ -6398,7 +18957,7 @@ fn version_file_for_repo(repo: &Repo) -> Option<PathBuf> {
     }
     let ecosystem = repo.ecosystem.as_ref()?;
     let plugin = plugin_for(ecosystem);
-    for pattern in plugin.file_patterns() {
+    for pattern in plugin.version_files() {
         let candidate = repo.path.join(pattern);
         if candidate.is_file() {
             return Some(candidate);
@@ -6419,7 +18978,7 @@ fn deps_file_for_repo(repo: &Repo) -> Option<PathBuf> {
     }
     let ecosystem = repo.ecosystem.as_ref()?;
     let plugin = plugin_for(ecosystem);
-    for pattern in plugin.file_patterns() {
+    for pattern in plugin.dependency_files() {
         let candidate = repo.path.join(pattern);
         if candidate.is_file() {
             return Some(candidate);
@@ -6795,11 +19354,16 @@ fn update_dependency_in_repo(
 #[derive(Debug)]
 struct StatusRow {
     repo: String,
+    /// `[teams]` entries that own the repo.
+    teams: Vec<String>,
+    external: bool,
     path: PathBuf,
     branch: String,
     ahead: usize,
     behind: usize,
     status: StatusSummary,
+    /// Tracked paths that differ only by case, grouped.
+    case_conflicts: Vec<Vec<String>>,
 }
 
 fn print_status_table(workspace: &Workspace, rows: &[StatusRow], short: bool) -> Result<()> {
@@ -6841,33 +19405,40 @@ fn print_status_table(workspace: &Workspace, rows: &[StatusRow], short: bool) ->
         .chain(std::iter::once("Branch".len()))
         .max()
         .unwrap_or("Branch".len());
-    println!(
-        "{:<repo_width$} {:<branch_width$} {:>4} {:>4} {:<5}",
+    // The Team column only appears when the workspace defines [teams].
+    let show_teams = !workspace.config.teams.is_empty();
+    let team_width = rows
+        .iter()
+        .map(|row| row.teams.join(",").len())
+        .chain(std::iter::once("Team".len()))
+        .max()
+        .unwrap_or("Team".len());
+    let team_cell = |value: &str| {
+        if show_teams {
+            format!("{:<team_width$} ", value, team_width = team_width)
+        } else {
+            String::new()
+        }
+    };
+    let header = format!(
+        "{:<repo_width$} {:<branch_width$} {}{:>4} {:>4} {:<5}",
         "Repo",
         "Branch",
+        team_cell("Team"),
         "↑",
         "↓",
         "Status",
         repo_width = repo_width,
         branch_width = branch_width
     );
-    let header_len = format!(
-        "{:<repo_width$} {:<branch_width$} {:>4} {:>4} {:<5}",
-        "Repo",
-        "Branch",
-        "↑",
-        "↓",
-        "Status",
-        repo_width = repo_width,
-        branch_width = branch_width
-    )
-    .len();
-    println!("{}", "-".repeat(header_len));
+    println!("{}", header);
+    println!("{}", "-".repeat(header.len()));
     for row in rows {
         println!(
-            "{:<repo_width$} {:<branch_width$} {:>4} {:>4} {}",
+            "{:<repo_width$} {:<branch_width$} {}{:>4} {:>4} {}",
             row.repo,
             row.branch,
+            team_cell(&row.teams.join(",")),
             row.ahead,
             row.behind,
             status_summary(row),
@@ -6879,6 +19450,13 @@ fn print_status_table(workspace: &Workspace, rows: &[StatusRow], short: bool) ->
 }
 
 fn status_summary(row: &StatusRow) -> String {
+    if row.external {
+        return format!("{} (external, read-only)", status_summary_text(row));
+    }
+    status_summary_text(row)
+}
+
+fn status_summary_text(row: &StatusRow) -> String {
     if row.status.is_clean() {
         "clean".to_string()
     } else {
@@ -6934,6 +19512,8 @@ fn print_status_json(rows: &[StatusRow]) -> Result<()> {
             .map(|row| {
                 serde_json::json!({
                     "repo": row.repo,
+                    "teams": row.teams,
+                    "external": row.external,
                     "branch": row.branch,
                     "ahead": row.ahead,
                     "behind": row.behind,
@@ -6941,6 +19521,7 @@ fn print_status_json(rows: &[StatusRow]) -> Result<()> {
                     "modified": row.status.modified.len(),
                     "untracked": row.status.untracked.len(),
                     "conflicts": row.status.conflicts.len(),
+                    "case_conflicts": row.case_conflicts,
                 })
             })
             .collect::<Vec<_>>(),
@@ -6950,44 +19531,633 @@ fn print_status_json(rows: &[StatusRow]) -> Result<()> {
     Ok(())
 }
 
-fn include_untracked_by_default(workspace: &Workspace) -> bool {
-    workspace
-        .config
-        .defaults
-        .as_ref()
-        .and_then(|defaults| defaults.include_untracked)
-        .unwrap_or(true)
-}
+fn include_untracked_by_default(workspace: &Workspace) -> bool {
+    workspace
+        .config
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.include_untracked)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_lint_fix, apply_test_coverage, apply_test_filter, apply_test_retry_failed,
+        auto_mr_labels, branch_matches_any, branch_name_policy_violation, clone_failure_kind,
+        clone_retry_command, closing_lines_for_repo, commit_web_url, config_origin_value,
+        destructive_git_reason, extract_ticket, forge_project_entries,
+        format_mr_branch_conflict_error, is_repo_pattern, merge_mr_state, missing_approvals,
+        parse_ahead_behind_counts, parse_closing_refs, parse_depth, parse_first_changed_lines,
+        parse_forge_source, parse_git_grep_output, parse_issue_state_filter, parse_name_status,
+        parse_numstat, parse_numstat_paths, parse_patch_hunks, pipeline_summary,
+        push_dependency_impact, push_target, read_only_git, readme_first_paragraph,
+        render_branch_template, render_changelog_section, render_diff_summary,
+        render_editor_invocation, render_exec_prefix, render_mr_title, render_squash_message,
+        repo_pattern_matches, repo_web_url, resolve_clone_url, resolve_mr_title, resolve_parallel,
+        resolve_template_url, review_surface, sample_issue_template_context,
+        sample_mr_template_context, slugify_branch_part, title_from_branch,
+        validate_config_document, with_mirrored_mr_status, workspace_config_add,
+        workspace_config_remove, CloneArgs, CloneFailure, ClosedMrEntry, DependencyImpactRow,
+        DiffSummary, MrBranchConflict, MrStateStore, MrStatusRow, PlanSummary,
+        SquashMessageContext, StoredMrEntry, WorkspaceState, BRANCH_SLUG_MAX_LEN,
+        DEFAULT_BRANCH_TEMPLATE, DEFAULT_TICKET_PATTERN,
+    };
+    use crate::core::repo::{Repo, RepoId};
+    use crate::ecosystem::EcosystemId;
+    use crate::forge::{CiState, IssueState, MrChanges, MrFileChange, MrState, Pipeline};
+
+    fn test_repo(ecosystem: EcosystemId) -> Repo {
+        Repo {
+            id: RepoId::new("app".to_string()),
+            path: std::path::PathBuf::from("/nonexistent/harmonia-test-repo"),
+            remote_url: String::new(),
+            default_branch: "main".to_string(),
+            package_name: None,
+            depends_on: Vec::new(),
+            ecosystem: Some(ecosystem),
+            config: None,
+            external: false,
+            ignored: false,
+            paused: None,
+        }
+    }
+
+    #[test]
+    fn workspace_state_reuses_versions_and_graph() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system clock before unix epoch")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "harmonia-workspace-state-{}-{nanos}",
+            std::process::id()
+        ));
+        let mut repo = test_repo(EcosystemId::Node);
+        repo.path = root.join("app");
+        std::fs::create_dir_all(&repo.path).expect("create repo dir");
+        let manifest = repo.path.join("package.json");
+        std::fs::write(&manifest, "{\"name\": \"app\", \"version\": \"1.0.0\"}\n")
+            .expect("write manifest");
+        let workspace = crate::core::workspace::Workspace {
+            root: root.clone(),
+            config: toml::from_str("").expect("parse config"),
+            repos: std::collections::HashMap::from([(repo.id.clone(), repo.clone())]),
+            graph: crate::graph::DependencyGraph::new(),
+        };
+
+        let state = WorkspaceState::new(&workspace);
+        let first = state.versions().expect("versions");
+        assert_eq!(first[&repo.id].raw, "1.0.0");
+        std::fs::write(&manifest, "{\"name\": \"app\", \"version\": \"10.0.0\"}\n")
+            .expect("rewrite manifest");
+        let second = state.versions().expect("versions");
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(second[&repo.id].raw, "1.0.0");
+        assert!(std::ptr::eq(state.graph(), &workspace.graph));
+
+        let fresh = WorkspaceState::new(&workspace);
+        assert_eq!(fresh.versions().expect("versions")[&repo.id].raw, "10.0.0");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn clone_failures_are_classified_and_retried_with_same_options() {
+        assert_eq!(
+            clone_failure_kind("git error: fatal: Authentication failed for 'https://x'"),
+            "auth"
+        );
+        assert_eq!(
+            clone_failure_kind("git error: Could not resolve host: gitlab.example.com"),
+            "network"
+        );
+        assert_eq!(
+            clone_failure_kind("api already exists at /work/repos/api"),
+            "conflict"
+        );
+        assert_eq!(clone_failure_kind("repo api missing url"), "missing-url");
+        assert_eq!(clone_failure_kind("disk full"), "other");
+
+        let failure = |repo: &str| CloneFailure {
+            repo: repo.to_string(),
+            kind: "network",
+            error: String::new(),
+            fatal: true,
+        };
+        let args = CloneArgs {
+            repos: Vec::new(),
+            group: Some("backend".to_string()),
+            all: false,
+            depth: Some("1".to_string()),
+            full: false,
+            protocol: Some("ssh".to_string()),
+            strict: false,
+            verify: false,
+            report: "text".to_string(),
+        };
+        assert_eq!(
+            clone_retry_command(&[failure("api"), failure("web")], &args).as_deref(),
+            Some("harmonia clone api web --depth 1 --protocol ssh")
+        );
+        assert_eq!(clone_retry_command(&[], &args), None);
+    }
+
+    #[test]
+    fn readme_summary_skips_headings_and_badges() {
+        let readme =
+            "# billing\n\n[![ci](badge.svg)](ci)\n\nInvoices and\npayment runs.\n\n## Usage\n";
+        assert_eq!(
+            readme_first_paragraph(readme).as_deref(),
+            Some("Invoices and payment runs.")
+        );
+        assert_eq!(readme_first_paragraph("# title\n\n<p>html</p>\n"), None);
+    }
+
+    #[test]
+    fn config_add_and_remove_edit_arrays_and_tables_in_place() {
+        let mut value: toml::Value = toml::from_str(
+            "[repos]\napi = { url = \"git@x:api.git\" }\n\n[groups]\ncore = [\"api\"]\n",
+        )
+        .expect("parse config");
+        let items = |items: &[&str]| {
+            items
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            workspace_config_add(&mut value, "groups.core", &items(&["api", "web"])).unwrap(),
+            1
+        );
+        assert_eq!(
+            workspace_config_add(&mut value, "mr.labels", &items(&["deps"])).unwrap(),
+            1
+        );
+        assert_eq!(
+            workspace_config_add(
+                &mut value,
+                "repos.web",
+                &items(&["url=git@x:web.git", "depends_on=[\"api\"]"])
+            )
+            .unwrap(),
+            2
+        );
+        assert_eq!(
+            value["groups"]["core"],
+            toml::Value::Array(vec!["api".into(), "web".into()])
+        );
+        assert_eq!(
+            value["mr"]["labels"],
+            toml::Value::Array(vec!["deps".into()])
+        );
+        assert_eq!(value["repos"]["web"]["url"].as_str(), Some("git@x:web.git"));
+        assert!(validate_config_document(&value).is_ok());
+
+        // Entries that already hold a different value are not overwritten.
+        assert!(workspace_config_add(&mut value, "repos.web", &items(&["url=other"])).is_err());
+        assert!(workspace_config_add(&mut value, "repos.web", &items(&["url"])).is_err());
+        assert!(workspace_config_add(&mut value, "repos.web.url", &items(&["x"])).is_err());
+
+        assert_eq!(
+            workspace_config_remove(&mut value, "groups.core", &items(&["web", "missing"]))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            workspace_config_remove(&mut value, "repos.web", &items(&["depends_on"])).unwrap(),
+            1
+        );
+        assert_eq!(workspace_config_remove(&mut value, "mr", &[]).unwrap(), 1);
+        assert_eq!(workspace_config_remove(&mut value, "mr", &[]).unwrap(), 0);
+        assert!(value.get("mr").is_none());
+        assert_eq!(
+            value["groups"]["core"],
+            toml::Value::Array(vec!["api".into()])
+        );
+
+        workspace_config_add(&mut value, "defaults", &items(&["clone_protocol=ftp"])).unwrap();
+        assert!(validate_config_document(&value).is_err());
+    }
+
+    #[test]
+    fn auto_labels_follow_merge_order_and_unmerged_upstreams() {
+        let id = |name: &str| RepoId::new(name.to_string());
+        let order = vec![id("core"), id("lib"), id("app"), id("docs")];
+        let edges = std::collections::HashMap::from([
+            (id("lib"), vec![id("core")]),
+            (id("app"), vec![id("core"), id("lib"), id("vendor")]),
+        ]);
+        let none = std::collections::HashSet::new();
+
+        assert_eq!(
+            auto_mr_labels(&order, &edges, &none, &id("core")),
+            vec!["merge-order:1"]
+        );
+        assert_eq!(
+            auto_mr_labels(&order, &edges, &none, &id("app")),
+            vec![
+                "merge-order:3",
+                "blocked-by:core",
+                "blocked-by:lib",
+                "leaf-change"
+            ]
+        );
+        assert_eq!(
+            auto_mr_labels(&order, &edges, &none, &id("docs")),
+            vec!["merge-order:4", "leaf-change"]
+        );
+
+        let merged = std::collections::HashSet::from([id("core")]);
+        assert_eq!(
+            auto_mr_labels(&order, &edges, &merged, &id("app")),
+            vec!["merge-order:3", "blocked-by:lib", "leaf-change"]
+        );
+    }
+
+    #[test]
+    fn counts_missing_approvals() {
+        assert_eq!(missing_approvals(2, 0), Some(2));
+        assert_eq!(missing_approvals(2, 1), Some(1));
+        assert_eq!(missing_approvals(2, 3), None);
+        assert_eq!(missing_approvals(0, 0), None);
+    }
+
+    #[test]
+    fn pipeline_summary_links_duration_and_trigger() {
+        let pipeline = Pipeline {
+            id: "7".to_string(),
+            status: "failed".to_string(),
+            url: Some("https://gitlab.example.com/platform/api/-/pipelines/7".to_string()),
+            duration_secs: Some(185),
+            triggered_by: Some("alice".to_string()),
+        };
+        assert_eq!(
+            pipeline_summary(&pipeline),
+            "failed https://gitlab.example.com/platform/api/-/pipelines/7 (3m05s, by alice)"
+        );
+        let running = Pipeline {
+            status: "running".to_string(),
+            ..Pipeline::default()
+        };
+        assert_eq!(pipeline_summary(&running), "running");
+    }
+
+    #[test]
+    fn mirrored_mr_status_replaces_only_its_own_section() {
+        let row = MrStatusRow {
+            repo: RepoId::new("api".to_string()),
+            iid: 12,
+            url: "https://gitlab.example.com/platform/api/-/merge_requests/12".to_string(),
+            state: MrState::Open,
+            labels: Vec::new(),
+            ci_state: Some(CiState::Running),
+            approvals: vec!["alice".to_string()],
+            required_approvals: 0,
+            pipeline: None,
+            checks: Vec::new(),
+            missing_required_checks: Vec::new(),
+            failed_required_checks: Vec::new(),
+            changes: None,
+        };
+        let first = with_mirrored_mr_status("Rollout notes.\n", &[&row]);
+        assert_eq!(
+            first,
+            "Rollout notes.\n\n<!-- harmonia-mr-status -->\n### MR status\n\n\
+             | Repo | MR | State | CI | Approvals |\n| --- | --- | --- | --- | --- |\n\
+             | api | [!12](https://gitlab.example.com/platform/api/-/merge_requests/12) | open | running | alice |\n\
+             <!-- /harmonia-mr-status -->"
+        );
+
+        let merged = MrStatusRow {
+            state: MrState::Merged,
+            ci_state: Some(CiState::Success),
+            ..row
+        };
+        let second = with_mirrored_mr_status(&format!("{first}\n\nFollow-up below."), &[&merged]);
+        assert!(second.starts_with("Rollout notes.\n\n<!-- harmonia-mr-status -->"));
+        assert!(second.contains("| merged | success | alice |"));
+        assert!(!second.contains("| open |"));
+        assert!(second.ends_with("<!-- /harmonia-mr-status -->\n\nFollow-up below."));
+    }
+
+    #[test]
+    fn review_surface_sums_changes_of_mrs_that_have_them() {
+        let file = |path: &str, additions, deletions| MrFileChange {
+            path: path.to_string(),
+            additions,
+            deletions,
+        };
+        let row = |repo: &str, changes: Option<MrChanges>| MrStatusRow {
+            repo: RepoId::new(repo.to_string()),
+            iid: 1,
+            url: String::new(),
+            state: MrState::Open,
+            labels: Vec::new(),
+            ci_state: None,
+            approvals: Vec::new(),
+            required_approvals: 0,
+            pipeline: None,
+            checks: Vec::new(),
+            missing_required_checks: Vec::new(),
+            failed_required_checks: Vec::new(),
+            changes,
+        };
+        let rows = vec![
+            row(
+                "api",
+                Some(MrChanges {
+                    files: vec![file("src/lib.rs", 10, 2), file("README.md", 1, 1)],
+                }),
+            ),
+            row(
+                "web",
+                Some(MrChanges {
+                    files: vec![file("app.ts", 5, 0)],
+                }),
+            ),
+            row("worker", None),
+        ];
+        let surface = review_surface(&rows);
+        assert_eq!(surface.mrs, 2);
+        assert_eq!(surface.summary(), "3 file(s), +16 -3");
+    }
+
+    #[test]
+    fn diff_summary_totals_numstat_and_aligns_columns() {
+        let api =
+            parse_numstat("10\t2\tsrc/lib.rs\n-\t-\tlogo.png\n0\t0\tsrc/{old.rs => new.rs}\n");
+        assert_eq!(
+            api,
+            DiffSummary {
+                files: 3,
+                insertions: 10,
+                deletions: 2,
+                renames: 1,
+            }
+        );
+        let core = parse_numstat("120\t45\tCargo.toml\n");
+
+        let lines = render_diff_summary(
+            &[("api".to_string(), api), ("core-lib".to_string(), core)],
+            false,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "api              3 files   +10   -2  1 renamed",
+                "core-lib          1 file  +120  -45",
+                "-----------------------------------",
+                "total (2 repos)  4 files  +130  -47  1 renamed",
+            ]
+        );
+    }
+
+    #[test]
+    fn dependency_impact_renders_a_markdown_table() {
+        let mut body = String::from("Merge order:\n1. core\n");
+        push_dependency_impact(&mut body, &[]);
+        assert_eq!(body, "Merge order:\n1. core\n");
+
+        push_dependency_impact(
+            &mut body,
+            &[
+                DependencyImpactRow {
+                    repo: "api".to_string(),
+                    dependency: "core".to_string(),
+                    from: "^1.2.0".to_string(),
+                    to: "^1.3.0".to_string(),
+                    in_changeset: true,
+                },
+                DependencyImpactRow {
+                    repo: "web".to_string(),
+                    dependency: "core".to_string(),
+                    from: String::new(),
+                    to: "1.3.0".to_string(),
+                    in_changeset: false,
+                },
+            ],
+        );
+        assert!(body.ends_with(
+            "## Dependency Impact\n\n\
+             | Dependent | Dependency | From | To |\n\
+             | --- | --- | --- | --- |\n\
+             | api | core | `^1.2.0` | `^1.3.0` |\n\
+             | web | core | - | `1.3.0` |\n"
+        ));
+    }
+
+    #[test]
+    fn push_target_prefers_the_configured_push_remote() {
+        let origin = || Some("origin".to_string());
+        let target = push_target(Some("fork"), origin(), Some("origin/main"), true);
+        assert_eq!(target.remote.as_deref(), Some("fork"));
+        assert!(target.set_upstream);
+        let target = push_target(Some("fork"), origin(), Some("fork/feature"), true);
+        assert_eq!(target.remote.as_deref(), Some("fork"));
+        assert!(!target.set_upstream);
+        assert!(!push_target(Some("fork"), origin(), None, false).set_upstream);
+
+        let target = push_target(None, origin(), None, true);
+        assert_eq!(target.remote.as_deref(), Some("origin"));
+        assert!(target.set_upstream);
+        let target = push_target(None, origin(), Some("origin/feature"), true);
+        assert_eq!(target.remote, None);
+        assert!(!target.set_upstream);
+        assert_eq!(push_target(None, origin(), None, false).remote, None);
+    }
+
+    #[test]
+    fn parse_ahead_behind_output() {
+        assert_eq!(parse_ahead_behind_counts("4\t9\n"), Some((9, 4)));
+        assert_eq!(parse_ahead_behind_counts("0 0"), Some((0, 0)));
+        assert_eq!(parse_ahead_behind_counts("x y"), None);
+    }
+
+    #[test]
+    fn squash_message_template_renders_ticket_and_changeset() {
+        let mut context = SquashMessageContext {
+            title: "rotate keys",
+            ticket: Some("PLAT-142"),
+            changeset: Some("auth-rotation"),
+            repo: "api",
+            branch: "feature/PLAT-142-rotate-keys",
+            iid: 7,
+        };
+        assert_eq!(
+            render_squash_message(
+                "{ticket}: {title} (!{iid})\n\nChangeset: {changeset}",
+                &context
+            ),
+            "PLAT-142: rotate keys (!7)\n\nChangeset: auth-rotation"
+        );
+        context.changeset = None;
+        assert_eq!(
+            render_squash_message("{title}\n\nChangeset: {changeset}", &context),
+            "rotate keys"
+        );
+        assert_eq!(
+            render_squash_message("{repo}: {title} [{branch}]", &context),
+            "api: rotate keys [feature/PLAT-142-rotate-keys]"
+        );
+    }
+
+    #[test]
+    fn closing_refs_go_to_their_repo_or_the_last_mr() {
+        let refs = vec![
+            "#12".to_string(),
+            "api#3".to_string(),
+            "platform/web#7".to_string(),
+            "not-a-ref".to_string(),
+        ];
+        let api = RepoId::new("api");
+        let app = RepoId::new("app");
+        let known = |id: &RepoId| id.as_str() == "api" || id.as_str() == "app";
+        assert_eq!(
+            closing_lines_for_repo(&refs, "Closes", &api, Some(&app), known),
+            vec!["Closes #3"]
+        );
+        assert_eq!(
+            closing_lines_for_repo(&refs, "Fixes", &app, Some(&app), known),
+            vec!["Fixes #12", "Fixes platform/web#7"]
+        );
+    }
+
+    #[test]
+    fn closing_lines_are_parsed_from_mr_descriptions() {
+        let description = "Adds login.\n\n---\nMerge order:\n1. api\n\nCloses #12\nfixes platform/web#7\nCloses the loop\nCloses #x\n";
+        assert_eq!(parse_closing_refs(description, "Closes"), vec![(None, 12)]);
+        assert_eq!(
+            parse_closing_refs(description, "Fixes"),
+            vec![(Some("platform/web".to_string()), 7)]
+        );
+    }
+
+    #[test]
+    fn commit_links_use_the_remote_web_url() {
+        assert_eq!(
+            repo_web_url("git@gitlab.example.com:platform/api.git", "").as_deref(),
+            Some("https://gitlab.example.com/platform/api")
+        );
+        assert_eq!(
+            repo_web_url("ssh://git@gitlab.example.com:2222/platform/api.git", "").as_deref(),
+            Some("https://gitlab.example.com/platform/api")
+        );
+        assert_eq!(
+            repo_web_url("http://gitlab.local:8080/platform/api.git", "").as_deref(),
+            Some("http://gitlab.local:8080/platform/api")
+        );
+        assert_eq!(repo_web_url("file:///tmp/remotes/api.git", ""), None);
+        assert_eq!(
+            commit_web_url("https://github.com/acme/api", true, "abc123"),
+            "https://github.com/acme/api/commit/abc123"
+        );
+        assert_eq!(
+            commit_web_url("https://gitlab.com/acme/api", false, "abc123"),
+            "https://gitlab.com/acme/api/-/commit/abc123"
+        );
+    }
+
+    #[test]
+    fn forge_sources_map_subgroups_to_groups() {
+        assert_eq!(
+            parse_forge_source("gitlab://platform/backend/").expect("gitlab"),
+            Some(("gitlab", "platform/backend".to_string()))
+        );
+        assert_eq!(
+            parse_forge_source("github://acme").expect("github"),
+            Some(("github", "acme".to_string()))
+        );
+        assert_eq!(
+            parse_forge_source("git@gitlab.com:platform/app.git").expect("plain"),
+            None
+        );
+        assert!(parse_forge_source("gitlab://").is_err());
+        assert!(parse_forge_source("github://acme/api").is_err());
+
+        let project = |namespace: &str, name: &str| crate::forge::ForgeProject {
+            name: name.to_string(),
+            path: format!("{namespace}/{name}"),
+            namespace: namespace.to_string(),
+            ssh_url: format!("git@gitlab.com:{namespace}/{name}.git"),
+            http_url: String::new(),
+            default_branch: None,
+            archived: false,
+            description: None,
+        };
+        let projects = vec![
+            project("platform", "docs"),
+            project("platform/backend", "api"),
+            project("platform/backend/jobs", "worker"),
+            project("platform/frontend", "api"),
+        ];
+        let entries: Vec<(String, String)> = forge_project_entries("platform", &projects)
+            .into_iter()
+            .map(|(name, group, _)| (name, group))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("backend-api".to_string(), "backend".to_string()),
+                ("docs".to_string(), "platform".to_string()),
+                ("frontend-api".to_string(), "frontend".to_string()),
+                ("worker".to_string(), "backend-jobs".to_string()),
+            ]
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::{
-        format_mr_branch_conflict_error, parse_ahead_behind_counts, parse_depth, resolve_clone_url,
-        to_https_url, to_ssh_url, MrBranchConflict,
-    };
-    use crate::core::repo::RepoId;
+    #[test]
+    fn release_branch_patterns_match_wildcards() {
+        let patterns = vec!["main".to_string(), "release/*".to_string()];
+        assert!(branch_matches_any("main", &patterns));
+        assert!(branch_matches_any("release/2.4", &patterns));
+        assert!(!branch_matches_any("mainline", &patterns));
+        assert!(!branch_matches_any("feature/release", &patterns));
+    }
 
     #[test]
-    fn parse_ahead_behind_output() {
-        assert_eq!(parse_ahead_behind_counts("4\t9\n"), Some((9, 4)));
-        assert_eq!(parse_ahead_behind_counts("0 0"), Some((0, 0)));
-        assert_eq!(parse_ahead_behind_counts("x y"), None);
+    fn repo_patterns_match_globs_and_classes() {
+        assert!(is_repo_pattern("service-*"));
+        assert!(!is_repo_pattern("service-api"));
+        assert!(repo_pattern_matches("service-*", "service-api"));
+        assert!(!repo_pattern_matches("service-*", "api-service"));
+        assert!(repo_pattern_matches("python/*", "python/ingest"));
+        assert!(repo_pattern_matches("api-v?", "api-v2"));
+        assert!(!repo_pattern_matches("api-v?", "api-v10"));
+        assert!(repo_pattern_matches("lib[ab]", "liba"));
+        assert!(!repo_pattern_matches("lib[!ab]", "libb"));
+        assert!(repo_pattern_matches("lib[!ab]", "libc"));
+        assert!(!repo_pattern_matches("lib[ab", "liba"));
+        assert!(repo_pattern_matches("core.*", "core.proto"));
+        assert!(!repo_pattern_matches("core.*", "core-proto"));
     }
 
     #[test]
     fn clone_url_protocol_conversion() {
         assert_eq!(
-            to_https_url("git@gitlab.example.com:team/repo.git"),
-            Some("https://gitlab.example.com/team/repo.git".to_string())
+            resolve_clone_url("git@gitlab.example.com:team/repo.git", Some("https"), ""),
+            "https://gitlab.example.com/team/repo.git"
         );
         assert_eq!(
-            to_ssh_url("https://gitlab.example.com/team/repo.git"),
-            Some("git@gitlab.example.com:team/repo.git".to_string())
+            resolve_clone_url("https://gitlab.example.com/team/repo.git", Some("ssh"), ""),
+            "git@gitlab.example.com:team/repo.git"
         );
         assert_eq!(
-            resolve_clone_url("file:///tmp/repo.git", Some("https")),
+            resolve_clone_url("file:///tmp/repo.git", Some("https"), ""),
             "file:///tmp/repo.git".to_string()
         );
+        assert_eq!(
+            resolve_clone_url(
+                "https://git.corp:8443/scm/group/repo.git",
+                Some("ssh"),
+                "scm"
+            ),
+            "git@git.corp:group/repo.git"
+        );
+        assert_eq!(
+            resolve_clone_url("git@git.corp:group/repo.git", Some("https"), "scm"),
+            "https://git.corp/scm/group/repo.git"
+        );
     }
 
     #[test]
@@ -7006,6 +20176,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn git_grep_output_splits_on_nul_separators() {
+        let matches = parse_git_grep_output(
+            "api",
+            "src/a:b.rs\u{0}12\u{0}let x = \"a:1\";\nREADME.md\u{0}3\u{0}TODO\n",
+            false,
+        );
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].path, "src/a:b.rs");
+        assert_eq!(matches[0].line, Some(12));
+        assert_eq!(matches[0].text.as_deref(), Some("let x = \"a:1\";"));
+        assert_eq!(matches[1].repo, "api");
+
+        let files = parse_git_grep_output("api", "src/lib.rs\0README.md\0", true);
+        let paths: Vec<&str> = files.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/lib.rs", "README.md"]);
+        assert!(files.iter().all(|entry| entry.line.is_none()));
+    }
+
+    #[test]
+    fn issue_state_filter_accepts_forge_spellings_and_all() {
+        assert_eq!(
+            parse_issue_state_filter("opened").expect("state"),
+            Some(IssueState::Open)
+        );
+        assert_eq!(
+            parse_issue_state_filter("Closed").expect("state"),
+            Some(IssueState::Closed)
+        );
+        assert_eq!(parse_issue_state_filter("all").expect("state"), None);
+        assert!(parse_issue_state_filter("merged").is_err());
+    }
+
     #[test]
     fn mr_branch_conflict_error_is_actionable() {
         let message = format_mr_branch_conflict_error(
@@ -7045,4 +20248,574 @@ mod tests {
             "message:\n{message}"
         );
     }
+
+    #[test]
+    fn rust_test_filter_and_coverage() {
+        let repo = test_repo(EcosystemId::Rust);
+        assert_eq!(
+            apply_test_filter("cargo test", &repo, "auth"),
+            "cargo test 'auth'"
+        );
+        assert_eq!(
+            apply_test_filter("cargo test -- --nocapture", &repo, "auth"),
+            "cargo test 'auth' -- --nocapture"
+        );
+        assert_eq!(
+            apply_test_coverage("cargo test --workspace", &repo),
+            (
+                "cargo llvm-cov --workspace --lcov --output-path target/lcov.info".to_string(),
+                true
+            )
+        );
+        assert_eq!(
+            apply_test_coverage("cargo test -- --nocapture", &repo),
+            (
+                "cargo llvm-cov --lcov --output-path target/lcov.info -- --nocapture".to_string(),
+                true
+            )
+        );
+        assert_eq!(
+            apply_test_coverage("make test", &repo),
+            ("make test".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn retry_failed_narrows_to_last_failures_where_supported() {
+        let python = test_repo(EcosystemId::Python);
+        assert_eq!(
+            apply_test_retry_failed("pytest -q", &python),
+            "pytest -q --lf"
+        );
+        let node = test_repo(EcosystemId::Node);
+        assert_eq!(
+            apply_test_retry_failed("npx jest", &node),
+            "npx jest --onlyFailures"
+        );
+        assert_eq!(
+            apply_test_retry_failed("npx vitest run", &node),
+            "npx vitest run"
+        );
+        let rust = test_repo(EcosystemId::Rust);
+        assert_eq!(apply_test_retry_failed("cargo test", &rust), "cargo test");
+    }
+
+    #[test]
+    fn php_test_filter_coverage_and_lint_fix() {
+        let repo = test_repo(EcosystemId::Php);
+        assert_eq!(
+            apply_test_filter("vendor/bin/phpunit", &repo, "login"),
+            "vendor/bin/phpunit --filter 'login'"
+        );
+        assert_eq!(
+            apply_test_coverage("vendor/bin/phpunit", &repo),
+            (
+                "vendor/bin/phpunit --coverage-cobertura coverage.xml".to_string(),
+                true
+            )
+        );
+        assert_eq!(
+            apply_lint_fix(
+                "vendor/bin/phpstan analyse && vendor/bin/php-cs-fixer fix --dry-run --diff",
+                &repo
+            ),
+            (
+                "vendor/bin/phpstan analyse && vendor/bin/php-cs-fixer fix --diff".to_string(),
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn node_test_filter_and_coverage_by_runner() {
+        let repo = test_repo(EcosystemId::Node);
+        assert_eq!(
+            apply_test_filter("npx jest", &repo, "login"),
+            "npx jest --testNamePattern 'login'"
+        );
+        assert_eq!(
+            apply_test_filter("node --test", &repo, "login"),
+            "node --test --test-name-pattern 'login'"
+        );
+        assert_eq!(
+            apply_test_filter("npm test", &repo, "login"),
+            "npm test -- 'login'"
+        );
+        assert_eq!(
+            apply_test_coverage("npx vitest run", &repo),
+            (
+                "npx vitest run --coverage --coverage.reporter=text --coverage.reporter=lcov"
+                    .to_string(),
+                true
+            )
+        );
+        assert_eq!(
+            apply_test_coverage("npm test", &repo),
+            ("npm test".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn mr_title_template_uses_ticket_from_branch() {
+        let branch = "feature/PLAT-142-rotate-keys";
+        let ticket = extract_ticket(branch, DEFAULT_TICKET_PATTERN).expect("ticket regex");
+        assert_eq!(ticket.as_deref(), Some("PLAT-142"));
+        let title = title_from_branch(branch, ticket.as_deref());
+        assert_eq!(title, "rotate keys");
+        assert_eq!(
+            render_mr_title("{ticket}: {title}", &title, ticket.as_deref(), branch),
+            "PLAT-142: rotate keys"
+        );
+        assert_eq!(
+            render_mr_title(
+                "{ticket}: {title}",
+                "rotate keys",
+                None,
+                "feature/rotate-keys"
+            ),
+            "rotate keys"
+        );
+        assert_eq!(
+            extract_ticket("gh-88/fix", r"gh-(\d+)")
+                .expect("ticket regex")
+                .as_deref(),
+            Some("88")
+        );
+    }
+
+    #[test]
+    fn release_notes_group_commits_into_a_changelog_section() {
+        let subjects: Vec<String> = [
+            "feat(auth): rotate signing keys",
+            "fix: handle empty manifests",
+            "Update README",
+            "feat!: drop the v1 API",
+            "chore(deps): bump serde",
+        ]
+        .iter()
+        .map(|subject| subject.to_string())
+        .collect();
+        assert_eq!(
+            render_changelog_section("v1.3.0", "2026-10-17", &subjects),
+            "## v1.3.0 (2026-10-17)\n\n\
+             ### Breaking changes\n\n- drop the v1 API\n\n\
+             ### Features\n\n- auth: rotate signing keys\n\n\
+             ### Fixes\n\n- handle empty manifests\n\n\
+             ### Other changes\n\n- Update README\n- deps: bump serde\n"
+        );
+        assert_eq!(
+            render_changelog_section("v0.1.0", "2026-10-17", &[]),
+            "## v0.1.0 (2026-10-17)\n\nInitial release.\n"
+        );
+    }
+
+    #[test]
+    fn explicit_mr_title_skips_the_title_template() {
+        let workspace = crate::core::workspace::Workspace {
+            root: std::path::PathBuf::from("/nonexistent/harmonia-test-workspace"),
+            config: toml::from_str(
+                "[mr]\ntitle_template = \"{ticket}: {title}\"\nticket_pattern = \"(change)set\"\n",
+            )
+            .expect("parse config"),
+            repos: std::collections::HashMap::new(),
+            graph: crate::graph::DependencyGraph::new(),
+        };
+        let plan = PlanSummary {
+            changed: Vec::new(),
+            merge_order: Vec::new(),
+            constraints: crate::graph::constraint::ConstraintReport {
+                violations: Vec::new(),
+                missing: Vec::new(),
+                cycles: Vec::new(),
+                duplicates: Vec::new(),
+                external: Vec::new(),
+            },
+            recommendations: Vec::new(),
+            changeset: None,
+        };
+
+        assert_eq!(
+            resolve_mr_title(&workspace, &plan, Some("Rotate signing keys".to_string()))
+                .expect("title"),
+            "Rotate signing keys"
+        );
+        assert!(resolve_mr_title(&workspace, &plan, None)
+            .expect("title")
+            .starts_with("change: "));
+    }
+
+    #[test]
+    fn branch_template_renders_and_drops_empty_placeholders() {
+        assert_eq!(
+            render_branch_template(DEFAULT_BRANCH_TEMPLATE, "jane", "ABC-12", "auth-flow", 1),
+            "feature/jane/ABC-12-auth-flow"
+        );
+        assert_eq!(
+            render_branch_template(DEFAULT_BRANCH_TEMPLATE, "jane", "", "auth-flow", 1),
+            "feature/jane/auth-flow"
+        );
+        assert_eq!(
+            render_branch_template("{ticket}/{slug}-{timestamp}", "jane", "", "fix", 42),
+            "fix-42"
+        );
+        assert_eq!(
+            slugify_branch_part("  Add OAuth: login flow! "),
+            "add-oauth-login-flow"
+        );
+        assert_eq!(
+            slugify_branch_part(&"x".repeat(60)).len(),
+            BRANCH_SLUG_MAX_LEN
+        );
+    }
+
+    #[test]
+    fn branch_policy_reports_pattern_and_length_violations() {
+        let pattern = Some(r"^(feature|fix)/");
+        assert_eq!(
+            branch_name_policy_violation("feature/jane/auth", pattern, Some(40))
+                .expect("policy check"),
+            None
+        );
+        let violation = branch_name_policy_violation("wip/auth", pattern, None)
+            .expect("policy check")
+            .expect("pattern violation");
+        assert!(violation.contains("does not match pattern"), "{violation}");
+        let violation = branch_name_policy_violation("feature/a-very-long-name", None, Some(10))
+            .expect("policy check")
+            .expect("length violation");
+        assert!(violation.contains("max_length 10"), "{violation}");
+    }
+
+    #[test]
+    fn template_shorthands_expand_to_clone_urls() {
+        assert_eq!(
+            resolve_template_url("gh:acme/workspace-template"),
+            "https://github.com/acme/workspace-template.git"
+        );
+        assert_eq!(
+            resolve_template_url("gl:platform/ws.git"),
+            "https://gitlab.com/platform/ws.git"
+        );
+        assert_eq!(
+            resolve_template_url("git@example.com:acme/ws.git"),
+            "git@example.com:acme/ws.git"
+        );
+    }
+
+    #[test]
+    fn first_changed_line_comes_from_the_first_hunk() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+index 1..2 100644\n\
+--- a/src/lib.rs\n\
++++ b/src/lib.rs\n\
+@@ -10,0 +11,2 @@ fn main() {\n\
++a\n\
+@@ -40 +42 @@\n\
+-b\n\
++c\n\
+diff --git a/old.txt b/old.txt\n\
+deleted file mode 100644\n\
+--- a/old.txt\n\
++++ /dev/null\n\
+@@ -1 +0,0 @@\n\
+-gone\n\
+diff --git a/empty.md b/empty.md\n\
+--- a/empty.md\n\
++++ b/empty.md\n\
+@@ -3 +0,0 @@\n";
+        assert_eq!(
+            parse_first_changed_lines(diff),
+            vec![("src/lib.rs".to_string(), 11), ("empty.md".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn editor_template_repeats_file_tokens_per_file() {
+        let editor = vec!["code".to_string(), "--reuse-window".to_string()];
+        let files = vec![
+            (std::path::PathBuf::from("/w/a.rs"), 3),
+            (std::path::PathBuf::from("/w/b.rs"), 1),
+        ];
+        assert_eq!(
+            render_editor_invocation(&editor, Some("{editor} -g {file}:{line}"), &files),
+            vec!["code", "--reuse-window", "-g", "/w/a.rs:3", "/w/b.rs:1"]
+        );
+        assert_eq!(
+            render_editor_invocation(&editor, None, &files),
+            vec!["code", "--reuse-window", "/w/a.rs", "/w/b.rs"]
+        );
+    }
+
+    #[test]
+    fn exec_prefix_pads_repo_to_width() {
+        assert_eq!(render_exec_prefix("{repo} | ", "api", 6), "api    | ");
+        assert_eq!(render_exec_prefix("[{repo}] ", "web", 3), "[web] ");
+        assert_eq!(render_exec_prefix("> ", "api", 6), "> ");
+    }
+
+    #[test]
+    fn destructive_git_subcommands_need_confirmation() {
+        let reason = |args: &str| {
+            let args = args
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            destructive_git_reason(&args)
+        };
+        assert_eq!(
+            reason("reset --hard HEAD~1"),
+            Some("discards local changes")
+        );
+        assert_eq!(reason("clean -fdx"), Some("deletes untracked files"));
+        assert_eq!(
+            reason("-C sub push --force-with-lease origin main"),
+            Some("rewrites or deletes remote refs")
+        );
+        assert_eq!(
+            reason("push origin +main"),
+            Some("rewrites or deletes remote refs")
+        );
+        assert_eq!(reason("branch -D old"), Some("deletes unmerged branches"));
+        assert_eq!(reason("stash drop"), Some("drops stashed changes"));
+        assert_eq!(reason("restore src/lib.rs"), Some("discards local changes"));
+        assert_eq!(reason("restore --staged src/lib.rs"), None);
+        assert_eq!(reason("clean -n"), None);
+        assert_eq!(reason("push -u origin main"), None);
+        assert_eq!(reason("log -1 --oneline"), None);
+        assert_eq!(reason("branch -d merged"), None);
+    }
+
+    #[test]
+    fn only_listed_git_subcommands_run_in_read_only_workspaces() {
+        let read_only = |args: &str| {
+            let args = args
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            read_only_git(&args)
+        };
+        for args in [
+            "status --short",
+            "-C sub log -1 --oneline",
+            "diff HEAD~1",
+            "branch",
+            "branch -a -v",
+            "branch --list feat/*",
+            "stash list",
+            "remote -v",
+            "config --get user.email",
+            "fetch --prune",
+            "--version",
+        ] {
+            assert!(read_only(args), "{args} only reads");
+        }
+        for args in [
+            "commit -m x",
+            "push",
+            "pull",
+            "checkout main",
+            "branch feat/x",
+            "branch -m old new",
+            "branch --set-upstream-to=origin/main",
+            "tag v1",
+            "stash",
+            "remote add up url",
+            "config user.email me@example.com",
+            "frobnicate",
+        ] {
+            assert!(!read_only(args), "{args} writes");
+        }
+    }
+
+    #[test]
+    fn sample_template_contexts_cover_documented_fields() {
+        let mr_template = "{{ repo }} {{ title }} {{ description }} {{ changeset.repo_summary }}\
+            {% for mr in changeset.mrs %}{{ mr.link }} {{ mr.status_emoji }} {{ mr.summary }}\
+            {% for commit in mr.commits %}{{ commit.short_sha }} {{ commit.url }}{% endfor %}\
+            {% endfor %}{% for row in changeset.dependency_impact %}{{ row.from }}{% endfor %}";
+        assert!(crate::util::template::unknown_template_variables(
+            mr_template,
+            &sample_mr_template_context()
+        )
+        .expect("parse")
+        .is_empty());
+        assert_eq!(
+            crate::util::template::unknown_template_variables(
+                mr_template,
+                &sample_issue_template_context()
+            )
+            .expect("parse"),
+            vec!["changeset.repo_summary", "repo"]
+        );
+    }
+
+    fn stored_mr(repo: &str, branch: &str, iid: u64) -> StoredMrEntry {
+        StoredMrEntry {
+            repo: repo.to_string(),
+            forge_repo: format!("group/{repo}"),
+            forge_host: None,
+            branch: branch.to_string(),
+            mr_id: iid.to_string(),
+            iid,
+            url: format!("https://example.test/group/{repo}/-/merge_requests/{iid}"),
+            source_branch: branch.to_string(),
+            target_branch: "main".to_string(),
+        }
+    }
+
+    #[test]
+    fn merge_mr_state_unions_entries_and_honours_closed_mrs() {
+        let local = MrStateStore {
+            entries: vec![stored_mr("api", "feat", 3), stored_mr("web", "feat", 7)],
+            closed: vec![ClosedMrEntry {
+                repo: "lib".to_string(),
+                branch: "feat".to_string(),
+                iid: 2,
+            }],
+            ..MrStateStore::default()
+        };
+        let shared = MrStateStore {
+            entries: vec![
+                stored_mr("api", "feat", 5),
+                stored_mr("lib", "feat", 2),
+                stored_mr("web", "feat", 6),
+                stored_mr("docs", "feat", 1),
+            ],
+            ..MrStateStore::default()
+        };
+
+        let merged = merge_mr_state(local, shared);
+        let entries: Vec<(&str, u64)> = merged
+            .entries
+            .iter()
+            .map(|entry| (entry.repo.as_str(), entry.iid))
+            .collect();
+        assert_eq!(entries, vec![("api", 5), ("web", 7), ("docs", 1)]);
+        assert_eq!(merged.closed.len(), 1);
+    }
+
+    #[test]
+    fn diff_json_parsers_read_status_counts_and_hunks() {
+        let changes = parse_name_status("M\0src/lib.rs\0R087\0old.rs\0new.rs\0A\0logo.png\0");
+        let summary: Vec<(&str, Option<&str>, &str)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.status,
+                    change.old_path.as_deref(),
+                    change.path.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("modified", None, "src/lib.rs"),
+                ("renamed", Some("old.rs"), "new.rs"),
+                ("added", None, "logo.png"),
+            ]
+        );
+
+        let counts =
+            parse_numstat_paths("3\t1\tsrc/lib.rs\x002\t2\t\0old.rs\0new.rs\0-\t-\tlogo.png\0");
+        assert_eq!(counts["src/lib.rs"], Some((3, 1)));
+        assert_eq!(counts["new.rs"], Some((2, 2)));
+        assert_eq!(counts["logo.png"], None);
+
+        let patch = "diff --git a/src/lib.rs b/src/lib.rs\n\
+            index 1..2 100644\n\
+            --- a/src/lib.rs\n\
+            +++ b/src/lib.rs\n\
+            @@ -1,2 +1,3 @@ fn main()\n\
+            \x20keep\n\
+            -old\n\
+            +new\n\
+            +added\n\
+            @@ -10 +11 @@\n\
+            -x\n\
+            +y\n\
+            diff --git a/logo.png b/logo.png\n\
+            Binary files /dev/null and b/logo.png differ\n";
+        let files = parse_patch_hunks(patch);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].len(), 2);
+        let first = &files[0][0];
+        assert_eq!(
+            (
+                first.old_start,
+                first.old_lines,
+                first.new_start,
+                first.new_lines
+            ),
+            (1, 2, 1, 3)
+        );
+        assert_eq!(first.lines, vec![" keep", "-old", "+new", "+added"]);
+        let second = &files[0][1];
+        assert_eq!(
+            (second.old_lines, second.new_start, second.new_lines),
+            (1, 11, 1)
+        );
+        assert!(files[1].is_empty());
+    }
+
+    #[test]
+    fn parallel_precedence_runs_flag_env_command_defaults_then_cores() {
+        let workspace = |config: &str| crate::core::workspace::Workspace {
+            root: std::path::PathBuf::from("/nonexistent/harmonia-test-workspace"),
+            config: toml::from_str(config).expect("parse config"),
+            repos: std::collections::HashMap::new(),
+            graph: crate::graph::DependencyGraph::new(),
+        };
+        let configured = workspace("[defaults]\nparallel = 2\n\n[exec]\nparallel = 3\n");
+        let defaults_only = workspace("[defaults]\nparallel = 2\n");
+        let cores = std::thread::available_parallelism().ok().map(|n| n.get());
+
+        std::env::remove_var("HARMONIA_PARALLEL");
+        assert_eq!(resolve_parallel(&configured, "exec", Some(5)), Some(5));
+        assert_eq!(resolve_parallel(&configured, "exec", None), Some(3));
+        assert_eq!(resolve_parallel(&configured, "sync", None), Some(2));
+        assert_eq!(resolve_parallel(&workspace(""), "exec", None), cores);
+        assert_eq!(
+            resolve_parallel(&configured, "exec", Some(0)),
+            Some(1),
+            "0 means one repo at a time"
+        );
+        assert_eq!(
+            resolve_parallel(&workspace("[exec]\nparallel = 0\n"), "exec", None),
+            Some(1)
+        );
+        assert_eq!(
+            resolve_parallel(&workspace("[defaults]\nparallel = 0\n"), "test", None),
+            Some(1)
+        );
+
+        std::env::set_var("HARMONIA_PARALLEL", "4");
+        assert_eq!(resolve_parallel(&configured, "exec", Some(5)), Some(5));
+        assert_eq!(resolve_parallel(&configured, "exec", None), Some(4));
+        assert_eq!(resolve_parallel(&defaults_only, "exec", None), Some(4));
+        std::env::set_var("HARMONIA_PARALLEL", "0");
+        assert_eq!(resolve_parallel(&configured, "exec", None), Some(1));
+        std::env::remove_var("HARMONIA_PARALLEL");
+    }
+
+    #[test]
+    fn config_origin_redacts_forge_tokens() {
+        let secret = toml::Value::String("SECRET".to_string());
+        for key in [
+            "forge.token",
+            "repos.api.forge.token",
+            "forge.hosts.gitlab.corp.token",
+            "forge.hosts.github.token",
+        ] {
+            assert_eq!(config_origin_value(key, &secret), "\"<redacted>\"", "{key}");
+        }
+        assert_eq!(
+            config_origin_value("forge.hosts.gitlab.corp.token_env", &secret),
+            "\"SECRET\""
+        );
+        assert_eq!(
+            config_origin_value("forge.hosts.gitlab.corp.host", &secret),
+            "\"SECRET\""
+        );
+    }
 }
@@ -0,0 +1,570 @@
+use std::collections::BTreeMap;
+
+use crate::config::ConfigError;
+
+/// Dotted keys understood by the workspace config. `*` matches a single
+/// user-chosen segment such as a repo, group, hook, or env var name.
+pub const WORKSPACE_CONFIG_KEYS: &[&str] = &[
+    "workspace.name",
+    "workspace.repos_dir",
+    "workspace.read_only",
+    "workspace.stats",
+    "forge.type",
+    "forge.host",
+    "forge.default_group",
+    "forge.token",
+    "forge.max_retries",
+    "forge.backoff_ms",
+    "forge.hosts.*.type",
+    "forge.hosts.*.host",
+    "forge.hosts.*.default_group",
+    "forge.hosts.*.token",
+    "forge.hosts.*.token_env",
+    "repos.*.url",
+    "repos.*.default_branch",
+    "repos.*.package_name",
+    "repos.*.ecosystem",
+    "repos.*.depends_on",
+    "repos.*.path",
+    "repos.*.external",
+    "repos.*.ignored",
+    "repos.*.paused",
+    "repos.*.paused_until",
+    "repos.*.pause_reason",
+    "repos.*.push_remote",
+    "groups.default",
+    "groups.*",
+    "defaults.default_branch",
+    "defaults.clone_protocol",
+    "defaults.clone_depth",
+    "defaults.include_untracked",
+    "defaults.editor_args",
+    "defaults.push_set_upstream",
+    "defaults.network_parallel",
+    "defaults.exec_prefix",
+    "defaults.changed_as",
+    "defaults.prefetch",
+    "defaults.git_transport",
+    "defaults.parallel",
+    "defaults.command_timeout",
+    "hooks.pre_commit",
+    "hooks.pre_push",
+    "hooks.pre_mr_create",
+    "hooks.post_mr_create",
+    "hooks.post_mr_merge",
+    "hooks.post_submit",
+    "hooks.pre_verify",
+    "hooks.custom.*",
+    "mr.template",
+    "mr.link_strategy",
+    "mr.create_tracking_issue",
+    "mr.issue_template",
+    "mr.add_trailers",
+    "mr.labels",
+    "mr.require_tests",
+    "mr.draft",
+    "mr.title_template",
+    "mr.ticket_pattern",
+    "mr.squash_template",
+    "mr.close_issues",
+    "mr.closing_keyword",
+    "mr.issue_pattern",
+    "mr.auto_labels",
+    "mr.forge_requirements",
+    "mr.state_branch",
+    "mr.state_remote",
+    "mr.reviewer_strategy",
+    "mr.reviewers_per_mr",
+    "mr.coordination.repo",
+    "mr.coordination.forge.type",
+    "mr.coordination.forge.host",
+    "mr.coordination.forge.default_group",
+    "mr.coordination.forge.token",
+    "versioning.strategy",
+    "versioning.bump_mode",
+    "versioning.calver_format",
+    "versioning.cascade_bumps",
+    "versioning.tag_format",
+    "versioning.sign_tags",
+    "changesets.enabled",
+    "changesets.dir",
+    "branching.template",
+    "branching.pattern",
+    "branching.max_length",
+    "env.*",
+    "external_packages.*",
+    "external_packages.*.version_url",
+    "safety.force_push",
+    "safety.force_branch",
+    "safety.amend",
+    "safety.mr_merge",
+    "safety.mr_close",
+    "safety.release_branches",
+    "safety.secret_scan",
+    "safety.secret_allowlist",
+    "propagate.dir",
+    "propagate.vars.*",
+    "teams.*.repos",
+    "teams.*.groups",
+    "teams.*.reviewers",
+    "ecosystems.*.files",
+    "ecosystems.*.version_file",
+    "ecosystems.*.version_pattern",
+    "ecosystems.*.version_path",
+    "ecosystems.*.dependency_file",
+    "ecosystems.*.dependency_pattern",
+    "ecosystems.*.dependency_path",
+    "ecosystems.*.commands.*",
+    "sync.parallel",
+    "exec.parallel",
+    "run.parallel",
+    "each.parallel",
+    "git.parallel",
+    "test.parallel",
+    "lint.parallel",
+    "toolchain.rust",
+    "toolchain.node",
+    "toolchain.python",
+    "toolchain.go",
+    "webhook.listen",
+    "webhook.secret",
+    "webhook.secret_env",
+    "webhook.events",
+    "webhook.actions.*",
+];
+
+/// Dotted keys understood by a repo's own `.harmonia.toml`.
+pub const REPO_CONFIG_KEYS: &[&str] = &[
+    "package.name",
+    "package.ecosystem",
+    "versioning.file",
+    "versioning.path",
+    "versioning.strategy",
+    "versioning.bump_mode",
+    "versioning.pattern",
+    "dependencies.file",
+    "dependencies.path",
+    "dependencies.internal_pattern",
+    "dependencies.internal_packages",
+    "hooks.disable_workspace_hooks",
+    "hooks.pre_commit",
+    "hooks.pre_push",
+    "hooks.custom.*",
+    "ci.required_checks",
+    "ci.required_approvals",
+    "ci.timeout_minutes",
+    "env.*",
+    "toolchain.rust",
+    "toolchain.node",
+    "toolchain.python",
+    "toolchain.go",
+];
+
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Checks `key` against [`WORKSPACE_CONFIG_KEYS`]. With `allow_tables`, a key
+/// naming a section (for example `mr` or `repos.api`) is accepted as well.
+pub fn validate_config_key(key: &str, allow_tables: bool) -> Result<(), ConfigError> {
+    validate_key_in(
+        WORKSPACE_CONFIG_KEYS,
+        key,
+        allow_tables,
+        "harmonia config list --schema",
+    )
+}
+
+/// Like [`validate_config_key`] for [`REPO_CONFIG_KEYS`].
+pub fn validate_repo_config_key(key: &str, allow_tables: bool) -> Result<(), ConfigError> {
+    validate_key_in(
+        REPO_CONFIG_KEYS,
+        key,
+        allow_tables,
+        "harmonia repo config list --schema",
+    )
+}
+
+fn validate_key_in(
+    schema: &[&str],
+    key: &str,
+    allow_tables: bool,
+    list_command: &str,
+) -> Result<(), ConfigError> {
+    let segments = key_segments(key);
+    if segments.is_empty() {
+        return Err(ConfigError::Validation(
+            "config key cannot be empty".to_string(),
+        ));
+    }
+
+    let known = schema.iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        if allow_tables {
+            segments.len() <= pattern.len() && segments_match(&pattern, &segments)
+        } else {
+            segments.len() == pattern.len() && segments_match(&pattern, &segments)
+        }
+    });
+    if known {
+        return Ok(());
+    }
+
+    let suggestions = suggest_keys_in(schema, key);
+    let message = if suggestions.is_empty() {
+        format!("unknown config key '{key}' (run `{list_command}` for known keys)")
+    } else {
+        format!(
+            "unknown config key '{key}'; did you mean {}?",
+            suggestions
+                .iter()
+                .map(|suggestion| format!("'{suggestion}'"))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )
+    };
+    Err(ConfigError::Validation(message))
+}
+
+/// Entries of [`WORKSPACE_CONFIG_KEYS`] under `prefix` (all of them when
+/// `prefix` is `None`); wildcard segments match any prefix segment.
+pub fn schema_config_keys(prefix: Option<&str>) -> Vec<&'static str> {
+    schema_keys_in(WORKSPACE_CONFIG_KEYS, prefix)
+}
+
+/// Like [`schema_config_keys`] for [`REPO_CONFIG_KEYS`].
+pub fn schema_repo_config_keys(prefix: Option<&str>) -> Vec<&'static str> {
+    schema_keys_in(REPO_CONFIG_KEYS, prefix)
+}
+
+fn schema_keys_in(schema: &[&'static str], prefix: Option<&str>) -> Vec<&'static str> {
+    let prefix = prefix.map(key_segments).unwrap_or_default();
+    schema
+        .iter()
+        .copied()
+        .filter(|pattern| {
+            let pattern: Vec<&str> = pattern.split('.').collect();
+            prefix.len() <= pattern.len() && segments_match(&pattern, &prefix)
+        })
+        .collect()
+}
+
+/// Known keys (or sections) with the same depth as `key` that are within a
+/// small edit distance of it, closest first. Wildcards take the segment the
+/// user typed, so `repos.api.urll` suggests `repos.api.url`.
+pub fn suggest_config_keys(key: &str) -> Vec<String> {
+    suggest_keys_in(WORKSPACE_CONFIG_KEYS, key)
+}
+
+fn suggest_keys_in(schema: &[&str], key: &str) -> Vec<String> {
+    let segments = key_segments(key);
+    if segments.is_empty() {
+        return Vec::new();
+    }
+    let typed = segments.join(".");
+    let threshold = (typed.len() / 3).clamp(1, 3);
+
+    let mut candidates: Vec<(usize, String)> = Vec::new();
+    for pattern in schema {
+        let pattern: Vec<&str> = pattern.split('.').collect();
+        if pattern.len() < segments.len() {
+            continue;
+        }
+        let candidate = pattern[..segments.len()]
+            .iter()
+            .zip(&segments)
+            .map(|(expected, actual)| if *expected == "*" { *actual } else { *expected })
+            .collect::<Vec<_>>()
+            .join(".");
+        let distance = edit_distance(&typed, &candidate);
+        if distance > 0
+            && distance <= threshold
+            && !candidates
+                .iter()
+                .any(|(_, existing)| *existing == candidate)
+        {
+            candidates.push((distance, candidate));
+        }
+    }
+
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Flattens a parsed config into `(dotted key, leaf value)` pairs sorted by
+/// key. Arrays are treated as leaf values.
+pub fn flatten_config_value(value: &toml::Value) -> Vec<(String, toml::Value)> {
+    let mut entries = Vec::new();
+    flatten_into(value, String::new(), &mut entries);
+    entries.sort_by(|left, right| left.0.cmp(&right.0));
+    entries
+}
+
+fn flatten_into(value: &toml::Value, prefix: String, entries: &mut Vec<(String, toml::Value)>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, child) in table {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{prefix}.{name}")
+                };
+                flatten_into(child, key, entries);
+            }
+        }
+        _ if !prefix.is_empty() => entries.push((prefix, value.clone())),
+        _ => {}
+    }
+}
+
+/// Maps each dotted key (and table header) defined in TOML `contents` to the
+/// 1-based line that defines it. Keys inside inline tables or multi-line values
+/// are not listed; use [`config_key_line`] to fall back to the enclosing key.
+pub fn config_key_lines(contents: &str) -> BTreeMap<String, usize> {
+    let mut lines = BTreeMap::new();
+    let mut table: Vec<String> = Vec::new();
+    let mut depth = 0i32;
+    let mut multiline: Option<&str> = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        if let Some(delimiter) = multiline {
+            if line.matches(delimiter).count() % 2 == 1 {
+                multiline = None;
+            }
+            continue;
+        }
+        if depth > 0 {
+            depth += bracket_balance(line);
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let header = header.strip_prefix('[').unwrap_or(header);
+            if let Some((path, _)) = parse_key_path(header) {
+                table = path;
+                lines.entry(table.join(".")).or_insert(index + 1);
+            }
+            continue;
+        }
+
+        let Some((path, rest)) = parse_key_path(trimmed) else {
+            continue;
+        };
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let key = table
+            .iter()
+            .chain(&path)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(".");
+        lines.insert(key, index + 1);
+
+        for delimiter in ["\"\"\"", "'''"] {
+            if value.matches(delimiter).count() % 2 == 1 {
+                multiline = Some(delimiter);
+            }
+        }
+        if multiline.is_none() {
+            depth = bracket_balance(value);
+        }
+    }
+    lines
+}
+
+/// Line of the most specific entry in `lines` that defines `key`.
+pub fn config_key_line(lines: &BTreeMap<String, usize>, key: &str) -> Option<usize> {
+    let segments = key_segments(key);
+    (1..=segments.len())
+        .rev()
+        .find_map(|len| lines.get(&segments[..len].join(".")).copied())
+}
+
+fn parse_key_path(input: &str) -> Option<(Vec<String>, &str)> {
+    let mut rest = input;
+    let mut path = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let (segment, remainder) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .char_indices()
+                .scan(false, |escaped, (offset, ch)| {
+                    let closes = ch == '"' && !*escaped;
+                    *escaped = ch == '\\' && !*escaped;
+                    Some((offset, closes))
+                })
+                .find(|(_, closes)| *closes)?
+                .0;
+            (quoted[..end].to_string(), &quoted[end + 1..])
+        } else if let Some(quoted) = rest.strip_prefix('\'') {
+            let end = quoted.find('\'')?;
+            (quoted[..end].to_string(), &quoted[end + 1..])
+        } else {
+            let end = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '-'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            (rest[..end].to_string(), &rest[end..])
+        };
+        path.push(segment);
+        rest = remainder.trim_start();
+        match rest.strip_prefix('.') {
+            Some(next) => rest = next,
+            None => return Some((path, rest)),
+        }
+    }
+}
+
+/// Net count of opening minus closing brackets and braces outside strings and
+/// comments.
+fn bracket_balance(line: &str) -> i32 {
+    let mut balance = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for ch in line.chars() {
+        match quote {
+            Some(open) => {
+                if ch == open && !escaped {
+                    quote = None;
+                }
+                escaped = open == '"' && ch == '\\' && !escaped;
+            }
+            None => match ch {
+                '"' | '\'' => quote = Some(ch),
+                '[' | '{' => balance += 1,
+                ']' | '}' => balance -= 1,
+                '#' => break,
+                _ => {}
+            },
+        }
+    }
+    balance
+}
+
+fn key_segments(key: &str) -> Vec<&str> {
+    key.split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+fn segments_match(pattern: &[&str], segments: &[&str]) -> bool {
+    pattern
+        .iter()
+        .zip(segments)
+        .all(|(expected, actual)| *expected == "*" || expected == actual)
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_known_keys_and_wildcard_segments() {
+        assert!(validate_config_key("workspace.name", false).is_ok());
+        assert!(validate_config_key("repos.api.default_branch", false).is_ok());
+        assert!(validate_config_key("hooks.custom.fmt", false).is_ok());
+        assert!(validate_config_key("mr", false).is_err());
+        assert!(validate_config_key("mr", true).is_ok());
+        assert!(validate_config_key("repos.api", true).is_ok());
+        assert!(validate_config_key("repos.api.url.extra", true).is_err());
+        assert_eq!(
+            schema_config_keys(Some("changesets")),
+            vec!["changesets.enabled", "changesets.dir"]
+        );
+        assert!(validate_repo_config_key("ci.required_checks", false).is_ok());
+        assert!(validate_repo_config_key("hooks.custom.fmt", false).is_ok());
+        assert!(validate_repo_config_key("workspace.name", false).is_err());
+        assert_eq!(
+            schema_repo_config_keys(Some("hooks.custom")),
+            vec!["hooks.custom.*"]
+        );
+    }
+
+    #[test]
+    fn suggests_near_matches_for_typos() {
+        assert_eq!(
+            suggest_config_keys("defaults.clone_protocl"),
+            vec!["defaults.clone_protocol".to_string()]
+        );
+        assert_eq!(
+            suggest_config_keys("repos.api.urll"),
+            vec!["repos.api.url".to_string()]
+        );
+        assert_eq!(
+            suggest_config_keys("versoning"),
+            vec!["versioning".to_string()]
+        );
+        assert!(suggest_config_keys("completely.unrelated").is_empty());
+
+        let err = validate_config_key("mr.darft", false).unwrap_err();
+        assert!(err.to_string().contains("did you mean 'mr.draft'"));
+    }
+
+    #[test]
+    fn locates_the_line_defining_each_key() {
+        let contents = r#"[workspace]
+name = "demo" # trailing comment
+
+[repos]
+"core" = { package_name = "core-pkg" }
+app.url = "file:///app.git"
+
+[mr]
+labels = [
+  "a=b",
+  "c",
+]
+template = '''
+draft = true
+'''
+draft = false
+"#;
+        let lines = config_key_lines(contents);
+        assert_eq!(config_key_line(&lines, "workspace.name"), Some(2));
+        assert_eq!(config_key_line(&lines, "repos.core.package_name"), Some(5));
+        assert_eq!(config_key_line(&lines, "repos.app.url"), Some(6));
+        assert_eq!(config_key_line(&lines, "mr.labels"), Some(9));
+        assert_eq!(config_key_line(&lines, "mr.draft"), Some(16));
+        assert_eq!(config_key_line(&lines, "mr.unknown"), Some(8));
+        assert_eq!(config_key_line(&lines, "forge.type"), None);
+    }
+
+    #[test]
+    fn flattens_nested_tables_into_dotted_keys() {
+        let value: toml::Value = toml::from_str(
+            "[workspace]\nname = \"demo\"\n[repos.api]\nurl = \"git@x:api.git\"\ndepends_on = [\"core\"]\n",
+        )
+        .unwrap();
+        let keys: Vec<String> = flatten_config_value(&value)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(
+            keys,
+            vec!["repos.api.depends_on", "repos.api.url", "workspace.name"]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod keys;
 pub mod repo;
 pub mod resolve;
 pub mod workspace;
@@ -6,8 +7,10 @@ pub use repo::{
     CiConfig, DepsConfig, PackageConfig, RepoConfig, RepoHooksConfig, RepoVersioningConfig,
 };
 pub use workspace::{
-    ChangesetsConfig, DefaultsConfig, ForgeConfig, GroupsConfig, HooksConfig, MrConfig, RepoEntry,
-    VersioningConfig, WorkspaceConfig, WorkspaceSettings,
+    ChangesetsConfig, CoordinationConfig, DefaultsConfig, EcosystemConfig, ExternalPackageEntry,
+    ForgeConfig, ForgeHostConfig, GroupsConfig, HooksConfig, MrConfig, PropagateConfig, RepoEntry,
+    SafetyConfig, TeamConfig, ToolchainConfig, VersioningConfig, WebhookConfig, WorkspaceConfig,
+    WorkspaceSettings,
 };
 
 use std::path::PathBuf;
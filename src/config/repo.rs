@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
+use crate::config::workspace::ToolchainConfig;
+
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct RepoConfig {
     #[serde(default)]
@@ -14,6 +16,10 @@ pub struct RepoConfig {
     pub hooks: Option<RepoHooksConfig>,
     #[serde(default)]
     pub ci: Option<CiConfig>,
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub toolchain: Option<ToolchainConfig>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -67,5 +73,7 @@ pub struct CiConfig {
     #[serde(default)]
     pub required_checks: Option<Vec<String>>,
     #[serde(default)]
+    pub required_approvals: Option<u32>,
+    #[serde(default)]
     pub timeout_minutes: Option<u64>,
 }
@@ -1,7 +1,17 @@
 use std::env;
 use std::path::{Path, PathBuf};
 
-use crate::config::{ConfigError, RepoConfig, WorkspaceConfig};
+use crate::config::{ConfigError, ForgeConfig, RepoConfig, WorkspaceConfig};
+
+/// Workspace config keys that can be overridden from the environment, so CI
+/// jobs can tweak behavior without editing the checked-in config. Applied in
+/// this order after the file is parsed and before it is validated.
+pub const CONFIG_ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("workspace.repos_dir", "HARMONIA_REPOS_DIR"),
+    ("forge.type", "HARMONIA_FORGE_TYPE"),
+    ("forge.host", "HARMONIA_FORGE_HOST"),
+    ("defaults.clone_protocol", "HARMONIA_CLONE_PROTOCOL"),
+];
 
 #[derive(Debug, Clone)]
 pub struct ResolvedWorkspace {
@@ -55,6 +65,57 @@ pub fn load_workspace_config(path: &Path) -> Result<WorkspaceConfig, ConfigError
     })
 }
 
+/// Overrides from [`CONFIG_ENV_OVERRIDES`] that are set to a non-empty value,
+/// as `(key, variable, value)`.
+pub fn config_env_overrides() -> Vec<(&'static str, &'static str, String)> {
+    CONFIG_ENV_OVERRIDES
+        .iter()
+        .filter_map(|(key, var)| {
+            let value = env::var(var)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())?;
+            Some((*key, *var, value))
+        })
+        .collect()
+}
+
+pub fn apply_env_overrides(config: &mut WorkspaceConfig) {
+    let overrides = config_env_overrides()
+        .into_iter()
+        .map(|(key, _, value)| (key, value))
+        .collect::<Vec<_>>();
+    apply_config_overrides(config, &overrides);
+}
+
+fn apply_config_overrides(config: &mut WorkspaceConfig, overrides: &[(&str, String)]) {
+    for (key, value) in overrides {
+        match *key {
+            "workspace.repos_dir" => config.workspace.repos_dir = value.clone(),
+            "forge.type" => {
+                config
+                    .forge
+                    .get_or_insert_with(ForgeConfig::default)
+                    .forge_type = value.to_ascii_lowercase();
+            }
+            // A host alone cannot pick a forge type, so it only applies on top
+            // of a configured (or overridden) forge.
+            "forge.host" => {
+                if let Some(forge) = config.forge.as_mut() {
+                    forge.host = Some(value.clone());
+                }
+            }
+            "defaults.clone_protocol" => {
+                config
+                    .defaults
+                    .get_or_insert_with(Default::default)
+                    .clone_protocol = Some(value.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
 pub fn load_repo_config(path: &Path) -> Result<Option<RepoConfig>, ConfigError> {
     if !path.is_file() {
         return Ok(None);
@@ -187,3 +248,40 @@ fn looks_like_workspace_root_config(path: &Path) -> bool {
         || contents.contains("[mr]")
         || contents.contains("[changesets]")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_replace_config_values() {
+        let mut config: WorkspaceConfig = toml::from_str(
+            "[workspace]\nname = \"platform\"\n\n[defaults]\nclone_protocol = \"ssh\"\n",
+        )
+        .expect("parse config");
+
+        apply_config_overrides(&mut config, &[("forge.host", "git.internal".to_string())]);
+        assert!(config.forge.is_none());
+
+        apply_config_overrides(
+            &mut config,
+            &[
+                ("workspace.repos_dir", "checkouts".to_string()),
+                ("forge.type", "GitLab".to_string()),
+                ("forge.host", "git.internal".to_string()),
+                ("defaults.clone_protocol", "https".to_string()),
+            ],
+        );
+        assert_eq!(config.workspace.repos_dir, "checkouts");
+        let forge = config.forge.as_ref().expect("forge");
+        assert_eq!(forge.forge_type, "gitlab");
+        assert_eq!(forge.host.as_deref(), Some("git.internal"));
+        assert_eq!(
+            config
+                .defaults
+                .as_ref()
+                .and_then(|defaults| defaults.clone_protocol.as_deref()),
+            Some("https")
+        );
+    }
+}
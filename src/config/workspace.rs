@@ -8,6 +8,13 @@ pub struct WorkspaceSettings {
     pub name: String,
     #[serde(default = "default_repos_dir")]
     pub repos_dir: String,
+    /// Blocks commands that commit, push, or change MRs, versions, or
+    /// dependencies.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Records local command usage stats in `.harmonia/stats.db`.
+    #[serde(default)]
+    pub stats: bool,
 }
 
 impl Default for WorkspaceSettings {
@@ -15,6 +22,8 @@ impl Default for WorkspaceSettings {
         Self {
             name: String::new(),
             repos_dir: default_repos_dir(),
+            read_only: false,
+            stats: false,
         }
     }
 }
@@ -39,6 +48,72 @@ pub struct WorkspaceConfig {
     pub versioning: Option<VersioningConfig>,
     #[serde(default)]
     pub changesets: Option<ChangesetsConfig>,
+    #[serde(default)]
+    pub branching: Option<BranchingConfig>,
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub external_packages: HashMap<String, ExternalPackageEntry>,
+    #[serde(default)]
+    pub safety: Option<SafetyConfig>,
+    #[serde(default)]
+    pub propagate: Option<PropagateConfig>,
+    #[serde(default)]
+    pub teams: HashMap<String, TeamConfig>,
+    #[serde(default)]
+    pub ecosystems: HashMap<String, EcosystemConfig>,
+    #[serde(default)]
+    pub sync: Option<CommandConfig>,
+    #[serde(default)]
+    pub exec: Option<CommandConfig>,
+    #[serde(default)]
+    pub run: Option<CommandConfig>,
+    #[serde(default)]
+    pub each: Option<CommandConfig>,
+    #[serde(default)]
+    pub git: Option<CommandConfig>,
+    #[serde(default)]
+    pub test: Option<CommandConfig>,
+    #[serde(default)]
+    pub lint: Option<CommandConfig>,
+    #[serde(default)]
+    pub toolchain: Option<ToolchainConfig>,
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+}
+
+/// Tool versions repos expect, checked and installed by `harmonia setup`.
+/// A repo's `.harmonia.toml` can override each entry.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ToolchainConfig {
+    /// Rust toolchain for rustup, e.g. `1.82` or `stable`.
+    #[serde(default)]
+    pub rust: Option<String>,
+    /// Node major or full version, e.g. `20`.
+    #[serde(default)]
+    pub node: Option<String>,
+    /// Python version for uv, e.g. `3.12`.
+    #[serde(default)]
+    pub python: Option<String>,
+    #[serde(default)]
+    pub go: Option<String>,
+}
+
+/// Per-command settings, such as `[test] parallel = 4`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommandConfig {
+    /// Repos processed at once by this command; overrides
+    /// `[defaults].parallel`. `0` runs them one at a time.
+    #[serde(default)]
+    pub parallel: Option<usize>,
+}
+
+/// A package outside the workspace that repos depend on, declared under
+/// `[external_packages]` so it is not reported as a missing internal mapping.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExternalPackageEntry {
+    #[serde(default)]
+    pub version_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -51,6 +126,91 @@ pub struct ForgeConfig {
     pub default_group: Option<String>,
     #[serde(default)]
     pub token: Option<String>,
+    /// Retries of API calls that hit a rate limit, a server error, or a
+    /// dropped connection (default 3).
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Wait before the first retry in milliseconds, doubled for each further
+    /// one, unless the forge says how long to wait (default 1000).
+    #[serde(default)]
+    pub backoff_ms: Option<u64>,
+    /// Forges for repos whose remote is on another host, keyed by that host
+    /// name, each with its own type and token.
+    #[serde(default)]
+    pub hosts: HashMap<String, ForgeHostConfig>,
+}
+
+impl ForgeConfig {
+    /// The `[forge.hosts]` entry serving remotes on `host`, matched by host
+    /// name without scheme, port, or path.
+    pub fn host_entry(&self, host: &str) -> Option<(&str, &ForgeHostConfig)> {
+        let host = host.trim().to_ascii_lowercase();
+        self.hosts
+            .iter()
+            .find(|(name, _)| bare_host_name(name) == host)
+            .map(|(name, entry)| (name.as_str(), entry))
+    }
+
+    /// Forge settings for a `[forge.hosts]` entry, inheriting `type` from
+    /// this section. The token is left to [`ForgeHostConfig::resolve_token`].
+    pub fn for_host(&self, name: &str, entry: &ForgeHostConfig) -> ForgeConfig {
+        ForgeConfig {
+            forge_type: entry
+                .forge_type
+                .clone()
+                .unwrap_or_else(|| self.forge_type.clone()),
+            host: Some(entry.host.clone().unwrap_or_else(|| name.to_string())),
+            default_group: entry.default_group.clone(),
+            token: None,
+            max_retries: self.max_retries,
+            backoff_ms: self.backoff_ms,
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ForgeHostConfig {
+    /// `github` or `gitlab`; defaults to `[forge].type`.
+    #[serde(rename = "type", default)]
+    pub forge_type: Option<String>,
+    /// API host or URL when it differs from the entry's name, e.g.
+    /// `https://git.corp/gitlab`.
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub default_group: Option<String>,
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Environment variable holding the token, preferred over `token`.
+    #[serde(default)]
+    pub token_env: Option<String>,
+}
+
+impl ForgeHostConfig {
+    pub fn resolve_token(&self) -> Option<String> {
+        self.token_env
+            .as_deref()
+            .and_then(|name| std::env::var(name).ok())
+            .or_else(|| self.token.clone())
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+/// `host` from a host name or URL such as `https://user@git.corp:8443/gitlab`.
+fn bare_host_name(value: &str) -> String {
+    let trimmed = value.trim();
+    let rest = trimmed.split_once("://").map_or(trimmed, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    authority
+        .split(':')
+        .next()
+        .unwrap_or(authority)
+        .to_ascii_lowercase()
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -66,9 +226,22 @@ pub struct RepoEntry {
     #[serde(default)]
     pub depends_on: Vec<String>,
     #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
     pub external: bool,
     #[serde(default)]
     pub ignored: bool,
+    /// Set by `repo pause`: skip the repo like `ignored` until `paused_until`.
+    #[serde(default)]
+    pub paused: bool,
+    /// Date (`YYYY-MM-DD`) on which a pause ends.
+    #[serde(default)]
+    pub paused_until: Option<String>,
+    #[serde(default)]
+    pub pause_reason: Option<String>,
+    /// Remote that `push` sends branches to, e.g. a personal fork.
+    #[serde(default)]
+    pub push_remote: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -89,6 +262,36 @@ pub struct DefaultsConfig {
     pub clone_depth: Option<String>,
     #[serde(default)]
     pub include_untracked: Option<bool>,
+    #[serde(default)]
+    pub editor_args: Option<String>,
+    /// Set upstream tracking on a branch's first push without `-u`.
+    #[serde(default)]
+    pub push_set_upstream: Option<bool>,
+    /// Concurrent fetches and clones, separate from the `--parallel` workers.
+    #[serde(default)]
+    pub network_parallel: Option<usize>,
+    /// Prefix for each line of `exec` output; `{repo}` is the repo name
+    /// padded to the longest targeted name.
+    #[serde(default)]
+    pub exec_prefix: Option<String>,
+    /// What counts as a changed repo: `dirty`, `unpushed`, and/or `branch`.
+    #[serde(default)]
+    pub changed_as: Option<Vec<String>>,
+    /// Fetch before `plan` and `mr status`, as if `--fresh` were given.
+    #[serde(default)]
+    pub prefetch: Option<bool>,
+    /// Repos processed at once by commands with `--parallel`, unless the
+    /// command's own table sets it. `0` runs them one at a time.
+    #[serde(default)]
+    pub parallel: Option<usize>,
+    /// Seconds before `exec`, `each`, `run`, `test`, and `lint` kill a repo's
+    /// command and report it as timed out. Unset or `0` means no limit.
+    #[serde(default)]
+    pub command_timeout: Option<u64>,
+    /// `native` (libgit2 where the remote allows it) or `cli` for fetch and
+    /// push.
+    #[serde(default)]
+    pub git_transport: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -97,9 +300,19 @@ pub struct HooksConfig {
     pub pre_commit: Option<String>,
     #[serde(default)]
     pub pre_push: Option<String>,
+    /// Runs before any MR is opened; a non-zero exit cancels `mr create`.
+    #[serde(default)]
+    pub pre_mr_create: Option<String>,
     #[serde(default)]
     pub post_mr_create: Option<String>,
     #[serde(default)]
+    pub post_mr_merge: Option<String>,
+    #[serde(default)]
+    pub post_submit: Option<String>,
+    /// Runs in each dependent before its tests during `plan --verify`.
+    #[serde(default)]
+    pub pre_verify: Option<String>,
+    #[serde(default)]
     pub custom: Option<HashMap<String, String>>,
 }
 
@@ -121,6 +334,52 @@ pub struct MrConfig {
     pub require_tests: Option<bool>,
     #[serde(default)]
     pub draft: Option<bool>,
+    #[serde(default)]
+    pub title_template: Option<String>,
+    #[serde(default)]
+    pub ticket_pattern: Option<String>,
+    #[serde(default)]
+    pub squash_template: Option<String>,
+    #[serde(default)]
+    pub close_issues: Option<bool>,
+    #[serde(default)]
+    pub closing_keyword: Option<String>,
+    #[serde(default)]
+    pub issue_pattern: Option<String>,
+    #[serde(default)]
+    pub coordination: Option<CoordinationConfig>,
+    #[serde(default)]
+    pub auto_labels: Option<bool>,
+    /// Read required checks and approvals from the forge's branch settings
+    /// (default true); `[ci]` in a repo's `.harmonia.toml` overrides them.
+    #[serde(default)]
+    pub forge_requirements: Option<bool>,
+    /// Branch of the workspace repo that `mr state push` and `mr state pull`
+    /// share MR state through (default `harmonia/mr-state`).
+    #[serde(default)]
+    pub state_branch: Option<String>,
+    /// Remote holding `state_branch` (default `origin`).
+    #[serde(default)]
+    pub state_remote: Option<String>,
+    /// How `mr create` picks from team reviewers: `all` (default),
+    /// `round-robin`, or `load` (fewest open reviews on the forge first).
+    #[serde(default)]
+    pub reviewer_strategy: Option<String>,
+    /// Reviewers per MR under `round-robin` and `load` (default 1).
+    #[serde(default)]
+    pub reviewers_per_mr: Option<usize>,
+}
+
+/// Repo that holds tracking issues instead of the first changed repo. It can
+/// live on a different forge than the workspace repos; `mr status` mirrors MR
+/// state into the issues opened there.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CoordinationConfig {
+    #[serde(default)]
+    pub repo: String,
+    /// Forge of `repo` when it differs from `[forge]`.
+    #[serde(default)]
+    pub forge: Option<ForgeConfig>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -133,6 +392,13 @@ pub struct VersioningConfig {
     pub calver_format: Option<String>,
     #[serde(default)]
     pub cascade_bumps: Option<bool>,
+    /// Tag name `harmonia release` creates; `{version}` and `{repo}` are
+    /// replaced. Defaults to `v{version}`.
+    #[serde(default)]
+    pub tag_format: Option<String>,
+    /// Sign `harmonia release` tags with `git tag -s` instead of `-a`.
+    #[serde(default)]
+    pub sign_tags: Option<bool>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -143,6 +409,133 @@ pub struct ChangesetsConfig {
     pub dir: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BranchingConfig {
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+}
+
+/// Repos a team owns, used by `--team` / `team:<name>` selection and status
+/// and plan annotations. `reviewers` become the default MR reviewers for
+/// those repos.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TeamConfig {
+    #[serde(default)]
+    pub repos: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+}
+
+/// A build system without a built-in plugin, used by repos whose
+/// `ecosystem` is the table name. Versions and dependencies are read and
+/// updated with a regex or a dotted path into a TOML, JSON, or YAML file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+pub struct EcosystemConfig {
+    /// Manifest file names, relative to the repo root. The first one present
+    /// is the default version and dependency file.
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub version_file: Option<String>,
+    /// Regex whose first capture group is the version.
+    #[serde(default)]
+    pub version_pattern: Option<String>,
+    /// Dotted path to the version, for example `project.version`.
+    #[serde(default)]
+    pub version_path: Option<String>,
+    #[serde(default)]
+    pub dependency_file: Option<String>,
+    /// Regex matched once per dependency, with `name` and `constraint`
+    /// capture groups.
+    #[serde(default)]
+    pub dependency_pattern: Option<String>,
+    /// Dotted path to a table of dependency name to constraint string.
+    #[serde(default)]
+    pub dependency_path: Option<String>,
+    /// Commands by name: `test`, `lint`, and `clean` replace the ecosystem
+    /// defaults, and any name can be run with `harmonia run <name>`.
+    #[serde(default)]
+    pub commands: std::collections::BTreeMap<String, String>,
+}
+
+/// Shared files that `harmonia propagate` keeps in sync across repos.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PropagateConfig {
+    /// Template directory, relative to the workspace root
+    /// (default `.harmonia/propagate`).
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Extra variables available to `.tera` templates as `vars.<name>`.
+    #[serde(default)]
+    pub vars: HashMap<String, String>,
+}
+
+/// Confirmation policy per dangerous operation: `confirm` (a y/N prompt that
+/// `--yes` skips), `type-name` (type each repo name, not skippable), or `none`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SafetyConfig {
+    #[serde(default)]
+    pub force_push: Option<String>,
+    #[serde(default)]
+    pub force_branch: Option<String>,
+    #[serde(default)]
+    pub amend: Option<String>,
+    #[serde(default)]
+    pub mr_merge: Option<String>,
+    #[serde(default)]
+    pub mr_close: Option<String>,
+    /// Target branch patterns (`*` wildcards) whose MRs only merge with
+    /// `--yes --confirm-release`.
+    #[serde(default)]
+    pub release_branches: Option<Vec<String>>,
+    /// Scan staged changes for likely credentials before `commit`/`submit`
+    /// (default: true).
+    #[serde(default)]
+    pub secret_scan: Option<bool>,
+    /// Regexes for secret scan findings to ignore, matched against the file
+    /// path and the matched text.
+    #[serde(default)]
+    pub secret_allowlist: Option<Vec<String>>,
+}
+
+/// Forge webhooks received by `harmonia webhook serve`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhookConfig {
+    /// Address to listen on (default `127.0.0.1:8787`).
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Shared secret GitHub signs deliveries with, or GitLab's secret token.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Environment variable holding the secret, preferred over `secret`.
+    #[serde(default)]
+    pub secret_env: Option<String>,
+    /// Events to act on, as `kind`, `kind.action`, `kind.*`, or `*`. Empty
+    /// allows every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Shell command to run in the workspace root per event filter.
+    #[serde(default)]
+    pub actions: std::collections::BTreeMap<String, String>,
+}
+
+impl WebhookConfig {
+    pub fn resolve_secret(&self) -> Option<String> {
+        self.secret_env
+            .as_deref()
+            .and_then(|name| std::env::var(name).ok())
+            .or_else(|| self.secret.clone())
+            .map(|secret| secret.trim().to_string())
+            .filter(|secret| !secret.is_empty())
+    }
+}
+
 fn default_repos_dir() -> String {
     "repos".to_string()
 }
@@ -41,6 +41,10 @@ pub struct ChangesetFile {
     pub branch: String,
     #[serde(default)]
     pub repos: Vec<ChangesetRepoSummary>,
+    /// Issue references (`#12`, `api#12`, `group/project#12`) the changeset's
+    /// MRs close when `[mr].close_issues` is enabled.
+    #[serde(default)]
+    pub closes: Vec<String>,
     #[serde(skip)]
     pub path: PathBuf,
 }
@@ -178,6 +182,7 @@ mod tests {
                 repo: "app".to_string(),
                 summary: String::new(),
             }],
+            closes: Vec::new(),
             path: std::path::PathBuf::new(),
         }];
 
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::error::{HarmoniaError, Result};
+
+/// Line coverage normalized from an lcov, Cobertura (`coverage.xml`), or Go
+/// cover profile report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LineCoverage {
+    pub covered: u64,
+    pub total: u64,
+}
+
+impl LineCoverage {
+    /// Covered share in percent; `None` when the report has no lines.
+    pub fn percent(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.covered as f64 * 100.0 / self.total as f64)
+    }
+
+    pub fn add(&mut self, other: LineCoverage) {
+        self.covered += other.covered;
+        self.total += other.total;
+    }
+}
+
+/// One repo's row in the workspace coverage summary.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RepoCoverage {
+    pub repo: String,
+    /// Report the numbers were read from, or `None` when none was found.
+    pub report: Option<PathBuf>,
+    #[serde(flatten)]
+    pub lines: LineCoverage,
+    pub percent: Option<f64>,
+}
+
+/// Combined coverage of every repo that produced a report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageSummary {
+    pub repos: Vec<RepoCoverage>,
+    #[serde(flatten)]
+    pub lines: LineCoverage,
+    pub percent: Option<f64>,
+}
+
+impl CoverageSummary {
+    pub fn new(repos: Vec<RepoCoverage>) -> Self {
+        let mut lines = LineCoverage::default();
+        for repo in repos.iter().filter(|repo| repo.report.is_some()) {
+            lines.add(repo.lines);
+        }
+        Self {
+            repos,
+            lines,
+            percent: lines.percent(),
+        }
+    }
+}
+
+/// Where the combined HTML index is written.
+pub fn coverage_index_path(workspace_root: &Path) -> PathBuf {
+    workspace_root
+        .join(".harmonia")
+        .join("coverage")
+        .join("index.html")
+}
+
+/// Reads a coverage report, picking the parser from its content.
+pub fn load_coverage_report(path: &Path) -> Result<LineCoverage> {
+    let content = fs::read_to_string(path)?;
+    parse_coverage_report(&content).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{}: unrecognized coverage report format",
+            path.display()
+        )))
+    })
+}
+
+pub fn parse_coverage_report(content: &str) -> Option<LineCoverage> {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('<') {
+        parse_cobertura(content)
+    } else if trimmed.starts_with("mode:") {
+        Some(parse_go_profile(content))
+    } else if content.lines().any(|line| line.starts_with("SF:")) {
+        Some(parse_lcov(content))
+    } else {
+        None
+    }
+}
+
+/// Sums `DA:` records per source file. `LH`/`LF` totals are ignored because
+/// some generators omit them.
+pub fn parse_lcov(content: &str) -> LineCoverage {
+    let mut lines: BTreeMap<(String, u64), bool> = BTreeMap::new();
+    let mut file = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("SF:") {
+            file = name.to_string();
+        } else if let Some(record) = line.strip_prefix("DA:") {
+            let mut fields = record.split(',');
+            let (Some(number), Some(hits)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let Ok(number) = number.trim().parse::<u64>() else {
+                continue;
+            };
+            let hit = hits.trim().parse::<u64>().is_ok_and(|hits| hits > 0);
+            *lines.entry((file.clone(), number)).or_default() |= hit;
+        }
+    }
+    count_lines(lines.values())
+}
+
+/// Reads `lines-covered`/`lines-valid` from the root `<coverage>` element,
+/// falling back to counting `<line hits=...>` elements.
+pub fn parse_cobertura(content: &str) -> Option<LineCoverage> {
+    let root = Regex::new(r"<coverage\b[^>]*>")
+        .ok()?
+        .find(content)?
+        .as_str();
+    let attribute = |name: &str| -> Option<u64> {
+        let pattern = Regex::new(&format!(r#"\b{name}="(\d+)""#)).ok()?;
+        pattern.captures(root)?.get(1)?.as_str().parse().ok()
+    };
+    if let (Some(covered), Some(total)) = (attribute("lines-covered"), attribute("lines-valid")) {
+        return Some(LineCoverage { covered, total });
+    }
+
+    let line = Regex::new(r#"<line\b[^>]*\bhits="(\d+)""#).ok()?;
+    let hits: Vec<bool> = line
+        .captures_iter(content)
+        .map(|captures| captures[1] != *"0")
+        .collect();
+    Some(count_lines(hits.iter()))
+}
+
+/// Counts statements from `go test -coverprofile` blocks, which look like
+/// `file.go:10.2,12.16 3 1` (statements, then hit count). Blocks repeated
+/// across packages count once.
+pub fn parse_go_profile(content: &str) -> LineCoverage {
+    let mut blocks: BTreeMap<&str, (u64, bool)> = BTreeMap::new();
+    for line in content.lines().skip(1) {
+        let mut fields = line.rsplitn(3, ' ');
+        let (Some(count), Some(statements), Some(block)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(statements) = statements.parse::<u64>() else {
+            continue;
+        };
+        let hit = count.parse::<u64>().is_ok_and(|count| count > 0);
+        let entry = blocks.entry(block).or_insert((statements, false));
+        entry.1 |= hit;
+    }
+    let mut lines = LineCoverage::default();
+    for (statements, hit) in blocks.values() {
+        lines.total += statements;
+        if *hit {
+            lines.covered += statements;
+        }
+    }
+    lines
+}
+
+fn count_lines<'a>(hits: impl Iterator<Item = &'a bool>) -> LineCoverage {
+    let mut lines = LineCoverage::default();
+    for hit in hits {
+        lines.total += 1;
+        if *hit {
+            lines.covered += 1;
+        }
+    }
+    lines
+}
+
+/// Renders the combined summary as a standalone HTML page.
+pub fn render_coverage_index(summary: &CoverageSummary) -> String {
+    let mut rows = String::new();
+    for repo in &summary.repos {
+        let name = escape_html(&repo.repo);
+        let (percent, lines, report) = match &repo.report {
+            Some(report) => (
+                format_percent(repo.percent),
+                format!("{}/{}", repo.lines.covered, repo.lines.total),
+                escape_html(&report.display().to_string()),
+            ),
+            None => ("-".to_string(), "-".to_string(), "no report".to_string()),
+        };
+        rows.push_str(&format!(
+            "<tr><td>{name}</td><td>{percent}</td><td>{lines}</td><td>{report}</td></tr>\n"
+        ));
+    }
+    format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Workspace coverage</title>\n\
+<style>body{{font-family:sans-serif}}td,th{{padding:4px 12px;text-align:left}}</style>\n\
+</head>\n<body>\n<h1>Workspace coverage: {total}</h1>\n<table>\n\
+<tr><th>Repo</th><th>Coverage</th><th>Lines</th><th>Report</th></tr>\n{rows}\
+<tr><th>Total</th><th>{total}</th><th>{covered}/{lines}</th><th></th></tr>\n</table>\n</body>\n</html>\n",
+        total = format_percent(summary.percent),
+        covered = summary.lines.covered,
+        lines = summary.lines.total,
+    )
+}
+
+pub fn format_percent(percent: Option<f64>) -> String {
+    match percent {
+        Some(percent) => format!("{percent:.1}%"),
+        None => "-".to_string(),
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_lcov_cobertura_and_go_profiles() {
+        let lcov = "TN:\nSF:src/lib.rs\nDA:1,3\nDA:2,0\nDA:3,1\nend_of_record\nSF:src/main.rs\nDA:1,0\nend_of_record\n";
+        assert_eq!(
+            parse_coverage_report(lcov),
+            Some(LineCoverage {
+                covered: 2,
+                total: 4
+            })
+        );
+
+        let cobertura = r#"<?xml version="1.0" ?>
+<coverage version="7.4" line-rate="0.75" lines-valid="8" lines-covered="6">
+</coverage>"#;
+        assert_eq!(
+            parse_coverage_report(cobertura),
+            Some(LineCoverage {
+                covered: 6,
+                total: 8
+            })
+        );
+        let counted = r#"<coverage line-rate="0.5"><lines><line number="1" hits="2"/><line number="2" hits="0"/></lines></coverage>"#;
+        assert_eq!(
+            parse_coverage_report(counted),
+            Some(LineCoverage {
+                covered: 1,
+                total: 2
+            })
+        );
+
+        let go = "mode: set\nexample.com/a/a.go:3.14,5.2 2 1\nexample.com/a/a.go:7.14,9.2 3 0\nexample.com/a/a.go:3.14,5.2 2 0\n";
+        assert_eq!(
+            parse_coverage_report(go),
+            Some(LineCoverage {
+                covered: 2,
+                total: 5
+            })
+        );
+        assert_eq!(parse_coverage_report("plain text"), None);
+    }
+
+    #[test]
+    fn summary_totals_only_repos_with_reports() {
+        let summary = CoverageSummary::new(vec![
+            RepoCoverage {
+                repo: "api".to_string(),
+                report: Some(PathBuf::from("coverage.xml")),
+                lines: LineCoverage {
+                    covered: 30,
+                    total: 40,
+                },
+                percent: Some(75.0),
+            },
+            RepoCoverage {
+                repo: "web".to_string(),
+                report: None,
+                lines: LineCoverage::default(),
+                percent: None,
+            },
+        ]);
+        assert_eq!(summary.percent, Some(75.0));
+        let html = render_coverage_index(&summary);
+        assert!(html.contains("<td>api</td><td>75.0%</td><td>30/40</td><td>coverage.xml</td>"));
+        assert!(html.contains("<td>web</td><td>-</td><td>-</td><td>no report</td>"));
+    }
+}
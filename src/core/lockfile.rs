@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HarmoniaError, Result};
+
+/// Pinned commits written by `harmonia lock` and read by `lock --restore`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub repos: BTreeMap<String, LockedRepo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedRepo {
+    pub commit: String,
+    /// Branch checked out when the lock was taken; empty for a detached HEAD.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub branch: String,
+}
+
+pub fn lockfile_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".harmonia").join("lockfile.toml")
+}
+
+pub fn load_lockfile(workspace_root: &Path) -> Result<Lockfile> {
+    let path = lockfile_path(workspace_root);
+    if !path.is_file() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "no lockfile at {}; create one with `harmonia lock`",
+            path.display()
+        ))));
+    }
+    let raw = fs::read_to_string(&path)?;
+    parse_lockfile(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {}",
+            path.display(),
+            err
+        )))
+    })
+}
+
+pub fn save_lockfile(workspace_root: &Path, lockfile: &Lockfile) -> Result<PathBuf> {
+    let path = lockfile_path(workspace_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, render_lockfile(lockfile)?)?;
+    Ok(path)
+}
+
+fn parse_lockfile(raw: &str) -> std::result::Result<Lockfile, toml::de::Error> {
+    toml::from_str(raw)
+}
+
+fn render_lockfile(lockfile: &Lockfile) -> Result<String> {
+    let body = toml::to_string_pretty(lockfile)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    Ok(format!(
+        "# Generated by `harmonia lock`; restore with `harmonia lock --restore`.\n{body}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockfile_round_trips_through_toml() {
+        let mut lockfile = Lockfile {
+            created_at: 1_700_000_000,
+            ..Lockfile::default()
+        };
+        lockfile.repos.insert(
+            "api".to_string(),
+            LockedRepo {
+                commit: "0123abcd".to_string(),
+                branch: "main".to_string(),
+            },
+        );
+        lockfile.repos.insert(
+            "core".to_string(),
+            LockedRepo {
+                commit: "4567ef01".to_string(),
+                branch: String::new(),
+            },
+        );
+
+        let rendered = render_lockfile(&lockfile).expect("render");
+        assert!(rendered.starts_with("# Generated by `harmonia lock`"));
+        assert!(rendered.contains("[repos.api]"));
+        assert!(!rendered.contains("branch = \"\""));
+        assert_eq!(parse_lockfile(&rendered).expect("parse"), lockfile);
+    }
+}
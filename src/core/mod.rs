@@ -1,5 +1,15 @@
 pub mod changeset;
+pub mod coverage;
+pub mod lockfile;
 pub mod repo;
+pub mod secrets;
+pub mod select;
+pub mod selection;
+pub mod snapshot;
+pub mod state;
+pub mod stats;
+pub mod teams;
+pub mod toolchain;
 pub mod version;
 pub mod workspace;
 
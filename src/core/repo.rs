@@ -5,16 +5,28 @@ use crate::core::version::VersionReq;
 use crate::ecosystem::EcosystemId;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct RepoId(String);
+pub struct RepoId(String, Option<String>);
 
 impl RepoId {
     pub fn new(id: impl Into<String>) -> Self {
-        Self(id.into())
+        Self(id.into(), None)
+    }
+
+    /// A forge project on the `[forge.hosts]` entry `host`, so that the same
+    /// project path on two hosts stays distinct.
+    pub fn on_host(id: impl Into<String>, host: impl Into<String>) -> Self {
+        Self(id.into(), Some(host.into()))
     }
 
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// The `[forge.hosts]` entry a forge project lives on; `None` for the
+    /// default `[forge]` and for workspace repo ids.
+    pub fn host(&self) -> Option<&str> {
+        self.1.as_deref()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,7 +40,16 @@ pub struct Repo {
     pub ecosystem: Option<EcosystemId>,
     pub config: Option<RepoConfig>,
     pub external: bool,
+    /// Also true while the repo is paused.
     pub ignored: bool,
+    pub paused: Option<RepoPause>,
+}
+
+/// An active `repo pause`, kept so that status can say why a repo is skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoPause {
+    pub until: Option<String>,
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Clone)]
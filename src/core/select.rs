@@ -0,0 +1,491 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::repo::{Repo, RepoId};
+use crate::error::{HarmoniaError, Result};
+use crate::graph::ops::{package_map, transitive_dependencies, transitive_dependents};
+use crate::graph::DependencyGraph;
+
+/// A parsed `--select` expression.
+///
+/// Operators, loosest first: `|` (union), `&` and `-` (intersection and
+/// difference), `!` (every repo not matched). Atoms are `all`, `changed`,
+/// `external`, `group:<name>`, `team:<name>`, `ecosystem:<id>`, `repo:<name>`,
+/// or a bare repo or package name. `deps(<expr>)` and `dependents(<expr>)` expand to the
+/// transitive dependencies or dependents of the inner selection, without it.
+/// A difference needs whitespace before `-` so names like `core-lib` parse as
+/// one name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectExpr {
+    All,
+    Changed,
+    External,
+    Group(String),
+    Team(String),
+    Ecosystem(String),
+    Repo(String),
+    Not(Box<SelectExpr>),
+    And(Box<SelectExpr>, Box<SelectExpr>),
+    Or(Box<SelectExpr>, Box<SelectExpr>),
+    Minus(Box<SelectExpr>, Box<SelectExpr>),
+    Deps(Box<SelectExpr>),
+    Dependents(Box<SelectExpr>),
+}
+
+/// Workspace data a selection is evaluated against. `changed` only needs to
+/// be filled in when [`SelectExpr::uses_changed`] is true.
+pub struct SelectContext<'a> {
+    pub repos: &'a HashMap<RepoId, Repo>,
+    pub graph: &'a DependencyGraph,
+    pub groups: &'a HashMap<String, Vec<String>>,
+    /// Repos owned by each team, with team groups already expanded.
+    pub teams: &'a HashMap<String, Vec<String>>,
+    pub changed: &'a HashSet<RepoId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    And,
+    Or,
+    Minus,
+    Not,
+    Open,
+    Close,
+}
+
+impl SelectExpr {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(select_error(input, "empty expression"));
+        }
+        let mut parser = Parser {
+            input,
+            tokens,
+            pos: 0,
+        };
+        let expr = parser.union()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(select_error(
+                input,
+                &format!("unexpected {}", describe(token)),
+            ));
+        }
+        Ok(expr)
+    }
+
+    pub fn uses_changed(&self) -> bool {
+        match self {
+            SelectExpr::Changed => true,
+            SelectExpr::Not(inner) | SelectExpr::Deps(inner) | SelectExpr::Dependents(inner) => {
+                inner.uses_changed()
+            }
+            SelectExpr::And(left, right)
+            | SelectExpr::Or(left, right)
+            | SelectExpr::Minus(left, right) => left.uses_changed() || right.uses_changed(),
+            _ => false,
+        }
+    }
+
+    /// Repos matched by the expression. Ignored repos never match.
+    pub fn evaluate(&self, ctx: &SelectContext) -> Result<HashSet<RepoId>> {
+        let selectable = |repo: &&Repo| !repo.ignored;
+        let selected = match self {
+            SelectExpr::All => ctx
+                .repos
+                .values()
+                .filter(selectable)
+                .map(|repo| repo.id.clone())
+                .collect(),
+            SelectExpr::Changed => ctx.changed.clone(),
+            SelectExpr::External => ctx
+                .repos
+                .values()
+                .filter(|repo| repo.external)
+                .map(|repo| repo.id.clone())
+                .collect(),
+            SelectExpr::Group(name) => {
+                let members = ctx.groups.get(name).ok_or_else(|| {
+                    HarmoniaError::Other(anyhow::anyhow!(format!("unknown group {}", name)))
+                })?;
+                members
+                    .iter()
+                    .map(|member| RepoId::new(member.clone()))
+                    .filter(|id| ctx.repos.contains_key(id))
+                    .collect()
+            }
+            SelectExpr::Team(name) => {
+                let members = ctx.teams.get(name).ok_or_else(|| {
+                    HarmoniaError::Other(anyhow::anyhow!(format!("unknown team {}", name)))
+                })?;
+                members
+                    .iter()
+                    .map(|member| RepoId::new(member.clone()))
+                    .filter(|id| ctx.repos.contains_key(id))
+                    .collect()
+            }
+            SelectExpr::Ecosystem(id) => ctx
+                .repos
+                .values()
+                .filter(|repo| {
+                    repo.ecosystem
+                        .as_ref()
+                        .is_some_and(|ecosystem| ecosystem.as_str().eq_ignore_ascii_case(id))
+                })
+                .map(|repo| repo.id.clone())
+                .collect(),
+            SelectExpr::Repo(name) => HashSet::from([resolve_repo(ctx, name)?]),
+            SelectExpr::Not(inner) => {
+                let excluded = inner.evaluate(ctx)?;
+                ctx.repos
+                    .values()
+                    .filter(selectable)
+                    .map(|repo| repo.id.clone())
+                    .filter(|id| !excluded.contains(id))
+                    .collect()
+            }
+            SelectExpr::And(left, right) => {
+                let right = right.evaluate(ctx)?;
+                left.evaluate(ctx)?
+                    .into_iter()
+                    .filter(|id| right.contains(id))
+                    .collect()
+            }
+            SelectExpr::Or(left, right) => {
+                let mut selected = left.evaluate(ctx)?;
+                selected.extend(right.evaluate(ctx)?);
+                selected
+            }
+            SelectExpr::Minus(left, right) => {
+                let right = right.evaluate(ctx)?;
+                left.evaluate(ctx)?
+                    .into_iter()
+                    .filter(|id| !right.contains(id))
+                    .collect()
+            }
+            SelectExpr::Deps(inner) => inner
+                .evaluate(ctx)?
+                .iter()
+                .flat_map(|id| transitive_dependencies(ctx.graph, ctx.repos, id))
+                .collect(),
+            SelectExpr::Dependents(inner) => inner
+                .evaluate(ctx)?
+                .iter()
+                .flat_map(|id| transitive_dependents(ctx.graph, ctx.repos, id))
+                .collect(),
+        };
+        Ok(selected
+            .into_iter()
+            .filter(|id| ctx.repos.get(id).is_some_and(|repo| !repo.ignored))
+            .collect())
+    }
+}
+
+/// A repo id, or the repo that provides the package `name`.
+fn resolve_repo(ctx: &SelectContext, name: &str) -> Result<RepoId> {
+    let id = RepoId::new(name.to_string());
+    if ctx.repos.contains_key(&id) {
+        return Ok(id);
+    }
+    package_map(ctx.repos).remove(name).ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "unknown repo or package '{}' in --select",
+            name
+        )))
+    })
+}
+
+fn select_error(input: &str, message: &str) -> HarmoniaError {
+    HarmoniaError::Other(anyhow::anyhow!(format!(
+        "invalid --select expression '{}': {}",
+        input, message
+    )))
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{word}'"),
+        Token::And => "'&'".to_string(),
+        Token::Or => "'|'".to_string(),
+        Token::Minus => "'-'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let is_word_char =
+        |ch: char| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | ':' | '/' | '-' | '@');
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        let token = match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '&' => Token::And,
+            '|' => Token::Or,
+            '-' => Token::Minus,
+            '!' => Token::Not,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ if is_word_char(ch) => {
+                let mut word = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !is_word_char(next) {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+                continue;
+            }
+            other => return Err(select_error(input, &format!("unexpected '{other}'"))),
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn union(&mut self) -> Result<SelectExpr> {
+        let mut expr = self.intersection()?;
+        while self.eat(&Token::Or) {
+            expr = SelectExpr::Or(Box::new(expr), Box::new(self.intersection()?));
+        }
+        Ok(expr)
+    }
+
+    fn intersection(&mut self) -> Result<SelectExpr> {
+        let mut expr = self.unary()?;
+        loop {
+            if self.eat(&Token::And) {
+                expr = SelectExpr::And(Box::new(expr), Box::new(self.unary()?));
+            } else if self.eat(&Token::Minus) {
+                expr = SelectExpr::Minus(Box::new(expr), Box::new(self.unary()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<SelectExpr> {
+        if self.eat(&Token::Not) {
+            return Ok(SelectExpr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.union()?;
+            self.expect_close()?;
+            return Ok(expr);
+        }
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                self.atom(&word)
+            }
+            Some(token) => Err(select_error(
+                self.input,
+                &format!("unexpected {}", describe(&token)),
+            )),
+            None => Err(select_error(self.input, "unexpected end of expression")),
+        }
+    }
+
+    fn atom(&mut self, word: &str) -> Result<SelectExpr> {
+        if matches!(word, "deps" | "dependents") && self.eat(&Token::Open) {
+            let inner = Box::new(self.union()?);
+            self.expect_close()?;
+            return Ok(if word == "deps" {
+                SelectExpr::Deps(inner)
+            } else {
+                SelectExpr::Dependents(inner)
+            });
+        }
+        if let Some((kind, value)) = word.split_once(':') {
+            if value.is_empty() {
+                return Err(select_error(
+                    self.input,
+                    &format!("missing value after '{kind}:'"),
+                ));
+            }
+            return match kind {
+                "group" => Ok(SelectExpr::Group(value.to_string())),
+                "team" => Ok(SelectExpr::Team(value.to_string())),
+                "ecosystem" => Ok(SelectExpr::Ecosystem(value.to_string())),
+                "repo" => Ok(SelectExpr::Repo(value.to_string())),
+                other => Err(select_error(
+                    self.input,
+                    &format!(
+                        "unknown selector '{other}:' (expected group:, team:, ecosystem:, or repo:)"
+                    ),
+                )),
+            };
+        }
+        Ok(match word {
+            "all" => SelectExpr::All,
+            "changed" => SelectExpr::Changed,
+            "external" => SelectExpr::External,
+            name => SelectExpr::Repo(name.to_string()),
+        })
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<()> {
+        if self.eat(&Token::Close) {
+            Ok(())
+        } else {
+            Err(select_error(self.input, "missing ')'"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::repo::Dependency;
+    use crate::core::version::VersionReq;
+    use std::path::PathBuf;
+
+    fn repo(name: &str) -> Repo {
+        Repo {
+            id: RepoId::new(name),
+            path: PathBuf::from(format!("/tmp/{name}")),
+            remote_url: String::new(),
+            default_branch: "main".to_string(),
+            package_name: Some(format!("{name}-pkg")),
+            depends_on: Vec::new(),
+            ecosystem: None,
+            config: None,
+            external: false,
+            ignored: false,
+            paused: None,
+        }
+    }
+
+    fn dep(name: &str) -> Dependency {
+        Dependency {
+            name: format!("{name}-pkg"),
+            constraint: VersionReq::new("*"),
+            is_internal: true,
+        }
+    }
+
+    fn names(selected: HashSet<RepoId>) -> Vec<String> {
+        let mut names: Vec<String> = selected
+            .into_iter()
+            .map(|id| id.as_str().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn parse_respects_precedence_and_hyphenated_names() {
+        assert_eq!(
+            SelectExpr::parse("changed & group:backend | dependents(libfoo)").expect("parse"),
+            SelectExpr::Or(
+                Box::new(SelectExpr::And(
+                    Box::new(SelectExpr::Changed),
+                    Box::new(SelectExpr::Group("backend".to_string())),
+                )),
+                Box::new(SelectExpr::Dependents(Box::new(SelectExpr::Repo(
+                    "libfoo".to_string()
+                )))),
+            )
+        );
+        assert_eq!(
+            SelectExpr::parse("core-lib - !web").expect("parse"),
+            SelectExpr::Minus(
+                Box::new(SelectExpr::Repo("core-lib".to_string())),
+                Box::new(SelectExpr::Not(Box::new(SelectExpr::Repo(
+                    "web".to_string()
+                )))),
+            )
+        );
+        assert!(SelectExpr::parse("").is_err());
+        assert!(SelectExpr::parse("deps(core").is_err());
+        assert!(SelectExpr::parse("tag:x").is_err());
+        assert!(SelectExpr::parse("core &").is_err());
+    }
+
+    #[test]
+    fn evaluate_combines_sets_and_graph_relations() {
+        let mut repos = HashMap::new();
+        for name in ["core", "lib", "app", "tools", "old"] {
+            repos.insert(RepoId::new(name), repo(name));
+        }
+        repos.get_mut(&RepoId::new("old")).expect("old").ignored = true;
+        let graph = DependencyGraph {
+            edges: HashMap::from([
+                (RepoId::new("app"), vec![dep("lib")]),
+                (RepoId::new("lib"), vec![dep("core")]),
+                (RepoId::new("tools"), Vec::new()),
+            ]),
+            ..DependencyGraph::default()
+        };
+        let groups = HashMap::from([(
+            "backend".to_string(),
+            vec!["core".to_string(), "lib".to_string(), "old".to_string()],
+        )]);
+        let teams = HashMap::from([(
+            "platform".to_string(),
+            vec!["core".to_string(), "tools".to_string()],
+        )]);
+        let changed = HashSet::from([RepoId::new("lib"), RepoId::new("tools")]);
+        let ctx = SelectContext {
+            repos: &repos,
+            graph: &graph,
+            groups: &groups,
+            teams: &teams,
+            changed: &changed,
+        };
+        let eval = |raw: &str| {
+            names(
+                SelectExpr::parse(raw)
+                    .expect("parse")
+                    .evaluate(&ctx)
+                    .expect("eval"),
+            )
+        };
+
+        assert_eq!(eval("changed & group:backend"), vec!["lib"]);
+        assert_eq!(
+            eval("changed & group:backend | dependents(core-pkg)"),
+            vec!["app", "lib"]
+        );
+        assert_eq!(eval("deps(app)"), vec!["core", "lib"]);
+        assert_eq!(eval("all - group:backend"), vec!["app", "tools"]);
+        assert_eq!(eval("!changed"), vec!["app", "core"]);
+        assert_eq!(eval("team:platform & !changed"), vec!["core"]);
+        assert!(SelectExpr::parse("team:missing")
+            .expect("parse")
+            .evaluate(&ctx)
+            .is_err());
+        assert!(SelectExpr::parse("group:missing")
+            .expect("parse")
+            .evaluate(&ctx)
+            .is_err());
+        assert!(SelectExpr::parse("changed").expect("parse").uses_changed());
+        assert!(!SelectExpr::parse("deps(app)")
+            .expect("parse")
+            .uses_changed());
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HarmoniaError, Result};
+
+/// Name used when `--save-selection` or `--selection` is given without one.
+pub const LAST_SELECTION: &str = "last";
+
+/// A repo set recorded by `--save-selection` and reused by `--selection`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSelection {
+    pub name: String,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub repos: Vec<String>,
+}
+
+pub fn selections_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".harmonia").join("selections")
+}
+
+pub fn selection_path(workspace_root: &Path, name: &str) -> Result<PathBuf> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed.starts_with('.')
+        || trimmed.contains(['/', '\\'])
+        || trimmed.contains(char::is_whitespace)
+    {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "invalid selection name '{}' (use letters, digits, '-', '_' or '.')",
+            name
+        ))));
+    }
+    Ok(selections_dir(workspace_root).join(format!("{trimmed}.json")))
+}
+
+pub fn load_selection(workspace_root: &Path, name: &str) -> Result<SavedSelection> {
+    let path = selection_path(workspace_root, name)?;
+    if !path.is_file() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "selection '{}' not found (save one with --save-selection {})",
+            name, name
+        ))));
+    }
+    let raw = fs::read_to_string(&path)?;
+    serde_json::from_str::<SavedSelection>(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {}",
+            path.display(),
+            err
+        )))
+    })
+}
+
+pub fn save_selection(workspace_root: &Path, selection: &SavedSelection) -> Result<PathBuf> {
+    let path = selection_path(workspace_root, &selection.name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(selection)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
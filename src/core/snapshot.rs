@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HarmoniaError, Result};
+
+/// Lockfiles recorded by `snapshot create --lockfiles`, relative to the repo root.
+pub const SNAPSHOT_LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "uv.lock",
+    "Pipfile.lock",
+    "go.sum",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub repos: BTreeMap<String, SnapshotRepo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotRepo {
+    pub commit: String,
+    #[serde(default)]
+    pub branch: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Lockfile path -> git blob id of its contents.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lockfiles: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotRepoDiff {
+    pub repo: String,
+    pub before: Option<SnapshotRepo>,
+    pub after: Option<SnapshotRepo>,
+}
+
+impl SnapshotRepoDiff {
+    /// Lockfiles that were added, removed, or whose contents differ.
+    pub fn changed_lockfiles(&self) -> Vec<String> {
+        let empty = BTreeMap::new();
+        let before = self
+            .before
+            .as_ref()
+            .map(|repo| &repo.lockfiles)
+            .unwrap_or(&empty);
+        let after = self
+            .after
+            .as_ref()
+            .map(|repo| &repo.lockfiles)
+            .unwrap_or(&empty);
+        let names: BTreeSet<&String> = before
+            .keys()
+            .chain(after.keys())
+            .filter(|name| before.get(*name) != after.get(*name))
+            .collect();
+        names.into_iter().cloned().collect()
+    }
+}
+
+/// Repos whose commit, version, or lockfiles differ between two snapshots,
+/// including repos present in only one of them.
+pub fn diff_snapshots(from: &Snapshot, to: &Snapshot) -> Vec<SnapshotRepoDiff> {
+    let mut names: Vec<&String> = from.repos.keys().chain(to.repos.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let before = from.repos.get(name);
+            let after = to.repos.get(name);
+            if before == after {
+                return None;
+            }
+            Some(SnapshotRepoDiff {
+                repo: name.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+pub fn snapshots_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".harmonia").join("snapshots")
+}
+
+pub fn snapshot_path(workspace_root: &Path, name: &str) -> Result<PathBuf> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed.starts_with('.')
+        || trimmed.contains(['/', '\\'])
+        || trimmed.contains(char::is_whitespace)
+    {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "invalid snapshot name '{}' (use letters, digits, '-', '_' or '.')",
+            name
+        ))));
+    }
+    Ok(snapshots_dir(workspace_root).join(format!("{trimmed}.json")))
+}
+
+pub fn load_snapshot(workspace_root: &Path, name: &str) -> Result<Snapshot> {
+    let path = snapshot_path(workspace_root, name)?;
+    if !path.is_file() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "snapshot '{}' not found (expected {})",
+            name,
+            path.display()
+        ))));
+    }
+    let raw = fs::read_to_string(&path)?;
+    serde_json::from_str::<Snapshot>(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {}",
+            path.display(),
+            err
+        )))
+    })
+}
+
+pub fn save_snapshot(workspace_root: &Path, snapshot: &Snapshot) -> Result<PathBuf> {
+    let path = snapshot_path(workspace_root, &snapshot.name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(snapshot)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+pub fn list_snapshots(workspace_root: &Path) -> Result<Vec<String>> {
+    let dir = snapshots_dir(workspace_root);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            names.push(stem.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(commit: &str, version: Option<&str>) -> SnapshotRepo {
+        SnapshotRepo {
+            commit: commit.to_string(),
+            branch: "main".to_string(),
+            version: version.map(str::to_string),
+            lockfiles: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed_repos() {
+        let mut staging = Snapshot {
+            name: "staging".to_string(),
+            ..Snapshot::default()
+        };
+        staging
+            .repos
+            .insert("api".to_string(), repo("aaa", Some("1.2.0")));
+        staging
+            .repos
+            .insert("core".to_string(), repo("bbb", Some("0.4.0")));
+        staging.repos.insert("old".to_string(), repo("ccc", None));
+
+        let mut prod = staging.clone();
+        prod.name = "prod".to_string();
+        prod.repos.remove("old");
+        prod.repos
+            .insert("api".to_string(), repo("ddd", Some("1.3.0")));
+        prod.repos.insert("web".to_string(), repo("eee", None));
+        let mut core = repo("bbb", Some("0.4.0"));
+        core.lockfiles
+            .insert("Cargo.lock".to_string(), "f00".to_string());
+        core.lockfiles
+            .insert("go.sum".to_string(), "ba5".to_string());
+        prod.repos.insert("core".to_string(), core.clone());
+        core.lockfiles
+            .insert("Cargo.lock".to_string(), "0ld".to_string());
+        staging.repos.insert("core".to_string(), core);
+
+        let diff = diff_snapshots(&staging, &prod);
+        let names: Vec<&str> = diff.iter().map(|entry| entry.repo.as_str()).collect();
+        assert_eq!(names, vec!["api", "core", "old", "web"]);
+        assert!(diff[2].after.is_none());
+        assert!(diff[3].before.is_none());
+        assert_eq!(diff[1].changed_lockfiles(), vec!["Cargo.lock".to_string()]);
+        assert!(diff_snapshots(&staging, &staging).is_empty());
+    }
+
+    #[test]
+    fn snapshot_names_cannot_escape_the_snapshot_dir() {
+        let root = Path::new("/workspace");
+        assert!(snapshot_path(root, "prod-2024.06").is_ok());
+        assert!(snapshot_path(root, "../prod").is_err());
+        assert!(snapshot_path(root, "").is_err());
+        assert!(snapshot_path(root, "two words").is_err());
+    }
+}
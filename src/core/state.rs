@@ -0,0 +1,271 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HarmoniaError, Result};
+use crate::util::hmac::{constant_time_eq, hmac_sha256, pbkdf2_sha256};
+
+/// Manifest written at the top of every `state export` bundle.
+pub const STATE_MANIFEST: &str = "HARMONIA_STATE.json";
+
+/// Environment variable holding the passphrase for encrypted bundles.
+pub const STATE_PASSPHRASE_ENV: &str = "HARMONIA_STATE_PASSPHRASE";
+
+/// Bundle layout version, bumped when the manifest changes incompatibly.
+pub const STATE_FORMAT: u32 = 1;
+
+/// Header `openssl enc` writes in front of salted ciphertext.
+const ENCRYPTED_MAGIC: &[u8] = b"Salted__";
+
+/// Header of an encrypted bundle: this magic, an HMAC-SHA256 tag, then the
+/// `openssl enc` output the tag covers. CBC alone is not authenticated, so the
+/// tag is what catches a wrong passphrase or a tampered archive.
+const SEALED_MAGIC: &[u8] = b"HMSTATE1";
+
+/// PBKDF2 rounds for the tag key, matching `openssl enc -pbkdf2`.
+const MAC_ITERATIONS: u32 = 10_000;
+
+const TAG_LEN: usize = 32;
+
+/// Directories under `.harmonia/` that are rebuilt locally and never bundled.
+const SKIPPED_STATE_DIRS: &[&str] = &["cache"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateManifest {
+    pub format: u32,
+    #[serde(default)]
+    pub created_at: u64,
+    /// Workspace root at export time, used to rewrite absolute paths in
+    /// restored state files.
+    #[serde(default)]
+    pub workspace_root: String,
+    /// Bundled files, relative to the workspace root.
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// Lists coordination state under `workspace_root`, relative to it: everything
+/// in `.harmonia/` except caches, plus `extra` paths (such as a root
+/// `.harmonia.toml` config or a changesets directory kept elsewhere in the
+/// workspace). Extra paths outside the workspace are returned separately.
+pub fn collect_state_files(
+    workspace_root: &Path,
+    extra: &[PathBuf],
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut files = Vec::new();
+    let mut outside = Vec::new();
+    let state_dir = workspace_root.join(".harmonia");
+    if state_dir.is_dir() {
+        collect_files(workspace_root, &state_dir, &mut files)?;
+    }
+    for path in extra {
+        let absolute = if path.is_absolute() {
+            path.clone()
+        } else {
+            workspace_root.join(path)
+        };
+        if !absolute.exists() {
+            continue;
+        }
+        if !absolute.starts_with(workspace_root) {
+            outside.push(absolute);
+            continue;
+        }
+        if absolute.is_dir() {
+            collect_files(workspace_root, &absolute, &mut files)?;
+        } else if let Ok(relative) = absolute.strip_prefix(workspace_root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok((files, outside))
+}
+
+fn collect_files(workspace_root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let skipped = path.parent() == Some(workspace_root.join(".harmonia").as_path())
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| SKIPPED_STATE_DIRS.contains(&name));
+            if !skipped {
+                collect_files(workspace_root, &path, files)?;
+            }
+        } else if let Ok(relative) = path.strip_prefix(workspace_root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a manifest entry stays inside the workspace it is restored to.
+pub fn validate_state_path(entry: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(entry);
+    let normal = !entry.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !normal {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "refusing to restore '{}': state paths must be relative to the workspace",
+            entry
+        ))));
+    }
+    Ok(path)
+}
+
+pub fn is_encrypted_bundle(header: &[u8]) -> bool {
+    header.starts_with(SEALED_MAGIC) || header.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Wraps `openssl enc` output in an authenticated bundle.
+pub fn seal_bundle(ciphertext: &[u8], passphrase: &str) -> Vec<u8> {
+    let tag = bundle_tag(ciphertext, passphrase);
+    let mut sealed = Vec::with_capacity(SEALED_MAGIC.len() + TAG_LEN + ciphertext.len());
+    sealed.extend_from_slice(SEALED_MAGIC);
+    sealed.extend_from_slice(&tag);
+    sealed.extend_from_slice(ciphertext);
+    sealed
+}
+
+/// Checks a sealed bundle's tag and returns the `openssl enc` output inside.
+pub fn unseal_bundle<'a>(sealed: &'a [u8], passphrase: &str) -> Result<&'a [u8]> {
+    if sealed.starts_with(ENCRYPTED_MAGIC) {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "encrypted archive has no integrity tag; export it again with this harmonia"
+        )));
+    }
+    let body = sealed
+        .strip_prefix(SEALED_MAGIC)
+        .filter(|body| body.len() > TAG_LEN)
+        .ok_or_else(corrupted_bundle)?;
+    let (tag, ciphertext) = body.split_at(TAG_LEN);
+    if !constant_time_eq(tag, &bundle_tag(ciphertext, passphrase)) {
+        return Err(corrupted_bundle());
+    }
+    Ok(ciphertext)
+}
+
+pub fn corrupted_bundle() -> HarmoniaError {
+    HarmoniaError::Other(anyhow::anyhow!(
+        "cannot decrypt state bundle: wrong passphrase or corrupted archive"
+    ))
+}
+
+/// The tag key is derived from the passphrase and the ciphertext's own salt,
+/// so it differs from the encryption key and between exports.
+fn bundle_tag(ciphertext: &[u8], passphrase: &str) -> [u8; 32] {
+    let mut salt = b"harmonia-state-mac:".to_vec();
+    salt.extend_from_slice(ciphertext.get(..16).unwrap_or(ciphertext));
+    let key = pbkdf2_sha256(passphrase.as_bytes(), &salt, MAC_ITERATIONS);
+    hmac_sha256(&key, ciphertext)
+}
+
+pub fn load_state_manifest(path: &Path) -> Result<StateManifest> {
+    if !path.is_file() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "archive is not a harmonia state bundle (missing HARMONIA_STATE.json)"
+        )));
+    }
+    let raw = fs::read_to_string(path)?;
+    let manifest = serde_json::from_str::<StateManifest>(&raw).map_err(|err| {
+        HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to parse {}: {}",
+            path.display(),
+            err
+        )))
+    })?;
+    if manifest.format > STATE_FORMAT {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "state bundle format {} is newer than this harmonia supports ({})",
+            manifest.format, STATE_FORMAT
+        ))));
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_dir(prefix: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock before epoch")
+            .as_nanos();
+        let pid = std::process::id();
+        std::env::temp_dir().join(format!("harmonia-{prefix}-{pid}-{nanos}"))
+    }
+
+    #[test]
+    fn collects_state_without_caches() {
+        let root = unique_temp_dir("state-files");
+        let state = root.join(".harmonia");
+        fs::create_dir_all(state.join("cache")).expect("create cache");
+        fs::create_dir_all(state.join("changesets")).expect("create changesets");
+        fs::create_dir_all(root.join("plans")).expect("create plans");
+        fs::write(state.join("config.toml"), "[workspace]\n").expect("write config");
+        fs::write(state.join("mr-state.json"), "{}").expect("write mr state");
+        fs::write(state.join("cache").join("manifests.json"), "{}").expect("write cache");
+        fs::write(state.join("changesets").join("auth.toml"), "").expect("write changeset");
+        fs::write(root.join("plans").join("auth.toml"), "").expect("write plan");
+
+        let (files, outside) = collect_state_files(
+            &root,
+            &[PathBuf::from("plans"), PathBuf::from("/nonexistent-state")],
+        )
+        .expect("collect");
+        let files: Vec<String> = files
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ".harmonia/changesets/auth.toml",
+                ".harmonia/config.toml",
+                ".harmonia/mr-state.json",
+                "plans/auth.toml",
+            ]
+        );
+        assert!(outside.is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn sealed_bundles_reject_wrong_passphrases_and_tampering() {
+        let ciphertext = b"Salted__12345678encrypted-bytes".to_vec();
+        let sealed = seal_bundle(&ciphertext, "hunter2");
+        assert!(is_encrypted_bundle(&sealed));
+        assert_eq!(
+            unseal_bundle(&sealed, "hunter2").expect("unseal"),
+            ciphertext.as_slice()
+        );
+
+        let wrong = unseal_bundle(&sealed, "hunter3").unwrap_err();
+        assert!(wrong
+            .to_string()
+            .contains("wrong passphrase or corrupted archive"));
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(unseal_bundle(&tampered, "hunter2").is_err());
+        assert!(unseal_bundle(&sealed[..SEALED_MAGIC.len() + 4], "hunter2").is_err());
+        assert!(unseal_bundle(&ciphertext, "hunter2").is_err());
+    }
+
+    #[test]
+    fn rejects_paths_escaping_the_workspace() {
+        assert!(validate_state_path(".harmonia/config.toml").is_ok());
+        assert!(validate_state_path("../etc/passwd").is_err());
+        assert!(validate_state_path("/etc/passwd").is_err());
+        assert!(validate_state_path(".harmonia/../../x").is_err());
+        assert!(validate_state_path("").is_err());
+    }
+}
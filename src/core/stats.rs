@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{HarmoniaError, Result};
+
+/// One command invocation recorded when `[workspace].stats` is enabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    pub timestamp: u64,
+    /// Subcommand path, e.g. `mr merge`.
+    pub command: String,
+    pub duration_ms: u64,
+    /// Repos the command selected, 0 for commands that do not select repos.
+    #[serde(default)]
+    pub repos: usize,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+/// Aggregated usage of one command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CommandStats {
+    pub command: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub total_ms: u64,
+    pub max_ms: u64,
+    pub total_repos: usize,
+    /// Failure category -> count.
+    pub failure_categories: BTreeMap<String, usize>,
+}
+
+impl CommandStats {
+    pub fn avg_ms(&self) -> u64 {
+        self.total_ms / self.runs.max(1) as u64
+    }
+
+    pub fn avg_repos(&self) -> f64 {
+        self.total_repos as f64 / self.runs.max(1) as f64
+    }
+}
+
+/// Stats are stored as one JSON record per line so that appending never
+/// rewrites earlier entries.
+pub fn stats_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".harmonia").join("stats.db")
+}
+
+pub fn append_stats(workspace_root: &Path, record: &StatsRecord) -> Result<()> {
+    let path = stats_path(workspace_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(record)
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+/// Loads recorded invocations, skipping lines that cannot be parsed (for
+/// example a record cut short by a crash).
+pub fn load_stats(workspace_root: &Path) -> Result<Vec<StatsRecord>> {
+    let path = stats_path(workspace_root);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(&path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str::<StatsRecord>(line).ok())
+        .collect())
+}
+
+/// Per-command totals, slowest overall first.
+pub fn summarize_stats(records: &[StatsRecord]) -> Vec<CommandStats> {
+    let mut by_command: BTreeMap<&str, CommandStats> = BTreeMap::new();
+    for record in records {
+        let entry = by_command
+            .entry(record.command.as_str())
+            .or_insert_with(|| CommandStats {
+                command: record.command.clone(),
+                ..CommandStats::default()
+            });
+        entry.runs += 1;
+        entry.total_ms += record.duration_ms;
+        entry.max_ms = entry.max_ms.max(record.duration_ms);
+        entry.total_repos += record.repos;
+        if !record.success {
+            entry.failures += 1;
+            let category = record
+                .failure
+                .clone()
+                .unwrap_or_else(|| "other".to_string());
+            *entry.failure_categories.entry(category).or_default() += 1;
+        }
+    }
+    let mut stats: Vec<CommandStats> = by_command.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.total_ms
+            .cmp(&a.total_ms)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    stats
+}
+
+/// Coarse failure bucket recorded for a failed command.
+pub fn failure_category(err: &HarmoniaError) -> &'static str {
+    match err {
+        HarmoniaError::Config(_) => "config",
+        HarmoniaError::Git(_) => "git",
+        HarmoniaError::Io(_) => "io",
+        HarmoniaError::Other(err) => {
+            let message = err.to_string();
+            if message.contains("workspace is read-only") {
+                "read_only"
+            } else if message.starts_with("command ") || message.contains("hook") {
+                "command"
+            } else {
+                "other"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, duration_ms: u64, failure: Option<&str>) -> StatsRecord {
+        StatsRecord {
+            timestamp: 0,
+            command: command.to_string(),
+            duration_ms,
+            repos: 2,
+            success: failure.is_none(),
+            failure: failure.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn summarizes_by_total_time() {
+        let stats = summarize_stats(&[
+            record("status", 100, None),
+            record("sync", 900, None),
+            record("sync", 300, Some("git")),
+            record("status", 200, Some("config")),
+        ]);
+        assert_eq!(
+            stats
+                .iter()
+                .map(|entry| entry.command.as_str())
+                .collect::<Vec<_>>(),
+            vec!["sync", "status"]
+        );
+        let sync = &stats[0];
+        assert_eq!((sync.runs, sync.failures), (2, 1));
+        assert_eq!((sync.avg_ms(), sync.max_ms), (600, 900));
+        assert_eq!(sync.avg_repos(), 2.0);
+        assert_eq!(sync.failure_categories.get("git"), Some(&1));
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::config::WorkspaceConfig;
+
+/// Repos owned by each `[teams]` entry, with the team's groups expanded.
+pub fn team_members(config: &WorkspaceConfig) -> HashMap<String, Vec<String>> {
+    config
+        .teams
+        .iter()
+        .map(|(name, team)| {
+            let mut members: BTreeSet<String> = team.repos.iter().cloned().collect();
+            for group in &team.groups {
+                if let Some(repos) = config
+                    .groups
+                    .as_ref()
+                    .and_then(|groups| groups.groups.get(group))
+                {
+                    members.extend(repos.iter().cloned());
+                }
+            }
+            (name.clone(), members.into_iter().collect())
+        })
+        .collect()
+}
+
+/// Teams that own `repo`, sorted by name.
+pub fn repo_teams(config: &WorkspaceConfig, repo: &str) -> Vec<String> {
+    let mut teams: Vec<String> = team_members(config)
+        .into_iter()
+        .filter(|(_, members)| members.iter().any(|member| member == repo))
+        .map(|(name, _)| name)
+        .collect();
+    teams.sort();
+    teams
+}
+
+/// Default MR reviewers for `repo`: the reviewers of every team that owns it.
+pub fn team_reviewers(config: &WorkspaceConfig, repo: &str) -> Vec<String> {
+    let reviewers: BTreeSet<String> = repo_teams(config, repo)
+        .iter()
+        .filter_map(|name| config.teams.get(name))
+        .flat_map(|team| team.reviewers.iter().cloned())
+        .collect();
+    reviewers.into_iter().collect()
+}
+
+/// How `mr create` spreads team reviewers over the MRs of a changeset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewerStrategy {
+    /// Every team reviewer on every MR.
+    All,
+    /// Rotate through the pool, starting from whoever has been given fewest.
+    RoundRobin,
+    /// Like round-robin, seeded with each reviewer's open reviews on the forge.
+    Load,
+}
+
+impl ReviewerStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "round-robin" | "roundrobin" => Some(Self::RoundRobin),
+            "load" => Some(Self::Load),
+            _ => None,
+        }
+    }
+}
+
+/// Hands out reviewers from per-repo pools, always picking the least-loaded
+/// members so one person doesn't end up on every MR.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerBalancer {
+    per_mr: usize,
+    load: HashMap<String, usize>,
+}
+
+impl ReviewerBalancer {
+    pub fn new(per_mr: usize) -> Self {
+        Self {
+            per_mr: per_mr.max(1),
+            load: HashMap::new(),
+        }
+    }
+
+    /// Whether `reviewer` already has a starting load.
+    pub fn knows(&self, reviewer: &str) -> bool {
+        self.load.contains_key(reviewer)
+    }
+
+    /// Seeds `reviewer` with reviews they already have open.
+    pub fn set_load(&mut self, reviewer: &str, open: usize) {
+        self.load.insert(reviewer.to_string(), open);
+    }
+
+    /// Picks `per_mr` reviewers from `pool` with the lowest load, ties going
+    /// to pool order, and counts the new assignment against them.
+    pub fn assign(&mut self, pool: &[String]) -> Vec<String> {
+        let mut candidates: Vec<&String> = pool.iter().collect();
+        candidates.dedup();
+        candidates.sort_by_key(|reviewer| self.load.get(reviewer.as_str()).copied().unwrap_or(0));
+        let picked: Vec<String> = candidates.into_iter().take(self.per_mr).cloned().collect();
+        for reviewer in &picked {
+            *self.load.entry(reviewer.clone()).or_default() += 1;
+        }
+        picked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_groups_and_collects_reviewers() {
+        let config: WorkspaceConfig = toml::from_str(
+            r#"
+[groups]
+billing = ["invoices", "ledger"]
+
+[teams.payments]
+repos = ["checkout"]
+groups = ["billing"]
+reviewers = ["alice", "bob"]
+
+[teams.platform]
+repos = ["ledger", "infra"]
+reviewers = ["bob", "carol"]
+"#,
+        )
+        .expect("parse config");
+
+        assert_eq!(
+            team_members(&config).get("payments"),
+            Some(&vec![
+                "checkout".to_string(),
+                "invoices".to_string(),
+                "ledger".to_string()
+            ])
+        );
+        assert_eq!(repo_teams(&config, "ledger"), vec!["payments", "platform"]);
+        assert!(repo_teams(&config, "docs").is_empty());
+        assert_eq!(
+            team_reviewers(&config, "ledger"),
+            vec!["alice", "bob", "carol"]
+        );
+        assert_eq!(team_reviewers(&config, "infra"), vec!["bob", "carol"]);
+    }
+
+    #[test]
+    fn balancer_spreads_pool_by_load() {
+        let pool: Vec<String> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut round_robin = ReviewerBalancer::new(1);
+        let picks: Vec<Vec<String>> = (0..4).map(|_| round_robin.assign(&pool)).collect();
+        assert_eq!(
+            picks,
+            vec![vec!["alice"], vec!["bob"], vec!["carol"], vec!["alice"]]
+        );
+
+        let mut by_load = ReviewerBalancer::new(2);
+        by_load.set_load("alice", 2);
+        by_load.set_load("bob", 1);
+        assert!(by_load.knows("bob") && !by_load.knows("carol"));
+        assert_eq!(by_load.assign(&pool), vec!["carol", "bob"]);
+        assert_eq!(by_load.assign(&pool), vec!["carol", "alice"]);
+        assert_eq!(by_load.assign(&pool), vec!["bob", "carol"]);
+
+        assert_eq!(
+            ReviewerStrategy::parse("Round-Robin"),
+            Some(ReviewerStrategy::RoundRobin)
+        );
+        assert_eq!(ReviewerStrategy::parse("random"), None);
+    }
+}
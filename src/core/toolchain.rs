@@ -0,0 +1,127 @@
+//! Developer tools that repos need, and the versions they ask for, for
+//! `harmonia setup`.
+
+use crate::config::ToolchainConfig;
+use crate::ecosystem::EcosystemId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Rust,
+    Node,
+    Python,
+    Go,
+}
+
+impl Tool {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tool::Rust => "rust",
+            Tool::Node => "node",
+            Tool::Python => "python",
+            Tool::Go => "go",
+        }
+    }
+
+    /// Tool a repo of `ecosystem` builds with, if setup knows how to check it.
+    pub fn for_ecosystem(ecosystem: &EcosystemId) -> Option<Self> {
+        match ecosystem {
+            EcosystemId::Rust => Some(Tool::Rust),
+            EcosystemId::Node => Some(Tool::Node),
+            EcosystemId::Python => Some(Tool::Python),
+            EcosystemId::Go => Some(Tool::Go),
+            EcosystemId::Php | EcosystemId::Java | EcosystemId::Custom(_) => None,
+        }
+    }
+
+    fn configured(self, config: &ToolchainConfig) -> Option<&String> {
+        match self {
+            Tool::Rust => config.rust.as_ref(),
+            Tool::Node => config.node.as_ref(),
+            Tool::Python => config.python.as_ref(),
+            Tool::Go => config.go.as_ref(),
+        }
+    }
+}
+
+/// Version of `tool` a repo asks for: its own `[toolchain]` entry, then the
+/// workspace's.
+pub fn required_version(
+    tool: Tool,
+    repo: Option<&ToolchainConfig>,
+    workspace: Option<&ToolchainConfig>,
+) -> Option<String> {
+    repo.and_then(|config| tool.configured(config))
+        .or_else(|| workspace.and_then(|config| tool.configured(config)))
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
+/// First dotted version number in a tool's `--version` output, such as
+/// `1.82.0` from `rustc 1.82.0 (f6e511eec 2024-10-15)` or `1.22.3` from
+/// `go version go1.22.3 linux/amd64`.
+pub fn parse_tool_version(output: &str) -> Option<String> {
+    let pattern = regex::Regex::new(r"\d+(\.\d+)+").ok()?;
+    pattern.find(output).map(|found| found.as_str().to_string())
+}
+
+/// Whether installed version `found` satisfies `required`, compared per
+/// component: `20` accepts `20.11.1` and `3.12` accepts `3.12.4` but not
+/// `3.1.2`. Channel names such as `stable` accept any version.
+pub fn version_satisfies(required: &str, found: &str) -> bool {
+    let required = required.trim().trim_start_matches('v');
+    if !required.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return true;
+    }
+    let found: Vec<&str> = found.trim().trim_start_matches('v').split('.').collect();
+    required
+        .split('.')
+        .enumerate()
+        .all(|(index, part)| found.get(index) == Some(&part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_required_versions_by_component() {
+        assert_eq!(
+            parse_tool_version("rustc 1.82.0 (f6e511eec 2024-10-15)").as_deref(),
+            Some("1.82.0")
+        );
+        assert_eq!(parse_tool_version("v20.11.1").as_deref(), Some("20.11.1"));
+        assert_eq!(
+            parse_tool_version("go version go1.22.3 linux/amd64").as_deref(),
+            Some("1.22.3")
+        );
+        assert_eq!(parse_tool_version("command not found"), None);
+
+        assert!(version_satisfies("20", "20.11.1"));
+        assert!(version_satisfies("3.12", "3.12.4"));
+        assert!(!version_satisfies("3.1", "3.12.4"));
+        assert!(!version_satisfies("1.82.0", "1.81.0"));
+        assert!(version_satisfies("stable", "1.82.0"));
+
+        let workspace = ToolchainConfig {
+            node: Some("20".to_string()),
+            python: Some("3.12".to_string()),
+            ..ToolchainConfig::default()
+        };
+        let repo = ToolchainConfig {
+            node: Some("22".to_string()),
+            ..ToolchainConfig::default()
+        };
+        assert_eq!(
+            required_version(Tool::Node, Some(&repo), Some(&workspace)).as_deref(),
+            Some("22")
+        );
+        assert_eq!(
+            required_version(Tool::Python, Some(&repo), Some(&workspace)).as_deref(),
+            Some("3.12")
+        );
+        assert_eq!(
+            required_version(Tool::Go, Some(&repo), Some(&workspace)),
+            None
+        );
+    }
+}
@@ -1,13 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::{Path, PathBuf};
 
-use crate::config::resolve::{load_repo_config, load_workspace_config, resolve_workspace};
-use crate::config::{ConfigError, WorkspaceConfig};
-use crate::core::repo::{Repo, RepoId};
+use crate::config::resolve::{
+    apply_env_overrides, load_repo_config, load_workspace_config, resolve_workspace,
+};
+use crate::config::{ConfigError, EcosystemConfig, RepoEntry, WorkspaceConfig};
+use crate::core::repo::{Repo, RepoId, RepoPause};
+use crate::core::teams::ReviewerStrategy;
+use crate::core::version::civil_from_days;
+use crate::ecosystem::cache::ManifestCache;
+use crate::ecosystem::custom::register_custom_ecosystems;
 use crate::ecosystem::EcosystemId;
-use crate::graph::builder::build_graph;
-use crate::graph::DependencyGraph;
+use crate::graph::builder::build_graph_with_cache;
+use crate::graph::{DependencyGraph, ExternalPackage};
+use crate::util::remote_url::forge_clone_url;
 
 #[derive(Debug)]
 pub struct Workspace {
@@ -27,9 +34,26 @@ impl Workspace {
         let mut config = load_workspace_config(&config_path)?;
         apply_env_overrides(&mut config);
         validate_workspace_config(&config)?;
+        register_custom_ecosystems(&config.ecosystems);
 
         let repos = build_repos(&root, &config)?;
-        let graph = build_graph(&repos).unwrap_or_else(|_| DependencyGraph::new());
+        let mut cache = ManifestCache::load(&root);
+        let mut graph =
+            build_graph_with_cache(&repos, &mut cache).unwrap_or_else(|_| DependencyGraph::new());
+        cache.save();
+        graph.external = config
+            .external_packages
+            .iter()
+            .map(|(name, entry)| {
+                (
+                    name.clone(),
+                    ExternalPackage {
+                        name: name.clone(),
+                        version_url: entry.version_url.clone(),
+                    },
+                )
+            })
+            .collect();
 
         Ok(Self {
             root,
@@ -38,15 +62,72 @@ impl Workspace {
             graph,
         })
     }
+
+    /// Environment variables injected into commands run for `repo`, or for the
+    /// workspace root when `repo` is `None`. Repo values override workspace values.
+    pub fn command_env(&self, repo: Option<&Repo>) -> Result<Vec<(String, String)>, ConfigError> {
+        resolve_command_env(&self.root, &self.config, repo)
+    }
 }
 
-fn apply_env_overrides(config: &mut WorkspaceConfig) {
-    if let Ok(repos_dir) = env::var("HARMONIA_REPOS_DIR") {
-        config.workspace.repos_dir = repos_dir;
+fn resolve_command_env(
+    root: &Path,
+    config: &WorkspaceConfig,
+    repo: Option<&Repo>,
+) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut merged: BTreeMap<String, String> = BTreeMap::new();
+    if let Some(vars) = config.env.as_ref() {
+        merged.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    if let Some(vars) = repo
+        .and_then(|repo| repo.config.as_ref())
+        .and_then(|cfg| cfg.env.as_ref())
+    {
+        merged.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
     }
+
+    let repo_name = repo.map(|repo| repo.id.as_str()).unwrap_or("");
+    let workspace_root = root.to_string_lossy();
+    merged
+        .into_iter()
+        .map(|(key, value)| {
+            let expanded = expand_env_value(&key, &value, repo_name, &workspace_root)?;
+            Ok((key, expanded))
+        })
+        .collect()
+}
+
+fn expand_env_value(
+    key: &str,
+    value: &str,
+    repo: &str,
+    workspace_root: &str,
+) -> Result<String, ConfigError> {
+    if let Some(source) = value.strip_prefix("env:") {
+        let source = source.trim();
+        return env::var(source).map_err(|_| {
+            ConfigError::Validation(format!(
+                "env.{} references unset environment variable '{}'",
+                key, source
+            ))
+        });
+    }
+    Ok(value
+        .replace("{repo}", repo)
+        .replace("{workspace_root}", workspace_root))
+}
+
+fn has_invalid_env_key(vars: &HashMap<String, String>) -> bool {
+    vars.keys()
+        .any(|key| key.trim().is_empty() || key.contains('='))
 }
 
-fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError> {
+/// Keywords both GitHub and GitLab recognize for closing issues from an MR.
+const CLOSING_KEYWORDS: &[&str] = &[
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+pub fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError> {
     if let Some(defaults) = config.defaults.as_ref() {
         if let Some(protocol) = defaults.clone_protocol.as_deref() {
             let protocol = protocol.trim().to_ascii_lowercase();
@@ -57,6 +138,73 @@ fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError
                 )));
             }
         }
+        if defaults.network_parallel == Some(0) {
+            return Err(ConfigError::Validation(
+                "defaults.network_parallel must be at least 1".to_string(),
+            ));
+        }
+    }
+
+    let mut team_names: Vec<&String> = config.teams.keys().collect();
+    team_names.sort();
+    for name in team_names {
+        let team = &config.teams[name];
+        for repo in &team.repos {
+            if !config.repos.contains_key(repo) {
+                return Err(ConfigError::Validation(format!(
+                    "teams.{}.repos references unknown repo '{}'",
+                    name, repo
+                )));
+            }
+        }
+        for group in &team.groups {
+            let known = config
+                .groups
+                .as_ref()
+                .is_some_and(|groups| groups.groups.contains_key(group));
+            if !known {
+                return Err(ConfigError::Validation(format!(
+                    "teams.{}.groups references unknown group '{}'",
+                    name, group
+                )));
+            }
+        }
+    }
+
+    let mut ecosystem_names: Vec<&String> = config.ecosystems.keys().collect();
+    ecosystem_names.sort();
+    for name in ecosystem_names {
+        validate_ecosystem_config(name, &config.ecosystems[name])?;
+    }
+
+    if let Some(allowlist) = config
+        .safety
+        .as_ref()
+        .and_then(|safety| safety.secret_allowlist.as_ref())
+    {
+        for pattern in allowlist {
+            if let Err(err) = regex::Regex::new(pattern) {
+                return Err(ConfigError::Validation(format!(
+                    "safety.secret_allowlist entry '{}' is not a valid regex: {}",
+                    pattern, err
+                )));
+            }
+        }
+    }
+
+    if let Some(forge) = config.forge.as_ref() {
+        let mut names: Vec<&String> = forge.hosts.keys().collect();
+        names.sort();
+        for name in names {
+            let entry = &forge.hosts[name];
+            let forge_type = entry.forge_type.as_deref().unwrap_or(&forge.forge_type);
+            if !matches!(forge_type, "github" | "gitlab") {
+                return Err(ConfigError::Validation(format!(
+                    "forge.hosts.\"{}\".type must be 'github' or 'gitlab', got '{}'",
+                    name, forge_type
+                )));
+            }
+        }
     }
 
     if let Some(mr) = config.mr.as_ref() {
@@ -72,6 +220,101 @@ fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError
                 )));
             }
         }
+        if let Some(strategy) = mr.reviewer_strategy.as_deref() {
+            if ReviewerStrategy::parse(strategy).is_none() {
+                return Err(ConfigError::Validation(format!(
+                    "mr.reviewer_strategy must be one of all, round-robin, load, got '{}'",
+                    strategy
+                )));
+            }
+        }
+        if mr.reviewers_per_mr == Some(0) {
+            return Err(ConfigError::Validation(
+                "mr.reviewers_per_mr must be at least 1".to_string(),
+            ));
+        }
+        if let Some(coordination) = mr.coordination.as_ref() {
+            if coordination.repo.trim().is_empty() {
+                return Err(ConfigError::Validation(
+                    "mr.coordination.repo cannot be empty".to_string(),
+                ));
+            }
+            if let Some(forge) = coordination.forge.as_ref() {
+                if !matches!(forge.forge_type.as_str(), "github" | "gitlab") {
+                    return Err(ConfigError::Validation(format!(
+                        "mr.coordination.forge.type must be 'github' or 'gitlab', got '{}'",
+                        forge.forge_type
+                    )));
+                }
+            }
+        }
+        for (key, pattern) in [
+            ("ticket_pattern", &mr.ticket_pattern),
+            ("issue_pattern", &mr.issue_pattern),
+        ] {
+            if let Some(pattern) = pattern.as_deref() {
+                if let Err(err) = regex::Regex::new(pattern) {
+                    return Err(ConfigError::Validation(format!(
+                        "mr.{} is not a valid regex: {}",
+                        key, err
+                    )));
+                }
+            }
+        }
+        if let Some(keyword) = mr.closing_keyword.as_deref() {
+            if !CLOSING_KEYWORDS.contains(&keyword.to_ascii_lowercase().as_str()) {
+                return Err(ConfigError::Validation(format!(
+                    "mr.closing_keyword must be one of {}, got '{}'",
+                    CLOSING_KEYWORDS.join(", "),
+                    keyword
+                )));
+            }
+        }
+    }
+
+    if let Some(safety) = config.safety.as_ref() {
+        for (key, policy) in [
+            ("force_push", &safety.force_push),
+            ("force_branch", &safety.force_branch),
+            ("amend", &safety.amend),
+            ("mr_merge", &safety.mr_merge),
+            ("mr_close", &safety.mr_close),
+        ] {
+            if let Some(policy) = policy.as_deref() {
+                let policy = policy.trim().to_ascii_lowercase();
+                if !matches!(policy.as_str(), "confirm" | "type-name" | "none") {
+                    return Err(ConfigError::Validation(format!(
+                        "safety.{} must be one of confirm, type-name, none, got '{}'",
+                        key, policy
+                    )));
+                }
+            }
+        }
+    }
+
+    if let Some(branching) = config.branching.as_ref() {
+        if branching
+            .template
+            .as_deref()
+            .is_some_and(|template| template.trim().is_empty())
+        {
+            return Err(ConfigError::Validation(
+                "branching.template cannot be empty".to_string(),
+            ));
+        }
+        if let Some(pattern) = branching.pattern.as_deref() {
+            if let Err(err) = regex::Regex::new(pattern) {
+                return Err(ConfigError::Validation(format!(
+                    "branching.pattern is not a valid regex: {}",
+                    err
+                )));
+            }
+        }
+        if branching.max_length == Some(0) {
+            return Err(ConfigError::Validation(
+                "branching.max_length must be greater than 0".to_string(),
+            ));
+        }
     }
 
     if let Some(changesets) = config.changesets.as_ref() {
@@ -89,6 +332,14 @@ fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError
         }
     }
 
+    if let Some(vars) = config.env.as_ref() {
+        if has_invalid_env_key(vars) {
+            return Err(ConfigError::Validation(
+                "env keys cannot be empty or contain '='".to_string(),
+            ));
+        }
+    }
+
     for (repo, entry) in &config.repos {
         if entry.external && entry.ignored {
             return Err(ConfigError::Validation(format!(
@@ -96,6 +347,34 @@ fn validate_workspace_config(config: &WorkspaceConfig) -> Result<(), ConfigError
                 repo
             )));
         }
+        if entry
+            .push_remote
+            .as_deref()
+            .is_some_and(|remote| remote.trim().is_empty())
+        {
+            return Err(ConfigError::Validation(format!(
+                "repo '{}' has empty push_remote value",
+                repo
+            )));
+        }
+        if let Some(until) = entry.paused_until.as_deref() {
+            if !is_pause_date(until) {
+                return Err(ConfigError::Validation(format!(
+                    "repo '{}' has invalid paused_until '{}' (expected YYYY-MM-DD)",
+                    repo, until
+                )));
+            }
+        }
+        if entry
+            .path
+            .as_deref()
+            .is_some_and(|path| path.trim().is_empty())
+        {
+            return Err(ConfigError::Validation(format!(
+                "repo '{}' has empty path value",
+                repo
+            )));
+        }
         if entry
             .ecosystem
             .as_deref()
@@ -116,6 +395,7 @@ fn build_repos(
     config: &WorkspaceConfig,
 ) -> Result<HashMap<RepoId, Repo>, ConfigError> {
     let mut repos = HashMap::new();
+    let today = today_date();
     let repos_dir = if config.workspace.repos_dir.is_empty() {
         "repos"
     } else {
@@ -124,8 +404,22 @@ fn build_repos(
 
     for (repo_key, entry) in &config.repos {
         let repo_id = RepoId::new(repo_key.clone());
-        let repo_path = root.join(repos_dir).join(repo_key);
+        let paused = active_pause(entry, &today);
+        let repo_path = match entry.path.as_deref() {
+            Some(path) => root.join(path),
+            None => root.join(repos_dir).join(repo_key),
+        };
         let repo_config = load_repo_config(&repo_path.join(".harmonia.toml"))?;
+        if repo_config
+            .as_ref()
+            .and_then(|repo_config| repo_config.env.as_ref())
+            .is_some_and(has_invalid_env_key)
+        {
+            return Err(ConfigError::Validation(format!(
+                "repo '{}' .harmonia.toml: env keys cannot be empty or contain '='",
+                repo_key
+            )));
+        }
         let default_branch = entry
             .default_branch
             .clone()
@@ -172,7 +466,8 @@ fn build_repos(
             ecosystem,
             config: repo_config,
             external: entry.external,
-            ignored: entry.ignored,
+            ignored: entry.ignored || paused.is_some(),
+            paused,
         };
         repos.insert(repo_id, repo);
     }
@@ -180,12 +475,106 @@ fn build_repos(
     Ok(repos)
 }
 
+/// Whether `value` is a `YYYY-MM-DD` date, as accepted by `paused_until`.
+pub fn is_pause_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    let numeric =
+        |part: &str, len: usize| part.len() == len && part.chars().all(|ch| ch.is_ascii_digit());
+    numeric(year, 4)
+        && numeric(month, 2)
+        && numeric(day, 2)
+        && (1..=12).contains(&month.parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&day.parse::<u32>().unwrap_or(0))
+}
+
+/// Today's UTC date as `YYYY-MM-DD`.
+pub fn today_date() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The pause in effect for `entry` on `today`. A pause ends on its
+/// `paused_until` date.
+fn active_pause(entry: &RepoEntry, today: &str) -> Option<RepoPause> {
+    if !entry.paused {
+        return None;
+    }
+    if entry
+        .paused_until
+        .as_deref()
+        .is_some_and(|until| today >= until)
+    {
+        return None;
+    }
+    Some(RepoPause {
+        until: entry.paused_until.clone(),
+        reason: entry.pause_reason.clone(),
+    })
+}
+
+fn validate_ecosystem_config(name: &str, ecosystem: &EcosystemConfig) -> Result<(), ConfigError> {
+    if matches!(name, "python" | "rust" | "node" | "go" | "php" | "java") {
+        return Err(ConfigError::Validation(format!(
+            "ecosystems.{name} would replace the built-in {name} plugin"
+        )));
+    }
+    for (kind, pattern, path) in [
+        (
+            "version",
+            &ecosystem.version_pattern,
+            &ecosystem.version_path,
+        ),
+        (
+            "dependency",
+            &ecosystem.dependency_pattern,
+            &ecosystem.dependency_path,
+        ),
+    ] {
+        if pattern.is_some() && path.is_some() {
+            return Err(ConfigError::Validation(format!(
+                "ecosystems.{name} sets both {kind}_pattern and {kind}_path"
+            )));
+        }
+        let Some(pattern) = pattern else {
+            continue;
+        };
+        let regex = regex::Regex::new(pattern).map_err(|err| {
+            ConfigError::Validation(format!(
+                "ecosystems.{name}.{kind}_pattern is not a valid regex: {err}"
+            ))
+        })?;
+        let groups: Vec<&str> = regex.capture_names().flatten().collect();
+        let missing = match kind {
+            "version" => regex.captures_len() < 2,
+            _ => !groups.contains(&"name") || !groups.contains(&"constraint"),
+        };
+        if missing {
+            let expected = match kind {
+                "version" => "a capture group",
+                _ => "'name' and 'constraint' capture groups",
+            };
+            return Err(ConfigError::Validation(format!(
+                "ecosystems.{name}.{kind}_pattern must include {expected}"
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn parse_ecosystem(value: &str) -> Option<EcosystemId> {
     match value {
         "python" => Some(EcosystemId::Python),
         "rust" => Some(EcosystemId::Rust),
         "node" => Some(EcosystemId::Node),
         "go" => Some(EcosystemId::Go),
+        "php" => Some(EcosystemId::Php),
         "java" => Some(EcosystemId::Java),
         other => Some(EcosystemId::Custom(other.to_string())),
     }
@@ -205,11 +594,7 @@ fn build_default_url(config: &WorkspaceConfig, repo_key: &str) -> Option<String>
         .unwrap_or_else(|| "ssh".to_string());
 
     let path = format!("{group}/{repo_key}.git");
-    if protocol == "https" {
-        Some(format!("https://{host}/{path}"))
-    } else {
-        Some(format!("git@{host}:{path}"))
-    }
+    Some(forge_clone_url(&host, &path, &protocol))
 }
 
 fn default_host_for_forge(forge_type: &str) -> Option<String> {
@@ -226,9 +611,13 @@ mod tests {
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    use crate::config::{MrConfig, RepoEntry, WorkspaceConfig};
-    use crate::core::repo::RepoId;
-    use crate::core::workspace::{build_repos, validate_workspace_config};
+    use std::collections::HashMap;
+
+    use crate::config::{MrConfig, RepoConfig, RepoEntry, SafetyConfig, WorkspaceConfig};
+    use crate::core::repo::{Repo, RepoId};
+    use crate::core::workspace::{
+        active_pause, build_repos, is_pause_date, resolve_command_env, validate_workspace_config,
+    };
     use crate::ecosystem::EcosystemId;
 
     fn unique_temp_dir(prefix: &str) -> PathBuf {
@@ -240,6 +629,22 @@ mod tests {
         std::env::temp_dir().join(format!("harmonia-{prefix}-{pid}-{nanos}"))
     }
 
+    #[test]
+    fn rejects_unknown_closing_keyword() {
+        let mut config = WorkspaceConfig {
+            mr: Some(MrConfig {
+                closing_keyword: Some("Fixes".to_string()),
+                ..MrConfig::default()
+            }),
+            ..WorkspaceConfig::default()
+        };
+        assert!(validate_workspace_config(&config).is_ok());
+
+        config.mr.as_mut().unwrap().closing_keyword = Some("Finishes".to_string());
+        let err = validate_workspace_config(&config).expect_err("should reject config");
+        assert!(format!("{}", err).contains("mr.closing_keyword"));
+    }
+
     #[test]
     fn rejects_invalid_link_strategy() {
         let config = WorkspaceConfig {
@@ -254,6 +659,140 @@ mod tests {
         assert!(format!("{}", err).contains("mr.link_strategy"));
     }
 
+    #[test]
+    fn rejects_unknown_reviewer_strategy_and_empty_assignment() {
+        let mut config: WorkspaceConfig =
+            toml::from_str("[mr]\nreviewer_strategy = \"Round-Robin\"\nreviewers_per_mr = 2\n")
+                .expect("parse config");
+        assert!(validate_workspace_config(&config).is_ok());
+
+        config.mr.as_mut().unwrap().reviewer_strategy = Some("random".to_string());
+        let err = validate_workspace_config(&config).expect_err("should reject strategy");
+        assert!(format!("{}", err).contains("mr.reviewer_strategy"));
+
+        config.mr.as_mut().unwrap().reviewer_strategy = None;
+        config.mr.as_mut().unwrap().reviewers_per_mr = Some(0);
+        let err = validate_workspace_config(&config).expect_err("should reject zero");
+        assert!(format!("{}", err).contains("mr.reviewers_per_mr"));
+    }
+
+    #[test]
+    fn matches_forge_hosts_by_remote_host_and_validates_their_type() {
+        let mut config: WorkspaceConfig = toml::from_str(
+            r#"
+[forge]
+type = "gitlab"
+
+[forge.hosts."gitlab.corp"]
+host = "https://gitlab.corp:8443/git"
+token_env = "CORP_GITLAB_TOKEN"
+
+[forge.hosts."github.com"]
+type = "github"
+token = "ghp_token"
+"#,
+        )
+        .expect("parse config");
+        assert!(validate_workspace_config(&config).is_ok());
+
+        let forge = config.forge.as_ref().unwrap();
+        let (name, entry) = forge.host_entry("GitLab.corp").expect("corp entry");
+        assert_eq!(name, "gitlab.corp");
+        let resolved = forge.for_host(name, entry);
+        assert_eq!(resolved.forge_type, "gitlab");
+        assert_eq!(
+            resolved.host.as_deref(),
+            Some("https://gitlab.corp:8443/git")
+        );
+        let (name, entry) = forge.host_entry("github.com").expect("github entry");
+        assert_eq!(forge.for_host(name, entry).forge_type, "github");
+        assert_eq!(entry.resolve_token().as_deref(), Some("ghp_token"));
+        assert!(forge.host_entry("gitlab.com").is_none());
+
+        config
+            .forge
+            .as_mut()
+            .unwrap()
+            .hosts
+            .get_mut("github.com")
+            .unwrap()
+            .forge_type = Some("gitea".to_string());
+        let err = validate_workspace_config(&config).expect_err("should reject host type");
+        assert!(format!("{}", err).contains("forge.hosts.\"github.com\".type"));
+    }
+
+    #[test]
+    fn rejects_incomplete_coordination_repo() {
+        let mut config: WorkspaceConfig = toml::from_str(
+            "[mr.coordination]\nrepo = \"platform/releases\"\n\n[mr.coordination.forge]\ntype = \"github\"\n",
+        )
+        .expect("parse config");
+        assert!(validate_workspace_config(&config).is_ok());
+
+        let coordination = config.mr.as_mut().unwrap().coordination.as_mut().unwrap();
+        coordination.forge.as_mut().unwrap().forge_type = "bitbucket".to_string();
+        let err = validate_workspace_config(&config).expect_err("should reject config");
+        assert!(format!("{}", err).contains("mr.coordination.forge.type"));
+
+        config
+            .mr
+            .as_mut()
+            .unwrap()
+            .coordination
+            .as_mut()
+            .unwrap()
+            .repo = " ".to_string();
+        let err = validate_workspace_config(&config).expect_err("should reject config");
+        assert!(format!("{}", err).contains("mr.coordination.repo"));
+    }
+
+    #[test]
+    fn rejects_invalid_safety_policy() {
+        let config = WorkspaceConfig {
+            safety: Some(SafetyConfig {
+                force_push: Some("type-name".to_string()),
+                mr_merge: Some("always".to_string()),
+                ..SafetyConfig::default()
+            }),
+            ..WorkspaceConfig::default()
+        };
+
+        let err = validate_workspace_config(&config).expect_err("should reject config");
+        assert!(format!("{}", err).contains("safety.mr_merge"));
+    }
+
+    #[test]
+    fn pauses_end_on_their_until_date() {
+        let mut entry = RepoEntry {
+            paused: true,
+            pause_reason: Some("mid-migration".to_string()),
+            ..RepoEntry::default()
+        };
+        let pause = active_pause(&entry, "2026-10-16").expect("open-ended pause");
+        assert_eq!(pause.reason.as_deref(), Some("mid-migration"));
+
+        entry.paused_until = Some("2026-11-01".to_string());
+        assert!(active_pause(&entry, "2026-10-31").is_some());
+        assert!(active_pause(&entry, "2026-11-01").is_none());
+        entry.paused = false;
+        assert!(active_pause(&entry, "2026-10-31").is_none());
+
+        assert!(is_pause_date("2026-11-01"));
+        assert!(!is_pause_date("2026-13-01"));
+        assert!(!is_pause_date("next week"));
+        let mut config = WorkspaceConfig::default();
+        config.repos.insert(
+            "svc".to_string(),
+            RepoEntry {
+                paused: true,
+                paused_until: Some("11/01/2026".to_string()),
+                ..RepoEntry::default()
+            },
+        );
+        let err = validate_workspace_config(&config).expect_err("should reject date");
+        assert!(format!("{}", err).contains("expected YYYY-MM-DD"));
+    }
+
     #[test]
     fn rejects_external_and_ignored_repo() {
         let mut config = WorkspaceConfig::default();
@@ -314,6 +853,23 @@ mod tests {
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn build_repos_rejects_invalid_repo_env_keys() {
+        let root = unique_temp_dir("repo-config-env");
+        let repo_path = root.join("repos").join("svc");
+        fs::create_dir_all(&repo_path).expect("create repo dir");
+        fs::write(repo_path.join(".harmonia.toml"), "[env]\n\"A=B\" = \"1\"\n")
+            .expect("write repo config");
+
+        let mut config = WorkspaceConfig::default();
+        config.repos.insert("svc".to_string(), RepoEntry::default());
+
+        let err = build_repos(&root, &config).expect_err("should reject repo env");
+        assert!(format!("{}", err).contains("repo 'svc' .harmonia.toml: env keys"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn build_repos_falls_back_to_repo_config_ecosystem() {
         let root = unique_temp_dir("repo-config-ecosystem");
@@ -336,4 +892,59 @@ mod tests {
 
         let _ = fs::remove_dir_all(root);
     }
+
+    #[test]
+    fn command_env_merges_repo_over_workspace_and_expands() {
+        let root = PathBuf::from("/ws");
+        let config = WorkspaceConfig {
+            env: Some(HashMap::from([
+                ("SERVICE".to_string(), "{repo}".to_string()),
+                (
+                    "CACHE_DIR".to_string(),
+                    "{workspace_root}/.cache".to_string(),
+                ),
+                ("LOG_LEVEL".to_string(), "info".to_string()),
+            ])),
+            ..WorkspaceConfig::default()
+        };
+        let repo = Repo {
+            id: RepoId::new("svc".to_string()),
+            path: root.join("repos").join("svc"),
+            remote_url: String::new(),
+            default_branch: "main".to_string(),
+            package_name: None,
+            depends_on: Vec::new(),
+            ecosystem: None,
+            config: Some(RepoConfig {
+                env: Some(HashMap::from([(
+                    "LOG_LEVEL".to_string(),
+                    "debug".to_string(),
+                )])),
+                ..RepoConfig::default()
+            }),
+            external: false,
+            ignored: false,
+            paused: None,
+        };
+
+        let vars = resolve_command_env(&root, &config, Some(&repo)).expect("resolve env");
+        assert_eq!(
+            vars,
+            vec![
+                ("CACHE_DIR".to_string(), "/ws/.cache".to_string()),
+                ("LOG_LEVEL".to_string(), "debug".to_string()),
+                ("SERVICE".to_string(), "svc".to_string()),
+            ]
+        );
+
+        let config = WorkspaceConfig {
+            env: Some(HashMap::from([(
+                "TOKEN".to_string(),
+                "env:HARMONIA_TEST_UNSET_SECRET".to_string(),
+            )])),
+            ..WorkspaceConfig::default()
+        };
+        let err = resolve_command_env(&root, &config, None).expect_err("unset secret");
+        assert!(format!("{}", err).contains("HARMONIA_TEST_UNSET_SECRET"));
+    }
 }
@@ -0,0 +1,411 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::repo::Dependency;
+use crate::core::version::VersionReq;
+use crate::ecosystem::traits::EcosystemPlugin;
+use crate::error::Result;
+
+const CACHE_FORMAT: u32 = 1;
+
+/// Files modified this recently are always re-read: a write landing in the
+/// same mtime tick as the cached read would otherwise go unnoticed.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Parsed manifests keyed by path, reused while the file content hash and the
+/// parsing plugin are unchanged. Stored in `.harmonia/cache/manifests.json`.
+///
+/// Entries also record the manifest's size and mtime, so the `load_*` methods
+/// can answer without reading files that were not touched since.
+///
+/// The cache is best effort: a missing or unreadable file starts empty and
+/// write failures are ignored.
+#[derive(Debug, Default)]
+pub struct ManifestCache {
+    path: Option<PathBuf>,
+    entries: BTreeMap<String, CachedManifest>,
+    dirty: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    format: u32,
+    #[serde(default)]
+    entries: BTreeMap<String, CachedManifest>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedManifest {
+    hash: String,
+    plugin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dependencies: Option<Vec<CachedDependency>>,
+    /// `Some(None)` records that the manifest has no version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stamp: Option<FileStamp>,
+}
+
+/// Size and mtime of a manifest when its entry was last verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    len: u64,
+    modified_ns: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified_ns: u64::try_from(modified.as_nanos()).ok()?,
+        })
+    }
+
+    /// Whether the file was modified long enough ago for the stamp to be
+    /// trusted later.
+    fn settled(&self) -> bool {
+        let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+            return false;
+        };
+        Duration::from_nanos(self.modified_ns) + RACY_WINDOW < now
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedDependency {
+    name: String,
+    constraint: String,
+    #[serde(default)]
+    is_internal: bool,
+}
+
+impl CachedDependency {
+    fn to_dependency(&self) -> Dependency {
+        Dependency {
+            name: self.name.clone(),
+            constraint: VersionReq::new(self.constraint.clone()),
+            is_internal: self.is_internal,
+        }
+    }
+}
+
+/// Directory holding harmonia's derived caches; safe to delete at any time.
+pub fn cache_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".harmonia").join("cache")
+}
+
+pub fn manifest_cache_path(workspace_root: &Path) -> PathBuf {
+    cache_dir(workspace_root).join("manifests.json")
+}
+
+impl ManifestCache {
+    /// Loads the workspace cache, starting empty when it is missing, corrupt,
+    /// or written by a different cache format.
+    pub fn load(workspace_root: &Path) -> Self {
+        let path = manifest_cache_path(workspace_root);
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<CacheFile>(&raw).ok())
+            .filter(|file| file.format == CACHE_FORMAT)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// A cache that is never read from or written to disk.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Dependencies declared in the manifest at `path`, read from disk only
+    /// when its size or mtime changed since the entry was recorded.
+    pub fn load_dependencies(
+        &mut self,
+        plugin: &dyn EcosystemPlugin,
+        path: &Path,
+    ) -> Result<Vec<Dependency>> {
+        if let Some(cached) = self
+            .unchanged(plugin, path)
+            .and_then(|entry| entry.dependencies.as_ref())
+        {
+            return Ok(cached.iter().map(CachedDependency::to_dependency).collect());
+        }
+        let content = fs::read_to_string(path)?;
+        let parsed = self.parse_dependencies(plugin, path, &content)?;
+        self.record_stamp(path);
+        Ok(parsed)
+    }
+
+    /// The raw version string in the manifest at `path`, read from disk only
+    /// when its size or mtime changed since the entry was recorded.
+    pub fn load_version(
+        &mut self,
+        plugin: &dyn EcosystemPlugin,
+        path: &Path,
+    ) -> Result<Option<String>> {
+        if let Some(cached) = self
+            .unchanged(plugin, path)
+            .and_then(|entry| entry.version.as_ref())
+        {
+            return Ok(cached.clone());
+        }
+        let content = fs::read_to_string(path)?;
+        let parsed = self.parse_version(plugin, path, &content)?;
+        self.record_stamp(path);
+        Ok(parsed)
+    }
+
+    pub fn parse_dependencies(
+        &mut self,
+        plugin: &dyn EcosystemPlugin,
+        path: &Path,
+        content: &str,
+    ) -> Result<Vec<Dependency>> {
+        let entry = self.entry(plugin, path, content);
+        if let Some(cached) = entry.dependencies.as_ref() {
+            return Ok(cached.iter().map(CachedDependency::to_dependency).collect());
+        }
+
+        let parsed = plugin.parse_dependencies(path, content)?;
+        entry.dependencies = Some(
+            parsed
+                .iter()
+                .map(|dep| CachedDependency {
+                    name: dep.name.clone(),
+                    constraint: dep.constraint.raw.clone(),
+                    is_internal: dep.is_internal,
+                })
+                .collect(),
+        );
+        self.dirty = true;
+        Ok(parsed)
+    }
+
+    /// The raw version string the plugin parses from `content`.
+    pub fn parse_version(
+        &mut self,
+        plugin: &dyn EcosystemPlugin,
+        path: &Path,
+        content: &str,
+    ) -> Result<Option<String>> {
+        let entry = self.entry(plugin, path, content);
+        if let Some(cached) = entry.version.as_ref() {
+            return Ok(cached.clone());
+        }
+
+        let parsed = plugin
+            .parse_version(path, content)?
+            .map(|version| version.raw);
+        entry.version = Some(parsed.clone());
+        self.dirty = true;
+        Ok(parsed)
+    }
+
+    /// Writes the cache when it changed, dropping entries for manifests that
+    /// no longer exist.
+    pub fn save(&mut self) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let before = self.entries.len();
+        self.entries
+            .retain(|manifest, _| Path::new(manifest).is_file());
+        if !self.dirty && self.entries.len() == before {
+            return;
+        }
+
+        let file = CacheFile {
+            format: CACHE_FORMAT,
+            entries: std::mem::take(&mut self.entries),
+        };
+        if let Ok(contents) = serde_json::to_string(&file) {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| {
+                    let tmp = path.with_extension("json.tmp");
+                    fs::write(&tmp, contents)?;
+                    fs::rename(&tmp, path)
+                });
+            if written.is_ok() {
+                self.dirty = false;
+            }
+        }
+        self.entries = file.entries;
+    }
+
+    /// The entry for `path` when the file still has the recorded stamp.
+    fn unchanged(&self, plugin: &dyn EcosystemPlugin, path: &Path) -> Option<&CachedManifest> {
+        let entry = self.entries.get(path.to_string_lossy().as_ref())?;
+        let stamp = entry.stamp?;
+        (entry.plugin == plugin.id() && FileStamp::read(path) == Some(stamp)).then_some(entry)
+    }
+
+    /// Records the current stamp of `path` after its content was hashed.
+    /// Recently modified files get no stamp, so they are hashed again.
+    fn record_stamp(&mut self, path: &Path) {
+        let stamp = FileStamp::read(path).filter(FileStamp::settled);
+        if let Some(entry) = self.entries.get_mut(path.to_string_lossy().as_ref()) {
+            if entry.stamp != stamp {
+                entry.stamp = stamp;
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// The entry for `path`, reset when the content hash or plugin changed.
+    fn entry(
+        &mut self,
+        plugin: &dyn EcosystemPlugin,
+        path: &Path,
+        content: &str,
+    ) -> &mut CachedManifest {
+        let hash = content_hash(content);
+        let entry = self
+            .entries
+            .entry(path.to_string_lossy().into_owned())
+            .or_default();
+        if entry.hash != hash || entry.plugin != plugin.id() {
+            *entry = CachedManifest {
+                hash,
+                plugin: plugin.id().to_string(),
+                ..CachedManifest::default()
+            };
+            self.dirty = true;
+        }
+        entry
+    }
+}
+
+/// 64-bit FNV-1a of the manifest contents, stable across builds.
+fn content_hash(content: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecosystem::rust::RustPlugin;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_dir(prefix: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock before epoch")
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("harmonia-{prefix}-{}-{nanos}", std::process::id()));
+        fs::create_dir_all(&path).expect("create temp dir");
+        path
+    }
+
+    #[test]
+    fn cached_results_are_reused_until_the_content_hash_changes() {
+        let root = unique_temp_dir("manifest-cache");
+        let manifest = root.join("Cargo.toml");
+        let v1 =
+            "[package]\nname = \"core\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n";
+        fs::write(&manifest, v1).expect("write manifest");
+
+        let mut cache = ManifestCache::load(&root);
+        let deps = cache
+            .parse_dependencies(&RustPlugin, &manifest, v1)
+            .expect("parse deps");
+        assert_eq!(deps[0].name, "serde");
+        assert_eq!(
+            cache
+                .parse_version(&RustPlugin, &manifest, v1)
+                .expect("version"),
+            Some("0.1.0".to_string())
+        );
+        cache.save();
+        assert!(manifest_cache_path(&root).is_file());
+
+        // A reloaded cache answers from disk, even for content the plugin
+        // would parse differently, as long as the hash matches.
+        let mut cache = ManifestCache::load(&root);
+        let key = manifest.to_string_lossy().into_owned();
+        cache.entries.get_mut(&key).expect("cached entry").version =
+            Some(Some("9.9.9".to_string()));
+        assert_eq!(
+            cache
+                .parse_version(&RustPlugin, &manifest, v1)
+                .expect("version"),
+            Some("9.9.9".to_string())
+        );
+
+        let v2 = v1.replace("0.1.0", "0.2.0");
+        assert_eq!(
+            cache
+                .parse_version(&RustPlugin, &manifest, &v2)
+                .expect("version"),
+            Some("0.2.0".to_string())
+        );
+        assert!(cache.entries[&key].dependencies.is_none());
+
+        fs::remove_file(&manifest).expect("remove manifest");
+        cache.save();
+        assert!(ManifestCache::load(&root).entries.is_empty());
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn settled_manifests_are_answered_from_their_stamp() {
+        let root = unique_temp_dir("manifest-stamp");
+        let manifest = root.join("Cargo.toml");
+        fs::write(
+            &manifest,
+            "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+        )
+        .expect("write manifest");
+        let key = manifest.to_string_lossy().into_owned();
+
+        let mut cache = ManifestCache::load(&root);
+        assert_eq!(
+            cache.load_version(&RustPlugin, &manifest).expect("version"),
+            Some("0.1.0".to_string())
+        );
+        // Just written, so the mtime is too recent to trust.
+        assert!(cache.entries[&key].stamp.is_none());
+
+        // Pretend the file settled: with a matching stamp the file is not
+        // read, so the cached answer wins even over different content.
+        let stamp = FileStamp::read(&manifest).expect("stamp");
+        let entry = cache.entries.get_mut(&key).expect("cached entry");
+        entry.stamp = Some(stamp);
+        entry.version = Some(Some("9.9.9".to_string()));
+        assert_eq!(
+            cache.load_version(&RustPlugin, &manifest).expect("version"),
+            Some("9.9.9".to_string())
+        );
+
+        // A size change invalidates the stamp and the file is parsed again.
+        fs::write(
+            &manifest,
+            "[package]\nname = \"core\"\nversion = \"0.10.0\"\n",
+        )
+        .expect("rewrite manifest");
+        assert_eq!(
+            cache.load_version(&RustPlugin, &manifest).expect("version"),
+            Some("0.10.0".to_string())
+        );
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -1,53 +1,460 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
 use std::path::Path;
+use std::sync::RwLock;
 
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+use crate::config::EcosystemConfig;
 use crate::core::repo::Dependency;
-use crate::core::version::Version;
+use crate::core::version::{Version, VersionKind, VersionReq};
 use crate::ecosystem::traits::EcosystemPlugin;
-use crate::error::Result;
+use crate::error::{HarmoniaError, Result};
+
+/// `[ecosystems.<name>]` definitions of the loaded workspace. Plugins hand
+/// out `'static` strings, so each definition is leaked once when registered.
+static REGISTRY: RwLock<BTreeMap<String, &'static CustomEcosystem>> = RwLock::new(BTreeMap::new());
+
+struct CustomEcosystem {
+    config: EcosystemConfig,
+    /// Name plus a hash of the definition, so the manifest cache drops
+    /// entries parsed under an earlier definition.
+    id: &'static str,
+    files: &'static [&'static str],
+    version_files: &'static [&'static str],
+    dependency_files: &'static [&'static str],
+    commands: BTreeMap<String, &'static str>,
+}
+
+impl CustomEcosystem {
+    fn new(name: &str, config: &EcosystemConfig) -> Self {
+        let leak_list = |files: Vec<String>| -> &'static [&'static str] {
+            let files: Vec<&'static str> = files.into_iter().map(leak).collect();
+            Box::leak(files.into_boxed_slice())
+        };
+        let with_first = |first: &Option<String>| {
+            let mut files: Vec<String> = first.iter().cloned().collect();
+            files.extend(
+                config
+                    .files
+                    .iter()
+                    .filter(|file| Some(*file) != first.as_ref())
+                    .cloned(),
+            );
+            files
+        };
+        let digest = Sha256::digest(format!("{config:?}").as_bytes());
+        let fingerprint: String = digest[..4].iter().map(|b| format!("{b:02x}")).collect();
+        Self {
+            config: config.clone(),
+            id: leak(format!("custom:{name}:{fingerprint}")),
+            files: leak_list(config.files.clone()),
+            version_files: leak_list(with_first(&config.version_file)),
+            dependency_files: leak_list(with_first(&config.dependency_file)),
+            commands: config
+                .commands
+                .iter()
+                .map(|(name, command)| (name.clone(), leak(command.clone())))
+                .collect(),
+        }
+    }
+
+    fn is_version_file(&self, path: &Path) -> bool {
+        matches_any(path, self.version_files)
+    }
+
+    fn is_dependency_file(&self, path: &Path) -> bool {
+        matches_any(path, self.dependency_files)
+    }
+}
+
+fn leak(value: String) -> &'static str {
+    Box::leak(value.into_boxed_str())
+}
+
+fn matches_any(path: &Path, files: &[&str]) -> bool {
+    files.iter().any(|file| path.ends_with(file))
+}
+
+/// Registers `ecosystems`, replacing earlier definitions of the same names.
+pub fn register_custom_ecosystems(ecosystems: &HashMap<String, EcosystemConfig>) {
+    let mut registry = REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (name, config) in ecosystems {
+        if registry
+            .get(name)
+            .is_some_and(|existing| existing.config == *config)
+        {
+            continue;
+        }
+        let ecosystem: &'static CustomEcosystem =
+            Box::leak(Box::new(CustomEcosystem::new(name, config)));
+        registry.insert(name.clone(), ecosystem);
+    }
+}
 
-pub struct CustomPlugin;
+/// Plugin for an ecosystem without built-in support. Without an
+/// `[ecosystems.<name>]` definition it parses and updates nothing.
+#[derive(Default)]
+pub struct CustomPlugin {
+    ecosystem: Option<&'static CustomEcosystem>,
+}
+
+impl CustomPlugin {
+    pub fn named(name: &str) -> Self {
+        let registry = REGISTRY
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Self {
+            ecosystem: registry.get(name).copied(),
+        }
+    }
+
+    fn command(&self, name: &str) -> Option<&'static str> {
+        self.ecosystem?.commands.get(name).copied()
+    }
+}
 
 impl EcosystemPlugin for CustomPlugin {
     fn id(&self) -> &'static str {
-        "custom"
+        self.ecosystem.map_or("custom", |ecosystem| ecosystem.id)
     }
 
     fn file_patterns(&self) -> &'static [&'static str] {
-        &[]
+        self.ecosystem.map_or(&[], |ecosystem| ecosystem.files)
     }
 
-    fn parse_version(&self, _path: &Path, _content: &str) -> Result<Option<Version>> {
-        Ok(None)
+    fn version_files(&self) -> &'static [&'static str] {
+        self.ecosystem
+            .map_or(&[], |ecosystem| ecosystem.version_files)
     }
 
-    fn parse_dependencies(&self, _path: &Path, _content: &str) -> Result<Vec<Dependency>> {
-        Ok(Vec::new())
+    fn dependency_files(&self) -> &'static [&'static str] {
+        self.ecosystem
+            .map_or(&[], |ecosystem| ecosystem.dependency_files)
     }
 
-    fn update_version(
-        &self,
-        _path: &Path,
-        content: &str,
-        _new_version: &Version,
-    ) -> Result<String> {
+    fn parse_version(&self, path: &Path, content: &str) -> Result<Option<Version>> {
+        let Some(ecosystem) = self.ecosystem.filter(|e| e.is_version_file(path)) else {
+            return Ok(None);
+        };
+        let raw = if let Some(pattern) = ecosystem.config.version_pattern.as_deref() {
+            compile(pattern)?
+                .captures(content)
+                .and_then(|captures| captures.get(1))
+                .map(|version| version.as_str().to_string())
+        } else if let Some(key_path) = ecosystem.config.version_path.as_deref() {
+            Document::parse(path, content)?.and_then(|doc| doc.string_at(key_path))
+        } else {
+            None
+        };
+        Ok(raw.map(|raw| Version::new(raw, VersionKind::Semver)))
+    }
+
+    fn parse_dependencies(&self, path: &Path, content: &str) -> Result<Vec<Dependency>> {
+        let Some(ecosystem) = self.ecosystem.filter(|e| e.is_dependency_file(path)) else {
+            return Ok(Vec::new());
+        };
+        let entries: Vec<(String, String)> =
+            if let Some(pattern) = ecosystem.config.dependency_pattern.as_deref() {
+                compile(pattern)?
+                    .captures_iter(content)
+                    .filter_map(|captures| {
+                        Some((
+                            captures.name("name")?.as_str().to_string(),
+                            captures.name("constraint")?.as_str().to_string(),
+                        ))
+                    })
+                    .collect()
+            } else if let Some(key_path) = ecosystem.config.dependency_path.as_deref() {
+                Document::parse(path, content)?
+                    .map(|doc| doc.entries_at(key_path))
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+        Ok(entries
+            .into_iter()
+            .map(|(name, constraint)| Dependency {
+                name,
+                constraint: VersionReq::new(constraint),
+                is_internal: false,
+            })
+            .collect())
+    }
+
+    fn update_version(&self, path: &Path, content: &str, new_version: &Version) -> Result<String> {
+        let Some(ecosystem) = self.ecosystem.filter(|e| e.is_version_file(path)) else {
+            return Ok(content.to_string());
+        };
+        if let Some(pattern) = ecosystem.config.version_pattern.as_deref() {
+            let regex = compile(pattern)?;
+            let Some(version) = regex.captures(content).and_then(|captures| captures.get(1)) else {
+                return Ok(content.to_string());
+            };
+            return Ok(splice(content, &[version.range()], &new_version.raw));
+        }
+        if let Some(key_path) = ecosystem.config.version_path.as_deref() {
+            if let Some(mut doc) = Document::parse(path, content)? {
+                if doc.set_string(key_path, &new_version.raw) {
+                    return doc.render();
+                }
+            }
+        }
         Ok(content.to_string())
     }
 
     fn update_dependency(
         &self,
-        _path: &Path,
+        path: &Path,
         content: &str,
-        _dep: &str,
-        _constraint: &str,
+        dep: &str,
+        constraint: &str,
     ) -> Result<String> {
+        let Some(ecosystem) = self.ecosystem.filter(|e| e.is_dependency_file(path)) else {
+            return Ok(content.to_string());
+        };
+        if let Some(pattern) = ecosystem.config.dependency_pattern.as_deref() {
+            let ranges: Vec<_> = compile(pattern)?
+                .captures_iter(content)
+                .filter(|captures| {
+                    captures
+                        .name("name")
+                        .is_some_and(|name| name.as_str() == dep)
+                })
+                .filter_map(|captures| captures.name("constraint").map(|m| m.range()))
+                .collect();
+            return Ok(splice(content, &ranges, constraint));
+        }
+        if let Some(key_path) = ecosystem.config.dependency_path.as_deref() {
+            if let Some(mut doc) = Document::parse(path, content)? {
+                if doc.set_string(&format!("{key_path}.{dep}"), constraint) {
+                    return doc.render();
+                }
+            }
+        }
         Ok(content.to_string())
     }
 
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        self.command("clean")
+    }
+
+    fn prefetch_command(&self, _repo_path: &Path) -> Option<&'static str> {
+        self.command("prefetch")
+    }
+
+    fn hook_command(&self, name: &str) -> Option<&'static str> {
+        self.command(name)
+    }
+
     fn default_test_command(&self) -> Option<&'static str> {
-        None
+        self.command("test")
     }
 
     fn default_lint_command(&self) -> Option<&'static str> {
-        None
+        self.command("lint")
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
+}
+
+/// `content` with every range replaced by `value`.
+fn splice(content: &str, ranges: &[std::ops::Range<usize>], value: &str) -> String {
+    let mut updated = content.to_string();
+    for range in ranges.iter().rev() {
+        updated.replace_range(range.clone(), value);
+    }
+    updated
+}
+
+/// A manifest addressed by dotted paths, in the format its extension names.
+enum Document {
+    Toml(toml::Value),
+    Json(serde_json::Value),
+    Yaml(serde_yaml::Value),
+}
+
+impl Document {
+    fn parse(path: &Path, content: &str) -> Result<Option<Self>> {
+        let other = |err: &dyn std::fmt::Display| {
+            HarmoniaError::Other(anyhow::anyhow!(format!("{}: {}", path.display(), err)))
+        };
+        let doc = match path.extension().and_then(OsStr::to_str).unwrap_or("") {
+            "toml" => Document::Toml(toml::from_str(content).map_err(|err| other(&err))?),
+            "json" => Document::Json(serde_json::from_str(content).map_err(|err| other(&err))?),
+            "yaml" | "yml" => {
+                Document::Yaml(serde_yaml::from_str(content).map_err(|err| other(&err))?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(doc))
+    }
+
+    /// The value at `key_path` as JSON, which all three formats convert to.
+    fn json_at(&self, key_path: &str) -> Option<serde_json::Value> {
+        let value = match self {
+            Document::Toml(value) => serde_json::to_value(value).ok()?,
+            Document::Json(value) => value.clone(),
+            Document::Yaml(value) => serde_json::to_value(value).ok()?,
+        };
+        let mut current = &value;
+        for segment in segments(key_path) {
+            current = current.get(segment)?;
+        }
+        Some(current.clone())
+    }
+
+    fn string_at(&self, key_path: &str) -> Option<String> {
+        match self.json_at(key_path)? {
+            serde_json::Value::String(value) => Some(value),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    /// `name = "constraint"` entries of the table at `key_path`.
+    fn entries_at(&self, key_path: &str) -> Vec<(String, String)> {
+        let Some(serde_json::Value::Object(map)) = self.json_at(key_path) else {
+            return Vec::new();
+        };
+        map.into_iter()
+            .filter_map(|(name, value)| value.as_str().map(|v| (name, v.to_string())))
+            .collect()
+    }
+
+    /// Sets an existing value at `key_path`; false when it is missing.
+    fn set_string(&mut self, key_path: &str, value: &str) -> bool {
+        let segments = segments(key_path);
+        match self {
+            Document::Toml(root) => {
+                let mut current = root;
+                for segment in &segments {
+                    let Some(next) = current.get_mut(*segment) else {
+                        return false;
+                    };
+                    current = next;
+                }
+                *current = toml::Value::String(value.to_string());
+            }
+            Document::Json(root) => {
+                let mut current = root;
+                for segment in &segments {
+                    let Some(next) = current.get_mut(*segment) else {
+                        return false;
+                    };
+                    current = next;
+                }
+                *current = serde_json::Value::String(value.to_string());
+            }
+            Document::Yaml(root) => {
+                let mut current = root;
+                for segment in &segments {
+                    let Some(next) = current.get_mut(*segment) else {
+                        return false;
+                    };
+                    current = next;
+                }
+                *current = serde_yaml::Value::String(value.to_string());
+            }
+        }
+        true
+    }
+
+    fn render(&self) -> Result<String> {
+        let rendered = match self {
+            Document::Toml(value) => toml::to_string(value).map_err(anyhow::Error::new),
+            Document::Json(value) => serde_json::to_string_pretty(value)
+                .map(|json| json + "\n")
+                .map_err(anyhow::Error::new),
+            Document::Yaml(value) => serde_yaml::to_string(value).map_err(anyhow::Error::new),
+        };
+        rendered.map_err(HarmoniaError::Other)
+    }
+}
+
+fn segments(key_path: &str) -> Vec<&str> {
+    key_path.split('.').filter(|s| !s.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use crate::config::EcosystemConfig;
+    use crate::core::version::{Version, VersionKind};
+    use crate::ecosystem::custom::{register_custom_ecosystems, CustomPlugin};
+    use crate::ecosystem::traits::EcosystemPlugin;
+
+    #[test]
+    fn regex_definition_reads_and_updates_versions_and_dependencies() {
+        let mut ecosystems = HashMap::new();
+        ecosystems.insert(
+            "bazel-test".to_string(),
+            EcosystemConfig {
+                files: vec!["MODULE.bazel".to_string()],
+                version_pattern: Some(r#"module\(name = "[^"]+", version = "([^"]+)""#.to_string()),
+                dependency_pattern: Some(
+                    r#"bazel_dep\(name = "(?P<name>[^"]+)", version = "(?P<constraint>[^"]+)""#
+                        .to_string(),
+                ),
+                commands: [("test".to_string(), "bazel test //...".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..EcosystemConfig::default()
+            },
+        );
+        ecosystems.insert(
+            "deps-test".to_string(),
+            EcosystemConfig {
+                files: vec!["deps.yaml".to_string()],
+                version_path: Some("release.version".to_string()),
+                dependency_path: Some("requires".to_string()),
+                ..EcosystemConfig::default()
+            },
+        );
+        register_custom_ecosystems(&ecosystems);
+
+        let plugin = CustomPlugin::named("bazel-test");
+        let path = Path::new("MODULE.bazel");
+        let content = "module(name = \"api\", version = \"1.2.0\")\nbazel_dep(name = \"core\", version = \"1.0.0\")\n";
+        assert_eq!(plugin.default_test_command(), Some("bazel test //..."));
+        assert_eq!(
+            plugin.parse_version(path, content).expect("version"),
+            Some(Version::new("1.2.0", VersionKind::Semver))
+        );
+        let deps = plugin.parse_dependencies(path, content).expect("deps");
+        assert_eq!(deps[0].name, "core");
+        assert_eq!(deps[0].constraint.raw, "1.0.0");
+        let updated = plugin
+            .update_dependency(path, content, "core", "2.0.0")
+            .expect("update dep");
+        assert!(updated.contains("bazel_dep(name = \"core\", version = \"2.0.0\")"));
+        let bumped = plugin
+            .update_version(path, content, &Version::new("1.3.0", VersionKind::Semver))
+            .expect("bump");
+        assert!(bumped.starts_with("module(name = \"api\", version = \"1.3.0\")"));
+
+        let plugin = CustomPlugin::named("deps-test");
+        let path = Path::new("deps.yaml");
+        let content = "release:\n  version: 0.4.0\nrequires:\n  core: ^1.0\n";
+        assert_eq!(
+            plugin.parse_version(path, content).expect("version"),
+            Some(Version::new("0.4.0", VersionKind::Semver))
+        );
+        let updated = plugin
+            .update_dependency(path, content, "core", "^2.0")
+            .expect("update dep");
+        let deps = plugin.parse_dependencies(path, &updated).expect("deps");
+        assert_eq!(deps[0].constraint.raw, "^2.0");
+
+        let unknown = CustomPlugin::named("unregistered");
+        assert!(unknown.file_patterns().is_empty());
+        assert_eq!(unknown.default_test_command(), None);
     }
 }
@@ -133,6 +133,64 @@ impl EcosystemPlugin for GoPlugin {
         Ok(out.join("\n"))
     }
 
+    fn link_dependency(
+        &self,
+        path: &Path,
+        content: &str,
+        dep: &str,
+        local: &Path,
+    ) -> Result<Option<String>> {
+        if path.file_name().and_then(|n| n.to_str()) != Some("go.mod") {
+            return Ok(None);
+        }
+        // go.mod rejects a second replace for the same module, so drop any
+        // existing one before adding the local path.
+        let mut out = Vec::new();
+        let mut in_block = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("replace (") {
+                in_block = true;
+                out.push(line);
+                continue;
+            }
+            if in_block && trimmed.starts_with(')') {
+                in_block = false;
+                out.push(line);
+                continue;
+            }
+            let target = if in_block {
+                trimmed.split_whitespace().next()
+            } else {
+                trimmed
+                    .strip_prefix("replace ")
+                    .and_then(|rest| rest.split_whitespace().next())
+            };
+            if target != Some(dep) {
+                out.push(line);
+            }
+        }
+        let mut linked = out.join("\n").trim_end().to_string();
+        linked.push_str(&format!("\n\nreplace {dep} => {}\n", local.display()));
+        Ok(Some(linked))
+    }
+
+    fn clean_command(&self, shared_caches: bool) -> Option<&'static str> {
+        if shared_caches {
+            Some("go clean -cache")
+        } else {
+            Some("go clean")
+        }
+    }
+
+    fn prefetch_command(&self, _repo_path: &Path) -> Option<&'static str> {
+        Some("go mod download")
+    }
+
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &["coverage.out", "coverage.xml"]
+    }
+
     fn default_test_command(&self) -> Option<&'static str> {
         Some("go test ./...")
     }
@@ -171,4 +229,35 @@ require (
             .expect("update dep");
         assert!(updated.contains("example.com/core v1.3.0"));
     }
+
+    #[test]
+    fn linking_replaces_an_existing_replace_for_the_module() {
+        let plugin = GoPlugin;
+        let path = std::path::Path::new("go.mod");
+        let content = r#"module example.com/svc
+
+require example.com/core v1.2.3
+
+replace example.com/core => ../old-core
+
+replace (
+	example.com/core v1.2.3 => ../pinned-core
+	example.com/util => ../util
+)
+"#;
+
+        let linked = plugin
+            .link_dependency(
+                path,
+                content,
+                "example.com/core",
+                std::path::Path::new("/w/core"),
+            )
+            .expect("link dep")
+            .expect("go.mod is linked");
+        assert_eq!(linked.matches("example.com/core =>").count(), 1);
+        assert!(!linked.contains("example.com/core v1.2.3 =>"));
+        assert!(linked.contains("example.com/util => ../util"));
+        assert!(linked.ends_with("replace example.com/core => /w/core\n"));
+    }
 }
@@ -4,13 +4,30 @@ pub enum EcosystemId {
     Rust,
     Node,
     Go,
+    Php,
     Java,
     Custom(String),
 }
 
+impl EcosystemId {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EcosystemId::Python => "python",
+            EcosystemId::Rust => "rust",
+            EcosystemId::Node => "node",
+            EcosystemId::Go => "go",
+            EcosystemId::Php => "php",
+            EcosystemId::Java => "java",
+            EcosystemId::Custom(name) => name,
+        }
+    }
+}
+
+pub mod cache;
 pub mod custom;
 pub mod go;
 pub mod node;
+pub mod php;
 pub mod python;
 pub mod rust;
 pub mod traits;
@@ -21,7 +38,8 @@ pub fn plugin_for(id: &EcosystemId) -> Box<dyn traits::EcosystemPlugin> {
         EcosystemId::Rust => Box::new(rust::RustPlugin),
         EcosystemId::Node => Box::new(node::NodePlugin),
         EcosystemId::Go => Box::new(go::GoPlugin),
-        EcosystemId::Java => Box::new(custom::CustomPlugin),
-        EcosystemId::Custom(_) => Box::new(custom::CustomPlugin),
+        EcosystemId::Php => Box::new(php::PhpPlugin),
+        EcosystemId::Java => Box::new(custom::CustomPlugin::default()),
+        EcosystemId::Custom(name) => Box::new(custom::CustomPlugin::named(name)),
     }
 }
@@ -110,6 +110,29 @@ impl EcosystemPlugin for NodePlugin {
             .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
     }
 
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        Some("rm -rf node_modules")
+    }
+
+    /// Picks the package manager from the lockfile; `pnpm fetch` fills the
+    /// store without touching `node_modules`, the others install without
+    /// running lifecycle scripts.
+    fn prefetch_command(&self, repo_path: &Path) -> Option<&'static str> {
+        if repo_path.join("pnpm-lock.yaml").is_file() {
+            Some("pnpm fetch")
+        } else if repo_path.join("yarn.lock").is_file() {
+            Some("yarn install --frozen-lockfile --ignore-scripts")
+        } else if repo_path.join("package-lock.json").is_file() {
+            Some("npm ci --ignore-scripts --no-audit --no-fund")
+        } else {
+            Some("npm install --ignore-scripts --no-audit --no-fund")
+        }
+    }
+
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &["coverage/lcov.info", "coverage/cobertura-coverage.xml"]
+    }
+
     fn default_test_command(&self) -> Option<&'static str> {
         Some("npm test")
     }
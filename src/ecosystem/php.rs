@@ -0,0 +1,337 @@
+use std::ops::Range;
+use std::path::Path;
+
+use crate::core::repo::Dependency;
+use crate::core::version::{Version, VersionKind, VersionReq};
+use crate::ecosystem::traits::EcosystemPlugin;
+use crate::error::{HarmoniaError, Result};
+
+pub struct PhpPlugin;
+
+const REQUIRE_KEYS: [&str; 2] = ["require", "require-dev"];
+
+impl PhpPlugin {
+    fn is_composer_json(path: &Path) -> bool {
+        path.file_name().and_then(|n| n.to_str()) == Some("composer.json")
+    }
+
+    fn parse(content: &str) -> Result<serde_json::Value> {
+        serde_json::from_str(content).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
+    }
+
+    /// `php`, `ext-*`, `lib-*`, and Composer's own API are platform
+    /// requirements, not packages.
+    fn is_platform_package(name: &str) -> bool {
+        name == "php"
+            || name.starts_with("php-")
+            || name.starts_with("ext-")
+            || name.starts_with("lib-")
+            || name.starts_with("composer-")
+            || name == "composer"
+    }
+
+    /// Replaces the string value of `key` inside the object spanning
+    /// `object`, leaving the rest of the file byte-for-byte intact. Composer
+    /// files are hand-edited, so they are not re-serialized.
+    fn replace_member(
+        content: &str,
+        object: Range<usize>,
+        key: &str,
+        value: &str,
+    ) -> Option<String> {
+        let span = member_value_span(content, object, key)?;
+        if !content[span.clone()].starts_with('"') {
+            return None;
+        }
+        let mut updated = String::with_capacity(content.len());
+        updated.push_str(&content[..span.start]);
+        updated.push_str(&serde_json::Value::String(value.to_string()).to_string());
+        updated.push_str(&content[span.end..]);
+        Some(updated)
+    }
+}
+
+impl EcosystemPlugin for PhpPlugin {
+    fn id(&self) -> &'static str {
+        "php"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["composer.json"]
+    }
+
+    fn parse_version(&self, path: &Path, content: &str) -> Result<Option<Version>> {
+        if !Self::is_composer_json(path) {
+            return Ok(None);
+        }
+        let value = Self::parse(content)?;
+        Ok(value
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| Version::new(v, VersionKind::Semver)))
+    }
+
+    fn parse_dependencies(&self, path: &Path, content: &str) -> Result<Vec<Dependency>> {
+        if !Self::is_composer_json(path) {
+            return Ok(Vec::new());
+        }
+        let value = Self::parse(content)?;
+        let mut deps = Vec::new();
+        for key in REQUIRE_KEYS {
+            let Some(map) = value.get(key).and_then(|v| v.as_object()) else {
+                continue;
+            };
+            deps.extend(
+                map.iter()
+                    .filter(|(name, _)| !Self::is_platform_package(name))
+                    .filter_map(|(name, value)| value.as_str().map(|v| (name, v)))
+                    .map(|(name, value)| Dependency {
+                        name: name.to_string(),
+                        constraint: VersionReq::new(value),
+                        is_internal: false,
+                    }),
+            );
+        }
+        Ok(deps)
+    }
+
+    fn update_version(&self, path: &Path, content: &str, new_version: &Version) -> Result<String> {
+        if !Self::is_composer_json(path) {
+            return Ok(content.to_string());
+        }
+        Self::parse(content)?;
+        let Some(root) = root_object_span(content) else {
+            return Ok(content.to_string());
+        };
+        if let Some(updated) =
+            Self::replace_member(content, root.clone(), "version", &new_version.raw)
+        {
+            return Ok(updated);
+        }
+        // No version yet: add it as the first member.
+        let indent = member_indent(content, root.clone()).unwrap_or_else(|| "    ".to_string());
+        let mut updated = String::with_capacity(content.len() + 32);
+        updated.push_str(&content[..root.start + 1]);
+        updated.push_str(&format!(
+            "\n{indent}\"version\": {},",
+            serde_json::Value::String(new_version.raw.clone())
+        ));
+        updated.push_str(&content[root.start + 1..]);
+        Ok(updated)
+    }
+
+    fn update_dependency(
+        &self,
+        path: &Path,
+        content: &str,
+        dep: &str,
+        constraint: &str,
+    ) -> Result<String> {
+        if !Self::is_composer_json(path) {
+            return Ok(content.to_string());
+        }
+        Self::parse(content)?;
+        let Some(root) = root_object_span(content) else {
+            return Ok(content.to_string());
+        };
+        for key in REQUIRE_KEYS {
+            let Some(section) = member_value_span(content, root.clone(), key) else {
+                continue;
+            };
+            if let Some(updated) = Self::replace_member(content, section, dep, constraint) {
+                return Ok(updated);
+            }
+        }
+        Ok(content.to_string())
+    }
+
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        Some("rm -rf vendor")
+    }
+
+    fn prefetch_command(&self, _repo_path: &Path) -> Option<&'static str> {
+        Some("composer install --no-scripts --no-autoloader --no-interaction")
+    }
+
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &["coverage.xml"]
+    }
+
+    fn default_test_command(&self) -> Option<&'static str> {
+        Some("vendor/bin/phpunit")
+    }
+
+    fn default_lint_command(&self) -> Option<&'static str> {
+        Some("vendor/bin/phpstan analyse && vendor/bin/php-cs-fixer fix --dry-run --diff")
+    }
+}
+
+/// Byte range of the document's root object, braces included.
+fn root_object_span(content: &str) -> Option<Range<usize>> {
+    let start = content.find('{')?;
+    let end = skip_value(content.as_bytes(), start)?;
+    Some(start..end)
+}
+
+/// Byte range of the value of member `key` directly inside `object`.
+fn member_value_span(content: &str, object: Range<usize>, key: &str) -> Option<Range<usize>> {
+    let bytes = content.as_bytes();
+    if bytes.get(object.start) != Some(&b'{') {
+        return None;
+    }
+    let mut index = object.start + 1;
+    loop {
+        index = skip_whitespace(bytes, index);
+        match bytes.get(index)? {
+            b'}' => return None,
+            b',' => {
+                index += 1;
+                continue;
+            }
+            b'"' => {}
+            _ => return None,
+        }
+        let key_end = skip_string(bytes, index)?;
+        let name: String = serde_json::from_str(&content[index..key_end]).ok()?;
+        index = skip_whitespace(bytes, key_end);
+        if bytes.get(index) != Some(&b':') {
+            return None;
+        }
+        let value_start = skip_whitespace(bytes, index + 1);
+        let value_end = skip_value(bytes, value_start)?;
+        if value_end > object.end {
+            return None;
+        }
+        if name == key {
+            return Some(value_start..value_end);
+        }
+        index = value_end;
+    }
+}
+
+/// Leading whitespace of the first member of `object`, on its own line.
+fn member_indent(content: &str, object: Range<usize>) -> Option<String> {
+    let inner = &content[object.start + 1..object.end];
+    let line = inner.split('\n').nth(1)?;
+    let indent: String = line
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    (!indent.is_empty()).then_some(indent)
+}
+
+fn skip_whitespace(bytes: &[u8], mut index: usize) -> usize {
+    while bytes.get(index).is_some_and(|b| b.is_ascii_whitespace()) {
+        index += 1;
+    }
+    index
+}
+
+/// End of the string starting at the quote at `start`.
+fn skip_string(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+    loop {
+        match bytes.get(index)? {
+            b'\\' => index += 2,
+            b'"' => return Some(index + 1),
+            _ => index += 1,
+        }
+    }
+}
+
+/// End of the JSON value starting at `start`.
+fn skip_value(bytes: &[u8], start: usize) -> Option<usize> {
+    match bytes.get(start)? {
+        b'"' => skip_string(bytes, start),
+        b'{' | b'[' => {
+            let mut depth = 0usize;
+            let mut index = start;
+            loop {
+                match bytes.get(index)? {
+                    b'"' => {
+                        index = skip_string(bytes, index)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(index + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                index += 1;
+            }
+        }
+        _ => {
+            let mut index = start;
+            while bytes
+                .get(index)
+                .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+            {
+                index += 1;
+            }
+            Some(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::version::{Version, VersionKind};
+    use crate::ecosystem::php::PhpPlugin;
+    use crate::ecosystem::traits::EcosystemPlugin;
+
+    const COMPOSER: &str = r#"{
+    "name": "acme/api",
+    "extra": { "version": "keep" },
+    "require": {
+        "php": "^8.2",
+        "ext-json": "*",
+        "acme/core": "^1.0"
+    },
+    "require-dev": {
+        "phpunit/phpunit": "^10.5"
+    }
+}
+"#;
+
+    #[test]
+    fn parses_require_entries_without_platform_packages() {
+        let plugin = PhpPlugin;
+        let path = std::path::Path::new("composer.json");
+        let deps = plugin.parse_dependencies(path, COMPOSER).expect("deps");
+        let names: Vec<&str> = deps.iter().map(|dep| dep.name.as_str()).collect();
+        assert_eq!(names, vec!["acme/core", "phpunit/phpunit"]);
+        assert_eq!(plugin.parse_version(path, COMPOSER).expect("version"), None);
+    }
+
+    #[test]
+    fn updates_constraints_and_version_in_place() {
+        let plugin = PhpPlugin;
+        let path = std::path::Path::new("composer.json");
+        let updated = plugin
+            .update_dependency(path, COMPOSER, "acme/core", "^2.0")
+            .expect("update dep");
+        assert_eq!(
+            updated,
+            COMPOSER.replace("\"acme/core\": \"^1.0\"", "\"acme/core\": \"^2.0\"")
+        );
+
+        let version = Version::new("1.3.0", VersionKind::Semver);
+        let added = plugin
+            .update_version(path, COMPOSER, &version)
+            .expect("add version");
+        assert!(added.starts_with("{\n    \"version\": \"1.3.0\",\n    \"name\""));
+        assert!(added.contains("\"extra\": { \"version\": \"keep\" }"));
+        let bumped = plugin
+            .update_version(path, &added, &Version::new("1.4.0", VersionKind::Semver))
+            .expect("bump version");
+        assert_eq!(bumped, added.replace("1.3.0", "1.4.0"));
+        assert_eq!(
+            plugin.parse_version(path, &bumped).expect("version"),
+            Some(Version::new("1.4.0", VersionKind::Semver))
+        );
+    }
+}
@@ -172,6 +172,31 @@ impl EcosystemPlugin for PythonPlugin {
         toml::to_string(&value).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
     }
 
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        Some("rm -rf build dist .pytest_cache .mypy_cache .ruff_cache")
+    }
+
+    /// uv projects sync from their lockfile; anything else goes through
+    /// `pip download` into a scratch directory, which leaves the wheels in
+    /// pip's cache.
+    fn prefetch_command(&self, repo_path: &Path) -> Option<&'static str> {
+        if repo_path.join("uv.lock").is_file() {
+            Some("uv sync --frozen --no-install-project")
+        } else if repo_path.join("requirements.txt").is_file() {
+            Some(
+                "dir=$(mktemp -d) && pip download -q -r requirements.txt -d \"$dir\"; status=$?; rm -rf \"$dir\"; exit $status",
+            )
+        } else {
+            Some(
+                "dir=$(mktemp -d) && pip download -q . -d \"$dir\"; status=$?; rm -rf \"$dir\"; exit $status",
+            )
+        }
+    }
+
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &["coverage.xml"]
+    }
+
     fn default_test_command(&self) -> Option<&'static str> {
         Some("pytest")
     }
@@ -124,6 +124,57 @@ impl EcosystemPlugin for RustPlugin {
         toml::to_string(&value).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
     }
 
+    fn link_dependency(
+        &self,
+        path: &Path,
+        content: &str,
+        dep: &str,
+        local: &Path,
+    ) -> Result<Option<String>> {
+        if path.file_name().and_then(|n| n.to_str()) != Some("Cargo.toml") {
+            return Ok(None);
+        }
+        let mut value: toml::Value =
+            toml::from_str(content).map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+        let Some(root) = value.as_table_mut() else {
+            return Ok(None);
+        };
+        let mut entry = toml::value::Table::new();
+        entry.insert(
+            "path".to_string(),
+            toml::Value::String(local.to_string_lossy().into_owned()),
+        );
+        let registry = root
+            .entry("patch")
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            .as_table_mut()
+            .map(|patch| {
+                patch
+                    .entry("crates-io")
+                    .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            })
+            .and_then(|registry| registry.as_table_mut());
+        let Some(registry) = registry else {
+            return Ok(None);
+        };
+        registry.insert(dep.to_string(), toml::Value::Table(entry));
+        toml::to_string(&value)
+            .map(Some)
+            .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
+    }
+
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        Some("cargo clean")
+    }
+
+    fn prefetch_command(&self, _repo_path: &Path) -> Option<&'static str> {
+        Some("cargo fetch")
+    }
+
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &["target/lcov.info", "lcov.info", "cobertura.xml"]
+    }
+
     fn default_test_command(&self) -> Option<&'static str> {
         Some("cargo test")
     }
@@ -167,4 +218,26 @@ mod tests {
             .expect("update version");
         assert!(updated_version.contains("version = \"0.2.0\""));
     }
+
+    #[test]
+    fn links_dependency_to_local_checkout_with_a_crates_io_patch() {
+        let plugin = RustPlugin;
+        let path = std::path::Path::new("Cargo.toml");
+        let content = "[package]\nname = \"svc\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore = \"0.1\"\n\n[patch.crates-io]\nserde = { path = \"../serde\" }\n";
+
+        let linked = plugin
+            .link_dependency(
+                path,
+                content,
+                "core",
+                std::path::Path::new("/ws/repos/core"),
+            )
+            .expect("link dep")
+            .expect("cargo manifests can be linked");
+        let value: toml::Value = toml::from_str(&linked).expect("valid toml");
+        let patch = &value["patch"]["crates-io"];
+        assert_eq!(patch["core"]["path"].as_str(), Some("/ws/repos/core"));
+        assert_eq!(patch["serde"]["path"].as_str(), Some("../serde"));
+        assert_eq!(value["dependencies"]["core"].as_str(), Some("0.1"));
+    }
 }
@@ -7,6 +7,14 @@ use crate::error::Result;
 pub trait EcosystemPlugin: Send + Sync {
     fn id(&self) -> &'static str;
     fn file_patterns(&self) -> &'static [&'static str];
+    /// Files the version is read from, first match wins.
+    fn version_files(&self) -> &'static [&'static str] {
+        self.file_patterns()
+    }
+    /// Files dependencies are read from, first match wins.
+    fn dependency_files(&self) -> &'static [&'static str] {
+        self.file_patterns()
+    }
     fn parse_version(&self, path: &Path, content: &str) -> Result<Option<Version>>;
     fn parse_dependencies(&self, path: &Path, content: &str) -> Result<Vec<Dependency>>;
     fn update_version(&self, path: &Path, content: &str, new_version: &Version) -> Result<String>;
@@ -17,6 +25,42 @@ pub trait EcosystemPlugin: Send + Sync {
         dep: &str,
         constraint: &str,
     ) -> Result<String>;
+    /// Manifest content that resolves `dep` from the checkout at `local`
+    /// instead of a registry, or `None` when the ecosystem has no manifest
+    /// override for it. Used by `plan --verify`, which restores the original
+    /// content afterwards.
+    fn link_dependency(
+        &self,
+        _path: &Path,
+        _content: &str,
+        _dep: &str,
+        _local: &Path,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+    /// Shell command that deletes build outputs from a checkout, for
+    /// `clean --build-artifacts`. `shared_caches` opts into also clearing
+    /// caches kept outside the repo, such as Go's build cache.
+    fn clean_command(&self, _shared_caches: bool) -> Option<&'static str> {
+        None
+    }
+    /// Shell command that downloads the dependencies of the checkout at
+    /// `repo_path` into the tool's cache without building or running package
+    /// scripts, for `harmonia prefetch`.
+    fn prefetch_command(&self, _repo_path: &Path) -> Option<&'static str> {
+        None
+    }
+    /// Coverage reports the ecosystem's test tooling writes, relative to the
+    /// repo root, in the order `test --coverage` looks for them. Each may be
+    /// lcov, Cobertura XML, or a Go cover profile.
+    fn coverage_reports(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Command the ecosystem provides for `harmonia run <name>` when neither
+    /// the repo nor the workspace defines that hook.
+    fn hook_command(&self, _name: &str) -> Option<&'static str> {
+        None
+    }
     fn default_test_command(&self) -> Option<&'static str>;
     fn default_lint_command(&self) -> Option<&'static str>;
 }
@@ -2,24 +2,31 @@ use std::collections::HashMap;
 
 use reqwest::blocking::Client;
 use reqwest::blocking::Response;
-use reqwest::Method;
+use reqwest::{Method, StatusCode};
 use serde_json::Value;
 
 use crate::core::repo::RepoId;
 use crate::error::{HarmoniaError, Result};
+use crate::forge::retry::RetryPolicy;
+use crate::forge::timestamp_duration_secs;
 use crate::forge::traits::{
-    CreateIssueParams, CreateMrParams, Forge, MergeMrParams, UpdateMrParams,
+    CreateIssueParams, CreateMrParams, CreateReleaseParams, Forge, ListIssuesParams, MergeMrParams,
+    UpdateMrParams,
 };
 use crate::forge::{
-    CheckRun, CiState, CiStatus, Issue, IssueState, MergeRequest, MrId, MrState, Pipeline, User,
+    CheckRun, CiState, CiStatus, ForgeProject, Issue, IssueState, MergeRequest, MergeRequirements,
+    MrChanges, MrFileChange, MrId, MrState, Pipeline, Release, User,
 };
 
+const REPOS_PER_PAGE: usize = 100;
+
 #[derive(Debug, Clone)]
 pub struct GitHubClient {
     pub host: String,
     pub token: String,
     pub default_group: Option<String>,
     client: Client,
+    retry: RetryPolicy,
 }
 
 impl GitHubClient {
@@ -34,9 +41,15 @@ impl GitHubClient {
             token: token.into(),
             default_group,
             client: Client::new(),
+            retry: RetryPolicy::default(),
         }
     }
 
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     fn api_url(&self, path: &str) -> String {
         format!("{}/{}", self.host, path.trim_start_matches('/'))
     }
@@ -55,17 +68,17 @@ impl GitHubClient {
         raw.to_string()
     }
 
-    fn send_json(
+    fn send(
         &self,
         method: Method,
         path: &str,
         query: Option<&[(&str, String)]>,
         body: Option<Value>,
-    ) -> Result<Value> {
+    ) -> Result<Response> {
         let url = self.api_url(path);
         let mut request = self
             .client
-            .request(method, &url)
+            .request(method.clone(), &url)
             .header("Authorization", format!("Bearer {}", self.token))
             .header("Accept", "application/vnd.github+json")
             .header("X-GitHub-Api-Version", "2022-11-28")
@@ -78,14 +91,34 @@ impl GitHubClient {
             request = request.json(&body);
         }
 
-        let response = request.send().map_err(|err| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "github request failed for {}: {}",
-                url, err
-            )))
-        })?;
+        self.retry.send("github", &method, &url, request)
+    }
+
+    fn send_json(
+        &self,
+        method: Method,
+        path: &str,
+        query: Option<&[(&str, String)]>,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        parse_json_response(self.send(method, path, query, body)?)
+    }
 
-        parse_json_response(response)
+    /// GET that yields `None` when the resource is missing or the token may
+    /// not read it, for settings that only some projects have.
+    fn get_optional_json(
+        &self,
+        path: &str,
+        query: Option<&[(&str, String)]>,
+    ) -> Result<Option<Value>> {
+        let response = self.send(Method::GET, path, query, None)?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+        parse_json_response(response).map(Some)
     }
 
     fn get_json(&self, path: &str, query: Option<&[(&str, String)]>) -> Result<Value> {
@@ -230,6 +263,11 @@ impl GitHubClient {
             })?;
 
         let title = json_string_field(value, "title")?;
+        let description = value
+            .get("body")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string();
         let url = value
             .get("html_url")
             .and_then(|value| value.as_str())
@@ -245,6 +283,7 @@ impl GitHubClient {
             id,
             iid,
             title,
+            description,
             url,
             state,
         })
@@ -427,6 +466,59 @@ impl Forge for GitHubClient {
         self.parse_pull_request(&response)
     }
 
+    fn get_mr_changes(&self, repo: &RepoId, mr_id: &MrId) -> Result<MrChanges> {
+        let project = self.parse_project_group(repo)?;
+        let iid = self.parse_pull_request_iid(mr_id)?;
+        let path = format!("/repos/{}/pulls/{}/files", encode_repo_path(&project), iid);
+        let mut changes = MrChanges::default();
+        for page in 1.. {
+            let query = vec![
+                ("per_page", REPOS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "github pull request files response was not an array"
+                ))
+            })?;
+            changes.files.extend(values.iter().map(|value| {
+                MrFileChange {
+                    path: value
+                        .get("filename")
+                        .and_then(|value| value.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    additions: value
+                        .get("additions")
+                        .and_then(|value| value.as_u64())
+                        .unwrap_or(0),
+                    deletions: value
+                        .get("deletions")
+                        .and_then(|value| value.as_u64())
+                        .unwrap_or(0),
+                }
+            }));
+            if values.len() < REPOS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+
+    fn find_open_mr(&self, repo: &RepoId, source_branch: &str) -> Result<Option<MergeRequest>> {
+        let project = self.parse_project_group(repo)?;
+        let path = format!("/repos/{}/pulls", encode_repo_path(&project));
+        let response = self.get_json(&path, Some(&open_pulls_query(&project, source_branch)))?;
+        let values = response.as_array().ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!("github pulls response was not an array"))
+        })?;
+        values
+            .first()
+            .map(|value| self.parse_pull_request(value))
+            .transpose()
+    }
+
     fn update_mr(
         &self,
         repo: &RepoId,
@@ -494,11 +586,8 @@ impl Forge for GitHubClient {
         let iid = self.parse_pull_request_iid(mr_id)?;
         let path = format!("/repos/{}/pulls/{}/merge", encode_repo_path(&project), iid);
 
-        let payload = serde_json::json!({
-            "merge_method": if params.squash { "squash" } else { "merge" },
-            "delete_branch": params.delete_source_branch,
-        });
-        self.put_json(&path, None, Some(payload)).map(|_| ())
+        self.put_json(&path, None, Some(merge_payload(&params)))
+            .map(|_| ())
     }
 
     fn close_mr(&self, repo: &RepoId, mr_id: &MrId) -> Result<()> {
@@ -512,6 +601,28 @@ impl Forge for GitHubClient {
         self.patch_json(&path, None, Some(payload)).map(|_| ())
     }
 
+    fn get_merge_requirements(&self, repo: &RepoId, branch: &str) -> Result<MergeRequirements> {
+        let project = self.parse_project_group(repo)?;
+        let protection = format!(
+            "/repos/{}/branches/{}/protection",
+            encode_repo_path(&project),
+            encode_ref(branch)
+        );
+        let rules = format!(
+            "/repos/{}/rules/branches/{}",
+            encode_repo_path(&project),
+            encode_ref(branch)
+        );
+        let mut requirements = self
+            .get_optional_json(&protection, None)?
+            .map(|value| parse_branch_protection(&value))
+            .unwrap_or_default();
+        if let Some(value) = self.get_optional_json(&rules, None)? {
+            requirements = requirements.merge(parse_branch_rules(&value));
+        }
+        Ok(requirements)
+    }
+
     fn get_ci_status(&self, repo: &RepoId, ref_name: &str) -> Result<CiStatus> {
         let project = self.parse_project_group(repo)?;
         let path = format!(
@@ -521,51 +632,27 @@ impl Forge for GitHubClient {
         );
 
         let response = self.get_json(&path, None)?;
-        let checks = response
+        let statuses = response
             .get("statuses")
             .and_then(|value| value.as_array())
-            .map(|statuses| {
-                statuses
-                    .iter()
-                    .filter_map(|status| {
-                        let name = status.get("context")?.as_str()?;
-                        let state = status.get("state")?.as_str()?;
-                        Some(CheckRun {
-                            name: name.to_string(),
-                            status: state.to_string(),
-                        })
-                    })
-                    .collect::<Vec<_>>()
-            })
+            .cloned()
             .unwrap_or_default();
-
-        let pipelines = response
-            .get("statuses")
-            .and_then(|value| value.as_array())
-            .map(|statuses| {
-                statuses
-                    .iter()
-                    .map(|status| Pipeline {
-                        id: status
-                            .get("id")
-                            .and_then(|value| value.as_u64())
-                            .map(|value| value.to_string())
-                            .or_else(|| {
-                                status
-                                    .get("context")
-                                    .and_then(|value| value.as_str())
-                                    .map(|value| value.to_string())
-                            })
-                            .unwrap_or_else(|| ref_name.to_string()),
-                        status: status
-                            .get("state")
-                            .and_then(|value| value.as_str())
-                            .unwrap_or("unknown")
-                            .to_string(),
-                    })
-                    .collect::<Vec<_>>()
+        let checks = statuses
+            .iter()
+            .filter_map(|status| {
+                let name = status.get("context")?.as_str()?;
+                let state = status.get("state")?.as_str()?;
+                Some(CheckRun {
+                    name: name.to_string(),
+                    status: state.to_string(),
+                    url: status_target_url(status),
+                })
             })
-            .unwrap_or_default();
+            .collect::<Vec<_>>();
+        let pipelines = statuses
+            .iter()
+            .map(|status| pipeline_from_status(status, ref_name))
+            .collect::<Vec<_>>();
 
         let overall = response
             .get("state")
@@ -601,7 +688,80 @@ impl Forge for GitHubClient {
         self.parse_issue(&response)
     }
 
-    fn get_user(&self, username: &str) -> Result<User> {
+    fn get_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        let project = self.parse_project_group(project)?;
+        let path = format!("/repos/{}/issues/{}", encode_repo_path(&project), iid);
+        let response = self.get_json(&path, None)?;
+        self.parse_issue(&response)
+    }
+
+    fn update_issue(&self, project: &RepoId, iid: u64, description: &str) -> Result<Issue> {
+        let project = self.parse_project_group(project)?;
+        let path = format!("/repos/{}/issues/{}", encode_repo_path(&project), iid);
+        let payload = serde_json::json!({ "body": description });
+        let response = self.patch_json(&path, None, Some(payload))?;
+        self.parse_issue(&response)
+    }
+
+    fn list_issues(&self, project: &RepoId, params: ListIssuesParams) -> Result<Vec<Issue>> {
+        let project = self.parse_project_group(project)?;
+        let path = format!("/repos/{}/issues", encode_repo_path(&project));
+        let state = match params.state {
+            Some(IssueState::Open) => "open",
+            Some(IssueState::Closed) => "closed",
+            None => "all",
+        };
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let mut query = vec![
+                ("state", state.to_string()),
+                ("sort", "created".to_string()),
+                ("direction", "desc".to_string()),
+                ("per_page", REPOS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            if !params.labels.is_empty() {
+                query.push(("labels", params.labels.join(",")));
+            }
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!("github issues response was not an array"))
+            })?;
+            for value in values {
+                // The issues endpoint also returns pull requests.
+                if value.get("pull_request").is_none() {
+                    issues.push(self.parse_issue(value)?);
+                }
+            }
+            if values.len() < REPOS_PER_PAGE || issues.len() >= params.limit {
+                break;
+            }
+        }
+        issues.truncate(params.limit);
+        Ok(issues)
+    }
+
+    fn close_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        let project = self.parse_project_group(project)?;
+        let path = format!("/repos/{}/issues/{}", encode_repo_path(&project), iid);
+        let payload = serde_json::json!({ "state": "closed" });
+        let response = self.patch_json(&path, None, Some(payload))?;
+        self.parse_issue(&response)
+    }
+
+    fn comment_issue(&self, project: &RepoId, iid: u64, body: &str) -> Result<()> {
+        let project = self.parse_project_group(project)?;
+        let path = format!(
+            "/repos/{}/issues/{}/comments",
+            encode_repo_path(&project),
+            iid
+        );
+        let payload = serde_json::json!({ "body": body });
+        self.post_json(&path, None, Some(payload))?;
+        Ok(())
+    }
+
+    fn get_user(&self, _repo: &RepoId, username: &str) -> Result<User> {
         let username = username.trim();
         if username.is_empty() {
             return Err(HarmoniaError::Other(anyhow::anyhow!(
@@ -618,6 +778,91 @@ impl Forge for GitHubClient {
             )))
         })
     }
+
+    fn create_release(&self, repo: &RepoId, params: CreateReleaseParams) -> Result<Release> {
+        let project = self.parse_project_group(repo)?;
+        let path = format!("/repos/{}/releases", encode_repo_path(&project));
+        let response = self.post_json(&path, None, Some(release_payload(&params)))?;
+        parse_release(&response).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "github release '{}' response missing required fields",
+                params.tag
+            )))
+        })
+    }
+
+    fn list_projects(&self, group: &str) -> Result<Vec<ForgeProject>> {
+        let org = group.trim().trim_matches('/');
+        let path = format!("/orgs/{}/repos", encode_path(org));
+        let mut projects = Vec::new();
+        for page in 1.. {
+            let query = vec![
+                ("type", "all".to_string()),
+                ("sort", "full_name".to_string()),
+                ("per_page", REPOS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "github org repos response was not an array"
+                ))
+            })?;
+            for value in values {
+                projects.push(parse_repository(value)?);
+            }
+            if values.len() < REPOS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(projects)
+    }
+
+    fn get_project(&self, repo: &RepoId) -> Result<ForgeProject> {
+        let project = self.parse_project_group(repo)?;
+        let path = format!("/repos/{}", encode_repo_path(&project));
+        parse_repository(&self.get_json(&path, None)?)
+    }
+
+    fn count_open_mrs(&self, repo: &RepoId) -> Result<usize> {
+        let project = self.parse_project_group(repo)?;
+        let path = format!("/repos/{}/pulls", encode_repo_path(&project));
+        let mut count = 0;
+        for page in 1.. {
+            let query = vec![
+                ("state", "open".to_string()),
+                ("per_page", REPOS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!("github pulls response was not an array"))
+            })?;
+            count += values.len();
+            if values.len() < REPOS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    fn count_open_reviews(&self, _repo: &RepoId, username: &str) -> Result<usize> {
+        let query = vec![
+            (
+                "q",
+                format!("type:pr state:open review-requested:{}", username),
+            ),
+            ("per_page", "1".to_string()),
+        ];
+        let response = self.get_json("/search/issues", Some(&query))?;
+        response
+            .get("total_count")
+            .and_then(|value| value.as_u64())
+            .map(|count| count as usize)
+            .ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!("github search response had no total_count"))
+            })
+    }
 }
 
 fn normalize_host(host: &str) -> String {
@@ -704,6 +949,40 @@ fn json_string_field(value: &Value, field: &str) -> Result<String> {
         })
 }
 
+fn parse_repository(value: &Value) -> Result<ForgeProject> {
+    let path = json_string_field(value, "full_name")?;
+    let namespace = value
+        .get("owner")
+        .and_then(|value| value.get("login"))
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| {
+            path.split_once('/')
+                .map(|(owner, _)| owner.to_string())
+                .unwrap_or_default()
+        });
+    Ok(ForgeProject {
+        name: json_string_field(value, "name")?,
+        path,
+        namespace,
+        ssh_url: json_string_field(value, "ssh_url")?,
+        http_url: json_string_field(value, "clone_url")?,
+        default_branch: value
+            .get("default_branch")
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string()),
+        archived: value
+            .get("archived")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false),
+        description: value
+            .get("description")
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string()),
+    })
+}
+
 fn parse_user(value: &Value) -> Option<User> {
     let username = value.get("login")?.as_str()?.to_string();
     let id = value.get("id").and_then(|value| value.as_u64());
@@ -731,11 +1010,227 @@ fn encode_path(value: &str) -> String {
     encoded
 }
 
+/// GitHub takes the squash commit title and body separately, so the message
+/// is split at its first line.
+fn merge_payload(params: &MergeMrParams) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "merge_method": if params.squash { "squash" } else { "merge" },
+        "delete_branch": params.delete_source_branch,
+    });
+    if let Some(message) = params
+        .squash_commit_message
+        .as_deref()
+        .filter(|_| params.squash)
+    {
+        let (title, body) = message.split_once('\n').unwrap_or((message, ""));
+        payload["commit_title"] = serde_json::Value::String(title.trim().to_string());
+        let body = body.trim();
+        if !body.is_empty() {
+            payload["commit_message"] = serde_json::Value::String(body.to_string());
+        }
+    }
+    payload
+}
+
+/// GitHub filters pulls by `owner:branch`, so PRs from forks with the same
+/// branch name are not matched.
+fn open_pulls_query(project: &str, source_branch: &str) -> Vec<(&'static str, String)> {
+    let owner = project.split('/').next().unwrap_or(project);
+    vec![
+        ("head", format!("{owner}:{source_branch}")),
+        ("state", "open".to_string()),
+    ]
+}
+
+fn status_target_url(status: &Value) -> Option<String> {
+    status
+        .get("target_url")
+        .and_then(|value| value.as_str())
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+/// A commit status as a pipeline. Statuses are updated in place, so the time
+/// from creation to the last update is how long the check has run.
+fn pipeline_from_status(status: &Value, ref_name: &str) -> Pipeline {
+    let text = |key: &str| status.get(key).and_then(|value| value.as_str());
+    Pipeline {
+        id: status
+            .get("id")
+            .and_then(|value| value.as_u64())
+            .map(|value| value.to_string())
+            .or_else(|| text("context").map(str::to_string))
+            .unwrap_or_else(|| ref_name.to_string()),
+        status: text("state").unwrap_or("unknown").to_string(),
+        url: status_target_url(status),
+        duration_secs: text("created_at")
+            .zip(text("updated_at"))
+            .and_then(|(created, updated)| timestamp_duration_secs(created, updated)),
+        triggered_by: status
+            .get("creator")
+            .and_then(|creator| creator.get("login"))
+            .and_then(|value| value.as_str())
+            .map(str::to_string),
+    }
+}
+
+/// Requirements from classic branch protection
+/// (`GET /repos/{repo}/branches/{branch}/protection`).
+fn parse_branch_protection(value: &Value) -> MergeRequirements {
+    let mut required_checks = Vec::new();
+    if let Some(status_checks) = value.get("required_status_checks") {
+        let contexts = status_checks
+            .get("contexts")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|context| context.as_str());
+        let checks = status_checks
+            .get("checks")
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|check| check.get("context").and_then(|value| value.as_str()));
+        required_checks.extend(contexts.chain(checks).map(str::to_string));
+    }
+    let required_approvals = value
+        .get("required_pull_request_reviews")
+        .and_then(|reviews| reviews.get("required_approving_review_count"))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(0) as u32;
+    MergeRequirements::default().merge(MergeRequirements {
+        required_checks,
+        required_approvals,
+    })
+}
+
+/// Requirements from repository rulesets
+/// (`GET /repos/{repo}/rules/branches/{branch}`).
+fn parse_branch_rules(value: &Value) -> MergeRequirements {
+    let mut requirements = MergeRequirements::default();
+    for rule in value.as_array().into_iter().flatten() {
+        let parameters = rule.get("parameters");
+        let rule_requirements = match rule.get("type").and_then(|value| value.as_str()) {
+            Some("required_status_checks") => MergeRequirements {
+                required_checks: parameters
+                    .and_then(|parameters| parameters.get("required_status_checks"))
+                    .and_then(|value| value.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|check| check.get("context").and_then(|value| value.as_str()))
+                    .map(str::to_string)
+                    .collect(),
+                required_approvals: 0,
+            },
+            Some("pull_request") => MergeRequirements {
+                required_checks: Vec::new(),
+                required_approvals: parameters
+                    .and_then(|parameters| parameters.get("required_approving_review_count"))
+                    .and_then(|value| value.as_u64())
+                    .unwrap_or(0) as u32,
+            },
+            _ => continue,
+        };
+        requirements = requirements.merge(rule_requirements);
+    }
+    requirements
+}
+
+fn release_payload(params: &CreateReleaseParams) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "tag_name": params.tag,
+        "name": params.name,
+        "body": params.notes,
+    });
+    if let Some(target) = params.target.as_deref() {
+        payload["target_commitish"] = serde_json::Value::String(target.to_string());
+    }
+    payload
+}
+
+fn parse_release(value: &Value) -> Option<Release> {
+    let tag = value.get("tag_name")?.as_str()?.to_string();
+    Some(Release {
+        name: value
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(tag.as_str())
+            .to_string(),
+        url: value
+            .get("html_url")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        tag,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::forge::github::{normalize_host, parse_pr_state, GitHubClient};
+    use crate::forge::github::{
+        merge_payload, normalize_host, open_pulls_query, parse_branch_protection,
+        parse_branch_rules, parse_pr_state, parse_release, parse_repository, pipeline_from_status,
+        release_payload, GitHubClient,
+    };
+    use crate::forge::traits::{CreateReleaseParams, MergeMrParams};
     use crate::forge::{CheckRun, CiState, Issue, IssueState, MrState};
 
+    #[test]
+    fn release_payload_uses_body_and_target_commitish() {
+        let payload = release_payload(&CreateReleaseParams {
+            tag: "v1.2.0".to_string(),
+            name: "v1.2.0".to_string(),
+            notes: "- rotate keys".to_string(),
+            target: Some("main".to_string()),
+        });
+        assert_eq!(payload["body"], "- rotate keys");
+        assert_eq!(payload["target_commitish"], "main");
+
+        let release = parse_release(&serde_json::json!({
+            "tag_name": "v1.2.0",
+            "name": "",
+            "html_url": "https://github.com/platform/api/releases/tag/v1.2.0",
+        }))
+        .expect("release");
+        assert_eq!(release.name, "v1.2.0");
+        assert_eq!(
+            release.url,
+            "https://github.com/platform/api/releases/tag/v1.2.0"
+        );
+    }
+
+    #[test]
+    fn open_pulls_query_filters_by_owner_and_branch() {
+        assert_eq!(
+            open_pulls_query("platform/api", "feature/auth"),
+            vec![
+                ("head", "platform:feature/auth".to_string()),
+                ("state", "open".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn squash_message_splits_into_commit_title_and_message() {
+        let params = MergeMrParams {
+            squash: true,
+            delete_source_branch: true,
+            squash_commit_message: Some("PLAT-1: rotate keys\n\nChangeset: auth".to_string()),
+        };
+        let payload = merge_payload(&params);
+        assert_eq!(payload["merge_method"], "squash");
+        assert_eq!(payload["commit_title"], "PLAT-1: rotate keys");
+        assert_eq!(payload["commit_message"], "Changeset: auth");
+
+        let payload = merge_payload(&MergeMrParams {
+            squash_commit_message: Some("only a title".to_string()),
+            ..params
+        });
+        assert_eq!(payload["commit_title"], "only a title");
+        assert!(payload.get("commit_message").is_none());
+    }
+
     #[test]
     fn normalizes_github_host() {
         assert_eq!(normalize_host("github.com"), "https://api.github.com");
@@ -757,6 +1252,65 @@ mod tests {
         assert_eq!(parse_pr_state(None, true), MrState::Draft);
     }
 
+    #[test]
+    fn reads_required_checks_and_approvals_from_protection_and_rules() {
+        let protection = parse_branch_protection(&serde_json::json!({
+            "required_status_checks": {
+                "contexts": ["ci/build", "ci/test"],
+                "checks": [{ "context": "ci/test", "app_id": 15368 }],
+            },
+            "required_pull_request_reviews": { "required_approving_review_count": 1 },
+        }));
+        assert_eq!(protection.required_checks, vec!["ci/build", "ci/test"]);
+        assert_eq!(protection.required_approvals, 1);
+
+        let rules = parse_branch_rules(&serde_json::json!([
+            { "type": "deletion" },
+            {
+                "type": "required_status_checks",
+                "parameters": { "required_status_checks": [{ "context": "lint" }] },
+            },
+            {
+                "type": "pull_request",
+                "parameters": { "required_approving_review_count": 2 },
+            },
+        ]));
+        let combined = protection.merge(rules);
+        assert_eq!(
+            combined.required_checks,
+            vec!["ci/build", "ci/test", "lint"]
+        );
+        assert_eq!(combined.required_approvals, 2);
+    }
+
+    #[test]
+    fn reads_status_links_durations_and_creators() {
+        let status = serde_json::json!({
+            "id": 42,
+            "state": "failure",
+            "context": "ci/build",
+            "target_url": "https://ci.example.com/builds/42",
+            "created_at": "2024-05-01T10:00:00Z",
+            "updated_at": "2024-05-01T10:03:20Z",
+            "creator": { "login": "ci-bot" },
+        });
+        let pipeline = pipeline_from_status(&status, "main");
+        assert_eq!(pipeline.id, "42");
+        assert_eq!(
+            pipeline.url.as_deref(),
+            Some("https://ci.example.com/builds/42")
+        );
+        assert_eq!(pipeline.duration_secs, Some(200));
+        assert_eq!(pipeline.triggered_by.as_deref(), Some("ci-bot"));
+
+        let pending = pipeline_from_status(
+            &serde_json::json!({ "context": "ci/lint", "state": "pending", "target_url": "" }),
+            "main",
+        );
+        assert_eq!(pending.id, "ci/lint");
+        assert_eq!((pending.url, pending.duration_secs), (None, None));
+    }
+
     #[test]
     fn computes_ci_state_from_checks() {
         let client = GitHubClient::new("github.com", "token", None);
@@ -764,10 +1318,12 @@ mod tests {
             CheckRun {
                 name: "build".into(),
                 status: "in_progress".into(),
+                ..CheckRun::default()
             },
             CheckRun {
                 name: "test".into(),
                 status: "success".into(),
+                ..CheckRun::default()
             },
         ];
         assert_eq!(
@@ -782,6 +1338,7 @@ mod tests {
             id: "1".into(),
             iid: 1,
             title: "fix".into(),
+            description: String::new(),
             url: "https://example/1".into(),
             state: IssueState::Open,
         };
@@ -790,6 +1347,27 @@ mod tests {
         assert_eq!(issue.title, "fix");
     }
 
+    #[test]
+    fn parses_repository_from_org_listing() {
+        let value = serde_json::json!({
+            "name": "api",
+            "full_name": "acme/api",
+            "owner": { "login": "acme" },
+            "ssh_url": "git@github.com:acme/api.git",
+            "clone_url": "https://github.com/acme/api.git",
+            "default_branch": "trunk",
+            "archived": true,
+            "description": "Public API"
+        });
+        let project = parse_repository(&value).expect("repository");
+        assert_eq!(project.name, "api");
+        assert_eq!(project.path, "acme/api");
+        assert_eq!(project.namespace, "acme");
+        assert_eq!(project.default_branch.as_deref(), Some("trunk"));
+        assert!(project.archived);
+        assert_eq!(project.description.as_deref(), Some("Public API"));
+    }
+
     #[test]
     fn test_client_constructs() {
         let client = GitHubClient::new("github.com", "token", Some("team".to_string()));
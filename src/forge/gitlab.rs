@@ -6,19 +6,26 @@ use serde_json::Value;
 
 use crate::core::repo::RepoId;
 use crate::error::{HarmoniaError, Result};
+use crate::forge::retry::RetryPolicy;
+use crate::forge::timestamp_duration_secs;
 use crate::forge::traits::{
-    CreateIssueParams, CreateMrParams, Forge, MergeMrParams, UpdateMrParams,
+    CreateIssueParams, CreateMrParams, CreateReleaseParams, Forge, ListIssuesParams, MergeMrParams,
+    UpdateMrParams,
 };
 use crate::forge::{
-    CheckRun, CiState, CiStatus, Issue, IssueState, MergeRequest, MrId, MrState, Pipeline, User,
+    CheckRun, CiState, CiStatus, ForgeProject, Issue, IssueState, MergeRequest, MergeRequirements,
+    MrChanges, MrFileChange, MrId, MrState, Pipeline, Release, User,
 };
 
+const PROJECTS_PER_PAGE: usize = 100;
+
 #[derive(Debug, Clone)]
 pub struct GitLabClient {
     pub host: String,
     pub token: String,
     pub default_group: Option<String>,
     client: Client,
+    retry: RetryPolicy,
 }
 
 impl GitLabClient {
@@ -33,9 +40,15 @@ impl GitLabClient {
             token: token.into(),
             default_group,
             client: Client::new(),
+            retry: RetryPolicy::default(),
         }
     }
 
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     fn api_url(&self, path: &str) -> String {
         format!("{}/api/v4{}", self.host, path)
     }
@@ -54,17 +67,17 @@ impl GitLabClient {
         raw.to_string()
     }
 
-    fn send_json(
+    fn send(
         &self,
         method: Method,
         path: &str,
         query: Option<&[(&str, String)]>,
         body: Option<Value>,
-    ) -> Result<Value> {
+    ) -> Result<reqwest::blocking::Response> {
         let url = self.api_url(path);
         let mut request = self
             .client
-            .request(method, &url)
+            .request(method.clone(), &url)
             .header("PRIVATE-TOKEN", &self.token)
             .header("Accept", "application/json");
         if let Some(query) = query {
@@ -74,13 +87,34 @@ impl GitLabClient {
             request = request.json(&body);
         }
 
-        let response = request.send().map_err(|err| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "gitlab request failed for {}: {}",
-                url, err
-            )))
-        })?;
-        parse_json_response(response)
+        self.retry.send("gitlab", &method, &url, request)
+    }
+
+    fn send_json(
+        &self,
+        method: Method,
+        path: &str,
+        query: Option<&[(&str, String)]>,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        parse_json_response(self.send(method, path, query, body)?)
+    }
+
+    /// GET that yields `None` when the resource is missing or the token may
+    /// not read it, for settings that only some projects have.
+    fn get_optional_json(
+        &self,
+        path: &str,
+        query: Option<&[(&str, String)]>,
+    ) -> Result<Option<Value>> {
+        let response = self.send(Method::GET, path, query, None)?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+        parse_json_response(response).map(Some)
     }
 
     fn put_json(
@@ -199,6 +233,11 @@ impl GitLabClient {
                 HarmoniaError::Other(anyhow::anyhow!("gitlab issue response missing iid"))
             })?;
         let title = json_string_field(value, "title")?;
+        let description = value
+            .get("description")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string();
         let url = value
             .get("web_url")
             .and_then(|value| value.as_str())
@@ -213,15 +252,16 @@ impl GitLabClient {
             id,
             iid,
             title,
+            description,
             url,
             state,
         })
     }
 
-    fn parse_reviewer_ids(&self, reviewers: &[String]) -> Result<Vec<u64>> {
+    fn parse_reviewer_ids(&self, repo: &RepoId, reviewers: &[String]) -> Result<Vec<u64>> {
         let mut ids = Vec::new();
         for reviewer in reviewers {
-            let user = self.get_user(reviewer)?;
+            let user = self.get_user(repo, reviewer)?;
             let id = user.id.ok_or_else(|| {
                 HarmoniaError::Other(anyhow::anyhow!(format!(
                     "gitlab user '{}' did not include an id",
@@ -265,12 +305,7 @@ impl GitLabClient {
             .header("PRIVATE-TOKEN", &self.token)
             .header("Accept", "application/json")
             .query(&query);
-        let response = request.send().map_err(|err| {
-            HarmoniaError::Other(anyhow::anyhow!(format!(
-                "gitlab request failed for {}: {}",
-                url, err
-            )))
-        })?;
+        let response = self.retry.send("gitlab", &Method::POST, &url, request)?;
 
         if response.status() == StatusCode::CONFLICT {
             return Ok(());
@@ -289,7 +324,7 @@ impl Forge for GitLabClient {
             params.title
         };
 
-        let reviewer_ids = self.parse_reviewer_ids(&params.reviewers)?;
+        let reviewer_ids = self.parse_reviewer_ids(repo, &params.reviewers)?;
 
         let mut payload = serde_json::json!({
             "title": title,
@@ -331,6 +366,53 @@ impl Forge for GitLabClient {
         self.parse_merge_request(&response)
     }
 
+    fn get_mr_changes(&self, repo: &RepoId, mr_id: &MrId) -> Result<MrChanges> {
+        let project = self.project_path_for_repo(repo);
+        let iid = self.parse_mr_iid(mr_id)?;
+        let path = format!(
+            "/projects/{}/merge_requests/{}/diffs",
+            encode_project_path(&project),
+            iid
+        );
+        let mut changes = MrChanges::default();
+        for page in 1.. {
+            let query = vec![
+                ("per_page", PROJECTS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "gitlab merge request diffs response was not an array"
+                ))
+            })?;
+            changes.files.extend(values.iter().map(parse_diff_file));
+            if values.len() < PROJECTS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+
+    fn find_open_mr(&self, repo: &RepoId, source_branch: &str) -> Result<Option<MergeRequest>> {
+        let project = self.project_path_for_repo(repo);
+        let path = format!("/projects/{}/merge_requests", encode_project_path(&project));
+        let query = vec![
+            ("source_branch", source_branch.to_string()),
+            ("state", "opened".to_string()),
+        ];
+        let response = self.get_json(&path, Some(&query))?;
+        let values = response.as_array().ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(
+                "gitlab merge requests response was not an array"
+            ))
+        })?;
+        values
+            .first()
+            .map(|value| self.parse_merge_request(value))
+            .transpose()
+    }
+
     fn update_mr(
         &self,
         repo: &RepoId,
@@ -346,7 +428,7 @@ impl Forge for GitLabClient {
         );
 
         let reviewer_ids = match params.reviewers {
-            Some(reviewers) => Some(self.parse_reviewer_ids(&reviewers)?),
+            Some(reviewers) => Some(self.parse_reviewer_ids(repo, &reviewers)?),
             None => None,
         };
 
@@ -408,11 +490,8 @@ impl Forge for GitLabClient {
             iid
         );
 
-        let payload = serde_json::json!({
-            "squash": params.squash,
-            "should_remove_source_branch": params.delete_source_branch,
-        });
-        self.put_json(&path, None, Some(payload)).map(|_| ())
+        self.put_json(&path, None, Some(merge_payload(&params)))
+            .map(|_| ())
     }
 
     fn close_mr(&self, repo: &RepoId, mr_id: &MrId) -> Result<()> {
@@ -430,6 +509,21 @@ impl Forge for GitLabClient {
         self.put_json(&path, None, Some(payload)).map(|_| ())
     }
 
+    fn get_merge_requirements(&self, repo: &RepoId, branch: &str) -> Result<MergeRequirements> {
+        // GitLab gates merges on pipelines and approval rules; status checks
+        // outside the pipeline are not reported as jobs, so only approvals
+        // are read here.
+        let project = self.project_path_for_repo(repo);
+        let path = format!("/projects/{}/approval_rules", encode_project_path(&project));
+        let rules = self.get_optional_json(&path, None)?;
+        Ok(MergeRequirements {
+            required_checks: Vec::new(),
+            required_approvals: rules
+                .map(|rules| required_approvals_for_branch(&rules, branch))
+                .unwrap_or(0),
+        })
+    }
+
     fn get_ci_status(&self, repo: &RepoId, ref_name: &str) -> Result<CiStatus> {
         let project = self.project_path_for_repo(repo);
         let path = format!("/projects/{}/pipelines", encode_project_path(&project));
@@ -447,7 +541,7 @@ impl Forge for GitLabClient {
             ))
         })?;
 
-        let pipelines = pipelines_array
+        let mut pipelines = pipelines_array
             .iter()
             .map(|pipeline| Pipeline {
                 id: pipeline
@@ -466,14 +560,27 @@ impl Forge for GitLabClient {
                     .and_then(|value| value.as_str())
                     .unwrap_or("unknown")
                     .to_string(),
+                url: pipeline
+                    .get("web_url")
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string),
+                ..Pipeline::default()
             })
             .collect::<Vec<_>>();
 
-        let checks = latest_pipeline_job_checks(
-            self,
-            &project,
-            pipelines_array.first().and_then(pipeline_id_from_value),
-        )?;
+        let latest_id = pipelines_array.first().and_then(pipeline_id_from_value);
+        if let (Some(latest), Some(pipeline_id)) = (pipelines.first_mut(), latest_id) {
+            // The list endpoint omits duration and user; only the latest
+            // pipeline is shown, so only it is fetched in full.
+            let path = format!(
+                "/projects/{}/pipelines/{}",
+                encode_project_path(&project),
+                pipeline_id
+            );
+            let details = self.get_json(&path, None)?;
+            apply_pipeline_details(latest, &details);
+        }
+        let checks = latest_pipeline_job_checks(self, &project, latest_id)?;
         let state = aggregate_ci_state(&pipelines);
         Ok(CiStatus {
             state,
@@ -508,7 +615,78 @@ impl Forge for GitLabClient {
         self.parse_issue(&response)
     }
 
-    fn get_user(&self, username: &str) -> Result<User> {
+    fn get_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        let project = self.project_path_for_repo(project);
+        let path = format!("/projects/{}/issues/{}", encode_project_path(&project), iid);
+        let response = self.get_json(&path, None)?;
+        self.parse_issue(&response)
+    }
+
+    fn update_issue(&self, project: &RepoId, iid: u64, description: &str) -> Result<Issue> {
+        let project = self.project_path_for_repo(project);
+        let path = format!("/projects/{}/issues/{}", encode_project_path(&project), iid);
+        let payload = serde_json::json!({ "description": description });
+        let response = self.put_json(&path, None, Some(payload))?;
+        self.parse_issue(&response)
+    }
+
+    fn list_issues(&self, project: &RepoId, params: ListIssuesParams) -> Result<Vec<Issue>> {
+        let project = self.project_path_for_repo(project);
+        let path = format!("/projects/{}/issues", encode_project_path(&project));
+        let state = match params.state {
+            Some(IssueState::Open) => "opened",
+            Some(IssueState::Closed) => "closed",
+            None => "all",
+        };
+        let per_page = params.limit.clamp(1, PROJECTS_PER_PAGE);
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let mut query = vec![
+                ("state", state.to_string()),
+                ("order_by", "created_at".to_string()),
+                ("sort", "desc".to_string()),
+                ("per_page", per_page.to_string()),
+                ("page", page.to_string()),
+            ];
+            if !params.labels.is_empty() {
+                query.push(("labels", params.labels.join(",")));
+            }
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!("gitlab issues response was not an array"))
+            })?;
+            for value in values {
+                issues.push(self.parse_issue(value)?);
+            }
+            if values.len() < per_page || issues.len() >= params.limit {
+                break;
+            }
+        }
+        issues.truncate(params.limit);
+        Ok(issues)
+    }
+
+    fn close_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        let project = self.project_path_for_repo(project);
+        let path = format!("/projects/{}/issues/{}", encode_project_path(&project), iid);
+        let payload = serde_json::json!({ "state_event": "close" });
+        let response = self.put_json(&path, None, Some(payload))?;
+        self.parse_issue(&response)
+    }
+
+    fn comment_issue(&self, project: &RepoId, iid: u64, body: &str) -> Result<()> {
+        let project = self.project_path_for_repo(project);
+        let path = format!(
+            "/projects/{}/issues/{}/notes",
+            encode_project_path(&project),
+            iid
+        );
+        let payload = serde_json::json!({ "body": body });
+        self.post_json(&path, None, Some(payload))?;
+        Ok(())
+    }
+
+    fn get_user(&self, _repo: &RepoId, username: &str) -> Result<User> {
         let query = vec![("username", username.to_string())];
         let response = self.get_json("/users", Some(&query))?;
         let users = response.as_array().ok_or_else(|| {
@@ -528,6 +706,128 @@ impl Forge for GitLabClient {
             )))
         })
     }
+
+    fn create_release(&self, repo: &RepoId, params: CreateReleaseParams) -> Result<Release> {
+        let project = self.project_path_for_repo(repo);
+        let path = format!("/projects/{}/releases", encode_project_path(&project));
+        let response = self.post_json(&path, None, Some(release_payload(&params)))?;
+        parse_release(&response).ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "gitlab release '{}' response missing required fields",
+                params.tag
+            )))
+        })
+    }
+
+    fn list_projects(&self, group: &str) -> Result<Vec<ForgeProject>> {
+        let group = group.trim().trim_matches('/');
+        let path = format!("/groups/{}/projects", encode_project_path(group));
+        let mut projects = Vec::new();
+        for page in 1.. {
+            let query = vec![
+                ("include_subgroups", "true".to_string()),
+                ("order_by", "path".to_string()),
+                ("sort", "asc".to_string()),
+                ("per_page", PROJECTS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "gitlab group projects response was not an array"
+                ))
+            })?;
+            for value in values {
+                projects.push(parse_project(value)?);
+            }
+            if values.len() < PROJECTS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(projects)
+    }
+
+    fn get_project(&self, repo: &RepoId) -> Result<ForgeProject> {
+        let project = self.project_path_for_repo(repo);
+        let path = format!("/projects/{}", encode_project_path(&project));
+        parse_project(&self.get_json(&path, None)?)
+    }
+
+    fn count_open_mrs(&self, repo: &RepoId) -> Result<usize> {
+        let project = self.project_path_for_repo(repo);
+        let path = format!("/projects/{}/merge_requests", encode_project_path(&project));
+        let mut count = 0;
+        for page in 1.. {
+            let query = vec![
+                ("state", "opened".to_string()),
+                ("per_page", PROJECTS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json(&path, Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "gitlab merge requests response was not an array"
+                ))
+            })?;
+            count += values.len();
+            if values.len() < PROJECTS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    fn count_open_reviews(&self, _repo: &RepoId, username: &str) -> Result<usize> {
+        let mut count = 0;
+        for page in 1.. {
+            let query = vec![
+                ("state", "opened".to_string()),
+                ("scope", "all".to_string()),
+                ("reviewer_username", username.to_string()),
+                ("per_page", PROJECTS_PER_PAGE.to_string()),
+                ("page", page.to_string()),
+            ];
+            let response = self.get_json("/merge_requests", Some(&query))?;
+            let values = response.as_array().ok_or_else(|| {
+                HarmoniaError::Other(anyhow::anyhow!(
+                    "gitlab merge requests response was not an array"
+                ))
+            })?;
+            count += values.len();
+            if values.len() < PROJECTS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// GitLab reports diffs without line counts, so they are counted from the
+/// unified diff text, which starts at the first hunk.
+fn parse_diff_file(value: &Value) -> MrFileChange {
+    let path = value
+        .get("new_path")
+        .or_else(|| value.get("old_path"))
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let diff = value
+        .get("diff")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    let mut change = MrFileChange {
+        path,
+        additions: 0,
+        deletions: 0,
+    };
+    for line in diff.lines() {
+        if line.starts_with('+') {
+            change.additions += 1;
+        } else if line.starts_with('-') {
+            change.deletions += 1;
+        }
+    }
+    change
 }
 
 fn normalize_host(host: &str) -> String {
@@ -602,6 +902,45 @@ fn percent_encode(value: &str) -> String {
     encoded
 }
 
+fn parse_project(value: &Value) -> Result<ForgeProject> {
+    let path = json_string_field(value, "path_with_namespace")?;
+    let name = value
+        .get("path")
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| path.rsplit('/').next().unwrap_or_default().to_string());
+    let namespace = value
+        .get("namespace")
+        .and_then(|value| value.get("full_path"))
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| {
+            path.rsplit_once('/')
+                .map(|(namespace, _)| namespace.to_string())
+                .unwrap_or_default()
+        });
+    Ok(ForgeProject {
+        name,
+        path,
+        namespace,
+        ssh_url: json_string_field(value, "ssh_url_to_repo")?,
+        http_url: json_string_field(value, "http_url_to_repo")?,
+        default_branch: value
+            .get("default_branch")
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string()),
+        archived: value
+            .get("archived")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false),
+        description: value
+            .get("description")
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string()),
+    })
+}
+
 fn json_string_field(value: &Value, field: &str) -> Result<String> {
     value
         .get(field)
@@ -714,6 +1053,10 @@ fn latest_pipeline_job_checks(
         checks.push(CheckRun {
             name: name.to_string(),
             status: status.to_string(),
+            url: job
+                .get("web_url")
+                .and_then(|value| value.as_str())
+                .map(str::to_string),
         });
     }
     checks.sort_by(|a, b| a.name.cmp(&b.name));
@@ -721,12 +1064,160 @@ fn latest_pipeline_job_checks(
     Ok(checks)
 }
 
+/// Approvals needed to merge into `branch`: the largest count among approval
+/// rules that apply to every branch or to a protected branch matching it.
+fn required_approvals_for_branch(rules: &Value, branch: &str) -> u32 {
+    rules
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|rule| {
+            let protected: Vec<&str> = rule
+                .get("protected_branches")
+                .and_then(|value| value.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|protected| protected.get("name").and_then(|value| value.as_str()))
+                .collect();
+            protected.is_empty()
+                || protected
+                    .iter()
+                    .any(|pattern| protected_branch_matches(pattern, branch))
+        })
+        .filter_map(|rule| {
+            rule.get("approvals_required")
+                .and_then(|value| value.as_u64())
+        })
+        .max()
+        .unwrap_or(0) as u32
+}
+
+/// Protected branch names may contain `*` wildcards, e.g. `release/*`.
+fn protected_branch_matches(pattern: &str, branch: &str) -> bool {
+    let regex = format!("^{}$", regex::escape(pattern).replace("\\*", ".*"));
+    regex::Regex::new(&regex).is_ok_and(|regex| regex.is_match(branch))
+}
+
+/// Fills duration and trigger user from a single-pipeline response. Running
+/// pipelines have no `duration` yet, so it is measured from `started_at`.
+fn apply_pipeline_details(pipeline: &mut Pipeline, details: &Value) {
+    let text = |key: &str| details.get(key).and_then(|value| value.as_str());
+    pipeline.duration_secs = details
+        .get("duration")
+        .and_then(|value| value.as_f64())
+        .map(|secs| secs.round() as u64)
+        .or_else(|| timestamp_duration_secs(text("started_at")?, text("finished_at")?));
+    pipeline.triggered_by = details
+        .get("user")
+        .and_then(|user| user.get("username"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    if pipeline.url.is_none() {
+        pipeline.url = text("web_url").map(str::to_string);
+    }
+}
+
+fn merge_payload(params: &MergeMrParams) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "squash": params.squash,
+        "should_remove_source_branch": params.delete_source_branch,
+    });
+    if let Some(message) = params
+        .squash_commit_message
+        .as_deref()
+        .filter(|_| params.squash)
+    {
+        payload["squash_commit_message"] = serde_json::Value::String(message.to_string());
+    }
+    payload
+}
+
+fn release_payload(params: &CreateReleaseParams) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "tag_name": params.tag,
+        "name": params.name,
+        "description": params.notes,
+    });
+    if let Some(target) = params.target.as_deref() {
+        payload["ref"] = serde_json::Value::String(target.to_string());
+    }
+    payload
+}
+
+fn parse_release(value: &Value) -> Option<Release> {
+    let tag = value.get("tag_name")?.as_str()?.to_string();
+    Some(Release {
+        name: value
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or(tag.as_str())
+            .to_string(),
+        url: value
+            .pointer("/_links/self")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        tag,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::core::repo::RepoId;
-    use crate::forge::gitlab::{aggregate_ci_state, encode_project_path, GitLabClient};
+    use crate::forge::gitlab::{
+        aggregate_ci_state, apply_pipeline_details, encode_project_path, merge_payload,
+        parse_diff_file, parse_project, parse_release, release_payload,
+        required_approvals_for_branch, GitLabClient,
+    };
+    use crate::forge::traits::{CreateReleaseParams, MergeMrParams};
     use crate::forge::{CiState, Pipeline};
 
+    #[test]
+    fn release_payload_attaches_notes_and_ref() {
+        let mut params = CreateReleaseParams {
+            tag: "v1.2.0".to_string(),
+            name: "api v1.2.0".to_string(),
+            notes: "## 1.2.0\n\n- rotate keys".to_string(),
+            target: None,
+        };
+        let payload = release_payload(&params);
+        assert_eq!(payload["tag_name"], "v1.2.0");
+        assert_eq!(payload["description"], "## 1.2.0\n\n- rotate keys");
+        assert!(payload.get("ref").is_none());
+
+        params.target = Some("main".to_string());
+        assert_eq!(release_payload(&params)["ref"], "main");
+
+        let release = parse_release(&serde_json::json!({
+            "tag_name": "v1.2.0",
+            "name": "api v1.2.0",
+            "_links": { "self": "https://gitlab.com/platform/api/-/releases/v1.2.0" },
+        }))
+        .expect("release");
+        assert_eq!(release.tag, "v1.2.0");
+        assert_eq!(
+            release.url,
+            "https://gitlab.com/platform/api/-/releases/v1.2.0"
+        );
+    }
+
+    #[test]
+    fn squash_message_is_sent_only_for_squash_merges() {
+        let mut params = MergeMrParams {
+            squash: true,
+            delete_source_branch: false,
+            squash_commit_message: Some("PLAT-1: rotate keys\n\nChangeset: auth".to_string()),
+        };
+        assert_eq!(
+            merge_payload(&params)["squash_commit_message"],
+            "PLAT-1: rotate keys\n\nChangeset: auth"
+        );
+        params.squash = false;
+        assert!(merge_payload(&params)
+            .get("squash_commit_message")
+            .is_none());
+    }
+
     #[test]
     fn project_path_uses_default_group_when_repo_is_unqualified() {
         let client = GitLabClient::new("gitlab.com", "token", Some("platform".to_string()));
@@ -746,18 +1237,112 @@ mod tests {
         assert_eq!(encode_project_path("group/sub/repo"), "group%2Fsub%2Frepo");
     }
 
+    #[test]
+    fn parses_project_from_group_listing() {
+        let value = serde_json::json!({
+            "path": "api",
+            "path_with_namespace": "platform/backend/api",
+            "namespace": { "full_path": "platform/backend" },
+            "ssh_url_to_repo": "git@gitlab.com:platform/backend/api.git",
+            "http_url_to_repo": "https://gitlab.com/platform/backend/api.git",
+            "default_branch": "main",
+            "archived": false,
+            "description": "Backend API"
+        });
+        let project = parse_project(&value).expect("project");
+        assert_eq!(project.name, "api");
+        assert_eq!(project.namespace, "platform/backend");
+        assert_eq!(project.default_branch.as_deref(), Some("main"));
+        assert!(!project.archived);
+        assert_eq!(project.description.as_deref(), Some("Backend API"));
+
+        assert!(parse_project(&serde_json::json!({ "path": "api" })).is_err());
+    }
+
     #[test]
     fn aggregates_ci_state_by_priority() {
         let pipelines = vec![
             Pipeline {
                 id: "1".to_string(),
                 status: "success".to_string(),
+                ..Pipeline::default()
             },
             Pipeline {
                 id: "2".to_string(),
                 status: "running".to_string(),
+                ..Pipeline::default()
             },
         ];
         assert_eq!(aggregate_ci_state(&pipelines), CiState::Running);
     }
+
+    #[test]
+    fn required_approvals_follow_matching_protected_branches() {
+        let rules = serde_json::json!([
+            { "name": "All members", "approvals_required": 1, "protected_branches": [] },
+            {
+                "name": "Release managers",
+                "approvals_required": 2,
+                "protected_branches": [{ "name": "release/*" }],
+            },
+            {
+                "name": "Security",
+                "approvals_required": 3,
+                "protected_branches": [{ "name": "stable" }],
+            },
+        ]);
+        assert_eq!(required_approvals_for_branch(&rules, "main"), 1);
+        assert_eq!(required_approvals_for_branch(&rules, "release/2.4"), 2);
+        assert_eq!(required_approvals_for_branch(&rules, "stable"), 3);
+        assert_eq!(
+            required_approvals_for_branch(&serde_json::json!([]), "main"),
+            0
+        );
+    }
+
+    #[test]
+    fn counts_diff_lines_for_mr_changes() {
+        let value = serde_json::json!({
+            "old_path": "src/old.rs",
+            "new_path": "src/new.rs",
+            "diff": "@@ -1,3 +1,3 @@\n context\n-let a = 1;\n--- removed comment\n+let a = 2;\n"
+        });
+        let change = parse_diff_file(&value);
+        assert_eq!(change.path, "src/new.rs");
+        assert_eq!((change.additions, change.deletions), (1, 2));
+    }
+
+    #[test]
+    fn reads_pipeline_duration_and_trigger_user() {
+        let mut pipeline = Pipeline {
+            id: "7".to_string(),
+            status: "failed".to_string(),
+            ..Pipeline::default()
+        };
+        apply_pipeline_details(
+            &mut pipeline,
+            &serde_json::json!({
+                "duration": 184.6,
+                "user": { "username": "alice" },
+                "web_url": "https://gitlab.example.com/platform/api/-/pipelines/7",
+            }),
+        );
+        assert_eq!(pipeline.duration_secs, Some(185));
+        assert_eq!(pipeline.triggered_by.as_deref(), Some("alice"));
+        assert_eq!(
+            pipeline.url.as_deref(),
+            Some("https://gitlab.example.com/platform/api/-/pipelines/7")
+        );
+
+        let mut running = Pipeline::default();
+        apply_pipeline_details(
+            &mut running,
+            &serde_json::json!({
+                "duration": null,
+                "started_at": "2024-05-01T10:00:00.000Z",
+                "finished_at": null,
+            }),
+        );
+        assert_eq!(running.duration_secs, None);
+    }
 }
@@ -2,7 +2,10 @@ pub mod bitbucket;
 pub mod gitea;
 pub mod github;
 pub mod gitlab;
+pub mod retry;
+pub mod router;
 pub mod traits;
+pub mod webhook;
 
 pub type MrId = String;
 pub type IssueId = String;
@@ -13,16 +16,42 @@ pub struct User {
     pub username: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Pipeline {
     pub id: String,
     pub status: String,
+    /// Web page of the pipeline (GitLab) or status target (GitHub).
+    pub url: Option<String>,
+    pub duration_secs: Option<u64>,
+    /// User (or app) that started the pipeline or posted the status.
+    pub triggered_by: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CheckRun {
     pub name: String,
     pub status: String,
+    pub url: Option<String>,
+}
+
+/// Merge gates configured on the forge for a target branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeRequirements {
+    /// Check names that have to pass, sorted and deduplicated.
+    pub required_checks: Vec<String>,
+    pub required_approvals: u32,
+}
+
+impl MergeRequirements {
+    /// Combines two sources of requirements: every check from both, and the
+    /// stricter approval count.
+    pub fn merge(mut self, other: MergeRequirements) -> MergeRequirements {
+        self.required_checks.extend(other.required_checks);
+        self.required_checks.sort();
+        self.required_checks.dedup();
+        self.required_approvals = self.required_approvals.max(other.required_approvals);
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +69,30 @@ pub struct MergeRequest {
     pub labels: Vec<String>,
 }
 
+/// One file in an MR's diff, with its changed line counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MrFileChange {
+    pub path: String,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+/// Files an MR changes, as reported by the forge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MrChanges {
+    pub files: Vec<MrFileChange>,
+}
+
+impl MrChanges {
+    pub fn additions(&self) -> u64 {
+        self.files.iter().map(|file| file.additions).sum()
+    }
+
+    pub fn deletions(&self) -> u64 {
+        self.files.iter().map(|file| file.deletions).sum()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MrState {
     Open,
@@ -70,18 +123,117 @@ pub struct Issue {
     pub id: IssueId,
     pub iid: u64,
     pub title: String,
+    pub description: String,
     pub url: String,
     pub state: IssueState,
 }
 
+/// A GitHub Release or GitLab Release attached to a tag.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub tag: String,
+    pub name: String,
+    pub url: String,
+}
+
+/// A project listed from a forge group or organization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeProject {
+    pub name: String,
+    /// Full path including the namespace, e.g. `platform/backend/api`.
+    pub path: String,
+    /// Namespace the project lives in, e.g. `platform/backend`.
+    pub namespace: String,
+    pub ssh_url: String,
+    pub http_url: String,
+    pub default_branch: Option<String>,
+    pub archived: bool,
+    pub description: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IssueState {
     Open,
     Closed,
 }
 
+/// Seconds between two forge timestamps (`2024-05-01T10:00:00Z`, with
+/// optional fractional seconds and UTC offset), `None` when either does not
+/// parse or `finished` is earlier than `started`.
+pub fn timestamp_duration_secs(started: &str, finished: &str) -> Option<u64> {
+    let started = parse_timestamp(started)?;
+    let finished = parse_timestamp(finished)?;
+    u64::try_from(finished - started).ok()
+}
+
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (date, time) = value.split_once(['T', ' '])?;
+    let mut date = date.splitn(3, '-').map(str::parse::<u32>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let offset_at = time.find(['Z', 'z', '+', '-']).unwrap_or(time.len());
+    let (clock, offset) = time.split_at(offset_at);
+    let clock = clock.split('.').next()?;
+    let mut clock = clock.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (
+        clock.next()?.ok()?,
+        clock.next()?.ok()?,
+        clock.next().unwrap_or(Ok(0)).ok()?,
+    );
+    let offset_secs = match offset.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let digits: String = offset[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i64 = digits.get(..2)?.parse().ok()?;
+            let minutes: i64 = digits.get(2..4).unwrap_or("0").parse().ok()?;
+            let secs = hours * 3_600 + minutes * 60;
+            if sign == '-' {
+                -secs
+            } else {
+                secs
+            }
+        }
+        _ => 0,
+    };
+
+    let days = crate::core::version::days_from_civil(year as i32, month, day);
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second - offset_secs)
+}
+
 pub fn client_from_forge_config(
     config: &crate::config::ForgeConfig,
+) -> crate::error::Result<Box<dyn traits::Forge>> {
+    let token = forge_token_from_sources(
+        config.token.as_deref(),
+        std::env::var("HARMONIA_FORGE_TOKEN").ok(),
+    )
+    .ok_or_else(|| {
+        crate::error::HarmoniaError::Other(anyhow::anyhow!(
+            "forge token is required (set HARMONIA_FORGE_TOKEN or configure [forge].token)"
+        ))
+    })?;
+    client_with_token(config, token)
+}
+
+/// Client for the `[forge.hosts]` entry `name`. Its token comes only from
+/// the entry, never from `HARMONIA_FORGE_TOKEN`, which belongs to `[forge]`.
+pub fn client_for_forge_host(
+    config: &crate::config::ForgeConfig,
+    name: &str,
+    entry: &crate::config::ForgeHostConfig,
+) -> crate::error::Result<Box<dyn traits::Forge>> {
+    let token = entry.resolve_token().ok_or_else(|| {
+        crate::error::HarmoniaError::Other(anyhow::anyhow!(format!(
+            "forge token is required for host '{}' (configure [forge.hosts.\"{}\"].token or token_env)",
+            name, name
+        )))
+    })?;
+    client_with_token(&config.for_host(name, entry), token)
+}
+
+fn client_with_token(
+    config: &crate::config::ForgeConfig,
+    token: String,
 ) -> crate::error::Result<Box<dyn traits::Forge>> {
     let host = config
         .host
@@ -93,27 +245,16 @@ pub fn client_from_forge_config(
                 config.forge_type
             )))
         })?;
-    let token = forge_token_from_sources(
-        config.token.as_deref(),
-        std::env::var("HARMONIA_FORGE_TOKEN").ok(),
-    )
-    .ok_or_else(|| {
-        crate::error::HarmoniaError::Other(anyhow::anyhow!(
-            "forge token is required (set HARMONIA_FORGE_TOKEN or configure [forge].token)"
-        ))
-    })?;
 
     match config.forge_type.as_str() {
-        "github" => Ok(Box::new(github::GitHubClient::new(
-            host,
-            token,
-            config.default_group.clone(),
-        ))),
-        "gitlab" => Ok(Box::new(gitlab::GitLabClient::new(
-            host,
-            token,
-            config.default_group.clone(),
-        ))),
+        "github" => Ok(Box::new(
+            github::GitHubClient::new(host, token, config.default_group.clone())
+                .with_retry(retry::RetryPolicy::from_config(config)),
+        )),
+        "gitlab" => Ok(Box::new(
+            gitlab::GitLabClient::new(host, token, config.default_group.clone())
+                .with_retry(retry::RetryPolicy::from_config(config)),
+        )),
         other => Err(crate::error::HarmoniaError::Other(anyhow::anyhow!(
             format!("forge '{}' is not implemented yet", other)
         ))),
@@ -147,7 +288,9 @@ fn forge_token_from_sources(
 #[cfg(test)]
 mod tests {
     use crate::config::ForgeConfig;
-    use crate::forge::{client_from_forge_config, forge_token_from_sources};
+    use crate::forge::{
+        client_from_forge_config, forge_token_from_sources, timestamp_duration_secs,
+    };
 
     #[test]
     fn creates_github_client_from_config() {
@@ -156,6 +299,7 @@ mod tests {
             host: None,
             default_group: Some("example-org".to_string()),
             token: Some("token".to_string()),
+            ..Default::default()
         };
         let client = client_from_forge_config(&config);
         assert!(client.is_ok());
@@ -168,6 +312,7 @@ mod tests {
             host: None,
             default_group: None,
             token: Some("token".to_string()),
+            ..Default::default()
         };
         let client = client_from_forge_config(&config);
         assert!(client.is_ok());
@@ -180,6 +325,7 @@ mod tests {
             host: None,
             default_group: None,
             token: None,
+            ..Default::default()
         };
         let client = client_from_forge_config(&config);
         assert!(client.is_err());
@@ -191,6 +337,26 @@ mod tests {
         assert_eq!(token.as_deref(), Some("env-token"));
     }
 
+    #[test]
+    fn measures_durations_between_forge_timestamps() {
+        assert_eq!(
+            timestamp_duration_secs("2024-02-28T23:59:30Z", "2024-02-29T00:01:00Z"),
+            Some(90)
+        );
+        assert_eq!(
+            timestamp_duration_secs("2024-05-01T10:00:00.250Z", "2024-05-01T12:05:05+02:00"),
+            Some(305)
+        );
+        assert_eq!(
+            timestamp_duration_secs("2024-05-01T10:00:00Z", "2024-05-01T09:00:00Z"),
+            None
+        );
+        assert_eq!(
+            timestamp_duration_secs("yesterday", "2024-05-01T09:00:00Z"),
+            None
+        );
+    }
+
     #[test]
     fn falls_back_to_config_token_when_env_missing() {
         let token = forge_token_from_sources(Some("config-token"), None);
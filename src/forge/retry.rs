@@ -0,0 +1,367 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+
+use crate::error::{HarmoniaError, Result};
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(1000);
+/// Longest wait before a retry. A rate limit that resets later than this is
+/// reported instead of waited out.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// How forge clients retry requests that failed for transient reasons:
+/// rate limits (429, or GitHub's 403 with an exhausted quota), server errors,
+/// and dropped connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Wait before the first retry; doubles with each further one unless the
+    /// forge says how long to wait.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `[forge].max_retries` and `[forge].backoff_ms`, with defaults.
+    pub fn from_config(config: &crate::config::ForgeConfig) -> Self {
+        let default = Self::default();
+        Self {
+            max_retries: config.max_retries.unwrap_or(default.max_retries),
+            backoff: config
+                .backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.backoff),
+        }
+    }
+
+    /// Sends `request`, retrying transient failures. Server errors and
+    /// timeouts are retried only for requests that are safe to repeat, so a
+    /// POST or merge that may have been applied is not sent twice. `forge`
+    /// names the forge in errors.
+    pub fn send(
+        &self,
+        forge: &str,
+        method: &Method,
+        url: &str,
+        request: RequestBuilder,
+    ) -> Result<Response> {
+        let idempotent = is_idempotent(method, url);
+        let mut attempt = 0;
+        loop {
+            let retry = request.try_clone().filter(|_| attempt < self.max_retries);
+            let current = match retry {
+                Some(next) => next,
+                None => return self.send_last(forge, url, request, attempt),
+            };
+            match current.send() {
+                Ok(response) => {
+                    let Some(delay) = self.retry_delay(
+                        attempt,
+                        response.status(),
+                        response.headers(),
+                        idempotent,
+                    ) else {
+                        return Ok(response);
+                    };
+                    if delay > MAX_RETRY_WAIT {
+                        return Err(rate_limited(forge, url, delay));
+                    }
+                    std::thread::sleep(delay);
+                }
+                Err(err) if err.is_connect() || (idempotent && err.is_timeout()) => {
+                    std::thread::sleep(self.backoff_for(attempt));
+                }
+                Err(err) => return Err(request_failed(forge, url, attempt, err)),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// The final attempt: a response that would still have been retried
+    /// becomes an error naming how many attempts were made.
+    fn send_last(
+        &self,
+        forge: &str,
+        url: &str,
+        request: RequestBuilder,
+        attempt: u32,
+    ) -> Result<Response> {
+        let response = request
+            .send()
+            .map_err(|err| request_failed(forge, url, attempt, err))?;
+        let status = response.status();
+        if attempt == 0 || !is_transient(status, response.headers()) {
+            return Ok(response);
+        }
+        let body = response.text().unwrap_or_default();
+        Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "{} API returned {} for {} after {} attempts: {}",
+            forge,
+            status,
+            url,
+            attempt + 1,
+            body.trim()
+        ))))
+    }
+
+    /// How long to wait before retrying a request that got `status`, or
+    /// `None` when it should not be retried. `Retry-After` and GitHub's
+    /// `x-ratelimit-reset` win over the exponential backoff.
+    pub fn retry_delay(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        headers: &HeaderMap,
+        idempotent: bool,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries || !is_transient(status, headers) {
+            return None;
+        }
+        if status.is_server_error() && !idempotent {
+            return None;
+        }
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        if let Some(seconds) = header("retry-after") {
+            return Some(Duration::from_secs(seconds));
+        }
+        if header("x-ratelimit-remaining") == Some(0) {
+            if let Some(reset) = header("x-ratelimit-reset") {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|now| now.as_secs())
+                    .unwrap_or_default();
+                return Some(Duration::from_secs(reset.saturating_sub(now).max(1)));
+            }
+        }
+        Some(self.backoff_for(attempt))
+    }
+
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(1 << attempt.min(16))
+    }
+}
+
+/// Whether sending the request again cannot apply it twice. PUT is, except
+/// for the merge endpoints (GitHub `PUT .../pulls/:n/merge`, GitLab
+/// `PUT .../merge_requests/:iid/merge`): a merge that went through before a
+/// 502 would be rejected on retry and reported as a failure.
+fn is_idempotent(method: &Method, url: &str) -> bool {
+    match *method {
+        Method::GET | Method::HEAD | Method::DELETE => true,
+        Method::PUT => {
+            let path = url.split('?').next().unwrap_or(url);
+            !path.trim_end_matches('/').ends_with("/merge")
+        }
+        _ => false,
+    }
+}
+
+/// Rate limits and server errors that are worth another try. GitHub signals
+/// exhausted quotas with 403 and rate-limit headers rather than 429.
+fn is_transient(status: StatusCode, headers: &HeaderMap) -> bool {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return true;
+    }
+    if status == StatusCode::FORBIDDEN {
+        let remaining = headers
+            .get("x-ratelimit-remaining")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim);
+        return remaining == Some("0") || headers.contains_key("retry-after");
+    }
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn rate_limited(forge: &str, url: &str, delay: Duration) -> HarmoniaError {
+    HarmoniaError::Other(anyhow::anyhow!(format!(
+        "{} rate limit reached for {}; it resets in {}s",
+        forge,
+        url,
+        delay.as_secs()
+    )))
+}
+
+fn request_failed(forge: &str, url: &str, attempt: u32, err: reqwest::Error) -> HarmoniaError {
+    let attempts = if attempt > 0 {
+        format!(" after {} attempts", attempt + 1)
+    } else {
+        String::new()
+    };
+    HarmoniaError::Other(anyhow::anyhow!(format!(
+        "{} request failed for {}{}: {}",
+        forge, url, attempts, err
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Serves `statuses` in order, one per connection, and counts requests.
+    fn serve(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for status in statuses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut buffer = [0u8; 4096];
+                let mut received = Vec::new();
+                while !received.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => received.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn send_retries_reads_but_not_merges() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            backoff: Duration::from_millis(1),
+        };
+        let client = reqwest::blocking::Client::new();
+
+        let (base, requests) = serve(vec![503, 502, 200]);
+        let url = format!("{base}/repos/org/api/pulls/1");
+        let response = policy
+            .send("github", &Method::GET, &url, client.get(&url))
+            .expect("read succeeds after retries");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        let (base, requests) = serve(vec![502, 200]);
+        let url = format!("{base}/repos/org/api/pulls/1/merge");
+        let response = policy
+            .send("github", &Method::PUT, &url, client.put(&url))
+            .expect("merge response is returned");
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            requests.load(Ordering::SeqCst),
+            1,
+            "a merge that may have applied is not sent again"
+        );
+
+        let (base, requests) = serve(vec![503, 503, 503]);
+        let url = format!("{base}/projects/1");
+        let err = policy
+            .send("gitlab", &Method::GET, &url, client.get(&url))
+            .expect_err("gives up after max_retries");
+        assert!(err.to_string().contains("after 3 attempts"), "{err}");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn merge_endpoints_are_not_idempotent() {
+        assert!(is_idempotent(
+            &Method::PUT,
+            "https://api.github.com/repos/o/r/pulls/1"
+        ));
+        assert!(!is_idempotent(
+            &Method::PUT,
+            "https://api.github.com/repos/o/r/pulls/1/merge"
+        ));
+        assert!(!is_idempotent(
+            &Method::PUT,
+            "https://gitlab.com/api/v4/projects/o%2Fr/merge_requests/3/merge?sha=abc"
+        ));
+        assert!(is_idempotent(&Method::DELETE, "https://gitlab.com/x/merge"));
+        assert!(!is_idempotent(&Method::POST, "https://gitlab.com/x"));
+    }
+
+    #[test]
+    fn retries_rate_limits_and_server_errors_with_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            backoff: Duration::from_millis(100),
+        };
+        let none = HeaderMap::new();
+        assert_eq!(
+            policy.retry_delay(0, StatusCode::SERVICE_UNAVAILABLE, &none, true),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry_delay(1, StatusCode::BAD_GATEWAY, &none, true),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            policy.retry_delay(2, StatusCode::BAD_GATEWAY, &none, true),
+            None
+        );
+        assert_eq!(
+            policy.retry_delay(0, StatusCode::BAD_GATEWAY, &none, false),
+            None,
+            "a POST that reached the server is not repeated"
+        );
+        assert_eq!(
+            policy.retry_delay(0, StatusCode::NOT_FOUND, &none, true),
+            None
+        );
+        assert_eq!(
+            policy.retry_delay(0, StatusCode::FORBIDDEN, &none, true),
+            None
+        );
+
+        let mut retry_after = HeaderMap::new();
+        retry_after.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(
+            policy.retry_delay(0, StatusCode::TOO_MANY_REQUESTS, &retry_after, false),
+            Some(Duration::from_secs(7))
+        );
+
+        let reset = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 30;
+        let mut github = HeaderMap::new();
+        github.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        github.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_str(&reset.to_string()).unwrap(),
+        );
+        let delay = policy
+            .retry_delay(0, StatusCode::FORBIDDEN, &github, true)
+            .expect("exhausted quota is retried");
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+    }
+}
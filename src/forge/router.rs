@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use crate::core::repo::RepoId;
+use crate::error::{HarmoniaError, Result};
+use crate::forge::traits::{
+    CreateIssueParams, CreateMrParams, CreateReleaseParams, Forge, ListIssuesParams, MergeMrParams,
+    UpdateMrParams,
+};
+use crate::forge::{
+    CiStatus, ForgeProject, Issue, MergeRequest, MergeRequirements, MrChanges, MrId, Release, User,
+};
+
+type ClientFactory = Box<dyn Fn() -> Result<Box<dyn Forge>> + Send + Sync>;
+
+/// Sends each call to the client of the host its project lives on, for
+/// workspaces whose repos span several forges (`[forge.hosts]`). Project ids
+/// that name their host ([`RepoId::on_host`]) go to that host; bare project
+/// paths go to the one host that registered them. Projects that were not
+/// registered, and calls not tied to a project, go to the default `[forge]`
+/// client, which is only built on first use so that a workspace whose repos
+/// all live on `[forge.hosts]` needs no `[forge]` token.
+pub struct HostRouter {
+    default: OnceLock<Box<dyn Forge>>,
+    make_default: ClientFactory,
+    /// `[forge.hosts]` entry name and its client.
+    hosts: Vec<(String, Box<dyn Forge>)>,
+    /// Forge project path to the indexes in `hosts` that registered it.
+    projects: HashMap<String, Vec<usize>>,
+}
+
+impl HostRouter {
+    pub fn new(make_default: impl Fn() -> Result<Box<dyn Forge>> + Send + Sync + 'static) -> Self {
+        Self {
+            default: OnceLock::new(),
+            make_default: Box::new(make_default),
+            hosts: Vec::new(),
+            projects: HashMap::new(),
+        }
+    }
+
+    /// Routes `projects` on the `[forge.hosts]` entry `host` to `client`.
+    pub fn add_host(&mut self, host: &str, client: Box<dyn Forge>, projects: Vec<String>) {
+        let index = self.hosts.len();
+        self.hosts.push((host.to_string(), client));
+        for project in projects {
+            self.projects.entry(project).or_default().push(index);
+        }
+    }
+
+    fn host_index(&self, repo: &RepoId) -> Result<Option<usize>> {
+        if let Some(host) = repo.host() {
+            return Ok(self.hosts.iter().position(|(name, _)| name == host));
+        }
+        match self.projects.get(repo.as_str()).map(Vec::as_slice) {
+            None | Some([]) => Ok(None),
+            Some([index]) => Ok(Some(*index)),
+            Some(indexes) => Err(HarmoniaError::Other(anyhow::anyhow!(
+                "project {} exists on several forge hosts ({}); cannot tell which one to use",
+                repo.as_str(),
+                indexes
+                    .iter()
+                    .map(|index| self.hosts[*index].0.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    fn default_client(&self) -> Result<&dyn Forge> {
+        if let Some(client) = self.default.get() {
+            return Ok(client.as_ref());
+        }
+        let client = (self.make_default)()?;
+        Ok(self.default.get_or_init(|| client).as_ref())
+    }
+
+    fn client(&self, index: Option<usize>) -> Result<&dyn Forge> {
+        match index {
+            Some(index) => Ok(self.hosts[index].1.as_ref()),
+            None => self.default_client(),
+        }
+    }
+
+    fn route(&self, repo: &RepoId) -> Result<&dyn Forge> {
+        self.client(self.host_index(repo)?)
+    }
+}
+
+impl Forge for HostRouter {
+    fn create_mr(&self, repo: &RepoId, params: CreateMrParams) -> Result<MergeRequest> {
+        self.route(repo)?.create_mr(repo, params)
+    }
+
+    fn get_mr(&self, repo: &RepoId, mr_id: &MrId) -> Result<MergeRequest> {
+        self.route(repo)?.get_mr(repo, mr_id)
+    }
+
+    fn get_mr_changes(&self, repo: &RepoId, mr_id: &MrId) -> Result<MrChanges> {
+        self.route(repo)?.get_mr_changes(repo, mr_id)
+    }
+
+    fn find_open_mr(&self, repo: &RepoId, source_branch: &str) -> Result<Option<MergeRequest>> {
+        self.route(repo)?.find_open_mr(repo, source_branch)
+    }
+
+    fn update_mr(
+        &self,
+        repo: &RepoId,
+        mr_id: &MrId,
+        params: UpdateMrParams,
+    ) -> Result<MergeRequest> {
+        self.route(repo)?.update_mr(repo, mr_id, params)
+    }
+
+    /// MRs can only be linked to others on the same forge, so each host links
+    /// its own.
+    fn link_mrs(&self, mrs: &[(RepoId, MrId)]) -> Result<()> {
+        let mut by_host: BTreeMap<Option<usize>, Vec<(RepoId, MrId)>> = BTreeMap::new();
+        for (repo, mr_id) in mrs {
+            by_host
+                .entry(self.host_index(repo)?)
+                .or_default()
+                .push((repo.clone(), mr_id.clone()));
+        }
+        for (index, mrs) in by_host {
+            self.client(index)?.link_mrs(&mrs)?;
+        }
+        Ok(())
+    }
+
+    fn merge_mr(&self, repo: &RepoId, mr_id: &MrId, params: MergeMrParams) -> Result<()> {
+        self.route(repo)?.merge_mr(repo, mr_id, params)
+    }
+
+    fn close_mr(&self, repo: &RepoId, mr_id: &MrId) -> Result<()> {
+        self.route(repo)?.close_mr(repo, mr_id)
+    }
+
+    fn get_ci_status(&self, repo: &RepoId, ref_name: &str) -> Result<CiStatus> {
+        self.route(repo)?.get_ci_status(repo, ref_name)
+    }
+
+    fn get_merge_requirements(&self, repo: &RepoId, branch: &str) -> Result<MergeRequirements> {
+        self.route(repo)?.get_merge_requirements(repo, branch)
+    }
+
+    fn create_issue(&self, params: CreateIssueParams) -> Result<Issue> {
+        let index = match params.project.as_ref() {
+            Some(project) => self.host_index(project)?,
+            None => None,
+        };
+        self.client(index)?.create_issue(params)
+    }
+
+    fn get_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        self.route(project)?.get_issue(project, iid)
+    }
+
+    fn update_issue(&self, project: &RepoId, iid: u64, description: &str) -> Result<Issue> {
+        self.route(project)?.update_issue(project, iid, description)
+    }
+
+    fn list_issues(&self, project: &RepoId, params: ListIssuesParams) -> Result<Vec<Issue>> {
+        self.route(project)?.list_issues(project, params)
+    }
+
+    fn close_issue(&self, project: &RepoId, iid: u64) -> Result<Issue> {
+        self.route(project)?.close_issue(project, iid)
+    }
+
+    fn comment_issue(&self, project: &RepoId, iid: u64, body: &str) -> Result<()> {
+        self.route(project)?.comment_issue(project, iid, body)
+    }
+
+    fn get_user(&self, repo: &RepoId, username: &str) -> Result<User> {
+        self.route(repo)?.get_user(repo, username)
+    }
+
+    fn create_release(&self, repo: &RepoId, params: CreateReleaseParams) -> Result<Release> {
+        self.route(repo)?.create_release(repo, params)
+    }
+
+    fn list_projects(&self, group: &str) -> Result<Vec<ForgeProject>> {
+        self.default_client()?.list_projects(group)
+    }
+
+    fn get_project(&self, repo: &RepoId) -> Result<ForgeProject> {
+        self.route(repo)?.get_project(repo)
+    }
+
+    fn count_open_mrs(&self, repo: &RepoId) -> Result<usize> {
+        self.route(repo)?.count_open_mrs(repo)
+    }
+
+    fn count_open_reviews(&self, repo: &RepoId, username: &str) -> Result<usize> {
+        self.route(repo)?.count_open_reviews(repo, username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// A forge that answers review counts and records which MRs it linked.
+    struct Stub {
+        reviews: usize,
+        linked: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Stub {
+        fn boxed(reviews: usize, linked: &Arc<Mutex<Vec<String>>>) -> Box<dyn Forge> {
+            Box::new(Self {
+                reviews,
+                linked: Arc::clone(linked),
+            })
+        }
+    }
+
+    fn unused<T>() -> Result<T> {
+        Err(HarmoniaError::Other(anyhow::anyhow!(
+            "not used by the test"
+        )))
+    }
+
+    impl Forge for Stub {
+        fn create_mr(&self, _: &RepoId, _: CreateMrParams) -> Result<MergeRequest> {
+            unused()
+        }
+        fn get_mr(&self, _: &RepoId, _: &MrId) -> Result<MergeRequest> {
+            unused()
+        }
+        fn get_mr_changes(&self, _: &RepoId, _: &MrId) -> Result<MrChanges> {
+            unused()
+        }
+        fn find_open_mr(&self, _: &RepoId, _: &str) -> Result<Option<MergeRequest>> {
+            unused()
+        }
+        fn update_mr(&self, _: &RepoId, _: &MrId, _: UpdateMrParams) -> Result<MergeRequest> {
+            unused()
+        }
+        fn link_mrs(&self, mrs: &[(RepoId, MrId)]) -> Result<()> {
+            let mut linked = self.linked.lock().unwrap();
+            linked.extend(mrs.iter().map(|(repo, _)| repo.as_str().to_string()));
+            Ok(())
+        }
+        fn merge_mr(&self, _: &RepoId, _: &MrId, _: MergeMrParams) -> Result<()> {
+            unused()
+        }
+        fn close_mr(&self, _: &RepoId, _: &MrId) -> Result<()> {
+            unused()
+        }
+        fn get_ci_status(&self, _: &RepoId, _: &str) -> Result<CiStatus> {
+            unused()
+        }
+        fn get_merge_requirements(&self, _: &RepoId, _: &str) -> Result<MergeRequirements> {
+            unused()
+        }
+        fn create_issue(&self, _: CreateIssueParams) -> Result<Issue> {
+            unused()
+        }
+        fn get_issue(&self, _: &RepoId, _: u64) -> Result<Issue> {
+            unused()
+        }
+        fn update_issue(&self, _: &RepoId, _: u64, _: &str) -> Result<Issue> {
+            unused()
+        }
+        fn list_issues(&self, _: &RepoId, _: ListIssuesParams) -> Result<Vec<Issue>> {
+            unused()
+        }
+        fn close_issue(&self, _: &RepoId, _: u64) -> Result<Issue> {
+            unused()
+        }
+        fn comment_issue(&self, _: &RepoId, _: u64, _: &str) -> Result<()> {
+            unused()
+        }
+        fn get_user(&self, _: &RepoId, _: &str) -> Result<User> {
+            unused()
+        }
+        fn create_release(&self, _: &RepoId, _: CreateReleaseParams) -> Result<Release> {
+            unused()
+        }
+        fn list_projects(&self, _: &str) -> Result<Vec<ForgeProject>> {
+            unused()
+        }
+        fn get_project(&self, _: &RepoId) -> Result<ForgeProject> {
+            unused()
+        }
+        fn count_open_mrs(&self, _: &RepoId) -> Result<usize> {
+            unused()
+        }
+        fn count_open_reviews(&self, _: &RepoId, _: &str) -> Result<usize> {
+            Ok(self.reviews)
+        }
+    }
+
+    #[test]
+    fn routes_calls_to_each_projects_host_without_a_default_token() {
+        let public = Arc::new(Mutex::new(Vec::new()));
+        let corp = Arc::new(Mutex::new(Vec::new()));
+        let built = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&built);
+        let mut router = HostRouter::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Err(HarmoniaError::Other(anyhow::anyhow!(
+                "forge token is required"
+            )))
+        });
+        router.add_host(
+            "public",
+            Stub::boxed(2, &public),
+            vec!["org/web".to_string()],
+        );
+        router.add_host("corp", Stub::boxed(5, &corp), vec!["corp/api".to_string()]);
+
+        let web = RepoId::new("org/web");
+        let api = RepoId::new("corp/api");
+        assert_eq!(router.count_open_reviews(&web, "alice").unwrap(), 2);
+        assert_eq!(router.count_open_reviews(&api, "alice").unwrap(), 5);
+        router
+            .link_mrs(&[
+                (web.clone(), "1".to_string()),
+                (api.clone(), "2".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(*public.lock().unwrap(), vec!["org/web".to_string()]);
+        assert_eq!(*corp.lock().unwrap(), vec!["corp/api".to_string()]);
+        assert_eq!(
+            built.load(Ordering::SeqCst),
+            0,
+            "the default client is not built while every call has a host"
+        );
+
+        let err = router
+            .count_open_reviews(&RepoId::new("other/lib"), "alice")
+            .unwrap_err();
+        assert!(err.to_string().contains("forge token is required"));
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn keeps_the_same_project_path_on_two_hosts_apart() {
+        let linked = Arc::new(Mutex::new(Vec::new()));
+        let mut router = HostRouter::new(|| {
+            Err(HarmoniaError::Other(anyhow::anyhow!(
+                "forge token is required"
+            )))
+        });
+        router.add_host(
+            "public",
+            Stub::boxed(2, &linked),
+            vec!["org/web".to_string()],
+        );
+        router.add_host("corp", Stub::boxed(5, &linked), vec!["org/web".to_string()]);
+
+        let public = RepoId::on_host("org/web", "public");
+        let corp = RepoId::on_host("org/web", "corp");
+        assert_eq!(router.count_open_reviews(&public, "alice").unwrap(), 2);
+        assert_eq!(router.count_open_reviews(&corp, "alice").unwrap(), 5);
+
+        let err = router
+            .count_open_reviews(&RepoId::new("org/web"), "alice")
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("org/web exists on several forge hosts (public, corp)"),
+            "{err}"
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crate::core::repo::RepoId;
 use crate::error::Result;
-use crate::forge::{CiStatus, Issue, MergeRequest, MrId, User};
+use crate::forge::{
+    CiStatus, ForgeProject, Issue, IssueState, MergeRequest, MergeRequirements, MrChanges, MrId,
+    Release, User,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct CreateMrParams {
@@ -25,6 +28,8 @@ pub struct UpdateMrParams {
 pub struct MergeMrParams {
     pub squash: bool,
     pub delete_source_branch: bool,
+    /// Squash commit message; the first line is the commit title.
+    pub squash_commit_message: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -35,11 +40,37 @@ pub struct CreateIssueParams {
     pub labels: Vec<String>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ListIssuesParams {
+    /// `None` lists open and closed issues.
+    pub state: Option<IssueState>,
+    /// Only issues carrying every label.
+    pub labels: Vec<String>,
+    /// Stop after this many issues, newest first.
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CreateReleaseParams {
+    pub tag: String,
+    pub name: String,
+    /// Release notes, usually the changelog section for `tag`.
+    pub notes: String,
+    /// Commit or branch to create `tag` from when it does not exist yet.
+    pub target: Option<String>,
+}
+
 pub trait Forge: Send + Sync {
     fn create_mr(&self, repo: &RepoId, params: CreateMrParams) -> Result<MergeRequest>;
 
     fn get_mr(&self, repo: &RepoId, mr_id: &MrId) -> Result<MergeRequest>;
 
+    /// Files changed by the MR and their added and deleted line counts.
+    fn get_mr_changes(&self, repo: &RepoId, mr_id: &MrId) -> Result<MrChanges>;
+
+    /// The open MR from `source_branch` in `repo`, if one exists.
+    fn find_open_mr(&self, repo: &RepoId, source_branch: &str) -> Result<Option<MergeRequest>>;
+
     fn update_mr(
         &self,
         repo: &RepoId,
@@ -55,7 +86,43 @@ pub trait Forge: Send + Sync {
 
     fn get_ci_status(&self, repo: &RepoId, ref_name: &str) -> Result<CiStatus>;
 
+    /// Required checks and approvals for merging into `branch`, read from the
+    /// forge's branch protection and approval settings. Unprotected branches,
+    /// and settings the token may not read, yield no requirements.
+    fn get_merge_requirements(&self, repo: &RepoId, branch: &str) -> Result<MergeRequirements>;
+
     fn create_issue(&self, params: CreateIssueParams) -> Result<Issue>;
 
-    fn get_user(&self, username: &str) -> Result<User>;
+    fn get_issue(&self, project: &RepoId, iid: u64) -> Result<Issue>;
+
+    /// Replaces the description of issue `iid` in `project`.
+    fn update_issue(&self, project: &RepoId, iid: u64, description: &str) -> Result<Issue>;
+
+    /// Issues in `project`, newest first. Pull requests are not issues here,
+    /// even on forges that list them together.
+    fn list_issues(&self, project: &RepoId, params: ListIssuesParams) -> Result<Vec<Issue>>;
+
+    fn close_issue(&self, project: &RepoId, iid: u64) -> Result<Issue>;
+
+    fn comment_issue(&self, project: &RepoId, iid: u64, body: &str) -> Result<()>;
+
+    /// Looks up `username` on the forge hosting `repo`. Clients for a single
+    /// forge ignore `repo`; it matters when a workspace spans several.
+    fn get_user(&self, repo: &RepoId, username: &str) -> Result<User>;
+
+    /// Publishes a release for `params.tag` in `repo`.
+    fn create_release(&self, repo: &RepoId, params: CreateReleaseParams) -> Result<Release>;
+
+    /// Every project in `group`, including subgroups where the forge has them.
+    fn list_projects(&self, group: &str) -> Result<Vec<ForgeProject>>;
+
+    /// The project behind `repo`.
+    fn get_project(&self, repo: &RepoId) -> Result<ForgeProject>;
+
+    /// Number of open MRs targeting any branch of `repo`.
+    fn count_open_mrs(&self, repo: &RepoId) -> Result<usize>;
+
+    /// Number of open MRs anywhere on the forge hosting `repo` awaiting
+    /// review from `username`.
+    fn count_open_reviews(&self, repo: &RepoId, username: &str) -> Result<usize>;
 }
@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::util::hmac::{constant_time_eq, hex_encode, hmac_sha256};
+
+/// Largest body accepted, the cap GitHub puts on its own deliveries.
+const MAX_BODY_BYTES: usize = 25 * 1024 * 1024;
+const MAX_HEADER_LINES: usize = 100;
+
+/// Forge that sent a webhook delivery, taken from its event header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookSource {
+    GitHub,
+    GitLab,
+}
+
+impl WebhookSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+}
+
+/// One HTTP request read off a webhook connection.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookRequest {
+    pub method: String,
+    pub path: String,
+    /// Header values by lowercased name.
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl WebhookRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// A delivery whose signature or token checked out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub source: WebhookSource,
+    /// Normalized event name, see [`webhook_event_name`].
+    pub event: String,
+    /// `org/repo` (GitHub) or `group/project` (GitLab) the event is about.
+    pub project: Option<String>,
+}
+
+/// Why a delivery was turned away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookRejection {
+    /// Neither a GitHub nor a GitLab event header.
+    UnknownSource,
+    MissingSignature(WebhookSource),
+    InvalidSignature(WebhookSource),
+    InvalidPayload(String),
+}
+
+impl WebhookRejection {
+    /// HTTP status to answer the delivery with.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::MissingSignature(_) | Self::InvalidSignature(_) => 401,
+            Self::UnknownSource | Self::InvalidPayload(_) => 400,
+        }
+    }
+}
+
+impl fmt::Display for WebhookRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSource => {
+                write!(f, "missing X-GitHub-Event or X-Gitlab-Event header")
+            }
+            Self::MissingSignature(WebhookSource::GitHub) => {
+                write!(f, "missing X-Hub-Signature-256 header")
+            }
+            Self::MissingSignature(WebhookSource::GitLab) => {
+                write!(f, "missing X-Gitlab-Token header")
+            }
+            Self::InvalidSignature(WebhookSource::GitHub) => {
+                write!(f, "X-Hub-Signature-256 does not match the body")
+            }
+            Self::InvalidSignature(WebhookSource::GitLab) => {
+                write!(f, "X-Gitlab-Token does not match the secret")
+            }
+            Self::InvalidPayload(err) => write!(f, "payload is not JSON: {err}"),
+        }
+    }
+}
+
+/// Reads one HTTP/1.1 request with a `Content-Length` body. Chunked bodies
+/// are not supported; neither forge sends them.
+pub fn read_request(reader: impl Read) -> io::Result<WebhookRequest> {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(invalid_request("malformed request line"));
+    };
+    let mut request = WebhookRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        ..WebhookRequest::default()
+    };
+
+    for _ in 0..MAX_HEADER_LINES {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_request("connection closed inside the headers"));
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            let length = match request.header("content-length") {
+                Some(value) => value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| invalid_request("invalid Content-Length"))?,
+                None => 0,
+            };
+            if length > MAX_BODY_BYTES {
+                return Err(invalid_request("body is too large"));
+            }
+            request.body = vec![0; length];
+            reader.read_exact(&mut request.body)?;
+            return Ok(request);
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(invalid_request("malformed header"));
+        };
+        request
+            .headers
+            .insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+    Err(invalid_request("too many headers"))
+}
+
+fn invalid_request(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Writes a JSON response and lets the connection close.
+pub fn write_response(
+    writer: &mut impl Write,
+    status: u16,
+    body: &serde_json::Value,
+) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    let body = body.to_string();
+    write!(
+        writer,
+        "HTTP/1.1 {status} {reason}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    writer.flush()
+}
+
+/// Checks a delivery's signature (GitHub) or token (GitLab) against `secret`
+/// before looking at its payload, then names its event and project.
+pub fn verify_delivery(
+    secret: &str,
+    request: &WebhookRequest,
+) -> std::result::Result<WebhookDelivery, WebhookRejection> {
+    let (source, event_header) = if let Some(event) = request.header("x-github-event") {
+        (WebhookSource::GitHub, event)
+    } else if let Some(event) = request.header("x-gitlab-event") {
+        (WebhookSource::GitLab, event)
+    } else {
+        return Err(WebhookRejection::UnknownSource);
+    };
+    let verified = match source {
+        WebhookSource::GitHub => request
+            .header("x-hub-signature-256")
+            .map(|header| verify_github_signature(secret, &request.body, header)),
+        WebhookSource::GitLab => request
+            .header("x-gitlab-token")
+            .map(|header| verify_gitlab_token(secret, header)),
+    };
+    match verified {
+        None => return Err(WebhookRejection::MissingSignature(source)),
+        Some(false) => return Err(WebhookRejection::InvalidSignature(source)),
+        Some(true) => {}
+    }
+
+    let payload: serde_json::Value = serde_json::from_slice(&request.body)
+        .map_err(|err| WebhookRejection::InvalidPayload(err.to_string()))?;
+    let project = match source {
+        WebhookSource::GitHub => payload.pointer("/repository/full_name"),
+        WebhookSource::GitLab => payload.pointer("/project/path_with_namespace"),
+    }
+    .and_then(|value| value.as_str())
+    .map(str::to_string);
+    Ok(WebhookDelivery {
+        source,
+        event: webhook_event_name(source, event_header, &payload),
+        project,
+    })
+}
+
+/// Checks GitHub's `X-Hub-Signature-256` header (`sha256=<hex>`) against an
+/// HMAC-SHA256 of the raw request body.
+pub fn verify_github_signature(secret: &str, body: &[u8], header: &str) -> bool {
+    let Some(expected) = header.trim().strip_prefix("sha256=") else {
+        return false;
+    };
+    let actual = hex_encode(&hmac_sha256(secret.as_bytes(), body));
+    constant_time_eq(expected.to_ascii_lowercase().as_bytes(), actual.as_bytes())
+}
+
+/// Checks GitLab's `X-Gitlab-Token` header, which carries the secret as-is.
+pub fn verify_gitlab_token(secret: &str, header: &str) -> bool {
+    constant_time_eq(secret.as_bytes(), header.trim().as_bytes())
+}
+
+/// Normalized `<kind>.<action>` name of a delivery, for example
+/// `pull_request.closed` (GitHub) or `merge_request.merge` (GitLab). Events
+/// without an action are just `<kind>`, such as `push`.
+pub fn webhook_event_name(
+    source: WebhookSource,
+    event_header: &str,
+    payload: &serde_json::Value,
+) -> String {
+    let (kind, action) = match source {
+        WebhookSource::GitHub => (
+            event_header.trim().to_string(),
+            payload.get("action").and_then(|value| value.as_str()),
+        ),
+        WebhookSource::GitLab => (
+            payload
+                .get("object_kind")
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    event_header
+                        .trim()
+                        .trim_end_matches(" Hook")
+                        .to_ascii_lowercase()
+                        .replace(' ', "_")
+                }),
+            payload
+                .get("object_attributes")
+                .and_then(|attributes| attributes.get("action"))
+                .and_then(|value| value.as_str()),
+        ),
+    };
+    match action {
+        Some(action) if !action.is_empty() => format!("{kind}.{action}"),
+        _ => kind,
+    }
+}
+
+/// Whether `event` passes the configured filters. An empty filter list allows
+/// everything; `kind` matches every action of that kind and `kind.*` or `*`
+/// work as wildcards.
+pub fn webhook_event_allowed(filters: &[String], event: &str) -> bool {
+    filters.is_empty()
+        || filters
+            .iter()
+            .any(|filter| event_filter_matches(filter, event))
+}
+
+/// Command configured for `event`, preferring the most specific filter:
+/// `kind.action`, then `kind.*` or `kind`, then `*`.
+pub fn webhook_action<'a>(actions: &'a BTreeMap<String, String>, event: &str) -> Option<&'a str> {
+    let kind = event.split_once('.').map_or(event, |(kind, _)| kind);
+    let wildcard = format!("{kind}.*");
+    let action = [event, wildcard.as_str(), kind, "*"]
+        .into_iter()
+        .find_map(|filter| actions.get(filter));
+    action.map(String::as_str)
+}
+
+fn event_filter_matches(filter: &str, event: &str) -> bool {
+    let kind = event.split_once('.').map_or(event, |(kind, _)| kind);
+    let filter = filter.trim();
+    filter == "*"
+        || filter == event
+        || filter == kind
+        || filter
+            .strip_suffix(".*")
+            .is_some_and(|prefix| prefix == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::forge::webhook::{
+        read_request, verify_delivery, verify_github_signature, verify_gitlab_token,
+        webhook_action, webhook_event_allowed, webhook_event_name, WebhookRejection,
+        WebhookRequest, WebhookSource,
+    };
+    use crate::util::hmac::{hex_encode, hmac_sha256};
+
+    fn github_request(body: &str, signature: Option<String>) -> WebhookRequest {
+        let mut request = WebhookRequest {
+            method: "POST".to_string(),
+            path: "/".to_string(),
+            body: body.as_bytes().to_vec(),
+            ..WebhookRequest::default()
+        };
+        request
+            .headers
+            .insert("x-github-event".to_string(), "pull_request".to_string());
+        if let Some(signature) = signature {
+            request
+                .headers
+                .insert("x-hub-signature-256".to_string(), signature);
+        }
+        request
+    }
+
+    #[test]
+    fn verifies_github_signatures_and_gitlab_tokens() {
+        // Example delivery from GitHub's webhook validation docs.
+        let header = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_github_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            header
+        ));
+        assert!(!verify_github_signature(
+            "other secret",
+            b"Hello, World!",
+            header
+        ));
+        assert!(!verify_github_signature(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            "sha1=757107ea"
+        ));
+
+        assert!(verify_gitlab_token("s3cret", "s3cret"));
+        assert!(!verify_gitlab_token("s3cret", "s3cre"));
+    }
+
+    #[test]
+    fn deliveries_need_a_matching_signature() {
+        let body = r#"{"action":"closed","repository":{"full_name":"org/api"}}"#;
+        let signature = format!(
+            "sha256={}",
+            hex_encode(&hmac_sha256(b"s3cret", body.as_bytes()))
+        );
+
+        let delivery = verify_delivery("s3cret", &github_request(body, Some(signature.clone())))
+            .expect("valid signature");
+        assert_eq!(delivery.source, WebhookSource::GitHub);
+        assert_eq!(delivery.event, "pull_request.closed");
+        assert_eq!(delivery.project.as_deref(), Some("org/api"));
+
+        assert_eq!(
+            verify_delivery("other", &github_request(body, Some(signature.clone()))),
+            Err(WebhookRejection::InvalidSignature(WebhookSource::GitHub))
+        );
+        let tampered = body.replace("closed", "opened");
+        assert_eq!(
+            verify_delivery("s3cret", &github_request(&tampered, Some(signature))),
+            Err(WebhookRejection::InvalidSignature(WebhookSource::GitHub))
+        );
+        let missing = verify_delivery("s3cret", &github_request(body, None));
+        assert_eq!(
+            missing,
+            Err(WebhookRejection::MissingSignature(WebhookSource::GitHub))
+        );
+        assert_eq!(missing.unwrap_err().status(), 401);
+
+        let mut gitlab = WebhookRequest {
+            body: br#"{"object_kind":"push","project":{"path_with_namespace":"group/api"}}"#
+                .to_vec(),
+            ..WebhookRequest::default()
+        };
+        gitlab
+            .headers
+            .insert("x-gitlab-event".to_string(), "Push Hook".to_string());
+        assert_eq!(
+            verify_delivery("s3cret", &gitlab),
+            Err(WebhookRejection::MissingSignature(WebhookSource::GitLab))
+        );
+        gitlab
+            .headers
+            .insert("x-gitlab-token".to_string(), "wrong".to_string());
+        assert_eq!(
+            verify_delivery("s3cret", &gitlab),
+            Err(WebhookRejection::InvalidSignature(WebhookSource::GitLab))
+        );
+        gitlab
+            .headers
+            .insert("x-gitlab-token".to_string(), "s3cret".to_string());
+        let delivery = verify_delivery("s3cret", &gitlab).expect("valid token");
+        assert_eq!(delivery.event, "push");
+        assert_eq!(delivery.project.as_deref(), Some("group/api"));
+
+        assert_eq!(
+            verify_delivery("s3cret", &WebhookRequest::default()),
+            Err(WebhookRejection::UnknownSource)
+        );
+    }
+
+    #[test]
+    fn reads_requests_with_a_content_length_body() {
+        let raw = "POST /hooks HTTP/1.1\r\nHost: localhost\r\nX-GitHub-Event: push\r\nContent-Length: 2\r\n\r\n{}";
+        let request = read_request(raw.as_bytes()).expect("read request");
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hooks");
+        assert_eq!(request.header("X-GitHub-Event"), Some("push"));
+        assert_eq!(request.body, b"{}");
+
+        assert!(read_request("POST / HTTP/1.1\r\nContent-Length: 9\r\n\r\n{}".as_bytes()).is_err());
+        assert!(read_request("garbage\r\n\r\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn names_and_filters_events() {
+        let github = serde_json::json!({ "action": "closed" });
+        let event = webhook_event_name(WebhookSource::GitHub, "pull_request", &github);
+        assert_eq!(event, "pull_request.closed");
+
+        let gitlab = serde_json::json!({
+            "object_kind": "merge_request",
+            "object_attributes": { "action": "merge" },
+        });
+        assert_eq!(
+            webhook_event_name(WebhookSource::GitLab, "Merge Request Hook", &gitlab),
+            "merge_request.merge"
+        );
+        assert_eq!(
+            webhook_event_name(WebhookSource::GitLab, "Push Hook", &serde_json::json!({})),
+            "push"
+        );
+
+        assert!(webhook_event_allowed(&[], &event));
+        assert!(webhook_event_allowed(&["pull_request".to_string()], &event));
+        assert!(webhook_event_allowed(
+            &["pull_request.*".to_string()],
+            &event
+        ));
+        assert!(webhook_event_allowed(
+            &["pull_request.closed".to_string()],
+            &event
+        ));
+        assert!(!webhook_event_allowed(
+            &["pull_request.opened".to_string()],
+            &event
+        ));
+        assert!(!webhook_event_allowed(&["push".to_string()], &event));
+    }
+
+    #[test]
+    fn picks_the_most_specific_action() {
+        let actions: BTreeMap<String, String> = [
+            ("*", "any"),
+            ("pull_request", "kind"),
+            ("pull_request.closed", "exact"),
+        ]
+        .into_iter()
+        .map(|(filter, command)| (filter.to_string(), command.to_string()))
+        .collect();
+        assert_eq!(
+            webhook_action(&actions, "pull_request.closed"),
+            Some("exact")
+        );
+        assert_eq!(
+            webhook_action(&actions, "pull_request.opened"),
+            Some("kind")
+        );
+        assert_eq!(webhook_action(&actions, "push"), Some("any"));
+        assert_eq!(webhook_action(&BTreeMap::new(), "push"), None);
+    }
+}
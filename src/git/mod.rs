@@ -1,3 +1,4 @@
 pub mod diff;
 pub mod ops;
 pub mod status;
+pub mod transport;
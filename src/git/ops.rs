@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -46,6 +46,197 @@ pub fn open_repo(path: &Path) -> Result<OpenRepo> {
     })
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloneHealth {
+    Missing,
+    Valid,
+    /// Directory tree without a single file, such as the leftover of a clone
+    /// interrupted before fetching; safe to delete and re-clone.
+    Partial(String),
+    /// Path exists but holds something other than a usable clone; never
+    /// removed automatically, since it may hold work that is not committed
+    /// or not readable by harmonia.
+    Foreign(String),
+}
+
+pub fn inspect_clone(path: &Path) -> CloneHealth {
+    if !path.exists() {
+        return CloneHealth::Missing;
+    }
+    if !path.is_dir() {
+        return CloneHealth::Foreign("path is not a directory".to_string());
+    }
+    if holds_no_files(path) {
+        return CloneHealth::Partial("directory holds no files".to_string());
+    }
+    if !path.join(".git").exists() {
+        return CloneHealth::Foreign("directory has no .git".to_string());
+    }
+
+    let repo = match gix::open(path) {
+        Ok(repo) => repo,
+        Err(err) => return CloneHealth::Foreign(format!("repository cannot be opened: {err}")),
+    };
+    if repo.head_id().is_err() {
+        return CloneHealth::Foreign("HEAD does not resolve to a commit".to_string());
+    }
+    if repo
+        .index_or_empty()
+        .map(|index| index.entries().is_empty())
+        .unwrap_or(true)
+    {
+        return CloneHealth::Foreign("worktree has no checked out files".to_string());
+    }
+
+    CloneHealth::Valid
+}
+
+/// Whether `path` contains only directories, all the way down. Unreadable
+/// entries count as files so that nothing unknown gets deleted.
+fn holds_no_files(path: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return false;
+    };
+    entries.into_iter().all(|entry| {
+        entry.is_ok_and(|entry| {
+            entry.file_type().is_ok_and(|kind| kind.is_dir()) && holds_no_files(&entry.path())
+        })
+    })
+}
+
+/// Tracked paths, and directories leading to them, that differ only by
+/// letter case, one sorted group per collision. Checkouts on case-insensitive
+/// filesystems (macOS, Windows) keep one file of each group and report the
+/// others as modified.
+pub fn case_conflicts(repo: &gix::Repository) -> Result<Vec<Vec<String>>> {
+    let index = repo
+        .index_or_empty()
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?;
+    let mut spellings: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for entry in index.entries() {
+        let path = entry.path(&index).to_str_lossy().to_string();
+        for (end, _) in path.match_indices('/') {
+            let dir = &path[..=end];
+            spellings
+                .entry(dir.to_lowercase())
+                .or_default()
+                .insert(dir.to_string());
+        }
+        spellings
+            .entry(path.to_lowercase())
+            .or_default()
+            .insert(path);
+    }
+    Ok(spellings
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|paths| paths.into_iter().collect())
+        .collect())
+}
+
+/// Runs `git fsck` against an existing clone and returns its diagnostics on failure.
+pub fn verify_clone(path: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .args(["fsck", "--no-progress", "--no-dangling"])
+        .current_dir(path)
+        .output()
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let details = format!("{}{}", stdout.trim(), stderr.trim());
+    Err(HarmoniaError::Git(anyhow::anyhow!(format!(
+        "git fsck failed for {}: {}",
+        path.display(),
+        details.trim()
+    ))))
+}
+
+/// Clones into a sibling staging directory and renames it into place, so an
+/// interrupted or concurrent clone never leaves a half-populated `dest`.
+pub fn clone_repo_atomic(url: &str, dest: &Path, depth: Option<u32>) -> Result<()> {
+    let parent = dest.parent().unwrap_or_else(|| Path::new("."));
+    let name = dest
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    let staging = parent.join(format!(".{}.harmonia-clone-{}", name, std::process::id()));
+    if staging.exists() {
+        remove_clone_dir(&staging)?;
+    }
+
+    if let Err(err) = clone_repo(url, &staging, depth) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+
+    if let Err(err) = std::fs::rename(&staging, dest) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "failed to move clone into {}: {} (another clone may have finished first)",
+            dest.display(),
+            err
+        ))));
+    }
+
+    Ok(())
+}
+
+/// Removes staging directories left behind by interrupted `clone_repo_atomic` runs.
+pub fn remove_stale_clone_staging(dest: &Path) -> Result<usize> {
+    let Some(parent) = dest.parent() else {
+        return Ok(0);
+    };
+    let Some(name) = dest
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+    else {
+        return Ok(0);
+    };
+    if !parent.is_dir() {
+        return Ok(0);
+    }
+    let prefix = format!(".{}.harmonia-clone-", name);
+    let mut removed = 0;
+    for entry in std::fs::read_dir(parent)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = file_name
+            .strip_prefix(&prefix)
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if staging_owner_may_be_running(pid) {
+            continue;
+        }
+        remove_clone_dir(&entry.path())?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+fn staging_owner_may_be_running(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    let proc_root = Path::new("/proc");
+    if !proc_root.is_dir() {
+        // Without a process table we cannot tell, so leave the directory alone.
+        return true;
+    }
+    proc_root.join(pid.to_string()).exists()
+}
+
+pub fn remove_clone_dir(path: &Path) -> Result<()> {
+    std::fs::remove_dir_all(path)
+        .with_context(|| format!("failed to remove {}", path.display()))
+        .map_err(HarmoniaError::Other)
+}
+
 pub fn clone_repo(url: &str, dest: &Path, depth: Option<u32>) -> Result<()> {
     let mut prepare =
         gix::prepare_clone(url, dest).map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?;
@@ -69,7 +260,23 @@ pub fn clone_repo(url: &str, dest: &Path, depth: Option<u32>) -> Result<()> {
 }
 
 pub fn sync_repo(repo: &gix::Repository, options: SyncOptions) -> Result<SyncOutcome> {
-    let fetch = fetch_repo(repo, options.prune)?;
+    let fetch = fetch_for_sync(repo, options)?;
+    apply_sync(repo, fetch, options)
+}
+
+/// The network half of [`sync_repo`]: fetches (and prunes) without touching
+/// the current branch.
+pub fn fetch_for_sync(repo: &gix::Repository, options: SyncOptions) -> Result<FetchOutcome> {
+    fetch_repo(repo, options.prune)
+}
+
+/// The local half of [`sync_repo`]: integrates a [`fetch_for_sync`] result
+/// into the current branch.
+pub fn apply_sync(
+    repo: &gix::Repository,
+    fetch: FetchOutcome,
+    options: SyncOptions,
+) -> Result<SyncOutcome> {
     if options.fetch_only {
         return Ok(SyncOutcome {
             fast_forwarded: false,
@@ -106,6 +313,95 @@ pub fn sync_repo(repo: &gix::Repository, options: SyncOptions) -> Result<SyncOut
     })
 }
 
+/// What [`sync_repo`] would do to the current branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// The branch already contains its upstream.
+    UpToDate,
+    FastForward,
+    Merge,
+    Rebase,
+    /// Histories diverged and `ff_only` forbids integrating them.
+    Diverged,
+}
+
+impl SyncAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UpToDate => "up-to-date",
+            Self::FastForward => "fast-forward",
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::Diverged => "diverged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncPreview {
+    pub action: SyncAction,
+    /// Local commits the upstream does not have.
+    pub ahead: usize,
+    /// Upstream commits the branch does not have.
+    pub behind: usize,
+    /// The working tree has changes that would block the update without
+    /// `autostash`.
+    pub needs_stash: bool,
+}
+
+/// Fetches like [`sync_repo`] (without pruning) and reports what it would do
+/// to the current branch, leaving branches and the working tree untouched.
+pub fn preview_sync(repo: &gix::Repository, options: SyncOptions) -> Result<SyncPreview> {
+    let fetch = fetch_repo(repo, false)?;
+    let tracking =
+        tracking_ref_name_for_head(repo, fetch.remote_name.as_deref())?.ok_or_else(|| {
+            HarmoniaError::Other(anyhow::anyhow!("no upstream tracking branch configured"))
+        })?;
+    let local_id = repo
+        .head_id()
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?
+        .detach();
+    let remote_id = repo
+        .find_reference(tracking.as_bstr())
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?
+        .peel_to_id()
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?
+        .detach();
+
+    let counts = run_git_command_output(
+        repo,
+        &[
+            "rev-list",
+            "--left-right",
+            "--count",
+            format!("{local_id}...{remote_id}").as_str(),
+        ],
+        "count commits against upstream",
+    )?;
+    let mut parts = counts.split_whitespace();
+    let ahead = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    let behind = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+
+    let action = if behind == 0 {
+        SyncAction::UpToDate
+    } else if options.rebase {
+        SyncAction::Rebase
+    } else if ahead == 0 {
+        SyncAction::FastForward
+    } else if options.ff_only {
+        SyncAction::Diverged
+    } else {
+        SyncAction::Merge
+    };
+    let changes_branch = !matches!(action, SyncAction::UpToDate | SyncAction::Diverged);
+    Ok(SyncPreview {
+        action,
+        ahead,
+        behind,
+        needs_stash: changes_branch && !options.autostash && !repo_status(repo)?.is_clean(),
+    })
+}
+
 pub fn repo_status(repo: &gix::Repository) -> Result<StatusSummary> {
     let platform = repo
         .status(Discard)
@@ -184,6 +480,14 @@ pub fn branch_exists(repo: &gix::Repository, name: &str) -> Result<bool> {
         .is_some())
 }
 
+pub fn tag_exists(repo: &gix::Repository, name: &str) -> Result<bool> {
+    let full_name = format!("refs/tags/{name}");
+    Ok(repo
+        .try_find_reference(full_name.as_str())
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?
+        .is_some())
+}
+
 pub fn create_branch(repo: &gix::Repository, name: &str, force: bool) -> Result<()> {
     let target = repo
         .head_id()
@@ -227,6 +531,85 @@ pub fn checkout_branch(repo: &gix::Repository, name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Checks out `commit`, fetching first when it is not available locally.
+/// When `branch` exists and points at `commit`, the branch is checked out;
+/// otherwise HEAD is detached at `commit`. Returns true when HEAD ends up on
+/// `branch`.
+pub fn checkout_commit(repo: &gix::Repository, commit: &str, branch: Option<&str>) -> Result<bool> {
+    let spec = format!("{commit}^{{commit}}");
+    let verify = ["rev-parse", "--verify", "--quiet", spec.as_str()];
+    if run_git_command_optional(repo, &verify)?.is_none() {
+        fetch_repo(repo, false)?;
+        if run_git_command_optional(repo, &verify)?.is_none() {
+            return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "commit {commit} not found locally or on the remote"
+            ))));
+        }
+    }
+
+    let status = repo_status(repo)?;
+    if !status.is_clean() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(
+            "working tree has uncommitted changes"
+        )));
+    }
+
+    if let Some(branch) = branch.filter(|branch| !branch.is_empty() && *branch != "HEAD") {
+        let full_name = format!("refs/heads/{branch}");
+        let tip = run_git_command_optional(
+            repo,
+            &["rev-parse", "--verify", "--quiet", full_name.as_str()],
+        )?;
+        if tip.as_deref() == Some(commit) {
+            checkout_branch(repo, branch)?;
+            return Ok(true);
+        }
+    }
+    run_git_command(
+        repo,
+        &["checkout", "--quiet", "--detach", commit],
+        "checkout commit",
+    )?;
+    Ok(false)
+}
+
+/// What happened to local changes when a checkout stashed them first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutostashOutcome {
+    /// The worktree was clean; nothing was stashed.
+    NotNeeded,
+    Reapplied,
+    /// Re-applying the stash conflicted; the stash entry is kept.
+    Conflicted,
+}
+
+/// Like `checkout_branch`, but stashes local changes (including untracked
+/// files) before switching and re-applies them afterwards. If the checkout
+/// itself fails the stash is restored on the original branch.
+pub fn checkout_branch_autostash(repo: &gix::Repository, name: &str) -> Result<AutostashOutcome> {
+    if repo_status(repo)?.is_clean() {
+        checkout_branch(repo, name)?;
+        return Ok(AutostashOutcome::NotNeeded);
+    }
+    if !push_autostash(repo, "harmonia-checkout")? {
+        checkout_branch(repo, name)?;
+        return Ok(AutostashOutcome::NotNeeded);
+    }
+    if let Err(err) = checkout_branch(repo, name) {
+        return Err(match pop_autostash(repo) {
+            Ok(()) => err,
+            Err(_) => HarmoniaError::Other(anyhow::anyhow!(format!(
+                "{}. local changes were stashed and are still available in 'git stash list'",
+                err
+            ))),
+        });
+    }
+    if pop_autostash(repo).is_err() {
+        return Ok(AutostashOutcome::Conflicted);
+    }
+    Ok(AutostashOutcome::Reapplied)
+}
+
 pub fn create_and_checkout_branch(repo: &gix::Repository, name: &str) -> Result<()> {
     run_git_command(
         repo,
@@ -247,7 +630,75 @@ pub fn set_branch_upstream(
     )
 }
 
-struct FetchOutcome {
+/// Git config key used to remember which branch a Harmonia-created branch
+/// should eventually merge into.
+const BRANCH_TARGET_CONFIG_KEY: &str = "harmonia-target";
+
+pub fn head_commit(repo: &gix::Repository) -> Result<String> {
+    Ok(repo
+        .head_id()
+        .map_err(|err| HarmoniaError::Git(anyhow::Error::new(err)))?
+        .detach()
+        .to_string())
+}
+
+/// Git blob id of a worktree file, as `git hash-object` reports it.
+pub fn worktree_blob_id(repo: &gix::Repository, relative_path: &str) -> Result<String> {
+    Ok(
+        run_git_command_output(repo, &["hash-object", "--", relative_path], "hash-object")?
+            .trim()
+            .to_string(),
+    )
+}
+
+pub fn branch_upstream(repo: &gix::Repository, branch: &str) -> Result<Option<String>> {
+    let upstream = format!("{branch}@{{upstream}}");
+    run_git_command_optional(repo, &["rev-parse", "--abbrev-ref", upstream.as_str()])
+}
+
+pub fn branch_target(repo: &gix::Repository, branch: &str) -> Result<Option<String>> {
+    let key = format!("branch.{branch}.{BRANCH_TARGET_CONFIG_KEY}");
+    run_git_command_optional(repo, &["config", "--get", key.as_str()])
+}
+
+pub fn set_branch_target(repo: &gix::Repository, branch: &str, target: &str) -> Result<()> {
+    let key = format!("branch.{branch}.{BRANCH_TARGET_CONFIG_KEY}");
+    run_git_command(
+        repo,
+        &["config", key.as_str(), target],
+        "record branch target",
+    )
+}
+
+/// Commits reachable from HEAD but not from `base`, or `None` when `base`
+/// does not resolve (for example a branch without an upstream).
+pub fn commits_ahead_of(repo: &gix::Repository, base: &str) -> Result<Option<usize>> {
+    let range = format!("{base}..HEAD");
+    Ok(
+        run_git_command_optional(repo, &["rev-list", "--count", range.as_str()])?
+            .and_then(|count| count.parse().ok()),
+    )
+}
+
+/// Remote new branches are pushed to: `origin` when present, otherwise the
+/// only configured remote.
+pub fn default_push_remote(repo: &gix::Repository) -> Option<String> {
+    let names: Vec<String> = repo
+        .remote_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    if names.iter().any(|name| name == "origin") {
+        return Some("origin".to_string());
+    }
+    match names.as_slice() {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+/// What a fetch from the current branch's remote brought in.
+pub struct FetchOutcome {
     remote_name: Option<String>,
     pruned: usize,
 }
@@ -463,7 +914,7 @@ where
         )));
     }
 
-    let stashed = push_autostash(repo)?;
+    let stashed = push_autostash(repo, "harmonia-sync")?;
     let result = operation();
     match result {
         Ok(value) => {
@@ -489,18 +940,12 @@ where
     }
 }
 
-fn push_autostash(repo: &gix::Repository) -> Result<bool> {
+fn push_autostash(repo: &gix::Repository, message: &str) -> Result<bool> {
     let before = stash_entry_count(repo)?;
     run_git_command(
         repo,
-        &[
-            "stash",
-            "push",
-            "--include-untracked",
-            "--message",
-            "harmonia-sync",
-        ],
-        "stash local changes before sync",
+        &["stash", "push", "--include-untracked", "--message", message],
+        "stash local changes",
     )?;
     let after = stash_entry_count(repo)?;
     Ok(after > before)
@@ -510,7 +955,7 @@ fn pop_autostash(repo: &gix::Repository) -> Result<()> {
     run_git_command(
         repo,
         &["stash", "pop", "--index"],
-        "re-apply stashed changes after sync",
+        "re-apply stashed changes",
     )
 }
 
@@ -569,6 +1014,26 @@ fn run_git_command_output(repo: &gix::Repository, args: &[&str], context: &str)
     ))))
 }
 
+/// Runs a git query whose non-zero exit means "not set" rather than failure.
+fn run_git_command_optional(repo: &gix::Repository, args: &[&str]) -> Result<Option<String>> {
+    let workdir = repo.workdir().ok_or_else(|| {
+        HarmoniaError::Other(anyhow::anyhow!(
+            "operation requires a worktree but repository is bare"
+        ))
+    })?;
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workdir)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(if value.is_empty() { None } else { Some(value) })
+}
+
 fn tracking_ref_name_for_head(
     repo: &gix::Repository,
     remote_name: Option<&str>,
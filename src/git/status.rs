@@ -16,3 +16,35 @@ impl StatusSummary {
             && self.conflicts.is_empty()
     }
 }
+
+/// Ways a repo can count as changed for `--changed` and `changed` selections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// Uncommitted work: staged, modified, untracked, or conflicted files.
+    Dirty,
+    /// Commits on the current branch that its upstream does not have yet.
+    Unpushed,
+    /// Commits on a non-default branch that the default branch does not have.
+    Branch,
+}
+
+impl ChangeKind {
+    pub const ALL: [ChangeKind; 3] = [ChangeKind::Dirty, ChangeKind::Unpushed, ChangeKind::Branch];
+
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "dirty" => Some(Self::Dirty),
+            "unpushed" => Some(Self::Unpushed),
+            "branch" => Some(Self::Branch),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dirty => "dirty",
+            Self::Unpushed => "unpushed",
+            Self::Branch => "branch",
+        }
+    }
+}
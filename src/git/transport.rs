@@ -0,0 +1,364 @@
+//! Fetch and push through libgit2 with progress bars. Remotes this build
+//! cannot reach natively (SSH), repos that authenticate through
+//! `http.extraheader`, and operations libgit2 does not cover go through the
+//! `git` CLI instead.
+
+use std::cell::{Cell, RefCell};
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use git2::{
+    Cred, CredentialType, ErrorClass, ErrorCode, FetchOptions, ProxyOptions, PushOptions,
+    RemoteCallbacks,
+};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::error::{HarmoniaError, Result};
+use crate::util::remote_url::RemoteUrl;
+
+/// How fetches and pushes reach remotes, from `[defaults].git_transport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitTransport {
+    /// libgit2 where the remote allows it, the `git` CLI otherwise.
+    Native,
+    /// Always the `git` CLI.
+    Cli,
+}
+
+impl GitTransport {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "native" => Ok(Self::Native),
+            "cli" => Ok(Self::Cli),
+            other => Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+                "unknown git_transport '{}'; expected native or cli",
+                other
+            )))),
+        }
+    }
+}
+
+/// Whether libgit2 in this build can reach `url`: HTTP(S), `file://`, and
+/// local paths. SSH remotes need the CLI.
+pub fn native_supports_url(url: &str) -> bool {
+    let url = url.trim();
+    if url.is_empty() {
+        return false;
+    }
+    if let Some((scheme, _)) = url.split_once("://") {
+        return matches!(
+            scheme.to_ascii_lowercase().as_str(),
+            "http" | "https" | "file"
+        );
+    }
+    // scp-like `git@host:group/repo.git`
+    RemoteUrl::parse(url).is_none() && !url.contains('@')
+}
+
+/// Whether pushing from `repo` would run a git `pre-push` hook, which
+/// libgit2 skips.
+pub fn has_pre_push_hook(repo: &git2::Repository) -> bool {
+    let hooks_dir = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok())
+        .map(|path| {
+            if path.is_absolute() {
+                path
+            } else {
+                repo.workdir().unwrap_or_else(|| repo.path()).join(path)
+            }
+        })
+        .unwrap_or_else(|| repo.path().join("hooks"));
+    hooks_dir.join("pre-push").is_file()
+}
+
+/// Whether `repo`'s git config sets `http.extraheader` or
+/// `http.<url>.extraheader`, as `actions/checkout` does for its token.
+/// libgit2 ignores these headers, so such repos need the CLI.
+pub fn has_extra_header(repo: &git2::Repository) -> bool {
+    let Ok(config) = repo.config() else {
+        return false;
+    };
+    let Ok(mut entries) = config.entries(None) else {
+        return false;
+    };
+    let mut found = false;
+    while let Some(Ok(entry)) = entries.next() {
+        if let Some(name) = entry.name() {
+            let name = name.to_ascii_lowercase();
+            if name.starts_with("http.") && name.ends_with(".extraheader") {
+                found = true;
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// Whether a `native_fetch` or `native_push` error is a missing or rejected
+/// credential, which the `git` CLI may still get past with its own helpers
+/// and prompts.
+pub fn is_auth_error(err: &HarmoniaError) -> bool {
+    let HarmoniaError::Git(err) = err else {
+        return false;
+    };
+    err.downcast_ref::<git2::Error>()
+        .is_some_and(|err| err.code() == ErrorCode::Auth)
+}
+
+pub struct PushSpec<'a> {
+    pub remote: &'a str,
+    pub branch: &'a str,
+    /// Branch name on the remote, usually the same as `branch`.
+    pub remote_branch: &'a str,
+    pub force: bool,
+}
+
+/// Pushes `spec.branch` with libgit2, drawing progress under `label`.
+/// Rejected updates are returned as errors with the remote's reason.
+pub fn native_push(
+    repo: &git2::Repository,
+    spec: &PushSpec<'_>,
+    label: &str,
+    progress: &MultiProgress,
+) -> Result<()> {
+    let mut remote = repo.find_remote(spec.remote).map_err(git_error)?;
+    let bar = progress_bar(progress, label);
+    let rejected = RefCell::new(Vec::new());
+    let mut callbacks = remote_callbacks(repo);
+    {
+        let bar = bar.clone();
+        callbacks.push_transfer_progress(move |current, total, bytes| {
+            bar.set_length(total as u64);
+            bar.set_position(current as u64);
+            bar.set_message(format!("push {}", human_bytes(bytes)));
+        });
+    }
+    callbacks.push_update_reference(|reference, status| {
+        if let Some(status) = status {
+            rejected
+                .borrow_mut()
+                .push(format!("{} rejected: {}", reference, status));
+        }
+        Ok(())
+    });
+
+    let refspec = format!(
+        "{}refs/heads/{}:refs/heads/{}",
+        if spec.force { "+" } else { "" },
+        spec.branch,
+        spec.remote_branch
+    );
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    options.proxy_options(auto_proxy());
+    let result = remote.push(&[refspec.as_str()], Some(&mut options));
+    drop(options);
+    bar.finish_and_clear();
+    result.map_err(git_error)?;
+
+    let rejected = rejected.into_inner();
+    if !rejected.is_empty() {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(rejected.join("; "))));
+    }
+    Ok(())
+}
+
+/// Fetches `remote` with libgit2, updating its remote-tracking refs and
+/// drawing progress under `label`.
+pub fn native_fetch(
+    repo: &git2::Repository,
+    remote: &str,
+    label: &str,
+    progress: &MultiProgress,
+) -> Result<()> {
+    let mut remote = repo.find_remote(remote).map_err(git_error)?;
+    let bar = progress_bar(progress, label);
+    let mut callbacks = remote_callbacks(repo);
+    {
+        let bar = bar.clone();
+        callbacks.transfer_progress(move |stats| {
+            bar.set_length(stats.total_objects() as u64);
+            bar.set_position(stats.received_objects() as u64);
+            bar.set_message(format!("fetch {}", human_bytes(stats.received_bytes())));
+            true
+        });
+    }
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    options.proxy_options(auto_proxy());
+    let refspecs: [&str; 0] = [];
+    let result = remote.fetch(&refspecs, Some(&mut options), None);
+    bar.finish_and_clear();
+    result.map_err(git_error)
+}
+
+/// `http.proxy` and the `HTTPS_PROXY` family of variables, as the CLI uses.
+fn auto_proxy<'a>() -> ProxyOptions<'a> {
+    let mut proxy = ProxyOptions::new();
+    proxy.auto();
+    proxy
+}
+
+/// Credential lookup shared by fetch and push: the configured git credential
+/// helper, then libgit2's defaults. Each kind is tried once so a rejected
+/// credential fails instead of looping.
+fn remote_callbacks<'a>(repo: &git2::Repository) -> RemoteCallbacks<'a> {
+    let config = repo.config().ok();
+    let tried_helper = Cell::new(false);
+    let tried_default = Cell::new(false);
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried_helper.replace(true) {
+            if let Some(config) = config.as_ref() {
+                if let Ok(cred) = Cred::credential_helper(config, url, username) {
+                    return Ok(cred);
+                }
+            }
+        }
+        if allowed.contains(CredentialType::DEFAULT) && !tried_default.replace(true) {
+            return Cred::default();
+        }
+        Err(git2::Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Http,
+            format!("authentication failed for {url}; configure a git credential helper"),
+        ))
+    });
+    callbacks
+}
+
+fn progress_bar(progress: &MultiProgress, label: &str) -> ProgressBar {
+    let bar = if std::io::stderr().is_terminal() {
+        progress.add(ProgressBar::new(0))
+    } else {
+        ProgressBar::hidden()
+    };
+    bar.set_style(
+        ProgressStyle::with_template("{prefix:>12} [{bar:24}] {pos}/{len} {msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> "),
+    );
+    bar.set_prefix(label.to_string());
+    bar.enable_steady_tick(Duration::from_millis(200));
+    bar
+}
+
+fn human_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn git_error(err: git2::Error) -> HarmoniaError {
+    HarmoniaError::Git(anyhow::Error::new(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_transport_covers_http_file_and_local_remotes() {
+        assert!(native_supports_url("https://gitlab.com/platform/api.git"));
+        assert!(native_supports_url("file:///srv/git/api.git"));
+        assert!(native_supports_url("/srv/git/api.git"));
+        assert!(!native_supports_url("git@gitlab.com:platform/api.git"));
+        assert!(!native_supports_url(
+            "ssh://git@gitlab.com/platform/api.git"
+        ));
+        assert!(!native_supports_url(""));
+
+        assert_eq!(GitTransport::parse("CLI").expect("cli"), GitTransport::Cli);
+        assert!(GitTransport::parse("libgit2").is_err());
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(1536), "1.5 KiB");
+    }
+
+    fn unique_temp_dir(prefix: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock before epoch")
+            .as_nanos();
+        let pid = std::process::id();
+        std::env::temp_dir().join(format!("harmonia-{prefix}-{pid}-{nanos}"))
+    }
+
+    #[test]
+    fn native_push_and_fetch_reach_a_file_remote() {
+        let root = unique_temp_dir("native-transport");
+        let remote_path = root.join("remote.git");
+        git2::Repository::init_bare(&remote_path).expect("init remote");
+        let remote_url = format!("file://{}", remote_path.display());
+
+        let work = git2::Repository::init(root.join("work")).expect("init work");
+        let signature = git2::Signature::now("Test", "test@example.com").expect("signature");
+        let tree_id = work
+            .index()
+            .and_then(|mut index| index.write_tree())
+            .expect("write tree");
+        let tree = work.find_tree(tree_id).expect("find tree");
+        let commit = work
+            .commit(
+                Some("refs/heads/main"),
+                &signature,
+                &signature,
+                "initial",
+                &tree,
+                &[],
+            )
+            .expect("commit");
+        work.remote("origin", &remote_url).expect("add remote");
+        assert!(!has_extra_header(&work));
+
+        let progress = MultiProgress::with_draw_target(indicatif::ProgressDrawTarget::hidden());
+        native_push(
+            &work,
+            &PushSpec {
+                remote: "origin",
+                branch: "main",
+                remote_branch: "feature",
+                force: false,
+            },
+            "work",
+            &progress,
+        )
+        .expect("native push");
+        let remote = git2::Repository::open_bare(&remote_path).expect("open remote");
+        assert_eq!(
+            remote.refname_to_id("refs/heads/feature").expect("pushed"),
+            commit
+        );
+
+        let other = git2::Repository::init(root.join("other")).expect("init other");
+        other.remote("origin", &remote_url).expect("add remote");
+        native_fetch(&other, "origin", "other", &progress).expect("native fetch");
+        assert_eq!(
+            other
+                .refname_to_id("refs/remotes/origin/feature")
+                .expect("fetched"),
+            commit
+        );
+
+        work.config()
+            .and_then(|mut config| {
+                config.set_str(
+                    "http.https://github.com/.extraheader",
+                    "AUTHORIZATION: basic x",
+                )
+            })
+            .expect("set extraheader");
+        assert!(has_extra_header(&work));
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -1,11 +1,20 @@
 use crate::core::repo::{Dependency, Repo, RepoId};
 use crate::core::version::VersionReq;
+use crate::ecosystem::cache::ManifestCache;
 use crate::ecosystem::plugin_for;
 use crate::error::Result;
 use crate::graph::DependencyGraph;
 use std::collections::{HashMap, HashSet};
 
 pub fn build_graph(repos: &HashMap<RepoId, Repo>) -> Result<DependencyGraph> {
+    build_graph_with_cache(repos, &mut ManifestCache::disabled())
+}
+
+/// Like `build_graph`, reusing manifests parsed by earlier runs from `cache`.
+pub fn build_graph_with_cache(
+    repos: &HashMap<RepoId, Repo>,
+    cache: &mut ManifestCache,
+) -> Result<DependencyGraph> {
     let mut edges: HashMap<RepoId, Vec<Dependency>> = HashMap::new();
 
     let mut package_map: HashMap<String, RepoId> = HashMap::new();
@@ -23,17 +32,21 @@ pub fn build_graph(repos: &HashMap<RepoId, Repo>) -> Result<DependencyGraph> {
         if repo.ignored {
             continue;
         }
-        let deps = parse_repo_dependencies(repo, &package_map, &repo_name_map)?;
+        let deps = parse_repo_dependencies(repo, &package_map, &repo_name_map, cache)?;
         edges.insert(id.clone(), deps);
     }
 
-    Ok(DependencyGraph { edges })
+    Ok(DependencyGraph {
+        edges,
+        external: HashMap::new(),
+    })
 }
 
 fn parse_repo_dependencies(
     repo: &Repo,
     package_map: &HashMap<String, RepoId>,
     repo_name_map: &HashMap<String, String>,
+    cache: &mut ManifestCache,
 ) -> Result<Vec<Dependency>> {
     let deps_cfg = repo
         .config
@@ -44,9 +57,8 @@ fn parse_repo_dependencies(
     if let Some(ecosystem) = repo.ecosystem.as_ref() {
         let path = dependency_file_for_repo(repo, deps_cfg, ecosystem);
         if let Some(path) = path.filter(|path| path.is_file()) {
-            let content = std::fs::read_to_string(&path)?;
             let plugin = plugin_for(ecosystem);
-            parsed = plugin.parse_dependencies(&path, &content)?;
+            parsed = cache.load_dependencies(plugin.as_ref(), &path)?;
         }
     }
 
@@ -125,7 +137,7 @@ fn dependency_file_for_repo(
     }
 
     let plugin = plugin_for(ecosystem);
-    for pattern in plugin.file_patterns() {
+    for pattern in plugin.dependency_files() {
         let candidate = repo.path.join(pattern);
         if candidate.is_file() {
             return Some(candidate);
@@ -174,6 +186,7 @@ mod tests {
                 config: None,
                 external: false,
                 ignored: false,
+                paused: None,
             },
         )
     }
@@ -2,7 +2,10 @@ use std::collections::HashMap;
 
 use crate::core::repo::{Repo, RepoId};
 use crate::core::version::{Version, VersionReq};
-use crate::graph::ops::{find_cycles, package_map, resolve_internal_edges, MissingDependency};
+use crate::graph::ops::{
+    duplicate_package_names, find_cycles, package_map, resolve_internal_edges, DuplicatePackage,
+    ExternalDependency, MissingDependency,
+};
 use crate::graph::DependencyGraph;
 
 #[derive(Debug, Clone)]
@@ -38,6 +41,9 @@ pub struct ConstraintReport {
     pub violations: Vec<ConstraintViolation>,
     pub missing: Vec<MissingDependency>,
     pub cycles: Vec<Vec<RepoId>>,
+    pub duplicates: Vec<DuplicatePackage>,
+    /// Dependencies on declared external packages, which are not managed here.
+    pub external: Vec<ExternalDependency>,
 }
 
 pub fn check_constraints(
@@ -109,6 +115,8 @@ pub fn check_constraints(
         violations,
         missing: resolved.missing,
         cycles,
+        duplicates: duplicate_package_names(repos),
+        external: resolved.external,
     }
 }
 
@@ -221,6 +229,7 @@ mod tests {
                 config: None,
                 external: false,
                 ignored: false,
+                paused: None,
             },
         )
     }
@@ -10,6 +10,17 @@ pub mod viz;
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
     pub edges: HashMap<RepoId, Vec<Dependency>>,
+    /// Packages declared in `[external_packages]`, keyed by package name.
+    pub external: HashMap<String, ExternalPackage>,
+}
+
+/// A package repos depend on that no workspace repo provides. Dependencies on
+/// it are reported as external instead of as missing internal mappings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExternalPackage {
+    pub name: String,
+    /// Where to look up released versions, for example a registry URL.
+    pub version_url: Option<String>,
 }
 
 impl DependencyGraph {
@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use anyhow::{anyhow, Result};
 
 use crate::core::repo::{Dependency, Repo, RepoId};
-use crate::graph::DependencyGraph;
+use crate::graph::{DependencyGraph, ExternalPackage};
 
 #[derive(Debug, Clone)]
 pub struct MissingDependency {
@@ -11,10 +11,36 @@ pub struct MissingDependency {
     pub dependency: Dependency,
 }
 
+/// A dependency on a package declared in `[external_packages]`.
+#[derive(Debug, Clone)]
+pub struct ExternalDependency {
+    pub from: RepoId,
+    pub dependency: Dependency,
+    pub package: ExternalPackage,
+}
+
+/// How far a change to `repo` ripples: `dependents` counts transitive
+/// dependents and `depth` is the longest chain of dependents above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImpactScore {
+    pub repo: RepoId,
+    pub dependents: usize,
+    pub depth: usize,
+}
+
+/// A package name declared by more than one repo. Dependencies on it are not
+/// resolved to any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicatePackage {
+    pub name: String,
+    pub repos: Vec<RepoId>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ResolvedGraph {
     pub edges: HashMap<RepoId, Vec<RepoId>>,
     pub missing: Vec<MissingDependency>,
+    pub external: Vec<ExternalDependency>,
 }
 
 pub fn dependencies_for(graph: &DependencyGraph, repo: &RepoId) -> Vec<Dependency> {
@@ -40,35 +66,81 @@ pub fn dependents_of(graph: &DependencyGraph, package: &str) -> Vec<RepoId> {
         .collect()
 }
 
+/// Package name -> repo. Names declared by several repos are left out so they
+/// never resolve to an arbitrary one; see `duplicate_package_names`.
 pub fn package_map(repos: &HashMap<RepoId, Repo>) -> HashMap<String, RepoId> {
+    let duplicates: HashSet<String> = duplicate_package_names(repos)
+        .into_iter()
+        .map(|duplicate| duplicate.name)
+        .collect();
     let mut map = HashMap::new();
     for (id, repo) in repos {
         let name = repo
             .package_name
             .clone()
             .unwrap_or_else(|| id.as_str().to_string());
-        map.insert(name, id.clone());
+        if !duplicates.contains(&name) {
+            map.insert(name, id.clone());
+        }
     }
     map
 }
 
+/// Package names declared by more than one repo, sorted by name, with the
+/// repos sorted by id.
+pub fn duplicate_package_names(repos: &HashMap<RepoId, Repo>) -> Vec<DuplicatePackage> {
+    let mut by_name: HashMap<String, Vec<RepoId>> = HashMap::new();
+    for (id, repo) in repos {
+        let name = repo
+            .package_name
+            .clone()
+            .unwrap_or_else(|| id.as_str().to_string());
+        by_name.entry(name).or_default().push(id.clone());
+    }
+    let mut duplicates: Vec<DuplicatePackage> = by_name
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(name, mut repos)| {
+            repos.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            DuplicatePackage { name, repos }
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.name.cmp(&b.name));
+    duplicates
+}
+
 pub fn resolve_internal_edges(
     graph: &DependencyGraph,
     repos: &HashMap<RepoId, Repo>,
 ) -> ResolvedGraph {
     let map = package_map(repos);
+    let ambiguous: HashSet<String> = duplicate_package_names(repos)
+        .into_iter()
+        .map(|duplicate| duplicate.name)
+        .collect();
     let mut edges = HashMap::new();
     let mut missing = Vec::new();
+    let mut external = Vec::new();
 
     for (repo_id, deps) in &graph.edges {
         let mut internal = Vec::new();
         for dep in deps {
+            if let Some(package) = graph.external.get(&dep.name) {
+                if !map.contains_key(&dep.name) {
+                    external.push(ExternalDependency {
+                        from: repo_id.clone(),
+                        dependency: dep.clone(),
+                        package: package.clone(),
+                    });
+                    continue;
+                }
+            }
             if !dep.is_internal {
                 continue;
             }
             if let Some(target) = map.get(&dep.name) {
                 internal.push(target.clone());
-            } else {
+            } else if !ambiguous.contains(&dep.name) {
                 missing.push(MissingDependency {
                     from: repo_id.clone(),
                     dependency: dep.clone(),
@@ -78,7 +150,11 @@ pub fn resolve_internal_edges(
         edges.insert(repo_id.clone(), internal);
     }
 
-    ResolvedGraph { edges, missing }
+    ResolvedGraph {
+        edges,
+        missing,
+        external,
+    }
 }
 
 pub fn transitive_dependencies(
@@ -217,6 +293,150 @@ fn visit_node(
     state.insert(node.clone(), VisitState::Visited);
 }
 
+/// Impact score for every repo in the graph, highest impact first (by
+/// dependents, then depth, then name).
+pub fn impact_scores(graph: &DependencyGraph, repos: &HashMap<RepoId, Repo>) -> Vec<ImpactScore> {
+    let resolved = resolve_internal_edges(graph, repos);
+    let mut reverse: HashMap<RepoId, Vec<RepoId>> = HashMap::new();
+    for (from, deps) in &resolved.edges {
+        for dep in deps {
+            reverse.entry(dep.clone()).or_default().push(from.clone());
+        }
+    }
+
+    let mut depths = HashMap::new();
+    let mut scores: Vec<ImpactScore> = resolved
+        .edges
+        .keys()
+        .map(|repo| ImpactScore {
+            repo: repo.clone(),
+            dependents: transitive_dependents(graph, repos, repo).len(),
+            depth: longest_chain(repo, &reverse, &mut depths, &mut Vec::new()),
+        })
+        .collect();
+    scores.sort_by(|a, b| {
+        b.dependents
+            .cmp(&a.dependents)
+            .then(b.depth.cmp(&a.depth))
+            .then(a.repo.as_str().cmp(b.repo.as_str()))
+    });
+    scores
+}
+
+/// Splits `targets` into batches that can run one after another, where every
+/// repo comes after the targets it depends on, directly or through repos that
+/// are not targets. Repos within a batch are independent of each other and
+/// sorted by name.
+pub fn graph_levels(
+    graph: &DependencyGraph,
+    repos: &HashMap<RepoId, Repo>,
+    targets: &[RepoId],
+) -> Result<Vec<Vec<RepoId>>> {
+    let resolved = resolve_internal_edges(graph, repos);
+    let order =
+        topological_order_with_nodes(&resolved.edges, resolved.edges.keys().cloned().collect())?;
+    let selected: HashSet<&RepoId> = targets.iter().collect();
+    let mut level: HashMap<RepoId, usize> = HashMap::new();
+    for node in order {
+        let depth = resolved
+            .edges
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .map(|dep| level.get(dep).copied().unwrap_or(0) + usize::from(selected.contains(dep)))
+            .max()
+            .unwrap_or(0);
+        level.insert(node, depth);
+    }
+
+    let mut levels: Vec<Vec<RepoId>> = Vec::new();
+    for target in targets {
+        let depth = level.get(target).copied().unwrap_or(0);
+        if levels.len() <= depth {
+            levels.resize_with(depth + 1, Vec::new);
+        }
+        if !levels[depth].contains(target) {
+            levels[depth].push(target.clone());
+        }
+    }
+    levels.retain(|batch| !batch.is_empty());
+    for batch in levels.iter_mut() {
+        batch.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    }
+    Ok(levels)
+}
+
+/// Longest dependency chain in the graph, dependency first. Ties prefer repos
+/// that sort first by name.
+pub fn critical_path(
+    graph: &DependencyGraph,
+    repos: &HashMap<RepoId, Repo>,
+) -> Result<Vec<RepoId>> {
+    let resolved = resolve_internal_edges(graph, repos);
+    let order =
+        topological_order_with_nodes(&resolved.edges, resolved.edges.keys().cloned().collect())?;
+
+    // Nodes on the longest chain starting at each repo and following its deps.
+    let mut lengths: HashMap<RepoId, usize> = HashMap::new();
+    let mut next: HashMap<RepoId, RepoId> = HashMap::new();
+    for repo in &order {
+        let mut deps = resolved.edges.get(repo).cloned().unwrap_or_default();
+        deps.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let mut best: Option<(&RepoId, usize)> = None;
+        for dep in &deps {
+            let length = lengths.get(dep).copied().unwrap_or(0);
+            if best.is_none_or(|(_, current)| length > current) {
+                best = Some((dep, length));
+            }
+        }
+        let length = 1 + best.map(|(_, length)| length).unwrap_or(0);
+        if let Some((dep, _)) = best {
+            next.insert(repo.clone(), dep.clone());
+        }
+        lengths.insert(repo.clone(), length);
+    }
+
+    let mut starts: Vec<&RepoId> = lengths.keys().collect();
+    starts.sort_by(|a, b| {
+        lengths[*b]
+            .cmp(&lengths[*a])
+            .then(a.as_str().cmp(b.as_str()))
+    });
+    let mut path = Vec::new();
+    let mut current = starts.first().map(|repo| (*repo).clone());
+    while let Some(repo) = current {
+        current = next.get(&repo).cloned();
+        path.push(repo);
+    }
+    path.reverse();
+    Ok(path)
+}
+
+fn longest_chain(
+    node: &RepoId,
+    edges: &HashMap<RepoId, Vec<RepoId>>,
+    memo: &mut HashMap<RepoId, usize>,
+    stack: &mut Vec<RepoId>,
+) -> usize {
+    if let Some(depth) = memo.get(node) {
+        return *depth;
+    }
+    // Edges back into the current chain belong to a cycle and add no depth.
+    if stack.contains(node) {
+        return 0;
+    }
+    stack.push(node.clone());
+    let mut depth = 0;
+    for next in edges.get(node).into_iter().flatten() {
+        if !stack.contains(next) {
+            depth = depth.max(1 + longest_chain(next, edges, memo, stack));
+        }
+    }
+    stack.pop();
+    memo.insert(node.clone(), depth);
+    depth
+}
+
 fn topological_order_with_nodes(
     edges: &HashMap<RepoId, Vec<RepoId>>,
     nodes: HashSet<RepoId>,
@@ -296,8 +516,11 @@ mod tests {
 
     use crate::core::repo::{Dependency, Repo, RepoId};
     use crate::core::version::VersionReq;
-    use crate::graph::ops::{merge_order, topological_order};
-    use crate::graph::DependencyGraph;
+    use crate::graph::ops::{
+        critical_path, duplicate_package_names, graph_levels, impact_scores, merge_order,
+        resolve_internal_edges, topological_order,
+    };
+    use crate::graph::{DependencyGraph, ExternalPackage};
 
     fn make_repo(name: &str) -> Repo {
         Repo {
@@ -311,6 +534,7 @@ mod tests {
             config: None,
             external: false,
             ignored: false,
+            paused: None,
         }
     }
 
@@ -340,6 +564,7 @@ mod tests {
                 (RepoId::new("lib"), vec![make_dependency("core")]),
                 (RepoId::new("core"), Vec::new()),
             ]),
+            ..DependencyGraph::default()
         };
 
         let order = topological_order(&graph, &repos).expect("topological order should succeed");
@@ -350,6 +575,31 @@ mod tests {
         assert_eq!(names, vec!["core", "lib", "app"]);
     }
 
+    #[test]
+    fn graph_levels_batch_independent_targets_and_skip_unselected_repos() {
+        let mut repos = make_repos();
+        let tool = make_repo("tool");
+        repos.insert(tool.id.clone(), tool);
+        let graph = DependencyGraph {
+            edges: HashMap::from([
+                (RepoId::new("app"), vec![make_dependency("lib")]),
+                (RepoId::new("lib"), vec![make_dependency("core")]),
+                (RepoId::new("core"), Vec::new()),
+                (RepoId::new("tool"), Vec::new()),
+            ]),
+            ..DependencyGraph::default()
+        };
+
+        let targets = [RepoId::new("app"), RepoId::new("tool"), RepoId::new("core")];
+        let levels = graph_levels(&graph, &repos, &targets).expect("levels");
+        let names: Vec<Vec<&str>> = levels
+            .iter()
+            .map(|batch| batch.iter().map(|id| id.as_str()).collect())
+            .collect();
+        // app depends on core through lib, which is not a target.
+        assert_eq!(names, vec![vec!["core", "tool"], vec!["app"]]);
+    }
+
     #[test]
     fn merge_order_includes_dependencies_before_target() {
         let repos = make_repos();
@@ -359,6 +609,7 @@ mod tests {
                 (RepoId::new("lib"), vec![make_dependency("core")]),
                 (RepoId::new("core"), Vec::new()),
             ]),
+            ..DependencyGraph::default()
         };
 
         let order =
@@ -370,6 +621,63 @@ mod tests {
         assert_eq!(names, vec!["core", "lib", "app"]);
     }
 
+    #[test]
+    fn duplicate_package_names_are_reported_and_never_resolved() {
+        let mut repos = make_repos();
+        let mut fork = make_repo("core-fork");
+        fork.package_name = Some("core".to_string());
+        repos.insert(fork.id.clone(), fork);
+        let graph = DependencyGraph {
+            edges: HashMap::from([
+                (RepoId::new("app"), vec![make_dependency("lib")]),
+                (RepoId::new("lib"), vec![make_dependency("core")]),
+            ]),
+            ..DependencyGraph::default()
+        };
+
+        let duplicates = duplicate_package_names(&repos);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].name, "core");
+        assert_eq!(
+            duplicates[0].repos,
+            vec![RepoId::new("core"), RepoId::new("core-fork")]
+        );
+
+        let resolved = resolve_internal_edges(&graph, &repos);
+        assert!(resolved.edges[&RepoId::new("lib")].is_empty());
+        assert!(resolved.missing.is_empty());
+    }
+
+    #[test]
+    fn declared_external_packages_are_not_missing() {
+        let repos = make_repos();
+        let mut graph = DependencyGraph {
+            edges: HashMap::from([(
+                RepoId::new("app"),
+                vec![make_dependency("lib"), make_dependency("acme-auth")],
+            )]),
+            ..DependencyGraph::default()
+        };
+        let resolved = resolve_internal_edges(&graph, &repos);
+        assert_eq!(resolved.missing.len(), 1);
+
+        graph.external.insert(
+            "acme-auth".to_string(),
+            ExternalPackage {
+                name: "acme-auth".to_string(),
+                version_url: Some("https://pypi.org/pypi/acme-auth/json".to_string()),
+            },
+        );
+        let resolved = resolve_internal_edges(&graph, &repos);
+        assert!(resolved.missing.is_empty());
+        assert_eq!(resolved.external.len(), 1);
+        assert_eq!(resolved.external[0].dependency.name, "acme-auth");
+        assert_eq!(
+            resolved.edges[&RepoId::new("app")],
+            vec![RepoId::new("lib")]
+        );
+    }
+
     #[test]
     fn topological_order_errors_on_cycle() {
         let repos = make_repos();
@@ -379,9 +687,45 @@ mod tests {
                 (RepoId::new("lib"), vec![make_dependency("app")]),
                 (RepoId::new("core"), Vec::new()),
             ]),
+            ..DependencyGraph::default()
         };
 
         let result = topological_order(&graph, &repos);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn impact_scores_and_critical_path_follow_the_longest_chain() {
+        let mut repos = make_repos();
+        let tools = make_repo("tools");
+        repos.insert(tools.id.clone(), tools);
+        let graph = DependencyGraph {
+            edges: HashMap::from([
+                (RepoId::new("app"), vec![make_dependency("lib")]),
+                (RepoId::new("lib"), vec![make_dependency("core")]),
+                (RepoId::new("tools"), vec![make_dependency("core")]),
+                (RepoId::new("core"), Vec::new()),
+            ]),
+            ..DependencyGraph::default()
+        };
+
+        let scores = impact_scores(&graph, &repos);
+        let summary: Vec<(&str, usize, usize)> = scores
+            .iter()
+            .map(|score| (score.repo.as_str(), score.dependents, score.depth))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("core", 3, 2),
+                ("lib", 1, 1),
+                ("app", 0, 0),
+                ("tools", 0, 0)
+            ]
+        );
+
+        let path = critical_path(&graph, &repos).expect("critical path should succeed");
+        let names: Vec<&str> = path.iter().map(|id| id.as_str()).collect();
+        assert_eq!(names, vec!["core", "lib", "app"]);
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::core::repo::RepoId;
 
@@ -48,32 +48,286 @@ pub fn render_flat(
     out
 }
 
+/// Per-node details rendered as attributes by the dot, mermaid, and graphml formats.
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetadata {
+    pub ecosystem: Option<String>,
+    pub version: Option<String>,
+    pub changed: bool,
+    pub external: bool,
+    /// MR state for the changeset view (`open`, `draft`, `merged`, `closed`,
+    /// `tracked`, or `none`); drawn as the node fill color.
+    pub mr_status: Option<String>,
+}
+
+/// Fill colors for `NodeMetadata::mr_status`, shared by dot and mermaid.
+fn mr_status_color(status: &str) -> &'static str {
+    match status {
+        "merged" => "#b7e4c7",
+        "open" => "#bde0fe",
+        "draft" => "#e9ecef",
+        "closed" => "#ffc9c9",
+        "tracked" => "#e5dbff",
+        "none" => "#ffe8a3",
+        _ => "#ffffff",
+    }
+}
+
+/// Renders Graphviz dot. Nodes with an entry in `clusters` are drawn inside a
+/// `cluster_<name>` subgraph for that name.
 pub fn render_dot(
     edges: &HashMap<RepoId, Vec<RepoId>>,
     labels: &HashMap<RepoId, String>,
+    metadata: &HashMap<RepoId, NodeMetadata>,
+    clusters: &HashMap<RepoId, String>,
 ) -> String {
+    let mut nodes: Vec<&RepoId> = labels.keys().collect();
+    nodes.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    let mut grouped: BTreeMap<&str, Vec<&RepoId>> = BTreeMap::new();
     let mut out = String::from("digraph harmonia {\n");
-    for (node, label) in labels {
-        let escaped = escape_dot_label(label);
-        out.push_str(&format!(
-            "  \"{}\" [label=\"{}\"];\n",
-            node.as_str(),
-            escaped
-        ));
+    for node in nodes {
+        match clusters.get(node) {
+            Some(cluster) => grouped.entry(cluster.as_str()).or_default().push(node),
+            None => out.push_str(&format!("  {}\n", dot_node_line(node, labels, metadata))),
+        }
+    }
+    for (index, (cluster, members)) in grouped.into_iter().enumerate() {
+        out.push_str(&format!("  subgraph \"cluster_{index}\" {{\n"));
+        out.push_str(&format!("    label=\"{}\";\n", escape_dot_label(cluster)));
+        for node in members {
+            out.push_str(&format!("    {}\n", dot_node_line(node, labels, metadata)));
+        }
+        out.push_str("  }\n");
     }
+
+    let mut edge_lines = Vec::new();
     for (from, deps) in edges {
         for dep in deps {
-            out.push_str(&format!(
+            edge_lines.push(format!(
                 "  \"{}\" -> \"{}\";\n",
                 from.as_str(),
                 dep.as_str()
             ));
         }
     }
+    edge_lines.sort();
+    out.extend(edge_lines);
     out.push_str("}\n");
     out
 }
 
+fn dot_node_line(
+    node: &RepoId,
+    labels: &HashMap<RepoId, String>,
+    metadata: &HashMap<RepoId, NodeMetadata>,
+) -> String {
+    let label = labels
+        .get(node)
+        .map(String::as_str)
+        .unwrap_or_else(|| node.as_str());
+    let mut attrs = format!("label=\"{}\"", escape_dot_label(label));
+    if let Some(meta) = metadata.get(node) {
+        if let Some(ecosystem) = meta.ecosystem.as_deref() {
+            attrs.push_str(&format!(", ecosystem=\"{}\"", escape_dot_label(ecosystem)));
+        }
+        if let Some(version) = meta.version.as_deref() {
+            attrs.push_str(&format!(", version=\"{}\"", escape_dot_label(version)));
+        }
+        attrs.push_str(&format!(", changed=\"{}\"", meta.changed));
+        if let Some(status) = meta.mr_status.as_deref() {
+            attrs.push_str(&format!(", mr_status=\"{}\"", escape_dot_label(status)));
+        }
+        let mut styles = Vec::new();
+        if meta.changed {
+            styles.push("bold");
+        }
+        if meta.external {
+            styles.push("dashed");
+        }
+        if let Some(status) = meta.mr_status.as_deref() {
+            styles.push("filled");
+            attrs.push_str(&format!(", fillcolor=\"{}\"", mr_status_color(status)));
+        }
+        match styles.as_slice() {
+            [] => {}
+            [style] => attrs.push_str(&format!(", style={style}")),
+            _ => attrs.push_str(&format!(", style=\"{}\"", styles.join(","))),
+        }
+    }
+    format!("\"{}\" [{}];", node.as_str(), attrs)
+}
+
+/// Renders GraphML with node attributes for label, ecosystem, version,
+/// changed, external, and the cluster from `clusters` when present.
+pub fn render_graphml(
+    edges: &HashMap<RepoId, Vec<RepoId>>,
+    labels: &HashMap<RepoId, String>,
+    metadata: &HashMap<RepoId, NodeMetadata>,
+    clusters: &HashMap<RepoId, String>,
+) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n\
+  <key id=\"ecosystem\" for=\"node\" attr.name=\"ecosystem\" attr.type=\"string\"/>\n\
+  <key id=\"version\" for=\"node\" attr.name=\"version\" attr.type=\"string\"/>\n\
+  <key id=\"changed\" for=\"node\" attr.name=\"changed\" attr.type=\"boolean\"/>\n\
+  <key id=\"external\" for=\"node\" attr.name=\"external\" attr.type=\"boolean\"/>\n\
+  <key id=\"cluster\" for=\"node\" attr.name=\"cluster\" attr.type=\"string\"/>\n\
+  <graph id=\"harmonia\" edgedefault=\"directed\">\n",
+    );
+
+    let mut nodes: Vec<&RepoId> = labels.keys().collect();
+    nodes.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    for node in nodes {
+        let meta = metadata.get(node).cloned().unwrap_or_default();
+        out.push_str(&format!(
+            "    <node id=\"{}\">\n",
+            escape_xml(node.as_str())
+        ));
+        let mut data = vec![("label", labels[node].clone())];
+        if let Some(ecosystem) = meta.ecosystem {
+            data.push(("ecosystem", ecosystem));
+        }
+        if let Some(version) = meta.version {
+            data.push(("version", version));
+        }
+        data.push(("changed", meta.changed.to_string()));
+        data.push(("external", meta.external.to_string()));
+        if let Some(cluster) = clusters.get(node) {
+            data.push(("cluster", cluster.clone()));
+        }
+        for (key, value) in data {
+            out.push_str(&format!(
+                "      <data key=\"{}\">{}</data>\n",
+                key,
+                escape_xml(&value)
+            ));
+        }
+        out.push_str("    </node>\n");
+    }
+
+    let mut edge_list: Vec<(&str, &str)> = edges
+        .iter()
+        .flat_map(|(from, deps)| deps.iter().map(move |dep| (from.as_str(), dep.as_str())))
+        .collect();
+    edge_list.sort();
+    for (from, to) in edge_list {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\"/>\n",
+            escape_xml(from),
+            escape_xml(to)
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a Mermaid flowchart. `orientation` is the flowchart direction
+/// (`TD`, `BT`, `LR`, or `RL`). Changed and external repos get the `changed`
+/// and `external` classes, and repos with an MR status an `mr_<status>` class.
+pub fn render_mermaid(
+    edges: &HashMap<RepoId, Vec<RepoId>>,
+    labels: &HashMap<RepoId, String>,
+    metadata: &HashMap<RepoId, NodeMetadata>,
+    orientation: &str,
+) -> String {
+    let mut nodes: Vec<&RepoId> = labels.keys().collect();
+    nodes.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    let mut ids: HashMap<&RepoId, String> = HashMap::new();
+    let mut used = HashSet::new();
+    for node in &nodes {
+        let base = mermaid_node_id(node.as_str());
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while !used.insert(id.clone()) {
+            id = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        ids.insert(node, id);
+    }
+
+    let mut out = format!("flowchart {orientation}\n");
+    for node in &nodes {
+        let label = labels[*node].as_str();
+        out.push_str(&format!(
+            "  {}[\"{}\"]\n",
+            ids[node],
+            escape_mermaid_label(label)
+        ));
+    }
+
+    let mut edge_lines = Vec::new();
+    for (from, deps) in edges {
+        for dep in deps {
+            if let (Some(from_id), Some(dep_id)) = (ids.get(from), ids.get(dep)) {
+                edge_lines.push(format!("  {from_id} --> {dep_id}\n"));
+            }
+        }
+    }
+    edge_lines.sort();
+    out.extend(edge_lines);
+
+    let class_members = |select: fn(&NodeMetadata) -> bool| {
+        nodes
+            .iter()
+            .filter(|node| metadata.get(**node).is_some_and(select))
+            .map(|node| ids[node].clone())
+            .collect::<Vec<_>>()
+    };
+    let changed = class_members(|meta| meta.changed);
+    let external = class_members(|meta| meta.external);
+    if !changed.is_empty() {
+        out.push_str("  classDef changed stroke-width:3px\n");
+        out.push_str(&format!("  class {} changed\n", changed.join(",")));
+    }
+    if !external.is_empty() {
+        out.push_str("  classDef external stroke-dasharray:5 5\n");
+        out.push_str(&format!("  class {} external\n", external.join(",")));
+    }
+
+    let mut by_status: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for node in &nodes {
+        if let Some(status) = metadata
+            .get(*node)
+            .and_then(|meta| meta.mr_status.as_deref())
+        {
+            by_status.entry(status).or_default().push(ids[node].clone());
+        }
+    }
+    for (status, members) in by_status {
+        let class = format!("mr_{}", mermaid_node_id(status).trim_start_matches("n_"));
+        out.push_str(&format!(
+            "  classDef {class} fill:{}\n",
+            mr_status_color(status)
+        ));
+        out.push_str(&format!("  class {} {class}\n", members.join(",")));
+    }
+    out
+}
+
+fn mermaid_node_id(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect();
+    format!("n_{id}")
+}
+
+fn escape_mermaid_label(label: &str) -> String {
+    label.replace('"', "#quot;")
+}
+
 fn render_tree_children(
     node: &RepoId,
     edges: &HashMap<RepoId, Vec<RepoId>>,
@@ -145,3 +399,110 @@ fn render_flat_children(
 fn escape_dot_label(label: &str) -> String {
     label.replace('"', "\\\"")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> RepoId {
+        RepoId::new(name.to_string())
+    }
+
+    #[test]
+    fn mermaid_output_has_safe_ids_labels_and_classes() {
+        let mut edges = HashMap::new();
+        edges.insert(id("web-app"), vec![id("core")]);
+        edges.insert(id("core"), Vec::new());
+        edges.insert(id("web.app"), Vec::new());
+        let mut labels = HashMap::new();
+        labels.insert(id("web-app"), "web-app (1.0.0)".to_string());
+        labels.insert(id("core"), "core \"lib\"".to_string());
+        labels.insert(id("web.app"), "web.app".to_string());
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            id("core"),
+            NodeMetadata {
+                changed: true,
+                ..NodeMetadata::default()
+            },
+        );
+
+        let rendered = render_mermaid(&edges, &labels, &metadata, "TD");
+        assert_eq!(
+            rendered,
+            "flowchart TD\n  n_core[\"core #quot;lib#quot;\"]\n  n_web_app[\"web-app (1.0.0)\"]\n  n_web_app_2[\"web.app\"]\n  n_web_app --> n_core\n  classDef changed stroke-width:3px\n  class n_core changed\n"
+        );
+    }
+
+    #[test]
+    fn dot_clusters_group_nodes_into_subgraphs() {
+        let mut edges = HashMap::new();
+        edges.insert(id("app"), vec![id("core")]);
+        let mut labels = HashMap::new();
+        for name in ["app", "core", "tools"] {
+            labels.insert(id(name), name.to_string());
+        }
+        let mut clusters = HashMap::new();
+        clusters.insert(id("app"), "frontend".to_string());
+        clusters.insert(id("core"), "backend".to_string());
+
+        let rendered = render_dot(&edges, &labels, &HashMap::new(), &clusters);
+        assert_eq!(
+            rendered,
+            "digraph harmonia {\n  \"tools\" [label=\"tools\"];\n  subgraph \"cluster_0\" {\n    label=\"backend\";\n    \"core\" [label=\"core\"];\n  }\n  subgraph \"cluster_1\" {\n    label=\"frontend\";\n    \"app\" [label=\"app\"];\n  }\n  \"app\" -> \"core\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn graphml_escapes_values_and_includes_cluster() {
+        let mut edges = HashMap::new();
+        edges.insert(id("a&b"), vec![id("core")]);
+        let mut labels = HashMap::new();
+        labels.insert(id("a&b"), "a&b <1.0>".to_string());
+        labels.insert(id("core"), "core".to_string());
+        let mut clusters = HashMap::new();
+        clusters.insert(id("core"), "rust".to_string());
+
+        let rendered = render_graphml(&edges, &labels, &HashMap::new(), &clusters);
+        assert!(rendered.contains("<node id=\"a&amp;b\">"));
+        assert!(rendered.contains("<data key=\"label\">a&amp;b &lt;1.0&gt;</data>"));
+        assert!(rendered.contains("<data key=\"cluster\">rust</data>"));
+        assert!(rendered.contains("<edge source=\"a&amp;b\" target=\"core\"/>"));
+        assert!(rendered.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn mr_status_fills_dot_and_mermaid_nodes() {
+        let mut edges = HashMap::new();
+        edges.insert(id("app"), vec![id("core")]);
+        let mut labels = HashMap::new();
+        labels.insert(id("app"), "2. app".to_string());
+        labels.insert(id("core"), "1. core !4".to_string());
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            id("core"),
+            NodeMetadata {
+                changed: true,
+                mr_status: Some("merged".to_string()),
+                ..NodeMetadata::default()
+            },
+        );
+        metadata.insert(
+            id("app"),
+            NodeMetadata {
+                mr_status: Some("none".to_string()),
+                ..NodeMetadata::default()
+            },
+        );
+
+        let dot = render_dot(&edges, &labels, &metadata, &HashMap::new());
+        assert!(dot.contains(
+            "\"core\" [label=\"1. core !4\", changed=\"true\", mr_status=\"merged\", fillcolor=\"#b7e4c7\", style=\"bold,filled\"];"
+        ));
+        assert!(dot.contains("\"app\" [label=\"2. app\", changed=\"false\", mr_status=\"none\", fillcolor=\"#ffe8a3\", style=filled];"));
+
+        let mermaid = render_mermaid(&edges, &labels, &metadata, "TD");
+        assert!(mermaid.contains("  classDef mr_merged fill:#b7e4c7\n  class n_core mr_merged\n"));
+        assert!(mermaid.contains("  classDef mr_none fill:#ffe8a3\n  class n_app mr_none\n"));
+    }
+}
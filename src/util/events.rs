@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{HarmoniaError, Result};
+
+/// Where `--events` lines go, and the command they are tagged with.
+struct EventSink {
+    command: String,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+static SINK: OnceLock<EventSink> = OnceLock::new();
+
+/// Starts streaming events for `command` in `format` (only `ndjson` is
+/// supported) to `file`, or stdout when no file is given. Later calls keep the
+/// first sink, so nested commands (such as `submit` running `push`) report as
+/// the outer command.
+pub fn init(command: &str, format: &str, file: Option<&Path>) -> Result<()> {
+    if !format.trim().eq_ignore_ascii_case("ndjson") {
+        return Err(HarmoniaError::Other(anyhow::anyhow!(format!(
+            "unknown events format '{}' (expected ndjson)",
+            format
+        ))));
+    }
+    let output: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let _ = SINK.set(EventSink {
+        command: command.to_string(),
+        output: Mutex::new(output),
+    });
+    Ok(())
+}
+
+/// Writes one event line: `ts` (milliseconds since the epoch), `command`,
+/// `event`, then the keys of `fields`. Does nothing unless [`init`] ran;
+/// write errors are ignored so a closed pipe never fails the command.
+pub fn emit(event: &str, fields: serde_json::Value) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let mut line = event_line(&sink.command, event, fields, now_millis());
+    line.push('\n');
+    let mut output = sink
+        .output
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _ = output.write_all(line.as_bytes());
+    let _ = output.flush();
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Builds the line by hand so `ts`, `command`, and `event` always come first;
+/// the remaining keys follow in `serde_json`'s order.
+fn event_line(command: &str, event: &str, fields: serde_json::Value, ts: u64) -> String {
+    let mut line = format!(
+        "{{\"ts\":{},\"command\":{},\"event\":{}",
+        ts,
+        serde_json::Value::from(command),
+        serde_json::Value::from(event)
+    );
+    if let serde_json::Value::Object(fields) = fields {
+        for (key, value) in fields {
+            line.push_str(&format!(",{}:{}", serde_json::Value::from(key), value));
+        }
+    }
+    line.push('}');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::event_line;
+
+    #[test]
+    fn event_lines_lead_with_timestamp_command_and_event() {
+        assert_eq!(
+            event_line(
+                "sync",
+                "repo_finished",
+                json!({ "ok": true, "repo": "api" }),
+                42
+            ),
+            r#"{"ts":42,"command":"sync","event":"repo_finished","ok":true,"repo":"api"}"#
+        );
+        assert_eq!(
+            event_line("submit", "started", json!(null), 7),
+            r#"{"ts":7,"command":"submit","event":"started"}"#
+        );
+    }
+}
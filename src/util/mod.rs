@@ -1,3 +1,8 @@
+pub mod events;
+pub mod hmac;
 pub mod output;
 pub mod parallel;
+pub mod remote_url;
+pub mod report;
 pub mod template;
+pub mod watch;
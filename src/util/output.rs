@@ -1,7 +1,7 @@
 use std::io::{self, Write};
 
 use console::style;
-use dialoguer::Confirm;
+use dialoguer::{Confirm, Input};
 
 pub fn info(message: &str) {
     let _ = writeln!(io::stderr(), "{}", message);
@@ -26,3 +26,12 @@ pub fn confirm(prompt: &str, assume_yes: bool) -> Result<bool, dialoguer::Error>
 
     Confirm::new().with_prompt(prompt).default(false).interact()
 }
+
+/// Asks the user to type `expected` back; anything else declines.
+pub fn confirm_typed(prompt: &str, expected: &str) -> Result<bool, dialoguer::Error> {
+    let typed: String = Input::new()
+        .with_prompt(format!("{prompt} Type '{expected}' to continue"))
+        .allow_empty(true)
+        .interact_text()?;
+    Ok(typed.trim() == expected)
+}
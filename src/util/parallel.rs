@@ -1,5 +1,11 @@
+use std::sync::{Condvar, Mutex};
+
 use rayon::prelude::*;
 
+/// Concurrent network operations (fetch, clone) when neither
+/// `HARMONIA_NETWORK_PARALLEL` nor `[defaults].network_parallel` is set.
+pub const DEFAULT_NETWORK_PARALLEL: usize = 8;
+
 pub fn run_in_parallel<T, R, F>(items: Vec<T>, jobs: Option<usize>, func: F) -> Vec<R>
 where
     T: Send,
@@ -17,3 +23,77 @@ where
         _ => items.into_iter().map(func).collect(),
     }
 }
+
+/// Caps how many workers of [`run_in_parallel`] talk to remotes at once.
+/// Forges throttle clients that open many connections, so network work waits
+/// for a permit while CPU-bound work keeps using every worker.
+pub struct NetworkQueue {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Held while a network operation runs; dropping it lets the next one start.
+pub struct NetworkPermit<'a> {
+    queue: &'a NetworkQueue,
+}
+
+impl NetworkQueue {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            available: Mutex::new(limit.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until fewer than the limit of network operations are running.
+    pub fn acquire(&self) -> NetworkPermit<'_> {
+        let mut available = self
+            .available
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while *available == 0 {
+            available = self
+                .released
+                .wait(available)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *available -= 1;
+        NetworkPermit { queue: self }
+    }
+}
+
+impl Drop for NetworkPermit<'_> {
+    fn drop(&mut self) {
+        let mut available = self
+            .queue
+            .available
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *available += 1;
+        self.queue.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn network_queue_caps_concurrent_operations() {
+        let queue = NetworkQueue::new(2);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results = run_in_parallel((0..12).collect(), Some(6), |item: usize| {
+            let _permit = queue.acquire();
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(5));
+            running.fetch_sub(1, Ordering::SeqCst);
+            item * 2
+        });
+        assert_eq!(results, (0..12).map(|item| item * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+}
@@ -0,0 +1,274 @@
+/// How a git remote is addressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteScheme {
+    Http,
+    Https,
+    /// `ssh://[user@]host[:port]/path`
+    Ssh,
+    /// scp-like `[user@]host:path`
+    Scp,
+}
+
+/// A network git remote split into its components. Local paths and `file://`
+/// remotes are not represented.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUrl {
+    pub scheme: RemoteScheme,
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    /// Path on the host without leading or trailing slashes, `.git` kept.
+    pub path: String,
+}
+
+impl RemoteUrl {
+    pub fn parse(remote_url: &str) -> Option<Self> {
+        let trimmed = remote_url.trim();
+        if let Some((scheme, rest)) = trimmed.split_once("://") {
+            let scheme = match scheme.to_ascii_lowercase().as_str() {
+                "http" => RemoteScheme::Http,
+                "https" => RemoteScheme::Https,
+                "ssh" | "git+ssh" | "ssh+git" => RemoteScheme::Ssh,
+                _ => return None,
+            };
+            let (authority, path) = rest.split_once('/')?;
+            let (user, host_port) = split_user(authority);
+            let (host, port) = match host_port.rsplit_once(':') {
+                Some((host, port)) if !port.is_empty() => (host, Some(port.parse().ok()?)),
+                Some((host, _)) => (host, None),
+                None => (host_port, None),
+            };
+            return Self::build(scheme, user, host, port, path);
+        }
+
+        // scp-like syntax: a ':' before any '/' separates host and path.
+        let (authority, path) = trimmed.split_once(':')?;
+        if authority.contains('/') {
+            return None;
+        }
+        let (user, host) = split_user(authority);
+        Self::build(RemoteScheme::Scp, user, host, None, path)
+    }
+
+    fn build(
+        scheme: RemoteScheme,
+        user: Option<&str>,
+        host: &str,
+        port: Option<u16>,
+        path: &str,
+    ) -> Option<Self> {
+        let path = path.trim().trim_matches('/');
+        if host.is_empty() || path.is_empty() {
+            return None;
+        }
+        Some(Self {
+            scheme,
+            user: user.map(str::to_string),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn is_http(&self) -> bool {
+        matches!(self.scheme, RemoteScheme::Http | RemoteScheme::Https)
+    }
+
+    /// Forge project path (`group/sub/repo`). `web_root` is the path a
+    /// self-hosted forge is served under (see [`forge_web_root`]); it is only
+    /// part of http(s) remotes, so it is stripped from those alone.
+    pub fn project_path(&self, web_root: &str) -> Option<String> {
+        let path = self.path.strip_suffix(".git").unwrap_or(&self.path);
+        let path = if self.is_http() {
+            strip_root(path, web_root)
+        } else {
+            path
+        };
+        let path = path.trim_matches('/');
+        (!path.is_empty()).then(|| path.to_string())
+    }
+
+    /// `https://` clone URL. ssh remotes gain `web_root` and lose their port,
+    /// which belongs to the ssh daemon rather than the web server.
+    pub fn to_https(&self, web_root: &str) -> String {
+        if self.is_http() {
+            return self.to_string();
+        }
+        format!("https://{}/{}", self.host, join_root(web_root, &self.path))
+    }
+
+    /// scp-like ssh clone URL. http(s) remotes lose `web_root` and their port.
+    pub fn to_ssh(&self, web_root: &str) -> String {
+        if !self.is_http() {
+            return self.to_string();
+        }
+        let path = strip_root(&self.path, web_root);
+        format!("git@{}:{}", self.host, path)
+    }
+
+    /// Browser URL of the project, without `.git`. http(s) remotes keep their
+    /// port; ssh ports are not web ports.
+    pub fn web_url(&self, web_root: &str) -> String {
+        let path = self.path.strip_suffix(".git").unwrap_or(&self.path);
+        match self.scheme {
+            RemoteScheme::Http | RemoteScheme::Https => {
+                format!("{}://{}/{}", self.scheme_name(), self.authority(), path)
+            }
+            RemoteScheme::Ssh | RemoteScheme::Scp => {
+                format!("https://{}/{}", self.host, join_root(web_root, path))
+            }
+        }
+    }
+
+    fn scheme_name(&self) -> &'static str {
+        match self.scheme {
+            RemoteScheme::Http => "http",
+            RemoteScheme::Https => "https",
+            RemoteScheme::Ssh | RemoteScheme::Scp => "ssh",
+        }
+    }
+
+    fn authority(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}:{}", self.host, port),
+            None => self.host.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for RemoteUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let user = self
+            .user
+            .as_deref()
+            .map(|user| format!("{user}@"))
+            .unwrap_or_default();
+        match self.scheme {
+            RemoteScheme::Scp => write!(f, "{}{}:{}", user, self.host, self.path),
+            _ => write!(
+                f,
+                "{}://{}{}/{}",
+                self.scheme_name(),
+                user,
+                self.authority(),
+                self.path
+            ),
+        }
+    }
+}
+
+/// Path prefix of a `[forge].host` value such as `https://git.corp/gitlab`,
+/// without the API suffix GitHub Enterprise hosts are often configured with.
+pub fn forge_web_root(forge_type: &str, host: &str) -> String {
+    let trimmed = host.trim().trim_end_matches('/');
+    let rest = trimmed.split_once("://").map_or(trimmed, |(_, rest)| rest);
+    let root = rest.split_once('/').map_or("", |(_, root)| root);
+    let root = if forge_type == "github" {
+        root.strip_suffix("api/v3")
+            .or_else(|| root.strip_suffix("api"))
+            .unwrap_or(root)
+    } else {
+        root
+    };
+    root.trim_matches('/').to_string()
+}
+
+/// Clone URL for `path` on the forge at `host` (a bare host name or a URL
+/// with scheme, port and web root).
+pub fn forge_clone_url(host: &str, path: &str, protocol: &str) -> String {
+    let trimmed = host.trim().trim_end_matches('/');
+    let (scheme, rest) = trimmed.split_once("://").unwrap_or(("https", trimmed));
+    let (authority, root) = rest.split_once('/').unwrap_or((rest, ""));
+    if protocol == "https" {
+        format!("{scheme}://{authority}/{}", join_root(root, path))
+    } else {
+        let (_, host) = split_user(authority);
+        let host = host.split(':').next().unwrap_or(host);
+        format!("git@{host}:{path}")
+    }
+}
+
+fn split_user(authority: &str) -> (Option<&str>, &str) {
+    match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    }
+}
+
+fn strip_root<'a>(path: &'a str, web_root: &str) -> &'a str {
+    let root = web_root.trim_matches('/');
+    if root.is_empty() {
+        return path;
+    }
+    path.strip_prefix(root)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(path)
+}
+
+fn join_root(web_root: &str, path: &str) -> String {
+    let root = web_root.trim_matches('/');
+    if root.is_empty() {
+        path.to_string()
+    } else {
+        format!("{root}/{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hosts_ports_and_paths() {
+        let url = RemoteUrl::parse("https://git.corp:8443/scm/group/repo.git").expect("parse");
+        assert_eq!(url.scheme, RemoteScheme::Https);
+        assert_eq!((url.host.as_str(), url.port), ("git.corp", Some(8443)));
+        assert_eq!(url.path, "scm/group/repo.git");
+        assert_eq!(url.project_path("scm").as_deref(), Some("group/repo"));
+        assert_eq!(url.to_ssh("scm"), "git@git.corp:group/repo.git");
+        assert_eq!(url.web_url("scm"), "https://git.corp:8443/scm/group/repo");
+        assert_eq!(
+            url.to_https("scm"),
+            "https://git.corp:8443/scm/group/repo.git"
+        );
+
+        let url = RemoteUrl::parse("ssh://git@git.corp:7999/group/repo.git").expect("parse");
+        assert_eq!(url.user.as_deref(), Some("git"));
+        assert_eq!(url.port, Some(7999));
+        assert_eq!(url.project_path("scm").as_deref(), Some("group/repo"));
+        assert_eq!(url.to_https("scm"), "https://git.corp/scm/group/repo.git");
+        assert_eq!(url.to_string(), "ssh://git@git.corp:7999/group/repo.git");
+
+        let url = RemoteUrl::parse("git@gitlab.example.com:team/sub/repo.git").expect("parse");
+        assert_eq!(url.scheme, RemoteScheme::Scp);
+        assert_eq!(url.project_path("").as_deref(), Some("team/sub/repo"));
+        assert_eq!(url.web_url(""), "https://gitlab.example.com/team/sub/repo");
+
+        assert_eq!(RemoteUrl::parse("file:///tmp/repo.git"), None);
+        assert_eq!(RemoteUrl::parse("/tmp/repo.git"), None);
+        assert_eq!(RemoteUrl::parse("https://git.corp/"), None);
+        assert_eq!(RemoteUrl::parse("https://git.corp:notaport/x.git"), None);
+    }
+
+    #[test]
+    fn derives_web_roots_and_clone_urls_from_forge_hosts() {
+        assert_eq!(forge_web_root("gitlab", "gitlab.com"), "");
+        assert_eq!(
+            forge_web_root("gitlab", "https://git.corp:8443/gitlab/"),
+            "gitlab"
+        );
+        assert_eq!(forge_web_root("github", "https://ghe.corp/api/v3"), "");
+        assert_eq!(
+            forge_clone_url("https://git.corp:8443/gitlab", "group/api.git", "https"),
+            "https://git.corp:8443/gitlab/group/api.git"
+        );
+        assert_eq!(
+            forge_clone_url("https://git.corp:8443/gitlab", "group/api.git", "ssh"),
+            "git@git.corp:group/api.git"
+        );
+        assert_eq!(
+            forge_clone_url("gitlab.com", "group/api.git", "https"),
+            "https://gitlab.com/group/api.git"
+        );
+    }
+}
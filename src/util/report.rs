@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Set by the global `--json` flag.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set when the running command prints its own JSON document.
+static NATIVE: AtomicBool = AtomicBool::new(false);
+/// Per-repo results recorded for the summary.
+static REPOS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether stdout is reserved for one JSON document.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Marks the running command as printing its own JSON, so [`finish`] stays
+/// quiet.
+pub fn native() {
+    NATIVE.store(true, Ordering::Relaxed);
+}
+
+/// Records one repo's result (an object with at least `repo` and `ok`) for
+/// the summary. Does nothing unless [`enable`] ran.
+pub fn repo(fields: serde_json::Value) {
+    if !enabled() {
+        return;
+    }
+    REPOS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(fields);
+}
+
+/// Prints the summary of a command without JSON output of its own:
+/// `command`, `ok`, `repos`, and `error` when it failed.
+pub fn finish(command: &str, error: Option<String>) {
+    if !enabled() || NATIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    let repos = std::mem::take(
+        &mut *REPOS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    println!("{}", summary(command, repos, error));
+}
+
+fn summary(command: &str, repos: Vec<serde_json::Value>, error: Option<String>) -> String {
+    let mut value = serde_json::json!({
+        "command": command,
+        "ok": error.is_none(),
+        "repos": repos,
+    });
+    if let Some(error) = error {
+        value["error"] = serde_json::Value::from(error);
+    }
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::summary;
+
+    #[test]
+    fn summary_reports_failure_with_error() {
+        let text = summary(
+            "sync",
+            vec![json!({ "repo": "api", "ok": false })],
+            Some("sync failed in 1 repositories".to_string()),
+        );
+        let value: serde_json::Value = serde_json::from_str(&text).expect("json");
+        assert_eq!(value["command"], "sync");
+        assert_eq!(value["ok"], false);
+        assert_eq!(value["repos"][0]["repo"], "api");
+        assert_eq!(value["error"], "sync failed in 1 repositories");
+    }
+}
@@ -1,13 +1,27 @@
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use tera::ast::{Expr, ExprVal, FunctionCall, Node};
 
 use crate::error::{HarmoniaError, Result};
 
 pub fn render_template(template: &str, context: &serde_json::Value) -> Result<String> {
     let context = tera::Context::from_serialize(context)
         .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))?;
-    tera::Tera::one_off(template, &context, true)
-        .map_err(|err| HarmoniaError::Other(anyhow::Error::new(err)))
+    tera::Tera::one_off(template, &context, true).map_err(tera_error)
+}
+
+/// Tera keeps the useful part of an error (the unknown variable, the parse
+/// position) in its sources, so flatten them into one message.
+fn tera_error(err: tera::Error) -> HarmoniaError {
+    let mut message = err.to_string();
+    let mut source = std::error::Error::source(&err);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    HarmoniaError::Other(anyhow::anyhow!(message))
 }
 
 pub fn render_template_file(path: &Path, context: &serde_json::Value) -> Result<String> {
@@ -15,6 +29,391 @@ pub fn render_template_file(path: &Path, context: &serde_json::Value) -> Result<
     render_template(&template, context)
 }
 
+/// Parses `template` and lists the context variables it reads, as dotted paths
+/// where `[]` stands for any element of a list (`changeset.mrs[].repo`).
+/// Variables guarded by `default(...)` or `is defined` are optional and left
+/// out. Syntax errors are reported with Tera's full explanation.
+pub fn template_variables(template: &str) -> Result<BTreeSet<String>> {
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template("template", template)
+        .map_err(tera_error)?;
+    let parsed = tera.get_template("template").map_err(tera_error)?;
+    let mut collector = VariableCollector::default();
+    collector.nodes(&parsed.ast);
+    Ok(collector.found)
+}
+
+/// Variables of `template` that `schema`, a sample context, does not provide.
+/// Lists in the schema are checked against their first element; empty objects
+/// accept any key.
+pub fn unknown_template_variables(
+    template: &str,
+    schema: &serde_json::Value,
+) -> Result<Vec<String>> {
+    Ok(template_variables(template)?
+        .into_iter()
+        .filter(|path| !schema_has_path(schema, path))
+        .collect())
+}
+
+fn schema_has_path(schema: &serde_json::Value, path: &str) -> bool {
+    let mut current = schema;
+    for segment in path.split('.') {
+        let (key, elements) = match segment.find("[]") {
+            Some(index) => (&segment[..index], segment[index..].matches("[]").count()),
+            None => (segment, 0),
+        };
+        current = match current {
+            serde_json::Value::Object(map) if map.is_empty() => return true,
+            serde_json::Value::Object(map) => match map.get(key) {
+                Some(value) => value,
+                None => return false,
+            },
+            _ => return false,
+        };
+        for _ in 0..elements {
+            current = match current {
+                serde_json::Value::Array(items) => match items.first() {
+                    Some(item) => item,
+                    None => return true,
+                },
+                // Strings can be indexed too.
+                serde_json::Value::String(_) => return true,
+                _ => return false,
+            };
+        }
+    }
+    true
+}
+
+/// Walks a template AST collecting context paths. Each scope maps local names
+/// (loop and `set` variables) to the context path they alias, or `None` when
+/// they hold a computed value that cannot be checked.
+#[derive(Default)]
+struct VariableCollector {
+    scopes: Vec<HashMap<String, Option<String>>>,
+    found: BTreeSet<String>,
+}
+
+impl VariableCollector {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.expr(expr),
+            Node::Set(_, set) => {
+                self.expr(&set.value);
+                let alias = self.alias(&set.value, "");
+                let scope = if set.global {
+                    self.scopes.first_mut()
+                } else {
+                    self.scopes.last_mut()
+                };
+                match scope {
+                    Some(scope) => {
+                        scope.insert(set.key.clone(), alias);
+                    }
+                    None => self.scopes.push(HashMap::from([(set.key.clone(), alias)])),
+                }
+            }
+            Node::FilterSection(_, section, _) => {
+                self.function_call(&section.filter);
+                self.nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.nodes(&block.body),
+            Node::MacroDefinition(_, definition, _) => {
+                let locals = definition
+                    .args
+                    .keys()
+                    .map(|name| (name.clone(), None))
+                    .collect();
+                self.scopes.push(locals);
+                self.nodes(&definition.body);
+                self.scopes.pop();
+            }
+            Node::Forloop(_, forloop, _) => {
+                self.expr(&forloop.container);
+                let mut locals = HashMap::new();
+                match forloop.key.as_ref() {
+                    Some(key) => {
+                        locals.insert(key.clone(), None);
+                        locals.insert(forloop.value.clone(), None);
+                    }
+                    None => {
+                        locals.insert(forloop.value.clone(), self.alias(&forloop.container, "[]"));
+                    }
+                }
+                self.scopes.push(locals);
+                self.nodes(&forloop.body);
+                self.scopes.pop();
+                if let Some(empty) = forloop.empty_body.as_ref() {
+                    self.nodes(empty);
+                }
+            }
+            Node::If(condition, _) => {
+                for (_, expr, body) in &condition.conditions {
+                    self.expr(expr);
+                    self.nodes(body);
+                }
+                if let Some((_, body)) = condition.otherwise.as_ref() {
+                    self.nodes(body);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Context path `expr` aliases with `suffix` appended, when it is a bare
+    /// variable.
+    fn alias(&self, expr: &Expr, suffix: &str) -> Option<String> {
+        match &expr.val {
+            ExprVal::Ident(ident) if expr.filters.is_empty() => self
+                .resolve(ident)
+                .map(|path| format!("{}{}", path, suffix)),
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        for filter in &expr.filters {
+            self.function_call(filter);
+        }
+        if expr.has_default_filter() {
+            return;
+        }
+        self.expr_val(&expr.val);
+    }
+
+    fn expr_val(&mut self, val: &ExprVal) {
+        match val {
+            ExprVal::Ident(ident) => self.ident(ident),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::Test(test) => {
+                if test.name != "defined" && test.name != "undefined" {
+                    self.ident(&test.ident);
+                }
+                for arg in &test.args {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::FunctionCall(call) => self.function_call(call),
+            ExprVal::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.expr_val(value);
+                }
+            }
+            ExprVal::In(expr) => {
+                self.expr(&expr.lhs);
+                self.expr(&expr.rhs);
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    fn function_call(&mut self, call: &FunctionCall) {
+        for arg in call.args.values() {
+            self.expr(arg);
+        }
+    }
+
+    fn ident(&mut self, ident: &str) {
+        if let Some(path) = self.resolve(ident) {
+            self.found.insert(path);
+        }
+    }
+
+    /// Context path for `ident`, with index lookups normalized to `[]` or a
+    /// key. `None` for locals that do not alias the context and for Tera's
+    /// built-in `loop` and `__tera_context`.
+    fn resolve(&self, ident: &str) -> Option<String> {
+        let normalized = normalize_ident(ident);
+        let head_end = normalized.find(['.', '[']).unwrap_or(normalized.len());
+        let (head, rest) = normalized.split_at(head_end);
+        for scope in self.scopes.iter().rev() {
+            if let Some(alias) = scope.get(head) {
+                return alias.as_ref().map(|path| format!("{}{}", path, rest));
+            }
+        }
+        if head == "loop" || head == "__tera_context" {
+            return None;
+        }
+        Some(normalized)
+    }
+}
+
+/// Rewrites `a["key"]` to `a.key` and any other index to `a[]`.
+fn normalize_ident(ident: &str) -> String {
+    let mut out = String::new();
+    let mut rest = ident;
+    while let Some(start) = rest.find('[') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find(']') else {
+            break;
+        };
+        let inner = rest[start + 1..start + end].trim();
+        let quoted = inner.len() >= 2
+            && (inner.starts_with('"') || inner.starts_with('\''))
+            && inner.ends_with(inner.chars().next().unwrap_or('"'));
+        if quoted {
+            out.push('.');
+            out.push_str(&inner[1..inner.len() - 1]);
+        } else {
+            out.push_str("[]");
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Suffix marking files in a workspace template that are rendered on copy.
+pub const TEMPLATE_FILE_SUFFIX: &str = ".tera";
+
+/// A file in a template directory and where it lands in a target tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateEntry {
+    pub source: PathBuf,
+    /// Destination relative to the target, without the `.tera` suffix.
+    pub target: PathBuf,
+    pub rendered: bool,
+}
+
+/// Lists the files of a template directory, sorted by destination, with the
+/// same `.tera` rules as [`render_template_dir`].
+pub fn list_template_entries(source: &Path) -> Result<Vec<TemplateEntry>> {
+    let mut entries = Vec::new();
+    list_template_entries_into(source, Path::new(""), &mut entries)?;
+    entries.sort_by(|a, b| a.target.cmp(&b.target));
+    Ok(entries)
+}
+
+fn list_template_entries_into(
+    dir: &Path,
+    relative: &Path,
+    entries: &mut Vec<TemplateEntry>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        if path.is_dir() {
+            list_template_entries_into(&path, &relative.join(&name), entries)?;
+            continue;
+        }
+        let (target, rendered) = match name.strip_suffix(TEMPLATE_FILE_SUFFIX) {
+            Some(stripped) if !stripped.is_empty() => (relative.join(stripped), true),
+            _ => (relative.join(&name), false),
+        };
+        entries.push(TemplateEntry {
+            source: path,
+            target,
+            rendered,
+        });
+    }
+    Ok(())
+}
+
+/// Contents `entry` should have in a target: rendered with `context` for
+/// `.tera` files, the raw bytes otherwise.
+pub fn render_template_entry(
+    entry: &TemplateEntry,
+    context: &serde_json::Value,
+) -> Result<Vec<u8>> {
+    if !entry.rendered {
+        return Ok(fs::read(&entry.source)?);
+    }
+    render_template_file(&entry.source, context)
+        .map(String::into_bytes)
+        .map_err(|err| {
+            HarmoniaError::Other(anyhow::anyhow!(format!(
+                "failed to render template {}: {}",
+                entry.source.display(),
+                err
+            )))
+        })
+}
+
+/// Copies a workspace template tree into `target`. Files ending in `.tera` are
+/// rendered with `context` and written without the suffix; everything else
+/// (including MR templates that are rendered later) is copied verbatim.
+pub fn render_template_dir(
+    source: &Path,
+    target: &Path,
+    context: &serde_json::Value,
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    render_template_dir_into(source, target, context, &mut written)?;
+    written.sort();
+    Ok(written)
+}
+
+fn render_template_dir_into(
+    source: &Path,
+    target: &Path,
+    context: &serde_json::Value,
+    written: &mut Vec<PathBuf>,
+) -> Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" {
+            continue;
+        }
+        if path.is_dir() {
+            render_template_dir_into(&path, &target.join(&name), context, written)?;
+            continue;
+        }
+        let destination = match name.strip_suffix(TEMPLATE_FILE_SUFFIX) {
+            Some(stripped) if !stripped.is_empty() => {
+                let destination = target.join(stripped);
+                let rendered = render_template_file(&path, context).map_err(|err| {
+                    HarmoniaError::Other(anyhow::anyhow!(format!(
+                        "failed to render template {}: {}",
+                        path.display(),
+                        err
+                    )))
+                })?;
+                fs::write(&destination, rendered)?;
+                fs::set_permissions(&destination, fs::metadata(&path)?.permissions())?;
+                destination
+            }
+            _ => {
+                let destination = target.join(&name);
+                fs::copy(&path, &destination)?;
+                destination
+            }
+        };
+        written.push(destination);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -22,7 +421,10 @@ mod tests {
 
     use serde_json::json;
 
-    use crate::util::template::{render_template, render_template_file};
+    use crate::util::template::{
+        list_template_entries, render_template, render_template_dir, render_template_entry,
+        render_template_file, template_variables, unknown_template_variables,
+    };
 
     #[test]
     fn renders_inline_template() {
@@ -37,6 +439,46 @@ mod tests {
         assert_eq!(output, "Hello harmonia. Repos: core app ");
     }
 
+    #[test]
+    fn collects_variables_through_loops_and_sets() {
+        let variables = template_variables(
+            "{{ title }}{% for mr in changeset.mrs %}{{ mr.repo }}{% for c in mr.commits %}\
+             {{ c.sha }}{{ loop.index }}{% endfor %}{% endfor %}{% set first = changeset.mrs[0] %}\
+             {{ first.link }}{{ note | default(value=\"\") }}{% if owner is defined %}{% endif %}",
+        )
+        .expect("parse template");
+        assert_eq!(
+            variables.into_iter().collect::<Vec<_>>(),
+            vec![
+                "changeset.mrs",
+                "changeset.mrs[]",
+                "changeset.mrs[].commits",
+                "changeset.mrs[].commits[].sha",
+                "changeset.mrs[].link",
+                "changeset.mrs[].repo",
+                "title",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_variables_and_syntax_errors() {
+        let schema = json!({
+            "title": "t",
+            "changeset": { "mrs": [{ "repo": "api", "commits": [{ "sha": "abc" }] }] },
+        });
+        assert_eq!(
+            unknown_template_variables(
+                "{{ titel }}{% for mr in changeset.mrs %}{{ mr.repo }}{{ mr.url }}{% endfor %}",
+                &schema,
+            )
+            .expect("parse template"),
+            vec!["changeset.mrs[].url", "titel"]
+        );
+        let err = template_variables("{% for mr in changeset.mrs %}").expect_err("unclosed loop");
+        assert!(err.to_string().contains("template"));
+    }
+
     #[test]
     fn renders_template_file() {
         let path = unique_temp_path("template-render");
@@ -53,6 +495,68 @@ mod tests {
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn lists_template_entries_by_destination() {
+        let source = unique_temp_path("template-entries");
+        fs::create_dir_all(source.join(".github").join("workflows")).expect("create workflows");
+        fs::write(source.join("LICENSE"), "MIT\n").expect("write license");
+        fs::write(
+            source.join(".github").join("workflows").join("ci.yml.tera"),
+            "name: {{ repo.name }} ci\n",
+        )
+        .expect("write ci template");
+
+        let entries = list_template_entries(&source).expect("list entries");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.target.to_string_lossy().to_string(), entry.rendered))
+                .collect::<Vec<_>>(),
+            vec![
+                (".github/workflows/ci.yml".to_string(), true),
+                ("LICENSE".to_string(), false),
+            ]
+        );
+        let rendered = render_template_entry(&entries[0], &json!({ "repo": { "name": "api" } }))
+            .expect("render entry");
+        assert_eq!(rendered, b"name: api ci\n");
+        let _ = fs::remove_dir_all(&source);
+    }
+
+    #[test]
+    fn renders_only_tera_files_in_template_dir() {
+        let source = unique_temp_path("template-dir-src");
+        let target = unique_temp_path("template-dir-dst");
+        fs::create_dir_all(source.join(".harmonia").join("templates")).expect("create template");
+        fs::create_dir_all(source.join(".git")).expect("create .git");
+        fs::write(
+            source.join(".harmonia").join("config.toml.tera"),
+            "[workspace]\nname = \"{{ workspace_name }}\"\n",
+        )
+        .expect("write config template");
+        fs::write(
+            source.join(".harmonia").join("templates").join("mr.md"),
+            "{{ description }}",
+        )
+        .expect("write mr template");
+
+        let written = render_template_dir(&source, &target, &json!({ "workspace_name": "acme" }))
+            .expect("render template dir");
+        assert_eq!(written.len(), 2);
+        assert_eq!(
+            fs::read_to_string(target.join(".harmonia").join("config.toml")).expect("config"),
+            "[workspace]\nname = \"acme\"\n"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join(".harmonia").join("templates").join("mr.md"))
+                .expect("mr template"),
+            "{{ description }}"
+        );
+        assert!(!target.join(".git").exists());
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&target);
+    }
+
     fn unique_temp_path(prefix: &str) -> std::path::PathBuf {
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
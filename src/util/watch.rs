@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+/// How often `--watch` looks for changed files.
+pub const POLL_INTERVAL_MS: u64 = 700;
+
+/// Modification time and size of every file git would consider part of a
+/// repo: tracked files plus untracked files that are not ignored, so build
+/// output such as `target/` or `node_modules/` never triggers a re-run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TreeStamp {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl TreeStamp {
+    pub fn capture(repo: &Path) -> Self {
+        let output = Command::new("git")
+            .current_dir(repo)
+            .args([
+                "ls-files",
+                "-z",
+                "--cached",
+                "--others",
+                "--exclude-standard",
+            ])
+            .output();
+        let Ok(output) = output else {
+            return Self::default();
+        };
+        let mut files = BTreeMap::new();
+        for path in output.stdout.split(|byte| *byte == 0) {
+            if path.is_empty() {
+                continue;
+            }
+            let path = PathBuf::from(String::from_utf8_lossy(path).as_ref());
+            // Deleted tracked files are still listed; record them as absent.
+            let stamp = match repo.join(&path).metadata() {
+                Ok(meta) => (meta.modified().ok(), meta.len()),
+                Err(_) => (None, 0),
+            };
+            files.insert(path, stamp);
+        }
+        Self { files }
+    }
+
+    pub fn from_entries(entries: impl IntoIterator<Item = (PathBuf, SystemTime, u64)>) -> Self {
+        Self {
+            files: entries
+                .into_iter()
+                .map(|(path, modified, len)| (path, (Some(modified), len)))
+                .collect(),
+        }
+    }
+
+    /// Paths added, removed, or modified since `earlier`.
+    pub fn changed_since(&self, earlier: &TreeStamp) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, stamp)| earlier.files.get(*path) != Some(*stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            earlier
+                .files
+                .keys()
+                .filter(|path| !self.files.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort();
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn reports_added_removed_and_modified_paths() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+        let t1 = t0 + Duration::from_secs(1);
+        let before = TreeStamp::from_entries([
+            (PathBuf::from("src/lib.rs"), t0, 10),
+            (PathBuf::from("README.md"), t0, 4),
+            (PathBuf::from("old.rs"), t0, 1),
+        ]);
+        let after = TreeStamp::from_entries([
+            (PathBuf::from("src/lib.rs"), t1, 10),
+            (PathBuf::from("README.md"), t0, 4),
+            (PathBuf::from("new.rs"), t1, 2),
+        ]);
+        assert_eq!(
+            after.changed_since(&before),
+            vec![
+                PathBuf::from("new.rs"),
+                PathBuf::from("old.rs"),
+                PathBuf::from("src/lib.rs"),
+            ]
+        );
+        assert!(before.changed_since(&before.clone()).is_empty());
+    }
+}
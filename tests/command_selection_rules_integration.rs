@@ -228,6 +228,158 @@ fn test_changed_targets_only_changed_repos() {
     assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
 }
 
+#[test]
+fn committed_branch_work_counts_as_changed_unless_only_dirty_is_asked() {
+    let workspace = TestWorkspace::new();
+    let core = workspace.root.join("repos").join("core");
+    run_git(&core, &["checkout", "--quiet", "-b", "feature"]);
+    fs::write(core.join("FEATURE.md"), "feature\n").expect("write feature file");
+    run_git(&core, &["add", "-A"]);
+    run_git(&core, &["commit", "--quiet", "-m", "Feature"]);
+    workspace.mark_repo_changed("app");
+
+    workspace.clear_selection_log();
+    let output = workspace.run_harmonia(&["test", "--changed", "--parallel", "1"]);
+    assert_success(&output, "test --changed");
+    assert_eq!(
+        workspace.read_selection_log(),
+        vec!["app".to_string(), "core".to_string()]
+    );
+
+    workspace.clear_selection_log();
+    let output = workspace.run_harmonia(&[
+        "--changed-as",
+        "dirty",
+        "test",
+        "--changed",
+        "--parallel",
+        "1",
+    ]);
+    assert_success(&output, "test --changed --changed-as dirty");
+    assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
+}
+
+#[test]
+fn select_expression_replaces_default_scope() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("app");
+    workspace.clear_selection_log();
+
+    let output = workspace.run_harmonia(&["test", "--select", "changed | core", "--parallel", "1"]);
+    assert_success(&output, "test --select");
+    assert_eq!(
+        workspace.read_selection_log(),
+        vec!["app".to_string(), "core".to_string()]
+    );
+
+    workspace.clear_selection_log();
+    let output = workspace.run_harmonia(&[
+        "lint",
+        "--all",
+        "--select",
+        "all - group:core_group",
+        "--parallel",
+        "1",
+    ]);
+    assert_success(&output, "lint --select");
+    assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
+
+    let output = workspace.run_harmonia(&["test", "--select", "tag:backend"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown selector 'tag:'"),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+fn team_flag_selects_repos_owned_by_the_team() {
+    let workspace = TestWorkspace::new();
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let config = fs::read_to_string(&config_path).expect("read config");
+    fs::write(
+        &config_path,
+        format!("{config}\n[teams.payments]\nrepos = [\"app\"]\ngroups = [\"core_group\"]\n\n[teams.web]\nrepos = [\"app\"]\n"),
+    )
+    .expect("write config");
+    workspace.clear_selection_log();
+
+    let output = workspace.run_harmonia(&["test", "--team", "web", "--parallel", "1"]);
+    assert_success(&output, "test --team");
+    assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
+
+    let output = workspace.run_harmonia(&["status", "--json", "--team", "payments"]);
+    assert_success(&output, "status --team");
+    let rows: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("parse status json");
+    let teams: Vec<(String, serde_json::Value)> = rows
+        .as_array()
+        .expect("status rows")
+        .iter()
+        .map(|row| {
+            (
+                row["repo"].as_str().unwrap().to_string(),
+                row["teams"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        teams,
+        vec![
+            ("app".to_string(), serde_json::json!(["payments", "web"])),
+            ("core".to_string(), serde_json::json!(["payments"])),
+        ]
+    );
+
+    let output = workspace.run_harmonia(&["status", "--team", "missing"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown team missing"), "stderr:\n{stderr}");
+}
+
+#[test]
+fn saved_selection_is_reused_by_later_commands() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("app");
+    workspace.clear_selection_log();
+
+    let output = workspace.run_harmonia(&[
+        "test",
+        "--changed",
+        "--save-selection",
+        "wip",
+        "--parallel",
+        "1",
+    ]);
+    assert_success(&output, "test --save-selection");
+    assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
+    let saved = fs::read_to_string(
+        workspace
+            .root
+            .join(".harmonia")
+            .join("selections")
+            .join("wip.json"),
+    )
+    .expect("read saved selection");
+    assert!(saved.contains("\"app\""), "saved:\n{saved}");
+
+    // A later change does not widen the saved set.
+    workspace.mark_repo_changed("core");
+    workspace.clear_selection_log();
+    let output = workspace.run_harmonia(&["lint", "--selection", "wip", "--parallel", "1"]);
+    assert_success(&output, "lint --selection");
+    assert_eq!(workspace.read_selection_log(), vec!["app".to_string()]);
+
+    let output = workspace.run_harmonia(&["test", "--selection"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("selection 'last' not found"),
+        "stderr:\n{stderr}"
+    );
+}
+
 #[test]
 fn lint_changed_targets_only_changed_repos() {
     let workspace = TestWorkspace::new();
@@ -251,3 +403,270 @@ fn branch_without_explicit_repos_uses_default_group() {
     assert_eq!(workspace.current_branch("external-sdk"), "main");
     assert_eq!(workspace.current_branch("scratch"), "main");
 }
+
+#[test]
+fn status_include_external_reports_external_repos_read_only() {
+    let workspace = TestWorkspace::new();
+
+    let repos_in = |output: &std::process::Output| -> Vec<(String, bool)> {
+        let rows: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("parse status json");
+        rows.as_array()
+            .expect("status rows")
+            .iter()
+            .map(|row| {
+                (
+                    row["repo"].as_str().unwrap_or_default().to_string(),
+                    row["external"].as_bool().unwrap_or_default(),
+                )
+            })
+            .collect()
+    };
+
+    let default_output = workspace.run_harmonia(&["status", "--json"]);
+    assert_success(&default_output, "status --json");
+    assert!(!repos_in(&default_output)
+        .iter()
+        .any(|(repo, _)| repo == "external-sdk"));
+
+    let external_output = workspace.run_harmonia(&["status", "--json", "--include-external"]);
+    assert_success(&external_output, "status --json --include-external");
+    let repos = repos_in(&external_output);
+    assert!(
+        repos.contains(&("external-sdk".to_string(), true)),
+        "repos: {repos:?}"
+    );
+    assert!(
+        repos.contains(&("core".to_string(), false)),
+        "repos: {repos:?}"
+    );
+    assert!(!repos.iter().any(|(repo, _)| repo == "scratch"));
+
+    let sync_output = workspace.run_harmonia(&["sync", "--include-external"]);
+    assert!(
+        !sync_output.status.success(),
+        "sync --include-external without --fetch-only should be rejected"
+    );
+}
+
+#[test]
+fn export_merged_assembles_repos_with_and_without_history() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("app");
+
+    let flat = workspace.root.join("export-flat");
+    let output = workspace.run_harmonia(&[
+        "export",
+        flat.to_str().expect("utf-8 path"),
+        "--merged",
+        "--repos",
+        "app,core",
+    ]);
+    assert_success(&output, "export --merged");
+    assert!(flat.join("app").join("src").join("lib.rs").is_file());
+    assert!(flat.join("core").join("Cargo.toml").is_file());
+    assert!(
+        !flat.join("app").join("CHANGED.md").exists(),
+        "uncommitted files must not be exported"
+    );
+    assert!(!flat.join("external-sdk").exists());
+    let manifest: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(flat.join("HARMONIA_EXPORT.json")).expect("read manifest"),
+    )
+    .expect("parse manifest");
+    assert_eq!(manifest["repos"].as_array().map(Vec::len), Some(2));
+
+    let rejected =
+        workspace.run_harmonia(&["export", flat.to_str().expect("utf-8 path"), "--merged"]);
+    assert!(
+        !rejected.status.success(),
+        "non-empty destination must be rejected"
+    );
+
+    let history = workspace.root.join("export-history");
+    let output = workspace.run_harmonia(&[
+        "export",
+        history.to_str().expect("utf-8 path"),
+        "--merged",
+        "--history",
+        "--repos",
+        "core",
+    ]);
+    assert_success(&output, "export --merged --history");
+    assert!(history.join("core").join("src").join("lib.rs").is_file());
+    let log = Command::new("git")
+        .current_dir(&history)
+        .args(["log", "--format=%s"])
+        .output()
+        .expect("run git log");
+    let log = String::from_utf8_lossy(&log.stdout).to_string();
+    assert!(log.contains("Import core history"), "log:\n{log}");
+    assert!(log.contains("Initial commit"), "log:\n{log}");
+}
+
+#[test]
+fn stats_record_command_usage_when_enabled() {
+    let workspace = TestWorkspace::new();
+    let output = workspace.run_harmonia(&["status"]);
+    assert_success(&output, "status without stats");
+    assert!(!workspace.root.join(".harmonia").join("stats.db").exists());
+
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let config = fs::read_to_string(&config_path).expect("read config");
+    fs::write(
+        &config_path,
+        config.replacen("[workspace]\n", "[workspace]\nstats = true\n", 1),
+    )
+    .expect("write config");
+    assert_success(&workspace.run_harmonia(&["status"]), "status");
+    assert_success(&workspace.run_harmonia(&["status"]), "status");
+    let failed = workspace.run_harmonia(&["status", "--select", "missing"]);
+    assert!(!failed.status.success(), "unknown repo should fail");
+
+    let output = workspace.run_harmonia(&["stats", "--json"]);
+    assert_success(&output, "stats --json");
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).expect("parse stats json");
+    let rows = rows.as_array().expect("stats rows");
+    assert_eq!(rows.len(), 1, "stats itself is not recorded: {rows:?}");
+    assert_eq!(rows[0]["command"], "status");
+    assert_eq!(rows[0]["runs"], 3);
+    assert_eq!(rows[0]["failures"], 1);
+    assert_eq!(rows[0]["failure_categories"]["other"], 1);
+
+    assert_success(
+        &workspace.run_harmonia(&["stats", "--clear"]),
+        "stats --clear",
+    );
+    assert!(!workspace.root.join(".harmonia").join("stats.db").exists());
+}
+
+#[test]
+fn state_export_and_import_round_trip_workspace_state() {
+    let workspace = TestWorkspace::new();
+    let state = workspace.root.join(".harmonia");
+    let repos_dir = workspace.root.join("repos");
+    fs::write(
+        state.join("mr-state.json"),
+        format!(
+            "{{\"checkout\": \"{}\"}}",
+            repos_dir.join("app").to_string_lossy()
+        ),
+    )
+    .expect("write mr state");
+    fs::create_dir_all(state.join("cache")).expect("create cache");
+    fs::write(state.join("cache").join("manifests.json"), "{}").expect("write cache");
+
+    let bundle = workspace.root.join("state.tar.gz");
+    let output = workspace.run_harmonia(&["state", "export", bundle.to_str().expect("utf-8")]);
+    assert_success(&output, "state export");
+    let rejected = workspace.run_harmonia(&["state", "export", bundle.to_str().expect("utf-8")]);
+    assert!(
+        !rejected.status.success(),
+        "existing bundle must not be overwritten without --force"
+    );
+
+    let restored = unique_temp_dir("state-import");
+    fs::create_dir_all(&restored).expect("create restore dir");
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&restored)
+        .args(["state", "import", bundle.to_str().expect("utf-8")])
+        .output()
+        .expect("run harmonia");
+    assert_success(&output, "state import");
+    assert_eq!(
+        fs::read_to_string(restored.join(".harmonia").join("config.toml")).expect("config"),
+        fs::read_to_string(state.join("config.toml")).expect("config")
+    );
+    assert!(!restored.join(".harmonia").join("cache").exists());
+    let mr_state =
+        fs::read_to_string(restored.join(".harmonia").join("mr-state.json")).expect("mr state");
+    assert!(
+        mr_state.contains(
+            &restored
+                .join("repos")
+                .join("app")
+                .to_string_lossy()
+                .to_string()
+        ),
+        "checkout paths must point at the restored workspace: {mr_state}"
+    );
+
+    fs::write(state.join("mr-state.json"), "{}").expect("edit mr state");
+    let conflict = workspace.run_harmonia(&["state", "import", bundle.to_str().expect("utf-8")]);
+    assert!(
+        !conflict.status.success(),
+        "differing state files must not be overwritten without --force"
+    );
+    let forced = workspace.run_harmonia(&[
+        "state",
+        "import",
+        bundle.to_str().expect("utf-8"),
+        "--force",
+    ]);
+    assert_success(&forced, "state import --force");
+    assert!(fs::read_to_string(state.join("mr-state.json"))
+        .expect("mr state")
+        .contains("checkout"));
+    let _ = fs::remove_dir_all(&restored);
+}
+
+#[test]
+fn encrypted_state_import_rejects_wrong_passphrases_and_tampering() {
+    let workspace = TestWorkspace::new();
+    let bundle = workspace.root.join("state.tar.gz.enc");
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args([
+            "state",
+            "export",
+            bundle.to_str().expect("utf-8"),
+            "--encrypt",
+        ])
+        .env("HARMONIA_STATE_PASSPHRASE", "correct horse")
+        .output()
+        .expect("run harmonia");
+    assert_success(&output, "state export --encrypt");
+
+    let import = |archive: &Path, passphrase: &str, target: &Path| {
+        Command::new(harmonia_bin())
+            .arg("--workspace")
+            .arg(target)
+            .args(["state", "import", archive.to_str().expect("utf-8")])
+            .env("HARMONIA_STATE_PASSPHRASE", passphrase)
+            .output()
+            .expect("run harmonia")
+    };
+    let restored = unique_temp_dir("state-import-encrypted");
+    fs::create_dir_all(&restored).expect("create restore dir");
+
+    let wrong = import(&bundle, "battery staple", &restored);
+    assert!(!wrong.status.success(), "a wrong passphrase must fail");
+    let stderr = String::from_utf8_lossy(&wrong.stderr);
+    assert!(
+        stderr.contains("wrong passphrase or corrupted archive"),
+        "stderr:\n{stderr}"
+    );
+
+    let mut bytes = fs::read(&bundle).expect("read bundle");
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    let tampered = workspace.root.join("tampered.tar.gz.enc");
+    fs::write(&tampered, bytes).expect("write tampered bundle");
+    let rejected = import(&tampered, "correct horse", &restored);
+    assert!(!rejected.status.success(), "a tampered bundle must fail");
+    let stderr = String::from_utf8_lossy(&rejected.stderr);
+    assert!(
+        stderr.contains("wrong passphrase or corrupted archive"),
+        "stderr:\n{stderr}"
+    );
+    assert!(
+        !restored.join(".harmonia").exists(),
+        "nothing is restored from a rejected bundle"
+    );
+
+    assert_success(&import(&bundle, "correct horse", &restored), "state import");
+    assert!(restored.join(".harmonia").join("config.toml").is_file());
+    let _ = fs::remove_dir_all(&restored);
+}
@@ -177,6 +177,40 @@ fn config_command_get_set_show_and_edit() {
     assert_success(&edit_output, "config edit");
 }
 
+#[test]
+fn config_add_and_remove_update_arrays_and_tables() {
+    let workspace = TestWorkspace::new();
+    let get = |key: &str| {
+        let output = workspace.run_harmonia(&["config", "get", key]);
+        assert_success(&output, "config get");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let add = workspace.run_harmonia(&["config", "add", "groups.core", "service", "worker"]);
+    assert_success(&add, "config add groups.core");
+    let add = workspace.run_harmonia(&["config", "add", "repos.worker", "path=repos/worker"]);
+    assert_success(&add, "config add repos.worker");
+    assert_eq!(get("groups.core"), r#"["service", "worker"]"#);
+    assert_eq!(get("repos.worker.path"), "repos/worker");
+
+    let conflict = workspace.run_harmonia(&["config", "add", "repos.worker", "path=elsewhere"]);
+    assert!(!conflict.status.success());
+    let invalid = workspace.run_harmonia(&["config", "add", "defaults", "clone_protocol=ftp"]);
+    assert!(!invalid.status.success());
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    assert!(stderr.contains("clone_protocol"), "stderr:\n{stderr}");
+    let config = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(!config.contains("ftp"), "config:\n{config}");
+
+    let remove = workspace.run_harmonia(&["config", "remove", "groups.core", "worker"]);
+    assert_success(&remove, "config remove groups.core");
+    let remove = workspace.run_harmonia(&["config", "remove", "repos.worker"]);
+    assert_success(&remove, "config remove repos.worker");
+    assert_eq!(get("groups.core"), r#"["service"]"#);
+    let missing = workspace.run_harmonia(&["config", "get", "repos.worker"]);
+    assert!(!missing.status.success());
+}
+
 #[test]
 fn repo_command_add_list_show_remove() {
     let workspace = TestWorkspace::new();
@@ -236,6 +270,218 @@ fn repo_command_add_list_show_remove() {
     );
 }
 
+#[test]
+fn repo_move_and_relayout_update_checkouts_and_config() {
+    let workspace = TestWorkspace::new();
+
+    let move_output = workspace.run_harmonia(&["repo", "move", "service", "work/service"]);
+    assert_success(&move_output, "repo move");
+    assert!(!workspace.repo_path.exists());
+    assert!(workspace
+        .root
+        .join("work")
+        .join("service")
+        .join(".git")
+        .exists());
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(
+        normalize_separators(&config_contents).contains("path = \"work/service\""),
+        "config:\n{config_contents}"
+    );
+
+    let status_output = workspace.run_harmonia(&["status", "--short"]);
+    assert_success(&status_output, "status after move");
+
+    let dry_run_output = workspace.run_harmonia(&["repo", "relayout", "--dry-run"]);
+    assert_success(&dry_run_output, "repo relayout --dry-run");
+    let dry_run_stdout = String::from_utf8_lossy(&dry_run_output.stdout).to_string();
+    assert!(
+        normalize_separators(&dry_run_stdout).contains("repos/core/service"),
+        "stdout:\n{dry_run_stdout}"
+    );
+    assert!(workspace.root.join("work").join("service").exists());
+
+    let relayout_output = workspace.run_harmonia(&["repo", "relayout", "--layout", "flat"]);
+    assert_success(&relayout_output, "repo relayout flat");
+    assert!(workspace.repo_path.join(".git").exists());
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(
+        !config_contents.contains("path ="),
+        "config:\n{config_contents}"
+    );
+}
+
+#[test]
+fn repo_relayout_records_each_move_and_skips_missing_checkouts() {
+    let workspace = TestWorkspace::new();
+    let api = workspace.root.join("repos").join("api");
+    fs::create_dir_all(&api).expect("create api checkout");
+    init_git_repo(&api);
+    fs::write(
+        &workspace.config_path,
+        r#"[workspace]
+name = "edit-clean-config-repo-integration"
+repos_dir = "repos"
+
+[repos]
+"api" = {}
+"docs" = {}
+"service" = {}
+
+[groups]
+core = ["api", "docs"]
+edge = ["service"]
+"#,
+    )
+    .expect("write workspace config");
+    // A file where the edge group directory belongs makes the service move fail.
+    fs::write(workspace.root.join("repos").join("edge"), "").expect("write blocker");
+
+    let output = workspace.run_harmonia(&["repo", "relayout", "--layout", "grouped"]);
+    assert!(!output.status.success(), "relayout should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(
+        stderr.contains("repo docs is not cloned"),
+        "stderr:\n{stderr}"
+    );
+
+    assert!(workspace
+        .root
+        .join("repos")
+        .join("core")
+        .join("api")
+        .join(".git")
+        .exists());
+    assert!(workspace.repo_path.join(".git").exists());
+    let config: toml::Value =
+        toml::from_str(&fs::read_to_string(&workspace.config_path).expect("read config"))
+            .expect("parse config");
+    assert_eq!(
+        config["repos"]["api"]["path"]
+            .as_str()
+            .map(normalize_separators),
+        Some("repos/core/api".to_string())
+    );
+    assert!(config["repos"]["docs"].get("path").is_none());
+    assert!(config["repos"]["service"].get("path").is_none());
+}
+
+#[test]
+fn repo_adopt_registers_existing_checkouts() {
+    let workspace = TestWorkspace::new();
+    let extra = workspace.root.join("repos").join("extra");
+    fs::create_dir_all(&extra).expect("create extra checkout");
+    fs::write(
+        extra.join("Cargo.toml"),
+        "[package]\nname = \"extra\"\nversion = \"0.1.0\"\n",
+    )
+    .expect("write Cargo.toml");
+    init_git_repo(&extra);
+    run_git(
+        &extra,
+        &[
+            "remote",
+            "add",
+            "origin",
+            "https://example.com/org/extra.git",
+        ],
+    );
+
+    let adopt_output = workspace.run_harmonia(&["repo", "adopt", "repos/extra", "--group", "core"]);
+    assert_success(&adopt_output, "repo adopt");
+    let show_output = workspace.run_harmonia(&["repo", "show", "extra"]);
+    assert_success(&show_output, "repo show adopted");
+    let stdout = String::from_utf8_lossy(&show_output.stdout).to_string();
+    assert!(
+        stdout.contains("url: https://example.com/org/extra.git"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("ecosystem: rust"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("path: (repos_dir/<name>)"),
+        "stdout:\n{stdout}"
+    );
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(
+        config_contents.contains("\"extra\""),
+        "config:\n{config_contents}"
+    );
+
+    let again = workspace.run_harmonia(&["repo", "adopt", "repos/extra", "--name", "other"]);
+    assert!(!again.status.success(), "adopting twice should fail");
+
+    let tool = workspace.root.join("vendor").join("tool");
+    fs::create_dir_all(&tool).expect("create tool checkout");
+    init_git_repo(&tool);
+    let adopt_output = workspace.run_harmonia(&["repo", "adopt", "vendor/tool"]);
+    assert_success(&adopt_output, "repo adopt outside repos_dir");
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(
+        normalize_separators(&config_contents).contains("path = \"vendor/tool\""),
+        "config:\n{config_contents}"
+    );
+
+    let not_git = workspace.run_harmonia(&["repo", "adopt", "vendor"]);
+    assert!(
+        !not_git.status.success(),
+        "non-git directories are rejected"
+    );
+}
+
+#[test]
+fn repo_pause_hides_repo_until_resumed() {
+    let workspace = TestWorkspace::new();
+
+    let pause_output = workspace.run_harmonia(&[
+        "repo",
+        "pause",
+        "service",
+        "--until",
+        "2999-01-01",
+        "--reason",
+        "mid-migration",
+    ]);
+    assert_success(&pause_output, "repo pause");
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    assert!(
+        config_contents.contains("paused_until = \"2999-01-01\""),
+        "config:\n{config_contents}"
+    );
+
+    let status_output = workspace.run_harmonia(&["status"]);
+    assert_success(&status_output, "status while paused");
+    let stdout = String::from_utf8_lossy(&status_output.stdout).to_string();
+    assert!(stdout.contains("(0 repos)"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("service (until 2999-01-01): mid-migration"),
+        "stdout:\n{stdout}"
+    );
+
+    let past = workspace.run_harmonia(&["repo", "pause", "service", "--until", "2000-01-01"]);
+    assert!(!past.status.success(), "past --until should be rejected");
+
+    let resume_output = workspace.run_harmonia(&["repo", "resume", "service"]);
+    assert_success(&resume_output, "repo resume");
+    let status_output = workspace.run_harmonia(&["status"]);
+    let stdout = String::from_utf8_lossy(&status_output.stdout).to_string();
+    assert!(stdout.contains("(1 repos)"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("Paused:"), "stdout:\n{stdout}");
+
+    // Pauses that have run out no longer hide the repo.
+    let config_contents = fs::read_to_string(&workspace.config_path).expect("read config");
+    fs::write(
+        &workspace.config_path,
+        config_contents.replace(
+            "[repos.service]",
+            "[repos.service]\npaused = true\npaused_until = \"2000-01-01\"",
+        ),
+    )
+    .expect("write config");
+    let status_output = workspace.run_harmonia(&["status"]);
+    let stdout = String::from_utf8_lossy(&status_output.stdout).to_string();
+    assert!(stdout.contains("(1 repos)"), "stdout:\n{stdout}");
+}
+
 #[test]
 fn repo_add_rejects_empty_ecosystem() {
     let workspace = TestWorkspace::new();
@@ -292,3 +538,32 @@ fn clean_command_dry_run_then_force() {
         "file should be removed by clean --force"
     );
 }
+
+#[test]
+fn edit_files_opens_changed_files_with_editor_args_template() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed();
+
+    let output = workspace.run_harmonia(&[
+        "edit",
+        "--files",
+        "--editor",
+        "echo",
+        "--editor-args",
+        "{editor} -g {file}:{line}",
+    ]);
+    assert_success(&output, "edit --files");
+    let stdout = normalize_separators(&String::from_utf8_lossy(&output.stdout));
+    assert!(
+        stdout.contains("-g ") && stdout.contains("repos/service/CHANGED.md:1"),
+        "stdout:\n{stdout}"
+    );
+
+    let output = workspace.run_harmonia(&["edit", "--files", "--editor", "echo"]);
+    assert_success(&output, "edit --files without template");
+    let stdout = normalize_separators(&String::from_utf8_lossy(&output.stdout));
+    assert!(
+        stdout.trim().ends_with("repos/service/CHANGED.md"),
+        "stdout:\n{stdout}"
+    );
+}
@@ -123,6 +123,24 @@ repos_dir = "repos"
 
         serde_json::from_slice(&output.stdout).expect("parse graph order json")
     }
+
+    fn graph_show_json(&self, extra: &[&str]) -> serde_json::Value {
+        let output = Command::new(harmonia_bin())
+            .arg("--workspace")
+            .arg(&self.root)
+            .args(["graph", "show", "--format", "json"])
+            .args(extra)
+            .output()
+            .expect("run harmonia graph show");
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        assert!(
+            output.status.success(),
+            "graph show command failed\nstdout:\n{stdout}\nstderr:\n{stderr}"
+        );
+
+        serde_json::from_slice(&output.stdout).expect("parse graph show json")
+    }
 }
 
 impl Drop for TestWorkspace {
@@ -227,3 +245,438 @@ fn graph_order_uses_workspace_declared_dependencies() {
     let order = workspace.graph_order(false);
     assert_eq!(order, vec!["core", "lib", "app"]);
 }
+
+#[test]
+fn graph_show_filters_by_depth_and_reports_node_metadata() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("lib");
+
+    let graph = workspace.graph_show_json(&["--from", "app", "--depth", "1"]);
+    let nodes = graph["nodes"].as_array().expect("nodes array");
+    let ids: Vec<&str> = nodes
+        .iter()
+        .map(|node| node["id"].as_str().expect("node id"))
+        .collect();
+    assert_eq!(ids, vec!["app", "lib"]);
+    assert_eq!(nodes[1]["ecosystem"], "rust");
+    assert_eq!(nodes[1]["version"], "0.1.0");
+    assert_eq!(nodes[1]["changed"], true);
+    assert_eq!(nodes[0]["changed"], false);
+    assert_eq!(
+        graph["edges"],
+        serde_json::json!([{ "from": "app", "to": "lib" }])
+    );
+
+    let graph = workspace.graph_show_json(&["--exclude", "changed", "--exclude", "repo:core"]);
+    let ids: Vec<&str> = graph["nodes"]
+        .as_array()
+        .expect("nodes array")
+        .iter()
+        .map(|node| node["id"].as_str().expect("node id"))
+        .collect();
+    assert_eq!(ids, vec!["app"]);
+
+    let graph = workspace.graph_show_json(&["--only", "changed"]);
+    assert_eq!(graph["nodes"][0]["id"], "lib");
+}
+
+#[test]
+fn graph_show_renders_mermaid_flowchart() {
+    let workspace = TestWorkspace::new();
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["graph", "show", "--format", "mermaid", "--direction", "up"])
+        .output()
+        .expect("run harmonia graph show");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    assert!(stdout.starts_with("flowchart BT\n"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("  n_core[\"core (0.1.0)\"]"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("  n_core --> n_lib"), "stdout:\n{stdout}");
+    assert!(stdout.contains("  n_lib --> n_app"), "stdout:\n{stdout}");
+}
+
+#[test]
+fn graph_show_exports_graphml_and_clustered_dot() {
+    let workspace = TestWorkspace::new();
+    let run = |format: &str| {
+        let output = Command::new(harmonia_bin())
+            .arg("--workspace")
+            .arg(&workspace.root)
+            .args([
+                "graph",
+                "show",
+                "--format",
+                format,
+                "--cluster-by",
+                "ecosystem",
+            ])
+            .output()
+            .expect("run harmonia graph show");
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        assert!(output.status.success(), "stdout:\n{stdout}");
+        stdout
+    };
+
+    let dot = run("dot");
+    assert!(dot.contains("subgraph \"cluster_0\" {"), "dot:\n{dot}");
+    assert!(dot.contains("    label=\"rust\";"), "dot:\n{dot}");
+    assert!(dot.contains("  \"app\" -> \"lib\";"), "dot:\n{dot}");
+
+    let graphml = run("graphml");
+    assert!(
+        graphml.contains("<node id=\"core\">"),
+        "graphml:\n{graphml}"
+    );
+    assert!(
+        graphml.contains("<data key=\"cluster\">rust</data>"),
+        "graphml:\n{graphml}"
+    );
+    assert!(
+        graphml.contains("<edge source=\"lib\" target=\"core\"/>"),
+        "graphml:\n{graphml}"
+    );
+}
+
+#[test]
+fn graph_critical_path_reports_longest_chain_and_impact() {
+    let workspace = TestWorkspace::new();
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["graph", "critical-path", "--json"])
+        .output()
+        .expect("run harmonia graph critical-path");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    let report: serde_json::Value = serde_json::from_str(&stdout).expect("parse json");
+    assert_eq!(
+        report["critical_path"],
+        serde_json::json!(["core", "lib", "app"])
+    );
+    assert_eq!(
+        report["impact"][0],
+        serde_json::json!({ "repo": "core", "dependents": 2, "depth": 2 })
+    );
+
+    let graph = workspace.graph_show_json(&["--impact"]);
+    let core = graph["nodes"]
+        .as_array()
+        .expect("nodes array")
+        .iter()
+        .find(|node| node["id"] == "core")
+        .expect("core node")
+        .clone();
+    assert_eq!(core["dependents"], 2);
+    assert_eq!(core["label"], "core (0.1.0) [2 dependents, depth 2]");
+}
+
+#[test]
+fn graph_check_reports_duplicate_package_names() {
+    let workspace = TestWorkspace::new();
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let mut config = fs::read_to_string(&config_path).expect("read config");
+    config.push_str("\"core-fork\" = { package_name = \"core\" }\n");
+    fs::write(&config_path, config).expect("write config");
+    fs::create_dir_all(workspace.root.join("repos").join("core-fork")).expect("create fork dir");
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["graph", "check", "--json"])
+        .output()
+        .expect("run harmonia graph check");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(
+        output.status.success(),
+        "stdout:\n{stdout}\nstderr:\n{stderr}"
+    );
+    assert!(
+        stderr.contains("package name 'core' is declared by core and core-fork"),
+        "stderr:\n{stderr}"
+    );
+    let report: serde_json::Value = serde_json::from_str(&stdout).expect("parse json");
+    assert_eq!(
+        report["duplicates"],
+        serde_json::json!([{ "name": "core", "repos": ["core", "core-fork"] }])
+    );
+    assert_eq!(report["missing"], serde_json::json!([]));
+}
+
+#[test]
+fn declared_external_packages_are_reported_separately_from_missing() {
+    let workspace = TestWorkspace::new();
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let mut config = fs::read_to_string(&config_path).expect("read config");
+    config.push_str(
+        "\n[external_packages]\n\"serde\" = { version_url = \"https://crates.io/api/v1/crates/serde\" }\n",
+    );
+    fs::write(&config_path, config).expect("write config");
+    let manifest = workspace.root.join("repos").join("app").join("Cargo.toml");
+    let mut cargo = fs::read_to_string(&manifest).expect("read Cargo.toml");
+    cargo.push_str("serde = \"1\"\n");
+    fs::write(&manifest, cargo).expect("write Cargo.toml");
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["graph", "check", "--json"])
+        .output()
+        .expect("run harmonia graph check");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    let report: serde_json::Value = serde_json::from_str(&stdout).expect("parse json");
+    assert_eq!(report["missing"], serde_json::json!([]));
+    assert_eq!(
+        report["external"],
+        serde_json::json!([{
+            "from": "app",
+            "dependency": "serde",
+            "constraint": "1",
+            "version_url": "https://crates.io/api/v1/crates/serde"
+        }])
+    );
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["graph", "deps", "app", "--json"])
+        .output()
+        .expect("run harmonia graph deps");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    let deps: serde_json::Value = serde_json::from_str(&stdout).expect("parse json");
+    assert_eq!(deps, serde_json::json!(["lib", "serde (external)"]));
+}
+
+#[test]
+fn deps_bump_external_plans_updates_for_repos_using_the_package() {
+    let workspace = TestWorkspace::new();
+    for (repo, constraint) in [("app", "1.0"), ("lib", "1.2")] {
+        let manifest = workspace.root.join("repos").join(repo).join("Cargo.toml");
+        let mut cargo = fs::read_to_string(&manifest).expect("read Cargo.toml");
+        cargo.push_str(&format!("serde = \"{constraint}\"\n"));
+        fs::write(&manifest, cargo).expect("write Cargo.toml");
+    }
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args([
+            "deps",
+            "bump-external",
+            "--package",
+            "serde",
+            "--to",
+            "1.2",
+            "--dry-run",
+        ])
+        .output()
+        .expect("run harmonia deps bump-external");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        vec![
+            "external dependency bump: serde -> 1.2",
+            "  app: 1.0 -> 1.2"
+        ]
+    );
+    let cargo = fs::read_to_string(workspace.root.join("repos").join("app").join("Cargo.toml"))
+        .expect("read Cargo.toml");
+    assert!(cargo.contains("serde = \"1.0\""));
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["deps", "bump-external", "--package", "core", "--to", "0.2"])
+        .output()
+        .expect("run harmonia deps bump-external");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("core is provided by workspace repo core"),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+fn plan_simulate_reports_violations_between_merges() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("core");
+    workspace.mark_repo_changed("lib");
+
+    let simulate = || {
+        let output = Command::new(harmonia_bin())
+            .arg("--workspace")
+            .arg(&workspace.root)
+            .args(["plan", "--simulate", "--level", "minor", "--json"])
+            .output()
+            .expect("run harmonia plan --simulate");
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        assert!(
+            output.status.success(),
+            "plan --simulate failed\nstdout:\n{stdout}\nstderr:\n{stderr}"
+        );
+        let plan: serde_json::Value = serde_json::from_str(&stdout).expect("parse plan json");
+        plan["simulation"].clone()
+    };
+    let pairs = |violations: &serde_json::Value| {
+        violations
+            .as_array()
+            .expect("violations array")
+            .iter()
+            .map(|violation| {
+                format!(
+                    "{}->{}",
+                    violation["from_repo"].as_str().unwrap(),
+                    violation["to_repo"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let simulation = simulate();
+    let steps = simulation["steps"].as_array().expect("steps");
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0]["repo"], "core");
+    assert_eq!(steps[0]["from_version"], "0.1.0");
+    assert_eq!(steps[0]["to_version"], "0.2.0");
+    assert_eq!(pairs(&steps[0]["introduced"]), vec!["lib->core"]);
+    assert_eq!(pairs(&steps[1]["introduced"]), vec!["app->lib"]);
+    assert_eq!(
+        pairs(&simulation["unresolved"]),
+        vec!["lib->core", "app->lib"]
+    );
+    assert!(pairs(&simulation["mid_sequence"]).is_empty());
+
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let mut config = fs::read_to_string(&config_path).expect("read config");
+    config.push_str("\n[versioning]\ncascade_bumps = true\n");
+    fs::write(&config_path, config).expect("write config");
+
+    let simulation = simulate();
+    let steps = simulation["steps"].as_array().expect("steps");
+    assert_eq!(steps[1]["constraint_updates"][0]["dependency"], "core");
+    assert_eq!(steps[1]["constraint_updates"][0]["constraint"], "0.2.0");
+    assert_eq!(pairs(&steps[1]["resolved"]), vec!["lib->core"]);
+    assert_eq!(pairs(&simulation["mid_sequence"]), vec!["lib->core"]);
+    assert_eq!(pairs(&simulation["unresolved"]), vec!["app->lib"]);
+}
+
+#[test]
+fn plan_verify_runs_dependent_tests_against_local_upstreams() {
+    let workspace = TestWorkspace::new();
+    let config_path = workspace.root.join(".harmonia").join("config.toml");
+    let mut config = fs::read_to_string(&config_path).expect("read config");
+    config.push_str(
+        "\n[hooks]\npre_verify = \"touch pre-verify.ran\"\n\n[hooks.custom]\ntest = \"cp Cargo.toml verify-Cargo.toml && echo linked > Cargo.lock && test ! -f BROKEN\"\n",
+    );
+    fs::write(&config_path, config).expect("write config");
+    workspace.mark_repo_changed("core");
+    fs::write(workspace.root.join("repos").join("app").join("BROKEN"), "").expect("write marker");
+    fs::write(
+        workspace.root.join("repos").join("lib").join("Cargo.lock"),
+        "original\n",
+    )
+    .expect("write lockfile");
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["plan", "--verify"])
+        .output()
+        .expect("run harmonia plan --verify");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(!output.status.success(), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("Verify against local upstreams:"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.trim() == "lib                ok"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.trim_start().starts_with("app") && line.contains("failed")),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stderr.contains("1 dependent(s) failed against local upstreams: app"),
+        "stderr:\n{stderr}"
+    );
+
+    let core_path = workspace.root.join("repos").join("core");
+    for repo in ["lib", "app"] {
+        let repo_path = workspace.root.join("repos").join(repo);
+        assert!(repo_path.join("pre-verify.ran").is_file());
+        let linked: toml::Value = toml::from_str(
+            &fs::read_to_string(repo_path.join("verify-Cargo.toml")).expect("read linked manifest"),
+        )
+        .expect("parse linked manifest");
+        assert_eq!(
+            linked["patch"]["crates-io"]["core"]["path"].as_str(),
+            Some(core_path.to_string_lossy().as_ref())
+        );
+        let restored = fs::read_to_string(repo_path.join("Cargo.toml")).expect("read Cargo.toml");
+        assert!(!restored.contains("patch"), "{repo} manifest not restored");
+    }
+    let lib_lock = workspace.root.join("repos").join("lib").join("Cargo.lock");
+    assert_eq!(
+        fs::read_to_string(lib_lock).expect("read lockfile"),
+        "original\n"
+    );
+    assert!(
+        !workspace
+            .root
+            .join("repos")
+            .join("app")
+            .join("Cargo.lock")
+            .exists(),
+        "lockfile created by the verify run is removed"
+    );
+}
+
+#[test]
+fn exec_graph_order_runs_each_level_after_its_dependencies() {
+    let workspace = TestWorkspace::new();
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args([
+            "exec",
+            "--all",
+            "--graph-order",
+            "--parallel",
+            "4",
+            "--",
+            "sh",
+            "-c",
+            "basename \"$PWD\" >> ../../order.log",
+        ])
+        .output()
+        .expect("run harmonia exec");
+    assert!(
+        output.status.success(),
+        "exec --graph-order failed\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let order = fs::read_to_string(workspace.root.join("order.log")).expect("read order log");
+    assert_eq!(
+        order.lines().collect::<Vec<_>>(),
+        vec!["core", "lib", "app"]
+    );
+}
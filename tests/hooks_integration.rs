@@ -253,3 +253,104 @@ fn disable_workspace_hooks_skips_workspace_pre_hooks() {
     assert!(workspace.flag_exists("repo-pre-commit.flag"));
     assert!(workspace.flag_exists("repo-pre-push.flag"));
 }
+
+#[test]
+fn run_list_and_dry_run_report_hooks_without_executing() {
+    let workspace = TestWorkspace::new();
+
+    let clone_output = workspace.run_harmonia(&["clone", "service"]);
+    assert_success(&clone_output, "clone");
+    workspace.write_repo_hooks(true);
+    let repo_config = workspace.cloned_repo_path().join(".harmonia.toml");
+    let mut contents = fs::read_to_string(&repo_config).expect("read repo hooks");
+    contents.push_str("\n[hooks.custom]\nfmt = \"touch ../../repo-fmt.flag\"\n");
+    fs::write(&repo_config, contents).expect("write repo custom hook");
+
+    let list_output = workspace.run_harmonia(&["run", "--list"]);
+    assert_success(&list_output, "run --list");
+    let stdout = String::from_utf8_lossy(&list_output.stdout).to_string();
+    assert!(
+        stdout.contains("pre_commit = touch workspace-pre-commit.flag"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("pre_push = touch ../../repo-pre-push.flag (workspace hook disabled)"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        !stdout.contains("runs after workspace hook"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("disables workspace hooks: pre_commit, pre_push"),
+        "stdout:\n{stdout}"
+    );
+
+    let dry_run_output = workspace.run_harmonia(&["run", "fmt", "--all", "--dry-run"]);
+    assert_success(&dry_run_output, "run --dry-run");
+    let stdout = String::from_utf8_lossy(&dry_run_output.stdout).to_string();
+    assert!(
+        stdout.contains("service: touch ../../repo-fmt.flag"),
+        "stdout:\n{stdout}"
+    );
+    assert!(!workspace.flag_exists("repo-fmt.flag"));
+}
+
+#[test]
+fn json_output_captures_hook_results_and_hook_timeout_kills_hangs() {
+    let workspace = TestWorkspace::new();
+
+    let clone_output = workspace.run_harmonia(&["clone", "service"]);
+    assert_success(&clone_output, "clone");
+    workspace.configure_clone_identity();
+    fs::write(
+        workspace.cloned_repo_path().join(".harmonia.toml"),
+        "[hooks]\npre_commit = \"echo repo-hook-output\"\npre_push = \"sleep 5\"\n",
+    )
+    .expect("write repo hooks");
+
+    workspace.append_change();
+    let commit_output = workspace.run_harmonia(&[
+        "commit",
+        "--repos",
+        "service",
+        "--message",
+        "feat: json hooks",
+        "--all",
+        "--json",
+    ]);
+    assert_success(&commit_output, "commit --json");
+    let report: serde_json::Value =
+        serde_json::from_slice(&commit_output.stdout).expect("commit --json output");
+    assert_eq!(report["repos"], serde_json::json!(["service"]));
+    let hooks = report["hooks"].as_array().expect("hooks array");
+    assert_eq!(hooks.len(), 2);
+    assert!(hooks[0]["repo"].is_null());
+    assert_eq!(hooks[0]["hook"], "pre_commit");
+    assert_eq!(hooks[1]["repo"], "service");
+    assert_eq!(hooks[1]["exit_code"], 0);
+    assert_eq!(hooks[1]["stdout"], "repo-hook-output\n");
+
+    let push_output = workspace.run_harmonia(&[
+        "push",
+        "--repos",
+        "service",
+        "--hook-timeout",
+        "1",
+        "--json",
+    ]);
+    assert!(
+        !push_output.status.success(),
+        "hanging hook should fail push"
+    );
+    let report: serde_json::Value =
+        serde_json::from_slice(&push_output.stdout).expect("push --json output");
+    let hooks = report["hooks"].as_array().expect("hooks array");
+    let last = hooks.last().expect("pre_push result");
+    assert_eq!(last["command"], "sleep 5");
+    assert_eq!(last["timed_out"], true);
+    assert!(last["exit_code"].is_null());
+    assert!(report["repos"].as_array().expect("repos array").is_empty());
+    let stderr = String::from_utf8_lossy(&push_output.stderr);
+    assert!(stderr.contains("timed out"), "stderr:\n{stderr}");
+}
@@ -270,6 +270,106 @@ fn plan_command_supports_json_include_and_exclude() {
     assert!(!repo_names.contains(&"app"), "json:\n{stdout}");
 }
 
+#[test]
+fn plan_graph_numbers_changed_repos_and_colors_mr_status() {
+    let workspace = TestWorkspace::new();
+    workspace.mark_repo_changed("core");
+    workspace.mark_repo_changed("app");
+    workspace.checkout_branch("core", "feature/auth");
+    fs::write(
+        workspace.root.join(".harmonia").join("mr-state.json"),
+        r#"{"entries":[{"repo":"core","forge_repo":"group/core","branch":"feature/auth","mr_id":"7","iid":7,"url":"https://example.invalid/core/7","source_branch":"feature/auth","target_branch":"main"}]}"#,
+    )
+    .expect("write mr state");
+
+    let output = workspace.run_harmonia(&["plan", "--graph"]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    assert!(stdout.starts_with("2. app (no MR)\n"), "stdout:\n{stdout}");
+    assert!(stdout.contains("1. core !7 (tracked)"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("Changeset Analysis"), "stdout:\n{stdout}");
+
+    let output = workspace.run_harmonia(&["plan", "--graph", "mermaid"]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("n_app[\"2. app (no MR)\"]"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("n_app --> n_core"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("class n_core mr_tracked"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("class n_app mr_none"), "stdout:\n{stdout}");
+
+    let output = workspace.run_harmonia(&["plan", "--graph", "graphml"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn mr_report_renders_merge_order_and_blockers_as_html() {
+    let workspace = TestWorkspace::new();
+    workspace.checkout_branch("core", "feature/auth");
+    workspace.checkout_branch("app", "feature/auth");
+    fs::write(
+        workspace.root.join(".harmonia").join("mr-state.json"),
+        r#"{"entries":[
+            {"repo":"app","forge_repo":"group/app","branch":"feature/auth","mr_id":"8","iid":8,"url":"https://example.invalid/app/8?a=1&b=2","source_branch":"feature/auth","target_branch":"main"},
+            {"repo":"core","forge_repo":"group/core","branch":"feature/auth","mr_id":"7","iid":7,"url":"https://example.invalid/core/7","source_branch":"feature/auth","target_branch":"main"}
+        ]}"#,
+    )
+    .expect("write mr state");
+
+    let output = workspace.run_harmonia(&["mr", "report"]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("1. core !7 feature/auth -> main (state: tracked"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("2. app !8 feature/auth -> main (state: tracked"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("   blocked: waiting on core to merge"),
+        "stdout:\n{stdout}"
+    );
+
+    let page = workspace.root.join("site").join("index.html");
+    let output = workspace.run_harmonia(&[
+        "mr",
+        "report",
+        "--html",
+        "--output",
+        page.to_str().expect("utf-8 path"),
+    ]);
+    assert!(
+        output.status.success(),
+        "stderr:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let html = fs::read_to_string(&page).expect("read report");
+    assert!(html.starts_with("<!DOCTYPE html>"), "html:\n{html}");
+    assert!(
+        html.contains("<p>2 MRs, 0 merged, 1 blocked."),
+        "html:\n{html}"
+    );
+    assert!(
+        html.contains("<a href=\"https://example.invalid/app/8?a=1&amp;b=2\">!8</a>"),
+        "html:\n{html}"
+    );
+    assert!(
+        html.find("<td>core</td>") < html.find("<td>app</td>"),
+        "html:\n{html}"
+    );
+    assert!(
+        html.contains("<li>waiting on core to merge</li>"),
+        "html:\n{html}"
+    );
+}
+
 #[test]
 fn plan_uses_active_changeset_file_for_scope_and_metadata() {
     let workspace = TestWorkspace::new();
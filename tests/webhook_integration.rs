@@ -0,0 +1,315 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use harmonia::util::hmac::{hex_encode, hmac_sha256};
+
+const SECRET: &str = "webhook-test-secret";
+
+struct TestWorkspace {
+    root: PathBuf,
+}
+
+impl TestWorkspace {
+    fn new() -> Self {
+        let root = unique_temp_dir("webhook");
+        fs::create_dir_all(root.join(".harmonia")).expect("create .harmonia");
+        fs::create_dir_all(root.join("repos")).expect("create repos dir");
+        fs::write(
+            root.join(".harmonia").join("config.toml"),
+            "[workspace]\nname = \"webhook\"\nrepos_dir = \"repos\"\n\n[webhook]\nlisten = \"127.0.0.1:0\"\nsecret_env = \"HARMONIA_TEST_WEBHOOK_SECRET\"\nevents = [\"pull_request.closed\", \"push\"]\n\n[webhook.actions]\n\"pull_request\" = \"echo $HARMONIA_WEBHOOK_SOURCE $HARMONIA_WEBHOOK_EVENT $HARMONIA_WEBHOOK_PROJECT >> actions.log\"\n",
+        )
+        .expect("write workspace config");
+        Self { root }
+    }
+
+    fn serve(&self, extra_args: &[&str]) -> Server {
+        let mut child = Command::new(harmonia_bin())
+            .arg("--workspace")
+            .arg(&self.root)
+            .args(["webhook", "serve"])
+            .args(extra_args)
+            .env("HARMONIA_TEST_WEBHOOK_SECRET", SECRET)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("start webhook serve");
+        let stderr = child.stderr.take().expect("stderr pipe");
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut server = Server {
+            child,
+            lines,
+            log: Vec::new(),
+            address: String::new(),
+        };
+        let line = server.wait_for_line("listening on http://");
+        server.address = line
+            .split("http://")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("listen address")
+            .to_string();
+        server
+    }
+
+    fn actions_log(&self) -> PathBuf {
+        self.root.join("actions.log")
+    }
+}
+
+impl Drop for TestWorkspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+struct Server {
+    child: Child,
+    lines: mpsc::Receiver<String>,
+    log: Vec<String>,
+    address: String,
+}
+
+impl Server {
+    fn wait_for_line(&mut self, needle: &str) -> String {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    self.log.push(line.clone());
+                    if line.contains(needle) {
+                        return line;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        panic!(
+            "webhook serve never logged '{needle}'; stderr so far:\n{}",
+            self.log.join("\n")
+        );
+    }
+
+    /// Posts `body` with `headers` and returns the status code and body.
+    fn post(&self, headers: &[(&str, String)], body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).expect("connect to webhook serve");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(20)))
+            .expect("set read timeout");
+        let mut request = format!(
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            self.address,
+            body.len()
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream
+            .write_all(request.as_bytes())
+            .expect("send webhook request");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("read webhook response");
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .unwrap_or_else(|| panic!("malformed response: {response}"));
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn github_signature(secret: &str, body: &str) -> String {
+    format!(
+        "sha256={}",
+        hex_encode(&hmac_sha256(secret.as_bytes(), body.as_bytes()))
+    )
+}
+
+fn pull_request_closed() -> &'static str {
+    r#"{"action":"closed","repository":{"full_name":"org/api"}}"#
+}
+
+fn wait_for_file(path: &Path) -> String {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while Instant::now() < deadline {
+        if let Ok(contents) = fs::read_to_string(path) {
+            if !contents.is_empty() {
+                return contents;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    panic!("{} was never written", path.display());
+}
+
+fn harmonia_bin() -> PathBuf {
+    if let Ok(path) = std::env::var("CARGO_BIN_EXE_harmonia") {
+        return PathBuf::from(path);
+    }
+
+    let current_exe = std::env::current_exe().expect("resolve current test binary path");
+    let target_dir = current_exe
+        .parent()
+        .and_then(|path| path.parent())
+        .expect("derive cargo target dir from test binary path");
+    let bin_name = if cfg!(windows) {
+        "harmonia.exe"
+    } else {
+        "harmonia"
+    };
+    let fallback = target_dir.join(bin_name);
+
+    if fallback.is_file() {
+        fallback
+    } else {
+        panic!(
+            "CARGO_BIN_EXE_harmonia is not set and fallback binary not found at {}",
+            fallback.display()
+        );
+    }
+}
+
+fn unique_temp_dir(prefix: &str) -> PathBuf {
+    static TEMP_DIR_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    let pid = std::process::id();
+    for _ in 0..32 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock before unix epoch")
+            .as_nanos();
+        let seq = TEMP_DIR_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let candidate = std::env::temp_dir().join(format!("harmonia-{prefix}-{pid}-{nanos}-{seq}"));
+        match fs::create_dir(&candidate) {
+            Ok(()) => return candidate,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => panic!("failed to create temp dir {}: {}", candidate.display(), err),
+        }
+    }
+
+    panic!("failed to create unique temp dir for {prefix}");
+}
+
+#[test]
+fn serve_runs_actions_only_for_signed_allowed_events() {
+    let workspace = TestWorkspace::new();
+    let mut server = workspace.serve(&[]);
+    let body = pull_request_closed();
+    let event = ("X-GitHub-Event", "pull_request".to_string());
+
+    let (status, response) = server.post(std::slice::from_ref(&event), body);
+    assert_eq!(status, 401, "missing signature: {response}");
+    assert!(response.contains("missing X-Hub-Signature-256"));
+
+    let (status, response) = server.post(
+        &[
+            event.clone(),
+            (
+                "X-Hub-Signature-256",
+                github_signature("wrong-secret", body),
+            ),
+        ],
+        body,
+    );
+    assert_eq!(status, 401, "invalid signature: {response}");
+    assert!(response.contains("does not match"));
+
+    let (status, response) = server.post(
+        &[
+            ("X-Gitlab-Event", "Push Hook".to_string()),
+            ("X-Gitlab-Token", "wrong-secret".to_string()),
+        ],
+        r#"{"object_kind":"push"}"#,
+    );
+    assert_eq!(status, 401, "invalid gitlab token: {response}");
+
+    let opened = r#"{"action":"opened","repository":{"full_name":"org/api"}}"#;
+    let (status, response) = server.post(
+        &[
+            event.clone(),
+            ("X-Hub-Signature-256", github_signature(SECRET, opened)),
+        ],
+        opened,
+    );
+    assert_eq!(status, 200, "filtered event: {response}");
+    assert!(response.contains("ignored"));
+    server.wait_for_line("ignoring pull_request.opened on org/api");
+    assert!(
+        !workspace.actions_log().exists(),
+        "rejected and filtered deliveries must not run actions"
+    );
+
+    let (status, response) = server.post(
+        &[
+            event,
+            ("X-Hub-Signature-256", github_signature(SECRET, body)),
+        ],
+        body,
+    );
+    assert_eq!(status, 202, "valid signature: {response}");
+    assert_eq!(
+        wait_for_file(&workspace.actions_log()).trim(),
+        "github pull_request.closed org/api"
+    );
+}
+
+#[test]
+fn serve_dry_run_only_logs_actions() {
+    let workspace = TestWorkspace::new();
+    let mut server = workspace.serve(&["--dry-run"]);
+    let body = pull_request_closed();
+
+    let (status, response) = server.post(
+        &[
+            ("X-GitHub-Event", "pull_request".to_string()),
+            ("X-Hub-Signature-256", github_signature(SECRET, body)),
+        ],
+        body,
+    );
+    assert_eq!(status, 202, "dry run delivery: {response}");
+    assert!(response.contains("\"dry_run\":true"));
+    let line = server.wait_for_line("would run");
+    assert!(line.contains("pull_request.closed on org/api"), "{line}");
+    assert!(!workspace.actions_log().exists());
+}
+
+#[test]
+fn serve_requires_a_secret() {
+    let workspace = TestWorkspace::new();
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["webhook", "serve"])
+        .env_remove("HARMONIA_TEST_WEBHOOK_SECRET")
+        .output()
+        .expect("run harmonia");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs a secret"));
+}
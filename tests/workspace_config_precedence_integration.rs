@@ -113,3 +113,122 @@ fn workspace_and_config_flags_use_explicit_config_path() {
     );
     assert_eq!(stdout.trim(), "override-config");
 }
+
+#[test]
+fn config_list_flattens_keys_and_get_set_reject_typos() {
+    let workspace = TestWorkspace::new();
+
+    let output = workspace.run_harmonia(&["config", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(output.status.success(), "config list failed:\n{stdout}");
+    assert!(
+        stdout.contains("workspace.name = \"override-config\""),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("workspace.repos_dir = \"repos\""),
+        "stdout:\n{stdout}"
+    );
+
+    let output = workspace.run_harmonia(&["config", "set", "defaults.clone_protocl", "ssh"]);
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(!output.status.success(), "typo should be rejected");
+    assert!(
+        stderr.contains("did you mean 'defaults.clone_protocol'"),
+        "stderr:\n{stderr}"
+    );
+
+    let output = workspace.run_harmonia(&["config", "get", "workspace.nmae"]);
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(!output.status.success(), "typo should be rejected");
+    assert!(
+        stderr.contains("did you mean 'workspace.name'"),
+        "stderr:\n{stderr}"
+    );
+
+    let output = workspace.run_harmonia(&["config", "set", "repos.api.default_branch", "main"]);
+    assert!(output.status.success(), "wildcard key should be accepted");
+    let output = workspace.run_harmonia(&["config", "list", "repos"]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!(stdout.trim(), "repos.api.default_branch = \"main\"");
+}
+
+#[test]
+fn config_show_origin_reports_file_lines_and_env_overrides() {
+    let workspace = TestWorkspace::new();
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .arg("--config")
+        .arg(".harmonia/override.toml")
+        .args(["config", "show", "--origin"])
+        .env("HARMONIA_REPOS_DIR", "checkouts")
+        .output()
+        .expect("run harmonia");
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(
+        output.status.success(),
+        "command failed\nstdout:\n{stdout}\nstderr:\n{stderr}"
+    );
+    assert!(
+        stdout.contains("(command line (--config))"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("file:.harmonia/override.toml:2\tworkspace.name = \"override-config\""),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "file:.harmonia/override.toml:3\tworkspace.repos_dir = \"repos\"  # overridden by env:HARMONIA_REPOS_DIR"
+        ),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("env:HARMONIA_REPOS_DIR\tworkspace.repos_dir = \"checkouts\""),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+fn env_overrides_apply_forge_and_clone_settings() {
+    let workspace = TestWorkspace::new();
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .args(["config", "show", "--origin"])
+        .env("HARMONIA_FORGE_TYPE", "github")
+        .env("HARMONIA_FORGE_HOST", "github.example.com")
+        .env("HARMONIA_CLONE_PROTOCOL", "https")
+        .output()
+        .expect("run harmonia");
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(
+        output.status.success(),
+        "command failed\nstdout:\n{stdout}\nstderr:\n{stderr}"
+    );
+    for line in [
+        "env:HARMONIA_FORGE_TYPE\tforge.type = \"github\"",
+        "env:HARMONIA_FORGE_HOST\tforge.host = \"github.example.com\"",
+        "env:HARMONIA_CLONE_PROTOCOL\tdefaults.clone_protocol = \"https\"",
+    ] {
+        assert!(stdout.contains(line), "missing {line}\nstdout:\n{stdout}");
+    }
+
+    let output = Command::new(harmonia_bin())
+        .arg("--workspace")
+        .arg(&workspace.root)
+        .arg("status")
+        .env("HARMONIA_CLONE_PROTOCOL", "ftp")
+        .output()
+        .expect("run harmonia");
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert!(!output.status.success(), "invalid override should fail");
+    assert!(
+        stderr.contains("defaults.clone_protocol must be 'ssh' or 'https'"),
+        "stderr:\n{stderr}"
+    );
+}